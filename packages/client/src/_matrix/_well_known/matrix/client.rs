@@ -0,0 +1,52 @@
+//! Matrix Client-Server API: Well-known URI
+//!
+//! Implementation of GET /.well-known/matrix/client per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#well-known-uri
+
+use crate::http_client::{HttpClientError, MatrixHttpClient};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use url::Url;
+
+/// Base URL of a server named by the well-known document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerInformation {
+    pub base_url: String,
+}
+
+/// Client discovery information published by a server name
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientWellKnown {
+    #[serde(rename = "m.homeserver")]
+    pub homeserver: ServerInformation,
+    #[serde(rename = "m.identity_server", skip_serializing_if = "Option::is_none")]
+    pub identity_server: Option<ServerInformation>,
+    /// Other properties, such as the OAuth 2.0 issuer
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+/// Client for the well-known discovery document of a server name
+#[derive(Clone)]
+pub struct WellKnownClient {
+    http_client: MatrixHttpClient,
+}
+
+impl WellKnownClient {
+    /// Create a client for `https://{server_name}`
+    pub fn new(server_name: &str) -> Result<Self, HttpClientError> {
+        let url = Url::parse(&format!("https://{}", server_name))?;
+        Ok(Self { http_client: MatrixHttpClient::new(url)? })
+    }
+
+    /// Get the discovery information of the server name
+    ///
+    /// GET /.well-known/matrix/client
+    ///
+    /// Not retried: a missing document is common and means the server name
+    /// is also the homeserver.
+    pub async fn get_client_info(&self) -> Result<ClientWellKnown, HttpClientError> {
+        self.http_client.get("/.well-known/matrix/client").await
+    }
+}
@@ -1 +1 @@
-
+pub mod matrix;
@@ -1,2 +1,70 @@
+//! Matrix Client-Server API bindings
+//!
+//! Typed request and response bindings for the client routes, laid out like
+//! the server's route tree. Each binding goes through
+//! [`MatrixHttpClient::request_with_retry`](crate::http_client::MatrixHttpClient::request_with_retry).
+
+use serde::{Deserialize, Serialize};
+
+pub mod v1;
 pub mod v3;
 pub mod versions;
+
+/// Empty JSON object, the response of endpoints without response fields
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EmptyResponse {}
+
+/// Direction of a paginated request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Forward,
+    Backward,
+}
+
+impl Direction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Direction::Forward => "f",
+            Direction::Backward => "b",
+        }
+    }
+}
+
+/// Append the query parameters that are set to `path`
+pub(crate) fn with_query<'a>(
+    path: &str,
+    params: impl IntoIterator<Item = (&'a str, Option<String>)>,
+) -> String {
+    let mut query = url::form_urlencoded::Serializer::new(String::new());
+    for (key, value) in params {
+        if let Some(value) = value {
+            query.append_pair(key, &value);
+        }
+    }
+    let query = query.finish();
+    if query.is_empty() {
+        path.to_string()
+    } else {
+        format!("{}?{}", path, query)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_query() {
+        assert_eq!(with_query("/a", [("limit", None)]), "/a");
+        assert_eq!(
+            with_query(
+                "/a",
+                [
+                    ("from", Some("s1 2&3".to_string())),
+                    ("dir", Some("b".to_string()))
+                ]
+            ),
+            "/a?from=s1+2%263&dir=b"
+        );
+    }
+}
@@ -0,0 +1,69 @@
+//! Matrix Client-Server API: Login Tokens
+//!
+//! Implementation of the login token endpoint per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#post_matrixclientv1loginget_token
+
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A short-lived token another device can log in with
+#[derive(Debug, Clone, Deserialize)]
+pub struct LoginTokenResponse {
+    /// Used with the `m.login.token` login type
+    pub login_token: String,
+    pub expires_in_ms: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct LoginTokenRequest<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    auth: Option<&'a Value>,
+}
+
+/// Client for login tokens
+#[derive(Clone)]
+pub struct LoginTokenClient {
+    http_client: MatrixHttpClient,
+}
+
+impl LoginTokenClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Get a token for logging in another device as the current user
+    ///
+    /// POST /_matrix/client/v1/login/get_token
+    ///
+    /// The server may require user-interactive authentication first.
+    pub async fn get_token(
+        &self,
+        auth: Option<&Value>,
+    ) -> Result<LoginTokenResponse, HttpClientError> {
+        self.request("/_matrix/client/v1/login/get_token", auth).await
+    }
+
+    /// Get a login token through the unstable v3 path
+    ///
+    /// POST /_matrix/client/v3/login/get_token
+    pub async fn get_token_v3(
+        &self,
+        auth: Option<&Value>,
+    ) -> Result<LoginTokenResponse, HttpClientError> {
+        self.request("/_matrix/client/v3/login/get_token", auth).await
+    }
+
+    async fn request(
+        &self,
+        path: &str,
+        auth: Option<&Value>,
+    ) -> Result<LoginTokenResponse, HttpClientError> {
+        let body = LoginTokenRequest { auth };
+
+        self.http_client
+            .request_with_retry(Method::POST, path, Some(&body), DEFAULT_MAX_RETRIES)
+            .await
+    }
+}
@@ -1,12 +0,0 @@
-//! Client stub for _matrix/client/v1/logout.rs
-//! 
-//! This is a placeholder stub for the client implementation.
-//! The actual HTTP client functionality should be implemented here
-//! using reqwest to make outbound HTTP requests.
-
-// Placeholder stub - implement actual client functionality as needed
-pub mod client_stub {
-    pub fn placeholder() {
-        // Client implementation would go here
-    }
-}
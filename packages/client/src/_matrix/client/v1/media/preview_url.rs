@@ -0,0 +1,64 @@
+//! Matrix Client-Server API: URL Previews
+//!
+//! Implementation of the authenticated URL preview endpoint per Matrix spec v1.11
+//! Reference: https://spec.matrix.org/v1.11/client-server-api/#get_matrixclientv1mediapreview_url
+
+use crate::_matrix::client::with_query;
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use reqwest::Method;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// OpenGraph data of a previewed URL
+#[derive(Debug, Clone, Deserialize)]
+pub struct UrlPreview {
+    #[serde(rename = "og:title")]
+    pub title: Option<String>,
+    #[serde(rename = "og:description")]
+    pub description: Option<String>,
+    /// MXC URI of the preview image
+    #[serde(rename = "og:image")]
+    pub image: Option<String>,
+    #[serde(rename = "matrix:image:size")]
+    pub image_size: Option<u64>,
+    /// Any other OpenGraph properties the server returned
+    #[serde(flatten)]
+    pub other: HashMap<String, Value>,
+}
+
+/// Client for URL previews
+#[derive(Clone)]
+pub struct PreviewUrlClient {
+    http_client: MatrixHttpClient,
+}
+
+impl PreviewUrlClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Get a preview of a URL
+    ///
+    /// GET /_matrix/client/v1/media/preview_url
+    ///
+    /// # Arguments
+    /// * `ts` - Preferred point in time of the preview, in milliseconds
+    pub async fn preview_url(
+        &self,
+        url: &str,
+        ts: Option<u64>,
+    ) -> Result<UrlPreview, HttpClientError> {
+        let path = with_query(
+            "/_matrix/client/v1/media/preview_url",
+            [
+                ("url", Some(url.to_string())),
+                ("ts", ts.map(|ts| ts.to_string())),
+            ],
+        );
+
+        self.http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await
+    }
+}
@@ -1,6 +1,4 @@
 pub mod login;
-pub mod logout;
 pub mod media;
-pub mod register;
 pub mod room_summary;
-pub mod rooms;
\ No newline at end of file
+pub mod rooms;
@@ -1,12 +1,61 @@
-//! Client stub for _matrix/client/v1/room_summary.rs
-//! 
-//! This is a placeholder stub for the client implementation.
-//! The actual HTTP client functionality should be implemented here
-//! using reqwest to make outbound HTTP requests.
-
-// Placeholder stub - implement actual client functionality as needed
-pub mod client_stub {
-    pub fn placeholder() {
-        // Client implementation would go here
+//! Matrix Client-Server API: Room Summary
+//!
+//! Implementation of room previews (MSC3266)
+//! Reference: https://github.com/matrix-org/matrix-spec-proposals/pull/3266
+
+use crate::_matrix::client::with_query;
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use reqwest::Method;
+use serde::Deserialize;
+
+/// Summary of a room, available without joining it
+#[derive(Debug, Clone, Deserialize)]
+pub struct RoomSummary {
+    pub room_id: String,
+    pub avatar_url: Option<String>,
+    pub canonical_alias: Option<String>,
+    pub guest_can_join: bool,
+    pub join_rule: Option<String>,
+    pub name: Option<String>,
+    pub num_joined_members: u64,
+    pub room_type: Option<String>,
+    pub topic: Option<String>,
+    pub world_readable: bool,
+    /// The requesting user's membership, if any
+    pub membership: Option<String>,
+    pub room_version: Option<String>,
+    pub encryption: Option<String>,
+}
+
+/// Client for room summaries
+#[derive(Clone)]
+pub struct RoomSummaryClient {
+    http_client: MatrixHttpClient,
+}
+
+impl RoomSummaryClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Get the summary of a room by ID or alias
+    ///
+    /// GET /_matrix/client/v1/room_summary/{roomIdOrAlias}
+    ///
+    /// # Arguments
+    /// * `via` - Servers to look the room up through
+    pub async fn get_room_summary(
+        &self,
+        room_id_or_alias: &str,
+        via: &[&str],
+    ) -> Result<RoomSummary, HttpClientError> {
+        let path = with_query(
+            &format!("/_matrix/client/v1/room_summary/{}", urlencoding::encode(room_id_or_alias)),
+            via.iter().map(|server| ("via", Some(server.to_string()))),
+        );
+
+        self.http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await
     }
 }
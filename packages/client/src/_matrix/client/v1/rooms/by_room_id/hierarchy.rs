@@ -1,12 +1,64 @@
-//! Client stub for _matrix/client/v1/rooms/by_room_id/hierarchy.rs
-//! 
-//! This is a placeholder stub for the client implementation.
-//! The actual HTTP client functionality should be implemented here
-//! using reqwest to make outbound HTTP requests.
-
-// Placeholder stub - implement actual client functionality as needed
-pub mod client_stub {
-    pub fn placeholder() {
-        // Client implementation would go here
+//! Matrix Client-Server API: Space Hierarchy
+//!
+//! Implementation of space hierarchy browsing per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#get_matrixclientv1roomsroomidhierarchy
+
+use crate::_matrix::client::with_query;
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use matryx_entity::types::SpaceHierarchyChildRoomsChunk;
+use reqwest::Method;
+use serde::Deserialize;
+
+/// Options of a hierarchy request
+#[derive(Debug, Clone, Default)]
+pub struct HierarchyRequest {
+    pub from: Option<String>,
+    pub limit: Option<u32>,
+    /// How many levels of subspaces to descend into
+    pub max_depth: Option<u32>,
+    /// Only return the space's direct children marked `suggested`
+    pub suggested_only: Option<bool>,
+}
+
+/// Response from GET /_matrix/client/v1/rooms/{roomId}/hierarchy
+#[derive(Debug, Clone, Deserialize)]
+pub struct HierarchyResponse {
+    /// The space itself on the first page, followed by its children
+    pub rooms: Vec<SpaceHierarchyChildRoomsChunk>,
+    pub next_batch: Option<String>,
+}
+
+/// Client for space hierarchies
+#[derive(Clone)]
+pub struct SpaceHierarchyClient {
+    http_client: MatrixHttpClient,
+}
+
+impl SpaceHierarchyClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Walk the rooms of a space, depth first
+    ///
+    /// GET /_matrix/client/v1/rooms/{roomId}/hierarchy
+    pub async fn get_hierarchy(
+        &self,
+        room_id: &str,
+        request: &HierarchyRequest,
+    ) -> Result<HierarchyResponse, HttpClientError> {
+        let path = with_query(
+            &format!("/_matrix/client/v1/rooms/{}/hierarchy", urlencoding::encode(room_id)),
+            [
+                ("from", request.from.clone()),
+                ("limit", request.limit.map(|limit| limit.to_string())),
+                ("max_depth", request.max_depth.map(|depth| depth.to_string())),
+                ("suggested_only", request.suggested_only.map(|only| only.to_string())),
+            ],
+        );
+
+        self.http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await
     }
 }
@@ -1 +1,3 @@
-pub mod hierarchy;
\ No newline at end of file
+pub mod hierarchy;
+pub mod relations;
+pub mod threads;
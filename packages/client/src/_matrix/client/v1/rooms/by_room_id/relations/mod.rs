@@ -0,0 +1,87 @@
+//! Matrix Client-Server API: Relationships
+//!
+//! Implementation of the relations endpoints per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#relationships
+
+use crate::_matrix::client::{Direction, with_query};
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use matryx_entity::Event;
+use reqwest::Method;
+use serde::Deserialize;
+
+/// Pagination options of a relations request
+#[derive(Debug, Clone, Default)]
+pub struct RelationsRequest {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub limit: Option<u32>,
+    /// Defaults to backwards on the server
+    pub dir: Option<Direction>,
+    /// Also return events relating to the related events
+    pub recurse: Option<bool>,
+}
+
+/// Response from GET /_matrix/client/v1/rooms/{roomId}/relations/{eventId}
+#[derive(Debug, Clone, Deserialize)]
+pub struct RelationsResponse {
+    /// Child events, in the requested direction
+    pub chunk: Vec<Event>,
+    pub next_batch: Option<String>,
+    pub prev_batch: Option<String>,
+    /// Depth the server recursed to, when `recurse` was set
+    pub recursion_depth: Option<u32>,
+}
+
+/// Client for event relationships
+#[derive(Clone)]
+pub struct RelationsClient {
+    http_client: MatrixHttpClient,
+}
+
+impl RelationsClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Get the events relating to an event, optionally only those with the
+    /// given relation type and event type
+    ///
+    /// GET /_matrix/client/v1/rooms/{roomId}/relations/{eventId}[/{relType}[/{eventType}]]
+    ///
+    /// # Arguments
+    /// * `event_type` - Only used together with `rel_type`
+    pub async fn get_relations(
+        &self,
+        room_id: &str,
+        event_id: &str,
+        rel_type: Option<&str>,
+        event_type: Option<&str>,
+        request: &RelationsRequest,
+    ) -> Result<RelationsResponse, HttpClientError> {
+        let mut path = format!(
+            "/_matrix/client/v1/rooms/{}/relations/{}",
+            urlencoding::encode(room_id),
+            urlencoding::encode(event_id)
+        );
+        if let Some(rel_type) = rel_type {
+            path.push_str(&format!("/{}", urlencoding::encode(rel_type)));
+            if let Some(event_type) = event_type {
+                path.push_str(&format!("/{}", urlencoding::encode(event_type)));
+            }
+        }
+        let path = with_query(
+            &path,
+            [
+                ("from", request.from.clone()),
+                ("to", request.to.clone()),
+                ("limit", request.limit.map(|limit| limit.to_string())),
+                ("dir", request.dir.map(|dir| dir.as_str().to_string())),
+                ("recurse", request.recurse.map(|recurse| recurse.to_string())),
+            ],
+        );
+
+        self.http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await
+    }
+}
@@ -0,0 +1,72 @@
+//! Matrix Client-Server API: Threads
+//!
+//! Implementation of thread listing per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#threading
+
+use crate::_matrix::client::with_query;
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use matryx_entity::Event;
+use reqwest::Method;
+use serde::Deserialize;
+
+/// Which threads to list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThreadInclude {
+    All,
+    /// Threads the user started or replied to
+    Participated,
+}
+
+impl ThreadInclude {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ThreadInclude::All => "all",
+            ThreadInclude::Participated => "participated",
+        }
+    }
+}
+
+/// Response from GET /_matrix/client/v1/rooms/{roomId}/threads
+#[derive(Debug, Clone, Deserialize)]
+pub struct ThreadsResponse {
+    /// Thread roots, most recently active first, with the thread summary
+    /// bundled in `unsigned.m.relations`
+    pub chunk: Vec<Event>,
+    pub next_batch: Option<String>,
+}
+
+/// Client for thread listing
+#[derive(Clone)]
+pub struct ThreadsClient {
+    http_client: MatrixHttpClient,
+}
+
+impl ThreadsClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// List the threads of a room
+    ///
+    /// GET /_matrix/client/v1/rooms/{roomId}/threads
+    pub async fn get_threads(
+        &self,
+        room_id: &str,
+        include: Option<ThreadInclude>,
+        from: Option<&str>,
+        limit: Option<u32>,
+    ) -> Result<ThreadsResponse, HttpClientError> {
+        let path = with_query(
+            &format!("/_matrix/client/v1/rooms/{}/threads", urlencoding::encode(room_id)),
+            [
+                ("include", include.map(|include| include.as_str().to_string())),
+                ("from", from.map(str::to_string)),
+                ("limit", limit.map(|limit| limit.to_string())),
+            ],
+        );
+
+        self.http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await
+    }
+}
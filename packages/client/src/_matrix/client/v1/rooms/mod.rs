@@ -1 +1 @@
-pub mod by_room_id;
\ No newline at end of file
+pub mod by_room_id;
@@ -1 +1,189 @@
+//! Matrix Client-Server API: Account Management
+//!
+//! Implementation of the account endpoints per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#account-registration-and-management
 
+pub mod threepid;
+
+use crate::_matrix::client::EmptyResponse;
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Response from GET /_matrix/client/v3/account/whoami
+#[derive(Debug, Clone, Deserialize)]
+pub struct WhoamiResponse {
+    pub user_id: String,
+    pub device_id: Option<String>,
+    #[serde(default)]
+    pub is_guest: bool,
+}
+
+/// Response from POST /_matrix/client/v3/account/deactivate
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeactivateResponse {
+    /// `"success"` if the identity server unbound the user's 3PIDs
+    pub id_server_unbind_result: String,
+}
+
+/// Session started by a `requestToken` call
+#[derive(Debug, Clone, Deserialize)]
+pub struct RequestTokenResponse {
+    /// Session ID to pass back with the client secret
+    pub sid: String,
+    /// URL to submit the validation token to, if the homeserver does not handle it
+    pub submit_url: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct DeactivateRequest<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    auth: Option<&'a Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id_server: Option<&'a str>,
+    /// Request erasure of the user's data (MSC4025)
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    erase: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct ChangePasswordRequest<'a> {
+    new_password: &'a str,
+    logout_devices: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    auth: Option<&'a Value>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct EmailTokenRequest<'a> {
+    pub(crate) client_secret: &'a str,
+    pub(crate) email: &'a str,
+    pub(crate) send_attempt: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) next_link: Option<&'a str>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct MsisdnTokenRequest<'a> {
+    pub(crate) client_secret: &'a str,
+    pub(crate) country: &'a str,
+    pub(crate) phone_number: &'a str,
+    pub(crate) send_attempt: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct SubmitTokenRequest<'a> {
+    pub(crate) token: &'a str,
+    pub(crate) session_id: &'a str,
+    pub(crate) client_secret: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct SubmitTokenResponse {
+    pub(crate) success: bool,
+}
+
+/// Client for account management
+///
+/// Endpoints protected by user-interactive auth fail with HTTP 401 until
+/// they are retried with a completed `auth` object.
+#[derive(Clone)]
+pub struct AccountClient {
+    http_client: MatrixHttpClient,
+}
+
+impl AccountClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Get the owner of the access token
+    ///
+    /// GET /_matrix/client/v3/account/whoami
+    pub async fn whoami(&self) -> Result<WhoamiResponse, HttpClientError> {
+        self.http_client
+            .request_with_retry(
+                Method::GET,
+                "/_matrix/client/v3/account/whoami",
+                None::<&()>,
+                DEFAULT_MAX_RETRIES,
+            )
+            .await
+    }
+
+    /// Deactivate the user's account
+    ///
+    /// POST /_matrix/client/v3/account/deactivate
+    ///
+    /// # Arguments
+    /// * `erase` - Ask the server to also erase the user's messages
+    pub async fn deactivate(
+        &self,
+        auth: Option<&Value>,
+        id_server: Option<&str>,
+        erase: bool,
+    ) -> Result<DeactivateResponse, HttpClientError> {
+        let body = DeactivateRequest { auth, id_server, erase };
+
+        self.http_client
+            .request_with_retry(
+                Method::POST,
+                "/_matrix/client/v3/account/deactivate",
+                Some(&body),
+                DEFAULT_MAX_RETRIES,
+            )
+            .await
+    }
+
+    /// Change the user's password
+    ///
+    /// POST /_matrix/client/v3/account/password
+    ///
+    /// # Arguments
+    /// * `logout_devices` - Invalidate the access tokens of all other devices
+    pub async fn change_password(
+        &self,
+        new_password: &str,
+        logout_devices: bool,
+        auth: Option<&Value>,
+    ) -> Result<(), HttpClientError> {
+        let body = ChangePasswordRequest { new_password, logout_devices, auth };
+
+        let _: EmptyResponse = self
+            .http_client
+            .request_with_retry(
+                Method::POST,
+                "/_matrix/client/v3/account/password",
+                Some(&body),
+                DEFAULT_MAX_RETRIES,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Email a validation token for resetting a forgotten password
+    ///
+    /// POST /_matrix/client/v3/account/password/email/requestToken
+    ///
+    /// # Arguments
+    /// * `send_attempt` - Increment to send a new email for the same secret
+    pub async fn request_password_email_token(
+        &self,
+        client_secret: &str,
+        email: &str,
+        send_attempt: u32,
+        next_link: Option<&str>,
+    ) -> Result<RequestTokenResponse, HttpClientError> {
+        let body = EmailTokenRequest { client_secret, email, send_attempt, next_link };
+
+        self.http_client
+            .request_with_retry(
+                Method::POST,
+                "/_matrix/client/v3/account/password/email/requestToken",
+                Some(&body),
+                DEFAULT_MAX_RETRIES,
+            )
+            .await
+    }
+}
@@ -0,0 +1,269 @@
+//! Matrix Client-Server API: Adding Account Administrative Contact Information
+//!
+//! Implementation of the /account/3pid endpoints per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#adding-account-administrative-contact-information
+
+use super::{
+    EmailTokenRequest, MsisdnTokenRequest, RequestTokenResponse, SubmitTokenRequest,
+    SubmitTokenResponse,
+};
+use crate::_matrix::client::EmptyResponse;
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A third-party identifier bound to the account
+#[derive(Debug, Clone, Deserialize)]
+pub struct ThirdPartyIdentifier {
+    /// `"email"` or `"msisdn"`
+    pub medium: String,
+    pub address: String,
+    pub validated_at: i64,
+    pub added_at: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct ThreePidsResponse {
+    #[serde(default)]
+    threepids: Vec<ThirdPartyIdentifier>,
+}
+
+#[derive(Debug, Serialize)]
+struct AddThreePidRequest<'a> {
+    client_secret: &'a str,
+    sid: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    auth: Option<&'a Value>,
+}
+
+#[derive(Debug, Serialize)]
+struct BindThreePidRequest<'a> {
+    client_secret: &'a str,
+    sid: &'a str,
+    id_server: &'a str,
+    id_access_token: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+struct RemoveThreePidRequest<'a> {
+    medium: &'a str,
+    address: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id_server: Option<&'a str>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RemoveThreePidResponse {
+    id_server_unbind_result: String,
+}
+
+/// Client for the account's third-party identifiers
+#[derive(Clone)]
+pub struct ThreePidClient {
+    http_client: MatrixHttpClient,
+}
+
+impl ThreePidClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// List the third-party identifiers on the account
+    ///
+    /// GET /_matrix/client/v3/account/3pid
+    pub async fn get_threepids(&self) -> Result<Vec<ThirdPartyIdentifier>, HttpClientError> {
+        let response: ThreePidsResponse = self
+            .http_client
+            .request_with_retry(
+                Method::GET,
+                "/_matrix/client/v3/account/3pid",
+                None::<&()>,
+                DEFAULT_MAX_RETRIES,
+            )
+            .await?;
+        Ok(response.threepids)
+    }
+
+    /// Add a validated identifier to the account
+    ///
+    /// POST /_matrix/client/v3/account/3pid/add
+    pub async fn add_threepid(
+        &self,
+        client_secret: &str,
+        sid: &str,
+        auth: Option<&Value>,
+    ) -> Result<(), HttpClientError> {
+        let body = AddThreePidRequest { client_secret, sid, auth };
+
+        let _: EmptyResponse = self
+            .http_client
+            .request_with_retry(
+                Method::POST,
+                "/_matrix/client/v3/account/3pid/add",
+                Some(&body),
+                DEFAULT_MAX_RETRIES,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Bind a validated identifier to the account on an identity server
+    ///
+    /// POST /_matrix/client/v3/account/3pid/bind
+    pub async fn bind_threepid(
+        &self,
+        client_secret: &str,
+        sid: &str,
+        id_server: &str,
+        id_access_token: &str,
+    ) -> Result<(), HttpClientError> {
+        let body = BindThreePidRequest { client_secret, sid, id_server, id_access_token };
+
+        let _: EmptyResponse = self
+            .http_client
+            .request_with_retry(
+                Method::POST,
+                "/_matrix/client/v3/account/3pid/bind",
+                Some(&body),
+                DEFAULT_MAX_RETRIES,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Remove an identifier from the account
+    ///
+    /// POST /_matrix/client/v3/account/3pid/delete
+    ///
+    /// Returns whether the identity server also unbound it.
+    pub async fn delete_threepid(
+        &self,
+        medium: &str,
+        address: &str,
+        id_server: Option<&str>,
+    ) -> Result<bool, HttpClientError> {
+        self.remove("/_matrix/client/v3/account/3pid/delete", medium, address, id_server)
+            .await
+    }
+
+    /// Unbind an identifier from an identity server, keeping it on the account
+    ///
+    /// POST /_matrix/client/v3/account/3pid/unbind
+    pub async fn unbind_threepid(
+        &self,
+        medium: &str,
+        address: &str,
+        id_server: Option<&str>,
+    ) -> Result<bool, HttpClientError> {
+        self.remove("/_matrix/client/v3/account/3pid/unbind", medium, address, id_server)
+            .await
+    }
+
+    /// Email a validation token for adding an address
+    ///
+    /// POST /_matrix/client/v3/account/3pid/email/requestToken
+    pub async fn request_email_token(
+        &self,
+        client_secret: &str,
+        email: &str,
+        send_attempt: u32,
+        next_link: Option<&str>,
+    ) -> Result<RequestTokenResponse, HttpClientError> {
+        let body = EmailTokenRequest { client_secret, email, send_attempt, next_link };
+
+        self.http_client
+            .request_with_retry(
+                Method::POST,
+                "/_matrix/client/v3/account/3pid/email/requestToken",
+                Some(&body),
+                DEFAULT_MAX_RETRIES,
+            )
+            .await
+    }
+
+    /// Text a validation token for adding a phone number
+    ///
+    /// POST /_matrix/client/v3/account/3pid/msisdn/requestToken
+    ///
+    /// # Arguments
+    /// * `country` - Two-letter country code the number is dialled from
+    pub async fn request_msisdn_token(
+        &self,
+        client_secret: &str,
+        country: &str,
+        phone_number: &str,
+        send_attempt: u32,
+    ) -> Result<RequestTokenResponse, HttpClientError> {
+        let body = MsisdnTokenRequest { client_secret, country, phone_number, send_attempt };
+
+        self.http_client
+            .request_with_retry(
+                Method::POST,
+                "/_matrix/client/v3/account/3pid/msisdn/requestToken",
+                Some(&body),
+                DEFAULT_MAX_RETRIES,
+            )
+            .await
+    }
+
+    /// Submit the token emailed by [`Self::request_email_token`]
+    ///
+    /// POST /_matrix/client/v3/account/3pid/email/submitToken
+    ///
+    /// Returns whether the token was accepted.
+    pub async fn submit_email_token(
+        &self,
+        token: &str,
+        sid: &str,
+        client_secret: &str,
+    ) -> Result<bool, HttpClientError> {
+        self.submit("/_matrix/client/v3/account/3pid/email/submitToken", token, sid, client_secret)
+            .await
+    }
+
+    /// Submit the token texted by [`Self::request_msisdn_token`]
+    ///
+    /// POST /_matrix/client/v3/account/3pid/msisdn/submitToken
+    pub async fn submit_msisdn_token(
+        &self,
+        token: &str,
+        sid: &str,
+        client_secret: &str,
+    ) -> Result<bool, HttpClientError> {
+        self.submit("/_matrix/client/v3/account/3pid/msisdn/submitToken", token, sid, client_secret)
+            .await
+    }
+
+    async fn submit(
+        &self,
+        path: &str,
+        token: &str,
+        sid: &str,
+        client_secret: &str,
+    ) -> Result<bool, HttpClientError> {
+        let body = SubmitTokenRequest { token, session_id: sid, client_secret };
+
+        let response: SubmitTokenResponse = self
+            .http_client
+            .request_with_retry(Method::POST, path, Some(&body), DEFAULT_MAX_RETRIES)
+            .await?;
+        Ok(response.success)
+    }
+
+    async fn remove(
+        &self,
+        path: &str,
+        medium: &str,
+        address: &str,
+        id_server: Option<&str>,
+    ) -> Result<bool, HttpClientError> {
+        let body = RemoveThreePidRequest { medium, address, id_server };
+
+        let response: RemoveThreePidResponse = self
+            .http_client
+            .request_with_retry(Method::POST, path, Some(&body), DEFAULT_MAX_RETRIES)
+            .await?;
+        Ok(response.id_server_unbind_result == "success")
+    }
+}
@@ -1 +1,67 @@
+//! Matrix Client-Server API: Server Administration
+//!
+//! Implementation of GET /_matrix/client/v3/admin/whois/{userId} per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#server-administration
 
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use reqwest::Method;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// A connection made by a session
+#[derive(Debug, Clone, Deserialize)]
+pub struct ConnectionInfo {
+    pub ip: Option<String>,
+    /// When the connection was last seen, in milliseconds
+    pub last_seen: Option<i64>,
+    pub user_agent: Option<String>,
+}
+
+/// A session of a device
+#[derive(Debug, Clone, Deserialize)]
+pub struct SessionInfo {
+    #[serde(default)]
+    pub connections: Vec<ConnectionInfo>,
+}
+
+/// Sessions of a device
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeviceInfo {
+    #[serde(default)]
+    pub sessions: Vec<SessionInfo>,
+}
+
+/// Response from GET /_matrix/client/v3/admin/whois/{userId}
+#[derive(Debug, Clone, Deserialize)]
+pub struct WhoisResponse {
+    pub user_id: String,
+    /// Sessions keyed by device ID
+    #[serde(default)]
+    pub devices: HashMap<String, DeviceInfo>,
+}
+
+/// Client for server administration
+#[derive(Clone)]
+pub struct AdminClient {
+    http_client: MatrixHttpClient,
+}
+
+impl AdminClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Get the sessions and connections of a user
+    ///
+    /// GET /_matrix/client/v3/admin/whois/{userId}
+    ///
+    /// # Errors
+    /// Servers only allow this for server admins and the user themselves.
+    pub async fn whois(&self, user_id: &str) -> Result<WhoisResponse, HttpClientError> {
+        let path = format!("/_matrix/client/v3/admin/whois/{}", urlencoding::encode(user_id));
+
+        self.http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await
+    }
+}
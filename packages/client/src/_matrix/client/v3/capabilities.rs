@@ -3,7 +3,7 @@
 //! Implementation of server capabilities discovery per Matrix spec v1.8
 //! Reference: https://spec.matrix.org/v1.8/client-server-api/#capabilities-negotiation
 
-use crate::http_client::{HttpClientError, MatrixHttpClient};
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
 use reqwest::Method;
 use serde::Deserialize;
 use std::collections::HashMap;
@@ -84,10 +84,11 @@ impl CapabilitiesClient {
     /// Server's supported feature set and capabilities
     pub async fn get_capabilities(&self) -> Result<CapabilitiesResponse, HttpClientError> {
         self.http_client
-            .request(
+            .request_with_retry(
                 Method::GET,
                 "/_matrix/client/v3/capabilities",
                 None::<&()>,
+                DEFAULT_MAX_RETRIES,
             )
             .await
     }
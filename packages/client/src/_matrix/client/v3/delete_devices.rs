@@ -3,7 +3,7 @@
 //! Implementation of bulk device deletion with UIA per Matrix spec v1.8
 //! Reference: https://spec.matrix.org/v1.8/client-server-api/#device-management
 
-use crate::http_client::{HttpClientError, MatrixHttpClient};
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
 use reqwest::Method;
 use serde::{Deserialize, Serialize};
 
@@ -102,10 +102,11 @@ impl DeleteDevicesClient {
         // Delete returns empty JSON object {} on success
         let _: serde_json::Value = self
             .http_client
-            .request(
+            .request_with_retry(
                 Method::POST,
                 "/_matrix/client/v3/delete_devices",
                 Some(&request),
+                DEFAULT_MAX_RETRIES,
             )
             .await?;
         
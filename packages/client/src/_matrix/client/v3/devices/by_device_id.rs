@@ -3,7 +3,7 @@
 //! Implementation of per-device endpoints per Matrix spec v1.8
 
 use super::DeviceInfo;
-use crate::http_client::{HttpClientError, MatrixHttpClient};
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
 use reqwest::Method;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -58,7 +58,7 @@ impl DeviceByIdClient {
         let path = format!("/_matrix/client/v3/devices/{}", device_id);
         
         self.http_client
-            .request(Method::GET, &path, None::<&()>)
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
             .await
     }
 
@@ -86,7 +86,7 @@ impl DeviceByIdClient {
 
         let _: EmptyResponse = self
             .http_client
-            .request(Method::PUT, &path, Some(&body))
+            .request_with_retry(Method::PUT, &path, Some(&body), DEFAULT_MAX_RETRIES)
             .await?;
 
         Ok(())
@@ -117,7 +117,7 @@ impl DeviceByIdClient {
 
         let _: EmptyResponse = self
             .http_client
-            .request(Method::DELETE, &path, Some(&body))
+            .request_with_retry(Method::DELETE, &path, Some(&body), DEFAULT_MAX_RETRIES)
             .await?;
 
         Ok(())
@@ -3,7 +3,7 @@
 //! Implementation of device management endpoints per Matrix spec v1.8
 //! Reference: https://spec.matrix.org/v1.8/client-server-api/#device-management
 
-use crate::http_client::{HttpClientError, MatrixHttpClient};
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
 use reqwest::Method;
 use serde::{Deserialize, Serialize};
 
@@ -66,10 +66,11 @@ impl DeviceClient {
     pub async fn get_devices(&self) -> Result<Vec<DeviceInfo>, HttpClientError> {
         let response: GetDevicesResponse = self
             .http_client
-            .request(
+            .request_with_retry(
                 Method::GET,
                 "/_matrix/client/v3/devices",
                 None::<&()>,
+                DEFAULT_MAX_RETRIES,
             )
             .await?;
 
@@ -1 +1,137 @@
-pub mod room;
+//! Matrix Client-Server API: Room Directory
+//!
+//! Implementation of room aliases and room directory visibility per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#room-aliases
+
+use crate::_matrix::client::EmptyResponse;
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use matryx_entity::types::{RoomAliasResponse, RoomAliasesResponse};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+/// Visibility of a room in the published room directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RoomVisibility {
+    Public,
+    Private,
+}
+
+#[derive(Debug, Serialize)]
+struct SetAliasRequest<'a> {
+    room_id: &'a str,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct VisibilityBody {
+    visibility: RoomVisibility,
+}
+
+/// Client for room aliases and the room directory
+#[derive(Clone)]
+pub struct DirectoryClient {
+    http_client: MatrixHttpClient,
+}
+
+impl DirectoryClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Resolve a room alias to a room ID and servers to join through
+    ///
+    /// GET /_matrix/client/v3/directory/room/{roomAlias}
+    pub async fn resolve_alias(
+        &self,
+        room_alias: &str,
+    ) -> Result<RoomAliasResponse, HttpClientError> {
+        let path = alias_path(room_alias);
+
+        self.http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await
+    }
+
+    /// Point a room alias at a room
+    ///
+    /// PUT /_matrix/client/v3/directory/room/{roomAlias}
+    ///
+    /// # Errors
+    /// * 409 if the alias already exists
+    pub async fn set_alias(&self, room_alias: &str, room_id: &str) -> Result<(), HttpClientError> {
+        let path = alias_path(room_alias);
+        let body = SetAliasRequest { room_id };
+
+        let _: EmptyResponse = self
+            .http_client
+            .request_with_retry(Method::PUT, &path, Some(&body), DEFAULT_MAX_RETRIES)
+            .await?;
+        Ok(())
+    }
+
+    /// Remove a room alias
+    ///
+    /// DELETE /_matrix/client/v3/directory/room/{roomAlias}
+    pub async fn delete_alias(&self, room_alias: &str) -> Result<(), HttpClientError> {
+        let path = alias_path(room_alias);
+
+        let _: EmptyResponse = self
+            .http_client
+            .request_with_retry(Method::DELETE, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await?;
+        Ok(())
+    }
+
+    /// Get the local aliases of a room
+    ///
+    /// GET /_matrix/client/v3/rooms/{roomId}/aliases
+    pub async fn get_room_aliases(
+        &self,
+        room_id: &str,
+    ) -> Result<RoomAliasesResponse, HttpClientError> {
+        let path = format!("/_matrix/client/v3/rooms/{}/aliases", urlencoding::encode(room_id));
+
+        self.http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await
+    }
+
+    /// Get whether a room is published in the room directory
+    ///
+    /// GET /_matrix/client/v3/directory/list/room/{roomId}
+    pub async fn get_visibility(&self, room_id: &str) -> Result<RoomVisibility, HttpClientError> {
+        let path = visibility_path(room_id);
+
+        let response: VisibilityBody = self
+            .http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await?;
+        Ok(response.visibility)
+    }
+
+    /// Publish a room in, or remove it from, the room directory
+    ///
+    /// PUT /_matrix/client/v3/directory/list/room/{roomId}
+    pub async fn set_visibility(
+        &self,
+        room_id: &str,
+        visibility: RoomVisibility,
+    ) -> Result<(), HttpClientError> {
+        let path = visibility_path(room_id);
+        let body = VisibilityBody { visibility };
+
+        let _: EmptyResponse = self
+            .http_client
+            .request_with_retry(Method::PUT, &path, Some(&body), DEFAULT_MAX_RETRIES)
+            .await?;
+        Ok(())
+    }
+}
+
+fn alias_path(room_alias: &str) -> String {
+    format!("/_matrix/client/v3/directory/room/{}", urlencoding::encode(room_alias))
+}
+
+fn visibility_path(room_id: &str) -> String {
+    format!("/_matrix/client/v3/directory/list/room/{}", urlencoding::encode(room_id))
+}
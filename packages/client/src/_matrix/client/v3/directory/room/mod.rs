@@ -1 +0,0 @@
-pub mod by_room_alias;
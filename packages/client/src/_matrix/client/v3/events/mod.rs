@@ -1 +1,69 @@
+//! Matrix Client-Server API: Events
+//!
+//! Implementation of the deprecated event stream endpoints per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#get_matrixclientv3events
 
+use crate::_matrix::client::with_query;
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use matryx_entity::Event;
+use reqwest::Method;
+use serde::Deserialize;
+
+/// Response from GET /_matrix/client/v3/events
+#[derive(Debug, Clone, Deserialize)]
+pub struct EventsResponse {
+    pub chunk: Vec<Event>,
+    pub start: String,
+    /// Token to pass as `from` for the next request
+    pub end: String,
+}
+
+/// Client for the event stream
+#[derive(Clone)]
+pub struct EventsClient {
+    http_client: MatrixHttpClient,
+}
+
+impl EventsClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Wait for events after a stream token
+    ///
+    /// GET /_matrix/client/v3/events
+    ///
+    /// # Arguments
+    /// * `timeout` - How long to wait for events, in milliseconds
+    /// * `room_id` - Only return events of this room
+    pub async fn get_events(
+        &self,
+        from: Option<&str>,
+        timeout: Option<u64>,
+        room_id: Option<&str>,
+    ) -> Result<EventsResponse, HttpClientError> {
+        let path = with_query(
+            "/_matrix/client/v3/events",
+            [
+                ("from", from.map(str::to_string)),
+                ("timeout", timeout.map(|timeout| timeout.to_string())),
+                ("room_id", room_id.map(str::to_string)),
+            ],
+        );
+
+        self.http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await
+    }
+
+    /// Get a single event by ID
+    ///
+    /// GET /_matrix/client/v3/events/{eventId}
+    pub async fn get_event(&self, event_id: &str) -> Result<Event, HttpClientError> {
+        let path = format!("/_matrix/client/v3/events/{}", urlencoding::encode(event_id));
+
+        self.http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await
+    }
+}
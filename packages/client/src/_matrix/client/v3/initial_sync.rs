@@ -1,12 +1,60 @@
-//! Client stub for _matrix/client/v3/initial_sync.rs
+//! Matrix Client-Server API: Initial Sync
 //!
-//! This is a placeholder stub for the client implementation.
-//! The actual HTTP client functionality should be implemented here
-//! using reqwest to make outbound HTTP requests.
-
-// Placeholder stub - implement actual client functionality as needed
-pub mod client_stub {
-    pub fn placeholder() {
-        // Client implementation would go here
+//! Implementation of the deprecated initial sync endpoint per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#get_matrixclientv3initialsync
+
+use crate::_matrix::client::with_query;
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use reqwest::Method;
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Response from GET /_matrix/client/v3/initialSync
+#[derive(Debug, Clone, Deserialize)]
+pub struct InitialSyncResponse {
+    #[serde(default)]
+    pub rooms: Vec<Value>,
+    #[serde(default)]
+    pub presence: Vec<Value>,
+    #[serde(default)]
+    pub account_data: Vec<Value>,
+    /// Token to continue from with `/sync` or `/events`
+    pub next_batch: Option<String>,
+}
+
+/// Client for the initial sync
+#[derive(Clone)]
+pub struct InitialSyncClient {
+    http_client: MatrixHttpClient,
+}
+
+impl InitialSyncClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Get a snapshot of all rooms the user is in
+    ///
+    /// GET /_matrix/client/v3/initialSync
+    ///
+    /// # Arguments
+    /// * `limit` - Maximum number of timeline events per room
+    /// * `archived` - Whether to include rooms the user has left
+    pub async fn initial_sync(
+        &self,
+        limit: Option<u32>,
+        archived: bool,
+    ) -> Result<InitialSyncResponse, HttpClientError> {
+        let path = with_query(
+            "/_matrix/client/v3/initialSync",
+            [
+                ("limit", limit.map(|limit| limit.to_string())),
+                ("archived", archived.then(|| "true".to_string())),
+            ],
+        );
+
+        self.http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await
     }
 }
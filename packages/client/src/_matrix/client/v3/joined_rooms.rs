@@ -1,12 +1,36 @@
-//! Client stub for _matrix/client/v3/joined_rooms.rs
+//! Matrix Client-Server API: Joined Rooms
 //!
-//! This is a placeholder stub for the client implementation.
-//! The actual HTTP client functionality should be implemented here
-//! using reqwest to make outbound HTTP requests.
-
-// Placeholder stub - implement actual client functionality as needed
-pub mod client_stub {
-    pub fn placeholder() {
-        // Client implementation would go here
+//! Implementation of GET /_matrix/client/v3/joined_rooms per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#get_matrixclientv3joined_rooms
+
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use matryx_entity::types::JoinedRoomsResponse;
+use reqwest::Method;
+
+/// Client for the user's joined rooms
+#[derive(Clone)]
+pub struct JoinedRoomsClient {
+    http_client: MatrixHttpClient,
+}
+
+impl JoinedRoomsClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Get the IDs of the rooms the user is joined to
+    ///
+    /// GET /_matrix/client/v3/joined_rooms
+    pub async fn get_joined_rooms(&self) -> Result<Vec<String>, HttpClientError> {
+        let response: JoinedRoomsResponse = self
+            .http_client
+            .request_with_retry(
+                Method::GET,
+                "/_matrix/client/v3/joined_rooms",
+                None::<&()>,
+                DEFAULT_MAX_RETRIES,
+            )
+            .await?;
+        Ok(response.joined_rooms)
     }
 }
@@ -1 +1,159 @@
+//! Matrix Client-Server API: End-to-End Encryption Keys
+//!
+//! Implementation of the key management endpoints per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#key-management-api
+//!
+//! Request and response bodies are shared with [`crate::crypto::OlmMachine`].
 
+use crate::_matrix::client::{EmptyResponse, with_query};
+use crate::crypto::{CrossSigningPublicKeys, KeysUploadRequest, SignatureUploadRequest};
+use crate::device::{
+    ClaimKeysResponse, QueryKeysResponse, UploadKeysResponse, UploadSignaturesResponse,
+};
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use matryx_entity::types::{KeyClaimRequest, KeyQueryRequest};
+use reqwest::Method;
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Response from GET /_matrix/client/v3/keys/changes
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeyChangesResponse {
+    /// Users whose devices changed and who share a room with the user
+    #[serde(default)]
+    pub changed: Vec<String>,
+    /// Users who no longer share an encrypted room with the user
+    #[serde(default)]
+    pub left: Vec<String>,
+}
+
+/// Client for end-to-end encryption keys
+#[derive(Clone)]
+pub struct KeysClient {
+    http_client: MatrixHttpClient,
+}
+
+impl KeysClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Publish device keys and one-time keys
+    ///
+    /// POST /_matrix/client/v3/keys/upload
+    pub async fn upload_keys(
+        &self,
+        request: &KeysUploadRequest,
+    ) -> Result<UploadKeysResponse, HttpClientError> {
+        self.http_client
+            .request_with_retry(
+                Method::POST,
+                "/_matrix/client/v3/keys/upload",
+                Some(request),
+                DEFAULT_MAX_RETRIES,
+            )
+            .await
+    }
+
+    /// Download the device and cross-signing keys of users
+    ///
+    /// POST /_matrix/client/v3/keys/query
+    pub async fn query_keys(
+        &self,
+        request: &KeyQueryRequest,
+    ) -> Result<QueryKeysResponse, HttpClientError> {
+        self.http_client
+            .request_with_retry(
+                Method::POST,
+                "/_matrix/client/v3/keys/query",
+                Some(request),
+                DEFAULT_MAX_RETRIES,
+            )
+            .await
+    }
+
+    /// Claim one-time keys to start Olm sessions with devices
+    ///
+    /// POST /_matrix/client/v3/keys/claim
+    pub async fn claim_keys(
+        &self,
+        request: &KeyClaimRequest,
+    ) -> Result<ClaimKeysResponse, HttpClientError> {
+        self.http_client
+            .request_with_retry(
+                Method::POST,
+                "/_matrix/client/v3/keys/claim",
+                Some(request),
+                DEFAULT_MAX_RETRIES,
+            )
+            .await
+    }
+
+    /// Get the users whose device keys changed between two sync tokens
+    ///
+    /// GET /_matrix/client/v3/keys/changes
+    pub async fn get_key_changes(
+        &self,
+        from: &str,
+        to: &str,
+    ) -> Result<KeyChangesResponse, HttpClientError> {
+        let path = with_query(
+            "/_matrix/client/v3/keys/changes",
+            [
+                ("from", Some(from.to_string())),
+                ("to", Some(to.to_string())),
+            ],
+        );
+
+        self.http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await
+    }
+
+    /// Publish cross-signing public keys
+    ///
+    /// POST /_matrix/client/v3/keys/device_signing/upload
+    ///
+    /// # Arguments
+    /// * `auth` - User-interactive auth, usually required when replacing keys
+    pub async fn upload_cross_signing_keys(
+        &self,
+        keys: &CrossSigningPublicKeys,
+        auth: Option<Value>,
+    ) -> Result<(), HttpClientError> {
+        let mut body = serde_json::to_value(keys)?;
+        if let Some(auth) = auth {
+            body["auth"] = auth;
+        }
+
+        let _: EmptyResponse = self
+            .http_client
+            .request_with_retry(
+                Method::POST,
+                "/_matrix/client/v3/keys/device_signing/upload",
+                Some(&body),
+                DEFAULT_MAX_RETRIES,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Publish cross-signing signatures of devices and master keys
+    ///
+    /// POST /_matrix/client/v3/keys/signatures/upload
+    ///
+    /// Signatures the server rejected are listed in the response's `failures`.
+    pub async fn upload_signatures(
+        &self,
+        signatures: &SignatureUploadRequest,
+    ) -> Result<UploadSignaturesResponse, HttpClientError> {
+        self.http_client
+            .request_with_retry(
+                Method::POST,
+                "/_matrix/client/v3/keys/signatures/upload",
+                Some(signatures),
+                DEFAULT_MAX_RETRIES,
+            )
+            .await
+    }
+}
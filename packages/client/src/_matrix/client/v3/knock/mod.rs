@@ -1 +1,51 @@
+//! Matrix Client-Server API: Knocking
+//!
+//! Implementation of POST /_matrix/client/v3/knock/{roomIdOrAlias} per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#knocking-on-rooms
 
+use crate::_matrix::client::with_query;
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use matryx_entity::types::KnockRoomResponse;
+use reqwest::Method;
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+struct KnockRequest<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<&'a str>,
+}
+
+/// Client for knocking on rooms
+#[derive(Clone)]
+pub struct KnockClient {
+    http_client: MatrixHttpClient,
+}
+
+impl KnockClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Ask to join a room with the `knock` join rule
+    ///
+    /// POST /_matrix/client/v3/knock/{roomIdOrAlias}
+    ///
+    /// # Arguments
+    /// * `via` - Servers to knock through, needed for rooms the server is not in
+    pub async fn knock(
+        &self,
+        room_id_or_alias: &str,
+        reason: Option<&str>,
+        via: &[&str],
+    ) -> Result<KnockRoomResponse, HttpClientError> {
+        let path = with_query(
+            &format!("/_matrix/client/v3/knock/{}", urlencoding::encode(room_id_or_alias)),
+            via.iter().map(|server| ("via", Some(server.to_string()))),
+        );
+        let body = KnockRequest { reason };
+
+        self.http_client
+            .request_with_retry(Method::POST, &path, Some(&body), DEFAULT_MAX_RETRIES)
+            .await
+    }
+}
@@ -77,7 +77,7 @@ impl LoginClient {
             device_id,
             initial_device_display_name: device_display_name,
             token: None,
-            refresh_token: None,
+            refresh_token: false,
         };
 
         self.login(&request).await
@@ -104,7 +104,7 @@ impl LoginClient {
             device_id,
             initial_device_display_name: device_display_name,
             token: Some(token.to_string()),
-            refresh_token: None,
+            refresh_token: false,
         };
 
         self.login(&request).await
@@ -1,6 +1,8 @@
 pub mod client;
+pub mod sso;
 
 pub use client::LoginClient;
+pub use super::refresh::RefreshResponse;
 
 use anyhow::Result;
 use reqwest::Client;
@@ -24,8 +26,9 @@ pub struct LoginRequest {
     pub initial_device_display_name: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub token: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub refresh_token: Option<String>,
+    /// Whether the client supports refresh tokens
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub refresh_token: bool,
 }
 
 /// Login response from Matrix server
@@ -134,7 +137,7 @@ pub async fn login_with_password(
         device_id,
         initial_device_display_name: device_display_name,
         token: None,
-        refresh_token: None,
+        refresh_token: false,
     };
 
     login(client, homeserver_url, request).await
@@ -155,28 +158,36 @@ pub async fn login_with_token(
         device_id,
         initial_device_display_name: device_display_name,
         token: Some(token.to_string()),
-        refresh_token: None,
+        refresh_token: false,
     };
 
     login(client, homeserver_url, request).await
 }
 
 /// Refresh access token using refresh token
+///
+/// Sends POST /_matrix/client/v3/refresh. The server may rotate the refresh
+/// token, in which case the old one stops working.
 pub async fn refresh_access_token(
     client: &Client,
     homeserver_url: &Url,
     refresh_token: &str,
-    device_id: Option<String>,
-) -> Result<LoginResponse> {
-    let request = LoginRequest {
-        login_type: "m.login.password".to_string(), // Use password type for refresh
-        user: None,
-        password: None,
-        device_id,
-        initial_device_display_name: None,
-        token: None,
-        refresh_token: Some(refresh_token.to_string()),
-    };
+) -> Result<RefreshResponse> {
+    let url = homeserver_url.join("/_matrix/client/v3/refresh")?;
 
-    login(client, homeserver_url, request).await
+    let response = client
+        .post(url)
+        .header("User-Agent", "Matryx-Client/0.1.0")
+        .json(&serde_json::json!({ "refresh_token": refresh_token }))
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_body = response.text().await.unwrap_or_default();
+        error!("Token refresh failed: {} - {}", status, error_body);
+        return Err(anyhow::anyhow!("Token refresh failed: {} - {}", status, error_body));
+    }
+
+    Ok(response.json::<RefreshResponse>().await?)
 }
@@ -0,0 +1,34 @@
+//! Matrix Client-Server API: Single Sign-On
+//!
+//! Implementation of GET /_matrix/client/v3/login/sso/redirect per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#client-login-via-sso
+//!
+//! The redirect endpoints are opened in a browser rather than called by the
+//! client, so this module only builds their URLs.
+
+use url::Url;
+
+/// URL to send the user's browser to for SSO login
+///
+/// After authenticating, the homeserver redirects the browser to
+/// `redirect_url` with a `loginToken` query parameter for `m.login.token`.
+///
+/// # Arguments
+/// * `idp_id` - Identity provider from the `m.login.sso` flow, or `None` to
+///   let the homeserver ask the user
+pub fn sso_redirect_url(
+    homeserver_url: &Url,
+    redirect_url: &Url,
+    idp_id: Option<&str>,
+) -> Result<Url, url::ParseError> {
+    let path = match idp_id {
+        Some(idp_id) => {
+            format!("/_matrix/client/v3/login/sso/redirect/{}", urlencoding::encode(idp_id))
+        },
+        None => "/_matrix/client/v3/login/sso/redirect".to_string(),
+    };
+
+    let mut url = homeserver_url.join(&path)?;
+    url.query_pairs_mut().append_pair("redirectUrl", redirect_url.as_str());
+    Ok(url)
+}
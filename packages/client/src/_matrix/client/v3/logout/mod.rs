@@ -1 +1,58 @@
+//! Matrix Client-Server API: Logout
+//!
+//! Implementation of the logout endpoints per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#post_matrixclientv3logout
 
+use crate::_matrix::client::EmptyResponse;
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use reqwest::Method;
+
+/// Client for ending sessions
+#[derive(Clone)]
+pub struct LogoutClient {
+    http_client: MatrixHttpClient,
+}
+
+impl LogoutClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Invalidate the access token and delete its device
+    ///
+    /// POST /_matrix/client/v3/logout
+    pub async fn logout(&self) -> Result<(), HttpClientError> {
+        self.post("/_matrix/client/v3/logout").await
+    }
+
+    /// Invalidate all access tokens of the user and delete all their devices
+    ///
+    /// POST /_matrix/client/v3/logout/all
+    pub async fn logout_all(&self) -> Result<(), HttpClientError> {
+        self.post("/_matrix/client/v3/logout/all").await
+    }
+
+    /// Invalidate the access token but keep its device and encryption keys
+    ///
+    /// POST /_matrix/client/v3/logout/soft
+    ///
+    /// The device can log in again with the same `device_id`.
+    pub async fn soft_logout(&self) -> Result<(), HttpClientError> {
+        self.post("/_matrix/client/v3/logout/soft").await
+    }
+
+    async fn post(&self, path: &str) -> Result<(), HttpClientError> {
+        let _: EmptyResponse = self
+            .http_client
+            .request_with_retry(
+                Method::POST,
+                path,
+                Some(&serde_json::json!({})),
+                DEFAULT_MAX_RETRIES,
+            )
+            .await?;
+
+        self.http_client.clear_access_token().await;
+        Ok(())
+    }
+}
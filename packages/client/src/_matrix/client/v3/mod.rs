@@ -30,5 +30,4 @@ pub mod sync;
 pub mod thirdparty;
 pub mod user;
 pub mod user_directory;
-pub mod users;
 pub mod voip;
@@ -1,12 +1,71 @@
-//! Client stub for _matrix/client/v3/notifications.rs
+//! Matrix Client-Server API: Notifications
 //!
-//! This is a placeholder stub for the client implementation.
-//! The actual HTTP client functionality should be implemented here
-//! using reqwest to make outbound HTTP requests.
-
-// Placeholder stub - implement actual client functionality as needed
-pub mod client_stub {
-    pub fn placeholder() {
-        // Client implementation would go here
+//! Implementation of GET /_matrix/client/v3/notifications per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#listing-notifications
+
+use crate::_matrix::client::with_query;
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use matryx_entity::Event;
+use matryx_entity::types::PushAction;
+use reqwest::Method;
+use serde::Deserialize;
+
+/// An event the user was notified about
+#[derive(Debug, Clone, Deserialize)]
+pub struct Notification {
+    /// Actions of the push rule that matched the event
+    pub actions: Vec<PushAction>,
+    pub event: Event,
+    pub profile_tag: Option<String>,
+    /// Whether the user has read the event
+    pub read: bool,
+    pub room_id: String,
+    /// When the notification was sent, in milliseconds
+    pub ts: i64,
+}
+
+/// Response from GET /_matrix/client/v3/notifications
+#[derive(Debug, Clone, Deserialize)]
+pub struct NotificationsResponse {
+    /// Most recent first
+    pub notifications: Vec<Notification>,
+    pub next_token: Option<String>,
+}
+
+/// Client for the notification list
+#[derive(Clone)]
+pub struct NotificationsClient {
+    http_client: MatrixHttpClient,
+}
+
+impl NotificationsClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// List the events the user was notified about
+    ///
+    /// GET /_matrix/client/v3/notifications
+    ///
+    /// # Arguments
+    /// * `only` - `"highlight"` to list only highlights
+    pub async fn get_notifications(
+        &self,
+        from: Option<&str>,
+        limit: Option<u32>,
+        only: Option<&str>,
+    ) -> Result<NotificationsResponse, HttpClientError> {
+        let path = with_query(
+            "/_matrix/client/v3/notifications",
+            [
+                ("from", from.map(str::to_string)),
+                ("limit", limit.map(|limit| limit.to_string())),
+                ("only", only.map(str::to_string)),
+            ],
+        );
+
+        self.http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await
     }
 }
@@ -1 +1,81 @@
+//! Matrix Client-Server API: Presence
+//!
+//! Implementation of the presence status endpoints per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#presence
 
+use crate::_matrix::client::EmptyResponse;
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+/// A user's presence state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PresenceState {
+    Online,
+    Offline,
+    Unavailable,
+}
+
+/// Response from GET /_matrix/client/v3/presence/{userId}/status
+#[derive(Debug, Clone, Deserialize)]
+pub struct PresenceStatus {
+    pub presence: PresenceState,
+    /// Milliseconds since the user last did something
+    pub last_active_ago: Option<u64>,
+    pub status_msg: Option<String>,
+    pub currently_active: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+struct SetPresenceRequest<'a> {
+    presence: PresenceState,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status_msg: Option<&'a str>,
+}
+
+/// Client for presence
+#[derive(Clone)]
+pub struct PresenceClient {
+    http_client: MatrixHttpClient,
+}
+
+impl PresenceClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Get a user's presence
+    ///
+    /// GET /_matrix/client/v3/presence/{userId}/status
+    pub async fn get_presence(&self, user_id: &str) -> Result<PresenceStatus, HttpClientError> {
+        let path = status_path(user_id);
+
+        self.http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await
+    }
+
+    /// Set the user's own presence
+    ///
+    /// PUT /_matrix/client/v3/presence/{userId}/status
+    pub async fn set_presence(
+        &self,
+        user_id: &str,
+        presence: PresenceState,
+        status_msg: Option<&str>,
+    ) -> Result<(), HttpClientError> {
+        let path = status_path(user_id);
+        let body = SetPresenceRequest { presence, status_msg };
+
+        let _: EmptyResponse = self
+            .http_client
+            .request_with_retry(Method::PUT, &path, Some(&body), DEFAULT_MAX_RETRIES)
+            .await?;
+        Ok(())
+    }
+}
+
+fn status_path(user_id: &str) -> String {
+    format!("/_matrix/client/v3/presence/{}/status", urlencoding::encode(user_id))
+}
@@ -3,11 +3,15 @@
 //! GET/PUT /_matrix/client/v3/profile/{userId}
 //! GET/PUT /_matrix/client/v3/profile/{userId}/displayname  
 //! GET/PUT /_matrix/client/v3/profile/{userId}/avatar_url
+//! PUT /_matrix/client/v3/profile/{userId}/{keyName}
 //!
-//! Manage user profiles including display names and avatar URLs.
+//! Manage user profiles including display names, avatar URLs and custom fields.
 
-use reqwest::Client;
+use crate::_matrix::client::EmptyResponse;
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use reqwest::{Client, Method};
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use tracing::{debug, error, info};
 use url::Url;
 
@@ -281,3 +285,40 @@ pub async fn set_avatar_url(
         Err(format!("HTTP error {}: {}", status, error_text).into())
     }
 }
+
+/// Client for custom profile fields (MSC4133)
+#[derive(Clone)]
+pub struct ProfileFieldClient {
+    http_client: MatrixHttpClient,
+}
+
+impl ProfileFieldClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Set a field of the user's profile
+    ///
+    /// PUT /_matrix/client/v3/profile/{userId}/{keyName}
+    ///
+    /// Custom keys should be namespaced, e.g. `m.tz` or `org.example.pronouns`.
+    pub async fn set_profile_field(
+        &self,
+        user_id: &str,
+        key_name: &str,
+        value: Value,
+    ) -> std::result::Result<(), HttpClientError> {
+        let path = format!(
+            "/_matrix/client/v3/profile/{}/{}",
+            urlencoding::encode(user_id),
+            urlencoding::encode(key_name)
+        );
+        let body = Map::from_iter([(key_name.to_string(), value)]);
+
+        let _: EmptyResponse = self
+            .http_client
+            .request_with_retry(Method::PUT, &path, Some(&body), DEFAULT_MAX_RETRIES)
+            .await?;
+        Ok(())
+    }
+}
@@ -1 +1,63 @@
+//! Matrix Client-Server API: Published Room Directory
+//!
+//! Implementation of the public rooms listing per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#listing-rooms
 
+use crate::_matrix::client::with_query;
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use matryx_entity::types::{PublicRoomsGetResponse, PublicRoomsPostRequest};
+use reqwest::Method;
+
+/// Client for the published room directory
+#[derive(Clone)]
+pub struct PublicRoomsClient {
+    http_client: MatrixHttpClient,
+}
+
+impl PublicRoomsClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// List a page of the published rooms
+    ///
+    /// GET /_matrix/client/v3/publicRooms
+    ///
+    /// # Arguments
+    /// * `server` - Server whose directory to list, the homeserver's if unset
+    pub async fn get_public_rooms(
+        &self,
+        server: Option<&str>,
+        limit: Option<u32>,
+        since: Option<&str>,
+    ) -> Result<PublicRoomsGetResponse, HttpClientError> {
+        let path = with_query(
+            "/_matrix/client/v3/publicRooms",
+            [
+                ("server", server.map(str::to_string)),
+                ("limit", limit.map(|limit| limit.to_string())),
+                ("since", since.map(str::to_string)),
+            ],
+        );
+
+        self.http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await
+    }
+
+    /// Search the published rooms
+    ///
+    /// POST /_matrix/client/v3/publicRooms
+    pub async fn search_public_rooms(
+        &self,
+        server: Option<&str>,
+        request: &PublicRoomsPostRequest,
+    ) -> Result<PublicRoomsGetResponse, HttpClientError> {
+        let path =
+            with_query("/_matrix/client/v3/publicRooms", [("server", server.map(str::to_string))]);
+
+        self.http_client
+            .request_with_retry(Method::POST, &path, Some(request), DEFAULT_MAX_RETRIES)
+            .await
+    }
+}
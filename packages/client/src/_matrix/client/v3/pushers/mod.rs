@@ -1 +1,122 @@
+//! Matrix Client-Server API: Pushers
+//!
+//! Implementation of the pusher management endpoints per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#push-notifications
 
+use crate::_matrix::client::EmptyResponse;
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+/// Data passed to the push gateway
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PusherData {
+    /// Push gateway URL, required for `http` pushers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// `"event_id_only"` to send only event IDs to the gateway
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+}
+
+/// A pusher registered for the user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pusher {
+    pub pushkey: String,
+    /// `"http"` or `"email"`
+    pub kind: String,
+    pub app_id: String,
+    pub app_display_name: String,
+    pub device_display_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile_tag: Option<String>,
+    pub lang: String,
+    pub data: PusherData,
+}
+
+#[derive(Debug, Deserialize)]
+struct PushersResponse {
+    #[serde(default)]
+    pushers: Vec<Pusher>,
+}
+
+#[derive(Debug, Serialize)]
+struct SetPusherRequest<'a> {
+    #[serde(flatten)]
+    pusher: &'a Pusher,
+    append: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct DeletePusherRequest<'a> {
+    pushkey: &'a str,
+    app_id: &'a str,
+    kind: Option<()>,
+}
+
+/// Client for pushers
+#[derive(Clone)]
+pub struct PushersClient {
+    http_client: MatrixHttpClient,
+}
+
+impl PushersClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// List the user's pushers
+    ///
+    /// GET /_matrix/client/v3/pushers
+    pub async fn get_pushers(&self) -> Result<Vec<Pusher>, HttpClientError> {
+        let response: PushersResponse = self
+            .http_client
+            .request_with_retry(
+                Method::GET,
+                "/_matrix/client/v3/pushers",
+                None::<&()>,
+                DEFAULT_MAX_RETRIES,
+            )
+            .await?;
+        Ok(response.pushers)
+    }
+
+    /// Create or update a pusher
+    ///
+    /// POST /_matrix/client/v3/pushers/set
+    ///
+    /// # Arguments
+    /// * `append` - Keep other pushers with the same pushkey for other users
+    pub async fn set_pusher(&self, pusher: &Pusher, append: bool) -> Result<(), HttpClientError> {
+        let body = SetPusherRequest { pusher, append };
+
+        let _: EmptyResponse = self
+            .http_client
+            .request_with_retry(
+                Method::POST,
+                "/_matrix/client/v3/pushers/set",
+                Some(&body),
+                DEFAULT_MAX_RETRIES,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Remove a pusher
+    ///
+    /// POST /_matrix/client/v3/pushers/set with a null `kind`
+    pub async fn delete_pusher(&self, pushkey: &str, app_id: &str) -> Result<(), HttpClientError> {
+        let body = DeletePusherRequest { pushkey, app_id, kind: None };
+
+        let _: EmptyResponse = self
+            .http_client
+            .request_with_retry(
+                Method::POST,
+                "/_matrix/client/v3/pushers/set",
+                Some(&body),
+                DEFAULT_MAX_RETRIES,
+            )
+            .await?;
+        Ok(())
+    }
+}
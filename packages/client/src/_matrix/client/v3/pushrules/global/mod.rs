@@ -1 +0,0 @@
-pub mod by_kind;
\ No newline at end of file
@@ -1,12 +1,222 @@
-//! Client stub for _matrix/client/v3/pushrules/mod.rs
+//! Matrix Client-Server API: Push Rules
 //!
-//! This is a placeholder stub for the client implementation.
-//! The actual HTTP client functionality should be implemented here
-//! using reqwest to make outbound HTTP requests.
-
-// Placeholder stub - implement actual client functionality as needed
-pub mod client_stub {
-    pub fn placeholder() {
-        // Client implementation would go here
+//! Implementation of the push rule endpoints per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#push-rules-api
+
+use crate::_matrix::client::{EmptyResponse, with_query};
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use matryx_entity::types::{
+    PushAction, PushRule, PushRuleActionsUpdateRequest, PushRuleCreateUpdateRequest,
+    PushRuleEnabledGetResponse, PushRuleEnabledUpdateRequest, PushRulesGetResponse, Ruleset,
+};
+use reqwest::Method;
+
+/// Kind of a push rule, which decides where it sits in the evaluation order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PushRuleKind {
+    Override,
+    Content,
+    Room,
+    Sender,
+    Underride,
+}
+
+impl PushRuleKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PushRuleKind::Override => "override",
+            PushRuleKind::Content => "content",
+            PushRuleKind::Room => "room",
+            PushRuleKind::Sender => "sender",
+            PushRuleKind::Underride => "underride",
+        }
+    }
+}
+
+/// Client for push rules
+///
+/// Only the `global` scope exists in the specification.
+#[derive(Clone)]
+pub struct PushRulesClient {
+    http_client: MatrixHttpClient,
+}
+
+impl PushRulesClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Get all push rules of the user
+    ///
+    /// GET /_matrix/client/v3/pushrules/
+    pub async fn get_push_rules(&self) -> Result<PushRulesGetResponse, HttpClientError> {
+        self.http_client
+            .request_with_retry(
+                Method::GET,
+                "/_matrix/client/v3/pushrules/",
+                None::<&()>,
+                DEFAULT_MAX_RETRIES,
+            )
+            .await
+    }
+
+    /// Get the push rules of the `global` scope
+    ///
+    /// GET /_matrix/client/v3/pushrules/global/
+    pub async fn get_global_rules(&self) -> Result<Ruleset, HttpClientError> {
+        self.http_client
+            .request_with_retry(
+                Method::GET,
+                "/_matrix/client/v3/pushrules/global/",
+                None::<&()>,
+                DEFAULT_MAX_RETRIES,
+            )
+            .await
+    }
+
+    /// Get a single push rule
+    ///
+    /// GET /_matrix/client/v3/pushrules/global/{kind}/{ruleId}
+    pub async fn get_rule(
+        &self,
+        kind: PushRuleKind,
+        rule_id: &str,
+    ) -> Result<PushRule, HttpClientError> {
+        let path = rule_path(kind, rule_id, None);
+
+        self.http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await
+    }
+
+    /// Create or replace a user-defined push rule
+    ///
+    /// PUT /_matrix/client/v3/pushrules/global/{kind}/{ruleId}
+    ///
+    /// # Arguments
+    /// * `before` - Rule ID of the same kind the new rule should come before
+    /// * `after` - Rule ID of the same kind the new rule should come after
+    pub async fn set_rule(
+        &self,
+        kind: PushRuleKind,
+        rule_id: &str,
+        rule: &PushRuleCreateUpdateRequest,
+        before: Option<&str>,
+        after: Option<&str>,
+    ) -> Result<(), HttpClientError> {
+        let path = with_query(
+            &rule_path(kind, rule_id, None),
+            [
+                ("before", before.map(str::to_string)),
+                ("after", after.map(str::to_string)),
+            ],
+        );
+
+        let _: EmptyResponse = self
+            .http_client
+            .request_with_retry(Method::PUT, &path, Some(rule), DEFAULT_MAX_RETRIES)
+            .await?;
+        Ok(())
+    }
+
+    /// Delete a user-defined push rule
+    ///
+    /// DELETE /_matrix/client/v3/pushrules/global/{kind}/{ruleId}
+    pub async fn delete_rule(
+        &self,
+        kind: PushRuleKind,
+        rule_id: &str,
+    ) -> Result<(), HttpClientError> {
+        let path = rule_path(kind, rule_id, None);
+
+        let _: EmptyResponse = self
+            .http_client
+            .request_with_retry(Method::DELETE, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await?;
+        Ok(())
+    }
+
+    /// Whether a push rule is enabled
+    ///
+    /// GET /_matrix/client/v3/pushrules/global/{kind}/{ruleId}/enabled
+    pub async fn is_rule_enabled(
+        &self,
+        kind: PushRuleKind,
+        rule_id: &str,
+    ) -> Result<bool, HttpClientError> {
+        let path = rule_path(kind, rule_id, Some("enabled"));
+
+        let response: PushRuleEnabledGetResponse = self
+            .http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await?;
+        Ok(response.enabled)
+    }
+
+    /// Enable or disable a push rule
+    ///
+    /// PUT /_matrix/client/v3/pushrules/global/{kind}/{ruleId}/enabled
+    pub async fn set_rule_enabled(
+        &self,
+        kind: PushRuleKind,
+        rule_id: &str,
+        enabled: bool,
+    ) -> Result<(), HttpClientError> {
+        let path = rule_path(kind, rule_id, Some("enabled"));
+        let body = PushRuleEnabledUpdateRequest::new(enabled);
+
+        let _: EmptyResponse = self
+            .http_client
+            .request_with_retry(Method::PUT, &path, Some(&body), DEFAULT_MAX_RETRIES)
+            .await?;
+        Ok(())
+    }
+
+    /// Get the actions of a push rule
+    ///
+    /// GET /_matrix/client/v3/pushrules/global/{kind}/{ruleId}/actions
+    pub async fn get_rule_actions(
+        &self,
+        kind: PushRuleKind,
+        rule_id: &str,
+    ) -> Result<Vec<PushAction>, HttpClientError> {
+        let path = rule_path(kind, rule_id, Some("actions"));
+
+        let response: PushRuleActionsUpdateRequest = self
+            .http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await?;
+        Ok(response.actions)
+    }
+
+    /// Replace the actions of a push rule, including server-default rules
+    ///
+    /// PUT /_matrix/client/v3/pushrules/global/{kind}/{ruleId}/actions
+    pub async fn set_rule_actions(
+        &self,
+        kind: PushRuleKind,
+        rule_id: &str,
+        actions: Vec<PushAction>,
+    ) -> Result<(), HttpClientError> {
+        let path = rule_path(kind, rule_id, Some("actions"));
+        let body = PushRuleActionsUpdateRequest::new(actions);
+
+        let _: EmptyResponse = self
+            .http_client
+            .request_with_retry(Method::PUT, &path, Some(&body), DEFAULT_MAX_RETRIES)
+            .await?;
+        Ok(())
+    }
+}
+
+fn rule_path(kind: PushRuleKind, rule_id: &str, attribute: Option<&str>) -> String {
+    let path = format!(
+        "/_matrix/client/v3/pushrules/global/{}/{}",
+        kind.as_str(),
+        urlencoding::encode(rule_id)
+    );
+    match attribute {
+        Some(attribute) => format!("{}/{}", path, attribute),
+        None => path,
     }
 }
@@ -1 +1,61 @@
+//! Matrix Client-Server API: Refreshing Access Tokens
+//!
+//! Implementation of POST /_matrix/client/v3/refresh per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#refreshing-access-tokens
 
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+/// Response from POST /_matrix/client/v3/refresh
+#[derive(Debug, Clone, Deserialize)]
+pub struct RefreshResponse {
+    pub access_token: String,
+    /// Replacement refresh token; the old one stops working when present
+    pub refresh_token: Option<String>,
+    /// Lifetime of the new access token in milliseconds
+    pub expires_in_ms: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct RefreshRequest<'a> {
+    refresh_token: &'a str,
+}
+
+/// Client for access token refresh
+#[derive(Clone)]
+pub struct RefreshClient {
+    http_client: MatrixHttpClient,
+}
+
+impl RefreshClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Exchange a refresh token for a new access token
+    ///
+    /// POST /_matrix/client/v3/refresh
+    ///
+    /// The new access token is also set on the underlying HTTP client.
+    ///
+    /// # Errors
+    /// Fails with `M_UNKNOWN_TOKEN` when the refresh token was already used
+    /// or the session ended.
+    pub async fn refresh(&self, refresh_token: &str) -> Result<RefreshResponse, HttpClientError> {
+        let body = RefreshRequest { refresh_token };
+
+        let response: RefreshResponse = self
+            .http_client
+            .request_with_retry(
+                Method::POST,
+                "/_matrix/client/v3/refresh",
+                Some(&body),
+                DEFAULT_MAX_RETRIES,
+            )
+            .await?;
+
+        self.http_client.set_access_token(response.access_token.clone()).await;
+        Ok(response)
+    }
+}
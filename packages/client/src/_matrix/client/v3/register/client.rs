@@ -1,6 +1,11 @@
 //! Registration client implementation using MatrixHttpClient
 
-use crate::http_client::{MatrixHttpClient, HttpClientError};
+use crate::_matrix::client::v3::account::{
+    EmailTokenRequest, MsisdnTokenRequest, RequestTokenResponse, SubmitTokenRequest,
+    SubmitTokenResponse,
+};
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use reqwest::Method;
 use super::{RegisterRequest, RegisterResponse, RegistrationFlowsResponse};
 
 /// Client for Matrix registration operations
@@ -88,4 +93,80 @@ impl RegisterClient {
 
         self.register(&request).await
     }
+
+    /// Email a validation token for registering with an address
+    ///
+    /// POST /_matrix/client/v3/register/email/requestToken
+    ///
+    /// Fails with M_THREEPID_IN_USE if the address is already bound to an account.
+    pub async fn request_email_token(
+        &self,
+        client_secret: &str,
+        email: &str,
+        send_attempt: u32,
+        next_link: Option<&str>,
+    ) -> Result<RequestTokenResponse, HttpClientError> {
+        let body = EmailTokenRequest { client_secret, email, send_attempt, next_link };
+
+        self.http_client
+            .request_with_retry(
+                Method::POST,
+                "/_matrix/client/v3/register/email/requestToken",
+                Some(&body),
+                DEFAULT_MAX_RETRIES,
+            )
+            .await
+    }
+
+    /// Text a validation token for registering with a phone number
+    ///
+    /// POST /_matrix/client/v3/register/msisdn/requestToken
+    ///
+    /// # Arguments
+    /// * `country` - Two-letter country code the number is dialled from
+    pub async fn request_msisdn_token(
+        &self,
+        client_secret: &str,
+        country: &str,
+        phone_number: &str,
+        send_attempt: u32,
+    ) -> Result<RequestTokenResponse, HttpClientError> {
+        let body = MsisdnTokenRequest { client_secret, country, phone_number, send_attempt };
+
+        self.http_client
+            .request_with_retry(
+                Method::POST,
+                "/_matrix/client/v3/register/msisdn/requestToken",
+                Some(&body),
+                DEFAULT_MAX_RETRIES,
+            )
+            .await
+    }
+
+    /// Submit a registration validation token
+    ///
+    /// POST /_matrix/client/v3/register/{medium}/submitToken
+    ///
+    /// # Arguments
+    /// * `medium` - `"email"` or `"msisdn"`, as used to request the token
+    ///
+    /// # Returns
+    /// - `Ok(true)` once validated; pass `sid` and `client_secret` in the
+    ///   `m.login.email.identity` or `m.login.msisdn` auth stage
+    pub async fn submit_token(
+        &self,
+        medium: &str,
+        token: &str,
+        sid: &str,
+        client_secret: &str,
+    ) -> Result<bool, HttpClientError> {
+        let path = format!("/_matrix/client/v3/register/{}/submitToken", medium);
+        let body = SubmitTokenRequest { token, session_id: sid, client_secret };
+
+        let response: SubmitTokenResponse = self
+            .http_client
+            .request_with_retry(Method::POST, &path, Some(&body), DEFAULT_MAX_RETRIES)
+            .await?;
+        Ok(response.success)
+    }
 }
@@ -3,7 +3,8 @@
 //! Implementation of room key backup endpoints per Matrix spec v1.8
 //! Reference: https://spec.matrix.org/v1.8/client-server-api/#server-side-key-backups
 
-use crate::http_client::{HttpClientError, MatrixHttpClient};
+use crate::_matrix::client::with_query;
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
 use reqwest::Method;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -88,7 +89,7 @@ impl RoomKeysClient {
         let path = format!("/_matrix/client/v3/room_keys/keys?version={}", version);
         
         self.http_client
-            .request(Method::PUT, &path, Some(&request))
+            .request_with_retry(Method::PUT, &path, Some(&request), DEFAULT_MAX_RETRIES)
             .await
     }
 
@@ -108,7 +109,7 @@ impl RoomKeysClient {
         let path = format!("/_matrix/client/v3/room_keys/keys?version={}", version);
         
         self.http_client
-            .request(Method::GET, &path, None::<&()>)
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
             .await
     }
 
@@ -122,7 +123,113 @@ impl RoomKeysClient {
         let path = format!("/_matrix/client/v3/room_keys/keys?version={}", version);
         
         self.http_client
-            .request(Method::DELETE, &path, None::<&()>)
+            .request_with_retry(Method::DELETE, &path, None::<&()>, DEFAULT_MAX_RETRIES)
             .await
     }
+
+    /// Backup the keys of one room
+    ///
+    /// PUT /_matrix/client/v3/room_keys/keys/{roomId}?version={version}
+    pub async fn backup_room_keys(
+        &self,
+        version: &str,
+        room_id: &str,
+        backup: &RoomKeyBackup,
+    ) -> Result<BackupKeysResponse, HttpClientError> {
+        let path = room_keys_path(version, room_id, None);
+
+        self.http_client
+            .request_with_retry(Method::PUT, &path, Some(backup), DEFAULT_MAX_RETRIES)
+            .await
+    }
+
+    /// Retrieve the backed up keys of one room
+    ///
+    /// GET /_matrix/client/v3/room_keys/keys/{roomId}?version={version}
+    pub async fn get_room_keys(
+        &self,
+        version: &str,
+        room_id: &str,
+    ) -> Result<RoomKeyBackup, HttpClientError> {
+        let path = room_keys_path(version, room_id, None);
+
+        self.http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await
+    }
+
+    /// Delete the backed up keys of one room
+    ///
+    /// DELETE /_matrix/client/v3/room_keys/keys/{roomId}?version={version}
+    pub async fn delete_room_keys(
+        &self,
+        version: &str,
+        room_id: &str,
+    ) -> Result<BackupKeysResponse, HttpClientError> {
+        let path = room_keys_path(version, room_id, None);
+
+        self.http_client
+            .request_with_retry(Method::DELETE, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await
+    }
+
+    /// Backup the key of one Megolm session
+    ///
+    /// PUT /_matrix/client/v3/room_keys/keys/{roomId}/{sessionId}?version={version}
+    ///
+    /// The server keeps an existing key if it is better than the new one.
+    pub async fn backup_session_key(
+        &self,
+        version: &str,
+        room_id: &str,
+        session_id: &str,
+        key: &KeyBackupData,
+    ) -> Result<BackupKeysResponse, HttpClientError> {
+        let path = room_keys_path(version, room_id, Some(session_id));
+
+        self.http_client
+            .request_with_retry(Method::PUT, &path, Some(key), DEFAULT_MAX_RETRIES)
+            .await
+    }
+
+    /// Retrieve the backed up key of one Megolm session
+    ///
+    /// GET /_matrix/client/v3/room_keys/keys/{roomId}/{sessionId}?version={version}
+    pub async fn get_session_key(
+        &self,
+        version: &str,
+        room_id: &str,
+        session_id: &str,
+    ) -> Result<KeyBackupData, HttpClientError> {
+        let path = room_keys_path(version, room_id, Some(session_id));
+
+        self.http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await
+    }
+
+    /// Delete the backed up key of one Megolm session
+    ///
+    /// DELETE /_matrix/client/v3/room_keys/keys/{roomId}/{sessionId}?version={version}
+    pub async fn delete_session_key(
+        &self,
+        version: &str,
+        room_id: &str,
+        session_id: &str,
+    ) -> Result<BackupKeysResponse, HttpClientError> {
+        let path = room_keys_path(version, room_id, Some(session_id));
+
+        self.http_client
+            .request_with_retry(Method::DELETE, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await
+    }
+}
+
+fn room_keys_path(version: &str, room_id: &str, session_id: Option<&str>) -> String {
+    let mut path = format!("/_matrix/client/v3/room_keys/keys/{}", urlencoding::encode(room_id));
+    if let Some(session_id) = session_id {
+        path.push('/');
+        path.push_str(&urlencoding::encode(session_id));
+    }
+    with_query(&path, [("version", Some(version.to_string()))])
 }
@@ -3,7 +3,7 @@
 //! Implementation of backup version endpoints per Matrix spec v1.8
 //! Reference: https://spec.matrix.org/v1.8/client-server-api/#server-side-key-backups
 
-use crate::http_client::{HttpClientError, MatrixHttpClient};
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
 use reqwest::Method;
 use serde::{Deserialize, Serialize};
 
@@ -79,10 +79,11 @@ impl BackupVersionClient {
         request: CreateBackupRequest,
     ) -> Result<CreateBackupResponse, HttpClientError> {
         self.http_client
-            .request(
+            .request_with_retry(
                 Method::POST,
                 "/_matrix/client/v3/room_keys/version",
                 Some(&request),
+                DEFAULT_MAX_RETRIES,
             )
             .await
     }
@@ -100,7 +101,7 @@ impl BackupVersionClient {
         let path = format!("/_matrix/client/v3/room_keys/version/{}", version);
         
         self.http_client
-            .request(Method::GET, &path, None::<&()>)
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
             .await
     }
 
@@ -109,14 +110,35 @@ impl BackupVersionClient {
     /// GET /_matrix/client/v3/room_keys/version
     pub async fn get_latest_version(&self) -> Result<BackupInfo, HttpClientError> {
         self.http_client
-            .request(
+            .request_with_retry(
                 Method::GET,
                 "/_matrix/client/v3/room_keys/version",
                 None::<&()>,
+                DEFAULT_MAX_RETRIES,
             )
             .await
     }
 
+    /// Replace the auth data of a backup version
+    ///
+    /// PUT /_matrix/client/v3/room_keys/version/{version}
+    ///
+    /// The algorithm must match the one the version was created with.
+    pub async fn update_version(
+        &self,
+        version: &str,
+        request: CreateBackupRequest,
+    ) -> Result<(), HttpClientError> {
+        let path = format!("/_matrix/client/v3/room_keys/version/{}", version);
+
+        let _: serde_json::Value = self
+            .http_client
+            .request_with_retry(Method::PUT, &path, Some(&request), DEFAULT_MAX_RETRIES)
+            .await?;
+
+        Ok(())
+    }
+
     /// Delete a backup version
     /// 
     /// DELETE /_matrix/client/v3/room_keys/version/{version}
@@ -129,7 +151,7 @@ impl BackupVersionClient {
         // Delete returns empty JSON object {}
         let _: serde_json::Value = self
             .http_client
-            .request(Method::DELETE, &path, None::<&()>)
+            .request_with_retry(Method::DELETE, &path, None::<&()>, DEFAULT_MAX_RETRIES)
             .await?;
         
         Ok(())
@@ -0,0 +1,73 @@
+//! Matrix Client-Server API: Event Context
+//!
+//! Implementation of the event context endpoint per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#get_matrixclientv3roomsroomidcontexteventid
+
+use crate::_matrix::client::with_query;
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use matryx_entity::Event;
+use matryx_entity::types::RoomEventFilter;
+use reqwest::Method;
+use serde::Deserialize;
+
+/// Response from GET /_matrix/client/v3/rooms/{roomId}/context/{eventId}
+#[derive(Debug, Clone, Deserialize)]
+pub struct ContextResponse {
+    pub event: Option<Event>,
+    /// Events before the requested one, most recent first
+    #[serde(default)]
+    pub events_before: Vec<Event>,
+    /// Events after the requested one, oldest first
+    #[serde(default)]
+    pub events_after: Vec<Event>,
+    /// Room state as of the last event returned
+    #[serde(default)]
+    pub state: Vec<Event>,
+    /// Token to paginate backwards from
+    pub start: Option<String>,
+    /// Token to paginate forwards from
+    pub end: Option<String>,
+}
+
+/// Client for event context
+#[derive(Clone)]
+pub struct ContextClient {
+    http_client: MatrixHttpClient,
+}
+
+impl ContextClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Get an event with the events around it
+    ///
+    /// GET /_matrix/client/v3/rooms/{roomId}/context/{eventId}
+    ///
+    /// # Arguments
+    /// * `limit` - Events to return in total, split between before and after
+    pub async fn get_context(
+        &self,
+        room_id: &str,
+        event_id: &str,
+        limit: Option<u32>,
+        filter: Option<&RoomEventFilter>,
+    ) -> Result<ContextResponse, HttpClientError> {
+        let filter = filter.map(serde_json::to_string).transpose()?;
+        let path = with_query(
+            &format!(
+                "/_matrix/client/v3/rooms/{}/context/{}",
+                urlencoding::encode(room_id),
+                urlencoding::encode(event_id)
+            ),
+            [
+                ("limit", limit.map(|limit| limit.to_string())),
+                ("filter", filter),
+            ],
+        );
+
+        self.http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await
+    }
+}
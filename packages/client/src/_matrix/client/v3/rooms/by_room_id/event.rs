@@ -0,0 +1,38 @@
+//! Matrix Client-Server API: Single Event Retrieval
+//!
+//! Implementation of GET /_matrix/client/v3/rooms/{roomId}/event/{eventId} per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#get_matrixclientv3roomsroomideventeventid
+
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use matryx_entity::Event;
+use reqwest::Method;
+
+/// Client for single room events
+#[derive(Clone)]
+pub struct RoomEventClient {
+    http_client: MatrixHttpClient,
+}
+
+impl RoomEventClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Get a single event of a room
+    ///
+    /// GET /_matrix/client/v3/rooms/{roomId}/event/{eventId}
+    ///
+    /// # Errors
+    /// * 404 if the event does not exist or the user may not see it
+    pub async fn get_event(&self, room_id: &str, event_id: &str) -> Result<Event, HttpClientError> {
+        let path = format!(
+            "/_matrix/client/v3/rooms/{}/event/{}",
+            urlencoding::encode(room_id),
+            urlencoding::encode(event_id)
+        );
+
+        self.http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await
+    }
+}
@@ -0,0 +1,60 @@
+//! Matrix Client-Server API: Room Initial Sync
+//!
+//! Implementation of the deprecated room snapshot endpoint per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#get_matrixclientv3roomsroomidinitialsync
+
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use matryx_entity::Event;
+use reqwest::Method;
+use serde::Deserialize;
+use serde_json::Value;
+
+/// A page of a room's recent timeline
+#[derive(Debug, Clone, Deserialize)]
+pub struct RoomInitialSyncMessages {
+    pub chunk: Vec<Event>,
+    pub start: Option<String>,
+    pub end: String,
+}
+
+/// Response from GET /_matrix/client/v3/rooms/{roomId}/initialSync
+#[derive(Debug, Clone, Deserialize)]
+pub struct RoomInitialSyncResponse {
+    pub room_id: String,
+    /// The user's membership, if any
+    pub membership: Option<String>,
+    pub messages: Option<RoomInitialSyncMessages>,
+    #[serde(default)]
+    pub state: Vec<Event>,
+    #[serde(default)]
+    pub presence: Vec<Value>,
+    #[serde(default)]
+    pub account_data: Vec<Value>,
+    pub visibility: Option<String>,
+}
+
+/// Client for room snapshots
+#[derive(Clone)]
+pub struct RoomInitialSyncClient {
+    http_client: MatrixHttpClient,
+}
+
+impl RoomInitialSyncClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Get a snapshot of a room's state and recent timeline
+    ///
+    /// GET /_matrix/client/v3/rooms/{roomId}/initialSync
+    pub async fn room_initial_sync(
+        &self,
+        room_id: &str,
+    ) -> Result<RoomInitialSyncResponse, HttpClientError> {
+        let path = format!("/_matrix/client/v3/rooms/{}/initialSync", urlencoding::encode(room_id));
+
+        self.http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await
+    }
+}
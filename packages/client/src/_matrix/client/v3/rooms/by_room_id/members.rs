@@ -1,12 +1,183 @@
-//! Client stub for _matrix/client/v3/rooms/by_room_id/members.rs
+//! Matrix Client-Server API: Room Membership
 //!
-//! This is a placeholder stub for the client implementation.
-//! The actual HTTP client functionality should be implemented here
-//! using reqwest to make outbound HTTP requests.
-
-// Placeholder stub - implement actual client functionality as needed
-pub mod client_stub {
-    pub fn placeholder() {
-        // Client implementation would go here
+//! Implementation of the member list and membership endpoints per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#room-membership
+
+use crate::_matrix::client::{EmptyResponse, with_query};
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use matryx_entity::Event;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Filters of a member list request
+#[derive(Debug, Clone, Default)]
+pub struct MembersRequest {
+    /// Return the members as of this sync token
+    pub at: Option<String>,
+    /// Only members with this membership
+    pub membership: Option<String>,
+    /// Exclude members with this membership
+    pub not_membership: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MembersResponse {
+    chunk: Vec<Event>,
+}
+
+/// A joined member's profile in the room
+#[derive(Debug, Clone, Deserialize)]
+pub struct RoomMember {
+    pub display_name: Option<String>,
+    pub avatar_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JoinedMembersResponse {
+    joined: HashMap<String, RoomMember>,
+}
+
+/// Request body of invite, kick, ban and unban
+#[derive(Debug, Serialize)]
+struct MembershipRequest<'a> {
+    user_id: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<&'a str>,
+}
+
+/// Client for room members
+#[derive(Clone)]
+pub struct RoomMembersClient {
+    http_client: MatrixHttpClient,
+}
+
+impl RoomMembersClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Get the `m.room.member` events of a room
+    ///
+    /// GET /_matrix/client/v3/rooms/{roomId}/members
+    pub async fn get_members(
+        &self,
+        room_id: &str,
+        request: &MembersRequest,
+    ) -> Result<Vec<Event>, HttpClientError> {
+        let path = with_query(
+            &format!("/_matrix/client/v3/rooms/{}/members", urlencoding::encode(room_id)),
+            [
+                ("at", request.at.clone()),
+                ("membership", request.membership.clone()),
+                ("not_membership", request.not_membership.clone()),
+            ],
+        );
+
+        let response: MembersResponse = self
+            .http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await?;
+        Ok(response.chunk)
+    }
+
+    /// Get the joined members of a room by user ID
+    ///
+    /// GET /_matrix/client/v3/rooms/{roomId}/joined_members
+    pub async fn get_joined_members(
+        &self,
+        room_id: &str,
+    ) -> Result<HashMap<String, RoomMember>, HttpClientError> {
+        let path =
+            format!("/_matrix/client/v3/rooms/{}/joined_members", urlencoding::encode(room_id));
+
+        let response: JoinedMembersResponse = self
+            .http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await?;
+        Ok(response.joined)
+    }
+
+    /// Invite a user to a room
+    ///
+    /// POST /_matrix/client/v3/rooms/{roomId}/invite
+    pub async fn invite(
+        &self,
+        room_id: &str,
+        user_id: &str,
+        reason: Option<&str>,
+    ) -> Result<(), HttpClientError> {
+        self.membership_action(room_id, "invite", user_id, reason).await
+    }
+
+    /// Kick a user from a room
+    ///
+    /// POST /_matrix/client/v3/rooms/{roomId}/kick
+    pub async fn kick(
+        &self,
+        room_id: &str,
+        user_id: &str,
+        reason: Option<&str>,
+    ) -> Result<(), HttpClientError> {
+        self.membership_action(room_id, "kick", user_id, reason).await
+    }
+
+    /// Ban a user from a room
+    ///
+    /// POST /_matrix/client/v3/rooms/{roomId}/ban
+    pub async fn ban(
+        &self,
+        room_id: &str,
+        user_id: &str,
+        reason: Option<&str>,
+    ) -> Result<(), HttpClientError> {
+        self.membership_action(room_id, "ban", user_id, reason).await
+    }
+
+    /// Lift a user's ban from a room
+    ///
+    /// POST /_matrix/client/v3/rooms/{roomId}/unban
+    pub async fn unban(
+        &self,
+        room_id: &str,
+        user_id: &str,
+        reason: Option<&str>,
+    ) -> Result<(), HttpClientError> {
+        self.membership_action(room_id, "unban", user_id, reason).await
+    }
+
+    /// Forget a room the user has left, so its history is no longer available
+    ///
+    /// POST /_matrix/client/v3/rooms/{roomId}/forget
+    pub async fn forget(&self, room_id: &str) -> Result<(), HttpClientError> {
+        let path = format!("/_matrix/client/v3/rooms/{}/forget", urlencoding::encode(room_id));
+
+        let _: EmptyResponse = self
+            .http_client
+            .request_with_retry(
+                Method::POST,
+                &path,
+                Some(&serde_json::json!({})),
+                DEFAULT_MAX_RETRIES,
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn membership_action(
+        &self,
+        room_id: &str,
+        action: &str,
+        user_id: &str,
+        reason: Option<&str>,
+    ) -> Result<(), HttpClientError> {
+        let path = format!("/_matrix/client/v3/rooms/{}/{}", urlencoding::encode(room_id), action);
+        let body = MembershipRequest { user_id, reason };
+
+        let _: EmptyResponse = self
+            .http_client
+            .request_with_retry(Method::POST, &path, Some(&body), DEFAULT_MAX_RETRIES)
+            .await?;
+        Ok(())
     }
 }
@@ -0,0 +1,96 @@
+//! Matrix Client-Server API: Room Messages
+//!
+//! Implementation of timeline pagination per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#get_matrixclientv3roomsroomidmessages
+
+use crate::_matrix::client::{Direction, with_query};
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use matryx_entity::Event;
+use matryx_entity::types::RoomEventFilter;
+use reqwest::Method;
+use serde::Deserialize;
+
+/// Options of a messages request
+#[derive(Debug, Clone)]
+pub struct MessagesRequest {
+    pub dir: Direction,
+    /// Token to start from, the start or end of the timeline if unset
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub limit: Option<u32>,
+    pub filter: Option<RoomEventFilter>,
+}
+
+impl MessagesRequest {
+    /// Page backwards from a token, e.g. a sync's `prev_batch`
+    pub fn backward(from: Option<String>) -> Self {
+        Self {
+            dir: Direction::Backward,
+            from,
+            to: None,
+            limit: None,
+            filter: None,
+        }
+    }
+
+    /// Page forwards from a token
+    pub fn forward(from: Option<String>) -> Self {
+        Self {
+            dir: Direction::Forward,
+            from,
+            to: None,
+            limit: None,
+            filter: None,
+        }
+    }
+}
+
+/// Response from GET /_matrix/client/v3/rooms/{roomId}/messages
+#[derive(Debug, Clone, Deserialize)]
+pub struct MessagesResponse {
+    /// Events in the requested direction
+    pub chunk: Vec<Event>,
+    pub start: String,
+    /// Token for the next page, `None` at the end of the timeline
+    pub end: Option<String>,
+    /// Member events of the senders when lazy-loading members
+    #[serde(default)]
+    pub state: Vec<Event>,
+}
+
+/// Client for room timeline pagination
+#[derive(Clone)]
+pub struct MessagesClient {
+    http_client: MatrixHttpClient,
+}
+
+impl MessagesClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Get a page of a room's timeline
+    ///
+    /// GET /_matrix/client/v3/rooms/{roomId}/messages
+    pub async fn get_messages(
+        &self,
+        room_id: &str,
+        request: &MessagesRequest,
+    ) -> Result<MessagesResponse, HttpClientError> {
+        let filter = request.filter.as_ref().map(serde_json::to_string).transpose()?;
+        let path = with_query(
+            &format!("/_matrix/client/v3/rooms/{}/messages", urlencoding::encode(room_id)),
+            [
+                ("dir", Some(request.dir.as_str().to_string())),
+                ("from", request.from.clone()),
+                ("to", request.to.clone()),
+                ("limit", request.limit.map(|limit| limit.to_string())),
+                ("filter", filter),
+            ],
+        );
+
+        self.http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await
+    }
+}
@@ -1,3 +1,13 @@
+pub mod context;
+pub mod event;
+pub mod initial_sync;
 pub mod leave;
 pub mod members;
+pub mod messages;
+pub mod receipt;
+pub mod redact;
+pub mod report;
 pub mod send;
+pub mod state;
+pub mod typing;
+pub mod upgrade;
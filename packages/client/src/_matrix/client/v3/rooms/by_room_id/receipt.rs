@@ -0,0 +1,94 @@
+//! Matrix Client-Server API: Receipts and Read Markers
+//!
+//! Implementation of the receipt and read marker endpoints per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#receipts
+
+use crate::_matrix::client::EmptyResponse;
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use reqwest::Method;
+use serde::Serialize;
+
+/// Receipt type for a public read receipt
+pub const READ_RECEIPT: &str = "m.read";
+
+/// Receipt type for a read receipt only the user's own devices see
+pub const PRIVATE_READ_RECEIPT: &str = "m.read.private";
+
+/// Receipt type for the fully read marker
+pub const FULLY_READ: &str = "m.fully_read";
+
+#[derive(Debug, Serialize)]
+struct ReceiptRequest<'a> {
+    /// `main` for the main timeline, or a thread root
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thread_id: Option<&'a str>,
+}
+
+/// Request body of POST /_matrix/client/v3/rooms/{roomId}/read_markers
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReadMarkersRequest {
+    #[serde(rename = "m.fully_read", skip_serializing_if = "Option::is_none")]
+    pub fully_read: Option<String>,
+    #[serde(rename = "m.read", skip_serializing_if = "Option::is_none")]
+    pub read: Option<String>,
+    #[serde(rename = "m.read.private", skip_serializing_if = "Option::is_none")]
+    pub read_private: Option<String>,
+}
+
+/// Client for receipts and read markers
+#[derive(Clone)]
+pub struct ReceiptClient {
+    http_client: MatrixHttpClient,
+}
+
+impl ReceiptClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Mark an event and everything before it as read
+    ///
+    /// POST /_matrix/client/v3/rooms/{roomId}/receipt/{receiptType}/{eventId}
+    ///
+    /// # Arguments
+    /// * `thread_id` - Makes the receipt threaded; `None` sends an unthreaded one
+    pub async fn send_receipt(
+        &self,
+        room_id: &str,
+        receipt_type: &str,
+        event_id: &str,
+        thread_id: Option<&str>,
+    ) -> Result<(), HttpClientError> {
+        let path = format!(
+            "/_matrix/client/v3/rooms/{}/receipt/{}/{}",
+            urlencoding::encode(room_id),
+            urlencoding::encode(receipt_type),
+            urlencoding::encode(event_id)
+        );
+        let body = ReceiptRequest { thread_id };
+
+        let _: EmptyResponse = self
+            .http_client
+            .request_with_retry(Method::POST, &path, Some(&body), DEFAULT_MAX_RETRIES)
+            .await?;
+        Ok(())
+    }
+
+    /// Move the fully read marker and read receipts in one request
+    ///
+    /// POST /_matrix/client/v3/rooms/{roomId}/read_markers
+    pub async fn set_read_markers(
+        &self,
+        room_id: &str,
+        request: &ReadMarkersRequest,
+    ) -> Result<(), HttpClientError> {
+        let path =
+            format!("/_matrix/client/v3/rooms/{}/read_markers", urlencoding::encode(room_id));
+
+        let _: EmptyResponse = self
+            .http_client
+            .request_with_retry(Method::POST, &path, Some(request), DEFAULT_MAX_RETRIES)
+            .await?;
+        Ok(())
+    }
+}
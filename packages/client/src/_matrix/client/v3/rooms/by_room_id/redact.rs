@@ -0,0 +1,58 @@
+//! Matrix Client-Server API: Redactions
+//!
+//! Implementation of event redaction per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#redactions
+
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize)]
+struct RedactRequest<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<&'a str>,
+}
+
+/// Response from PUT /_matrix/client/v3/rooms/{roomId}/redact/{eventId}/{txnId}
+#[derive(Debug, Clone, Deserialize)]
+pub struct RedactResponse {
+    /// ID of the redaction event
+    pub event_id: String,
+}
+
+/// Client for redactions
+#[derive(Clone)]
+pub struct RedactClient {
+    http_client: MatrixHttpClient,
+}
+
+impl RedactClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Redact an event
+    ///
+    /// PUT /_matrix/client/v3/rooms/{roomId}/redact/{eventId}/{txnId}
+    ///
+    /// Retries reuse `txn_id`, so the event is redacted at most once.
+    pub async fn redact(
+        &self,
+        room_id: &str,
+        event_id: &str,
+        txn_id: &str,
+        reason: Option<&str>,
+    ) -> Result<RedactResponse, HttpClientError> {
+        let path = format!(
+            "/_matrix/client/v3/rooms/{}/redact/{}/{}",
+            urlencoding::encode(room_id),
+            urlencoding::encode(event_id),
+            urlencoding::encode(txn_id)
+        );
+        let body = RedactRequest { reason };
+
+        self.http_client
+            .request_with_retry(Method::PUT, &path, Some(&body), DEFAULT_MAX_RETRIES)
+            .await
+    }
+}
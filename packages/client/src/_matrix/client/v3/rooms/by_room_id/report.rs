@@ -0,0 +1,80 @@
+//! Matrix Client-Server API: Reporting Content
+//!
+//! Implementation of the content reporting endpoints per Matrix spec v1.13
+//! Reference: https://spec.matrix.org/v1.13/client-server-api/#reporting-content
+
+use crate::_matrix::client::EmptyResponse;
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use reqwest::Method;
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+struct ReportRequest<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<&'a str>,
+    /// Offensiveness from -100 (most offensive) to 0, events only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    score: Option<i32>,
+}
+
+/// Client for reporting rooms, events and users to the server admins
+#[derive(Clone)]
+pub struct ReportClient {
+    http_client: MatrixHttpClient,
+}
+
+impl ReportClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Report an event
+    ///
+    /// POST /_matrix/client/v3/rooms/{roomId}/report/{eventId}
+    pub async fn report_event(
+        &self,
+        room_id: &str,
+        event_id: &str,
+        reason: Option<&str>,
+        score: Option<i32>,
+    ) -> Result<(), HttpClientError> {
+        let path = format!(
+            "/_matrix/client/v3/rooms/{}/report/{}",
+            urlencoding::encode(room_id),
+            urlencoding::encode(event_id)
+        );
+        self.report(&path, ReportRequest { reason, score }).await
+    }
+
+    /// Report a room
+    ///
+    /// POST /_matrix/client/v3/rooms/{roomId}/report
+    pub async fn report_room(
+        &self,
+        room_id: &str,
+        reason: Option<&str>,
+    ) -> Result<(), HttpClientError> {
+        let path = format!("/_matrix/client/v3/rooms/{}/report", urlencoding::encode(room_id));
+        self.report(&path, ReportRequest { reason, score: None }).await
+    }
+
+    /// Report a user
+    ///
+    /// POST /_matrix/client/v3/users/{userId}/report
+    pub async fn report_user(
+        &self,
+        user_id: &str,
+        reason: Option<&str>,
+    ) -> Result<(), HttpClientError> {
+        let path = format!("/_matrix/client/v3/users/{}/report", urlencoding::encode(user_id));
+        self.report(&path, ReportRequest { reason, score: None }).await
+    }
+
+    async fn report(&self, path: &str, body: ReportRequest<'_>) -> Result<(), HttpClientError> {
+        let _: EmptyResponse = self
+            .http_client
+            .request_with_retry(Method::POST, path, Some(&body), DEFAULT_MAX_RETRIES)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+//! Matrix Client-Server API: Room State
+//!
+//! Implementation of the room state endpoints per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#getting-events-for-a-room
+
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use matryx_entity::Event;
+use reqwest::Method;
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Response from PUT /_matrix/client/v3/rooms/{roomId}/state/{eventType}/{stateKey}
+#[derive(Debug, Clone, Deserialize)]
+pub struct SendStateEventResponse {
+    pub event_id: String,
+}
+
+/// Client for room state
+#[derive(Clone)]
+pub struct RoomStateClient {
+    http_client: MatrixHttpClient,
+}
+
+impl RoomStateClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Get the current state events of a room
+    ///
+    /// GET /_matrix/client/v3/rooms/{roomId}/state
+    pub async fn get_state(&self, room_id: &str) -> Result<Vec<Event>, HttpClientError> {
+        let path = format!("/_matrix/client/v3/rooms/{}/state", urlencoding::encode(room_id));
+
+        self.http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await
+    }
+
+    /// Get the content of a state event
+    ///
+    /// GET /_matrix/client/v3/rooms/{roomId}/state/{eventType}/{stateKey}
+    ///
+    /// # Errors
+    /// * 404 if the room has no such state event
+    pub async fn get_state_event(
+        &self,
+        room_id: &str,
+        event_type: &str,
+        state_key: &str,
+    ) -> Result<Value, HttpClientError> {
+        let path = state_event_path(room_id, event_type, state_key);
+
+        self.http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await
+    }
+
+    /// Send a state event, replacing the current one with the same type and key
+    ///
+    /// PUT /_matrix/client/v3/rooms/{roomId}/state/{eventType}/{stateKey}
+    pub async fn send_state_event(
+        &self,
+        room_id: &str,
+        event_type: &str,
+        state_key: &str,
+        content: &Value,
+    ) -> Result<SendStateEventResponse, HttpClientError> {
+        let path = state_event_path(room_id, event_type, state_key);
+
+        self.http_client
+            .request_with_retry(Method::PUT, &path, Some(content), DEFAULT_MAX_RETRIES)
+            .await
+    }
+}
+
+/// Path of a state event; the empty state key is addressed without a trailing segment
+fn state_event_path(room_id: &str, event_type: &str, state_key: &str) -> String {
+    let mut path = format!(
+        "/_matrix/client/v3/rooms/{}/state/{}",
+        urlencoding::encode(room_id),
+        urlencoding::encode(event_type)
+    );
+    if !state_key.is_empty() {
+        path.push_str(&format!("/{}", urlencoding::encode(state_key)));
+    }
+    path
+}
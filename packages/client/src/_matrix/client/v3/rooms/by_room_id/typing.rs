@@ -0,0 +1,69 @@
+//! Matrix Client-Server API: Typing Notifications
+//!
+//! Implementation of PUT /_matrix/client/v3/rooms/{roomId}/typing/{userId} per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#typing-notifications
+
+use crate::_matrix::client::EmptyResponse;
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use reqwest::Method;
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+struct TypingRequest {
+    typing: bool,
+    /// How long the user stays typing, in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timeout: Option<u64>,
+}
+
+/// Client for typing notifications
+#[derive(Clone)]
+pub struct TypingClient {
+    http_client: MatrixHttpClient,
+}
+
+impl TypingClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Start typing in a room for `timeout_ms`
+    ///
+    /// PUT /_matrix/client/v3/rooms/{roomId}/typing/{userId}
+    pub async fn start_typing(
+        &self,
+        room_id: &str,
+        user_id: &str,
+        timeout_ms: u64,
+    ) -> Result<(), HttpClientError> {
+        let body = TypingRequest { typing: true, timeout: Some(timeout_ms) };
+        self.set_typing(room_id, user_id, &body).await
+    }
+
+    /// Stop typing in a room
+    ///
+    /// PUT /_matrix/client/v3/rooms/{roomId}/typing/{userId}
+    pub async fn stop_typing(&self, room_id: &str, user_id: &str) -> Result<(), HttpClientError> {
+        let body = TypingRequest { typing: false, timeout: None };
+        self.set_typing(room_id, user_id, &body).await
+    }
+
+    async fn set_typing(
+        &self,
+        room_id: &str,
+        user_id: &str,
+        body: &TypingRequest,
+    ) -> Result<(), HttpClientError> {
+        let path = format!(
+            "/_matrix/client/v3/rooms/{}/typing/{}",
+            urlencoding::encode(room_id),
+            urlencoding::encode(user_id)
+        );
+
+        let _: EmptyResponse = self
+            .http_client
+            .request_with_retry(Method::PUT, &path, Some(body), DEFAULT_MAX_RETRIES)
+            .await?;
+        Ok(())
+    }
+}
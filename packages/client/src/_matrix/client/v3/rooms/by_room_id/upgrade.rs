@@ -0,0 +1,48 @@
+//! Matrix Client-Server API: Room Upgrades
+//!
+//! Implementation of POST /_matrix/client/v3/rooms/{roomId}/upgrade per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#room-upgrades
+
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize)]
+struct UpgradeRoomRequest<'a> {
+    new_version: &'a str,
+}
+
+/// Response from POST /_matrix/client/v3/rooms/{roomId}/upgrade
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpgradeRoomResponse {
+    /// ID of the room that replaces the upgraded one
+    pub replacement_room: String,
+}
+
+/// Client for room upgrades
+#[derive(Clone)]
+pub struct UpgradeClient {
+    http_client: MatrixHttpClient,
+}
+
+impl UpgradeClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Replace a room with a new room of another room version
+    ///
+    /// POST /_matrix/client/v3/rooms/{roomId}/upgrade
+    pub async fn upgrade_room(
+        &self,
+        room_id: &str,
+        new_version: &str,
+    ) -> Result<UpgradeRoomResponse, HttpClientError> {
+        let path = format!("/_matrix/client/v3/rooms/{}/upgrade", urlencoding::encode(room_id));
+        let body = UpgradeRoomRequest { new_version };
+
+        self.http_client
+            .request_with_retry(Method::POST, &path, Some(&body), DEFAULT_MAX_RETRIES)
+            .await
+    }
+}
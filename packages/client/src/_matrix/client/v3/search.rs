@@ -1 +1,156 @@
+//! Matrix Client-Server API: Server Side Search
+//!
+//! Implementation of POST /_matrix/client/v3/search per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#server-side-search
 
+use crate::_matrix::client::with_query;
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use matryx_entity::Event;
+use matryx_entity::types::RoomEventFilter;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Ordering of search results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchOrder {
+    Recent,
+    Rank,
+}
+
+/// Events to return around each result
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EventContext {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before_limit: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after_limit: Option<u32>,
+    pub include_profile: bool,
+}
+
+/// Search criteria for room events
+#[derive(Debug, Clone, Serialize)]
+pub struct RoomEventsCriteria {
+    pub search_term: String,
+    /// Fields to search: `content.body`, `content.name` or `content.topic`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keys: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<RoomEventFilter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order_by: Option<SearchOrder>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_context: Option<EventContext>,
+    pub include_state: bool,
+}
+
+impl RoomEventsCriteria {
+    pub fn new(search_term: impl Into<String>) -> Self {
+        Self {
+            search_term: search_term.into(),
+            keys: None,
+            filter: None,
+            order_by: None,
+            event_context: None,
+            include_state: false,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct SearchRequest<'a> {
+    search_categories: SearchCategoriesRequest<'a>,
+}
+
+#[derive(Debug, Serialize)]
+struct SearchCategoriesRequest<'a> {
+    room_events: &'a RoomEventsCriteria,
+}
+
+/// Events around a search result
+#[derive(Debug, Clone, Deserialize)]
+pub struct ResultContext {
+    #[serde(default)]
+    pub events_before: Vec<Event>,
+    #[serde(default)]
+    pub events_after: Vec<Event>,
+    pub start: Option<String>,
+    pub end: Option<String>,
+    /// Profiles of the senders, keyed by user ID
+    #[serde(default)]
+    pub profile_info: HashMap<String, serde_json::Value>,
+}
+
+/// A single search hit
+#[derive(Debug, Clone, Deserialize)]
+pub struct SearchResult {
+    pub rank: Option<f64>,
+    pub result: Option<Event>,
+    pub context: Option<ResultContext>,
+}
+
+/// Room event results of a search
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RoomEventsResults {
+    /// Approximate number of results
+    pub count: Option<u64>,
+    /// Words to highlight in the results
+    #[serde(default)]
+    pub highlights: Vec<String>,
+    /// Token to pass back to fetch more results
+    pub next_batch: Option<String>,
+    #[serde(default)]
+    pub results: Vec<SearchResult>,
+    /// Current state of the rooms with results, if `include_state` was set
+    #[serde(default)]
+    pub state: HashMap<String, Vec<Event>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchResponse {
+    search_categories: SearchCategoriesResponse,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchCategoriesResponse {
+    room_events: Option<RoomEventsResults>,
+}
+
+/// Client for server side search
+#[derive(Clone)]
+pub struct SearchClient {
+    http_client: MatrixHttpClient,
+}
+
+impl SearchClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Search the events of the rooms the user is in
+    ///
+    /// POST /_matrix/client/v3/search
+    ///
+    /// # Arguments
+    /// * `next_batch` - Token from a previous result to continue from
+    pub async fn search_room_events(
+        &self,
+        criteria: &RoomEventsCriteria,
+        next_batch: Option<&str>,
+    ) -> Result<RoomEventsResults, HttpClientError> {
+        let path = with_query(
+            "/_matrix/client/v3/search",
+            [("next_batch", next_batch.map(str::to_string))],
+        );
+        let body = SearchRequest {
+            search_categories: SearchCategoriesRequest { room_events: criteria },
+        };
+
+        let response: SearchResponse = self
+            .http_client
+            .request_with_retry(Method::POST, &path, Some(&body), DEFAULT_MAX_RETRIES)
+            .await?;
+        Ok(response.search_categories.room_events.unwrap_or_default())
+    }
+}
@@ -1 +1,54 @@
+//! Matrix Client-Server API: Send-to-Device Messaging
+//!
+//! Implementation of PUT /_matrix/client/v3/sendToDevice/{eventType}/{txnId} per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#send-to-device-messaging
 
+use crate::_matrix::client::EmptyResponse;
+use crate::crypto::ToDeviceRequest;
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use reqwest::Method;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+#[derive(Debug, Serialize)]
+struct SendToDeviceBody<'a> {
+    messages: &'a HashMap<String, HashMap<String, Value>>,
+}
+
+/// Client for send-to-device messages
+#[derive(Clone)]
+pub struct SendToDeviceClient {
+    http_client: MatrixHttpClient,
+}
+
+impl SendToDeviceClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Send messages directly to devices
+    ///
+    /// PUT /_matrix/client/v3/sendToDevice/{eventType}/{txnId}
+    ///
+    /// The transaction ID makes retries idempotent, so the same ID must be
+    /// reused when resending the same request.
+    pub async fn send_to_device(
+        &self,
+        request: &ToDeviceRequest,
+        txn_id: &str,
+    ) -> Result<(), HttpClientError> {
+        let path = format!(
+            "/_matrix/client/v3/sendToDevice/{}/{}",
+            urlencoding::encode(&request.event_type),
+            urlencoding::encode(txn_id)
+        );
+        let body = SendToDeviceBody { messages: &request.messages };
+
+        let _: EmptyResponse = self
+            .http_client
+            .request_with_retry(Method::PUT, &path, Some(&body), DEFAULT_MAX_RETRIES)
+            .await?;
+        Ok(())
+    }
+}
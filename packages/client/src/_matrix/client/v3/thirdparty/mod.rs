@@ -1 +1,155 @@
+//! Matrix Client-Server API: Third-party Networks
+//!
+//! Implementation of the application service protocol lookups per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#third-party-networks
 
+use crate::_matrix::client::with_query;
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use reqwest::Method;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// A third-party protocol bridged by an application service
+#[derive(Debug, Clone, Deserialize)]
+pub struct Protocol {
+    /// Fields used to identify a third-party user
+    #[serde(default)]
+    pub user_fields: Vec<String>,
+    /// Fields used to identify a third-party location
+    #[serde(default)]
+    pub location_fields: Vec<String>,
+    pub icon: Option<String>,
+    /// Type definitions of the fields, keyed by field name
+    #[serde(default)]
+    pub field_types: HashMap<String, Value>,
+    /// Networks the protocol can reach
+    #[serde(default)]
+    pub instances: Vec<Value>,
+}
+
+/// A portal room on a third-party network
+#[derive(Debug, Clone, Deserialize)]
+pub struct ThirdPartyLocation {
+    pub alias: String,
+    pub protocol: String,
+    #[serde(default)]
+    pub fields: HashMap<String, Value>,
+}
+
+/// A Matrix user standing in for a third-party user
+#[derive(Debug, Clone, Deserialize)]
+pub struct ThirdPartyUser {
+    pub userid: String,
+    pub protocol: String,
+    #[serde(default)]
+    pub fields: HashMap<String, Value>,
+}
+
+/// Client for third-party network lookups
+#[derive(Clone)]
+pub struct ThirdPartyClient {
+    http_client: MatrixHttpClient,
+}
+
+impl ThirdPartyClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// List the protocols the homeserver bridges to
+    ///
+    /// GET /_matrix/client/v3/thirdparty/protocols
+    pub async fn get_protocols(&self) -> Result<HashMap<String, Protocol>, HttpClientError> {
+        self.http_client
+            .request_with_retry(
+                Method::GET,
+                "/_matrix/client/v3/thirdparty/protocols",
+                None::<&()>,
+                DEFAULT_MAX_RETRIES,
+            )
+            .await
+    }
+
+    /// Get the metadata of one protocol
+    ///
+    /// GET /_matrix/client/v3/thirdparty/protocol/{protocol}
+    pub async fn get_protocol(&self, protocol: &str) -> Result<Protocol, HttpClientError> {
+        let path =
+            format!("/_matrix/client/v3/thirdparty/protocol/{}", urlencoding::encode(protocol));
+
+        self.http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await
+    }
+
+    /// Find portal rooms on a third-party network by protocol fields
+    ///
+    /// GET /_matrix/client/v3/thirdparty/location/{protocol}
+    pub async fn query_locations(
+        &self,
+        protocol: &str,
+        fields: &[(&str, &str)],
+    ) -> Result<Vec<ThirdPartyLocation>, HttpClientError> {
+        let path = with_query(
+            &format!("/_matrix/client/v3/thirdparty/location/{}", urlencoding::encode(protocol)),
+            fields.iter().map(|(key, value)| (*key, Some(value.to_string()))),
+        );
+
+        self.http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await
+    }
+
+    /// Find Matrix users standing in for third-party users by protocol fields
+    ///
+    /// GET /_matrix/client/v3/thirdparty/user/{protocol}
+    pub async fn query_users(
+        &self,
+        protocol: &str,
+        fields: &[(&str, &str)],
+    ) -> Result<Vec<ThirdPartyUser>, HttpClientError> {
+        let path = with_query(
+            &format!("/_matrix/client/v3/thirdparty/user/{}", urlencoding::encode(protocol)),
+            fields.iter().map(|(key, value)| (*key, Some(value.to_string()))),
+        );
+
+        self.http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await
+    }
+
+    /// Find portal rooms by alias
+    ///
+    /// GET /_matrix/client/v3/thirdparty/location
+    pub async fn get_location_by_alias(
+        &self,
+        alias: &str,
+    ) -> Result<Vec<ThirdPartyLocation>, HttpClientError> {
+        let path = with_query(
+            "/_matrix/client/v3/thirdparty/location",
+            [("alias", Some(alias.to_string()))],
+        );
+
+        self.http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await
+    }
+
+    /// Find the third-party users a Matrix user stands in for
+    ///
+    /// GET /_matrix/client/v3/thirdparty/user
+    pub async fn get_user_by_id(
+        &self,
+        user_id: &str,
+    ) -> Result<Vec<ThirdPartyUser>, HttpClientError> {
+        let path = with_query(
+            "/_matrix/client/v3/thirdparty/user",
+            [("userid", Some(user_id.to_string()))],
+        );
+
+        self.http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await
+    }
+}
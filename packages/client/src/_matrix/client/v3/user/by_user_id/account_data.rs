@@ -0,0 +1,109 @@
+//! Matrix Client-Server API: Client Config
+//!
+//! Implementation of the global and per-room account data endpoints per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#client-config
+
+use crate::_matrix::client::EmptyResponse;
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use reqwest::Method;
+use serde_json::Value;
+
+/// Client for account data
+#[derive(Clone)]
+pub struct AccountDataClient {
+    http_client: MatrixHttpClient,
+}
+
+impl AccountDataClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Get a global account data event
+    ///
+    /// GET /_matrix/client/v3/user/{userId}/account_data/{type}
+    ///
+    /// # Errors
+    /// Fails with `M_NOT_FOUND` when the user has no data of that type.
+    pub async fn get_account_data(
+        &self,
+        user_id: &str,
+        event_type: &str,
+    ) -> Result<Value, HttpClientError> {
+        let path = global_path(user_id, event_type);
+
+        self.http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await
+    }
+
+    /// Set a global account data event
+    ///
+    /// PUT /_matrix/client/v3/user/{userId}/account_data/{type}
+    pub async fn set_account_data(
+        &self,
+        user_id: &str,
+        event_type: &str,
+        content: &Value,
+    ) -> Result<(), HttpClientError> {
+        let path = global_path(user_id, event_type);
+
+        let _: EmptyResponse = self
+            .http_client
+            .request_with_retry(Method::PUT, &path, Some(content), DEFAULT_MAX_RETRIES)
+            .await?;
+        Ok(())
+    }
+
+    /// Get an account data event of a room
+    ///
+    /// GET /_matrix/client/v3/user/{userId}/rooms/{roomId}/account_data/{type}
+    pub async fn get_room_account_data(
+        &self,
+        user_id: &str,
+        room_id: &str,
+        event_type: &str,
+    ) -> Result<Value, HttpClientError> {
+        let path = room_path(user_id, room_id, event_type);
+
+        self.http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await
+    }
+
+    /// Set an account data event of a room
+    ///
+    /// PUT /_matrix/client/v3/user/{userId}/rooms/{roomId}/account_data/{type}
+    pub async fn set_room_account_data(
+        &self,
+        user_id: &str,
+        room_id: &str,
+        event_type: &str,
+        content: &Value,
+    ) -> Result<(), HttpClientError> {
+        let path = room_path(user_id, room_id, event_type);
+
+        let _: EmptyResponse = self
+            .http_client
+            .request_with_retry(Method::PUT, &path, Some(content), DEFAULT_MAX_RETRIES)
+            .await?;
+        Ok(())
+    }
+}
+
+fn global_path(user_id: &str, event_type: &str) -> String {
+    format!(
+        "/_matrix/client/v3/user/{}/account_data/{}",
+        urlencoding::encode(user_id),
+        urlencoding::encode(event_type)
+    )
+}
+
+fn room_path(user_id: &str, room_id: &str, event_type: &str) -> String {
+    format!(
+        "/_matrix/client/v3/user/{}/rooms/{}/account_data/{}",
+        urlencoding::encode(user_id),
+        urlencoding::encode(room_id),
+        urlencoding::encode(event_type)
+    )
+}
@@ -0,0 +1,62 @@
+//! Matrix Client-Server API: Filtering
+//!
+//! Implementation of the filter upload and download endpoints per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#filtering
+
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use matryx_entity::types::MatrixFilter;
+use reqwest::Method;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+struct CreateFilterResponse {
+    filter_id: String,
+}
+
+/// Client for sync filters
+#[derive(Clone)]
+pub struct FilterClient {
+    http_client: MatrixHttpClient,
+}
+
+impl FilterClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Upload a filter and get its ID for use with `/sync`
+    ///
+    /// POST /_matrix/client/v3/user/{userId}/filter
+    pub async fn create_filter(
+        &self,
+        user_id: &str,
+        filter: &MatrixFilter,
+    ) -> Result<String, HttpClientError> {
+        let path = format!("/_matrix/client/v3/user/{}/filter", urlencoding::encode(user_id));
+
+        let response: CreateFilterResponse = self
+            .http_client
+            .request_with_retry(Method::POST, &path, Some(filter), DEFAULT_MAX_RETRIES)
+            .await?;
+        Ok(response.filter_id)
+    }
+
+    /// Download a previously uploaded filter
+    ///
+    /// GET /_matrix/client/v3/user/{userId}/filter/{filterId}
+    pub async fn get_filter(
+        &self,
+        user_id: &str,
+        filter_id: &str,
+    ) -> Result<MatrixFilter, HttpClientError> {
+        let path = format!(
+            "/_matrix/client/v3/user/{}/filter/{}",
+            urlencoding::encode(user_id),
+            urlencoding::encode(filter_id)
+        );
+
+        self.http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await
+    }
+}
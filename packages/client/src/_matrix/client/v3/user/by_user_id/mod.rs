@@ -1 +1,4 @@
+pub mod account_data;
+pub mod filter;
+pub mod openid;
 pub mod rooms;
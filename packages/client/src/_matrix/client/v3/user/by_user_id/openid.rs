@@ -0,0 +1,51 @@
+//! Matrix Client-Server API: OpenID
+//!
+//! Implementation of POST /_matrix/client/v3/user/{userId}/openid/request_token per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#openid
+
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use reqwest::Method;
+use serde::Deserialize;
+
+/// Token a third party can exchange with the homeserver to verify the user's identity
+#[derive(Debug, Clone, Deserialize)]
+pub struct OpenIdToken {
+    pub access_token: String,
+    /// Always `"Bearer"`
+    pub token_type: String,
+    /// Server the third party should verify the token against
+    pub matrix_server_name: String,
+    /// Lifetime of the token in seconds
+    pub expires_in: u64,
+}
+
+/// Client for OpenID tokens
+#[derive(Clone)]
+pub struct OpenIdClient {
+    http_client: MatrixHttpClient,
+}
+
+impl OpenIdClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Get an OpenID token for the user
+    ///
+    /// POST /_matrix/client/v3/user/{userId}/openid/request_token
+    pub async fn request_token(&self, user_id: &str) -> Result<OpenIdToken, HttpClientError> {
+        let path = format!(
+            "/_matrix/client/v3/user/{}/openid/request_token",
+            urlencoding::encode(user_id)
+        );
+
+        self.http_client
+            .request_with_retry(
+                Method::POST,
+                &path,
+                Some(&serde_json::json!({})),
+                DEFAULT_MAX_RETRIES,
+            )
+            .await
+    }
+}
@@ -1 +1,104 @@
-pub mod by_tag;
+//! Matrix Client-Server API: Room Tagging
+//!
+//! Implementation of the room tag endpoints per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#room-tagging
+
+use crate::_matrix::client::EmptyResponse;
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Tag the client uses for favourite rooms
+pub const FAVOURITE_TAG: &str = "m.favourite";
+
+/// Tag the client uses for low-priority rooms
+pub const LOW_PRIORITY_TAG: &str = "m.lowpriority";
+
+/// Tag attached to a room
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Tag {
+    /// Position of the room in the tag, between 0 and 1
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TagsResponse {
+    #[serde(default)]
+    tags: HashMap<String, Tag>,
+}
+
+/// Client for room tags
+#[derive(Clone)]
+pub struct TagsClient {
+    http_client: MatrixHttpClient,
+}
+
+impl TagsClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Get the tags the user attached to a room
+    ///
+    /// GET /_matrix/client/v3/user/{userId}/rooms/{roomId}/tags
+    pub async fn get_tags(
+        &self,
+        user_id: &str,
+        room_id: &str,
+    ) -> Result<HashMap<String, Tag>, HttpClientError> {
+        let path = tags_path(user_id, room_id);
+
+        let response: TagsResponse = self
+            .http_client
+            .request_with_retry(Method::GET, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await?;
+        Ok(response.tags)
+    }
+
+    /// Attach a tag to a room
+    ///
+    /// PUT /_matrix/client/v3/user/{userId}/rooms/{roomId}/tags/{tag}
+    pub async fn set_tag(
+        &self,
+        user_id: &str,
+        room_id: &str,
+        tag: &str,
+        content: &Tag,
+    ) -> Result<(), HttpClientError> {
+        let path = format!("{}/{}", tags_path(user_id, room_id), urlencoding::encode(tag));
+
+        let _: EmptyResponse = self
+            .http_client
+            .request_with_retry(Method::PUT, &path, Some(content), DEFAULT_MAX_RETRIES)
+            .await?;
+        Ok(())
+    }
+
+    /// Remove a tag from a room
+    ///
+    /// DELETE /_matrix/client/v3/user/{userId}/rooms/{roomId}/tags/{tag}
+    pub async fn delete_tag(
+        &self,
+        user_id: &str,
+        room_id: &str,
+        tag: &str,
+    ) -> Result<(), HttpClientError> {
+        let path = format!("{}/{}", tags_path(user_id, room_id), urlencoding::encode(tag));
+
+        let _: EmptyResponse = self
+            .http_client
+            .request_with_retry(Method::DELETE, &path, None::<&()>, DEFAULT_MAX_RETRIES)
+            .await?;
+        Ok(())
+    }
+}
+
+fn tags_path(user_id: &str, room_id: &str) -> String {
+    format!(
+        "/_matrix/client/v3/user/{}/rooms/{}/tags",
+        urlencoding::encode(user_id),
+        urlencoding::encode(room_id)
+    )
+}
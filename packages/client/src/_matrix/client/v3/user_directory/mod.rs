@@ -1 +1,63 @@
+//! Matrix Client-Server API: User Directory
+//!
+//! Implementation of POST /_matrix/client/v3/user_directory/search per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#user-directory
 
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+/// A user found in the directory
+#[derive(Debug, Clone, Deserialize)]
+pub struct UserDirectoryUser {
+    pub user_id: String,
+    pub display_name: Option<String>,
+    pub avatar_url: Option<String>,
+}
+
+/// Response from POST /_matrix/client/v3/user_directory/search
+#[derive(Debug, Clone, Deserialize)]
+pub struct UserDirectorySearchResponse {
+    pub results: Vec<UserDirectoryUser>,
+    /// Whether the result list was truncated by the limit
+    pub limited: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct UserDirectorySearchRequest<'a> {
+    search_term: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    limit: Option<u32>,
+}
+
+/// Client for the user directory
+#[derive(Clone)]
+pub struct UserDirectoryClient {
+    http_client: MatrixHttpClient,
+}
+
+impl UserDirectoryClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Search users by ID and display name
+    ///
+    /// POST /_matrix/client/v3/user_directory/search
+    pub async fn search_users(
+        &self,
+        search_term: &str,
+        limit: Option<u32>,
+    ) -> Result<UserDirectorySearchResponse, HttpClientError> {
+        let body = UserDirectorySearchRequest { search_term, limit };
+
+        self.http_client
+            .request_with_retry(
+                Method::POST,
+                "/_matrix/client/v3/user_directory/search",
+                Some(&body),
+                DEFAULT_MAX_RETRIES,
+            )
+            .await
+    }
+}
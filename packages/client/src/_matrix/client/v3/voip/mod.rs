@@ -1 +1,45 @@
+//! Matrix Client-Server API: Voice over IP
+//!
+//! Implementation of GET /_matrix/client/v3/voip/turnServer per Matrix spec v1.8
+//! Reference: https://spec.matrix.org/v1.8/client-server-api/#turn-server-information
 
+use crate::http_client::{DEFAULT_MAX_RETRIES, HttpClientError, MatrixHttpClient};
+use reqwest::Method;
+use serde::Deserialize;
+
+/// Credentials for the homeserver's TURN servers
+#[derive(Debug, Clone, Deserialize)]
+pub struct TurnServerCredentials {
+    pub username: String,
+    pub password: String,
+    /// `turn:` and `turns:` URIs of the servers
+    pub uris: Vec<String>,
+    /// Lifetime of the credentials in seconds
+    pub ttl: u64,
+}
+
+/// Client for VoIP server information
+#[derive(Clone)]
+pub struct VoipClient {
+    http_client: MatrixHttpClient,
+}
+
+impl VoipClient {
+    pub fn new(http_client: MatrixHttpClient) -> Self {
+        Self { http_client }
+    }
+
+    /// Get TURN server credentials for calls
+    ///
+    /// GET /_matrix/client/v3/voip/turnServer
+    pub async fn get_turn_server(&self) -> Result<TurnServerCredentials, HttpClientError> {
+        self.http_client
+            .request_with_retry(
+                Method::GET,
+                "/_matrix/client/v3/voip/turnServer",
+                None::<&()>,
+                DEFAULT_MAX_RETRIES,
+            )
+            .await
+    }
+}
@@ -51,10 +51,14 @@ impl MatrixVersionsClient {
     /// * `Err(Box<dyn std::error::Error + Send + Sync>)` - HTTP or parsing error
     ///
     /// # Example
-    /// ```rust
-    /// let client = MatrixVersionsClient::new();
+    /// ```no_run
+    /// # use matryx_client::_matrix::client::versions::MatrixVersionsClient;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    /// let client = MatrixVersionsClient::new()?;
     /// let versions = client.get_supported_versions("https://matrix.org").await?;
     /// println!("Supported versions: {:?}", versions.versions);
+    /// # Ok(())
+    /// # }
     /// ```
     pub async fn get_supported_versions(
         &self,
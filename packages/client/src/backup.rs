@@ -0,0 +1,262 @@
+//! Server-side key backup for the Matrix client
+//!
+//! Creates `m.megolm_backup.v1.curve25519-aes-sha2` backup versions, uploads
+//! room keys the [`crate::crypto::OlmMachine`] has not backed up yet and
+//! restores a whole backup on a new device, with the decryption key either
+//! given directly or loaded from secret storage.
+
+use anyhow::Result;
+use matryx_entity::types::{BackupAuthData, RoomKeysGetResponse, RoomKeysPutResponse};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tracing::{debug, warn};
+
+use crate::MatrixClient;
+use crate::crypto::secret_storage::MEGOLM_BACKUP_SECRET;
+use crate::crypto::{BackupDecryptionKey, BackupKey, MEGOLM_BACKUP_V1_ALGORITHM, SecretStorageKey};
+
+/// Room keys uploaded per `PUT /room_keys/keys` request
+const BACKUP_BATCH_SIZE: usize = 100;
+
+/// A backup version as returned by `GET /room_keys/version`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupVersionInfo {
+    pub algorithm: String,
+    pub auth_data: Value,
+    #[serde(default)]
+    pub count: u64,
+    #[serde(default)]
+    pub etag: String,
+    pub version: String,
+}
+
+impl BackupVersionInfo {
+    /// `auth_data` of a `m.megolm_backup.v1.curve25519-aes-sha2` backup
+    pub fn megolm_auth_data(&self) -> Result<BackupAuthData> {
+        if self.algorithm != MEGOLM_BACKUP_V1_ALGORITHM {
+            return Err(anyhow::anyhow!("Unsupported backup algorithm: {}", self.algorithm));
+        }
+        Ok(serde_json::from_value(self.auth_data.clone())?)
+    }
+}
+
+/// Progress of a backup restore
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RestoreProgress {
+    /// Room keys in the backup
+    pub total: usize,
+    /// Room keys processed so far
+    pub processed: usize,
+    /// Room keys that were new or extended a known session
+    pub imported: usize,
+    /// Room keys that could not be decrypted or imported
+    pub failed: usize,
+}
+
+/// Key backup functionality
+impl MatrixClient {
+    /// Get the current backup version, if any
+    pub async fn key_backup_version(&self) -> Result<Option<BackupVersionInfo>> {
+        let request =
+            self.authenticated_request(Method::GET, "/_matrix/client/v3/room_keys/version")?;
+        let response = self.send(request).await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Failed to get backup version: {}", error_text));
+        }
+
+        Ok(Some(response.json().await?))
+    }
+
+    /// Create a new backup version and start backing up to it
+    ///
+    /// The decryption key is stored in secret storage when `secret_storage`
+    /// is given; otherwise the caller has to keep it. Returns the version.
+    pub async fn create_key_backup(
+        &self,
+        secret_storage: Option<&SecretStorageKey>,
+    ) -> Result<(String, BackupDecryptionKey)> {
+        let olm = self.require_olm_machine()?;
+        let decryption_key = BackupDecryptionKey::new();
+        let auth_data = olm.lock().await.backup_auth_data(&decryption_key.backup_key())?;
+
+        let request =
+            self.authenticated_request(Method::POST, "/_matrix/client/v3/room_keys/version")?;
+        let body = json!({ "algorithm": MEGOLM_BACKUP_V1_ALGORITHM, "auth_data": auth_data });
+        let response = self.send(request.json(&body)).await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Failed to create backup version: {}", error_text));
+        }
+
+        #[derive(Deserialize)]
+        struct CreateVersionResponse {
+            version: String,
+        }
+
+        let version = response.json::<CreateVersionResponse>().await?.version;
+        debug!("Created key backup version {}", version);
+
+        if let Some(key) = secret_storage {
+            self.store_secret(key, MEGOLM_BACKUP_SECRET, &decryption_key.to_base64())
+                .await?;
+        }
+
+        olm.lock().await.enable_backup(&version, decryption_key.backup_key());
+        Ok((version, decryption_key))
+    }
+
+    /// Back up room keys to an existing backup version
+    ///
+    /// Fails unless the version's `auth_data` is signed by this device, a
+    /// verified device of ours or our verified master key.
+    pub async fn enable_key_backup(&self, info: &BackupVersionInfo) -> Result<()> {
+        let auth_data = info.megolm_auth_data()?;
+        let olm = self.require_olm_machine()?;
+        let mut olm = olm.lock().await;
+
+        if !olm.is_backup_trusted(&auth_data) {
+            return Err(anyhow::anyhow!("Backup version {} is not trusted", info.version));
+        }
+        olm.enable_backup(&info.version, BackupKey::from_base64(&auth_data.public_key)?);
+        Ok(())
+    }
+
+    /// Stop backing up room keys
+    pub async fn disable_key_backup(&self) -> Result<()> {
+        self.require_olm_machine()?.lock().await.disable_backup();
+        Ok(())
+    }
+
+    /// Upload the room keys missing from the enabled backup
+    ///
+    /// Only keys received since the last upload are sent. Returns the number
+    /// of uploaded keys; nothing is uploaded while no backup is enabled.
+    pub async fn backup_room_keys(&self) -> Result<usize> {
+        let olm = self.require_olm_machine()?;
+        let mut uploaded = 0;
+
+        loop {
+            let request = olm.lock().await.backup_request(BACKUP_BATCH_SIZE)?;
+            let Some(request) = request else {
+                return Ok(uploaded);
+            };
+
+            let path = format!(
+                "/_matrix/client/v3/room_keys/keys?version={}",
+                urlencoding::encode(&request.version)
+            );
+            let response = self
+                .send(self.authenticated_request(Method::PUT, &path)?.json(&request.keys))
+                .await?;
+
+            if !response.status().is_success() {
+                let error_text = response.text().await?;
+                return Err(anyhow::anyhow!("Failed to back up room keys: {}", error_text));
+            }
+
+            let response: RoomKeysPutResponse = response.json().await?;
+            debug!(
+                "Backed up {} room keys, {} in backup {}",
+                request.sessions.len(),
+                response.count,
+                request.version
+            );
+
+            olm.lock().await.mark_backed_up(&request.sessions);
+            uploaded += request.sessions.len();
+        }
+    }
+
+    /// Download and import every room key of the current backup
+    ///
+    /// `progress` is called after each key. Keys that fail to decrypt are
+    /// counted and skipped. Backing up to the version is enabled as well,
+    /// since holding its private key proves the backup is ours.
+    pub async fn restore_key_backup<F>(
+        &self,
+        decryption_key: &BackupDecryptionKey,
+        mut progress: F,
+    ) -> Result<RestoreProgress>
+    where
+        F: FnMut(&RestoreProgress),
+    {
+        let olm = self.require_olm_machine()?;
+        let info = self
+            .key_backup_version()
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("No key backup exists"))?;
+
+        let auth_data = info.megolm_auth_data()?;
+        if BackupKey::from_base64(&auth_data.public_key)? != decryption_key.backup_key() {
+            return Err(anyhow::anyhow!(
+                "The decryption key does not match backup version {}",
+                info.version
+            ));
+        }
+
+        let path = format!(
+            "/_matrix/client/v3/room_keys/keys?version={}",
+            urlencoding::encode(&info.version)
+        );
+        let response = self.send(self.authenticated_request(Method::GET, &path)?).await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Failed to download room keys: {}", error_text));
+        }
+
+        let backup: RoomKeysGetResponse = response.json().await?;
+        // Enable first so restored keys count as backed up instead of being re-uploaded
+        olm.lock().await.enable_backup(&info.version, decryption_key.backup_key());
+
+        let mut state = RestoreProgress {
+            total: backup.rooms.values().map(|room| room.sessions.len()).sum(),
+            ..RestoreProgress::default()
+        };
+        progress(&state);
+
+        for (room_id, room) in &backup.rooms {
+            for (session_id, key_data) in &room.sessions {
+                let result = olm.lock().await.restore_backed_up_key(
+                    decryption_key,
+                    room_id,
+                    session_id,
+                    key_data,
+                );
+                match result {
+                    Ok(true) => state.imported += 1,
+                    Ok(false) => {},
+                    Err(e) => {
+                        warn!("Failed to restore room key {} for {}: {}", session_id, room_id, e);
+                        state.failed += 1;
+                    },
+                }
+                state.processed += 1;
+                progress(&state);
+            }
+        }
+
+        Ok(state)
+    }
+
+    /// Restore the current backup with the decryption key from secret storage
+    pub async fn restore_key_backup_from_secret_storage<F>(
+        &self,
+        key: &SecretStorageKey,
+        progress: F,
+    ) -> Result<RestoreProgress>
+    where
+        F: FnMut(&RestoreProgress),
+    {
+        let secret = self.require_secret(key, MEGOLM_BACKUP_SECRET).await?;
+        let decryption_key = BackupDecryptionKey::from_base64(&secret)?;
+        self.restore_key_backup(&decryption_key, progress).await
+    }
+}
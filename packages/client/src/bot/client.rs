@@ -0,0 +1,125 @@
+//! What a bot needs from the homeserver
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::Value;
+use url::Url;
+
+use crate::_matrix::client::EmptyResponse;
+use crate::http_client::{HttpClientError, MatrixHttpClient};
+use crate::send_queue::SendQueue;
+
+/// Homeserver access of a bot
+///
+/// [`HttpBotClient`] talks to a homeserver; unit tests record what a bot
+/// does instead.
+#[async_trait]
+pub trait BotClient: Send + Sync {
+    /// The bot's own user ID
+    fn user_id(&self) -> &str;
+
+    /// Send a room event, returning its event ID
+    async fn send_event(&self, room_id: &str, event_type: &str, content: Value) -> Result<String>;
+
+    async fn join_room(&self, room_id: &str) -> Result<()>;
+
+    /// Content of a room state event, `None` if the room has none
+    async fn state_event(
+        &self,
+        room_id: &str,
+        event_type: &str,
+        state_key: &str,
+    ) -> Result<Option<Value>>;
+
+    /// Content of the bot's global account data of the given type
+    async fn account_data(&self, event_type: &str) -> Result<Option<Value>>;
+
+    async fn set_account_data(&self, event_type: &str, content: &Value) -> Result<()>;
+}
+
+/// [`BotClient`] over the Client-Server API
+///
+/// Events go through the client's [`SendQueue`], so they are sent in order
+/// per room, keep their transaction ID across retries and are encrypted for
+/// encrypted rooms when the client has encryption enabled.
+pub struct HttpBotClient {
+    user_id: String,
+    http_client: MatrixHttpClient,
+    send_queue: SendQueue,
+}
+
+impl HttpBotClient {
+    pub async fn new(
+        homeserver_url: &Url,
+        access_token: &str,
+        user_id: &str,
+        send_queue: SendQueue,
+    ) -> Result<Self> {
+        let http_client = MatrixHttpClient::new(homeserver_url.clone())?;
+        http_client.set_access_token(access_token.to_string()).await;
+        Ok(Self {
+            user_id: user_id.to_string(),
+            http_client,
+            send_queue,
+        })
+    }
+
+    fn account_data_path(&self, event_type: &str) -> String {
+        format!(
+            "/_matrix/client/v3/user/{}/account_data/{}",
+            urlencoding::encode(&self.user_id),
+            urlencoding::encode(event_type)
+        )
+    }
+}
+
+#[async_trait]
+impl BotClient for HttpBotClient {
+    fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    async fn send_event(&self, room_id: &str, event_type: &str, content: Value) -> Result<String> {
+        // A failed event holds up its room; the bot moves on instead
+        self.send_queue.send(room_id, event_type, content).await
+    }
+
+    async fn join_room(&self, room_id: &str) -> Result<()> {
+        let path = format!("/_matrix/client/v3/join/{}", urlencoding::encode(room_id));
+        let _: Value = self.http_client.post(&path, &serde_json::json!({})).await?;
+        Ok(())
+    }
+
+    async fn state_event(
+        &self,
+        room_id: &str,
+        event_type: &str,
+        state_key: &str,
+    ) -> Result<Option<Value>> {
+        let path = format!(
+            "/_matrix/client/v3/rooms/{}/state/{}/{}",
+            urlencoding::encode(room_id),
+            urlencoding::encode(event_type),
+            urlencoding::encode(state_key)
+        );
+        not_found_as_none(self.http_client.get(&path).await)
+    }
+
+    async fn account_data(&self, event_type: &str) -> Result<Option<Value>> {
+        not_found_as_none(self.http_client.get(&self.account_data_path(event_type)).await)
+    }
+
+    async fn set_account_data(&self, event_type: &str, content: &Value) -> Result<()> {
+        let _: EmptyResponse =
+            self.http_client.put(&self.account_data_path(event_type), content).await?;
+        Ok(())
+    }
+}
+
+fn not_found_as_none(result: Result<Value, HttpClientError>) -> Result<Option<Value>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(e) if e.status_code() == Some(404) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,301 @@
+//! Bot commands and their arguments
+
+use anyhow::Result;
+use futures_util::future::BoxFuture;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::CommandContext;
+
+pub(crate) type CommandHandler =
+    Arc<dyn Fn(CommandContext) -> BoxFuture<'static, Result<()>> + Send + Sync>;
+
+/// A command the bot answers to, like `!echo hello`
+#[derive(Clone)]
+pub struct Command {
+    pub(crate) name: String,
+    pub(crate) description: Option<String>,
+    pub(crate) usage: Option<String>,
+    pub(crate) power_level: Option<i64>,
+    pub(crate) rooms: Option<Vec<String>>,
+    pub(crate) handler: CommandHandler,
+}
+
+impl std::fmt::Debug for Command {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Command")
+            .field("name", &self.name)
+            .field("power_level", &self.power_level)
+            .field("rooms", &self.rooms)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Command {
+    /// A command named `name`, without the prefix
+    pub fn new<F, Fut>(name: impl Into<String>, handler: F) -> Self
+    where
+        F: Fn(CommandContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        Self {
+            name: name.into(),
+            description: None,
+            usage: None,
+            power_level: None,
+            rooms: None,
+            handler: Arc::new(move |context| Box::pin(handler(context))),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// One line for `help`
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Arguments as shown by `help`, e.g. `<user> [reason]`
+    pub fn with_usage(mut self, usage: impl Into<String>) -> Self {
+        self.usage = Some(usage.into());
+        self
+    }
+
+    /// Only users with at least this power level in the room may run it
+    pub fn with_power_level(mut self, power_level: i64) -> Self {
+        self.power_level = Some(power_level);
+        self
+    }
+
+    /// Only answer in these rooms
+    pub fn with_rooms(mut self, rooms: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.rooms = Some(rooms.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub(crate) fn allowed_in(&self, room_id: &str) -> bool {
+        self.rooms
+            .as_ref()
+            .is_none_or(|rooms| rooms.iter().any(|room| room == room_id))
+    }
+
+    /// `name usage`, for `help` and usage errors
+    pub(crate) fn synopsis(&self, prefix: &str) -> String {
+        match &self.usage {
+            Some(usage) => format!("{}{} {}", prefix, self.name, usage),
+            None => format!("{}{}", prefix, self.name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ArgError {
+    #[error("Missing argument <{0}>")]
+    Missing(String),
+    #[error("Invalid argument <{name}>: expected {expected}, got \"{value}\"")]
+    Invalid {
+        name: String,
+        value: String,
+        expected: &'static str,
+    },
+    #[error("Unexpected argument \"{0}\"")]
+    Unexpected(String),
+}
+
+/// A type command arguments can be parsed into
+pub trait FromArg: Sized {
+    /// What a valid argument looks like, for error messages
+    const EXPECTED: &'static str;
+
+    fn from_arg(arg: &str) -> Option<Self>;
+}
+
+impl FromArg for String {
+    const EXPECTED: &'static str = "text";
+
+    fn from_arg(arg: &str) -> Option<Self> {
+        Some(arg.to_string())
+    }
+}
+
+macro_rules! from_arg_via_parse {
+    ($($ty:ty => $expected:literal),* $(,)?) => {
+        $(impl FromArg for $ty {
+            const EXPECTED: &'static str = $expected;
+
+            fn from_arg(arg: &str) -> Option<Self> {
+                arg.parse().ok()
+            }
+        })*
+    };
+}
+
+from_arg_via_parse! {
+    i32 => "a whole number",
+    i64 => "a whole number",
+    u32 => "a positive whole number",
+    u64 => "a positive whole number",
+    usize => "a positive whole number",
+    f64 => "a number",
+}
+
+impl FromArg for bool {
+    const EXPECTED: &'static str = "yes or no";
+
+    fn from_arg(arg: &str) -> Option<Self> {
+        match arg.to_lowercase().as_str() {
+            "yes" | "y" | "true" | "on" | "1" => Some(true),
+            "no" | "n" | "false" | "off" | "0" => Some(false),
+            _ => None,
+        }
+    }
+}
+
+impl FromArg for Duration {
+    const EXPECTED: &'static str = "a duration like 30s, 10m, 2h or 1d";
+
+    fn from_arg(arg: &str) -> Option<Self> {
+        let split = arg.find(|c: char| !c.is_ascii_digit())?;
+        let (amount, unit) = arg.split_at(split);
+        let amount: u64 = amount.parse().ok()?;
+        let seconds = match unit {
+            "s" => amount,
+            "m" => amount.checked_mul(60)?,
+            "h" => amount.checked_mul(60 * 60)?,
+            "d" => amount.checked_mul(24 * 60 * 60)?,
+            _ => return None,
+        };
+        Some(Duration::from_secs(seconds))
+    }
+}
+
+/// Arguments after the command name
+///
+/// Arguments are separated by whitespace; quote an argument with `"` or `'`
+/// to include spaces and escape a quote with `\`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandArgs {
+    raw: String,
+    /// Arguments with their byte offset in `raw`
+    args: Vec<(usize, String)>,
+    next: usize,
+}
+
+impl CommandArgs {
+    pub fn parse(raw: &str) -> Self {
+        let mut args = Vec::new();
+        let mut chars = raw.char_indices().peekable();
+
+        while let Some(&(start, c)) = chars.peek() {
+            if c.is_whitespace() {
+                chars.next();
+                continue;
+            }
+
+            let mut arg = String::new();
+            let mut quote = None;
+            while let Some(&(_, c)) = chars.peek() {
+                match (c, quote) {
+                    ('\\', _) => {
+                        chars.next();
+                        if let Some((_, escaped)) = chars.next() {
+                            arg.push(escaped);
+                        }
+                        continue;
+                    },
+                    ('"' | '\'', None) => quote = Some(c),
+                    (c, Some(open)) if c == open => quote = None,
+                    (c, None) if c.is_whitespace() => break,
+                    (c, _) => arg.push(c),
+                }
+                chars.next();
+            }
+            args.push((start, arg));
+        }
+
+        Self { raw: raw.to_string(), args, next: 0 }
+    }
+
+    /// All arguments, parsed or not
+    pub fn all(&self) -> Vec<&str> {
+        self.args.iter().map(|(_, arg)| arg.as_str()).collect()
+    }
+
+    /// Whether every argument has been taken
+    pub fn is_empty(&self) -> bool {
+        self.next >= self.args.len()
+    }
+
+    /// Take the next argument, which `name` describes in errors
+    pub fn next<T: FromArg>(&mut self, name: &str) -> Result<T, ArgError> {
+        self.optional(name)?.ok_or_else(|| ArgError::Missing(name.to_string()))
+    }
+
+    /// Take the next argument if there is one
+    pub fn optional<T: FromArg>(&mut self, name: &str) -> Result<Option<T>, ArgError> {
+        let Some((_, arg)) = self.args.get(self.next) else {
+            return Ok(None);
+        };
+        let value = T::from_arg(arg).ok_or_else(|| ArgError::Invalid {
+            name: name.to_string(),
+            value: arg.clone(),
+            expected: T::EXPECTED,
+        })?;
+        self.next += 1;
+        Ok(Some(value))
+    }
+
+    /// The rest of the text as typed, quotes and all
+    pub fn rest(&mut self) -> String {
+        let rest = match self.args.get(self.next) {
+            Some((start, _)) => self.raw[*start..].trim_end().to_string(),
+            None => String::new(),
+        };
+        self.next = self.args.len();
+        rest
+    }
+
+    /// Fail if arguments are left over
+    pub fn finish(&self) -> Result<(), ArgError> {
+        match self.args.get(self.next) {
+            Some((_, arg)) => Err(ArgError::Unexpected(arg.clone())),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_args() {
+        let mut args = CommandArgs::parse(r#" 42  "two words" it\'s yes  rest of  it "#);
+        assert_eq!(args.all(), vec!["42", "two words", "it's", "yes", "rest", "of", "it"]);
+
+        assert_eq!(args.next::<u32>("count"), Ok(42));
+        assert_eq!(args.next::<String>("text"), Ok("two words".to_string()));
+        assert_eq!(args.next::<String>("text"), Ok("it's".to_string()));
+        assert_eq!(args.optional::<bool>("flag"), Ok(Some(true)));
+        assert_eq!(args.rest(), "rest of  it");
+        assert!(args.is_empty());
+        assert_eq!(args.next::<String>("reason"), Err(ArgError::Missing("reason".to_string())));
+    }
+
+    #[test]
+    fn test_invalid_args() {
+        let mut args = CommandArgs::parse("ten 5m extra");
+        assert!(matches!(
+            args.next::<i64>("count"),
+            Err(ArgError::Invalid { expected: "a whole number", .. })
+        ));
+        // A failed parse does not take the argument
+        assert_eq!(args.next::<String>("count"), Ok("ten".to_string()));
+        assert_eq!(args.next::<Duration>("delay"), Ok(Duration::from_secs(300)));
+        assert_eq!(args.finish(), Err(ArgError::Unexpected("extra".to_string())));
+    }
+}
@@ -0,0 +1,602 @@
+//! Bots on top of [`RealtimeMatrixClient`]
+//!
+//! A [`Bot`] routes `!command` messages to [`Command`] handlers with typed
+//! arguments, checks the sender's power level, joins rooms it is invited to
+//! by allowed users, runs scheduled tasks and keeps a small key-value state
+//! in account data. When sync gives up, the bot reconnects with backoff.
+//!
+//! Handlers get a [`CommandContext`] with reply, thread and reaction
+//! helpers. Bots are tested end-to-end against an in-process matryxd by
+//! the harness in `tests/bot.rs`.
+
+pub mod client;
+pub mod command;
+#[cfg(test)]
+mod recording;
+
+pub use client::{BotClient, HttpBotClient};
+pub use command::{ArgError, Command, CommandArgs, FromArg};
+
+use anyhow::Result;
+use futures_util::future::BoxFuture;
+use matryx_entity::{ConnectionStatus, Event, MembershipState};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value, json};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, broadcast};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use crate::realtime::{RealtimeEvent, RealtimeMatrixClient};
+use command::CommandHandler;
+
+const MESSAGE_EVENT_TYPE: &str = "m.room.message";
+const REACTION_EVENT_TYPE: &str = "m.reaction";
+const POWER_LEVELS_EVENT_TYPE: &str = "m.room.power_levels";
+const CREATE_EVENT_TYPE: &str = "m.room.create";
+
+type TaskHandler = Arc<dyn Fn(BotContext) -> BoxFuture<'static, Result<()>> + Send + Sync>;
+
+/// How a bot behaves
+#[derive(Debug, Clone)]
+pub struct BotConfig {
+    /// What commands start with
+    pub prefix: String,
+    /// Join rooms the bot is invited to
+    pub auto_join: bool,
+    /// Who may invite the bot: user IDs, or server names to allow everyone
+    /// on a server; empty allows everyone
+    pub invite_allowlist: Vec<String>,
+    /// Users who may run every command, whatever their power level
+    pub admins: Vec<String>,
+    /// Account data type the bot state is kept in
+    pub state_event_type: String,
+    /// Reconnect attempts in a row before [`Bot::run`] gives up
+    pub max_reconnect_attempts: u32,
+    /// Delay before the first reconnect attempt, doubled after each failure
+    pub reconnect_delay: Duration,
+}
+
+impl Default for BotConfig {
+    fn default() -> Self {
+        Self {
+            prefix: "!".to_string(),
+            auto_join: true,
+            invite_allowlist: Vec::new(),
+            admins: Vec::new(),
+            state_event_type: "org.matryx.bot.state".to_string(),
+            max_reconnect_attempts: 5,
+            reconnect_delay: Duration::from_secs(5),
+        }
+    }
+}
+
+impl BotConfig {
+    /// Whether `inviter` may invite the bot
+    pub fn allows_invite_from(&self, inviter: &str) -> bool {
+        let server = inviter.split_once(':').map(|(_, server)| server);
+        self.invite_allowlist.is_empty() ||
+            self.invite_allowlist
+                .iter()
+                .any(|allowed| allowed == inviter || Some(allowed.as_str()) == server)
+    }
+}
+
+struct ScheduledTask {
+    interval: Duration,
+    handler: TaskHandler,
+}
+
+/// A command bot
+///
+/// ```no_run
+/// # use matryx_client::bot::{Bot, BotConfig, Command};
+/// # async fn run(client: matryx_client::realtime::RealtimeMatrixClient) -> anyhow::Result<()> {
+/// let bot = Bot::new(BotConfig::default()).with_command(
+///     Command::new("echo", |mut context| async move {
+///         let text = context.args.rest();
+///         context.reply(&text).await?;
+///         Ok(())
+///     })
+///     .with_description("Repeat the text"),
+/// );
+/// bot.run(client).await
+/// # }
+/// ```
+pub struct Bot {
+    config: BotConfig,
+    /// Commands by name
+    commands: BTreeMap<String, Command>,
+    tasks: Vec<ScheduledTask>,
+}
+
+impl Bot {
+    /// A bot with the built-in `help` command
+    pub fn new(config: BotConfig) -> Self {
+        let help = Command::new("help", |context: CommandContext| async move {
+            let text = context.help.clone();
+            context.reply(&text).await?;
+            Ok(())
+        })
+        .with_description("List the commands");
+
+        Self {
+            config,
+            commands: BTreeMap::from([(help.name.clone(), help)]),
+            tasks: Vec::new(),
+        }
+    }
+
+    pub fn config(&self) -> &BotConfig {
+        &self.config
+    }
+
+    /// Add a command, replacing one of the same name
+    pub fn with_command(mut self, command: Command) -> Self {
+        self.commands.insert(command.name.clone(), command);
+        self
+    }
+
+    /// Run `handler` every `interval` while the bot runs
+    pub fn with_task<F, Fut>(mut self, interval: Duration, handler: F) -> Self
+    where
+        F: Fn(BotContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.tasks.push(ScheduledTask {
+            interval,
+            handler: Arc::new(move |context| Box::pin(handler(context))),
+        });
+        self
+    }
+
+    /// Run the bot on a logged-in client until sync cannot be restarted
+    ///
+    /// Only events sent after the bot started are handled, so the history
+    /// of an initial sync is not answered again.
+    pub async fn run(self, mut client: RealtimeMatrixClient) -> Result<()> {
+        let user_id = client.user_id().ok_or_else(|| anyhow::anyhow!("Not authenticated"))?;
+        let access_token =
+            client.access_token().ok_or_else(|| anyhow::anyhow!("Not authenticated"))?;
+        let send_queue =
+            client.send_queue().cloned().ok_or_else(|| anyhow::anyhow!("Not authenticated"))?;
+        let bot_client: Arc<dyn BotClient> = Arc::new(
+            HttpBotClient::new(client.homeserver_url(), access_token, user_id, send_queue).await?,
+        );
+
+        let bot = Arc::new(self);
+        let context = bot.context(bot_client);
+        let started_at = chrono::Utc::now().timestamp_millis();
+        let _tasks = TaskGuard(bot.spawn_tasks(&context));
+        let mut events = client.subscribe_to_events();
+
+        info!("Bot {} is running", context.user_id());
+        let mut failures = 0;
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("Bot dropped {} events", skipped);
+                    continue;
+                },
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            };
+
+            match &event {
+                RealtimeEvent::ConnectionStatusChanged {
+                    new_status: ConnectionStatus::Error(message),
+                    ..
+                } => {
+                    warn!("Bot lost sync: {}", message);
+                    loop {
+                        if failures >= bot.config.max_reconnect_attempts {
+                            return Err(anyhow::anyhow!("Giving up after {} reconnects", failures));
+                        }
+                        tokio::time::sleep(bot.config.reconnect_delay * 2u32.pow(failures)).await;
+                        failures += 1;
+                        match client.reconnect().await {
+                            Ok(()) => break,
+                            Err(e) => warn!("Bot failed to reconnect: {}", e),
+                        }
+                    }
+                },
+                RealtimeEvent::SyncUpdate(_) => failures = 0,
+                RealtimeEvent::RoomEvent { event: room_event, .. }
+                    if room_event.origin_server_ts < started_at =>
+                {
+                    continue;
+                },
+                _ => {},
+            }
+
+            let bot = bot.clone();
+            let context = context.clone();
+            tokio::spawn(async move {
+                if let Err(e) = bot.handle_event(&context, &event).await {
+                    warn!("Bot failed to handle event: {}", e);
+                }
+            });
+        }
+    }
+
+    /// Shared context of handlers and tasks
+    pub fn context(&self, client: Arc<dyn BotClient>) -> BotContext {
+        BotContext {
+            state: Arc::new(BotState::new(client.clone(), &self.config.state_event_type)),
+            client,
+        }
+    }
+
+    fn spawn_tasks(&self, context: &BotContext) -> Vec<JoinHandle<()>> {
+        self.tasks
+            .iter()
+            .map(|task| {
+                let handler = task.handler.clone();
+                let context = context.clone();
+                let period = task.interval;
+                tokio::spawn(async move {
+                    let start = tokio::time::Instant::now() + period;
+                    let mut interval = tokio::time::interval_at(start, period);
+                    loop {
+                        interval.tick().await;
+                        if let Err(e) = handler(context.clone()).await {
+                            warn!("Scheduled bot task failed: {}", e);
+                        }
+                    }
+                })
+            })
+            .collect()
+    }
+
+    /// Run every scheduled task once, e.g. from a test
+    pub async fn run_tasks(&self, context: &BotContext) -> Result<()> {
+        for task in &self.tasks {
+            (task.handler)(context.clone()).await?;
+        }
+        Ok(())
+    }
+
+    /// React to an event: join on invites and run commands
+    pub async fn handle_event(&self, context: &BotContext, event: &RealtimeEvent) -> Result<()> {
+        match event {
+            RealtimeEvent::MembershipChanged { room_id, user_id, membership }
+                if user_id == context.user_id() &&
+                    membership.membership == MembershipState::Invite =>
+            {
+                self.handle_invite(context, room_id, membership.invited_by.as_deref())
+                    .await
+            },
+            RealtimeEvent::RoomEvent { room_id, event } => {
+                self.handle_message(context, room_id, event).await
+            },
+            _ => Ok(()),
+        }
+    }
+
+    async fn handle_invite(
+        &self,
+        context: &BotContext,
+        room_id: &str,
+        inviter: Option<&str>,
+    ) -> Result<()> {
+        if !self.config.auto_join {
+            return Ok(());
+        }
+        if !inviter.is_some_and(|inviter| self.config.allows_invite_from(inviter)) {
+            debug!("Ignoring invite to {} from {:?}", room_id, inviter);
+            return Ok(());
+        }
+
+        info!("Joining {} on invite from {:?}", room_id, inviter);
+        context.client.join_room(room_id).await
+    }
+
+    async fn handle_message(
+        &self,
+        context: &BotContext,
+        room_id: &str,
+        event: &Event,
+    ) -> Result<()> {
+        if event.event_type != MESSAGE_EVENT_TYPE || event.sender == context.user_id() {
+            return Ok(());
+        }
+        let content = serde_json::to_value(&event.content)?;
+        if content.get("msgtype").and_then(Value::as_str) != Some("m.text") || is_edit(&content) {
+            return Ok(());
+        }
+        let Some(body) = content.get("body").and_then(Value::as_str) else {
+            return Ok(());
+        };
+        let Some(text) = body.strip_prefix(&self.config.prefix) else {
+            return Ok(());
+        };
+
+        let (name, args) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        let Some(command) = self.commands.get(name) else {
+            return Ok(());
+        };
+        if !command.allowed_in(room_id) {
+            return Ok(());
+        }
+
+        let command_context = CommandContext {
+            bot: context.clone(),
+            room_id: room_id.to_string(),
+            sender: event.sender.clone(),
+            event: event.clone(),
+            content,
+            args: CommandArgs::parse(args),
+            help: self.help(room_id),
+        };
+
+        if let Some(required) = command.power_level
+            && !self.config.admins.contains(&event.sender)
+        {
+            let level = context.power_level(room_id, &event.sender).await?;
+            if level < required {
+                let text = format!(
+                    "You need power level {} to use {}{}",
+                    required, self.config.prefix, command.name
+                );
+                command_context.reply(&text).await?;
+                return Ok(());
+            }
+        }
+
+        self.run_command(command, command_context).await
+    }
+
+    async fn run_command(&self, command: &Command, context: CommandContext) -> Result<()> {
+        let handler: CommandHandler = command.handler.clone();
+        let reply_to = context.clone();
+
+        let Err(e) = handler(context).await else {
+            return Ok(());
+        };
+
+        // Bad arguments are the sender's mistake, anything else is the bot's
+        if let Some(error) = e.downcast_ref::<ArgError>() {
+            let text = format!("{}\nUsage: {}", error, command.synopsis(&self.config.prefix));
+            reply_to.reply(&text).await?;
+            return Ok(());
+        }
+        let text = format!("{}{} failed: {}", self.config.prefix, command.name, e);
+        reply_to.reply(&text).await?;
+        Err(e)
+    }
+
+    fn help(&self, room_id: &str) -> String {
+        self.commands
+            .values()
+            .filter(|command| command.allowed_in(room_id))
+            .map(|command| match &command.description {
+                Some(description) => {
+                    format!("{} - {}", command.synopsis(&self.config.prefix), description)
+                },
+                None => command.synopsis(&self.config.prefix),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Aborts scheduled tasks when the bot stops
+struct TaskGuard(Vec<JoinHandle<()>>);
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        for task in &self.0 {
+            task.abort();
+        }
+    }
+}
+
+fn is_edit(content: &Value) -> bool {
+    content.pointer("/m.relates_to/rel_type").and_then(Value::as_str) == Some("m.replace")
+}
+
+/// What handlers and scheduled tasks can do
+#[derive(Clone)]
+pub struct BotContext {
+    client: Arc<dyn BotClient>,
+    state: Arc<BotState>,
+}
+
+impl BotContext {
+    pub fn user_id(&self) -> &str {
+        self.client.user_id()
+    }
+
+    pub fn client(&self) -> &Arc<dyn BotClient> {
+        &self.client
+    }
+
+    /// Persistent state of the bot
+    pub fn state(&self) -> &BotState {
+        &self.state
+    }
+
+    /// Send a text message
+    pub async fn send_text(&self, room_id: &str, text: &str) -> Result<String> {
+        self.send_message(room_id, json!({ "msgtype": "m.text", "body": text }))
+            .await
+    }
+
+    /// Send a notice, the message type for bot output
+    pub async fn send_notice(&self, room_id: &str, text: &str) -> Result<String> {
+        self.send_message(room_id, json!({ "msgtype": "m.notice", "body": text }))
+            .await
+    }
+
+    pub async fn send_message(&self, room_id: &str, content: Value) -> Result<String> {
+        self.client.send_event(room_id, MESSAGE_EVENT_TYPE, content).await
+    }
+
+    /// React to an event with `key`, usually an emoji
+    pub async fn react(&self, room_id: &str, event_id: &str, key: &str) -> Result<String> {
+        let content = json!({
+            "m.relates_to": { "rel_type": "m.annotation", "event_id": event_id, "key": key },
+        });
+        self.client.send_event(room_id, REACTION_EVENT_TYPE, content).await
+    }
+
+    /// Power level of a user in a room
+    ///
+    /// Without `m.room.power_levels` the room creator has 100 and everyone
+    /// else 0.
+    pub async fn power_level(&self, room_id: &str, user_id: &str) -> Result<i64> {
+        let Some(power_levels) =
+            self.client.state_event(room_id, POWER_LEVELS_EVENT_TYPE, "").await?
+        else {
+            let create = self.client.state_event(room_id, CREATE_EVENT_TYPE, "").await?;
+            let creator = create.as_ref().and_then(|create| create.get("creator")?.as_str());
+            return Ok(if creator == Some(user_id) { 100 } else { 0 });
+        };
+
+        let level = |value: Option<&Value>| value.and_then(Value::as_i64);
+        Ok(level(power_levels.get("users").and_then(|users| users.get(user_id)))
+            .or_else(|| level(power_levels.get("users_default")))
+            .unwrap_or(0))
+    }
+}
+
+/// A command being run
+#[derive(Clone)]
+pub struct CommandContext {
+    pub bot: BotContext,
+    pub room_id: String,
+    pub sender: String,
+    /// The message with the command
+    pub event: Event,
+    /// Content of the message
+    pub content: Value,
+    pub args: CommandArgs,
+    /// Commands available in the room, one per line
+    help: String,
+}
+
+impl CommandContext {
+    /// Thread the command was sent in, if any
+    pub fn thread_root(&self) -> Option<&str> {
+        let relation = self.content.get("m.relates_to")?;
+        if relation.get("rel_type")?.as_str()? != "m.thread" {
+            return None;
+        }
+        relation.get("event_id")?.as_str()
+    }
+
+    /// Send a notice to the room, in the command's thread if it has one
+    pub async fn send(&self, text: &str) -> Result<String> {
+        let mut content = json!({ "msgtype": "m.notice", "body": text });
+        if let Some(root) = self.thread_root() {
+            content["m.relates_to"] = json!({
+                "rel_type": "m.thread",
+                "event_id": root,
+                "is_falling_back": true,
+                "m.in_reply_to": { "event_id": self.event.event_id },
+            });
+        }
+        self.bot.send_message(&self.room_id, content).await
+    }
+
+    /// Reply to the command, staying in its thread if it has one
+    pub async fn reply(&self, text: &str) -> Result<String> {
+        let mut content = json!({ "msgtype": "m.notice", "body": text });
+        content["m.relates_to"] = match self.thread_root() {
+            Some(root) => json!({
+                "rel_type": "m.thread",
+                "event_id": root,
+                "is_falling_back": false,
+                "m.in_reply_to": { "event_id": self.event.event_id },
+            }),
+            None => json!({ "m.in_reply_to": { "event_id": self.event.event_id } }),
+        };
+        self.bot.send_message(&self.room_id, content).await
+    }
+
+    /// Answer in a thread, starting one on the command if needed
+    pub async fn reply_in_thread(&self, text: &str) -> Result<String> {
+        let root = self.thread_root().unwrap_or(&self.event.event_id).to_string();
+        let content = json!({
+            "msgtype": "m.notice",
+            "body": text,
+            "m.relates_to": {
+                "rel_type": "m.thread",
+                "event_id": root,
+                "is_falling_back": true,
+                "m.in_reply_to": { "event_id": self.event.event_id },
+            },
+        });
+        self.bot.send_message(&self.room_id, content).await
+    }
+
+    /// React to the command
+    pub async fn react(&self, key: &str) -> Result<String> {
+        self.bot.react(&self.room_id, &self.event.event_id, key).await
+    }
+}
+
+/// Key-value state of a bot, kept in account data so it survives restarts
+pub struct BotState {
+    client: Arc<dyn BotClient>,
+    event_type: String,
+    /// Loaded on first use
+    values: Mutex<Option<Map<String, Value>>>,
+}
+
+impl BotState {
+    fn new(client: Arc<dyn BotClient>, event_type: &str) -> Self {
+        Self {
+            client,
+            event_type: event_type.to_string(),
+            values: Mutex::new(None),
+        }
+    }
+
+    pub async fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        let mut values = self.values.lock().await;
+        match self.load(&mut values).await?.get(key) {
+            Some(value) => Ok(Some(serde_json::from_value(value.clone())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Set a value and save the state
+    pub async fn set<T: Serialize>(&self, key: &str, value: &T) -> Result<()> {
+        let value = serde_json::to_value(value)?;
+        let mut values = self.values.lock().await;
+        let loaded = self.load(&mut values).await?;
+        loaded.insert(key.to_string(), value);
+        let content = Value::Object(loaded.clone());
+        self.client.set_account_data(&self.event_type, &content).await
+    }
+
+    /// Remove a value and save the state
+    pub async fn remove(&self, key: &str) -> Result<()> {
+        let mut values = self.values.lock().await;
+        let loaded = self.load(&mut values).await?;
+        if loaded.remove(key).is_some() {
+            let content = Value::Object(loaded.clone());
+            self.client.set_account_data(&self.event_type, &content).await?;
+        }
+        Ok(())
+    }
+
+    async fn load<'a>(
+        &self,
+        values: &'a mut Option<Map<String, Value>>,
+    ) -> Result<&'a mut Map<String, Value>> {
+        if values.is_none() {
+            let stored = self.client.account_data(&self.event_type).await?;
+            *values = Some(match stored {
+                Some(Value::Object(stored)) => stored,
+                _ => Map::new(),
+            });
+        }
+        Ok(values.get_or_insert_with(Map::new))
+    }
+}
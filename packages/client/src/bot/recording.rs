@@ -0,0 +1,345 @@
+//! Unit testing bots without a homeserver
+//!
+//! [`RecordingHarness`] feeds a [`Bot`] the events a homeserver would
+//! deliver and records what the bot sends back. End-to-end tests against a
+//! real server live in `tests/bot.rs`.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use matryx_entity::{Event, EventContent, Membership, MembershipState};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use super::{Bot, BotClient, BotContext};
+use crate::realtime::RealtimeEvent;
+
+/// An event the bot sent
+#[derive(Debug, Clone, PartialEq)]
+pub struct SentEvent {
+    pub room_id: String,
+    pub event_type: String,
+    pub content: Value,
+}
+
+impl SentEvent {
+    /// Body of a message
+    pub fn body(&self) -> Option<&str> {
+        self.content.get("body")?.as_str()
+    }
+}
+
+#[derive(Default)]
+struct Recorded {
+    sent: Vec<SentEvent>,
+    joined_rooms: Vec<String>,
+    /// State contents by room, type and state key
+    state: HashMap<(String, String, String), Value>,
+    account_data: HashMap<String, Value>,
+}
+
+/// [`BotClient`] that records instead of talking to a homeserver
+pub struct RecordingClient {
+    user_id: String,
+    recorded: Mutex<Recorded>,
+}
+
+impl RecordingClient {
+    pub fn new(user_id: &str) -> Self {
+        Self {
+            user_id: user_id.to_string(),
+            recorded: Mutex::new(Recorded::default()),
+        }
+    }
+
+    fn recorded(&self) -> MutexGuard<'_, Recorded> {
+        self.recorded.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[async_trait]
+impl BotClient for RecordingClient {
+    fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    async fn send_event(&self, room_id: &str, event_type: &str, content: Value) -> Result<String> {
+        let mut recorded = self.recorded();
+        let event_id = format!("$sent{}", recorded.sent.len());
+        recorded.sent.push(SentEvent {
+            room_id: room_id.to_string(),
+            event_type: event_type.to_string(),
+            content,
+        });
+        Ok(event_id)
+    }
+
+    async fn join_room(&self, room_id: &str) -> Result<()> {
+        self.recorded().joined_rooms.push(room_id.to_string());
+        Ok(())
+    }
+
+    async fn state_event(
+        &self,
+        room_id: &str,
+        event_type: &str,
+        state_key: &str,
+    ) -> Result<Option<Value>> {
+        let key = (room_id.to_string(), event_type.to_string(), state_key.to_string());
+        Ok(self.recorded().state.get(&key).cloned())
+    }
+
+    async fn account_data(&self, event_type: &str) -> Result<Option<Value>> {
+        Ok(self.recorded().account_data.get(event_type).cloned())
+    }
+
+    async fn set_account_data(&self, event_type: &str, content: &Value) -> Result<()> {
+        self.recorded()
+            .account_data
+            .insert(event_type.to_string(), content.clone());
+        Ok(())
+    }
+}
+
+/// Runs a [`Bot`] against a [`RecordingClient`]
+pub struct RecordingHarness {
+    bot: Bot,
+    client: Arc<RecordingClient>,
+    context: BotContext,
+    next_event: usize,
+}
+
+impl RecordingHarness {
+    pub fn new(bot: Bot, user_id: &str) -> Self {
+        let client = Arc::new(RecordingClient::new(user_id));
+        let context = bot.context(client.clone());
+        Self { bot, client, context, next_event: 0 }
+    }
+
+    pub fn client(&self) -> &Arc<RecordingClient> {
+        &self.client
+    }
+
+    /// Set a room state event the bot can read
+    pub fn set_state(&self, room_id: &str, event_type: &str, state_key: &str, content: Value) {
+        let key = (room_id.to_string(), event_type.to_string(), state_key.to_string());
+        self.client.recorded().state.insert(key, content);
+    }
+
+    /// Give users power levels in a room
+    pub fn set_power_levels(&self, room_id: &str, users: &[(&str, i64)]) {
+        let users: serde_json::Map<String, Value> = users
+            .iter()
+            .map(|(user_id, level)| (user_id.to_string(), json!(level)))
+            .collect();
+        self.set_state(room_id, "m.room.power_levels", "", json!({ "users": users }));
+    }
+
+    /// Deliver a text message, returning its event ID
+    pub async fn message(&mut self, room_id: &str, sender: &str, body: &str) -> Result<String> {
+        self.event(room_id, sender, json!({ "msgtype": "m.text", "body": body }))
+            .await
+    }
+
+    /// Deliver an `m.room.message` with the given content
+    pub async fn event(&mut self, room_id: &str, sender: &str, content: Value) -> Result<String> {
+        self.next_event += 1;
+        let event = Event {
+            event_id: format!("$event{}", self.next_event),
+            sender: sender.to_string(),
+            origin_server_ts: chrono::Utc::now().timestamp_millis(),
+            event_type: "m.room.message".to_string(),
+            room_id: room_id.to_string(),
+            content: EventContent::unknown(content),
+            ..Default::default()
+        };
+        let event_id = event.event_id.clone();
+
+        let event = RealtimeEvent::RoomEvent { room_id: room_id.to_string(), event };
+        self.bot.handle_event(&self.context, &event).await?;
+        Ok(event_id)
+    }
+
+    /// Deliver an invite of the bot
+    pub async fn invite(&self, room_id: &str, inviter: &str) -> Result<()> {
+        let user_id = self.context.user_id().to_string();
+        let mut membership =
+            Membership::new(room_id.to_string(), user_id.clone(), MembershipState::Invite);
+        membership.invited_by = Some(inviter.to_string());
+
+        let event =
+            RealtimeEvent::MembershipChanged { room_id: room_id.to_string(), user_id, membership };
+        self.bot.handle_event(&self.context, &event).await
+    }
+
+    /// Run every scheduled task once
+    pub async fn tick(&self) -> Result<()> {
+        self.bot.run_tasks(&self.context).await
+    }
+
+    /// Events the bot sent, oldest first
+    pub fn sent(&self) -> Vec<SentEvent> {
+        self.client.recorded().sent.clone()
+    }
+
+    /// Bodies of the messages the bot sent
+    pub fn sent_bodies(&self) -> Vec<String> {
+        self.sent()
+            .iter()
+            .filter_map(|event| event.body().map(str::to_string))
+            .collect()
+    }
+
+    pub fn joined_rooms(&self) -> Vec<String> {
+        self.client.recorded().joined_rooms.clone()
+    }
+
+    /// Forget what the bot sent
+    pub fn clear_sent(&self) {
+        self.client.recorded().sent.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::{BotConfig, Command};
+    use std::time::Duration;
+
+    const ROOM: &str = "!room:example.org";
+    const BOT: &str = "@bot:example.org";
+
+    fn bot() -> Bot {
+        Bot::new(BotConfig {
+            invite_allowlist: vec!["@alice:example.org".to_string(), "trusted.org".to_string()],
+            ..Default::default()
+        })
+        .with_command(
+            Command::new("add", |mut context| async move {
+                let a: i64 = context.args.next("a")?;
+                let b: i64 = context.args.next("b")?;
+                context.args.finish()?;
+                context.reply(&(a + b).to_string()).await?;
+                Ok(())
+            })
+            .with_usage("<a> <b>")
+            .with_description("Add two numbers"),
+        )
+        .with_command(
+            Command::new("kick", |context| async move {
+                context.react("👍").await?;
+                Ok(())
+            })
+            .with_power_level(50),
+        )
+        .with_command(Command::new("count", |context| async move {
+            let count: u64 = context.bot.state().get("count").await?.unwrap_or(0);
+            context.bot.state().set("count", &(count + 1)).await?;
+            context.send(&(count + 1).to_string()).await?;
+            Ok(())
+        }))
+        .with_task(Duration::from_secs(60), |context| async move {
+            context.send_notice(ROOM, "tick").await?;
+            Ok(())
+        })
+    }
+
+    #[tokio::test]
+    async fn test_command_with_typed_args() -> Result<()> {
+        let mut harness = RecordingHarness::new(bot(), BOT);
+
+        let event_id = harness.message(ROOM, "@alice:example.org", "!add 2 40").await?;
+        let sent = harness.sent();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].room_id, ROOM);
+        assert_eq!(sent[0].body(), Some("42"));
+        assert_eq!(sent[0].content["m.relates_to"]["m.in_reply_to"]["event_id"], event_id);
+
+        harness.clear_sent();
+        harness.message(ROOM, "@alice:example.org", "!add 2 two").await?;
+        assert_eq!(
+            harness.sent_bodies(),
+            vec!["Invalid argument <b>: expected a whole number, got \"two\"\nUsage: !add <a> <b>"]
+        );
+
+        // Plain messages, unknown commands and the bot's own messages are ignored
+        harness.clear_sent();
+        harness.message(ROOM, "@alice:example.org", "add 1 2").await?;
+        harness.message(ROOM, "@alice:example.org", "!nope").await?;
+        harness.message(ROOM, BOT, "!add 1 2").await?;
+        assert!(harness.sent().is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_power_level_check() -> Result<()> {
+        let mut harness = RecordingHarness::new(bot(), BOT);
+        harness.set_power_levels(ROOM, &[("@mod:example.org", 50)]);
+
+        harness
+            .message(ROOM, "@alice:example.org", "!kick @bob:example.org")
+            .await?;
+        assert_eq!(harness.sent_bodies(), vec!["You need power level 50 to use !kick"]);
+
+        harness.clear_sent();
+        let event_id = harness.message(ROOM, "@mod:example.org", "!kick").await?;
+        let sent = harness.sent();
+        assert_eq!(sent[0].event_type, "m.reaction");
+        assert_eq!(
+            sent[0].content["m.relates_to"],
+            json!({ "rel_type": "m.annotation", "event_id": event_id, "key": "👍" })
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_auto_join_allowlist() -> Result<()> {
+        let harness = RecordingHarness::new(bot(), BOT);
+
+        harness.invite("!a:example.org", "@alice:example.org").await?;
+        harness.invite("!b:example.org", "@mallory:example.org").await?;
+        harness.invite("!c:example.org", "@anyone:trusted.org").await?;
+        assert_eq!(harness.joined_rooms(), vec!["!a:example.org", "!c:example.org"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_state_and_threads() -> Result<()> {
+        let mut harness = RecordingHarness::new(bot(), BOT);
+
+        harness.message(ROOM, "@alice:example.org", "!count").await?;
+        let threaded = json!({
+            "msgtype": "m.text",
+            "body": "!count",
+            "m.relates_to": { "rel_type": "m.thread", "event_id": "$root" },
+        });
+        harness.event(ROOM, "@alice:example.org", threaded).await?;
+
+        let sent = harness.sent();
+        assert_eq!(harness.sent_bodies(), vec!["1", "2"]);
+        assert!(sent[0].content.get("m.relates_to").is_none());
+        assert_eq!(sent[1].content["m.relates_to"]["rel_type"], "m.thread");
+        assert_eq!(sent[1].content["m.relates_to"]["event_id"], "$root");
+
+        // The state is persisted in account data
+        let stored = harness.client().account_data("org.matryx.bot.state").await?;
+        assert_eq!(stored, Some(json!({ "count": 2 })));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_help_and_tasks() -> Result<()> {
+        let mut harness = RecordingHarness::new(bot(), BOT);
+
+        harness.message(ROOM, "@alice:example.org", "!help").await?;
+        assert_eq!(
+            harness.sent_bodies(),
+            vec!["!add <a> <b> - Add two numbers\n!count\n!help - List the commands\n!kick"]
+        );
+
+        harness.clear_sent();
+        harness.tick().await?;
+        assert_eq!(harness.sent_bodies(), vec!["tick"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,144 @@
+//! Encrypted attachments
+//!
+//! Files sent to encrypted rooms are encrypted with AES-256-CTR under a fresh
+//! key before upload. The key, IV and the SHA-256 of the ciphertext travel in
+//! the event as an [`EncryptedFile`], so the receiver can detect a tampered
+//! download before decrypting it.
+
+use aes::Aes256;
+use aes::cipher::{KeyIvInit, StreamCipher};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use matryx_entity::types::{EncryptedFile, JWK};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use vodozemac::{base64_decode, base64_encode};
+
+use super::CryptoError;
+
+/// Version of the attachment encryption scheme
+pub const ATTACHMENT_VERSION: &str = "v2";
+
+const JWK_ALGORITHM: &str = "A256CTR";
+
+type Aes256Ctr = ctr::Ctr128BE<Aes256>;
+
+/// Key material of an encrypted attachment, waiting for its upload URL
+#[derive(Clone)]
+pub struct AttachmentKey {
+    key: [u8; 32],
+    iv: [u8; 16],
+    sha256: [u8; 32],
+}
+
+impl AttachmentKey {
+    /// Describe the encrypted attachment uploaded to `url`
+    pub fn into_encrypted_file(self, url: String) -> EncryptedFile {
+        let key = JWK::new(
+            "oct".to_string(),
+            vec!["encrypt".to_string(), "decrypt".to_string()],
+            JWK_ALGORITHM.to_string(),
+            URL_SAFE_NO_PAD.encode(self.key),
+            true,
+        );
+        let hashes = HashMap::from([("sha256".to_string(), base64_encode(self.sha256))]);
+        EncryptedFile::new(url, key, base64_encode(self.iv), hashes, ATTACHMENT_VERSION.to_string())
+    }
+}
+
+impl std::fmt::Debug for AttachmentKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AttachmentKey").finish_non_exhaustive()
+    }
+}
+
+/// Encrypt an attachment under a fresh key, returning the ciphertext to upload
+pub fn encrypt_attachment(data: &[u8]) -> Result<(Vec<u8>, AttachmentKey), CryptoError> {
+    let mut key = [0u8; 32];
+    rand::rng().fill_bytes(&mut key);
+    // Only the nonce half is random, the counter half starts at zero
+    let mut iv = [0u8; 16];
+    rand::rng().fill_bytes(&mut iv[..8]);
+
+    let mut ciphertext = data.to_vec();
+    apply_keystream(&key, &iv, &mut ciphertext)?;
+    let sha256 = Sha256::digest(&ciphertext).into();
+    Ok((ciphertext, AttachmentKey { key, iv, sha256 }))
+}
+
+/// Verify and decrypt a downloaded attachment
+pub fn decrypt_attachment(ciphertext: &[u8], file: &EncryptedFile) -> Result<Vec<u8>, CryptoError> {
+    if file.v != ATTACHMENT_VERSION {
+        return Err(CryptoError::UnsupportedAlgorithm(format!("attachment version {}", file.v)));
+    }
+    if file.key.alg != JWK_ALGORITHM {
+        return Err(CryptoError::UnsupportedAlgorithm(file.key.alg.clone()));
+    }
+
+    let expected = file
+        .hashes
+        .get("sha256")
+        .ok_or_else(|| CryptoError::Decryption("Attachment has no SHA-256 hash".to_string()))?;
+    if Sha256::digest(ciphertext).as_slice() != decode(expected)?.as_slice() {
+        return Err(CryptoError::Decryption("Attachment hash mismatch".to_string()));
+    }
+
+    let key: [u8; 32] = URL_SAFE_NO_PAD
+        .decode(file.key.k.trim_end_matches('='))
+        .ok()
+        .and_then(|key| key.try_into().ok())
+        .ok_or_else(|| CryptoError::InvalidKey {
+            key: "k".to_string(),
+            message: "Attachment key must be 32 bytes of base64url".to_string(),
+        })?;
+    let iv: [u8; 16] = decode(&file.iv)?
+        .try_into()
+        .map_err(|_| CryptoError::Decryption("IV must be 16 bytes".to_string()))?;
+
+    let mut plaintext = ciphertext.to_vec();
+    apply_keystream(&key, &iv, &mut plaintext)?;
+    Ok(plaintext)
+}
+
+fn apply_keystream(key: &[u8; 32], iv: &[u8; 16], data: &mut [u8]) -> Result<(), CryptoError> {
+    let mut cipher = Aes256Ctr::new_from_slices(key, iv).map_err(|e| CryptoError::InvalidKey {
+        key: "attachment_key".to_string(),
+        message: e.to_string(),
+    })?;
+    cipher.apply_keystream(data);
+    Ok(())
+}
+
+fn decode(value: &str) -> Result<Vec<u8>, CryptoError> {
+    // Some clients pad their base64
+    base64_decode(value.trim_end_matches('=')).map_err(|e| CryptoError::Decryption(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attachment_round_trip() {
+        let (ciphertext, key) = encrypt_attachment(b"hello attachment").expect("should encrypt");
+        assert_ne!(ciphertext.as_slice(), b"hello attachment");
+
+        let file = key.into_encrypted_file("mxc://example.org/abc".to_string());
+        assert_eq!(file.key.k.len(), 43);
+        assert!(file.iv.ends_with("AAAAAAAAAA"));
+
+        let plaintext = decrypt_attachment(&ciphertext, &file).expect("should decrypt");
+        assert_eq!(plaintext, b"hello attachment");
+    }
+
+    #[test]
+    fn test_tampered_attachment_is_rejected() {
+        let (mut ciphertext, key) =
+            encrypt_attachment(b"hello attachment").expect("should encrypt");
+        let file = key.into_encrypted_file("mxc://example.org/abc".to_string());
+
+        ciphertext[0] ^= 1;
+        assert!(matches!(decrypt_attachment(&ciphertext, &file), Err(CryptoError::Decryption(_))));
+    }
+}
@@ -0,0 +1,174 @@
+//! Server-side key backup
+//!
+//! Implements `m.megolm_backup.v1.curve25519-aes-sha2`: room keys are
+//! encrypted to the backup's public Curve25519 key, so any device can upload
+//! them while only holders of the private key, kept in secret storage, can
+//! restore them.
+
+use matryx_entity::types::{BackedUpSessionData, EncryptedData, KeyBackupData, SessionData};
+use vodozemac::pk_encryption::{Message, PkDecryption, PkEncryption};
+use vodozemac::{Curve25519PublicKey, Curve25519SecretKey, base64_decode, base64_encode};
+
+use super::CryptoError;
+
+/// Key backup algorithm identifier
+pub const MEGOLM_BACKUP_V1_ALGORITHM: &str = "m.megolm_backup.v1.curve25519-aes-sha2";
+
+/// Public key room keys are encrypted to
+#[derive(Debug, Clone, Copy)]
+pub struct BackupKey {
+    public_key: Curve25519PublicKey,
+}
+
+impl BackupKey {
+    pub fn from_base64(public_key: &str) -> Result<Self, CryptoError> {
+        let public_key =
+            Curve25519PublicKey::from_base64(public_key).map_err(|e| CryptoError::InvalidKey {
+                key: "backup public_key".to_string(),
+                message: e.to_string(),
+            })?;
+        Ok(Self { public_key })
+    }
+
+    /// Unpadded base64 public key, as in the backup's `auth_data`
+    pub fn to_base64(&self) -> String {
+        self.public_key.to_base64()
+    }
+
+    /// Encrypt an exported room key for upload
+    pub fn encrypt_session(
+        &self,
+        session: &BackedUpSessionData,
+        first_message_index: u32,
+        is_verified: bool,
+    ) -> Result<KeyBackupData, CryptoError> {
+        let plaintext = serde_json::to_vec(session)?;
+        let message = PkEncryption::from_key(self.public_key).encrypt(&plaintext);
+
+        Ok(KeyBackupData::new(
+            i64::from(first_message_index),
+            session.forwarding_curve25519_key_chain.len() as i64,
+            is_verified,
+            SessionData::new(EncryptedData::new(
+                base64_encode(&message.ciphertext),
+                base64_encode(&message.mac),
+                message.ephemeral_key.to_base64(),
+            )),
+        ))
+    }
+}
+
+impl PartialEq for BackupKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.public_key.as_bytes() == other.public_key.as_bytes()
+    }
+}
+
+impl Eq for BackupKey {}
+
+/// Private key of a backup, stored in secret storage as `m.megolm_backup.v1`
+pub struct BackupDecryptionKey {
+    inner: PkDecryption,
+}
+
+impl std::fmt::Debug for BackupDecryptionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BackupDecryptionKey")
+            .field("public_key", &self.inner.public_key().to_base64())
+            .finish()
+    }
+}
+
+impl Default for BackupDecryptionKey {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BackupDecryptionKey {
+    /// Generate a key for a new backup version
+    pub fn new() -> Self {
+        Self { inner: PkDecryption::new() }
+    }
+
+    /// Parse the unpadded base64 private key held in secret storage
+    pub fn from_base64(private_key: &str) -> Result<Self, CryptoError> {
+        let invalid = |message: String| CryptoError::InvalidKey {
+            key: "backup private key".to_string(),
+            message,
+        };
+        let bytes: [u8; 32] = base64_decode(private_key)
+            .map_err(|e| invalid(e.to_string()))?
+            .try_into()
+            .map_err(|_| invalid("private key must be 32 bytes".to_string()))?;
+        Ok(Self {
+            inner: PkDecryption::from_key(Curve25519SecretKey::from_slice(&bytes)),
+        })
+    }
+
+    /// Unpadded base64 private key, for secret storage
+    pub fn to_base64(&self) -> String {
+        base64_encode(self.inner.secret_key().to_bytes().as_slice())
+    }
+
+    pub fn backup_key(&self) -> BackupKey {
+        BackupKey { public_key: self.inner.public_key() }
+    }
+
+    /// Decrypt a room key downloaded from the backup
+    pub fn decrypt_session(
+        &self,
+        key_data: &KeyBackupData,
+    ) -> Result<BackedUpSessionData, CryptoError> {
+        let encrypted = &key_data.session_data.encrypted_data;
+        let message =
+            Message::from_base64(&encrypted.ciphertext, &encrypted.mac, &encrypted.ephemeral)
+                .map_err(|e| CryptoError::Decryption(e.to_string()))?;
+        let plaintext = self
+            .inner
+            .decrypt(&message)
+            .map_err(|e| CryptoError::Decryption(e.to_string()))?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_session_round_trip() {
+        let decryption_key = BackupDecryptionKey::new();
+        let restored = BackupDecryptionKey::from_base64(&decryption_key.to_base64())
+            .expect("private key should parse");
+        assert_eq!(restored.backup_key(), decryption_key.backup_key());
+
+        let mut claimed = HashMap::new();
+        claimed.insert("ed25519".to_string(), "claimed".to_string());
+        let session = BackedUpSessionData::new(
+            crate::crypto::MEGOLM_V1_ALGORITHM.to_string(),
+            Vec::new(),
+            claimed,
+            "sender".to_string(),
+            "session key".to_string(),
+        );
+
+        let key_data = decryption_key
+            .backup_key()
+            .encrypt_session(&session, 3, true)
+            .expect("session should encrypt");
+        assert_eq!(key_data.first_message_index, 3);
+
+        let wire = serde_json::to_value(&key_data).expect("key data should serialize");
+        assert!(wire["session_data"]["ephemeral"].is_string());
+        let key_data: KeyBackupData =
+            serde_json::from_value(wire).expect("key data should deserialize");
+
+        let decrypted = restored.decrypt_session(&key_data).expect("session should decrypt");
+        assert_eq!(decrypted.session_key, "session key");
+        assert_eq!(decrypted.sender_claimed_keys["ed25519"], "claimed");
+
+        assert!(BackupDecryptionKey::new().decrypt_session(&key_data).is_err());
+    }
+}
@@ -0,0 +1,178 @@
+//! Cross-signing keys
+//!
+//! The master key identifies a user; it signs the self-signing key, which
+//! signs the user's own devices, and the user-signing key, which signs the
+//! master keys of other users once they have been verified.
+
+use matryx_entity::{CrossSigningKey, DeviceKeys};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use vodozemac::Ed25519SecretKey;
+
+use super::CryptoError;
+use super::signing::{add_signature, signing_payload};
+
+/// Public halves of the cross-signing keys, as uploaded to `/keys/device_signing/upload`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrossSigningPublicKeys {
+    pub master_key: CrossSigningKey,
+    pub self_signing_key: CrossSigningKey,
+    pub user_signing_key: CrossSigningKey,
+}
+
+/// Body of `/keys/signatures/upload`: signed objects by user ID and key or device ID
+pub type SignatureUploadRequest = HashMap<String, HashMap<String, Value>>;
+
+/// Everything to upload after creating cross-signing keys
+#[derive(Debug, Clone)]
+pub struct CrossSigningBootstrap {
+    /// Body of `/keys/device_signing/upload`, without `auth`
+    pub public_keys: CrossSigningPublicKeys,
+    /// Self-signing signature of the current device
+    pub signatures: SignatureUploadRequest,
+}
+
+/// Private cross-signing keys of the logged-in user
+pub struct CrossSigningAccount {
+    user_id: String,
+    master: Ed25519SecretKey,
+    self_signing: Ed25519SecretKey,
+    user_signing: Ed25519SecretKey,
+}
+
+impl std::fmt::Debug for CrossSigningAccount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CrossSigningAccount")
+            .field("user_id", &self.user_id)
+            .field("master_key", &self.master_key())
+            .finish()
+    }
+}
+
+/// Serializable form of a [`CrossSigningAccount`]
+///
+/// Contains private key material; callers must store it encrypted.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CrossSigningAccountPickle {
+    pub user_id: String,
+    pub master_key: String,
+    pub self_signing_key: String,
+    pub user_signing_key: String,
+}
+
+impl CrossSigningAccount {
+    /// Generate a fresh set of cross-signing keys
+    pub fn new(user_id: &str) -> Self {
+        Self {
+            user_id: user_id.to_string(),
+            master: Ed25519SecretKey::new(),
+            self_signing: Ed25519SecretKey::new(),
+            user_signing: Ed25519SecretKey::new(),
+        }
+    }
+
+    pub fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    /// Unpadded base64 public master key
+    pub fn master_key(&self) -> String {
+        self.master.public_key().to_base64()
+    }
+
+    pub fn self_signing_key(&self) -> String {
+        self.self_signing.public_key().to_base64()
+    }
+
+    pub fn user_signing_key(&self) -> String {
+        self.user_signing.public_key().to_base64()
+    }
+
+    /// Public keys for upload; the subkeys are signed by the master key
+    pub fn public_keys(&self) -> Result<CrossSigningPublicKeys, CryptoError> {
+        let master_key = self.public_key(&self.master, "master");
+
+        let mut self_signing_key =
+            serde_json::to_value(self.public_key(&self.self_signing, "self_signing"))?;
+        self.sign_with(&self.master, &mut self_signing_key)?;
+
+        let mut user_signing_key =
+            serde_json::to_value(self.public_key(&self.user_signing, "user_signing"))?;
+        self.sign_with(&self.master, &mut user_signing_key)?;
+
+        Ok(CrossSigningPublicKeys {
+            master_key,
+            self_signing_key: serde_json::from_value(self_signing_key)?,
+            user_signing_key: serde_json::from_value(user_signing_key)?,
+        })
+    }
+
+    /// Sign one of our own devices with the self-signing key
+    pub fn sign_device(&self, device_keys: &DeviceKeys) -> Result<Value, CryptoError> {
+        let mut value = serde_json::to_value(device_keys)?;
+        strip_foreign_signatures(&mut value);
+        self.sign_with(&self.self_signing, &mut value)?;
+        Ok(value)
+    }
+
+    /// Sign another user's master key with the user-signing key
+    pub fn sign_user(&self, master_key: &Value) -> Result<Value, CryptoError> {
+        let mut value = master_key.clone();
+        strip_foreign_signatures(&mut value);
+        self.sign_with(&self.user_signing, &mut value)?;
+        Ok(value)
+    }
+
+    /// Sign an object of ours, such as a key backup's `auth_data`, with the master key
+    pub fn sign_with_master(&self, value: &mut Value) -> Result<(), CryptoError> {
+        self.sign_with(&self.master, value)
+    }
+
+    pub fn pickle(&self) -> CrossSigningAccountPickle {
+        CrossSigningAccountPickle {
+            user_id: self.user_id.clone(),
+            master_key: self.master.to_base64(),
+            self_signing_key: self.self_signing.to_base64(),
+            user_signing_key: self.user_signing.to_base64(),
+        }
+    }
+
+    pub fn from_pickle(pickle: &CrossSigningAccountPickle) -> Result<Self, CryptoError> {
+        let parse = |name: &str, key: &str| {
+            Ed25519SecretKey::from_base64(key).map_err(|e| CryptoError::InvalidKey {
+                key: name.to_string(),
+                message: e.to_string(),
+            })
+        };
+
+        Ok(Self {
+            user_id: pickle.user_id.clone(),
+            master: parse("master", &pickle.master_key)?,
+            self_signing: parse("self_signing", &pickle.self_signing_key)?,
+            user_signing: parse("user_signing", &pickle.user_signing_key)?,
+        })
+    }
+
+    fn public_key(&self, key: &Ed25519SecretKey, usage: &str) -> CrossSigningKey {
+        let public_key = key.public_key().to_base64();
+        let mut keys = HashMap::new();
+        keys.insert(format!("ed25519:{}", public_key), public_key);
+        CrossSigningKey::new(keys, None, vec![usage.to_string()], self.user_id.clone())
+    }
+
+    fn sign_with(&self, key: &Ed25519SecretKey, value: &mut Value) -> Result<(), CryptoError> {
+        let signature = key.sign(signing_payload(value)?.as_bytes());
+        let key_id = format!("ed25519:{}", key.public_key().to_base64());
+        add_signature(value, &self.user_id, &key_id, &signature);
+        Ok(())
+    }
+}
+
+/// `/keys/signatures/upload` only needs the new signature, so drop the rest
+fn strip_foreign_signatures(value: &mut Value) {
+    if let Some(object) = value.as_object_mut() {
+        object.remove("signatures");
+        object.remove("unsigned");
+    }
+}
@@ -0,0 +1,257 @@
+//! Device list tracking for end-to-end encryption
+//!
+//! Keeps the verified device keys of every user we share an encrypted room
+//! with, and remembers which users need a fresh `/keys/query` because sync
+//! reported their device list as changed.
+
+use matryx_entity::DeviceKeys;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use tracing::warn;
+
+use super::signing::verify_json;
+use super::{CryptoError, MEGOLM_V1_ALGORITHM, OLM_V1_ALGORITHM};
+use crate::device::QueryKeysResponse;
+use crate::sync::DeviceListUpdates;
+
+/// A remote device whose keys passed self-signature verification
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackedDevice {
+    pub user_id: String,
+    pub device_id: String,
+    pub curve25519_key: String,
+    pub ed25519_key: String,
+    pub algorithms: Vec<String>,
+    pub display_name: Option<String>,
+    /// The device keys as published, for cross-signing
+    pub device_keys: DeviceKeys,
+}
+
+impl TrackedDevice {
+    /// Build a tracked device from uploaded device keys, verifying the self-signature
+    pub fn from_device_keys(keys: &DeviceKeys) -> Result<Self, CryptoError> {
+        let curve25519_key = device_key(keys, "curve25519")?;
+        let ed25519_key = device_key(keys, "ed25519")?;
+
+        let value = serde_json::to_value(keys)?;
+        verify_json(&value, &keys.user_id, &format!("ed25519:{}", keys.device_id), &ed25519_key)
+            .map_err(|message| CryptoError::InvalidSignature {
+                user_id: keys.user_id.clone(),
+                device_id: keys.device_id.clone(),
+                message,
+            })?;
+
+        Ok(Self {
+            user_id: keys.user_id.clone(),
+            device_id: keys.device_id.clone(),
+            curve25519_key,
+            ed25519_key,
+            algorithms: keys.algorithms.clone(),
+            display_name: keys.unsigned.as_ref().and_then(|u| u.device_display_name.clone()),
+            device_keys: keys.clone(),
+        })
+    }
+
+    /// Whether the device advertises support for Olm and Megolm
+    pub fn supports_encryption(&self) -> bool {
+        self.algorithms.iter().any(|a| a == OLM_V1_ALGORITHM)
+            && self.algorithms.iter().any(|a| a == MEGOLM_V1_ALGORITHM)
+    }
+}
+
+/// Tracks device lists of users sharing encrypted rooms with us
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeviceTracker {
+    /// Users whose device lists we keep up to date
+    tracked_users: HashSet<String>,
+    /// Tracked users that need a `/keys/query`
+    outdated_users: HashSet<String>,
+    /// Verified devices by user ID and device ID
+    devices: HashMap<String, HashMap<String, TrackedDevice>>,
+    /// Published cross-signing master keys by user ID
+    #[serde(default)]
+    master_keys: HashMap<String, Value>,
+    /// Devices verified interactively, as `(user_id, device_id)`
+    #[serde(default)]
+    verified_devices: HashSet<(String, String)>,
+    /// Users whose master key was verified interactively
+    #[serde(default)]
+    verified_users: HashSet<String>,
+}
+
+impl DeviceTracker {
+    /// Start tracking users, marking previously unknown ones as outdated
+    pub fn track_users<I>(&mut self, users: I)
+    where
+        I: IntoIterator<Item = String>,
+    {
+        for user_id in users {
+            if self.tracked_users.insert(user_id.clone()) {
+                self.outdated_users.insert(user_id);
+            }
+        }
+    }
+
+    /// Whether the device list of a user is tracked
+    pub fn is_tracked(&self, user_id: &str) -> bool {
+        self.tracked_users.contains(user_id)
+    }
+
+    /// Apply `device_lists` from a sync response
+    ///
+    /// Returns the users that stopped sharing encrypted rooms with us so that
+    /// outbound group sessions shared with them can be rotated.
+    pub fn receive_device_list_updates(&mut self, updates: &DeviceListUpdates) -> Vec<String> {
+        for user_id in &updates.changed {
+            if self.tracked_users.contains(user_id) {
+                self.outdated_users.insert(user_id.clone());
+            }
+        }
+
+        let mut left = Vec::new();
+        for user_id in &updates.left {
+            if self.tracked_users.remove(user_id) {
+                self.outdated_users.remove(user_id);
+                self.devices.remove(user_id);
+                left.push(user_id.clone());
+            }
+        }
+        left
+    }
+
+    /// Users with outdated device lists, in the shape of a `/keys/query` body
+    pub fn users_for_key_query(&self) -> Option<HashMap<String, Vec<String>>> {
+        if self.outdated_users.is_empty() {
+            return None;
+        }
+
+        Some(
+            self.outdated_users
+                .iter()
+                .map(|user_id| (user_id.clone(), Vec::new()))
+                .collect(),
+        )
+    }
+
+    /// Store the devices returned by `/keys/query`
+    ///
+    /// Devices with invalid signatures, mismatched IDs or an Ed25519 key that
+    /// differs from the one we saw before are dropped.
+    pub fn receive_keys_query_response(&mut self, response: &QueryKeysResponse) {
+        for (user_id, devices) in &response.device_keys {
+            let known = self.devices.remove(user_id).unwrap_or_default();
+            let mut updated = HashMap::new();
+
+            for (device_id, keys) in devices {
+                if &keys.user_id != user_id || &keys.device_id != device_id {
+                    warn!("Ignoring device keys with mismatched IDs for {}/{}", user_id, device_id);
+                    continue;
+                }
+
+                let device = match TrackedDevice::from_device_keys(keys) {
+                    Ok(device) => device,
+                    Err(e) => {
+                        warn!("Ignoring device keys for {}/{}: {}", user_id, device_id, e);
+                        continue;
+                    },
+                };
+
+                if let Some(previous) = known.get(device_id)
+                    && previous.ed25519_key != device.ed25519_key
+                {
+                    warn!("Ed25519 key of {}/{} changed, keeping the old one", user_id, device_id);
+                    updated.insert(device_id.clone(), previous.clone());
+                    continue;
+                }
+
+                updated.insert(device_id.clone(), device);
+            }
+
+            self.devices.insert(user_id.clone(), updated);
+            self.outdated_users.remove(user_id);
+        }
+
+        for (user_id, master_key) in response.master_keys.iter().flatten() {
+            if master_key.get("user_id").and_then(Value::as_str) != Some(user_id.as_str()) {
+                warn!("Ignoring master key with mismatched user ID for {}", user_id);
+                continue;
+            }
+
+            let changed = self
+                .master_key(user_id)
+                .is_some_and(|previous| Some(previous) != cross_signing_key(master_key));
+            if changed {
+                warn!("Master key of {} changed, dropping its verification", user_id);
+                self.verified_users.remove(user_id);
+            }
+            self.master_keys.insert(user_id.clone(), master_key.clone());
+        }
+    }
+
+    /// Get a verified device
+    pub fn get_device(&self, user_id: &str, device_id: &str) -> Option<&TrackedDevice> {
+        self.devices.get(user_id).and_then(|devices| devices.get(device_id))
+    }
+
+    /// Get all verified devices of a user
+    pub fn user_devices(&self, user_id: &str) -> impl Iterator<Item = &TrackedDevice> {
+        self.devices.get(user_id).into_iter().flat_map(|devices| devices.values())
+    }
+
+    /// Public Ed25519 master key of a user, if published
+    pub fn master_key(&self, user_id: &str) -> Option<String> {
+        self.master_keys.get(user_id).and_then(cross_signing_key)
+    }
+
+    /// Record a user's master key without a `/keys/query` round trip
+    pub fn set_master_key(&mut self, user_id: &str, master_key: Value) {
+        self.master_keys.insert(user_id.to_string(), master_key);
+    }
+
+    /// The published master key object of a user, for signing
+    pub fn master_key_object(&self, user_id: &str) -> Option<&Value> {
+        self.master_keys.get(user_id)
+    }
+
+    pub fn mark_device_verified(&mut self, user_id: &str, device_id: &str) {
+        self.verified_devices.insert((user_id.to_string(), device_id.to_string()));
+    }
+
+    pub fn is_device_verified(&self, user_id: &str, device_id: &str) -> bool {
+        self.verified_devices
+            .contains(&(user_id.to_string(), device_id.to_string()))
+    }
+
+    pub fn mark_user_verified(&mut self, user_id: &str) {
+        self.verified_users.insert(user_id.to_string());
+    }
+
+    pub fn is_user_verified(&self, user_id: &str) -> bool {
+        self.verified_users.contains(user_id)
+    }
+
+    /// Find the device owning a Curve25519 identity key
+    pub fn device_by_curve25519_key(&self, user_id: &str, key: &str) -> Option<&TrackedDevice> {
+        self.user_devices(user_id).find(|device| device.curve25519_key == key)
+    }
+}
+
+/// Read `<algorithm>:<device_id>` from device keys
+fn device_key(keys: &DeviceKeys, algorithm: &str) -> Result<String, CryptoError> {
+    let key_id = format!("{}:{}", algorithm, keys.device_id);
+    keys.keys.get(&key_id).cloned().ok_or_else(|| CryptoError::InvalidKey {
+        key: key_id,
+        message: "missing from device keys".to_string(),
+    })
+}
+
+/// The single Ed25519 key of a cross-signing key object
+fn cross_signing_key(value: &Value) -> Option<String> {
+    value
+        .get("keys")
+        .and_then(Value::as_object)
+        .and_then(|keys| keys.values().next())
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
@@ -0,0 +1,401 @@
+//! Megolm group sessions
+//!
+//! Outbound sessions encrypt our own room messages and are rotated according
+//! to the room's `m.room.encryption` settings; inbound sessions are the room
+//! keys other devices shared with us over Olm.
+
+use chrono::Utc;
+use matryx_entity::types::RoomEncryptionContent;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use vodozemac::megolm::{
+    ExportedSessionKey, GroupSession, GroupSessionPickle, InboundGroupSession,
+    InboundGroupSessionPickle, MegolmMessage, SessionConfig, SessionKey,
+};
+
+use super::CryptoError;
+
+/// Our Megolm session for one room
+pub struct OutboundGroupSession {
+    pub room_id: String,
+    session: GroupSession,
+    created_at_ms: i64,
+    message_count: u64,
+    rotation_period_ms: u64,
+    rotation_period_msgs: u64,
+    /// Devices the session key was sent to, as `(user_id, device_id)`
+    shared_with: HashSet<(String, String)>,
+}
+
+impl std::fmt::Debug for OutboundGroupSession {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OutboundGroupSession")
+            .field("room_id", &self.room_id)
+            .field("session_id", &self.session.session_id())
+            .field("message_count", &self.message_count)
+            .field("shared_with", &self.shared_with.len())
+            .finish()
+    }
+}
+
+impl OutboundGroupSession {
+    /// Create a fresh session using the room's rotation settings
+    pub fn new(room_id: &str, settings: &RoomEncryptionContent) -> Self {
+        Self {
+            room_id: room_id.to_string(),
+            session: GroupSession::new(SessionConfig::version_1()),
+            created_at_ms: Utc::now().timestamp_millis(),
+            message_count: 0,
+            rotation_period_ms: settings.rotation_period_ms(),
+            rotation_period_msgs: settings.rotation_period_msgs(),
+            shared_with: HashSet::new(),
+        }
+    }
+
+    pub fn session_id(&self) -> String {
+        self.session.session_id()
+    }
+
+    /// Current session key, shared with recipients as `m.room_key`
+    pub fn session_key(&self) -> String {
+        self.session.session_key().to_base64()
+    }
+
+    /// Whether the session reached its age or message limit
+    pub fn is_expired(&self) -> bool {
+        let age_ms = Utc::now().timestamp_millis().saturating_sub(self.created_at_ms);
+        self.message_count >= self.rotation_period_msgs
+            || u64::try_from(age_ms).unwrap_or(0) >= self.rotation_period_ms
+    }
+
+    /// Whether the session was shared with a device
+    pub fn is_shared_with(&self, user_id: &str, device_id: &str) -> bool {
+        self.shared_with.contains(&(user_id.to_string(), device_id.to_string()))
+    }
+
+    /// Whether the session was shared with a device outside `recipients`
+    ///
+    /// A departed member or deleted device must not be able to read future
+    /// messages, so such a session has to be rotated.
+    pub fn shared_outside(&self, recipients: &HashSet<(String, String)>) -> bool {
+        self.shared_with.iter().any(|device| !recipients.contains(device))
+    }
+
+    /// Whether the session was shared with any device of a user
+    pub fn shared_with_user(&self, user_id: &str) -> bool {
+        self.shared_with.iter().any(|(user, _)| user == user_id)
+    }
+
+    pub fn mark_shared_with(&mut self, user_id: &str, device_id: &str) {
+        self.shared_with.insert((user_id.to_string(), device_id.to_string()));
+    }
+
+    /// Encrypt a serialized room event payload
+    pub fn encrypt(&mut self, plaintext: &str) -> String {
+        self.message_count += 1;
+        self.session.encrypt(plaintext).to_base64()
+    }
+
+    pub fn pickle(&self) -> OutboundGroupSessionPickle {
+        OutboundGroupSessionPickle {
+            room_id: self.room_id.clone(),
+            session: self.session.pickle(),
+            created_at_ms: self.created_at_ms,
+            message_count: self.message_count,
+            rotation_period_ms: self.rotation_period_ms,
+            rotation_period_msgs: self.rotation_period_msgs,
+            shared_with: self.shared_with.iter().cloned().collect(),
+        }
+    }
+
+    pub fn from_pickle(pickle: OutboundGroupSessionPickle) -> Self {
+        Self {
+            room_id: pickle.room_id,
+            session: GroupSession::from_pickle(pickle.session),
+            created_at_ms: pickle.created_at_ms,
+            message_count: pickle.message_count,
+            rotation_period_ms: pickle.rotation_period_ms,
+            rotation_period_msgs: pickle.rotation_period_msgs,
+            shared_with: pickle.shared_with.into_iter().collect(),
+        }
+    }
+}
+
+/// Serializable form of an [`OutboundGroupSession`]
+#[derive(Serialize, Deserialize)]
+pub struct OutboundGroupSessionPickle {
+    room_id: String,
+    session: GroupSessionPickle,
+    created_at_ms: i64,
+    message_count: u64,
+    rotation_period_ms: u64,
+    rotation_period_msgs: u64,
+    shared_with: Vec<(String, String)>,
+}
+
+/// A room key received from another device
+struct StoredInboundSession {
+    session: InboundGroupSession,
+    /// Curve25519 key of the Olm session the key arrived over
+    sender_key: String,
+    /// Ed25519 key the sender claimed in the Olm payload
+    sender_claimed_ed25519_key: String,
+    /// Event ID seen at each message index, for replay detection
+    seen_indices: HashMap<u32, String>,
+    /// Whether the session is in the current key backup
+    backed_up: bool,
+}
+
+/// Result of decrypting a Megolm message
+#[derive(Debug, Clone)]
+pub struct GroupDecryption {
+    pub plaintext: String,
+    pub message_index: u32,
+    pub sender_key: String,
+    pub sender_claimed_ed25519_key: String,
+}
+
+/// An inbound session exported at its first known index
+#[derive(Debug, Clone)]
+pub struct ExportedInboundSession {
+    pub room_id: String,
+    pub session_id: String,
+    /// Unpadded base64 of the exported session key
+    pub session_key: String,
+    pub first_known_index: u32,
+    pub sender_key: String,
+    pub sender_claimed_ed25519_key: String,
+}
+
+/// Inbound Megolm sessions keyed by room ID and session ID
+#[derive(Default)]
+pub struct InboundGroupSessionStore {
+    sessions: HashMap<(String, String), StoredInboundSession>,
+}
+
+impl std::fmt::Debug for InboundGroupSessionStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InboundGroupSessionStore")
+            .field("sessions", &self.sessions.len())
+            .finish()
+    }
+}
+
+impl InboundGroupSessionStore {
+    /// Add a room key, keeping an existing session that knows earlier indices
+    pub fn add(
+        &mut self,
+        room_id: &str,
+        session_key: &str,
+        sender_key: &str,
+        sender_claimed_ed25519_key: &str,
+    ) -> Result<String, CryptoError> {
+        let key = SessionKey::from_base64(session_key).map_err(|e| CryptoError::InvalidKey {
+            key: "session_key".to_string(),
+            message: e.to_string(),
+        })?;
+        let session = InboundGroupSession::new(&key, SessionConfig::version_1());
+        let session_id = session.session_id();
+
+        let map_key = (room_id.to_string(), session_id.clone());
+        if let Some(existing) = self.sessions.get(&map_key)
+            && existing.session.first_known_index() <= session.first_known_index()
+        {
+            return Ok(session_id);
+        }
+
+        self.sessions.insert(
+            map_key,
+            StoredInboundSession {
+                session,
+                sender_key: sender_key.to_string(),
+                sender_claimed_ed25519_key: sender_claimed_ed25519_key.to_string(),
+                seen_indices: HashMap::new(),
+                backed_up: false,
+            },
+        );
+        Ok(session_id)
+    }
+
+    /// Add an exported room key, e.g. from a key backup
+    ///
+    /// Returns whether the key was stored; an existing session that knows
+    /// earlier indices is kept. Imported sessions count as backed up.
+    pub fn import(
+        &mut self,
+        room_id: &str,
+        session_id: &str,
+        exported_key: &str,
+        sender_key: &str,
+        sender_claimed_ed25519_key: &str,
+    ) -> Result<bool, CryptoError> {
+        let key =
+            ExportedSessionKey::from_base64(exported_key).map_err(|e| CryptoError::InvalidKey {
+                key: "session_key".to_string(),
+                message: e.to_string(),
+            })?;
+        let session = InboundGroupSession::import(&key, SessionConfig::version_1());
+        if session.session_id() != session_id {
+            return Err(CryptoError::PayloadMismatch("session_id".to_string()));
+        }
+
+        let map_key = (room_id.to_string(), session_id.to_string());
+        if let Some(existing) = self.sessions.get(&map_key)
+            && existing.session.first_known_index() <= session.first_known_index()
+        {
+            return Ok(false);
+        }
+
+        self.sessions.insert(
+            map_key,
+            StoredInboundSession {
+                session,
+                sender_key: sender_key.to_string(),
+                sender_claimed_ed25519_key: sender_claimed_ed25519_key.to_string(),
+                seen_indices: HashMap::new(),
+                backed_up: true,
+            },
+        );
+        Ok(true)
+    }
+
+    /// Up to `limit` sessions missing from the key backup
+    pub fn sessions_to_back_up(&self, limit: usize) -> Vec<ExportedInboundSession> {
+        self.sessions
+            .iter()
+            .filter(|(_, stored)| !stored.backed_up)
+            .take(limit)
+            .map(|((room_id, session_id), stored)| ExportedInboundSession {
+                room_id: room_id.clone(),
+                session_id: session_id.clone(),
+                session_key: stored.session.export_at_first_known_index().to_base64(),
+                first_known_index: stored.session.first_known_index(),
+                sender_key: stored.sender_key.clone(),
+                sender_claimed_ed25519_key: stored.sender_claimed_ed25519_key.clone(),
+            })
+            .collect()
+    }
+
+    /// Number of sessions missing from the key backup
+    pub fn pending_backup_count(&self) -> usize {
+        self.sessions.values().filter(|stored| !stored.backed_up).count()
+    }
+
+    pub fn mark_backed_up(&mut self, room_id: &str, session_id: &str) {
+        if let Some(stored) = self.sessions.get_mut(&(room_id.to_string(), session_id.to_string()))
+        {
+            stored.backed_up = true;
+        }
+    }
+
+    /// Forget backup progress, e.g. when a new backup version is used
+    pub fn reset_backup_state(&mut self) {
+        for stored in self.sessions.values_mut() {
+            stored.backed_up = false;
+        }
+    }
+
+    pub fn contains(&self, room_id: &str, session_id: &str) -> bool {
+        self.sessions.contains_key(&(room_id.to_string(), session_id.to_string()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    /// Decrypt a Megolm ciphertext, rejecting replayed message indices
+    pub fn decrypt(
+        &mut self,
+        room_id: &str,
+        session_id: &str,
+        event_id: &str,
+        ciphertext: &str,
+    ) -> Result<GroupDecryption, CryptoError> {
+        let stored = self
+            .sessions
+            .get_mut(&(room_id.to_string(), session_id.to_string()))
+            .ok_or_else(|| CryptoError::MissingMegolmSession {
+                room_id: room_id.to_string(),
+                session_id: session_id.to_string(),
+            })?;
+
+        let message = MegolmMessage::from_base64(ciphertext)
+            .map_err(|e| CryptoError::Decryption(e.to_string()))?;
+        let decrypted = stored
+            .session
+            .decrypt(&message)
+            .map_err(|e| CryptoError::Decryption(e.to_string()))?;
+
+        match stored.seen_indices.get(&decrypted.message_index) {
+            Some(seen) if seen != event_id => {
+                return Err(CryptoError::ReplayedMessage {
+                    session_id: session_id.to_string(),
+                    index: decrypted.message_index,
+                });
+            },
+            Some(_) => {},
+            None => {
+                stored.seen_indices.insert(decrypted.message_index, event_id.to_string());
+            },
+        }
+
+        let plaintext = String::from_utf8(decrypted.plaintext)
+            .map_err(|e| CryptoError::Decryption(e.to_string()))?;
+
+        Ok(GroupDecryption {
+            plaintext,
+            message_index: decrypted.message_index,
+            sender_key: stored.sender_key.clone(),
+            sender_claimed_ed25519_key: stored.sender_claimed_ed25519_key.clone(),
+        })
+    }
+
+    pub fn pickle(&self) -> Vec<InboundGroupSessionEntryPickle> {
+        self.sessions
+            .iter()
+            .map(|((room_id, _), stored)| InboundGroupSessionEntryPickle {
+                room_id: room_id.clone(),
+                session: stored.session.pickle(),
+                sender_key: stored.sender_key.clone(),
+                sender_claimed_ed25519_key: stored.sender_claimed_ed25519_key.clone(),
+                seen_indices: stored.seen_indices.clone(),
+                backed_up: stored.backed_up,
+            })
+            .collect()
+    }
+
+    pub fn from_pickle(entries: Vec<InboundGroupSessionEntryPickle>) -> Self {
+        let sessions = entries
+            .into_iter()
+            .map(|entry| {
+                let session = InboundGroupSession::from_pickle(entry.session);
+                let key = (entry.room_id, session.session_id());
+                let stored = StoredInboundSession {
+                    session,
+                    sender_key: entry.sender_key,
+                    sender_claimed_ed25519_key: entry.sender_claimed_ed25519_key,
+                    seen_indices: entry.seen_indices,
+                    backed_up: entry.backed_up,
+                };
+                (key, stored)
+            })
+            .collect();
+        Self { sessions }
+    }
+}
+
+/// Serializable form of one inbound group session
+#[derive(Serialize, Deserialize)]
+pub struct InboundGroupSessionEntryPickle {
+    room_id: String,
+    session: InboundGroupSessionPickle,
+    sender_key: String,
+    sender_claimed_ed25519_key: String,
+    seen_indices: HashMap<u32, String>,
+    #[serde(default)]
+    backed_up: bool,
+}
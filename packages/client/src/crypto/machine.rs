@@ -0,0 +1,1325 @@
+//! The Olm/Megolm state machine
+//!
+//! [`OlmMachine`] owns the device's Olm account, its Olm sessions with other
+//! devices and all Megolm sessions. It never talks to the homeserver itself:
+//! every method either returns the payload of a request to send or consumes a
+//! response or sync section.
+
+use matryx_entity::CipherText;
+use matryx_entity::DeviceKeys;
+use matryx_entity::types::{
+    BackedUpSessionData, BackupAuthData, EncryptedCiphertext, KeyBackupData, RoomEncryptedContent,
+    RoomEncryptionContent, RoomKeyBackup, RoomKeysPutRequest,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tracing::{debug, warn};
+use vodozemac::olm::{Account, AccountPickle, OlmMessage, Session, SessionConfig, SessionPickle};
+use vodozemac::{Curve25519PublicKey, base64_decode, base64_encode};
+
+use super::backup::{BackupDecryptionKey, BackupKey};
+use super::cross_signing::{
+    CrossSigningAccount, CrossSigningAccountPickle, CrossSigningBootstrap, SignatureUploadRequest,
+};
+use super::device_tracker::DeviceTracker;
+use super::group_sessions::{
+    InboundGroupSessionEntryPickle, InboundGroupSessionStore, OutboundGroupSession,
+    OutboundGroupSessionPickle,
+};
+use super::signing::{add_signature, signing_payload, verify_json};
+use super::{CryptoError, MEGOLM_V1_ALGORITHM, OLM_V1_ALGORITHM, SIGNED_CURVE25519};
+use crate::device::{ClaimKeysResponse, QueryKeysResponse, UploadKeysResponse};
+use crate::sync::DeviceListUpdates;
+
+/// Body of a `/keys/upload` request
+#[derive(Debug, Clone, Serialize)]
+pub struct KeysUploadRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_keys: Option<DeviceKeys>,
+    pub one_time_keys: HashMap<String, Value>,
+}
+
+/// Messages to send with `/sendToDevice`
+#[derive(Debug, Clone)]
+pub struct ToDeviceRequest {
+    pub event_type: String,
+    /// Message content by user ID and device ID
+    pub messages: HashMap<String, HashMap<String, Value>>,
+}
+
+/// A to-device event after Olm decryption
+#[derive(Debug, Clone)]
+pub struct DecryptedToDeviceEvent {
+    pub sender: String,
+    pub event_type: String,
+    pub content: Value,
+    /// Curve25519 key of the sending device; `None` for plaintext events
+    pub sender_key: Option<String>,
+    /// Ed25519 key the sender claimed inside the Olm payload
+    pub sender_claimed_ed25519_key: Option<String>,
+}
+
+/// A room event after Megolm decryption
+#[derive(Debug, Clone)]
+pub struct DecryptedRoomEvent {
+    pub event_type: String,
+    pub content: Value,
+    pub sender_key: String,
+    pub sender_claimed_ed25519_key: String,
+    pub message_index: u32,
+    /// Whether the sending device is known and owns the claimed keys
+    pub verified_sender_device: bool,
+}
+
+/// Room keys to upload with `PUT /room_keys/keys`
+#[derive(Debug, Clone)]
+pub struct KeyBackupRequest {
+    pub version: String,
+    pub keys: RoomKeysPutRequest,
+    /// The uploaded sessions as `(room_id, session_id)`
+    pub sessions: Vec<(String, String)>,
+}
+
+/// Serializable snapshot of an [`OlmMachine`]
+///
+/// Contains private key material; callers must store it encrypted.
+#[derive(Serialize, Deserialize)]
+pub struct OlmMachinePickle {
+    user_id: String,
+    device_id: String,
+    account: AccountPickle,
+    device_keys_uploaded: bool,
+    uploaded_one_time_key_count: u64,
+    devices: DeviceTracker,
+    olm_sessions: Vec<(String, SessionPickle)>,
+    outbound_group_sessions: Vec<OutboundGroupSessionPickle>,
+    inbound_group_sessions: Vec<InboundGroupSessionEntryPickle>,
+    #[serde(default)]
+    cross_signing: Option<CrossSigningAccountPickle>,
+    /// Backup version and public key
+    #[serde(default)]
+    backup: Option<(String, String)>,
+}
+
+/// End-to-end encryption state of one device
+pub struct OlmMachine {
+    user_id: String,
+    device_id: String,
+    account: Account,
+    device_keys_uploaded: bool,
+    /// Server-side count of our unclaimed signed one-time keys
+    uploaded_one_time_key_count: u64,
+    devices: DeviceTracker,
+    /// Olm sessions by the remote device's Curve25519 key, newest last
+    olm_sessions: HashMap<String, Vec<Session>>,
+    outbound_group_sessions: HashMap<String, OutboundGroupSession>,
+    inbound_group_sessions: InboundGroupSessionStore,
+    /// Private cross-signing keys, when this device holds them
+    cross_signing: Option<CrossSigningAccount>,
+    /// Backup version new room keys are uploaded to
+    backup: Option<(String, BackupKey)>,
+}
+
+impl std::fmt::Debug for OlmMachine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OlmMachine")
+            .field("user_id", &self.user_id)
+            .field("device_id", &self.device_id)
+            .field("curve25519_key", &self.curve25519_key())
+            .field("olm_sessions", &self.olm_sessions.len())
+            .field("outbound_group_sessions", &self.outbound_group_sessions.len())
+            .field("inbound_group_sessions", &self.inbound_group_sessions)
+            .finish()
+    }
+}
+
+impl OlmMachine {
+    /// Create a machine with a freshly generated Olm account
+    pub fn new(user_id: &str, device_id: &str) -> Self {
+        Self {
+            user_id: user_id.to_string(),
+            device_id: device_id.to_string(),
+            account: Account::new(),
+            device_keys_uploaded: false,
+            uploaded_one_time_key_count: 0,
+            devices: DeviceTracker::default(),
+            olm_sessions: HashMap::new(),
+            outbound_group_sessions: HashMap::new(),
+            inbound_group_sessions: InboundGroupSessionStore::default(),
+            cross_signing: None,
+            backup: None,
+        }
+    }
+
+    pub fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    pub fn device_id(&self) -> &str {
+        &self.device_id
+    }
+
+    pub fn curve25519_key(&self) -> String {
+        self.account.curve25519_key().to_base64()
+    }
+
+    pub fn ed25519_key(&self) -> String {
+        self.account.ed25519_key().to_base64()
+    }
+
+    pub fn device_tracker(&self) -> &DeviceTracker {
+        &self.devices
+    }
+
+    pub fn inbound_group_sessions(&self) -> &InboundGroupSessionStore {
+        &self.inbound_group_sessions
+    }
+
+    pub(crate) fn device_tracker_mut(&mut self) -> &mut DeviceTracker {
+        &mut self.devices
+    }
+
+    pub fn cross_signing(&self) -> Option<&CrossSigningAccount> {
+        self.cross_signing.as_ref()
+    }
+
+    /// Our master key, from our private keys or as last published
+    pub fn own_master_key(&self) -> Option<String> {
+        match &self.cross_signing {
+            Some(account) => Some(account.master_key()),
+            None => self.devices.master_key(&self.user_id),
+        }
+    }
+
+    /// Create new cross-signing keys and sign this device with them
+    ///
+    /// The master key is additionally signed by the device key so other
+    /// devices of ours can trust it once they trust this device.
+    pub fn bootstrap_cross_signing(&mut self) -> Result<CrossSigningBootstrap, CryptoError> {
+        let account = CrossSigningAccount::new(&self.user_id);
+        let mut public_keys = account.public_keys()?;
+
+        let mut master_key = serde_json::to_value(&public_keys.master_key)?;
+        self.sign_json(&mut master_key)?;
+        public_keys.master_key = serde_json::from_value(master_key.clone())?;
+
+        let mut own_device = HashMap::new();
+        own_device.insert(self.device_id.clone(), account.sign_device(&self.device_keys()?)?);
+        let mut signatures = SignatureUploadRequest::new();
+        signatures.insert(self.user_id.clone(), own_device);
+
+        self.devices.set_master_key(&self.user_id, master_key);
+        self.devices.mark_user_verified(&self.user_id);
+        self.cross_signing = Some(account);
+
+        Ok(CrossSigningBootstrap { public_keys, signatures })
+    }
+
+    /// Cross-signing signatures to upload after verifying a device or user
+    ///
+    /// Our own devices are signed with the self-signing key and other users'
+    /// master keys with the user-signing key. Returns `None` when this device
+    /// holds no private cross-signing keys or the target key is unknown.
+    pub fn signatures_for_verified(
+        &self,
+        user_id: &str,
+        device_id: &str,
+    ) -> Result<Option<SignatureUploadRequest>, CryptoError> {
+        let Some(account) = &self.cross_signing else {
+            return Ok(None);
+        };
+
+        let (key_id, signed) = if user_id == self.user_id {
+            let Some(device) = self.devices.get_device(user_id, device_id) else {
+                return Ok(None);
+            };
+            (device_id.to_string(), account.sign_device(&device.device_keys)?)
+        } else {
+            let (Some(master_key), Some(master_key_object)) =
+                (self.devices.master_key(user_id), self.devices.master_key_object(user_id))
+            else {
+                return Ok(None);
+            };
+            (master_key, account.sign_user(master_key_object)?)
+        };
+
+        let mut signed_keys = HashMap::new();
+        signed_keys.insert(key_id, signed);
+        let mut signatures = SignatureUploadRequest::new();
+        signatures.insert(user_id.to_string(), signed_keys);
+        Ok(Some(signatures))
+    }
+
+    /// Install private cross-signing keys, e.g. from secret storage
+    ///
+    /// The master key must match the one published for our user, if known.
+    /// Since that key is now proven to be ours, our identity becomes trusted.
+    pub fn import_cross_signing_keys(
+        &mut self,
+        keys: &CrossSigningAccountPickle,
+    ) -> Result<(), CryptoError> {
+        if keys.user_id != self.user_id {
+            return Err(CryptoError::InvalidKey {
+                key: "master".to_string(),
+                message: format!("keys belong to {}", keys.user_id),
+            });
+        }
+
+        let account = CrossSigningAccount::from_pickle(keys)?;
+        if let Some(published) = self.devices.master_key(&self.user_id)
+            && published != account.master_key()
+        {
+            return Err(CryptoError::InvalidKey {
+                key: "master".to_string(),
+                message: "does not match the published master key".to_string(),
+            });
+        }
+
+        self.devices.mark_user_verified(&self.user_id);
+        self.cross_signing = Some(account);
+        Ok(())
+    }
+
+    /// The backup version and key new room keys are uploaded to
+    pub fn backup(&self) -> Option<(&str, &BackupKey)> {
+        self.backup.as_ref().map(|(version, key)| (version.as_str(), key))
+    }
+
+    /// Back up room keys to a backup version
+    ///
+    /// Switching to another version or key uploads every session again.
+    pub fn enable_backup(&mut self, version: &str, key: BackupKey) {
+        let unchanged = self
+            .backup
+            .as_ref()
+            .is_some_and(|(current, current_key)| current == version && *current_key == key);
+        if !unchanged {
+            self.inbound_group_sessions.reset_backup_state();
+            self.backup = Some((version.to_string(), key));
+        }
+    }
+
+    pub fn disable_backup(&mut self) {
+        self.backup = None;
+    }
+
+    /// `auth_data` of a new backup, signed by this device and our master key
+    pub fn backup_auth_data(&self, key: &BackupKey) -> Result<BackupAuthData, CryptoError> {
+        let mut auth_data = json!({ "public_key": key.to_base64() });
+        self.sign_json(&mut auth_data)?;
+        if let Some(account) = &self.cross_signing {
+            account.sign_with_master(&mut auth_data)?;
+        }
+        Ok(serde_json::from_value(auth_data)?)
+    }
+
+    /// Whether a backup's `auth_data` carries a signature we trust
+    ///
+    /// Trusted signers are this device, another of our verified devices and
+    /// our master key once our identity is verified.
+    pub fn is_backup_trusted(&self, auth_data: &BackupAuthData) -> bool {
+        let Ok(value) = serde_json::to_value(auth_data) else {
+            return false;
+        };
+
+        let own_device_key = format!("ed25519:{}", self.device_id);
+        if verify_json(&value, &self.user_id, &own_device_key, &self.ed25519_key()).is_ok() {
+            return true;
+        }
+
+        if self.devices.is_user_verified(&self.user_id)
+            && let Some(master_key) = self.own_master_key()
+            && verify_json(&value, &self.user_id, &format!("ed25519:{}", master_key), &master_key)
+                .is_ok()
+        {
+            return true;
+        }
+
+        self.devices.user_devices(&self.user_id).any(|device| {
+            self.devices.is_device_verified(&self.user_id, &device.device_id)
+                && verify_json(
+                    &value,
+                    &self.user_id,
+                    &format!("ed25519:{}", device.device_id),
+                    &device.ed25519_key,
+                )
+                .is_ok()
+        })
+    }
+
+    /// Encrypt up to `limit` room keys missing from the backup
+    pub fn backup_request(&self, limit: usize) -> Result<Option<KeyBackupRequest>, CryptoError> {
+        let Some((version, key)) = &self.backup else {
+            return Ok(None);
+        };
+
+        let sessions = self.inbound_group_sessions.sessions_to_back_up(limit);
+        if sessions.is_empty() {
+            return Ok(None);
+        }
+
+        let mut rooms: HashMap<String, RoomKeyBackup> = HashMap::new();
+        let mut uploaded = Vec::with_capacity(sessions.len());
+        for session in sessions {
+            let mut sender_claimed_keys = HashMap::new();
+            sender_claimed_keys
+                .insert("ed25519".to_string(), session.sender_claimed_ed25519_key.clone());
+            let data = BackedUpSessionData::new(
+                MEGOLM_V1_ALGORITHM.to_string(),
+                Vec::new(),
+                sender_claimed_keys,
+                session.sender_key.clone(),
+                session.session_key.clone(),
+            );
+
+            let is_verified = session.sender_key == self.curve25519_key()
+                || self.devices.user_devices(&self.user_id).any(|device| {
+                    device.curve25519_key == session.sender_key
+                        && self.devices.is_device_verified(&self.user_id, &device.device_id)
+                });
+            let key_data = key.encrypt_session(&data, session.first_known_index, is_verified)?;
+
+            rooms
+                .entry(session.room_id.clone())
+                .or_default()
+                .add_session(session.session_id.clone(), key_data);
+            uploaded.push((session.room_id, session.session_id));
+        }
+
+        Ok(Some(KeyBackupRequest {
+            version: version.clone(),
+            keys: RoomKeysPutRequest::new(rooms),
+            sessions: uploaded,
+        }))
+    }
+
+    /// Record a successful `PUT /room_keys/keys`
+    pub fn mark_backed_up(&mut self, sessions: &[(String, String)]) {
+        for (room_id, session_id) in sessions {
+            self.inbound_group_sessions.mark_backed_up(room_id, session_id);
+        }
+    }
+
+    /// Import a room key decrypted from the backup
+    ///
+    /// Returns whether the key was new or extended an existing session.
+    pub fn import_backed_up_session(
+        &mut self,
+        room_id: &str,
+        session_id: &str,
+        session: &BackedUpSessionData,
+    ) -> Result<bool, CryptoError> {
+        if session.algorithm != MEGOLM_V1_ALGORITHM {
+            return Err(CryptoError::UnsupportedAlgorithm(session.algorithm.clone()));
+        }
+        let claimed_key = session
+            .sender_claimed_keys
+            .get("ed25519")
+            .map(String::as_str)
+            .unwrap_or_default();
+
+        self.inbound_group_sessions.import(
+            room_id,
+            session_id,
+            &session.session_key,
+            &session.sender_key,
+            claimed_key,
+        )
+    }
+
+    /// Decrypt a backed up key with the backup's private key and import it
+    pub fn restore_backed_up_key(
+        &mut self,
+        decryption_key: &BackupDecryptionKey,
+        room_id: &str,
+        session_id: &str,
+        key_data: &KeyBackupData,
+    ) -> Result<bool, CryptoError> {
+        let session = decryption_key.decrypt_session(key_data)?;
+        self.import_backed_up_session(room_id, session_id, &session)
+    }
+
+    /// Sign a JSON object with the device's Ed25519 key
+    ///
+    /// The signature is added under `signatures.<user_id>.ed25519:<device_id>`.
+    pub fn sign_json(&self, value: &mut Value) -> Result<(), CryptoError> {
+        let signature = self.account.sign(signing_payload(value)?.as_str());
+        add_signature(value, &self.user_id, &format!("ed25519:{}", self.device_id), &signature);
+        Ok(())
+    }
+
+    /// Signed device keys of this device
+    pub fn device_keys(&self) -> Result<DeviceKeys, CryptoError> {
+        let mut keys = HashMap::new();
+        keys.insert(format!("curve25519:{}", self.device_id), self.curve25519_key());
+        keys.insert(format!("ed25519:{}", self.device_id), self.ed25519_key());
+
+        let device_keys = DeviceKeys::new(
+            vec![
+                OLM_V1_ALGORITHM.to_string(),
+                MEGOLM_V1_ALGORITHM.to_string(),
+            ],
+            self.device_id.clone(),
+            keys,
+            HashMap::new(),
+            None,
+            self.user_id.clone(),
+        );
+
+        let mut value = serde_json::to_value(&device_keys)?;
+        self.sign_json(&mut value)?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Keys that need uploading, if any
+    ///
+    /// Device keys are uploaded once; signed one-time keys are topped up to
+    /// half of the account's capacity.
+    pub fn keys_for_upload(&mut self) -> Result<Option<KeysUploadRequest>, CryptoError> {
+        let device_keys = if self.device_keys_uploaded {
+            None
+        } else {
+            Some(self.device_keys()?)
+        };
+
+        let target = (self.account.max_number_of_one_time_keys() / 2) as u64;
+        if self.uploaded_one_time_key_count < target && self.account.one_time_keys().is_empty() {
+            let count = target - self.uploaded_one_time_key_count;
+            self.account.generate_one_time_keys(count as usize);
+        }
+
+        let mut one_time_keys = HashMap::new();
+        for (key_id, key) in self.account.one_time_keys() {
+            let mut signed = json!({ "key": key.to_base64() });
+            self.sign_json(&mut signed)?;
+            one_time_keys.insert(format!("{}:{}", SIGNED_CURVE25519, key_id.to_base64()), signed);
+        }
+
+        if device_keys.is_none() && one_time_keys.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(KeysUploadRequest { device_keys, one_time_keys }))
+    }
+
+    /// Record a successful `/keys/upload`
+    pub fn receive_keys_upload_response(&mut self, response: &UploadKeysResponse) {
+        self.device_keys_uploaded = true;
+        self.account.mark_keys_as_published();
+        self.update_one_time_key_counts(&response.one_time_key_counts);
+    }
+
+    /// Apply `device_one_time_keys_count` from sync or `/keys/upload`
+    pub fn update_one_time_key_counts(&mut self, counts: &HashMap<String, u64>) {
+        self.uploaded_one_time_key_count = counts.get(SIGNED_CURVE25519).copied().unwrap_or(0);
+    }
+
+    /// Start tracking the device lists of room members
+    pub fn update_tracked_users<I>(&mut self, users: I)
+    where
+        I: IntoIterator<Item = String>,
+    {
+        self.devices.track_users(users);
+    }
+
+    /// Apply `device_lists` from sync
+    ///
+    /// Outbound group sessions shared with users who left are discarded so
+    /// the next message uses a key they never received.
+    pub fn receive_device_list_updates(&mut self, updates: &DeviceListUpdates) {
+        for user_id in self.devices.receive_device_list_updates(updates) {
+            self.outbound_group_sessions
+                .retain(|_, session| !session.shared_with_user(&user_id));
+        }
+    }
+
+    /// Body of the `/keys/query` request for outdated device lists
+    pub fn users_for_key_query(&self) -> Option<HashMap<String, Vec<String>>> {
+        self.devices.users_for_key_query()
+    }
+
+    pub fn receive_keys_query_response(&mut self, response: &QueryKeysResponse) {
+        self.devices.receive_keys_query_response(response);
+    }
+
+    /// Body of the `/keys/claim` request for devices we have no Olm session with
+    pub fn missing_sessions(
+        &self,
+        users: &[String],
+    ) -> Option<HashMap<String, HashMap<String, String>>> {
+        let mut missing: HashMap<String, HashMap<String, String>> = HashMap::new();
+
+        for user_id in users {
+            for device in self.devices.user_devices(user_id) {
+                if device.user_id == self.user_id && device.device_id == self.device_id {
+                    continue;
+                }
+                if !device.supports_encryption()
+                    || self.olm_sessions.contains_key(&device.curve25519_key)
+                {
+                    continue;
+                }
+                missing
+                    .entry(user_id.clone())
+                    .or_default()
+                    .insert(device.device_id.clone(), SIGNED_CURVE25519.to_string());
+            }
+        }
+
+        if missing.is_empty() {
+            None
+        } else {
+            Some(missing)
+        }
+    }
+
+    /// Create outbound Olm sessions from claimed one-time keys
+    pub fn receive_keys_claim_response(
+        &mut self,
+        response: &ClaimKeysResponse,
+    ) -> Result<(), CryptoError> {
+        for (user_id, devices) in &response.one_time_keys {
+            for (device_id, keys) in devices {
+                let Some(device) = self.devices.get_device(user_id, device_id).cloned() else {
+                    warn!("Claimed a one-time key for unknown device {}/{}", user_id, device_id);
+                    continue;
+                };
+
+                let Some(signed_key) = keys
+                    .iter()
+                    .find(|(key_id, _)| key_id.starts_with(SIGNED_CURVE25519))
+                    .map(|(_, key)| key)
+                else {
+                    warn!("No signed one-time key claimed for {}/{}", user_id, device_id);
+                    continue;
+                };
+
+                if let Err(message) = verify_json(
+                    signed_key,
+                    user_id,
+                    &format!("ed25519:{}", device_id),
+                    &device.ed25519_key,
+                ) {
+                    warn!(
+                        "Invalid one-time key signature for {}/{}: {}",
+                        user_id, device_id, message
+                    );
+                    continue;
+                }
+
+                let one_time_key =
+                    signed_key.get("key").and_then(Value::as_str).ok_or_else(|| {
+                        CryptoError::InvalidKey {
+                            key: format!("{}/{}", user_id, device_id),
+                            message: "signed one-time key has no key".to_string(),
+                        }
+                    })?;
+
+                let session = self.account.create_outbound_session(
+                    SessionConfig::version_2(),
+                    parse_curve25519(&device.curve25519_key)?,
+                    parse_curve25519(one_time_key)?,
+                );
+                debug!(
+                    "Created Olm session {} with {}/{}",
+                    session.session_id(),
+                    user_id,
+                    device_id
+                );
+                self.olm_sessions
+                    .entry(device.curve25519_key.clone())
+                    .or_default()
+                    .push(session);
+            }
+        }
+        Ok(())
+    }
+
+    /// Olm-encrypt a to-device event for one device
+    pub fn encrypt_to_device(
+        &mut self,
+        user_id: &str,
+        device_id: &str,
+        event_type: &str,
+        content: Value,
+    ) -> Result<Value, CryptoError> {
+        let device = self.devices.get_device(user_id, device_id).cloned().ok_or_else(|| {
+            CryptoError::UnknownDevice {
+                user_id: user_id.to_string(),
+                device_id: device_id.to_string(),
+            }
+        })?;
+
+        let payload = json!({
+            "type": event_type,
+            "content": content,
+            "sender": self.user_id,
+            "sender_device": self.device_id,
+            "keys": { "ed25519": self.ed25519_key() },
+            "recipient": user_id,
+            "recipient_keys": { "ed25519": device.ed25519_key },
+        });
+
+        let session = self
+            .olm_sessions
+            .get_mut(&device.curve25519_key)
+            .and_then(|sessions| sessions.last_mut())
+            .ok_or_else(|| CryptoError::MissingOlmSession(device.curve25519_key.clone()))?;
+
+        let (message_type, body) = session.encrypt(serde_json::to_string(&payload)?).to_parts();
+
+        let mut ciphertext = BTreeMap::new();
+        ciphertext.insert(
+            device.curve25519_key,
+            CipherText::new(base64_encode(body), message_type as u8),
+        );
+
+        let content = RoomEncryptedContent {
+            algorithm: OLM_V1_ALGORITHM.to_string(),
+            ciphertext: EncryptedCiphertext::Olm(ciphertext),
+            sender_key: Some(self.curve25519_key()),
+            device_id: None,
+            session_id: None,
+            relates_to: None,
+            unknown_fields: BTreeMap::new(),
+        };
+        Ok(serde_json::to_value(content)?)
+    }
+
+    /// Decrypt to-device events from sync, storing any room keys they carry
+    ///
+    /// Events that fail to decrypt are logged and dropped.
+    pub fn receive_to_device_events(&mut self, events: Vec<Value>) -> Vec<DecryptedToDeviceEvent> {
+        let mut decrypted = Vec::new();
+
+        for event in events {
+            let sender = event.get("sender").and_then(Value::as_str).unwrap_or_default();
+            let event_type = event.get("type").and_then(Value::as_str).unwrap_or_default();
+            let content = event.get("content").cloned().unwrap_or(Value::Null);
+
+            if event_type != RoomEncryptedContent::EVENT_TYPE {
+                decrypted.push(DecryptedToDeviceEvent {
+                    sender: sender.to_string(),
+                    event_type: event_type.to_string(),
+                    content,
+                    sender_key: None,
+                    sender_claimed_ed25519_key: None,
+                });
+                continue;
+            }
+
+            match self.decrypt_to_device_event(sender, content) {
+                Ok(event) => {
+                    if event.event_type == "m.room_key"
+                        && let Err(e) = self.receive_room_key(&event)
+                    {
+                        warn!("Failed to store room key from {}: {}", sender, e);
+                    }
+                    decrypted.push(event);
+                },
+                Err(e) => warn!("Failed to decrypt to-device event from {}: {}", sender, e),
+            }
+        }
+
+        decrypted
+    }
+
+    fn decrypt_to_device_event(
+        &mut self,
+        sender: &str,
+        content: Value,
+    ) -> Result<DecryptedToDeviceEvent, CryptoError> {
+        let content: RoomEncryptedContent = serde_json::from_value(content)?;
+        if content.algorithm != OLM_V1_ALGORITHM {
+            return Err(CryptoError::UnsupportedAlgorithm(content.algorithm));
+        }
+
+        let sender_key = content
+            .sender_key
+            .ok_or_else(|| CryptoError::Decryption("missing sender_key".to_string()))?;
+        let EncryptedCiphertext::Olm(ciphertexts) = content.ciphertext else {
+            return Err(CryptoError::Decryption("expected Olm ciphertext".to_string()));
+        };
+        let ciphertext = ciphertexts
+            .get(&self.curve25519_key())
+            .ok_or_else(|| CryptoError::Decryption("not encrypted for this device".to_string()))?;
+
+        let body =
+            base64_decode(&ciphertext.body).map_err(|e| CryptoError::Decryption(e.to_string()))?;
+        let message = OlmMessage::from_parts(ciphertext.message_type as usize, &body)
+            .map_err(|e| CryptoError::Decryption(e.to_string()))?;
+        let plaintext = self.decrypt_olm_message(&sender_key, &message)?;
+        let payload: Value = serde_json::from_str(&plaintext)?;
+
+        // Bind the payload to both ends of the session, per the Olm algorithm rules
+        let field = |name: &str| payload.get(name).and_then(Value::as_str).unwrap_or_default();
+        if field("sender") != sender {
+            return Err(CryptoError::PayloadMismatch("sender".to_string()));
+        }
+        if field("recipient") != self.user_id {
+            return Err(CryptoError::PayloadMismatch("recipient".to_string()));
+        }
+        let recipient_key = payload
+            .pointer("/recipient_keys/ed25519")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        if recipient_key != self.ed25519_key() {
+            return Err(CryptoError::PayloadMismatch("recipient_keys".to_string()));
+        }
+        let claimed_key = payload.pointer("/keys/ed25519").and_then(Value::as_str);
+        if let Some(device) = self.devices.device_by_curve25519_key(sender, &sender_key)
+            && claimed_key != Some(device.ed25519_key.as_str())
+        {
+            return Err(CryptoError::PayloadMismatch("keys".to_string()));
+        }
+
+        Ok(DecryptedToDeviceEvent {
+            sender: sender.to_string(),
+            event_type: field("type").to_string(),
+            content: payload.get("content").cloned().unwrap_or(Value::Null),
+            sender_key: Some(sender_key),
+            sender_claimed_ed25519_key: claimed_key.map(str::to_string),
+        })
+    }
+
+    fn decrypt_olm_message(
+        &mut self,
+        sender_key: &str,
+        message: &OlmMessage,
+    ) -> Result<String, CryptoError> {
+        if let Some(sessions) = self.olm_sessions.get_mut(sender_key) {
+            for session in sessions.iter_mut().rev() {
+                if let Ok(plaintext) = session.decrypt(message) {
+                    return String::from_utf8(plaintext)
+                        .map_err(|e| CryptoError::Decryption(e.to_string()));
+                }
+            }
+        }
+
+        // Only a pre-key message may establish a new session
+        let OlmMessage::PreKey(pre_key) = message else {
+            return Err(CryptoError::MissingOlmSession(sender_key.to_string()));
+        };
+
+        let result = self
+            .account
+            .create_inbound_session(parse_curve25519(sender_key)?, pre_key)
+            .map_err(|e| CryptoError::Decryption(e.to_string()))?;
+        debug!("Created inbound Olm session {} with {}", result.session.session_id(), sender_key);
+        self.olm_sessions
+            .entry(sender_key.to_string())
+            .or_default()
+            .push(result.session);
+
+        String::from_utf8(result.plaintext).map_err(|e| CryptoError::Decryption(e.to_string()))
+    }
+
+    fn receive_room_key(&mut self, event: &DecryptedToDeviceEvent) -> Result<(), CryptoError> {
+        let field = |name: &str| event.content.get(name).and_then(Value::as_str);
+
+        let algorithm = field("algorithm").unwrap_or_default();
+        if algorithm != MEGOLM_V1_ALGORITHM {
+            return Err(CryptoError::UnsupportedAlgorithm(algorithm.to_string()));
+        }
+
+        let (Some(room_id), Some(session_key)) = (field("room_id"), field("session_key")) else {
+            return Err(CryptoError::Decryption("incomplete m.room_key".to_string()));
+        };
+        let sender_key = event.sender_key.as_deref().unwrap_or_default();
+        let claimed_key = event.sender_claimed_ed25519_key.as_deref().unwrap_or_default();
+
+        let session_id =
+            self.inbound_group_sessions
+                .add(room_id, session_key, sender_key, claimed_key)?;
+        debug!("Stored room key {} for {}", session_id, room_id);
+        Ok(())
+    }
+
+    /// Whether the outbound session of a room has to be replaced before use
+    fn needs_new_group_session(
+        &self,
+        room_id: &str,
+        recipients: &HashSet<(String, String)>,
+    ) -> bool {
+        match self.outbound_group_sessions.get(room_id) {
+            Some(session) => session.is_expired() || session.shared_outside(recipients),
+            None => true,
+        }
+    }
+
+    /// Share the room's Megolm key with every known device of `members`
+    ///
+    /// Rotates the outbound session first when it expired or was shared with
+    /// a device that is no longer a recipient. Returns the Olm-encrypted
+    /// `m.room_key` messages to send; devices without an Olm session are
+    /// skipped, so [`Self::missing_sessions`] should be resolved beforehand.
+    pub fn share_room_key(
+        &mut self,
+        room_id: &str,
+        members: &[String],
+        settings: &RoomEncryptionContent,
+    ) -> Result<Option<ToDeviceRequest>, CryptoError> {
+        if settings.algorithm != MEGOLM_V1_ALGORITHM {
+            return Err(CryptoError::UnsupportedAlgorithm(settings.algorithm.clone()));
+        }
+
+        let recipients: Vec<(String, String)> = members
+            .iter()
+            .flat_map(|user_id| self.devices.user_devices(user_id))
+            .filter(|device| {
+                device.supports_encryption()
+                    && !(device.user_id == self.user_id && device.device_id == self.device_id)
+            })
+            .map(|device| (device.user_id.clone(), device.device_id.clone()))
+            .collect();
+        let recipient_set: HashSet<(String, String)> = recipients.iter().cloned().collect();
+
+        if self.needs_new_group_session(room_id, &recipient_set) {
+            let session = OutboundGroupSession::new(room_id, settings);
+            debug!("Rotating Megolm session for {} to {}", room_id, session.session_id());
+
+            // Keep our own copy so we can read back what we sent
+            let (curve25519_key, ed25519_key) = (self.curve25519_key(), self.ed25519_key());
+            self.inbound_group_sessions.add(
+                room_id,
+                &session.session_key(),
+                &curve25519_key,
+                &ed25519_key,
+            )?;
+            self.outbound_group_sessions.insert(room_id.to_string(), session);
+        }
+
+        let Some(session) = self.outbound_group_sessions.get(room_id) else {
+            return Ok(None);
+        };
+        let room_key = json!({
+            "algorithm": MEGOLM_V1_ALGORITHM,
+            "room_id": room_id,
+            "session_id": session.session_id(),
+            "session_key": session.session_key(),
+        });
+        let pending: Vec<(String, String)> = recipients
+            .into_iter()
+            .filter(|(user_id, device_id)| !session.is_shared_with(user_id, device_id))
+            .collect();
+
+        let mut messages: HashMap<String, HashMap<String, Value>> = HashMap::new();
+        for (user_id, device_id) in pending {
+            match self.encrypt_to_device(&user_id, &device_id, "m.room_key", room_key.clone()) {
+                Ok(content) => {
+                    messages
+                        .entry(user_id.clone())
+                        .or_default()
+                        .insert(device_id.clone(), content);
+                    if let Some(session) = self.outbound_group_sessions.get_mut(room_id) {
+                        session.mark_shared_with(&user_id, &device_id);
+                    }
+                },
+                Err(e) => warn!("Not sharing room key with {}/{}: {}", user_id, device_id, e),
+            }
+        }
+
+        if messages.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(ToDeviceRequest {
+            event_type: RoomEncryptedContent::EVENT_TYPE.to_string(),
+            messages,
+        }))
+    }
+
+    /// Megolm-encrypt a room event with the room's current outbound session
+    ///
+    /// [`Self::share_room_key`] must have been called for the room first.
+    pub fn encrypt_room_event(
+        &mut self,
+        room_id: &str,
+        event_type: &str,
+        content: Value,
+    ) -> Result<RoomEncryptedContent, CryptoError> {
+        // Relations stay in the clear so the server can aggregate them
+        let relates_to = content
+            .get("m.relates_to")
+            .cloned()
+            .map(serde_json::from_value)
+            .transpose()?;
+
+        let payload = json!({
+            "type": event_type,
+            "content": content,
+            "room_id": room_id,
+        });
+
+        let session = self.outbound_group_sessions.get_mut(room_id).ok_or_else(|| {
+            CryptoError::MissingMegolmSession {
+                room_id: room_id.to_string(),
+                session_id: String::new(),
+            }
+        })?;
+        let ciphertext = session.encrypt(&serde_json::to_string(&payload)?);
+
+        let mut encrypted = RoomEncryptedContent::megolm(ciphertext, session.session_id());
+        encrypted.sender_key = Some(self.curve25519_key());
+        encrypted.device_id = Some(self.device_id.clone());
+        encrypted.relates_to = relates_to;
+        Ok(encrypted)
+    }
+
+    /// Drop the outbound session of a room so the next message rotates it
+    pub fn discard_room_key(&mut self, room_id: &str) {
+        self.outbound_group_sessions.remove(room_id);
+    }
+
+    /// Decrypt an `m.room.encrypted` timeline event
+    pub fn decrypt_room_event(
+        &mut self,
+        room_id: &str,
+        event_id: &str,
+        sender: &str,
+        content: &Value,
+    ) -> Result<DecryptedRoomEvent, CryptoError> {
+        let content: RoomEncryptedContent = serde_json::from_value(content.clone())?;
+        if content.algorithm != MEGOLM_V1_ALGORITHM {
+            return Err(CryptoError::UnsupportedAlgorithm(content.algorithm));
+        }
+        let EncryptedCiphertext::Megolm(ciphertext) = &content.ciphertext else {
+            return Err(CryptoError::Decryption("expected Megolm ciphertext".to_string()));
+        };
+        let session_id = content.session_id.as_deref().unwrap_or_default();
+
+        let decryption = self
+            .inbound_group_sessions
+            .decrypt(room_id, session_id, event_id, ciphertext)?;
+        let payload: Value = serde_json::from_str(&decryption.plaintext)?;
+
+        if payload.get("room_id").and_then(Value::as_str) != Some(room_id) {
+            return Err(CryptoError::PayloadMismatch("room_id".to_string()));
+        }
+
+        let verified_sender_device = self
+            .devices
+            .device_by_curve25519_key(sender, &decryption.sender_key)
+            .is_some_and(|device| device.ed25519_key == decryption.sender_claimed_ed25519_key)
+            || (sender == self.user_id && decryption.sender_key == self.curve25519_key());
+
+        let mut decrypted_content = payload.get("content").cloned().unwrap_or(Value::Null);
+        if let (Some(object), Some(relates_to)) =
+            (decrypted_content.as_object_mut(), content.relates_to)
+        {
+            object.entry("m.relates_to").or_insert(serde_json::to_value(relates_to)?);
+        }
+
+        Ok(DecryptedRoomEvent {
+            event_type: payload.get("type").and_then(Value::as_str).unwrap_or_default().to_string(),
+            content: decrypted_content,
+            sender_key: decryption.sender_key,
+            sender_claimed_ed25519_key: decryption.sender_claimed_ed25519_key,
+            message_index: decryption.message_index,
+            verified_sender_device,
+        })
+    }
+
+    pub fn pickle(&self) -> OlmMachinePickle {
+        OlmMachinePickle {
+            user_id: self.user_id.clone(),
+            device_id: self.device_id.clone(),
+            account: self.account.pickle(),
+            device_keys_uploaded: self.device_keys_uploaded,
+            uploaded_one_time_key_count: self.uploaded_one_time_key_count,
+            devices: self.devices.clone(),
+            olm_sessions: self
+                .olm_sessions
+                .iter()
+                .flat_map(|(key, sessions)| {
+                    sessions.iter().map(move |session| (key.clone(), session.pickle()))
+                })
+                .collect(),
+            outbound_group_sessions: self
+                .outbound_group_sessions
+                .values()
+                .map(OutboundGroupSession::pickle)
+                .collect(),
+            inbound_group_sessions: self.inbound_group_sessions.pickle(),
+            cross_signing: self.cross_signing.as_ref().map(CrossSigningAccount::pickle),
+            backup: self
+                .backup
+                .as_ref()
+                .map(|(version, key)| (version.clone(), key.to_base64())),
+        }
+    }
+
+    pub fn from_pickle(pickle: OlmMachinePickle) -> Result<Self, CryptoError> {
+        let mut olm_sessions: HashMap<String, Vec<Session>> = HashMap::new();
+        for (key, session) in pickle.olm_sessions {
+            olm_sessions.entry(key).or_default().push(Session::from_pickle(session));
+        }
+
+        let cross_signing = pickle
+            .cross_signing
+            .as_ref()
+            .map(CrossSigningAccount::from_pickle)
+            .transpose()?;
+        let backup = match pickle.backup {
+            Some((version, public_key)) => Some((version, BackupKey::from_base64(&public_key)?)),
+            None => None,
+        };
+
+        Ok(Self {
+            user_id: pickle.user_id,
+            device_id: pickle.device_id,
+            account: Account::from_pickle(pickle.account),
+            device_keys_uploaded: pickle.device_keys_uploaded,
+            uploaded_one_time_key_count: pickle.uploaded_one_time_key_count,
+            devices: pickle.devices,
+            olm_sessions,
+            outbound_group_sessions: pickle
+                .outbound_group_sessions
+                .into_iter()
+                .map(OutboundGroupSession::from_pickle)
+                .map(|session| (session.room_id.clone(), session))
+                .collect(),
+            inbound_group_sessions: InboundGroupSessionStore::from_pickle(
+                pickle.inbound_group_sessions,
+            ),
+            cross_signing,
+            backup,
+        })
+    }
+}
+
+fn parse_curve25519(key: &str) -> Result<Curve25519PublicKey, CryptoError> {
+    Curve25519PublicKey::from_base64(key)
+        .map_err(|e| CryptoError::InvalidKey { key: key.to_string(), message: e.to_string() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROOM_ID: &str = "!room:example.com";
+
+    fn machine(user_id: &str, device_id: &str) -> (OlmMachine, KeysUploadRequest) {
+        let mut machine = OlmMachine::new(user_id, device_id);
+        let upload = machine
+            .keys_for_upload()
+            .expect("keys_for_upload failed")
+            .expect("new machine should upload keys");
+        let mut counts = HashMap::new();
+        counts.insert(SIGNED_CURVE25519.to_string(), upload.one_time_keys.len() as u64);
+        machine.receive_keys_upload_response(&UploadKeysResponse { one_time_key_counts: counts });
+        (machine, upload)
+    }
+
+    fn query_response(uploads: &[&KeysUploadRequest]) -> QueryKeysResponse {
+        let mut device_keys: HashMap<String, HashMap<String, DeviceKeys>> = HashMap::new();
+        for upload in uploads {
+            let keys = upload.device_keys.clone().expect("upload should contain device keys");
+            device_keys
+                .entry(keys.user_id.clone())
+                .or_default()
+                .insert(keys.device_id.clone(), keys);
+        }
+        QueryKeysResponse {
+            device_keys,
+            master_keys: None,
+            self_signing_keys: None,
+            user_signing_keys: None,
+        }
+    }
+
+    fn claim_response(upload: &KeysUploadRequest) -> ClaimKeysResponse {
+        let keys = upload.device_keys.as_ref().expect("upload should contain device keys");
+        let (key_id, key) = upload
+            .one_time_keys
+            .iter()
+            .next()
+            .expect("upload should contain one-time keys");
+
+        let mut device = HashMap::new();
+        device.insert(key_id.clone(), key.clone());
+        let mut user = HashMap::new();
+        user.insert(keys.device_id.clone(), device);
+        let mut one_time_keys = HashMap::new();
+        one_time_keys.insert(keys.user_id.clone(), user);
+        ClaimKeysResponse { one_time_keys }
+    }
+
+    fn to_device_events(sender: &str, request: &ToDeviceRequest, user_id: &str) -> Vec<Value> {
+        request
+            .messages
+            .get(user_id)
+            .into_iter()
+            .flat_map(|devices| devices.values())
+            .map(|content| json!({ "sender": sender, "type": request.event_type, "content": content }))
+            .collect()
+    }
+
+    /// Alice and Bob with verified device lists, Alice holding an Olm session to Bob
+    fn paired() -> (OlmMachine, OlmMachine) {
+        let (mut alice, alice_upload) = machine("@alice:example.com", "ALICEDEVICE");
+        let (mut bob, bob_upload) = machine("@bob:example.com", "BOBDEVICE");
+
+        alice.update_tracked_users(vec!["@bob:example.com".to_string()]);
+        bob.update_tracked_users(vec!["@alice:example.com".to_string()]);
+        assert!(alice.users_for_key_query().is_some());
+
+        alice.receive_keys_query_response(&query_response(&[&bob_upload]));
+        bob.receive_keys_query_response(&query_response(&[&alice_upload]));
+        assert!(alice.users_for_key_query().is_none());
+
+        let missing = alice
+            .missing_sessions(&["@bob:example.com".to_string()])
+            .expect("Alice should need a session with Bob");
+        assert!(missing["@bob:example.com"].contains_key("BOBDEVICE"));
+
+        alice
+            .receive_keys_claim_response(&claim_response(&bob_upload))
+            .expect("claim response should create a session");
+        assert!(alice.missing_sessions(&["@bob:example.com".to_string()]).is_none());
+
+        (alice, bob)
+    }
+
+    fn share_with_bob(alice: &mut OlmMachine, bob: &mut OlmMachine) {
+        let request = alice
+            .share_room_key(
+                ROOM_ID,
+                &["@bob:example.com".to_string()],
+                &RoomEncryptionContent::megolm(),
+            )
+            .expect("sharing should succeed")
+            .expect("Bob should receive the room key");
+        let events = to_device_events("@alice:example.com", &request, "@bob:example.com");
+        let decrypted = bob.receive_to_device_events(events);
+        assert_eq!(decrypted.len(), 1);
+        assert_eq!(decrypted[0].event_type, "m.room_key");
+    }
+
+    #[test]
+    fn test_device_keys_are_self_signed() {
+        let (_, upload) = machine("@alice:example.com", "ALICEDEVICE");
+        let keys = upload.device_keys.expect("device keys should be uploaded");
+        let device = crate::crypto::TrackedDevice::from_device_keys(&keys)
+            .expect("own device keys should verify");
+        assert!(device.supports_encryption());
+        assert!(upload.one_time_keys.keys().all(|id| id.starts_with("signed_curve25519:")));
+    }
+
+    #[test]
+    fn test_megolm_round_trip() {
+        let (mut alice, mut bob) = paired();
+        share_with_bob(&mut alice, &mut bob);
+
+        let encrypted = alice
+            .encrypt_room_event(
+                ROOM_ID,
+                "m.room.message",
+                json!({ "msgtype": "m.text", "body": "hi" }),
+            )
+            .expect("encryption should succeed");
+        let content = serde_json::to_value(&encrypted).expect("content should serialize");
+
+        let decrypted = bob
+            .decrypt_room_event(ROOM_ID, "$event1", "@alice:example.com", &content)
+            .expect("Bob should decrypt Alice's message");
+        assert_eq!(decrypted.event_type, "m.room.message");
+        assert_eq!(decrypted.content["body"], "hi");
+        assert!(decrypted.verified_sender_device);
+
+        // The same ciphertext under another event ID is a replay
+        let replay = bob.decrypt_room_event(ROOM_ID, "$event2", "@alice:example.com", &content);
+        assert!(matches!(replay, Err(CryptoError::ReplayedMessage { .. })));
+    }
+
+    #[test]
+    fn test_room_key_rotates_when_member_leaves() {
+        let (mut alice, mut bob) = paired();
+        share_with_bob(&mut alice, &mut bob);
+        let first = alice
+            .encrypt_room_event(ROOM_ID, "m.room.message", json!({ "body": "one" }))
+            .expect("encryption should succeed");
+
+        alice.receive_device_list_updates(&DeviceListUpdates {
+            changed: Vec::new(),
+            left: vec!["@bob:example.com".to_string()],
+        });
+        assert!(alice.encrypt_room_event(ROOM_ID, "m.room.message", json!({})).is_err());
+
+        alice
+            .share_room_key(ROOM_ID, &[], &RoomEncryptionContent::megolm())
+            .expect("rotation should succeed");
+        let second = alice
+            .encrypt_room_event(ROOM_ID, "m.room.message", json!({ "body": "two" }))
+            .expect("encryption should succeed");
+        assert_ne!(first.session_id, second.session_id);
+    }
+
+    #[test]
+    fn test_pickle_round_trip() {
+        let (mut alice, bob) = paired();
+        let pickle = serde_json::to_string(&bob.pickle()).expect("pickle should serialize");
+        let mut bob =
+            OlmMachine::from_pickle(serde_json::from_str(&pickle).expect("pickle should parse"))
+                .expect("pickle should restore");
+
+        share_with_bob(&mut alice, &mut bob);
+        let encrypted = alice
+            .encrypt_room_event(ROOM_ID, "m.room.message", json!({ "body": "restored" }))
+            .expect("encryption should succeed");
+        let content = serde_json::to_value(&encrypted).expect("content should serialize");
+        let decrypted = bob
+            .decrypt_room_event(ROOM_ID, "$event", "@alice:example.com", &content)
+            .expect("restored machine should decrypt");
+        assert_eq!(decrypted.content["body"], "restored");
+    }
+
+    #[test]
+    fn test_key_backup_round_trip() {
+        let (mut alice, mut bob) = paired();
+        share_with_bob(&mut alice, &mut bob);
+
+        let decryption_key = BackupDecryptionKey::new();
+        let auth_data = bob
+            .backup_auth_data(&decryption_key.backup_key())
+            .expect("auth data should be signed");
+        assert!(bob.is_backup_trusted(&auth_data));
+        assert!(!alice.is_backup_trusted(&auth_data));
+
+        bob.enable_backup("1", decryption_key.backup_key());
+        let request = bob
+            .backup_request(100)
+            .expect("backup request should encrypt")
+            .expect("Bob's room key should need backing up");
+        assert_eq!(request.sessions.len(), 1);
+        bob.mark_backed_up(&request.sessions);
+        assert!(bob.backup_request(100).expect("backup request should succeed").is_none());
+
+        let mut new_device = OlmMachine::new("@bob:example.com", "BOBDEVICE2");
+        new_device.enable_backup("1", decryption_key.backup_key());
+        for (room_id, room) in &request.keys.rooms {
+            for (session_id, key_data) in &room.sessions {
+                let imported = new_device
+                    .restore_backed_up_key(&decryption_key, room_id, session_id, key_data)
+                    .expect("room key should restore");
+                assert!(imported);
+            }
+        }
+        // Restored keys are already in the backup
+        assert!(
+            new_device
+                .backup_request(100)
+                .expect("backup request should succeed")
+                .is_none()
+        );
+
+        let encrypted = alice
+            .encrypt_room_event(ROOM_ID, "m.room.message", json!({ "body": "backed up" }))
+            .expect("encryption should succeed");
+        let content = serde_json::to_value(&encrypted).expect("content should serialize");
+        let decrypted = new_device
+            .decrypt_room_event(ROOM_ID, "$event", "@alice:example.com", &content)
+            .expect("restored key should decrypt");
+        assert_eq!(decrypted.content["body"], "backed up");
+    }
+}
@@ -0,0 +1,92 @@
+//! End-to-end encryption for the Matrix client
+//!
+//! Wraps vodozemac's Olm and Megolm primitives in a transport-agnostic state
+//! machine: the [`OlmMachine`] produces the key upload, query, claim and
+//! to-device payloads the client has to send, and consumes the matching
+//! responses and sync data. Secret storage and key backup keys are handled
+//! the same way. All network I/O stays in [`crate::MatrixClient`].
+
+pub mod attachment;
+pub mod backup;
+pub mod cross_signing;
+pub mod device_tracker;
+pub mod group_sessions;
+pub mod machine;
+pub mod secret_storage;
+mod signing;
+pub mod verification;
+
+pub use attachment::{
+    ATTACHMENT_VERSION, AttachmentKey, decrypt_attachment, encrypt_attachment,
+};
+pub use backup::{BackupDecryptionKey, BackupKey, MEGOLM_BACKUP_V1_ALGORITHM};
+pub use cross_signing::{
+    CrossSigningAccount, CrossSigningAccountPickle, CrossSigningBootstrap, CrossSigningPublicKeys,
+    SignatureUploadRequest,
+};
+pub use device_tracker::{DeviceTracker, TrackedDevice};
+pub use group_sessions::{ExportedInboundSession, InboundGroupSessionStore, OutboundGroupSession};
+pub use machine::{
+    DecryptedRoomEvent, DecryptedToDeviceEvent, KeyBackupRequest, KeysUploadRequest, OlmMachine,
+    OlmMachinePickle, ToDeviceRequest,
+};
+pub use secret_storage::{
+    EncryptedSecret, SecretContent, SecretStorageKey, SecretStorageKeyDescription,
+};
+pub use verification::{
+    OutgoingVerificationMessage, VerificationError, VerificationEvent, VerificationFlow,
+    VerificationMachine,
+};
+
+/// Olm algorithm identifier used for to-device encryption
+pub const OLM_V1_ALGORITHM: &str = "m.olm.v1.curve25519-aes-sha2";
+
+/// Megolm algorithm identifier used for room encryption
+pub const MEGOLM_V1_ALGORITHM: &str = "m.megolm.v1.aes-sha2";
+
+/// Algorithm name of signed one-time keys
+pub const SIGNED_CURVE25519: &str = "signed_curve25519";
+
+/// End-to-end encryption errors
+#[derive(Debug, thiserror::Error)]
+pub enum CryptoError {
+    #[error("Invalid key {key}: {message}")]
+    InvalidKey { key: String, message: String },
+
+    #[error("Signature verification failed for {user_id}/{device_id}: {message}")]
+    InvalidSignature {
+        user_id: String,
+        device_id: String,
+        message: String,
+    },
+
+    #[error("Unsupported algorithm: {0}")]
+    UnsupportedAlgorithm(String),
+
+    #[error("No Olm session with {0}")]
+    MissingOlmSession(String),
+
+    #[error("No Megolm session {session_id} for room {room_id}")]
+    MissingMegolmSession { room_id: String, session_id: String },
+
+    #[error("Unknown device {user_id}/{device_id}")]
+    UnknownDevice { user_id: String, device_id: String },
+
+    #[error("Decryption failed: {0}")]
+    Decryption(String),
+
+    #[error("Message index {index} of session {session_id} was replayed")]
+    ReplayedMessage { session_id: String, index: u32 },
+
+    #[error("Decrypted payload mismatch: {0}")]
+    PayloadMismatch(String),
+
+    #[error("Pickle error: {0}")]
+    Pickle(String),
+
+    #[error("Canonical JSON error: {0}")]
+    CanonicalJson(#[from] matryx_entity::utils::CanonicalJsonError),
+
+    #[error("JSON serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
@@ -0,0 +1,482 @@
+//! Secret storage
+//!
+//! Implements `m.secret_storage.v1.aes-hmac-sha2`: secrets such as the
+//! private cross-signing keys and the key backup decryption key are
+//! encrypted with a secret storage key and kept in account data under the
+//! secret's name. The key itself never leaves the client; the user holds it
+//! as a recovery key or derives it from a passphrase.
+
+use aes::Aes256;
+use aes::cipher::{KeyIvInit, StreamCipher};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use rand::distr::{Alphanumeric, SampleString};
+use serde::{Deserialize, Serialize};
+use sha2::{Sha256, Sha512};
+use std::collections::HashMap;
+use vodozemac::base64_decode;
+
+use super::CryptoError;
+
+/// Secret storage algorithm identifier
+pub const SECRET_STORAGE_V1_ALGORITHM: &str = "m.secret_storage.v1.aes-hmac-sha2";
+
+/// Passphrase derivation algorithm identifier
+pub const PBKDF2_ALGORITHM: &str = "m.pbkdf2";
+
+/// Account data type naming the default secret storage key
+pub const DEFAULT_KEY_EVENT_TYPE: &str = "m.secret_storage.default_key";
+
+/// Account data type prefix of secret storage key descriptions
+pub const KEY_EVENT_TYPE_PREFIX: &str = "m.secret_storage.key.";
+
+/// Private master cross-signing key, unpadded base64
+pub const MASTER_KEY_SECRET: &str = "m.cross_signing.master";
+
+/// Private self-signing key, unpadded base64
+pub const SELF_SIGNING_KEY_SECRET: &str = "m.cross_signing.self_signing";
+
+/// Private user-signing key, unpadded base64
+pub const USER_SIGNING_KEY_SECRET: &str = "m.cross_signing.user_signing";
+
+/// Key backup decryption key, unpadded base64
+pub const MEGOLM_BACKUP_SECRET: &str = "m.megolm_backup.v1";
+
+const KEY_LENGTH: usize = 32;
+const KEY_BITS: u32 = 256;
+const PBKDF2_ITERATIONS: u32 = 500_000;
+const RECOVERY_KEY_PREFIX: [u8; 2] = [0x8b, 0x01];
+
+type Aes256Ctr = ctr::Ctr128BE<Aes256>;
+type HmacSha256 = Hmac<Sha256>;
+
+/// How a key is derived from a passphrase
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PassphraseInfo {
+    pub algorithm: String,
+    pub salt: String,
+    pub iterations: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bits: Option<u32>,
+}
+
+/// Content of `m.secret_storage.key.<key_id>` account data
+///
+/// `iv` and `mac` encrypt 32 zero bytes under the empty name so a key can be
+/// checked without decrypting a secret.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecretStorageKeyDescription {
+    pub algorithm: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub passphrase: Option<PassphraseInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iv: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mac: Option<String>,
+}
+
+/// Content of `m.secret_storage.default_key` account data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefaultKeyContent {
+    pub key: String,
+}
+
+/// A secret encrypted with one secret storage key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptedSecret {
+    pub iv: String,
+    pub ciphertext: String,
+    pub mac: String,
+}
+
+/// Content of a secret's account data, encrypted by key ID
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SecretContent {
+    pub encrypted: HashMap<String, EncryptedSecret>,
+}
+
+/// A secret storage key together with its description
+pub struct SecretStorageKey {
+    key_id: String,
+    key: [u8; KEY_LENGTH],
+    description: SecretStorageKeyDescription,
+}
+
+impl std::fmt::Debug for SecretStorageKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SecretStorageKey")
+            .field("key_id", &self.key_id)
+            .field("description", &self.description)
+            .finish()
+    }
+}
+
+impl SecretStorageKey {
+    /// Generate a random key, to be handed to the user as a recovery key
+    pub fn new(name: Option<&str>) -> Result<Self, CryptoError> {
+        let mut key = [0u8; KEY_LENGTH];
+        rand::rng().fill_bytes(&mut key);
+        Self::with_key(key, name, None)
+    }
+
+    /// Derive a new key from a passphrase with a fresh salt
+    pub fn new_from_passphrase(passphrase: &str, name: Option<&str>) -> Result<Self, CryptoError> {
+        Self::with_passphrase(passphrase, name, PBKDF2_ITERATIONS)
+    }
+
+    /// Open an existing key with a recovery key
+    pub fn from_recovery_key(
+        key_id: &str,
+        description: SecretStorageKeyDescription,
+        recovery_key: &str,
+    ) -> Result<Self, CryptoError> {
+        let key = decode_recovery_key(recovery_key)?;
+        Self::open(key_id, description, key)
+    }
+
+    /// Open an existing key by deriving it from its passphrase
+    pub fn from_passphrase(
+        key_id: &str,
+        description: SecretStorageKeyDescription,
+        passphrase: &str,
+    ) -> Result<Self, CryptoError> {
+        let info = description.passphrase.as_ref().ok_or_else(|| CryptoError::InvalidKey {
+            key: key_id.to_string(),
+            message: "key cannot be derived from a passphrase".to_string(),
+        })?;
+        let key = derive_from_passphrase(passphrase, info)?;
+        Self::open(key_id, description, key)
+    }
+
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    pub fn description(&self) -> &SecretStorageKeyDescription {
+        &self.description
+    }
+
+    /// Account data type holding the key description
+    pub fn event_type(&self) -> String {
+        format!("{}{}", KEY_EVENT_TYPE_PREFIX, self.key_id)
+    }
+
+    /// Base58 recovery key, in groups of four characters
+    pub fn recovery_key(&self) -> String {
+        let mut bytes = Vec::with_capacity(RECOVERY_KEY_PREFIX.len() + KEY_LENGTH + 1);
+        bytes.extend_from_slice(&RECOVERY_KEY_PREFIX);
+        bytes.extend_from_slice(&self.key);
+        bytes.push(bytes.iter().fold(0, |parity, byte| parity ^ byte));
+
+        let encoded: Vec<char> = bs58::encode(bytes).into_string().chars().collect();
+        encoded
+            .chunks(4)
+            .map(|group| group.iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Encrypt a secret for storage under `secret_name`
+    pub fn encrypt(&self, secret_name: &str, secret: &str) -> Result<EncryptedSecret, CryptoError> {
+        encrypt_with_key(&self.key, secret_name, secret.as_bytes(), random_iv())
+    }
+
+    /// Decrypt a secret stored under `secret_name`
+    pub fn decrypt(
+        &self,
+        secret_name: &str,
+        encrypted: &EncryptedSecret,
+    ) -> Result<String, CryptoError> {
+        let (aes_key, hmac_key) = derive_keys(&self.key, secret_name)?;
+        let mut plaintext = decode(&encrypted.ciphertext)?;
+        if !mac_matches(&hmac_key, &plaintext, &encrypted.mac)? {
+            return Err(CryptoError::Decryption(format!(
+                "MAC mismatch for secret {}",
+                secret_name
+            )));
+        }
+
+        apply_keystream(&aes_key, &decode_iv(&encrypted.iv)?, &mut plaintext)?;
+        String::from_utf8(plaintext).map_err(|e| CryptoError::Decryption(e.to_string()))
+    }
+
+    fn with_passphrase(
+        passphrase: &str,
+        name: Option<&str>,
+        iterations: u32,
+    ) -> Result<Self, CryptoError> {
+        let info = PassphraseInfo {
+            algorithm: PBKDF2_ALGORITHM.to_string(),
+            salt: Alphanumeric.sample_string(&mut rand::rng(), 32),
+            iterations,
+            bits: Some(KEY_BITS),
+        };
+        let key = derive_from_passphrase(passphrase, &info)?;
+        Self::with_key(key, name, Some(info))
+    }
+
+    fn with_key(
+        key: [u8; KEY_LENGTH],
+        name: Option<&str>,
+        passphrase: Option<PassphraseInfo>,
+    ) -> Result<Self, CryptoError> {
+        let check = encrypt_with_key(&key, "", &[0u8; KEY_LENGTH], random_iv())?;
+        Ok(Self {
+            key_id: Alphanumeric.sample_string(&mut rand::rng(), 32),
+            key,
+            description: SecretStorageKeyDescription {
+                algorithm: SECRET_STORAGE_V1_ALGORITHM.to_string(),
+                name: name.map(str::to_string),
+                passphrase,
+                iv: Some(check.iv),
+                mac: Some(check.mac),
+            },
+        })
+    }
+
+    /// Check a candidate key against the description's `iv` and `mac`
+    fn open(
+        key_id: &str,
+        description: SecretStorageKeyDescription,
+        key: [u8; KEY_LENGTH],
+    ) -> Result<Self, CryptoError> {
+        if description.algorithm != SECRET_STORAGE_V1_ALGORITHM {
+            return Err(CryptoError::UnsupportedAlgorithm(description.algorithm));
+        }
+
+        if let (Some(iv), Some(mac)) = (&description.iv, &description.mac) {
+            let (aes_key, hmac_key) = derive_keys(&key, "")?;
+            let mut zeros = [0u8; KEY_LENGTH];
+            apply_keystream(&aes_key, &decode_iv(iv)?, &mut zeros)?;
+            if !mac_matches(&hmac_key, &zeros, mac)? {
+                return Err(CryptoError::InvalidKey {
+                    key: key_id.to_string(),
+                    message: "wrong recovery key or passphrase".to_string(),
+                });
+            }
+        }
+
+        Ok(Self { key_id: key_id.to_string(), key, description })
+    }
+}
+
+fn derive_from_passphrase(
+    passphrase: &str,
+    info: &PassphraseInfo,
+) -> Result<[u8; KEY_LENGTH], CryptoError> {
+    if info.algorithm != PBKDF2_ALGORITHM {
+        return Err(CryptoError::UnsupportedAlgorithm(info.algorithm.clone()));
+    }
+    if info.bits.unwrap_or(KEY_BITS) != KEY_BITS {
+        return Err(CryptoError::InvalidKey {
+            key: "passphrase".to_string(),
+            message: format!("unsupported key length of {} bits", info.bits.unwrap_or_default()),
+        });
+    }
+
+    let mut key = [0u8; KEY_LENGTH];
+    pbkdf2::pbkdf2_hmac::<Sha512>(
+        passphrase.as_bytes(),
+        info.salt.as_bytes(),
+        info.iterations,
+        &mut key,
+    );
+    Ok(key)
+}
+
+fn decode_recovery_key(recovery_key: &str) -> Result<[u8; KEY_LENGTH], CryptoError> {
+    let invalid = |message: &str| CryptoError::InvalidKey {
+        key: "recovery_key".to_string(),
+        message: message.to_string(),
+    };
+
+    let compact: String = recovery_key.chars().filter(|c| !c.is_whitespace()).collect();
+    let bytes = bs58::decode(compact)
+        .into_vec()
+        .map_err(|_| invalid("not a base58 string"))?;
+
+    if bytes.len() != RECOVERY_KEY_PREFIX.len() + KEY_LENGTH + 1 {
+        return Err(invalid("wrong length"));
+    }
+    if bytes[..RECOVERY_KEY_PREFIX.len()] != RECOVERY_KEY_PREFIX {
+        return Err(invalid("wrong prefix"));
+    }
+    if bytes.iter().fold(0, |parity, byte| parity ^ byte) != 0 {
+        return Err(invalid("parity check failed"));
+    }
+
+    let mut key = [0u8; KEY_LENGTH];
+    key.copy_from_slice(&bytes[RECOVERY_KEY_PREFIX.len()..RECOVERY_KEY_PREFIX.len() + KEY_LENGTH]);
+    Ok(key)
+}
+
+/// AES-CTR and HMAC keys for one secret name
+fn derive_keys(
+    key: &[u8; KEY_LENGTH],
+    secret_name: &str,
+) -> Result<([u8; KEY_LENGTH], [u8; KEY_LENGTH]), CryptoError> {
+    let mut output = [0u8; 2 * KEY_LENGTH];
+    Hkdf::<Sha256>::new(Some(&[0u8; KEY_LENGTH]), key)
+        .expand(secret_name.as_bytes(), &mut output)
+        .map_err(|e| CryptoError::InvalidKey {
+            key: secret_name.to_string(),
+            message: e.to_string(),
+        })?;
+
+    let mut aes_key = [0u8; KEY_LENGTH];
+    let mut hmac_key = [0u8; KEY_LENGTH];
+    aes_key.copy_from_slice(&output[..KEY_LENGTH]);
+    hmac_key.copy_from_slice(&output[KEY_LENGTH..]);
+    Ok((aes_key, hmac_key))
+}
+
+fn encrypt_with_key(
+    key: &[u8; KEY_LENGTH],
+    secret_name: &str,
+    plaintext: &[u8],
+    iv: [u8; 16],
+) -> Result<EncryptedSecret, CryptoError> {
+    let (aes_key, hmac_key) = derive_keys(key, secret_name)?;
+    let mut ciphertext = plaintext.to_vec();
+    apply_keystream(&aes_key, &iv, &mut ciphertext)?;
+
+    let mut mac = hmac(&hmac_key)?;
+    mac.update(&ciphertext);
+
+    Ok(EncryptedSecret {
+        iv: STANDARD.encode(iv),
+        ciphertext: STANDARD.encode(&ciphertext),
+        mac: STANDARD.encode(mac.finalize().into_bytes()),
+    })
+}
+
+fn apply_keystream(
+    aes_key: &[u8; KEY_LENGTH],
+    iv: &[u8; 16],
+    data: &mut [u8],
+) -> Result<(), CryptoError> {
+    let mut cipher = Aes256Ctr::new_from_slices(aes_key, iv).map_err(|e| {
+        CryptoError::InvalidKey { key: "aes_key".to_string(), message: e.to_string() }
+    })?;
+    cipher.apply_keystream(data);
+    Ok(())
+}
+
+fn hmac(hmac_key: &[u8; KEY_LENGTH]) -> Result<HmacSha256, CryptoError> {
+    HmacSha256::new_from_slice(hmac_key).map_err(|e| CryptoError::InvalidKey {
+        key: "hmac_key".to_string(),
+        message: e.to_string(),
+    })
+}
+
+/// Constant-time check of a base64 MAC over `ciphertext`
+fn mac_matches(
+    hmac_key: &[u8; KEY_LENGTH],
+    ciphertext: &[u8],
+    mac: &str,
+) -> Result<bool, CryptoError> {
+    let mut verifier = hmac(hmac_key)?;
+    verifier.update(ciphertext);
+    Ok(verifier.verify_slice(&decode(mac)?).is_ok())
+}
+
+/// 16 random bytes with bit 63 cleared, so the counter cannot wrap into the nonce
+fn random_iv() -> [u8; 16] {
+    let mut iv = [0u8; 16];
+    rand::rng().fill_bytes(&mut iv);
+    iv[8] &= 0x7f;
+    iv
+}
+
+fn decode(value: &str) -> Result<Vec<u8>, CryptoError> {
+    base64_decode(value).map_err(|e| CryptoError::Decryption(e.to_string()))
+}
+
+fn decode_iv(value: &str) -> Result<[u8; 16], CryptoError> {
+    decode(value)?
+        .try_into()
+        .map_err(|_| CryptoError::Decryption("IV must be 16 bytes".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recovery_key_round_trip() {
+        let key = SecretStorageKey::new(Some("Recovery")).expect("key should be created");
+        let encrypted = key
+            .encrypt(MASTER_KEY_SECRET, "private key")
+            .expect("secret should encrypt");
+
+        let recovery_key = key.recovery_key();
+        assert!(recovery_key.starts_with("Es"));
+        assert!(recovery_key.split(' ').all(|group| group.len() <= 4));
+
+        let reopened = SecretStorageKey::from_recovery_key(
+            key.key_id(),
+            key.description().clone(),
+            &recovery_key,
+        )
+        .expect("recovery key should open the key");
+        assert_eq!(
+            reopened
+                .decrypt(MASTER_KEY_SECRET, &encrypted)
+                .expect("secret should decrypt"),
+            "private key"
+        );
+        assert!(reopened.decrypt(SELF_SIGNING_KEY_SECRET, &encrypted).is_err());
+
+        let other = SecretStorageKey::new(None).expect("key should be created");
+        let wrong = SecretStorageKey::from_recovery_key(
+            key.key_id(),
+            key.description().clone(),
+            &other.recovery_key(),
+        );
+        assert!(matches!(wrong, Err(CryptoError::InvalidKey { .. })));
+
+        let mut typo = recovery_key.into_bytes();
+        typo[5] = if typo[5] == b'a' { b'b' } else { b'a' };
+        let typo = String::from_utf8(typo).expect("recovery key is ASCII");
+        assert!(
+            SecretStorageKey::from_recovery_key(key.key_id(), key.description().clone(), &typo)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_passphrase_key() {
+        // Few iterations keep the test fast; real keys use PBKDF2_ITERATIONS
+        let key = SecretStorageKey::with_passphrase("correct horse", None, 1000)
+            .expect("key should derive");
+
+        let encrypted = key
+            .encrypt(MEGOLM_BACKUP_SECRET, "backup key")
+            .expect("secret should encrypt");
+        let reopened = SecretStorageKey::from_passphrase(
+            key.key_id(),
+            key.description().clone(),
+            "correct horse",
+        )
+        .expect("passphrase should open the key");
+        assert_eq!(
+            reopened
+                .decrypt(MEGOLM_BACKUP_SECRET, &encrypted)
+                .expect("secret should decrypt"),
+            "backup key"
+        );
+        assert!(
+            SecretStorageKey::from_passphrase(
+                key.key_id(),
+                key.description().clone(),
+                "wrong horse"
+            )
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,56 @@
+//! Signing JSON objects
+//!
+//! Implements the "Signing JSON" rules: `signatures` and `unsigned` are
+//! stripped and the remainder is canonicalised before signing or verifying.
+
+use matryx_entity::utils::canonical_json;
+use serde_json::{Value, json};
+use vodozemac::{Ed25519PublicKey, Ed25519Signature};
+
+use super::CryptoError;
+
+/// Canonical JSON of a value with `signatures` and `unsigned` removed
+pub(crate) fn signing_payload(value: &Value) -> Result<String, CryptoError> {
+    let mut unsigned = value.clone();
+    if let Some(object) = unsigned.as_object_mut() {
+        object.remove("signatures");
+        object.remove("unsigned");
+    }
+    Ok(canonical_json(&unsigned)?)
+}
+
+/// Add a signature under `signatures.<user_id>.<key_id>`
+pub(crate) fn add_signature(
+    value: &mut Value,
+    user_id: &str,
+    key_id: &str,
+    signature: &Ed25519Signature,
+) {
+    if let Some(object) = value.as_object_mut() {
+        let signatures = object.entry("signatures").or_insert_with(|| json!({}));
+        signatures[user_id][key_id] = Value::String(signature.to_base64());
+    }
+}
+
+/// Verify a signed JSON object against an Ed25519 key
+pub(crate) fn verify_json(
+    value: &Value,
+    user_id: &str,
+    key_id: &str,
+    ed25519_key: &str,
+) -> Result<(), String> {
+    let signature = value
+        .get("signatures")
+        .and_then(|s| s.get(user_id))
+        .and_then(|s| s.get(key_id))
+        .and_then(|s| s.as_str())
+        .ok_or_else(|| format!("no signature by {}", key_id))?;
+
+    let canonical = signing_payload(value).map_err(|e| e.to_string())?;
+
+    let public_key = Ed25519PublicKey::from_base64(ed25519_key).map_err(|e| e.to_string())?;
+    let signature = Ed25519Signature::from_base64(signature).map_err(|e| e.to_string())?;
+    public_key
+        .verify(canonical.as_bytes(), &signature)
+        .map_err(|e| e.to_string())
+}
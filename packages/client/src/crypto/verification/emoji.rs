@@ -0,0 +1,90 @@
+//! SAS emoji table
+//!
+//! Index `n` of the table is the emoji for the 6-bit value `n`, as listed in
+//! the "SAS method: emoji" section of the client-server specification.
+
+use serde::Serialize;
+
+/// An emoji with its English description
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SasEmoji {
+    pub symbol: &'static str,
+    pub description: &'static str,
+}
+
+const fn emoji(symbol: &'static str, description: &'static str) -> SasEmoji {
+    SasEmoji { symbol, description }
+}
+
+/// The 64 emojis used to render a short authentication string
+pub const EMOJI_TABLE: [SasEmoji; 64] = [
+    emoji("🐶", "Dog"),
+    emoji("🐱", "Cat"),
+    emoji("🦁", "Lion"),
+    emoji("🐎", "Horse"),
+    emoji("🦄", "Unicorn"),
+    emoji("🐷", "Pig"),
+    emoji("🐘", "Elephant"),
+    emoji("🐰", "Rabbit"),
+    emoji("🐼", "Panda"),
+    emoji("🐓", "Rooster"),
+    emoji("🐧", "Penguin"),
+    emoji("🐢", "Turtle"),
+    emoji("🐟", "Fish"),
+    emoji("🐙", "Octopus"),
+    emoji("🦋", "Butterfly"),
+    emoji("🌷", "Flower"),
+    emoji("🌳", "Tree"),
+    emoji("🌵", "Cactus"),
+    emoji("🍄", "Mushroom"),
+    emoji("🌏", "Globe"),
+    emoji("🌙", "Moon"),
+    emoji("☁️", "Cloud"),
+    emoji("🔥", "Fire"),
+    emoji("🍌", "Banana"),
+    emoji("🍎", "Apple"),
+    emoji("🍓", "Strawberry"),
+    emoji("🌽", "Corn"),
+    emoji("🍕", "Pizza"),
+    emoji("🎂", "Cake"),
+    emoji("❤️", "Heart"),
+    emoji("😀", "Smiley"),
+    emoji("🤖", "Robot"),
+    emoji("🎩", "Hat"),
+    emoji("👓", "Glasses"),
+    emoji("🔧", "Spanner"),
+    emoji("🎅", "Santa"),
+    emoji("👍", "Thumbs Up"),
+    emoji("☂️", "Umbrella"),
+    emoji("⌛", "Hourglass"),
+    emoji("⏰", "Clock"),
+    emoji("🎁", "Gift"),
+    emoji("💡", "Light Bulb"),
+    emoji("📕", "Book"),
+    emoji("✏️", "Pencil"),
+    emoji("📎", "Paperclip"),
+    emoji("✂️", "Scissors"),
+    emoji("🔒", "Lock"),
+    emoji("🔑", "Key"),
+    emoji("🔨", "Hammer"),
+    emoji("☎️", "Telephone"),
+    emoji("🏁", "Flag"),
+    emoji("🚂", "Train"),
+    emoji("🚲", "Bicycle"),
+    emoji("✈️", "Aeroplane"),
+    emoji("🚀", "Rocket"),
+    emoji("🏆", "Trophy"),
+    emoji("⚽", "Ball"),
+    emoji("🎸", "Guitar"),
+    emoji("🎺", "Trumpet"),
+    emoji("🔔", "Bell"),
+    emoji("⚓", "Anchor"),
+    emoji("🎧", "Headphones"),
+    emoji("📁", "Folder"),
+    emoji("📌", "Pin"),
+];
+
+/// Map the seven 6-bit SAS indices to emojis
+pub fn emojis_from_indices(indices: [u8; 7]) -> Vec<SasEmoji> {
+    indices.iter().map(|&i| EMOJI_TABLE[usize::from(i & 0x3f)]).collect()
+}
@@ -0,0 +1,1403 @@
+//! Verification flow bookkeeping
+//!
+//! Tracks every verification flow by its flow ID, routes incoming
+//! `m.key.verification.*` events to the right flow and applies the
+//! resulting trust to the [`OlmMachine`]'s device tracker.
+
+use chrono::{DateTime, Utc};
+use matryx_entity::types::{
+    QRReciprocateStart, SASVerificationStart, VerificationAccept, VerificationCancel,
+    VerificationDone, VerificationKey, VerificationMAC, VerificationReady, VerificationRelatesTo,
+    VerificationRequestInRoom, VerificationRequestToDevice,
+};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use tokio::sync::broadcast;
+use tracing::{debug, warn};
+
+use super::qr::{QrCode, QrCodeMode};
+use super::sas::{SasVerification, VerificationParty};
+use super::{
+    CancelCode, OutgoingVerificationMessage, QR_SCAN_V1_METHOD, QR_SHOW_V1_METHOD,
+    RECIPROCATE_V1_METHOD, SAS_V1_METHOD, VERIFICATION_TIMEOUT_SECS, VerificationError,
+    VerificationEvent, VerificationFlow,
+};
+use crate::crypto::{OlmMachine, SignatureUploadRequest};
+
+/// Requests from further in the future than this are ignored
+const MAX_REQUEST_CLOCK_SKEW_SECS: i64 = 5 * 60;
+
+/// Methods this client supports, in preference order
+const SUPPORTED_METHODS: [&str; 4] = [
+    SAS_V1_METHOD,
+    QR_SHOW_V1_METHOD,
+    QR_SCAN_V1_METHOD,
+    RECIPROCATE_V1_METHOD,
+];
+
+/// Trust established by a successful flow
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Trust {
+    Device { user_id: String, device_id: String },
+    MasterKey { user_id: String },
+}
+
+enum FlowState {
+    Requested,
+    Ready,
+    Sas(Box<SasVerification>),
+    /// The other device scanned our code and echoed the secret
+    QrScanned(QrCode),
+    /// We scanned the other device's code
+    Reciprocated,
+}
+
+struct Verification {
+    flow: VerificationFlow,
+    other_user: String,
+    /// Set once the other side sent a ready or start
+    other_device: Option<String>,
+    /// Devices a to-device request went to, to cancel the ones not answering
+    requested_devices: Vec<String>,
+    their_methods: Vec<String>,
+    state: FlowState,
+    /// Code we display, while waiting for the other side to scan it
+    shown_qr: Option<QrCode>,
+    done_sent: bool,
+    done_received: bool,
+    last_activity: DateTime<Utc>,
+}
+
+impl Verification {
+    fn new(flow: VerificationFlow, other_user: &str, state: FlowState) -> Self {
+        Self {
+            flow,
+            other_user: other_user.to_string(),
+            other_device: None,
+            requested_devices: Vec::new(),
+            their_methods: Vec::new(),
+            state,
+            shown_qr: None,
+            done_sent: false,
+            done_received: false,
+            last_activity: Utc::now(),
+        }
+    }
+}
+
+/// Drives all verification flows of the logged-in device
+pub struct VerificationMachine {
+    user_id: String,
+    device_id: String,
+    flows: HashMap<String, Verification>,
+    outgoing: Vec<OutgoingVerificationMessage>,
+    signature_uploads: Vec<SignatureUploadRequest>,
+    events: broadcast::Sender<VerificationEvent>,
+}
+
+impl std::fmt::Debug for VerificationMachine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VerificationMachine")
+            .field("user_id", &self.user_id)
+            .field("device_id", &self.device_id)
+            .field("flows", &self.flows.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl VerificationMachine {
+    pub fn new(user_id: &str, device_id: &str) -> Self {
+        let (events, _) = broadcast::channel(64);
+        Self {
+            user_id: user_id.to_string(),
+            device_id: device_id.to_string(),
+            flows: HashMap::new(),
+            outgoing: Vec::new(),
+            signature_uploads: Vec::new(),
+            events,
+        }
+    }
+
+    /// Subscribe to verification progress
+    pub fn subscribe(&self) -> broadcast::Receiver<VerificationEvent> {
+        self.events.subscribe()
+    }
+
+    /// Take the messages queued for sending
+    pub fn outgoing_messages(&mut self) -> Vec<OutgoingVerificationMessage> {
+        std::mem::take(&mut self.outgoing)
+    }
+
+    /// Take the cross-signing signatures queued for `/keys/signatures/upload`
+    pub fn signature_uploads(&mut self) -> Vec<SignatureUploadRequest> {
+        std::mem::take(&mut self.signature_uploads)
+    }
+
+    /// Whether a flow is still in progress
+    pub fn is_active(&self, flow_id: &str) -> bool {
+        self.flows.contains_key(flow_id)
+    }
+
+    /// SAS state of a flow, to render the short authentication string
+    pub fn sas(&self, flow_id: &str) -> Option<&SasVerification> {
+        match &self.flows.get(flow_id)?.state {
+            FlowState::Sas(sas) => Some(sas),
+            _ => None,
+        }
+    }
+
+    /// Ask devices of a user to verify over to-device messages
+    ///
+    /// Without `device_ids` the request goes to all of the user's devices;
+    /// the ones that did not answer are cancelled once one sends ready.
+    /// Returns the transaction ID.
+    pub fn request_to_device(
+        &mut self,
+        other_user: &str,
+        device_ids: Option<Vec<String>>,
+    ) -> String {
+        let transaction_id = uuid::Uuid::new_v4().to_string();
+        let flow = VerificationFlow::ToDevice { transaction_id: transaction_id.clone() };
+
+        let request = VerificationRequestToDevice::new(
+            self.device_id.clone(),
+            supported_methods(),
+            Utc::now().timestamp_millis(),
+            transaction_id.clone(),
+        );
+        let content = serde_json::to_value(request).unwrap_or_default();
+
+        let devices = device_ids.unwrap_or_else(|| vec!["*".to_string()]);
+        for device_id in &devices {
+            self.outgoing.push(OutgoingVerificationMessage::ToDevice {
+                user_id: other_user.to_string(),
+                device_id: device_id.clone(),
+                event_type: VerificationRequestToDevice::EVENT_TYPE.to_string(),
+                content: content.clone(),
+            });
+        }
+
+        let mut verification = Verification::new(flow, other_user, FlowState::Requested);
+        verification.requested_devices = devices;
+        self.flows.insert(transaction_id.clone(), verification);
+        transaction_id
+    }
+
+    /// `m.room.message` content asking a user to verify in a room
+    ///
+    /// Once sent, register the event ID with [`Self::room_request_sent`].
+    pub fn request_in_room_content(&self, other_user: &str) -> VerificationRequestInRoom {
+        VerificationRequestInRoom::new(
+            format!(
+                "{} is asking to verify your key, but your client does not support in-room verification.",
+                self.user_id
+            ),
+            None,
+            None,
+            self.device_id.clone(),
+            supported_methods(),
+            VerificationRequestInRoom::MSGTYPE.to_string(),
+            other_user.to_string(),
+        )
+    }
+
+    /// Track an in-room request we sent
+    pub fn room_request_sent(&mut self, room_id: &str, event_id: &str, other_user: &str) {
+        let flow = VerificationFlow::InRoom {
+            room_id: room_id.to_string(),
+            event_id: event_id.to_string(),
+        };
+        self.flows.insert(
+            event_id.to_string(),
+            Verification::new(flow, other_user, FlowState::Requested),
+        );
+    }
+
+    /// Accept a request from the other side by sending ready
+    pub fn accept_request(&mut self, flow_id: &str) -> Result<(), VerificationError> {
+        let own_device = self.device_id.clone();
+        let verification = self.flow_mut(flow_id)?;
+        if !matches!(verification.state, FlowState::Requested)
+            || verification.other_device.is_none()
+        {
+            return Err(invalid_state(flow_id, "accept the request"));
+        }
+
+        let methods = common_methods(&verification.their_methods);
+        verification.state = FlowState::Ready;
+        let flow = verification.flow.clone();
+
+        let ready = VerificationReady::new(own_device, None, methods.clone(), None);
+        self.send(flow_id, VerificationReady::EVENT_TYPE, &ready)?;
+        self.emit(VerificationEvent::Ready { flow, methods });
+        Ok(())
+    }
+
+    /// Start SAS verification on a ready flow
+    pub fn start_sas(&mut self, flow_id: &str) -> Result<(), VerificationError> {
+        let own = self.own_party();
+        let start = SasVerification::start_content(&self.device_id);
+        let verification = self.flow_mut(flow_id)?;
+        let other = other_party(verification, flow_id)?;
+        if !matches!(verification.state, FlowState::Ready) {
+            return Err(invalid_state(flow_id, "start SAS"));
+        }
+
+        let content = address(&verification.flow, &start)?;
+        verification.state = FlowState::Sas(Box::new(SasVerification::new_outgoing(
+            flow_id,
+            own,
+            other,
+            content.clone(),
+        )));
+        self.send_value(flow_id, SASVerificationStart::EVENT_TYPE, content)
+    }
+
+    /// Accept the SAS start the other side sent
+    pub fn accept_sas(&mut self, flow_id: &str) -> Result<(), VerificationError> {
+        let accept = match &mut self.flow_mut(flow_id)?.state {
+            FlowState::Sas(sas) if !sas.we_started() => sas.accept()?,
+            _ => return Err(invalid_state(flow_id, "accept SAS")),
+        };
+        self.send(flow_id, VerificationAccept::EVENT_TYPE, &accept)
+    }
+
+    /// The user confirmed the short authentication strings match
+    pub fn confirm_sas(
+        &mut self,
+        olm: &mut OlmMachine,
+        flow_id: &str,
+    ) -> Result<(), VerificationError> {
+        let keys = own_mac_keys(olm);
+        let mac = match &mut self.flow_mut(flow_id)?.state {
+            FlowState::Sas(sas) if sas.keys_exchanged() && !sas.is_confirmed() => {
+                sas.confirm(&keys)?
+            },
+            _ => return Err(invalid_state(flow_id, "confirm SAS")),
+        };
+        self.send(flow_id, VerificationMAC::EVENT_TYPE, &mac)?;
+        match self.try_finish_sas(olm, flow_id) {
+            Ok(()) => Ok(()),
+            Err(e) => self.fail(flow_id, e),
+        }
+    }
+
+    /// The user reported that the short authentication strings differ
+    pub fn mismatch_sas(&mut self, flow_id: &str) -> Result<(), VerificationError> {
+        self.cancel(flow_id, CancelCode::MismatchedSas)
+    }
+
+    /// Build the QR code to display on a ready flow
+    pub fn generate_qr_code(
+        &mut self,
+        olm: &OlmMachine,
+        flow_id: &str,
+    ) -> Result<QrCode, VerificationError> {
+        let own_user = self.user_id.clone();
+        let verification = self.flow_mut(flow_id)?;
+        if !matches!(verification.state, FlowState::Ready) {
+            return Err(invalid_state(flow_id, "show a QR code"));
+        }
+
+        let missing = |what: &str| {
+            VerificationError::cancel(
+                CancelCode::UnknownMethod,
+                format!("no {} for a QR code", what),
+            )
+        };
+        let own_master_key = olm.own_master_key().ok_or_else(|| missing("master key"))?;
+        let code = if verification.other_user != own_user {
+            let their_master_key = olm
+                .device_tracker()
+                .master_key(&verification.other_user)
+                .ok_or_else(|| missing("master key of the other user"))?;
+            QrCode::new(
+                QrCodeMode::VerifyingAnotherUser,
+                flow_id,
+                &own_master_key,
+                &their_master_key,
+            )
+        } else if trusts_own_master_key(olm) {
+            let other_device = other_party(verification, flow_id)?.device_id;
+            let device_key = olm
+                .device_tracker()
+                .get_device(&own_user, &other_device)
+                .map(|device| device.ed25519_key.clone())
+                .ok_or_else(|| missing("key of the other device"))?;
+            QrCode::new(QrCodeMode::SelfVerifyingTrusted, flow_id, &own_master_key, &device_key)
+        } else {
+            QrCode::new(
+                QrCodeMode::SelfVerifyingUntrusted,
+                flow_id,
+                &olm.ed25519_key(),
+                &own_master_key,
+            )
+        };
+
+        verification.shown_qr = Some(code.clone());
+        Ok(code)
+    }
+
+    /// Check a QR code scanned from the other device and reciprocate
+    ///
+    /// Returns the flow ID embedded in the code.
+    pub fn scan_qr_code(
+        &mut self,
+        olm: &mut OlmMachine,
+        bytes: &[u8],
+    ) -> Result<String, VerificationError> {
+        let code = QrCode::from_bytes(bytes)?;
+        let flow_id = code.flow_id.clone();
+        match self.check_scanned_code(olm, &code) {
+            Ok(trust) => {
+                let verification = self.flow_mut(&flow_id)?;
+                verification.state = FlowState::Reciprocated;
+                let start = QRReciprocateStart::new(
+                    self.device_id.clone(),
+                    None,
+                    RECIPROCATE_V1_METHOD.to_string(),
+                    code.secret_base64(),
+                    None,
+                );
+                self.send(&flow_id, QRReciprocateStart::EVENT_TYPE, &start)?;
+                self.succeed(olm, &flow_id, &[trust])?;
+                Ok(flow_id)
+            },
+            Err(e) if self.flows.contains_key(&flow_id) => {
+                self.fail(&flow_id, e)?;
+                Ok(flow_id)
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    /// The user confirmed the other device shows a successful scan of our code
+    pub fn confirm_qr_scanned(
+        &mut self,
+        olm: &mut OlmMachine,
+        flow_id: &str,
+    ) -> Result<(), VerificationError> {
+        let own_user = self.user_id.clone();
+        let verification = self.flow_mut(flow_id)?;
+        let FlowState::QrScanned(code) = &verification.state else {
+            return Err(invalid_state(flow_id, "confirm a QR scan"));
+        };
+
+        let trust = match code.mode {
+            QrCodeMode::VerifyingAnotherUser => {
+                Trust::MasterKey { user_id: verification.other_user.clone() }
+            },
+            QrCodeMode::SelfVerifyingTrusted => Trust::Device {
+                user_id: own_user,
+                device_id: other_party(verification, flow_id)?.device_id,
+            },
+            QrCodeMode::SelfVerifyingUntrusted => Trust::MasterKey { user_id: own_user },
+        };
+        self.succeed(olm, flow_id, &[trust])
+    }
+
+    /// Cancel a flow and tell the other side
+    pub fn cancel(&mut self, flow_id: &str, code: CancelCode) -> Result<(), VerificationError> {
+        let reason = code.reason().to_string();
+        self.fail(flow_id, VerificationError::Cancelled { code, reason })
+    }
+
+    /// Cancel flows without progress for [`VERIFICATION_TIMEOUT_SECS`]
+    pub fn cancel_stale(&mut self, now: DateTime<Utc>) {
+        let stale: Vec<String> = self
+            .flows
+            .iter()
+            .filter(|(_, v)| (now - v.last_activity).num_seconds() >= VERIFICATION_TIMEOUT_SECS)
+            .map(|(flow_id, _)| flow_id.clone())
+            .collect();
+        for flow_id in stale {
+            if let Err(e) = self.cancel(&flow_id, CancelCode::Timeout) {
+                warn!("Failed to cancel stale verification {}: {}", flow_id, e);
+            }
+        }
+    }
+
+    /// Handle a to-device event; returns whether it was a verification event
+    pub fn receive_to_device_event(
+        &mut self,
+        olm: &mut OlmMachine,
+        sender: &str,
+        event_type: &str,
+        content: &Value,
+    ) -> bool {
+        if !event_type.starts_with("m.key.verification.") {
+            return false;
+        }
+
+        if event_type == VerificationRequestToDevice::EVENT_TYPE {
+            self.receive_to_device_request(sender, content);
+            return true;
+        }
+
+        let Some(flow_id) = content.get("transaction_id").and_then(Value::as_str) else {
+            warn!("Ignoring {} from {} without a transaction ID", event_type, sender);
+            return true;
+        };
+
+        if !self.flows.contains_key(flow_id) {
+            if event_type != VerificationCancel::EVENT_TYPE {
+                debug!("Cancelling unknown verification transaction {} from {}", flow_id, sender);
+                let device_id = content.get("from_device").and_then(Value::as_str).unwrap_or("*");
+                let cancel = VerificationCancel::new(
+                    CancelCode::UnknownTransaction.as_str().to_string(),
+                    None,
+                    CancelCode::UnknownTransaction.reason().to_string(),
+                    Some(flow_id.to_string()),
+                );
+                self.outgoing.push(OutgoingVerificationMessage::ToDevice {
+                    user_id: sender.to_string(),
+                    device_id: device_id.to_string(),
+                    event_type: VerificationCancel::EVENT_TYPE.to_string(),
+                    content: serde_json::to_value(cancel).unwrap_or_default(),
+                });
+            }
+            return true;
+        }
+
+        let flow_id = flow_id.to_string();
+        self.receive_flow_event(olm, &flow_id, sender, event_type, content);
+        true
+    }
+
+    /// Handle a room timeline event; returns whether it was a verification event
+    pub fn receive_room_event(
+        &mut self,
+        olm: &mut OlmMachine,
+        room_id: &str,
+        event_id: &str,
+        sender: &str,
+        event_type: &str,
+        content: &Value,
+    ) -> bool {
+        let is_request = event_type == "m.room.message"
+            && content.get("msgtype").and_then(Value::as_str)
+                == Some(VerificationRequestInRoom::MSGTYPE);
+        if !is_request && !event_type.starts_with("m.key.verification.") {
+            return false;
+        }
+
+        // In-room verification is only between users; skip our own echoes
+        if sender == self.user_id {
+            return true;
+        }
+
+        if is_request {
+            self.receive_room_request(room_id, event_id, sender, content);
+            return true;
+        }
+
+        let Some(flow_id) = content
+            .get("m.relates_to")
+            .and_then(|relates_to| relates_to.get("event_id"))
+            .and_then(Value::as_str)
+        else {
+            return true;
+        };
+        if !self.flows.contains_key(flow_id) {
+            return true;
+        }
+
+        let flow_id = flow_id.to_string();
+        self.receive_flow_event(olm, &flow_id, sender, event_type, content);
+        true
+    }
+
+    fn receive_to_device_request(&mut self, sender: &str, content: &Value) {
+        let Ok(request) = parse::<VerificationRequestToDevice>(content) else {
+            warn!("Ignoring malformed verification request from {}", sender);
+            return;
+        };
+        if sender == self.user_id && request.from_device == self.device_id {
+            return;
+        }
+
+        let age = Utc::now().timestamp_millis() - request.timestamp;
+        if age > VERIFICATION_TIMEOUT_SECS * 1000 || -age > MAX_REQUEST_CLOCK_SKEW_SECS * 1000 {
+            debug!(
+                "Ignoring stale verification request {} from {}",
+                request.transaction_id, sender
+            );
+            return;
+        }
+
+        let flow = VerificationFlow::ToDevice { transaction_id: request.transaction_id.clone() };
+        self.insert_request(flow, sender, request.from_device, request.methods);
+    }
+
+    fn receive_room_request(
+        &mut self,
+        room_id: &str,
+        event_id: &str,
+        sender: &str,
+        content: &Value,
+    ) {
+        let Ok(request) = parse::<VerificationRequestInRoom>(content) else {
+            warn!("Ignoring malformed verification request {} from {}", event_id, sender);
+            return;
+        };
+        if request.to != self.user_id {
+            return;
+        }
+
+        let flow = VerificationFlow::InRoom {
+            room_id: room_id.to_string(),
+            event_id: event_id.to_string(),
+        };
+        self.insert_request(flow, sender, request.from_device, request.methods);
+    }
+
+    fn insert_request(
+        &mut self,
+        flow: VerificationFlow,
+        sender: &str,
+        from_device: String,
+        methods: Vec<String>,
+    ) {
+        let flow_id = flow.flow_id().to_string();
+        if self.flows.contains_key(&flow_id) {
+            return;
+        }
+
+        let mut verification = Verification::new(flow.clone(), sender, FlowState::Requested);
+        verification.other_device = Some(from_device.clone());
+        verification.their_methods = methods.clone();
+        self.flows.insert(flow_id, verification);
+
+        self.emit(VerificationEvent::Requested {
+            flow,
+            user_id: sender.to_string(),
+            device_id: from_device,
+            methods,
+        });
+    }
+
+    fn receive_flow_event(
+        &mut self,
+        olm: &mut OlmMachine,
+        flow_id: &str,
+        sender: &str,
+        event_type: &str,
+        content: &Value,
+    ) {
+        let Some(verification) = self.flows.get_mut(flow_id) else {
+            return;
+        };
+        if verification.other_user != sender {
+            warn!("Ignoring {} for flow {} from unexpected sender {}", event_type, flow_id, sender);
+            return;
+        }
+        verification.last_activity = Utc::now();
+
+        let result = match event_type {
+            VerificationReady::EVENT_TYPE => self.receive_ready(olm, flow_id, content),
+            SASVerificationStart::EVENT_TYPE => self.receive_start(flow_id, content),
+            VerificationAccept::EVENT_TYPE => self.receive_accept(flow_id, content),
+            VerificationKey::EVENT_TYPE => self.receive_key(flow_id, content),
+            VerificationMAC::EVENT_TYPE => self.receive_mac(olm, flow_id, content),
+            VerificationDone::EVENT_TYPE => self.receive_done(flow_id),
+            VerificationCancel::EVENT_TYPE => {
+                self.receive_cancel(flow_id, content);
+                Ok(())
+            },
+            _ => Err(VerificationError::cancel(
+                CancelCode::UnexpectedMessage,
+                format!("unknown verification event {}", event_type),
+            )),
+        };
+
+        if let Err(e) = result
+            && let Err(e) = self.fail(flow_id, e)
+        {
+            warn!("Failed to cancel verification {}: {}", flow_id, e);
+        }
+    }
+
+    fn receive_ready(
+        &mut self,
+        olm: &OlmMachine,
+        flow_id: &str,
+        content: &Value,
+    ) -> Result<(), VerificationError> {
+        let ready: VerificationReady = parse(content)?;
+        let verification = self.flow_mut(flow_id)?;
+        if !matches!(verification.state, FlowState::Requested)
+            || verification.other_device.is_some()
+        {
+            return Err(unexpected("ready"));
+        }
+
+        verification.other_device = Some(ready.from_device.clone());
+        verification.their_methods = ready.methods.clone();
+        verification.state = FlowState::Ready;
+        let flow = verification.flow.clone();
+        let other_user = verification.other_user.clone();
+        let requested_devices = std::mem::take(&mut verification.requested_devices);
+
+        // Tell the other devices we asked that someone else answered
+        if let VerificationFlow::ToDevice { transaction_id } = &flow {
+            let asked_all = requested_devices.iter().any(|d| d == "*");
+            let others: Vec<String> = if asked_all {
+                olm.device_tracker()
+                    .user_devices(&other_user)
+                    .map(|device| device.device_id.clone())
+                    .collect()
+            } else {
+                requested_devices
+            };
+            let cancel = VerificationCancel::new(
+                CancelCode::Accepted.as_str().to_string(),
+                None,
+                CancelCode::Accepted.reason().to_string(),
+                Some(transaction_id.clone()),
+            );
+            for device_id in others {
+                if device_id == ready.from_device || device_id == self.device_id {
+                    continue;
+                }
+                self.outgoing.push(OutgoingVerificationMessage::ToDevice {
+                    user_id: other_user.clone(),
+                    device_id,
+                    event_type: VerificationCancel::EVENT_TYPE.to_string(),
+                    content: serde_json::to_value(&cancel).unwrap_or_default(),
+                });
+            }
+        }
+
+        let methods = common_methods(&ready.methods);
+        self.emit(VerificationEvent::Ready { flow, methods });
+        Ok(())
+    }
+
+    fn receive_start(&mut self, flow_id: &str, content: &Value) -> Result<(), VerificationError> {
+        let method = content.get("method").and_then(Value::as_str).unwrap_or_default();
+        match method {
+            SAS_V1_METHOD => self.receive_sas_start(flow_id, content),
+            RECIPROCATE_V1_METHOD => self.receive_reciprocate(flow_id, content),
+            other => Err(VerificationError::cancel(
+                CancelCode::UnknownMethod,
+                format!("unsupported method {}", other),
+            )),
+        }
+    }
+
+    fn receive_sas_start(
+        &mut self,
+        flow_id: &str,
+        content: &Value,
+    ) -> Result<(), VerificationError> {
+        let own = self.own_party();
+        let verification = self.flow_mut(flow_id)?;
+        let other = other_party(verification, flow_id)?;
+
+        match &verification.state {
+            FlowState::Ready => {},
+            // Both sides started: the lexicographically smaller party's start wins
+            FlowState::Sas(sas) if sas.we_started() => {
+                if (&own.user_id, &own.device_id) < (&other.user_id, &other.device_id) {
+                    return Ok(());
+                }
+            },
+            _ => return Err(unexpected("start")),
+        }
+
+        let sas = SasVerification::from_start(flow_id, own, other, content)?;
+        verification.state = FlowState::Sas(Box::new(sas));
+        let flow = verification.flow.clone();
+        self.emit(VerificationEvent::SasStarted { flow });
+        Ok(())
+    }
+
+    fn receive_reciprocate(
+        &mut self,
+        flow_id: &str,
+        content: &Value,
+    ) -> Result<(), VerificationError> {
+        let start: QRReciprocateStart = parse(content)?;
+        let verification = self.flow_mut(flow_id)?;
+        if !matches!(verification.state, FlowState::Ready) {
+            return Err(unexpected("reciprocate"));
+        }
+        let code = verification.shown_qr.take().ok_or_else(|| unexpected("reciprocate"))?;
+        if start.secret != code.secret_base64() {
+            return Err(VerificationError::cancel(
+                CancelCode::KeyMismatch,
+                "the QR code secret does not match",
+            ));
+        }
+
+        verification.state = FlowState::QrScanned(code);
+        let flow = verification.flow.clone();
+        self.emit(VerificationEvent::QrCodeScanned { flow });
+        Ok(())
+    }
+
+    fn receive_accept(&mut self, flow_id: &str, content: &Value) -> Result<(), VerificationError> {
+        let accept: VerificationAccept = parse(content)?;
+        let key = match &mut self.flow_mut(flow_id)?.state {
+            FlowState::Sas(sas) => sas.receive_accept(&accept)?,
+            _ => return Err(unexpected("accept")),
+        };
+        self.send(flow_id, VerificationKey::EVENT_TYPE, &key)
+    }
+
+    fn receive_key(&mut self, flow_id: &str, content: &Value) -> Result<(), VerificationError> {
+        let key: VerificationKey = parse(content)?;
+        let verification = self.flow_mut(flow_id)?;
+        let FlowState::Sas(sas) = &mut verification.state else {
+            return Err(unexpected("key"));
+        };
+
+        let reply = sas.receive_key(&key)?;
+        let event = VerificationEvent::SasKeysExchanged {
+            flow: verification.flow.clone(),
+            emojis: sas.emojis().unwrap_or_default(),
+            decimals: sas.decimals().unwrap_or_default(),
+        };
+
+        if let Some(reply) = reply {
+            self.send(flow_id, VerificationKey::EVENT_TYPE, &reply)?;
+        }
+        self.emit(event);
+        Ok(())
+    }
+
+    fn receive_mac(
+        &mut self,
+        olm: &mut OlmMachine,
+        flow_id: &str,
+        content: &Value,
+    ) -> Result<(), VerificationError> {
+        let mac: VerificationMAC = parse(content)?;
+        match &mut self.flow_mut(flow_id)?.state {
+            FlowState::Sas(sas) => sas.receive_mac(mac)?,
+            _ => return Err(unexpected("MAC")),
+        }
+        self.try_finish_sas(olm, flow_id)
+    }
+
+    fn receive_done(&mut self, flow_id: &str) -> Result<(), VerificationError> {
+        let verification = self.flow_mut(flow_id)?;
+        verification.done_received = true;
+        if verification.done_sent {
+            self.finish(flow_id);
+        }
+        Ok(())
+    }
+
+    fn receive_cancel(&mut self, flow_id: &str, content: &Value) {
+        let Some(verification) = self.flows.remove(flow_id) else {
+            return;
+        };
+        let code = content.get("code").and_then(Value::as_str).unwrap_or("m.user");
+        let reason = content.get("reason").and_then(Value::as_str).unwrap_or_default();
+        debug!("Verification {} cancelled by {}: {}", flow_id, verification.other_user, code);
+
+        self.emit(VerificationEvent::Cancelled {
+            flow: verification.flow,
+            code: CancelCode::parse(code),
+            reason: reason.to_string(),
+            by_us: false,
+        });
+    }
+
+    /// Verify the other side's MAC once the user confirmed and it arrived
+    fn try_finish_sas(
+        &mut self,
+        olm: &mut OlmMachine,
+        flow_id: &str,
+    ) -> Result<(), VerificationError> {
+        let own_user = self.user_id.clone();
+        let verification = self.flow_mut(flow_id)?;
+        let other = other_party(verification, flow_id)?;
+        let FlowState::Sas(sas) = &mut verification.state else {
+            return Err(unexpected("MAC"));
+        };
+        if !sas.is_confirmed() || !sas.has_their_mac() {
+            return Ok(());
+        }
+
+        let mut known_keys = HashMap::new();
+        let device_key_id = format!("ed25519:{}", other.device_id);
+        if let Some(device) = olm.device_tracker().get_device(&other.user_id, &other.device_id) {
+            known_keys.insert(device_key_id.clone(), device.ed25519_key.clone());
+        }
+        let master_key = if other.user_id == own_user {
+            olm.own_master_key()
+        } else {
+            olm.device_tracker().master_key(&other.user_id)
+        };
+        if let Some(master_key) = &master_key {
+            known_keys.insert(format!("ed25519:{}", master_key), master_key.clone());
+        }
+
+        let verified = sas.verify_their_mac(&known_keys)?;
+        let trust: Vec<Trust> = verified
+            .iter()
+            .map(|key_id| {
+                if *key_id == device_key_id {
+                    Trust::Device {
+                        user_id: other.user_id.clone(),
+                        device_id: other.device_id.clone(),
+                    }
+                } else {
+                    Trust::MasterKey { user_id: other.user_id.clone() }
+                }
+            })
+            .collect();
+        self.succeed(olm, flow_id, &trust)
+    }
+
+    /// Record the trust a flow established and send done
+    fn succeed(
+        &mut self,
+        olm: &mut OlmMachine,
+        flow_id: &str,
+        trust: &[Trust],
+    ) -> Result<(), VerificationError> {
+        for trust in trust {
+            let signatures = match trust {
+                Trust::Device { user_id, device_id } => {
+                    olm.device_tracker_mut().mark_device_verified(user_id, device_id);
+                    if *user_id == self.user_id {
+                        olm.signatures_for_verified(user_id, device_id)?
+                    } else {
+                        None
+                    }
+                },
+                Trust::MasterKey { user_id } => {
+                    olm.device_tracker_mut().mark_user_verified(user_id);
+                    if *user_id != self.user_id {
+                        olm.signatures_for_verified(user_id, "")?
+                    } else {
+                        None
+                    }
+                },
+            };
+            self.signature_uploads.extend(signatures);
+        }
+
+        let done = VerificationDone::new(None, None);
+        self.send(flow_id, VerificationDone::EVENT_TYPE, &done)?;
+        let verification = self.flow_mut(flow_id)?;
+        verification.done_sent = true;
+        if verification.done_received {
+            self.finish(flow_id);
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, flow_id: &str) {
+        let Some(verification) = self.flows.remove(flow_id) else {
+            return;
+        };
+        self.emit(VerificationEvent::Done {
+            flow: verification.flow,
+            user_id: verification.other_user,
+            device_id: verification.other_device.unwrap_or_default(),
+        });
+    }
+
+    /// Cancel a flow because of an error, sending the matching cancel code
+    fn fail(&mut self, flow_id: &str, error: VerificationError) -> Result<(), VerificationError> {
+        let (code, reason) = match error {
+            VerificationError::Cancelled { code, reason } => (code, reason),
+            VerificationError::Crypto(e) => (CancelCode::InvalidMessage, e.to_string()),
+            other => return Err(other),
+        };
+        warn!("Cancelling verification {}: {}", flow_id, reason);
+
+        let cancel = VerificationCancel::new(code.as_str().to_string(), None, reason.clone(), None);
+        self.send(flow_id, VerificationCancel::EVENT_TYPE, &cancel)?;
+        if let Some(verification) = self.flows.remove(flow_id) {
+            self.emit(VerificationEvent::Cancelled {
+                flow: verification.flow,
+                code,
+                reason,
+                by_us: true,
+            });
+        }
+        Ok(())
+    }
+
+    fn send<T: Serialize>(
+        &mut self,
+        flow_id: &str,
+        event_type: &str,
+        content: &T,
+    ) -> Result<(), VerificationError> {
+        let flow = self.flow_mut(flow_id)?.flow.clone();
+        let content = address(&flow, content)?;
+        self.send_value(flow_id, event_type, content)
+    }
+
+    /// Queue already addressed content for the other side of a flow
+    fn send_value(
+        &mut self,
+        flow_id: &str,
+        event_type: &str,
+        content: Value,
+    ) -> Result<(), VerificationError> {
+        let verification = self.flow_mut(flow_id)?;
+        let message = match &verification.flow {
+            VerificationFlow::ToDevice { .. } => OutgoingVerificationMessage::ToDevice {
+                user_id: verification.other_user.clone(),
+                device_id: verification.other_device.clone().unwrap_or_else(|| "*".to_string()),
+                event_type: event_type.to_string(),
+                content,
+            },
+            VerificationFlow::InRoom { room_id, .. } => OutgoingVerificationMessage::InRoom {
+                room_id: room_id.clone(),
+                event_type: event_type.to_string(),
+                content,
+            },
+        };
+        verification.last_activity = Utc::now();
+        self.outgoing.push(message);
+        Ok(())
+    }
+
+    fn check_scanned_code(
+        &self,
+        olm: &OlmMachine,
+        code: &QrCode,
+    ) -> Result<Trust, VerificationError> {
+        let verification = self
+            .flows
+            .get(&code.flow_id)
+            .ok_or_else(|| VerificationError::UnknownFlow(code.flow_id.clone()))?;
+        if !matches!(verification.state, FlowState::Ready) {
+            return Err(invalid_state(&code.flow_id, "scan a QR code"));
+        }
+
+        let mismatch = |what: &str| {
+            VerificationError::cancel(CancelCode::KeyMismatch, format!("QR code {} mismatch", what))
+        };
+        let own_master_key = olm.own_master_key();
+        let tracker = olm.device_tracker();
+
+        match code.mode {
+            QrCodeMode::VerifyingAnotherUser => {
+                if verification.other_user == self.user_id {
+                    return Err(mismatch("mode"));
+                }
+                if tracker.master_key(&verification.other_user).as_deref() != Some(&code.first_key)
+                {
+                    return Err(mismatch("master key"));
+                }
+                if own_master_key.as_deref() != Some(&code.second_key) {
+                    return Err(mismatch("own master key"));
+                }
+                Ok(Trust::MasterKey { user_id: verification.other_user.clone() })
+            },
+            QrCodeMode::SelfVerifyingTrusted => {
+                if verification.other_user != self.user_id {
+                    return Err(mismatch("mode"));
+                }
+                if own_master_key.as_deref() != Some(&code.first_key) {
+                    return Err(mismatch("master key"));
+                }
+                if olm.ed25519_key() != code.second_key {
+                    return Err(mismatch("device key"));
+                }
+                Ok(Trust::MasterKey { user_id: self.user_id.clone() })
+            },
+            QrCodeMode::SelfVerifyingUntrusted => {
+                if verification.other_user != self.user_id || !trusts_own_master_key(olm) {
+                    return Err(mismatch("mode"));
+                }
+                let other_device = other_party(verification, &code.flow_id)?.device_id;
+                let device_key = tracker
+                    .get_device(&self.user_id, &other_device)
+                    .map(|d| d.ed25519_key.as_str());
+                if device_key != Some(code.first_key.as_str()) {
+                    return Err(mismatch("device key"));
+                }
+                if own_master_key.as_deref() != Some(&code.second_key) {
+                    return Err(mismatch("master key"));
+                }
+                Ok(Trust::Device {
+                    user_id: self.user_id.clone(),
+                    device_id: other_device,
+                })
+            },
+        }
+    }
+
+    fn flow_mut(&mut self, flow_id: &str) -> Result<&mut Verification, VerificationError> {
+        self.flows
+            .get_mut(flow_id)
+            .ok_or_else(|| VerificationError::UnknownFlow(flow_id.to_string()))
+    }
+
+    fn own_party(&self) -> VerificationParty {
+        VerificationParty::new(&self.user_id, &self.device_id)
+    }
+
+    fn emit(&self, event: VerificationEvent) {
+        // Nobody listening is fine; the state is kept regardless
+        let _ = self.events.send(event);
+    }
+}
+
+/// Serialize content and tie it to its flow
+fn address<T: Serialize>(flow: &VerificationFlow, content: &T) -> Result<Value, VerificationError> {
+    let mut content = serde_json::to_value(content).map_err(crate::crypto::CryptoError::from)?;
+    if let Some(object) = content.as_object_mut() {
+        match flow {
+            VerificationFlow::ToDevice { transaction_id } => {
+                object.insert("transaction_id".to_string(), Value::String(transaction_id.clone()));
+            },
+            VerificationFlow::InRoom { event_id, .. } => {
+                let relates_to = VerificationRelatesTo::reference(event_id.clone());
+                object.insert(
+                    "m.relates_to".to_string(),
+                    serde_json::to_value(relates_to).map_err(crate::crypto::CryptoError::from)?,
+                );
+            },
+        }
+    }
+    Ok(content)
+}
+
+fn parse<T: DeserializeOwned>(content: &Value) -> Result<T, VerificationError> {
+    serde_json::from_value(content.clone())
+        .map_err(|e| VerificationError::cancel(CancelCode::InvalidMessage, e.to_string()))
+}
+
+fn other_party(
+    verification: &Verification,
+    flow_id: &str,
+) -> Result<VerificationParty, VerificationError> {
+    let device_id = verification
+        .other_device
+        .as_deref()
+        .ok_or_else(|| invalid_state(flow_id, "continue before the other device answered"))?;
+    Ok(VerificationParty::new(&verification.other_user, device_id))
+}
+
+/// Keys we MAC during SAS: our device key and, if trusted, our master key
+fn own_mac_keys(olm: &OlmMachine) -> BTreeMap<String, String> {
+    let mut keys = BTreeMap::new();
+    keys.insert(format!("ed25519:{}", olm.device_id()), olm.ed25519_key());
+    if trusts_own_master_key(olm)
+        && let Some(master_key) = olm.own_master_key()
+    {
+        keys.insert(format!("ed25519:{}", master_key), master_key);
+    }
+    keys
+}
+
+fn trusts_own_master_key(olm: &OlmMachine) -> bool {
+    olm.cross_signing().is_some() || olm.device_tracker().is_user_verified(olm.user_id())
+}
+
+fn supported_methods() -> Vec<String> {
+    SUPPORTED_METHODS.iter().map(|m| m.to_string()).collect()
+}
+
+fn common_methods(theirs: &[String]) -> Vec<String> {
+    SUPPORTED_METHODS
+        .iter()
+        .filter(|m| theirs.iter().any(|t| t == *m))
+        .map(|m| m.to_string())
+        .collect()
+}
+
+fn invalid_state(flow_id: &str, action: &'static str) -> VerificationError {
+    VerificationError::InvalidState { flow_id: flow_id.to_string(), action }
+}
+
+fn unexpected(message: &str) -> VerificationError {
+    VerificationError::cancel(
+        CancelCode::UnexpectedMessage,
+        format!("unexpected {} message", message),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::KeysUploadRequest;
+    use crate::device::{QueryKeysResponse, UploadKeysResponse};
+    use matryx_entity::DeviceKeys;
+
+    const ALICE: &str = "@alice:example.com";
+    const BOB: &str = "@bob:example.com";
+    const ROOM_ID: &str = "!room:example.com";
+
+    struct Side {
+        olm: OlmMachine,
+        verification: VerificationMachine,
+        events: broadcast::Receiver<VerificationEvent>,
+    }
+
+    fn side(user_id: &str, device_id: &str) -> (Side, KeysUploadRequest) {
+        let mut olm = OlmMachine::new(user_id, device_id);
+        let upload = olm
+            .keys_for_upload()
+            .expect("keys_for_upload failed")
+            .expect("new machine should upload keys");
+        olm.receive_keys_upload_response(&UploadKeysResponse {
+            one_time_key_counts: HashMap::new(),
+        });
+
+        let verification = VerificationMachine::new(user_id, device_id);
+        let events = verification.subscribe();
+        (Side { olm, verification, events }, upload)
+    }
+
+    /// Let both sides see each other's device keys and published master keys
+    fn introduce(sides: &mut [&mut Side], uploads: &[&KeysUploadRequest], master_keys: &[Value]) {
+        let mut device_keys: HashMap<String, HashMap<String, DeviceKeys>> = HashMap::new();
+        for upload in uploads {
+            let keys = upload.device_keys.clone().expect("upload should contain device keys");
+            device_keys
+                .entry(keys.user_id.clone())
+                .or_default()
+                .insert(keys.device_id.clone(), keys);
+        }
+        let master_keys = master_keys
+            .iter()
+            .map(|key| (key["user_id"].as_str().expect("user_id").to_string(), key.clone()))
+            .collect();
+        let response = QueryKeysResponse {
+            device_keys,
+            master_keys: Some(master_keys),
+            self_signing_keys: None,
+            user_signing_keys: None,
+        };
+
+        for side in sides {
+            side.olm.update_tracked_users([ALICE.to_string(), BOB.to_string()]);
+            side.olm.receive_keys_query_response(&response);
+        }
+    }
+
+    fn bootstrap(side: &mut Side) -> Value {
+        let bootstrap = side.olm.bootstrap_cross_signing().expect("bootstrap failed");
+        serde_json::to_value(bootstrap.public_keys.master_key).expect("master key serializes")
+    }
+
+    /// Deliver everything `from` queued to `to`, as the homeserver would
+    fn deliver(from: &mut Side, sender: &str, to: &mut Side) -> usize {
+        let messages = from.verification.outgoing_messages();
+        let count = messages.len();
+        for (index, message) in messages.into_iter().enumerate() {
+            match message {
+                OutgoingVerificationMessage::ToDevice {
+                    user_id,
+                    device_id,
+                    event_type,
+                    content,
+                } => {
+                    let recipient = to.olm.device_id().to_string();
+                    if user_id == to.olm.user_id() && (device_id == "*" || device_id == recipient) {
+                        to.verification.receive_to_device_event(
+                            &mut to.olm,
+                            sender,
+                            &event_type,
+                            &content,
+                        );
+                    }
+                },
+                OutgoingVerificationMessage::InRoom { room_id, event_type, content } => {
+                    let event_id = format!("$event{}", index);
+                    to.verification.receive_room_event(
+                        &mut to.olm,
+                        &room_id,
+                        &event_id,
+                        sender,
+                        &event_type,
+                        &content,
+                    );
+                },
+            }
+        }
+        count
+    }
+
+    /// Shuttle messages until both sides are idle
+    fn settle(a: &mut Side, a_user: &str, b: &mut Side, b_user: &str) {
+        while deliver(a, a_user, b) + deliver(b, b_user, a) > 0 {}
+    }
+
+    fn drain(side: &mut Side) -> Vec<VerificationEvent> {
+        std::iter::from_fn(|| side.events.try_recv().ok()).collect()
+    }
+
+    #[test]
+    fn test_sas_self_verification_signs_new_device() {
+        let (mut old, old_upload) = side(ALICE, "OLDDEVICE");
+        let (mut new, new_upload) = side(ALICE, "NEWDEVICE");
+        let master_key = bootstrap(&mut old);
+        introduce(&mut [&mut old, &mut new], &[&old_upload, &new_upload], &[master_key]);
+
+        let flow_id = new.verification.request_to_device(ALICE, None);
+        settle(&mut new, ALICE, &mut old, ALICE);
+        assert!(matches!(drain(&mut old).as_slice(), [VerificationEvent::Requested { .. }]));
+
+        old.verification.accept_request(&flow_id).expect("accept_request failed");
+        settle(&mut old, ALICE, &mut new, ALICE);
+        new.verification.start_sas(&flow_id).expect("start_sas failed");
+        settle(&mut new, ALICE, &mut old, ALICE);
+        old.verification.accept_sas(&flow_id).expect("accept_sas failed");
+        settle(&mut old, ALICE, &mut new, ALICE);
+
+        let old_sas = old.verification.sas(&flow_id).expect("old device should run SAS");
+        let new_sas = new.verification.sas(&flow_id).expect("new device should run SAS");
+        assert!(old_sas.keys_exchanged());
+        assert_eq!(old_sas.emojis(), new_sas.emojis());
+        assert_eq!(old_sas.decimals(), new_sas.decimals());
+        assert_eq!(old_sas.emojis().map(|e| e.len()), Some(7));
+
+        old.verification
+            .confirm_sas(&mut old.olm, &flow_id)
+            .expect("confirm failed");
+        new.verification
+            .confirm_sas(&mut new.olm, &flow_id)
+            .expect("confirm failed");
+        settle(&mut old, ALICE, &mut new, ALICE);
+
+        assert!(!old.verification.is_active(&flow_id));
+        assert!(!new.verification.is_active(&flow_id));
+        assert!(drain(&mut old).iter().any(|e| matches!(e, VerificationEvent::Done { .. })));
+        assert!(drain(&mut new).iter().any(|e| matches!(e, VerificationEvent::Done { .. })));
+
+        // The old device holds the self-signing key and signs the new one
+        let uploads = old.verification.signature_uploads();
+        assert_eq!(uploads.len(), 1);
+        assert!(uploads[0][ALICE]["NEWDEVICE"]["signatures"][ALICE].is_object());
+        assert!(old.olm.device_tracker().is_device_verified(ALICE, "NEWDEVICE"));
+
+        // The new device learned to trust the master key from the old one's MAC
+        assert!(new.olm.device_tracker().is_user_verified(ALICE));
+        assert!(new.olm.device_tracker().is_device_verified(ALICE, "OLDDEVICE"));
+        assert!(new.verification.signature_uploads().is_empty());
+    }
+
+    #[test]
+    fn test_sas_mismatch_cancels_both_sides() {
+        let (mut alice, alice_upload) = side(ALICE, "ALICEDEVICE");
+        let (mut bob, bob_upload) = side(BOB, "BOBDEVICE");
+        introduce(&mut [&mut alice, &mut bob], &[&alice_upload, &bob_upload], &[]);
+
+        let flow_id = alice.verification.request_to_device(BOB, Some(vec!["BOBDEVICE".into()]));
+        settle(&mut alice, ALICE, &mut bob, BOB);
+        bob.verification.accept_request(&flow_id).expect("accept_request failed");
+        settle(&mut bob, BOB, &mut alice, ALICE);
+        alice.verification.start_sas(&flow_id).expect("start_sas failed");
+        settle(&mut alice, ALICE, &mut bob, BOB);
+        bob.verification.accept_sas(&flow_id).expect("accept_sas failed");
+        settle(&mut bob, BOB, &mut alice, ALICE);
+
+        bob.verification.mismatch_sas(&flow_id).expect("mismatch failed");
+        settle(&mut bob, BOB, &mut alice, ALICE);
+
+        assert!(!alice.verification.is_active(&flow_id));
+        let cancelled = drain(&mut alice).into_iter().find_map(|event| match event {
+            VerificationEvent::Cancelled { code, by_us, .. } => Some((code, by_us)),
+            _ => None,
+        });
+        assert_eq!(cancelled, Some((CancelCode::MismatchedSas, false)));
+        assert!(!alice.olm.device_tracker().is_device_verified(BOB, "BOBDEVICE"));
+    }
+
+    #[test]
+    fn test_in_room_qr_verification_cross_signs_users() {
+        let (mut alice, alice_upload) = side(ALICE, "ALICEDEVICE");
+        let (mut bob, bob_upload) = side(BOB, "BOBDEVICE");
+        let alice_master = bootstrap(&mut alice);
+        let bob_master = bootstrap(&mut bob);
+        introduce(
+            &mut [&mut alice, &mut bob],
+            &[&alice_upload, &bob_upload],
+            &[alice_master, bob_master],
+        );
+
+        let request = alice.verification.request_in_room_content(BOB);
+        let content = serde_json::to_value(request).expect("request serializes");
+        alice.verification.room_request_sent(ROOM_ID, "$request", BOB);
+        bob.verification.receive_room_event(
+            &mut bob.olm,
+            ROOM_ID,
+            "$request",
+            ALICE,
+            "m.room.message",
+            &content,
+        );
+        bob.verification.accept_request("$request").expect("accept_request failed");
+        settle(&mut bob, BOB, &mut alice, ALICE);
+
+        let code = alice
+            .verification
+            .generate_qr_code(&alice.olm, "$request")
+            .expect("qr failed");
+        assert_eq!(code.mode, QrCodeMode::VerifyingAnotherUser);
+        let bytes = code.to_bytes().expect("qr encodes");
+        assert_eq!(QrCode::from_bytes(&bytes).expect("qr decodes"), code);
+
+        let flow_id = bob.verification.scan_qr_code(&mut bob.olm, &bytes).expect("scan failed");
+        assert_eq!(flow_id, "$request");
+        settle(&mut bob, BOB, &mut alice, ALICE);
+        assert!(
+            drain(&mut alice)
+                .iter()
+                .any(|e| matches!(e, VerificationEvent::QrCodeScanned { .. }))
+        );
+
+        alice
+            .verification
+            .confirm_qr_scanned(&mut alice.olm, "$request")
+            .expect("confirm failed");
+        settle(&mut alice, ALICE, &mut bob, BOB);
+
+        assert!(!alice.verification.is_active("$request"));
+        assert!(!bob.verification.is_active("$request"));
+        assert!(alice.olm.device_tracker().is_user_verified(BOB));
+        assert!(bob.olm.device_tracker().is_user_verified(ALICE));
+
+        let bob_master_key = bob.olm.own_master_key().expect("bob has a master key");
+        let uploads = alice.verification.signature_uploads();
+        assert!(uploads[0][BOB][&bob_master_key]["signatures"][ALICE].is_object());
+        assert_eq!(bob.verification.signature_uploads().len(), 1);
+    }
+
+    #[test]
+    fn test_tampered_qr_code_is_rejected() {
+        let (mut alice, alice_upload) = side(ALICE, "ALICEDEVICE");
+        let (mut bob, bob_upload) = side(BOB, "BOBDEVICE");
+        let alice_master = bootstrap(&mut alice);
+        let bob_master = bootstrap(&mut bob);
+        introduce(
+            &mut [&mut alice, &mut bob],
+            &[&alice_upload, &bob_upload],
+            &[alice_master, bob_master],
+        );
+
+        let flow_id = alice.verification.request_to_device(BOB, None);
+        settle(&mut alice, ALICE, &mut bob, BOB);
+        bob.verification.accept_request(&flow_id).expect("accept_request failed");
+        settle(&mut bob, BOB, &mut alice, ALICE);
+
+        let mut code = alice
+            .verification
+            .generate_qr_code(&alice.olm, &flow_id)
+            .expect("qr failed");
+        code.first_key = bob.olm.ed25519_key();
+        let bytes = code.to_bytes().expect("qr encodes");
+
+        bob.verification
+            .scan_qr_code(&mut bob.olm, &bytes)
+            .expect("scan should cancel");
+        assert!(!bob.verification.is_active(&flow_id));
+        assert!(!bob.olm.device_tracker().is_user_verified(ALICE));
+        assert!(bob.verification.signature_uploads().is_empty());
+    }
+}
@@ -0,0 +1,203 @@
+//! Interactive key verification
+//!
+//! Implements the `m.key.verification.*` framework for to-device and
+//! in-room flows: request/ready negotiation, the `m.sas.v1` short
+//! authentication string method and the QR code methods with
+//! `m.reciprocate.v1`. Like the [`super::OlmMachine`], the
+//! [`VerificationMachine`] does no I/O; it queues the messages to send and
+//! the cross-signing signatures to upload once a flow succeeds.
+
+pub mod emoji;
+pub mod machine;
+pub mod qr;
+pub mod sas;
+
+pub use emoji::{EMOJI_TABLE, SasEmoji};
+pub use machine::VerificationMachine;
+pub use qr::{QrCode, QrCodeMode};
+pub use sas::{SasVerification, VerificationParty};
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// Short authentication string verification
+pub const SAS_V1_METHOD: &str = "m.sas.v1";
+
+/// The device can display a QR code for the other side to scan
+pub const QR_SHOW_V1_METHOD: &str = "m.qr_code.show.v1";
+
+/// The device can scan a QR code displayed by the other side
+pub const QR_SCAN_V1_METHOD: &str = "m.qr_code.scan.v1";
+
+/// Start method sent by the device that scanned a QR code
+pub const RECIPROCATE_V1_METHOD: &str = "m.reciprocate.v1";
+
+/// A flow that has not made progress for this long is cancelled
+pub const VERIFICATION_TIMEOUT_SECS: i64 = 10 * 60;
+
+/// How a verification is transported
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub enum VerificationFlow {
+    /// Between devices, identified by a `transaction_id`
+    ToDevice { transaction_id: String },
+    /// In a room, identified by the event ID of the request
+    InRoom { room_id: String, event_id: String },
+}
+
+impl VerificationFlow {
+    /// The transaction ID or request event ID
+    pub fn flow_id(&self) -> &str {
+        match self {
+            Self::ToDevice { transaction_id } => transaction_id,
+            Self::InRoom { event_id, .. } => event_id,
+        }
+    }
+}
+
+/// Progress of a verification, surfaced through `RealtimeEvent::Verification`
+#[derive(Debug, Clone, Serialize)]
+pub enum VerificationEvent {
+    /// The other side asked us to verify
+    Requested {
+        flow: VerificationFlow,
+        user_id: String,
+        device_id: String,
+        methods: Vec<String>,
+    },
+    /// Both sides agreed on the methods to use
+    Ready { flow: VerificationFlow, methods: Vec<String> },
+    /// The other side started SAS; accept it to continue
+    SasStarted { flow: VerificationFlow },
+    /// SAS keys were exchanged; the user must compare the string
+    SasKeysExchanged {
+        flow: VerificationFlow,
+        emojis: Vec<SasEmoji>,
+        decimals: (u16, u16, u16),
+    },
+    /// The other device scanned our QR code; the user must confirm it did
+    QrCodeScanned { flow: VerificationFlow },
+    /// The other device or user is now verified
+    Done {
+        flow: VerificationFlow,
+        user_id: String,
+        device_id: String,
+    },
+    /// The flow was cancelled by either side
+    Cancelled {
+        flow: VerificationFlow,
+        code: CancelCode,
+        reason: String,
+        by_us: bool,
+    },
+}
+
+/// `m.key.verification.cancel` codes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum CancelCode {
+    User,
+    Timeout,
+    UnknownTransaction,
+    UnknownMethod,
+    UnexpectedMessage,
+    KeyMismatch,
+    UserMismatch,
+    InvalidMessage,
+    Accepted,
+    MismatchedCommitment,
+    MismatchedSas,
+    Other(String),
+}
+
+impl CancelCode {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::User => "m.user",
+            Self::Timeout => "m.timeout",
+            Self::UnknownTransaction => "m.unknown_transaction",
+            Self::UnknownMethod => "m.unknown_method",
+            Self::UnexpectedMessage => "m.unexpected_message",
+            Self::KeyMismatch => "m.key_mismatch",
+            Self::UserMismatch => "m.user_mismatch",
+            Self::InvalidMessage => "m.invalid_message",
+            Self::Accepted => "m.accepted",
+            Self::MismatchedCommitment => "m.mismatched_commitment",
+            Self::MismatchedSas => "m.mismatched_sas",
+            Self::Other(code) => code,
+        }
+    }
+
+    /// Human-readable reason sent along with the code
+    pub fn reason(&self) -> &str {
+        match self {
+            Self::User => "The user cancelled the verification",
+            Self::Timeout => "The verification timed out",
+            Self::UnknownTransaction => "Unknown verification transaction",
+            Self::UnknownMethod => "Unknown verification method",
+            Self::UnexpectedMessage => "Unexpected verification message",
+            Self::KeyMismatch => "The keys did not match",
+            Self::UserMismatch => "The user did not match",
+            Self::InvalidMessage => "Invalid verification message",
+            Self::Accepted => "The verification was accepted by another device",
+            Self::MismatchedCommitment => "The key commitment did not match",
+            Self::MismatchedSas => "The short authentication strings did not match",
+            Self::Other(_) => "The verification was cancelled",
+        }
+    }
+
+    pub fn parse(code: &str) -> Self {
+        match code {
+            "m.user" => Self::User,
+            "m.timeout" => Self::Timeout,
+            "m.unknown_transaction" => Self::UnknownTransaction,
+            "m.unknown_method" => Self::UnknownMethod,
+            "m.unexpected_message" => Self::UnexpectedMessage,
+            "m.key_mismatch" => Self::KeyMismatch,
+            "m.user_mismatch" => Self::UserMismatch,
+            "m.invalid_message" => Self::InvalidMessage,
+            "m.accepted" => Self::Accepted,
+            "m.mismatched_commitment" => Self::MismatchedCommitment,
+            "m.mismatched_sas" => Self::MismatchedSas,
+            other => Self::Other(other.to_string()),
+        }
+    }
+}
+
+/// A verification message the client has to send
+#[derive(Debug, Clone)]
+pub enum OutgoingVerificationMessage {
+    /// Send with `/sendToDevice`; `device_id` may be `*`
+    ToDevice {
+        user_id: String,
+        device_id: String,
+        event_type: String,
+        content: Value,
+    },
+    /// Send as a room event
+    InRoom {
+        room_id: String,
+        event_type: String,
+        content: Value,
+    },
+}
+
+/// Verification protocol errors, answered with a cancellation
+#[derive(Debug, thiserror::Error)]
+pub enum VerificationError {
+    #[error("Unknown verification flow {0}")]
+    UnknownFlow(String),
+
+    #[error("Verification flow {flow_id} cannot {action} in its current state")]
+    InvalidState { flow_id: String, action: &'static str },
+
+    #[error("Verification cancelled ({}): {reason}", code.as_str())]
+    Cancelled { code: CancelCode, reason: String },
+
+    #[error(transparent)]
+    Crypto(#[from] super::CryptoError),
+}
+
+impl VerificationError {
+    pub(crate) fn cancel(code: CancelCode, reason: impl Into<String>) -> Self {
+        Self::Cancelled { code, reason: reason.into() }
+    }
+}
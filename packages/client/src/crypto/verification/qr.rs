@@ -0,0 +1,160 @@
+//! QR code payloads for verification
+//!
+//! Binary layout: the ASCII string `MATRIX`, version byte `0x02`, the mode
+//! byte, the flow ID length as a big-endian `u16`, the flow ID, two 32-byte
+//! Ed25519 keys whose meaning depends on the mode, and a shared secret of at
+//! least 8 random bytes that the scanning device echoes back in its
+//! `m.reciprocate.v1` start message.
+
+use rand::RngCore;
+use vodozemac::Ed25519PublicKey;
+
+use super::{CancelCode, VerificationError};
+
+const PREFIX: &[u8] = b"MATRIX";
+const VERSION: u8 = 0x02;
+const KEY_LENGTH: usize = 32;
+const SECRET_LENGTH: usize = 16;
+const MIN_SECRET_LENGTH: usize = 8;
+
+/// What the two keys of a QR code stand for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QrCodeMode {
+    /// Verifying another user: our master key, then their master key as we know it
+    VerifyingAnotherUser,
+    /// Self-verification from a device trusting the master key: the master
+    /// key, then the other device's key as we know it
+    SelfVerifyingTrusted,
+    /// Self-verification from a device not trusting the master key: our
+    /// device key, then the master key as we know it
+    SelfVerifyingUntrusted,
+}
+
+impl QrCodeMode {
+    fn as_byte(self) -> u8 {
+        match self {
+            Self::VerifyingAnotherUser => 0x00,
+            Self::SelfVerifyingTrusted => 0x01,
+            Self::SelfVerifyingUntrusted => 0x02,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0x00 => Some(Self::VerifyingAnotherUser),
+            0x01 => Some(Self::SelfVerifyingTrusted),
+            0x02 => Some(Self::SelfVerifyingUntrusted),
+            _ => None,
+        }
+    }
+}
+
+/// Decoded QR code contents
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QrCode {
+    pub mode: QrCodeMode,
+    pub flow_id: String,
+    /// First key, unpadded base64
+    pub first_key: String,
+    /// Second key, unpadded base64
+    pub second_key: String,
+    pub secret: Vec<u8>,
+}
+
+impl QrCode {
+    /// Create a code with a fresh random shared secret
+    pub fn new(mode: QrCodeMode, flow_id: &str, first_key: &str, second_key: &str) -> Self {
+        let mut secret = vec![0u8; SECRET_LENGTH];
+        rand::rng().fill_bytes(&mut secret);
+
+        Self {
+            mode,
+            flow_id: flow_id.to_string(),
+            first_key: first_key.to_string(),
+            second_key: second_key.to_string(),
+            secret,
+        }
+    }
+
+    /// Unpadded base64 of the shared secret, as sent in `m.reciprocate.v1`
+    pub fn secret_base64(&self) -> String {
+        vodozemac::base64_encode(&self.secret)
+    }
+
+    /// Binary payload to render as a QR code
+    pub fn to_bytes(&self) -> Result<Vec<u8>, VerificationError> {
+        let flow_id_length = u16::try_from(self.flow_id.len()).map_err(|_| {
+            VerificationError::cancel(CancelCode::InvalidMessage, "flow ID too long for a QR code")
+        })?;
+
+        let mut bytes = Vec::with_capacity(
+            PREFIX.len() + 4 + self.flow_id.len() + 2 * KEY_LENGTH + self.secret.len(),
+        );
+        bytes.extend_from_slice(PREFIX);
+        bytes.push(VERSION);
+        bytes.push(self.mode.as_byte());
+        bytes.extend_from_slice(&flow_id_length.to_be_bytes());
+        bytes.extend_from_slice(self.flow_id.as_bytes());
+        bytes.extend_from_slice(&decode_key(&self.first_key)?);
+        bytes.extend_from_slice(&decode_key(&self.second_key)?);
+        bytes.extend_from_slice(&self.secret);
+        Ok(bytes)
+    }
+
+    /// Parse a scanned payload
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, VerificationError> {
+        let invalid = |reason: &str| VerificationError::cancel(CancelCode::InvalidMessage, reason);
+
+        let rest = bytes.strip_prefix(PREFIX).ok_or_else(|| invalid("not a Matrix QR code"))?;
+        let (&version, rest) = rest.split_first().ok_or_else(|| invalid("truncated QR code"))?;
+        if version != VERSION {
+            return Err(invalid("unsupported QR code version"));
+        }
+
+        let (&mode, rest) = rest.split_first().ok_or_else(|| invalid("truncated QR code"))?;
+        let mode = QrCodeMode::from_byte(mode).ok_or_else(|| invalid("unknown QR code mode"))?;
+
+        let (length, rest) =
+            rest.split_at_checked(2).ok_or_else(|| invalid("truncated QR code"))?;
+        let length = usize::from(u16::from_be_bytes([length[0], length[1]]));
+        let (flow_id, rest) =
+            rest.split_at_checked(length).ok_or_else(|| invalid("truncated QR code"))?;
+        let flow_id =
+            String::from_utf8(flow_id.to_vec()).map_err(|_| invalid("flow ID is not UTF-8"))?;
+
+        let (first_key, rest) = rest
+            .split_at_checked(KEY_LENGTH)
+            .ok_or_else(|| invalid("truncated QR code"))?;
+        let (second_key, secret) = rest
+            .split_at_checked(KEY_LENGTH)
+            .ok_or_else(|| invalid("truncated QR code"))?;
+        if secret.len() < MIN_SECRET_LENGTH {
+            return Err(invalid("QR code secret is too short"));
+        }
+
+        Ok(Self {
+            mode,
+            flow_id,
+            first_key: encode_key(first_key)?,
+            second_key: encode_key(second_key)?,
+            secret: secret.to_vec(),
+        })
+    }
+}
+
+fn decode_key(key: &str) -> Result<[u8; KEY_LENGTH], VerificationError> {
+    Ed25519PublicKey::from_base64(key).map(|key| *key.as_bytes()).map_err(|e| {
+        VerificationError::cancel(CancelCode::InvalidMessage, format!("invalid key {}: {}", key, e))
+    })
+}
+
+fn encode_key(bytes: &[u8]) -> Result<String, VerificationError> {
+    let bytes: &[u8; KEY_LENGTH] = bytes
+        .try_into()
+        .map_err(|_| VerificationError::cancel(CancelCode::InvalidMessage, "invalid key length"))?;
+    Ed25519PublicKey::from_slice(bytes)
+        .map(|key| key.to_base64())
+        .map_err(|e| {
+            VerificationError::cancel(CancelCode::InvalidMessage, format!("invalid key: {}", e))
+        })
+}
@@ -0,0 +1,451 @@
+//! The `m.sas.v1` short authentication string method
+//!
+//! The device that sent `m.key.verification.start` is the starter. The other
+//! device accepts with a commitment to its ephemeral key, both sides swap
+//! keys, derive the short authentication string and, once the user confirmed
+//! it matches, exchange MACs of the keys they want the other side to trust.
+
+use matryx_entity::types::sas_verification_start::SasVerificationParams;
+use matryx_entity::types::{
+    SASVerificationStart, VerificationAccept, VerificationKey, VerificationMAC,
+};
+use matryx_entity::utils::canonical_json;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use vodozemac::sas::{EstablishedSas, Mac, Sas};
+
+use super::emoji::{SasEmoji, emojis_from_indices};
+use super::{CancelCode, SAS_V1_METHOD, VerificationError};
+use crate::crypto::CryptoError;
+
+const KEY_AGREEMENT_PROTOCOL: &str = "curve25519-hkdf-sha256";
+const HASH: &str = "sha256";
+const MAC_METHOD: &str = "hkdf-hmac-sha256.v2";
+const SAS_DECIMAL: &str = "decimal";
+const SAS_EMOJI: &str = "emoji";
+
+/// Key ID under which the MAC of the sorted key ID list is sent
+const KEY_IDS: &str = "KEY_IDS";
+
+/// User and device on one side of a verification
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerificationParty {
+    pub user_id: String,
+    pub device_id: String,
+}
+
+impl VerificationParty {
+    pub fn new(user_id: &str, device_id: &str) -> Self {
+        Self {
+            user_id: user_id.to_string(),
+            device_id: device_id.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SasState {
+    /// We sent the start and wait for an accept
+    Started,
+    /// We received a start and have not accepted yet
+    Received,
+    /// Accept sent or received, keys not exchanged yet
+    Accepted,
+    /// Both keys known; waiting for the user and the MACs
+    KeysExchanged,
+    /// Their MAC passed verification
+    Verified,
+}
+
+/// SAS state of a single verification flow
+pub struct SasVerification {
+    flow_id: String,
+    we_started: bool,
+    own: VerificationParty,
+    other: VerificationParty,
+    /// Content of the start event as sent, for the commitment
+    start_content: Value,
+    sas: Option<Sas>,
+    our_public_key: String,
+    their_public_key: Option<String>,
+    /// Commitment from the other side's accept, when we started
+    commitment: Option<String>,
+    established: Option<EstablishedSas>,
+    /// Short authentication string methods both sides support
+    sas_methods: Vec<String>,
+    confirmed: bool,
+    their_mac: Option<VerificationMAC>,
+    state: SasState,
+}
+
+impl std::fmt::Debug for SasVerification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SasVerification")
+            .field("flow_id", &self.flow_id)
+            .field("we_started", &self.we_started)
+            .field("other", &self.other)
+            .field("state", &self.state)
+            .finish()
+    }
+}
+
+impl SasVerification {
+    /// `m.key.verification.start` content offering everything we support
+    ///
+    /// The caller adds `transaction_id` or `m.relates_to` before sending and
+    /// passes the final content to [`Self::new_outgoing`].
+    pub fn start_content(own_device_id: &str) -> SASVerificationStart {
+        SASVerificationStart::new(SasVerificationParams {
+            from_device: own_device_id.to_string(),
+            hashes: vec![HASH.to_string()],
+            key_agreement_protocols: vec![KEY_AGREEMENT_PROTOCOL.to_string()],
+            m_relates_to: None,
+            message_authentication_codes: vec![MAC_METHOD.to_string()],
+            method: SAS_V1_METHOD.to_string(),
+            short_authentication_string: vec![SAS_DECIMAL.to_string(), SAS_EMOJI.to_string()],
+            transaction_id: None,
+        })
+    }
+
+    /// Track a start we sent
+    pub fn new_outgoing(
+        flow_id: &str,
+        own: VerificationParty,
+        other: VerificationParty,
+        start_content: Value,
+    ) -> Self {
+        Self::new(flow_id, true, own, other, start_content, Vec::new(), SasState::Started)
+    }
+
+    /// Track a start we received, checking we support one of each offered method
+    pub fn from_start(
+        flow_id: &str,
+        own: VerificationParty,
+        other: VerificationParty,
+        start_content: &Value,
+    ) -> Result<Self, VerificationError> {
+        let start: SASVerificationStart = serde_json::from_value(start_content.clone())
+            .map_err(|e| VerificationError::cancel(CancelCode::InvalidMessage, e.to_string()))?;
+
+        let offers = |methods: &[String], ours: &str| methods.iter().any(|m| m == ours);
+        if !offers(&start.key_agreement_protocols, KEY_AGREEMENT_PROTOCOL)
+            || !offers(&start.hashes, HASH)
+            || !offers(&start.message_authentication_codes, MAC_METHOD)
+            || !offers(&start.short_authentication_string, SAS_DECIMAL)
+        {
+            return Err(VerificationError::cancel(
+                CancelCode::UnknownMethod,
+                "no common SAS key agreement, hash, MAC or string method",
+            ));
+        }
+
+        let sas_methods = supported_sas_methods(&start.short_authentication_string);
+        Ok(Self::new(
+            flow_id,
+            false,
+            own,
+            other,
+            start_content.clone(),
+            sas_methods,
+            SasState::Received,
+        ))
+    }
+
+    fn new(
+        flow_id: &str,
+        we_started: bool,
+        own: VerificationParty,
+        other: VerificationParty,
+        start_content: Value,
+        sas_methods: Vec<String>,
+        state: SasState,
+    ) -> Self {
+        let sas = Sas::new();
+        Self {
+            flow_id: flow_id.to_string(),
+            we_started,
+            own,
+            other,
+            start_content,
+            our_public_key: sas.public_key().to_base64(),
+            sas: Some(sas),
+            their_public_key: None,
+            commitment: None,
+            established: None,
+            sas_methods,
+            confirmed: false,
+            their_mac: None,
+            state,
+        }
+    }
+
+    pub fn we_started(&self) -> bool {
+        self.we_started
+    }
+
+    pub fn other(&self) -> &VerificationParty {
+        &self.other
+    }
+
+    /// Whether the short authentication string can be shown
+    pub fn keys_exchanged(&self) -> bool {
+        self.established.is_some()
+    }
+
+    pub fn is_confirmed(&self) -> bool {
+        self.confirmed
+    }
+
+    pub fn is_verified(&self) -> bool {
+        self.state == SasState::Verified
+    }
+
+    /// Accept a received start, committing to our ephemeral key
+    pub fn accept(&mut self) -> Result<VerificationAccept, VerificationError> {
+        self.expect_state(SasState::Received, "accept")?;
+
+        let commitment = commitment(&self.our_public_key, &self.start_content)?;
+        self.state = SasState::Accepted;
+
+        Ok(VerificationAccept::new(
+            commitment,
+            HASH.to_string(),
+            KEY_AGREEMENT_PROTOCOL.to_string(),
+            None,
+            MAC_METHOD.to_string(),
+            self.sas_methods.clone(),
+            None,
+        ))
+    }
+
+    /// Handle the accept for our start; returns our key to send
+    pub fn receive_accept(
+        &mut self,
+        accept: &VerificationAccept,
+    ) -> Result<VerificationKey, VerificationError> {
+        self.expect_state(SasState::Started, "receive an accept")?;
+
+        if accept.key_agreement_protocol != KEY_AGREEMENT_PROTOCOL
+            || accept.hash != HASH
+            || accept.message_authentication_code != MAC_METHOD
+            || !accept.short_authentication_string.iter().any(|m| m == SAS_DECIMAL)
+        {
+            return Err(VerificationError::cancel(
+                CancelCode::UnknownMethod,
+                "accepted methods were not offered",
+            ));
+        }
+
+        self.sas_methods = supported_sas_methods(&accept.short_authentication_string);
+        self.commitment = Some(accept.commitment.clone());
+        self.state = SasState::Accepted;
+        Ok(VerificationKey::new(self.our_public_key.clone(), None, None))
+    }
+
+    /// Handle the other side's ephemeral key
+    ///
+    /// Returns our key when we accepted, since the starter sends its key first.
+    pub fn receive_key(
+        &mut self,
+        key: &VerificationKey,
+    ) -> Result<Option<VerificationKey>, VerificationError> {
+        self.expect_state(SasState::Accepted, "receive a key")?;
+
+        if self.we_started {
+            let expected = commitment(&key.key, &self.start_content)?;
+            if self.commitment.as_deref() != Some(expected.as_str()) {
+                return Err(VerificationError::cancel(
+                    CancelCode::MismatchedCommitment,
+                    "the key does not match the accepted commitment",
+                ));
+            }
+        }
+
+        let sas = self.sas.take().ok_or(VerificationError::InvalidState {
+            flow_id: self.flow_id.clone(),
+            action: "receive a key",
+        })?;
+        let established = sas.diffie_hellman_with_raw(&key.key).map_err(|e| {
+            VerificationError::cancel(CancelCode::KeyMismatch, format!("invalid SAS key: {}", e))
+        })?;
+
+        self.their_public_key = Some(key.key.clone());
+        self.established = Some(established);
+        self.state = SasState::KeysExchanged;
+
+        if self.we_started {
+            Ok(None)
+        } else {
+            Ok(Some(VerificationKey::new(self.our_public_key.clone(), None, None)))
+        }
+    }
+
+    /// Emojis to compare, if the other side supports them
+    pub fn emojis(&self) -> Option<Vec<SasEmoji>> {
+        if !self.sas_methods.iter().any(|m| m == SAS_EMOJI) {
+            return None;
+        }
+        let established = self.established.as_ref()?;
+        Some(emojis_from_indices(established.bytes(&self.sas_info()).emoji_indices()))
+    }
+
+    /// Three four-digit numbers to compare
+    pub fn decimals(&self) -> Option<(u16, u16, u16)> {
+        let established = self.established.as_ref()?;
+        Some(established.bytes(&self.sas_info()).decimals())
+    }
+
+    /// The user confirmed the strings match; MAC the keys we vouch for
+    ///
+    /// `keys` maps key IDs such as `ed25519:DEVICEID` to public keys.
+    pub fn confirm(
+        &mut self,
+        keys: &BTreeMap<String, String>,
+    ) -> Result<VerificationMAC, VerificationError> {
+        self.expect_state(SasState::KeysExchanged, "confirm")?;
+        let established = self.established.as_ref().ok_or(VerificationError::InvalidState {
+            flow_id: self.flow_id.clone(),
+            action: "confirm",
+        })?;
+
+        let mut mac = HashMap::new();
+        for (key_id, key) in keys {
+            let info = self.mac_info(&self.own, &self.other, key_id);
+            mac.insert(key_id.clone(), established.calculate_mac(key, &info).to_base64());
+        }
+
+        let key_ids = keys.keys().cloned().collect::<Vec<_>>().join(",");
+        let info = self.mac_info(&self.own, &self.other, KEY_IDS);
+        let key_ids_mac = established.calculate_mac(&key_ids, &info).to_base64();
+
+        self.confirmed = true;
+        Ok(VerificationMAC::new(key_ids_mac, None, mac, None))
+    }
+
+    /// Store the other side's MAC until the user has confirmed
+    pub fn receive_mac(&mut self, mac: VerificationMAC) -> Result<(), VerificationError> {
+        self.expect_state(SasState::KeysExchanged, "receive a MAC")?;
+        self.their_mac = Some(mac);
+        Ok(())
+    }
+
+    pub fn has_their_mac(&self) -> bool {
+        self.their_mac.is_some()
+    }
+
+    /// Check the other side's MAC against the keys we know for them
+    ///
+    /// `known_keys` maps key IDs to the keys we hold for the other device and
+    /// user. Key IDs we do not know are ignored, but at least one must match.
+    /// Returns the verified key IDs.
+    pub fn verify_their_mac(
+        &mut self,
+        known_keys: &HashMap<String, String>,
+    ) -> Result<Vec<String>, VerificationError> {
+        self.expect_state(SasState::KeysExchanged, "verify a MAC")?;
+        let (Some(established), Some(their_mac)) = (&self.established, &self.their_mac) else {
+            return Err(VerificationError::InvalidState {
+                flow_id: self.flow_id.clone(),
+                action: "verify a MAC",
+            });
+        };
+
+        let mismatch = || VerificationError::cancel(CancelCode::KeyMismatch, "MAC mismatch");
+
+        let mut key_ids: Vec<&str> = their_mac.mac.keys().map(String::as_str).collect();
+        key_ids.sort_unstable();
+        let info = self.mac_info(&self.other, &self.own, KEY_IDS);
+        let tag = Mac::from_base64(&their_mac.keys).map_err(|_| mismatch())?;
+        established
+            .verify_mac(&key_ids.join(","), &info, &tag)
+            .map_err(|_| mismatch())?;
+
+        let mut verified = Vec::new();
+        for (key_id, mac) in &their_mac.mac {
+            let Some(key) = known_keys.get(key_id) else {
+                continue;
+            };
+            let info = self.mac_info(&self.other, &self.own, key_id);
+            let tag = Mac::from_base64(mac).map_err(|_| mismatch())?;
+            established.verify_mac(key, &info, &tag).map_err(|_| mismatch())?;
+            verified.push(key_id.clone());
+        }
+
+        if verified.is_empty() {
+            return Err(VerificationError::cancel(
+                CancelCode::KeyMismatch,
+                "no known key was covered by the MAC",
+            ));
+        }
+
+        self.state = SasState::Verified;
+        Ok(verified)
+    }
+
+    fn expect_state(&self, state: SasState, action: &'static str) -> Result<(), VerificationError> {
+        if self.state == state {
+            Ok(())
+        } else {
+            Err(VerificationError::cancel(
+                CancelCode::UnexpectedMessage,
+                format!("cannot {} in state {:?}", action, self.state),
+            ))
+        }
+    }
+
+    /// HKDF info for the short authentication string
+    fn sas_info(&self) -> String {
+        let their_key = self.their_public_key.as_deref().unwrap_or_default();
+        let (starter, starter_key, acceptor, acceptor_key) = if self.we_started {
+            (&self.own, self.our_public_key.as_str(), &self.other, their_key)
+        } else {
+            (&self.other, their_key, &self.own, self.our_public_key.as_str())
+        };
+
+        format!(
+            "MATRIX_KEY_VERIFICATION_SAS|{}|{}|{}|{}|{}|{}|{}",
+            starter.user_id,
+            starter.device_id,
+            starter_key,
+            acceptor.user_id,
+            acceptor.device_id,
+            acceptor_key,
+            self.flow_id
+        )
+    }
+
+    /// HKDF info for the MAC of one key, from `sender` to `receiver`
+    fn mac_info(
+        &self,
+        sender: &VerificationParty,
+        receiver: &VerificationParty,
+        key_id: &str,
+    ) -> String {
+        format!(
+            "MATRIX_KEY_VERIFICATION_MAC{}{}{}{}{}{}",
+            sender.user_id,
+            sender.device_id,
+            receiver.user_id,
+            receiver.device_id,
+            self.flow_id,
+            key_id
+        )
+    }
+}
+
+/// Unpadded base64 SHA-256 of the public key followed by the canonical start content
+fn commitment(public_key: &str, start_content: &Value) -> Result<String, CryptoError> {
+    let mut hasher = Sha256::new();
+    hasher.update(public_key.as_bytes());
+    hasher.update(canonical_json(start_content)?.as_bytes());
+    Ok(vodozemac::base64_encode(hasher.finalize()))
+}
+
+fn supported_sas_methods(offered: &[String]) -> Vec<String> {
+    offered
+        .iter()
+        .filter(|m| *m == SAS_DECIMAL || *m == SAS_EMOJI)
+        .cloned()
+        .collect()
+}
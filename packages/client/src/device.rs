@@ -10,6 +10,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 use crate::MatrixClient;
+use crate::crypto::{CrossSigningPublicKeys, SignatureUploadRequest};
 
 /// Device information for the current client
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,7 +31,7 @@ impl MatrixClient {
     pub async fn get_device(&self, device_id: &str) -> Result<Device> {
         let path = format!("/_matrix/client/v3/devices/{}", device_id);
         let request = self.authenticated_request(Method::GET, &path)?;
-        let response = request.send().await?;
+        let response = self.send(request).await?;
 
         if !response.status().is_success() {
             let error_text = response.text().await?;
@@ -44,7 +45,7 @@ impl MatrixClient {
     /// Get all devices for the current user
     pub async fn get_devices(&self) -> Result<Vec<Device>> {
         let request = self.authenticated_request(Method::GET, "/_matrix/client/v3/devices")?;
-        let response = request.send().await?;
+        let response = self.send(request).await?;
 
         if !response.status().is_success() {
             let error_text = response.text().await?;
@@ -70,7 +71,7 @@ impl MatrixClient {
         }
 
         let request = self.authenticated_request(Method::PUT, &path)?;
-        let response = request.json(&update_data).send().await?;
+        let response = self.send(request.json(&update_data)).await?;
 
         if !response.status().is_success() {
             let error_text = response.text().await?;
@@ -89,7 +90,7 @@ impl MatrixClient {
         });
 
         let request = self.authenticated_request(Method::DELETE, &path)?;
-        let response = request.json(&delete_data).send().await?;
+        let response = self.send(request.json(&delete_data)).await?;
 
         if !response.status().is_success() {
             let error_text = response.text().await?;
@@ -112,7 +113,7 @@ impl MatrixClient {
 
         let request =
             self.authenticated_request(Method::POST, "/_matrix/client/v3/delete_devices")?;
-        let response = request.json(&delete_data).send().await?;
+        let response = self.send(request.json(&delete_data)).await?;
 
         if !response.status().is_success() {
             let error_text = response.text().await?;
@@ -136,7 +137,7 @@ impl MatrixClient {
         });
 
         let request = self.authenticated_request(Method::PUT, &path)?;
-        let response = request.json(&send_data).send().await?;
+        let response = self.send(request.json(&send_data)).await?;
 
         if !response.status().is_success() {
             let error_text = response.text().await?;
@@ -163,7 +164,7 @@ impl MatrixClient {
         }
 
         let request = self.authenticated_request(Method::POST, "/_matrix/client/v3/keys/upload")?;
-        let response = request.json(&upload_data).send().await?;
+        let response = self.send(request.json(&upload_data)).await?;
 
         if !response.status().is_success() {
             let error_text = response.text().await?;
@@ -184,7 +185,7 @@ impl MatrixClient {
         });
 
         let request = self.authenticated_request(Method::POST, "/_matrix/client/v3/keys/query")?;
-        let response = request.json(&query_data).send().await?;
+        let response = self.send(request.json(&query_data)).await?;
 
         if !response.status().is_success() {
             let error_text = response.text().await?;
@@ -205,7 +206,7 @@ impl MatrixClient {
         });
 
         let request = self.authenticated_request(Method::POST, "/_matrix/client/v3/keys/claim")?;
-        let response = request.json(&claim_data).send().await?;
+        let response = self.send(request.json(&claim_data)).await?;
 
         if !response.status().is_success() {
             let error_text = response.text().await?;
@@ -215,6 +216,51 @@ impl MatrixClient {
         let claim_response: ClaimKeysResponse = response.json().await?;
         Ok(claim_response)
     }
+
+    /// Publish cross-signing public keys (usually requires user-interactive auth)
+    pub async fn upload_cross_signing_keys(
+        &self,
+        keys: &CrossSigningPublicKeys,
+        auth_data: Option<serde_json::Value>,
+    ) -> Result<()> {
+        let mut upload_data = serde_json::to_value(keys)?;
+        if let Some(auth) = auth_data {
+            upload_data["auth"] = auth;
+        }
+
+        let request = self
+            .authenticated_request(Method::POST, "/_matrix/client/v3/keys/device_signing/upload")?;
+        let response = self.send(request.json(&upload_data)).await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Failed to upload cross-signing keys: {}", error_text));
+        }
+
+        Ok(())
+    }
+
+    /// Upload cross-signing signatures of devices and master keys
+    pub async fn upload_signatures(&self, signatures: &SignatureUploadRequest) -> Result<()> {
+        let request =
+            self.authenticated_request(Method::POST, "/_matrix/client/v3/keys/signatures/upload")?;
+        let response = self.send(request.json(signatures)).await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Failed to upload signatures: {}", error_text));
+        }
+
+        let upload_response: UploadSignaturesResponse = response.json().await?;
+        if !upload_response.failures.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Server rejected signatures: {}",
+                serde_json::to_string(&upload_response.failures)?
+            ));
+        }
+
+        Ok(())
+    }
 }
 
 /// Response from uploading keys
@@ -237,6 +283,14 @@ pub struct QueryKeysResponse {
     pub user_signing_keys: Option<HashMap<String, serde_json::Value>>,
 }
 
+/// Response from uploading signatures
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadSignaturesResponse {
+    /// Failed signatures by user ID and key or device ID
+    #[serde(default)]
+    pub failures: HashMap<String, HashMap<String, serde_json::Value>>,
+}
+
 /// Response from claiming keys
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClaimKeysResponse {
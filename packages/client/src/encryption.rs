@@ -0,0 +1,301 @@
+//! End-to-end encryption for the Matrix client
+//!
+//! Drives the [`OlmMachine`] over the client-server API: key uploads, device
+//! list queries, one-time key claims, room key sharing and decryption of
+//! `m.room.encrypted` events.
+
+use anyhow::Result;
+use matryx_entity::Event;
+use matryx_entity::types::{RoomEncryptedContent, RoomEncryptionContent};
+use reqwest::Method;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::crypto::{
+    DecryptedRoomEvent, DecryptedToDeviceEvent, OlmMachine, OlmMachinePickle, VerificationMachine,
+};
+use crate::http_client::HttpClientError;
+use crate::sync::DeviceListUpdates;
+use crate::{MatrixClient, SyncResponse};
+
+/// Encryption functionality
+impl MatrixClient {
+    /// Enable end-to-end encryption for the logged-in device
+    ///
+    /// Creates a new Olm account and uploads its device and one-time keys.
+    pub async fn enable_encryption(&mut self) -> Result<()> {
+        let (user_id, device_id) = self.crypto_identity()?;
+        self.olm_machine = Some(Arc::new(Mutex::new(OlmMachine::new(&user_id, &device_id))));
+        self.verification_machine =
+            Some(Arc::new(Mutex::new(VerificationMachine::new(&user_id, &device_id))));
+        self.reset_send_queue();
+        self.process_crypto_requests().await
+    }
+
+    /// Restore encryption state saved with [`Self::encryption_pickle`]
+    pub async fn restore_encryption(&mut self, pickle: OlmMachinePickle) -> Result<()> {
+        let (user_id, device_id) = self.crypto_identity()?;
+        let machine = OlmMachine::from_pickle(pickle)?;
+        if machine.user_id() != user_id || machine.device_id() != device_id {
+            return Err(anyhow::anyhow!(
+                "Encryption state belongs to {}/{}, not {}/{}",
+                machine.user_id(),
+                machine.device_id(),
+                user_id,
+                device_id
+            ));
+        }
+
+        self.olm_machine = Some(Arc::new(Mutex::new(machine)));
+        self.verification_machine =
+            Some(Arc::new(Mutex::new(VerificationMachine::new(&user_id, &device_id))));
+        self.reset_send_queue();
+        self.process_crypto_requests().await
+    }
+
+    /// Snapshot the encryption state for persistence
+    pub async fn encryption_pickle(&self) -> Option<OlmMachinePickle> {
+        match &self.olm_machine {
+            Some(machine) => Some(machine.lock().await.pickle()),
+            None => None,
+        }
+    }
+
+    /// Check if end-to-end encryption is enabled
+    pub fn is_encryption_enabled(&self) -> bool {
+        self.olm_machine.is_some()
+    }
+
+    /// Get the Olm machine (if encryption is enabled)
+    pub fn olm_machine(&self) -> Option<Arc<Mutex<OlmMachine>>> {
+        self.olm_machine.clone()
+    }
+
+    fn crypto_identity(&self) -> Result<(String, String)> {
+        let session = self
+            .session()
+            .ok_or_else(|| anyhow::anyhow!("Client is not authenticated"))?;
+        let device_id = session
+            .device_id
+            .ok_or_else(|| anyhow::anyhow!("Encryption requires a device ID"))?;
+        Ok((session.user_id, device_id))
+    }
+
+    pub(crate) fn require_olm_machine(&self) -> Result<Arc<Mutex<OlmMachine>>> {
+        self.olm_machine
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("End-to-end encryption is not enabled"))
+    }
+
+    /// Upload pending keys and refresh outdated device lists
+    pub async fn process_crypto_requests(&self) -> Result<()> {
+        let machine = self.require_olm_machine()?;
+
+        let upload = machine.lock().await.keys_for_upload()?;
+        if let Some(upload) = upload {
+            let response = self.upload_keys(upload.device_keys, Some(upload.one_time_keys)).await?;
+            machine.lock().await.receive_keys_upload_response(&response);
+        }
+
+        let query = machine.lock().await.users_for_key_query();
+        if let Some(query) = query {
+            let response = self.query_keys(query).await?;
+            machine.lock().await.receive_keys_query_response(&response);
+        }
+
+        Ok(())
+    }
+
+    /// Apply `device_lists` updates, e.g. from [`crate::sync::SyncState`]
+    pub async fn receive_device_list_updates(&self, updates: &DeviceListUpdates) -> Result<()> {
+        let machine = self.require_olm_machine()?;
+        machine.lock().await.receive_device_list_updates(updates);
+        Ok(())
+    }
+
+    /// Feed the encryption-related sections of a sync response to the machine
+    ///
+    /// Returns the to-device events with Olm-encrypted ones decrypted.
+    pub(crate) async fn receive_sync_crypto(
+        &self,
+        response: &SyncResponse,
+    ) -> Result<Vec<DecryptedToDeviceEvent>> {
+        let machine = self.require_olm_machine()?;
+
+        let to_device_events = response
+            .to_device
+            .as_ref()
+            .and_then(|to_device| to_device.get("events"))
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+
+        let decrypted = {
+            let mut machine = machine.lock().await;
+
+            if let Some(device_lists) = &response.device_lists {
+                let updates: DeviceListUpdates = serde_json::from_value(device_lists.clone())?;
+                machine.receive_device_list_updates(&updates);
+            }
+
+            if let Some(counts) = &response.device_one_time_keys_count {
+                let counts: HashMap<String, u64> = serde_json::from_value(counts.clone())?;
+                machine.update_one_time_key_counts(&counts);
+            }
+
+            machine.receive_to_device_events(to_device_events)
+        };
+
+        self.process_crypto_requests().await?;
+        Ok(decrypted)
+    }
+
+    /// Get the `m.room.encryption` settings of a room, if it is encrypted
+    pub async fn room_encryption(&self, room_id: &str) -> Result<Option<RoomEncryptionContent>> {
+        let path = format!(
+            "/_matrix/client/v3/rooms/{}/state/{}/",
+            urlencoding::encode(room_id),
+            RoomEncryptionContent::EVENT_TYPE
+        );
+        let request = self.authenticated_request(Method::GET, &path)?;
+        let response = self.send(request).await?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        // Kept as a request error, so the send queue retries transient failures
+        if !status.is_success() {
+            let error_text = response.text().await?;
+            return Err(HttpClientError::from_response(status.as_u16(), &error_text).into());
+        }
+
+        Ok(Some(response.json().await?))
+    }
+
+    /// Get the user IDs of a room's joined members
+    pub async fn joined_members(&self, room_id: &str) -> Result<Vec<String>> {
+        let path =
+            format!("/_matrix/client/v3/rooms/{}/joined_members", urlencoding::encode(room_id));
+        let request = self.authenticated_request(Method::GET, &path)?;
+        let response = self.send(request).await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await?;
+            return Err(HttpClientError::from_response(status.as_u16(), &error_text).into());
+        }
+
+        #[derive(Deserialize)]
+        struct JoinedMembersResponse {
+            joined: HashMap<String, Value>,
+        }
+
+        let members: JoinedMembersResponse = response.json().await?;
+        Ok(members.joined.into_keys().collect())
+    }
+
+    /// Make sure every member device of an encrypted room holds our current room key
+    ///
+    /// Tracks the members' device lists, establishes missing Olm sessions and
+    /// sends the Megolm key over to-device, rotating it when required.
+    pub async fn share_room_key(
+        &self,
+        room_id: &str,
+        settings: &RoomEncryptionContent,
+    ) -> Result<()> {
+        let machine = self.require_olm_machine()?;
+        let members = self.joined_members(room_id).await?;
+
+        machine.lock().await.update_tracked_users(members.iter().cloned());
+        self.process_crypto_requests().await?;
+
+        let missing = machine.lock().await.missing_sessions(&members);
+        if let Some(missing) = missing {
+            let response = self.claim_keys(missing).await?;
+            machine.lock().await.receive_keys_claim_response(&response)?;
+        }
+
+        let request = machine.lock().await.share_room_key(room_id, &members, settings)?;
+        if let Some(request) = request {
+            debug!("Sharing room key for {} with {} users", room_id, request.messages.len());
+            self.send_to_device(&request.event_type, request.messages).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Encrypt an event with Megolm and send it to an encrypted room
+    pub async fn send_encrypted_event(
+        &self,
+        room_id: &str,
+        event_type: &str,
+        content: Value,
+        settings: &RoomEncryptionContent,
+    ) -> Result<String> {
+        let txn_id = uuid::Uuid::new_v4().to_string();
+        self.send_encrypted_event_with_txn_id(room_id, event_type, content, settings, &txn_id)
+            .await
+    }
+
+    /// Encrypt and send an event under the given transaction ID
+    pub(crate) async fn send_encrypted_event_with_txn_id(
+        &self,
+        room_id: &str,
+        event_type: &str,
+        content: Value,
+        settings: &RoomEncryptionContent,
+        txn_id: &str,
+    ) -> Result<String> {
+        let machine = self.olm_machine.clone().ok_or_else(|| {
+            anyhow::anyhow!("Room {} is encrypted but encryption is not enabled", room_id)
+        })?;
+
+        self.share_room_key(room_id, settings).await?;
+        let encrypted = machine.lock().await.encrypt_room_event(room_id, event_type, content)?;
+
+        let path = format!(
+            "/_matrix/client/v3/rooms/{}/send/{}/{}",
+            urlencoding::encode(room_id),
+            RoomEncryptedContent::EVENT_TYPE,
+            urlencoding::encode(txn_id)
+        );
+        let request = self.authenticated_request(Method::PUT, &path)?;
+        let response = self.send(request.json(&encrypted)).await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await?;
+            return Err(HttpClientError::from_response(status.as_u16(), &error_text).into());
+        }
+
+        #[derive(Deserialize)]
+        struct SendEventResponse {
+            event_id: String,
+        }
+
+        let send_response: SendEventResponse = response.json().await?;
+        Ok(send_response.event_id)
+    }
+
+    /// Decrypt an `m.room.encrypted` timeline event
+    pub async fn decrypt_event(&self, event: &Event) -> Result<DecryptedRoomEvent> {
+        if event.event_type != RoomEncryptedContent::EVENT_TYPE {
+            return Err(anyhow::anyhow!("Event {} is not encrypted", event.event_id));
+        }
+
+        let machine = self.require_olm_machine()?;
+        let content = serde_json::to_value(&event.content)?;
+        let decrypted = machine.lock().await.decrypt_room_event(
+            &event.room_id,
+            &event.event_id,
+            &event.sender,
+            &content,
+        )?;
+        Ok(decrypted)
+    }
+}
@@ -50,6 +50,10 @@ pub enum HttpClientError {
 
     #[error("Max retries exceeded")]
     MaxRetriesExceeded,
+
+    /// Encrypting a request failed, e.g. because encryption is not enabled
+    #[error("Encryption failed: {0}")]
+    Encryption(String),
 }
 
 impl HttpClientError {
@@ -69,6 +73,7 @@ impl HttpClientError {
             HttpClientError::MaxRetriesExceeded => false,
             HttpClientError::Serialization(_) => false,
             HttpClientError::InvalidUrl(_) => false,
+            HttpClientError::Encryption(_) => false,
         }
     }
 
@@ -112,6 +117,40 @@ impl HttpClientError {
             None => false,
         }
     }
+
+    /// Build the error for an unsuccessful response from its status and body
+    pub fn from_response(status: u16, body: &str) -> Self {
+        match serde_json::from_str::<MatrixErrorResponse>(body) {
+            Ok(matrix_err) => {
+                HttpClientError::Matrix {
+                    status,
+                    errcode: matrix_err.errcode,
+                    error: matrix_err.error,
+                    retry_after_ms: matrix_err.retry_after_ms,
+                }
+            }
+            Err(parse_err) => {
+                // Log parse error with full details for debugging
+                tracing::warn!(
+                    "Failed to parse error response as Matrix error (status {}): {}",
+                    status,
+                    parse_err
+                );
+                tracing::debug!("Response body: {}", body);
+
+                // Return InvalidResponse error with preserved information
+                HttpClientError::InvalidResponse {
+                    status,
+                    body: if body.len() > 200 {
+                        format!("{}... (truncated)", &body[..200])
+                    } else {
+                        body.to_string()
+                    },
+                    parse_error: parse_err.to_string(),
+                }
+            }
+        }
+    }
 }
 
 /// Matrix error response format per specification
@@ -125,6 +164,10 @@ struct MatrixErrorResponse {
     #[allow(dead_code)] // Used for deserialization but not accessed
     soft_logout: Option<bool>,
 }
+
+/// Retry budget of the typed endpoint bindings in [`crate::_matrix`]
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
+
 /// Low-level HTTP client for Matrix API requests
 #[derive(Clone)]
 pub struct MatrixHttpClient {
@@ -213,36 +256,7 @@ impl MatrixHttpClient {
 
     /// Parse Matrix error response per specification
     fn parse_matrix_error<T>(&self, status: u16, body: &str) -> Result<T, HttpClientError> {
-        match serde_json::from_str::<MatrixErrorResponse>(body) {
-            Ok(matrix_err) => {
-                Err(HttpClientError::Matrix {
-                    status,
-                    errcode: matrix_err.errcode,
-                    error: matrix_err.error,
-                    retry_after_ms: matrix_err.retry_after_ms,
-                })
-            }
-            Err(parse_err) => {
-                // Log parse error with full details for debugging
-                tracing::warn!(
-                    "Failed to parse error response as Matrix error (status {}): {}",
-                    status,
-                    parse_err
-                );
-                tracing::debug!("Response body: {}", body);
-
-                // Return InvalidResponse error with preserved information
-                Err(HttpClientError::InvalidResponse {
-                    status,
-                    body: if body.len() > 200 {
-                        format!("{}... (truncated)", &body[..200])
-                    } else {
-                        body.to_string()
-                    },
-                    parse_error: parse_err.to_string(),
-                })
-            }
-        }
+        Err(HttpClientError::from_response(status, body))
     }
 
     /// Set access token for authenticated requests
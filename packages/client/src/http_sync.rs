@@ -0,0 +1,688 @@
+//! Client-Server API sync engine
+//!
+//! Runs `/sync` in a loop, long-polling or as a `text/event-stream`, and
+//! turns every response into the same [`SyncUpdate`]s that
+//! [`crate::sync::LiveQuerySync`] produces, so the real-time client works
+//! with nothing but an access token.
+
+use anyhow::Result;
+use futures_util::{Stream, StreamExt};
+use matryx_entity::{Event, Membership, MembershipState, SyncTransport};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, RwLock, broadcast};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+use url::Url;
+
+use crate::room_list::RoomListService;
+use crate::store::{
+    DEFAULT_TIMELINE_LIMIT, RoomMember, StateChanges, StateStore, account_data_events,
+    receipt_events, section_events,
+};
+use crate::sync::{
+    DeviceListUpdates, InvitedRoomState, LeftRoomState, PresenceState, SyncState, SyncUpdate,
+};
+use crate::{MatrixClient, SyncResponse};
+
+/// Time allowed beyond the long-poll timeout before a `/sync` request is abandoned
+const REQUEST_GRACE: Duration = Duration::from_secs(15);
+
+/// Silence after which an event stream counts as dead; the server sends a
+/// keep-alive every 30 seconds
+const SSE_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// `/sync` loop over the Client-Server API
+pub struct HttpSync {
+    sync_loop: SyncLoop,
+    /// Receiver for sync updates
+    update_receiver: broadcast::Receiver<SyncUpdate>,
+    /// Running sync loop
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+#[derive(Clone)]
+struct SyncLoop {
+    http_client: reqwest::Client,
+    sync_url: Url,
+    access_token: String,
+    transport: SyncTransport,
+    timeout: Duration,
+    max_retries: u32,
+    retry_delay: Duration,
+    state: Arc<RwLock<SyncState>>,
+    store: Option<Arc<dyn StateStore>>,
+    /// Room list to keep up to date, with our user ID
+    room_list: Option<(String, Arc<RoomListService>)>,
+    /// Client whose Olm machine receives the to-device events and decrypts
+    /// room events
+    crypto: Option<Arc<MatrixClient>>,
+    update_sender: broadcast::Sender<SyncUpdate>,
+}
+
+impl HttpSync {
+    /// Create a sync loop for the user of `access_token`
+    ///
+    /// [`SyncTransport::LiveQuery`] does not go through the Client-Server
+    /// API and is rejected.
+    pub fn new(
+        homeserver_url: &Url,
+        access_token: String,
+        transport: SyncTransport,
+    ) -> Result<Self> {
+        if transport == SyncTransport::LiveQuery {
+            return Err(anyhow::anyhow!("LiveQuery sync requires LiveQuerySync"));
+        }
+
+        // No overall timeout: it would cut off event streams
+        let http_client = reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(30))
+            .user_agent("Matryx-Realtime-Client/0.1.0")
+            .build()?;
+        let (update_sender, update_receiver) = broadcast::channel(1000);
+
+        Ok(Self {
+            sync_loop: SyncLoop {
+                http_client,
+                sync_url: homeserver_url.join("/_matrix/client/v3/sync")?,
+                access_token,
+                transport,
+                timeout: Duration::from_secs(30),
+                max_retries: 5,
+                retry_delay: Duration::from_secs(5),
+                state: Arc::new(RwLock::new(SyncState::default())),
+                store: None,
+                room_list: None,
+                crypto: None,
+                update_sender,
+            },
+            update_receiver,
+            task: Mutex::new(None),
+        })
+    }
+
+    /// Long-poll timeout of each `/sync` request
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.sync_loop.timeout = timeout;
+        self
+    }
+
+    /// Give up after `max_retries` failures in a row, waiting `retry_delay`
+    /// longer after each one
+    pub fn with_retries(mut self, max_retries: u32, retry_delay: Duration) -> Self {
+        self.sync_loop.max_retries = max_retries;
+        self.sync_loop.retry_delay = retry_delay;
+        self
+    }
+
+    /// Persist every response and resume from the stored sync token
+    pub fn with_store(mut self, store: Arc<dyn StateStore>) -> Self {
+        self.sync_loop.store = Some(store);
+        self
+    }
+
+    /// Keep `room_list` up to date, seeding it from the store if there is one
+    pub fn with_room_list(mut self, own_user_id: String, room_list: Arc<RoomListService>) -> Self {
+        self.sync_loop.room_list = Some((own_user_id, room_list));
+        self
+    }
+
+    /// Feed the encryption state of `client` and decrypt `m.room.encrypted`
+    /// room events before they are published
+    pub fn with_encryption(mut self, client: Arc<MatrixClient>) -> Self {
+        self.sync_loop.crypto = Some(client);
+        self
+    }
+
+    /// Start the sync loop in the background
+    pub async fn start(&self) -> Result<()> {
+        let mut task = self.task.lock().await;
+        if task.is_some() {
+            return Err(anyhow::anyhow!("Sync is already running"));
+        }
+
+        let since = match &self.sync_loop.store {
+            Some(store) => store.sync_token().await?,
+            None => None,
+        };
+        info!(
+            "Starting {:?} sync {}",
+            self.sync_loop.transport,
+            since
+                .as_deref()
+                .map_or("from scratch".to_string(), |token| format!("from {}", token))
+        );
+
+        *task = Some(tokio::spawn(self.sync_loop.clone().run(since)));
+        Ok(())
+    }
+
+    /// Stop the sync loop
+    pub async fn stop(&self) -> Result<()> {
+        if let Some(task) = self.task.lock().await.take() {
+            task.abort();
+            info!("Stopped {:?} sync", self.sync_loop.transport);
+        }
+        Ok(())
+    }
+
+    /// Get a stream of sync updates
+    pub fn sync_stream(&self) -> impl Stream<Item = SyncUpdate> + Send + Unpin {
+        let receiver = self.sync_loop.update_sender.subscribe();
+        Box::pin(tokio_stream::wrappers::BroadcastStream::new(receiver).filter_map(
+            |result| async move {
+                match result {
+                    Ok(update) => Some(update),
+                    Err(e) => {
+                        warn!("Error in sync stream: {}", e);
+                        None
+                    },
+                }
+            },
+        ))
+    }
+
+    /// Get current sync state
+    pub async fn get_sync_state(&self) -> SyncState {
+        self.sync_loop.state.read().await.clone()
+    }
+
+    /// Get a receiver for sync updates
+    pub fn subscribe_to_updates(&self) -> broadcast::Receiver<SyncUpdate> {
+        self.update_receiver.resubscribe()
+    }
+}
+
+impl SyncLoop {
+    async fn run(self, mut since: Option<String>) {
+        let mut failures: u32 = 0;
+
+        loop {
+            let previous = since.clone();
+            let result = match self.transport {
+                SyncTransport::ServerSentEvents => self.stream(&mut since).await,
+                _ => self.poll(&mut since).await,
+            };
+            // A stream that delivered updates before failing made progress
+            if since != previous {
+                failures = 0;
+            }
+
+            if let Err(e) = result {
+                failures += 1;
+                let recoverable = failures <= self.max_retries;
+                warn!("Sync failed ({} in a row): {}", failures, e);
+                let _ = self
+                    .update_sender
+                    .send(SyncUpdate::Error { message: e.to_string(), recoverable });
+
+                if !recoverable {
+                    break;
+                }
+                tokio::time::sleep(self.retry_delay * failures).await;
+            }
+        }
+    }
+
+    /// One long-poll `/sync` request
+    async fn poll(&self, since: &mut Option<String>) -> Result<()> {
+        let timeout_ms = self.timeout.as_millis().to_string();
+        let mut request = self
+            .http_client
+            .get(self.sync_url.clone())
+            .bearer_auth(&self.access_token)
+            .timeout(self.timeout + REQUEST_GRACE)
+            .query(&[("timeout", timeout_ms.as_str())]);
+        if let Some(since) = since.as_deref() {
+            request = request.query(&[("since", since)]);
+        }
+
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Sync failed: {}", error_text));
+        }
+
+        let response: SyncResponse = response.json().await?;
+        self.handle_response(response, since).await
+    }
+
+    /// Read `sync` events from a `text/event-stream` until it ends
+    async fn stream(&self, since: &mut Option<String>) -> Result<()> {
+        let mut request = self
+            .http_client
+            .get(self.sync_url.clone())
+            .bearer_auth(&self.access_token)
+            .header(reqwest::header::ACCEPT, "text/event-stream");
+        if let Some(since) = since.as_deref() {
+            request = request.query(&[("since", since)]);
+        }
+
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Sync stream failed: {}", error_text));
+        }
+
+        let mut body = response.bytes_stream();
+        let mut parser = SseParser::default();
+        loop {
+            let chunk = match tokio::time::timeout(SSE_IDLE_TIMEOUT, body.next()).await {
+                Ok(Some(chunk)) => chunk?,
+                Ok(None) => {
+                    debug!("Sync stream ended");
+                    return Ok(());
+                },
+                Err(_) => return Err(anyhow::anyhow!("Sync stream went silent")),
+            };
+
+            for event in parser.push(&chunk) {
+                if event.event.as_deref() != Some("sync") {
+                    continue;
+                }
+                let response: SyncResponse = serde_json::from_str(&event.data)?;
+                self.handle_response(response, since).await?;
+            }
+        }
+    }
+
+    async fn handle_response(
+        &self,
+        response: SyncResponse,
+        since: &mut Option<String>,
+    ) -> Result<()> {
+        if let Some(store) = &self.store {
+            store.save_changes(&StateChanges::from_sync_response(&response)).await?;
+        }
+        if let Some((own_user_id, room_list)) = &self.room_list {
+            if let Some(store) = &self.store {
+                room_list.load_from_store(own_user_id, store.as_ref()).await?;
+            }
+            room_list.handle_sync_response(own_user_id, &response).await;
+        }
+        apply_to_state(&mut *self.state.write().await, &response);
+        if let Some(client) = &self.crypto {
+            client.receive_sync_crypto(&response).await?;
+        }
+
+        for update in sync_updates(&response) {
+            let update = match (update, &self.crypto) {
+                (SyncUpdate::RoomEvent { room_id, event }, Some(client)) => SyncUpdate::RoomEvent {
+                    room_id,
+                    event: client.decrypt_timeline_event(event).await,
+                },
+                (update, _) => update,
+            };
+            // Only fails without receivers, and HttpSync keeps one
+            let _ = self.update_sender.send(update);
+        }
+
+        *since = Some(response.next_batch);
+        Ok(())
+    }
+}
+
+/// Updates of a sync response, in the order the events happened
+fn sync_updates(response: &SyncResponse) -> Vec<SyncUpdate> {
+    let mut updates = Vec::new();
+
+    for (room_id, room) in &response.rooms.join {
+        let state = room.state.iter().flat_map(|state| &state.events);
+        let timeline = room.timeline.iter().flat_map(|timeline| &timeline.events);
+        room_event_updates(&mut updates, room_id, state, false);
+        room_event_updates(&mut updates, room_id, timeline, true);
+
+        for event in section_events(room.ephemeral.as_ref()) {
+            if event.get("type").and_then(Value::as_str) == Some("m.typing") {
+                let user_ids = event
+                    .pointer("/content/user_ids")
+                    .and_then(|ids| serde_json::from_value(ids.clone()).ok())
+                    .unwrap_or_default();
+                updates.push(SyncUpdate::Typing { room_id: room_id.clone(), user_ids });
+            }
+        }
+        for receipt in receipt_events(room.ephemeral.as_ref()) {
+            updates.push(SyncUpdate::Receipt { room_id: room_id.clone(), receipt });
+        }
+    }
+
+    for (room_id, room) in &response.rooms.invite {
+        let state = room.invite_state.iter().flat_map(|state| &state.events);
+        room_event_updates(&mut updates, room_id, state, false);
+    }
+
+    for (room_id, room) in &response.rooms.leave {
+        let state = room.state.iter().flat_map(|state| &state.events);
+        let timeline = room.timeline.iter().flat_map(|timeline| &timeline.events);
+        room_event_updates(&mut updates, room_id, state, false);
+        room_event_updates(&mut updates, room_id, timeline, true);
+    }
+
+    for (data_type, content) in account_data_events(response.account_data.as_ref()) {
+        updates.push(SyncUpdate::AccountDataUpdate { data_type, content });
+    }
+
+    for (user_id, presence) in presence_updates(response) {
+        updates.push(SyncUpdate::PresenceUpdate { user_id, presence });
+    }
+
+    if let Some(device_lists) = device_list_updates(response)
+        && (!device_lists.changed.is_empty() || !device_lists.left.is_empty())
+    {
+        updates.push(SyncUpdate::DeviceListUpdate {
+            changed: device_lists.changed,
+            left: device_lists.left,
+        });
+    }
+
+    updates
+}
+
+/// State and timeline events of a room, with the membership changes among them
+fn room_event_updates<'a>(
+    updates: &mut Vec<SyncUpdate>,
+    room_id: &str,
+    events: impl Iterator<Item = &'a Event>,
+    timeline: bool,
+) {
+    for event in events {
+        if let Some(member) = RoomMember::from_event(event) {
+            let mut membership =
+                Membership::new(room_id.to_string(), member.user_id.clone(), member.membership);
+            membership.display_name = member.display_name;
+            membership.avatar_url = member.avatar_url;
+            if membership.membership == MembershipState::Invite {
+                membership.invited_by = Some(event.sender.clone());
+            }
+            updates.push(SyncUpdate::MembershipUpdate {
+                room_id: room_id.to_string(),
+                user_id: member.user_id,
+                membership,
+            });
+        }
+
+        let room_id = room_id.to_string();
+        let event = event.clone();
+        updates.push(if timeline && event.state_key.is_none() {
+            SyncUpdate::RoomEvent { room_id, event }
+        } else {
+            SyncUpdate::StateUpdate { room_id, event }
+        });
+    }
+}
+
+fn presence_updates(response: &SyncResponse) -> Vec<(String, PresenceState)> {
+    section_events(response.presence.as_ref())
+        .filter_map(|event| {
+            let user_id = event.get("sender")?.as_str()?.to_string();
+            let content = event.get("content")?;
+            let string_field =
+                |field: &str| content.get(field).and_then(Value::as_str).map(str::to_string);
+
+            Some((
+                user_id,
+                PresenceState {
+                    presence: string_field("presence").unwrap_or_else(|| "offline".to_string()),
+                    status_msg: string_field("status_msg"),
+                    last_active_ago: content.get("last_active_ago").and_then(Value::as_u64),
+                    currently_active: content.get("currently_active").and_then(Value::as_bool),
+                },
+            ))
+        })
+        .collect()
+}
+
+fn device_list_updates(response: &SyncResponse) -> Option<DeviceListUpdates> {
+    serde_json::from_value(response.device_lists.clone()?).ok()
+}
+
+/// Fold a sync response into the sync state
+fn apply_to_state(state: &mut SyncState, response: &SyncResponse) {
+    state.next_batch = response.next_batch.clone();
+
+    for (room_id, room) in &response.rooms.join {
+        state.invited_rooms.remove(room_id);
+        state.left_rooms.remove(room_id);
+        let joined = state.joined_rooms.entry(room_id.clone()).or_default();
+
+        if let Some(room_state) = &room.state {
+            merge_state(&mut joined.state, &room_state.events);
+        }
+        if let Some(timeline) = &room.timeline {
+            if timeline.limited.unwrap_or(false) {
+                joined.timeline.clear();
+            }
+            joined.timeline.extend(timeline.events.iter().cloned());
+            let excess = joined.timeline.len().saturating_sub(DEFAULT_TIMELINE_LIMIT);
+            joined.timeline.drain(..excess);
+
+            let state_events: Vec<Event> =
+                timeline.events.iter().filter(|e| e.state_key.is_some()).cloned().collect();
+            merge_state(&mut joined.state, &state_events);
+        }
+        if room.ephemeral.is_some() {
+            joined.ephemeral = section_events(room.ephemeral.as_ref()).cloned().collect();
+        }
+        merge_account_data(&mut joined.account_data, room.account_data.as_ref());
+        if let Some(counts) = room
+            .unread_notifications
+            .clone()
+            .and_then(|counts| serde_json::from_value(counts).ok())
+        {
+            joined.unread_notifications = counts;
+        }
+    }
+
+    for (room_id, room) in &response.rooms.invite {
+        let invite_state = room.invite_state.as_ref().map(|s| s.events.clone()).unwrap_or_default();
+        state
+            .invited_rooms
+            .insert(room_id.clone(), InvitedRoomState { invite_state });
+    }
+
+    for (room_id, room) in &response.rooms.leave {
+        state.joined_rooms.remove(room_id);
+        state.invited_rooms.remove(room_id);
+        let mut left = LeftRoomState {
+            state: room.state.as_ref().map(|s| s.events.clone()).unwrap_or_default(),
+            timeline: room.timeline.as_ref().map(|t| t.events.clone()).unwrap_or_default(),
+            account_data: Vec::new(),
+        };
+        merge_account_data(&mut left.account_data, room.account_data.as_ref());
+        state.left_rooms.insert(room_id.clone(), left);
+    }
+
+    state
+        .account_data
+        .extend(account_data_events(response.account_data.as_ref()));
+
+    if let Some(device_lists) = device_list_updates(response) {
+        state.device_lists = device_lists;
+    }
+    state.presence.extend(presence_updates(response));
+}
+
+/// Replace state events by type and state key
+fn merge_state(state: &mut Vec<Event>, events: &[Event]) {
+    for event in events {
+        let existing = state.iter_mut().find(|known| {
+            known.event_type == event.event_type && known.state_key == event.state_key
+        });
+        match existing {
+            Some(known) => *known = event.clone(),
+            None => state.push(event.clone()),
+        }
+    }
+}
+
+/// Replace account data events by type
+fn merge_account_data(account_data: &mut Vec<Value>, section: Option<&Value>) {
+    let latest: HashMap<&str, &Value> = section_events(section)
+        .filter_map(|event| Some((event.get("type")?.as_str()?, event)))
+        .collect();
+    account_data.retain(|event| {
+        !event
+            .get("type")
+            .and_then(Value::as_str)
+            .is_some_and(|t| latest.contains_key(t))
+    });
+    account_data.extend(latest.into_values().cloned());
+}
+
+/// A dispatched Server-Sent Event
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SseEvent {
+    event: Option<String>,
+    data: String,
+}
+
+/// Incremental `text/event-stream` parser
+#[derive(Debug, Default)]
+struct SseParser {
+    buffer: Vec<u8>,
+    event: Option<String>,
+    data: Vec<String>,
+}
+
+impl SseParser {
+    /// Feed a chunk of the body and return the events it completed
+    fn push(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events = Vec::new();
+
+        while let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\n', '\r']);
+
+            if line.is_empty() {
+                if !self.data.is_empty() {
+                    events.push(SseEvent {
+                        event: self.event.take(),
+                        data: self.data.join("\n"),
+                    });
+                }
+                self.event = None;
+                self.data.clear();
+                continue;
+            }
+            if line.starts_with(':') {
+                continue;
+            }
+
+            let (field, value) = line.split_once(':').unwrap_or((line, ""));
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            match field {
+                "event" => self.event = Some(value.to_string()),
+                "data" => self.data.push(value.to_string()),
+                _ => {},
+            }
+        }
+
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event(event_id: &str, event_type: &str, state_key: Option<&str>) -> Value {
+        json!({
+            "event_id": event_id,
+            "sender": "@alice:example.org",
+            "origin_server_ts": 0,
+            "type": event_type,
+            "room_id": "!room:example.org",
+            "content": { "membership": "join", "body": "hi" },
+            "state_key": state_key,
+        })
+    }
+
+    #[test]
+    fn test_sse_parser() {
+        let mut parser = SseParser::default();
+        assert!(parser.push(b": keep-alive\n\nevent: sync\nda").is_empty());
+
+        let events = parser.push(b"ta: {\"a\":1}\r\n\r\ndata: x\ndata: y\n\n");
+        assert_eq!(
+            events,
+            [
+                SseEvent {
+                    event: Some("sync".to_string()),
+                    data: "{\"a\":1}".to_string()
+                },
+                SseEvent { event: None, data: "x\ny".to_string() },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sync_updates_from_streamed_response() {
+        // Streamed updates leave out sections as `null`
+        let response: SyncResponse = serde_json::from_value(json!({
+            "next_batch": "s1",
+            "rooms": {
+                "join": {
+                    "!room:example.org": {
+                        "timeline": {
+                            "events": [
+                                event("$member", "m.room.member", Some("@bob:example.org")),
+                                event("$message", "m.room.message", None),
+                            ],
+                            "limited": false,
+                        },
+                        "state": null,
+                        "ephemeral": { "events": [
+                            { "type": "m.typing", "content": { "user_ids": ["@bob:example.org"] } },
+                        ] },
+                        "account_data": null,
+                        "unread_notifications": { "highlight_count": 1, "notification_count": 2 },
+                    },
+                },
+                "invite": null,
+                "leave": null,
+            },
+            "presence": { "events": [
+                { "type": "m.presence", "sender": "@bob:example.org", "content": { "presence": "online" } },
+            ] },
+            "account_data": null,
+            "to_device": null,
+            "device_lists": { "changed": ["@bob:example.org"] },
+        }))
+        .expect("streamed update should deserialize");
+
+        let updates = sync_updates(&response);
+        assert!(matches!(
+            &updates[0],
+            SyncUpdate::MembershipUpdate { user_id, .. } if user_id == "@bob:example.org"
+        ));
+        assert!(
+            matches!(&updates[1], SyncUpdate::StateUpdate { event, .. } if event.event_id == "$member")
+        );
+        assert!(
+            matches!(&updates[2], SyncUpdate::RoomEvent { event, .. } if event.event_id == "$message")
+        );
+        assert!(matches!(&updates[3], SyncUpdate::Typing { user_ids, .. } if user_ids.len() == 1));
+        assert!(
+            matches!(&updates[4], SyncUpdate::PresenceUpdate { presence, .. } if presence.presence == "online")
+        );
+        assert!(
+            matches!(&updates[5], SyncUpdate::DeviceListUpdate { changed, .. } if changed.len() == 1)
+        );
+        assert_eq!(updates.len(), 6);
+
+        let mut state = SyncState::default();
+        apply_to_state(&mut state, &response);
+        apply_to_state(&mut state, &response);
+        assert_eq!(state.next_batch, "s1");
+        let room = &state.joined_rooms["!room:example.org"];
+        assert_eq!(room.state.len(), 1);
+        assert_eq!(room.timeline.len(), 4);
+        assert_eq!(room.unread_notifications.notification_count, 2);
+        assert!(state.presence.contains_key("@bob:example.org"));
+    }
+}
@@ -8,22 +8,49 @@
 #![cfg_attr(test, allow(clippy::unwrap_used, clippy::expect_used))]
 
 pub mod _matrix;
+pub mod backup;
+pub mod bot;
+pub mod crypto;
 pub mod device;
+pub mod encryption;
 pub mod http_client;
+pub mod http_sync;
+pub mod login;
+pub mod media;
+pub mod notifications;
+pub mod oauth;
 pub mod realtime;
 pub mod repositories;
+pub mod room_list;
+pub mod room_timeline;
+pub mod secret_storage;
+pub mod send_queue;
+pub mod state_store;
+pub mod store;
 pub mod sync;
+pub mod timeline;
+pub mod verification;
 
 use anyhow::Result;
 use chrono::{DateTime, Utc};
+use matryx_entity::types::RoomMessageContent;
 use std::sync::OnceLock;
 
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, OnceCell, RwLock};
 use url::Url;
 
+use crate::_matrix::client::versions::VersionsResponse;
+use crate::crypto::{OlmMachine, VerificationMachine};
+use crate::http_client::HttpClientError;
+use crate::login::MatrixSession;
+use crate::room_list::RoomListService;
+use crate::send_queue::SendQueue;
+use crate::store::StateStore;
+use crate::timeline::Timelines;
+
 /// Default homeserver URL - initialized once and cached
 static DEFAULT_HOMESERVER_URL: OnceLock<Url> = OnceLock::new();
 
@@ -94,10 +121,28 @@ pub struct MatrixClient {
     http_client: Client,
     /// Client configuration
     config: ClientConfig,
-    /// Authentication credentials
-    credentials: Option<Credentials>,
+    /// Login session, replaced when the access token is refreshed
+    session: Arc<std::sync::RwLock<Option<MatrixSession>>>,
+    /// Serializes access token refreshes, as refresh tokens are single-use
+    refresh_lock: Arc<Mutex<()>>,
+    /// Notifies about new sessions, so they can be saved
+    session_sender: tokio::sync::broadcast::Sender<MatrixSession>,
+    /// Specification versions advertised by the homeserver, once checked
+    server_versions: Option<VersionsResponse>,
     /// Client state
     state: Arc<RwLock<ClientState>>,
+    /// End-to-end encryption state, once enabled
+    olm_machine: Option<Arc<Mutex<OlmMachine>>>,
+    /// Interactive verification flows, alongside the Olm machine
+    verification_machine: Option<Arc<Mutex<VerificationMachine>>>,
+    /// Persistent client state, once set
+    store: Option<Arc<dyn StateStore>>,
+    /// Open room and thread timelines
+    timelines: Mutex<Timelines>,
+    /// Sorted joined and invited rooms
+    room_list: Arc<RoomListService>,
+    /// Queue of outgoing room events, started by the first send
+    send_queue: OnceCell<SendQueue>,
 }
 
 impl MatrixClient {
@@ -114,10 +159,28 @@ impl MatrixClient {
             connected: false,
         }));
 
-        Ok(Self { http_client, config, credentials: None, state })
+        Ok(Self {
+            http_client,
+            config,
+            session: Arc::new(std::sync::RwLock::new(None)),
+            refresh_lock: Arc::new(Mutex::new(())),
+            session_sender: tokio::sync::broadcast::channel(16).0,
+            server_versions: None,
+            state,
+            olm_machine: None,
+            verification_machine: None,
+            store: None,
+            timelines: Mutex::new(std::collections::HashMap::new()),
+            room_list: Arc::new(RoomListService::new()),
+            send_queue: OnceCell::new(),
+        })
     }
 
     /// Login with username and password
+    ///
+    /// `username` is a localpart or user ID on the configured homeserver; use
+    /// [`Self::login_with_user_id`] to discover the homeserver instead. Passing
+    /// the device ID of an earlier session keeps using that device.
     pub async fn login(
         &mut self,
         username: &str,
@@ -125,29 +188,29 @@ impl MatrixClient {
         device_id: Option<String>,
     ) -> Result<()> {
         let response = self.login_password(username, password, device_id.as_deref()).await?;
-
-        self.credentials = Some(Credentials {
-            user_id: response.user_id,
-            access_token: response.access_token,
-            device_id: response.device_id,
-        });
-
-        Ok(())
+        self.finish_login(response)
     }
 
     /// Get the current user ID (if logged in)
-    pub fn user_id(&self) -> Option<&String> {
-        self.credentials.as_ref().map(|c| &c.user_id)
+    pub fn user_id(&self) -> Option<String> {
+        self.read_session().as_ref().map(|session| session.user_id.clone())
+    }
+
+    /// Get the current device ID (if logged in)
+    pub fn device_id(&self) -> Option<String> {
+        self.read_session().as_ref().and_then(|session| session.device_id.clone())
     }
 
     /// Check if client is authenticated
     pub fn is_authenticated(&self) -> bool {
-        self.credentials.is_some()
+        self.read_session().is_some()
     }
 
     /// Get the access token (if logged in)
-    pub fn access_token(&self) -> Option<&str> {
-        self.credentials.as_ref().map(|c| c.access_token.as_str())
+    ///
+    /// The token changes when it is refreshed.
+    pub fn access_token(&self) -> Option<String> {
+        self.read_session().as_ref().map(|session| session.access_token.clone())
     }
 
     /// Get the homeserver URL
@@ -161,16 +224,12 @@ impl MatrixClient {
         method: reqwest::Method,
         path: &str,
     ) -> Result<reqwest::RequestBuilder> {
-        let credentials = self
-            .credentials
-            .as_ref()
+        let access_token = self
+            .access_token()
             .ok_or_else(|| anyhow::anyhow!("Client is not authenticated"))?;
 
         let url = self.config.homeserver_url.join(path)?;
-        let request = self
-            .http_client
-            .request(method, url)
-            .bearer_auth(&credentials.access_token);
+        let request = self.http_client.request(method, url).bearer_auth(access_token);
 
         Ok(request)
     }
@@ -186,8 +245,12 @@ impl MatrixClient {
 
         let mut login_data = serde_json::json!({
             "type": "m.login.password",
-            "user": username,
-            "password": password
+            "identifier": {
+                "type": "m.id.user",
+                "user": username
+            },
+            "password": password,
+            "refresh_token": true
         });
 
         if let Some(device_id) = device_id {
@@ -232,14 +295,39 @@ impl MatrixClient {
         }
 
         let request = self.authenticated_request(reqwest::Method::GET, url.path())?;
-        let response = request.send().await?;
+        let response = self.send(request).await?;
 
         if !response.status().is_success() {
             let error_text = response.text().await?;
             return Err(anyhow::anyhow!("Sync failed: {}", error_text));
         }
 
-        let sync_response: SyncResponse = response.json().await?;
+        let mut sync_response: SyncResponse = response.json().await?;
+
+        // Store and back up room keys, drive verifications and hand back decrypted
+        // to-device events
+        if self.olm_machine.is_some() {
+            let to_device = self.receive_sync_crypto(&sync_response).await?;
+            self.receive_sync_verification(&to_device, &sync_response).await?;
+            if let Err(e) = self.backup_room_keys().await {
+                tracing::warn!("Failed to back up room keys: {}", e);
+            }
+            let events: Vec<serde_json::Value> = to_device
+                .into_iter()
+                .map(|event| {
+                    serde_json::json!({
+                        "sender": event.sender,
+                        "type": event.event_type,
+                        "content": event.content,
+                    })
+                })
+                .collect();
+            sync_response.to_device = Some(serde_json::json!({ "events": events }));
+        }
+
+        self.save_sync_response(&sync_response).await?;
+        self.update_timelines(&sync_response).await;
+        self.update_room_list(&sync_response).await?;
 
         // Update client state
         {
@@ -257,6 +345,23 @@ impl MatrixClient {
         self.state.read().await.clone()
     }
 
+    /// Sorted list of the joined and invited rooms, kept up to date by [`Self::sync`]
+    pub fn room_list(&self) -> Arc<RoomListService> {
+        self.room_list.clone()
+    }
+
+    /// Feed a sync to the room list, first seeding it from the store
+    async fn update_room_list(&self, response: &SyncResponse) -> Result<()> {
+        let Some(user_id) = self.user_id() else {
+            return Ok(());
+        };
+        if let Some(store) = &self.store {
+            self.room_list.load_from_store(&user_id, store.as_ref()).await?;
+        }
+        self.room_list.handle_sync_response(&user_id, response).await;
+        Ok(())
+    }
+
     /// Create a room
     pub async fn create_room(
         &self,
@@ -280,7 +385,7 @@ impl MatrixClient {
 
         let request =
             self.authenticated_request(reqwest::Method::POST, "/_matrix/client/v3/createRoom")?;
-        let response = request.json(&room_data).send().await?;
+        let response = self.send(request.json(&room_data)).await?;
 
         if !response.status().is_success() {
             let error_text = response.text().await?;
@@ -296,22 +401,99 @@ impl MatrixClient {
         Ok(create_response.room_id)
     }
 
-    /// Send a message to a room
+    /// Send a message to a room through [`Self::send_queue`]
+    ///
+    /// Messages to rooms with `m.room.encryption` set are Megolm-encrypted;
+    /// this fails if encryption has not been enabled on the client.
+    ///
+    /// Waits until the homeserver accepted the message. Network errors,
+    /// server errors and rate limits are retried under the same transaction
+    /// ID, so the message is sent at most once.
     pub async fn send_message(&self, room_id: &str, message: &str) -> Result<String> {
-        let txn_id = uuid::Uuid::new_v4().to_string();
-        let path = format!("/_matrix/client/v3/rooms/{}/send/m.room.message/{}", room_id, txn_id);
+        let content = serde_json::to_value(RoomMessageContent::text_plain(message))?;
+        self.send_queue()
+            .await?
+            .send(room_id, RoomMessageContent::EVENT_TYPE, content)
+            .await
+    }
 
-        let message_data = serde_json::json!({
-            "msgtype": "m.text",
-            "body": message
-        });
+    /// The queue outgoing messages are sent through, started on first use
+    ///
+    /// With a state store, events left from before a restart are sent first.
+    pub async fn send_queue(&self) -> Result<&SendQueue> {
+        self.send_queue
+            .get_or_try_init(|| async {
+                let queue = SendQueue::new(Arc::new(self.send_handle()), self.store.clone());
+                queue.load().await?;
+                Ok::<_, anyhow::Error>(queue)
+            })
+            .await
+    }
+
+    /// A client for the send queue to send through
+    ///
+    /// It shares the session, so token refreshes apply to both, and takes
+    /// the encryption state and store as they are now.
+    fn send_handle(&self) -> Self {
+        Self {
+            http_client: self.http_client.clone(),
+            config: self.config.clone(),
+            session: self.session.clone(),
+            refresh_lock: self.refresh_lock.clone(),
+            session_sender: self.session_sender.clone(),
+            server_versions: self.server_versions.clone(),
+            state: self.state.clone(),
+            olm_machine: self.olm_machine.clone(),
+            verification_machine: self.verification_machine.clone(),
+            store: self.store.clone(),
+            timelines: Mutex::new(std::collections::HashMap::new()),
+            room_list: self.room_list.clone(),
+            send_queue: OnceCell::new(),
+        }
+    }
+
+    /// Start a new send queue on the next send
+    ///
+    /// Called when the state [`Self::send_handle`] copies changes. The old
+    /// queue still sends what it holds, and an event it sends to an
+    /// encrypted room without encryption fails rather than going out in
+    /// plaintext.
+    fn reset_send_queue(&mut self) {
+        self.send_queue.take();
+    }
+
+    /// Send an event to a room under the given transaction ID
+    ///
+    /// Retrying with the same transaction ID does not send the event twice.
+    /// Events to encrypted rooms are Megolm-encrypted like in [`Self::send_message`].
+    /// Request failures are returned as [`HttpClientError`].
+    pub async fn send_event(
+        &self,
+        room_id: &str,
+        event_type: &str,
+        content: serde_json::Value,
+        txn_id: &str,
+    ) -> Result<String> {
+        if let Some(settings) = self.room_encryption(room_id).await? {
+            return self
+                .send_encrypted_event_with_txn_id(room_id, event_type, content, &settings, txn_id)
+                .await;
+        }
+
+        let path = format!(
+            "/_matrix/client/v3/rooms/{}/send/{}/{}",
+            urlencoding::encode(room_id),
+            urlencoding::encode(event_type),
+            urlencoding::encode(txn_id)
+        );
 
         let request = self.authenticated_request(reqwest::Method::PUT, &path)?;
-        let response = request.json(&message_data).send().await?;
+        let response = self.send(request.json(&content)).await?;
 
-        if !response.status().is_success() {
+        let status = response.status();
+        if !status.is_success() {
             let error_text = response.text().await?;
-            return Err(anyhow::anyhow!("Failed to send message: {}", error_text));
+            return Err(HttpClientError::from_response(status.as_u16(), &error_text).into());
         }
 
         #[derive(Deserialize)]
@@ -328,7 +510,7 @@ impl MatrixClient {
         let path = format!("/_matrix/client/v3/join/{}", room_id_or_alias);
 
         let request = self.authenticated_request(reqwest::Method::POST, &path)?;
-        let response = request.json(&serde_json::json!({})).send().await?;
+        let response = self.send(request.json(&serde_json::json!({}))).await?;
 
         if !response.status().is_success() {
             let error_text = response.text().await?;
@@ -349,7 +531,7 @@ impl MatrixClient {
         let path = format!("/_matrix/client/v3/rooms/{}/leave", room_id);
 
         let request = self.authenticated_request(reqwest::Method::POST, &path)?;
-        let response = request.json(&serde_json::json!({})).send().await?;
+        let response = self.send(request.json(&serde_json::json!({}))).await?;
 
         if !response.status().is_success() {
             let error_text = response.text().await?;
@@ -402,7 +584,7 @@ impl MatrixClient {
         }
 
         let request = self.authenticated_request(reqwest::Method::POST, &path)?;
-        let response = request.json(&body).send().await?;
+        let response = self.send(request.json(&body)).await?;
 
         if !response.status().is_success() {
             let error_text = response.text().await?;
@@ -447,20 +629,82 @@ impl MatrixClient {
         self.send_receipt(room_id, receipt_type, event_id, Some(thread_id)).await
     }
 
+    /// Get global account data of the logged-in user, if set
+    pub async fn get_account_data(&self, event_type: &str) -> Result<Option<serde_json::Value>> {
+        let path = self.account_data_path(event_type)?;
+        let request = self.authenticated_request(reqwest::Method::GET, &path)?;
+        let response = self.send(request).await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Failed to get account data: {}", error_text));
+        }
+
+        Ok(Some(response.json().await?))
+    }
+
+    /// Set global account data of the logged-in user
+    pub async fn set_account_data(
+        &self,
+        event_type: &str,
+        content: &serde_json::Value,
+    ) -> Result<()> {
+        let path = self.account_data_path(event_type)?;
+        let request = self.authenticated_request(reqwest::Method::PUT, &path)?;
+        let response = self.send(request.json(content)).await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Failed to set account data: {}", error_text));
+        }
+
+        Ok(())
+    }
+
+    fn account_data_path(&self, event_type: &str) -> Result<String> {
+        let user_id = self
+            .user_id()
+            .ok_or_else(|| anyhow::anyhow!("Client is not authenticated"))?;
+        Ok(format!(
+            "/_matrix/client/v3/user/{}/account_data/{}",
+            urlencoding::encode(&user_id),
+            urlencoding::encode(event_type)
+        ))
+    }
+
     /// Logout from the Matrix server
+    ///
+    /// Sessions from OAuth 2.0 login are ended by revoking their tokens.
     pub async fn logout(&mut self) -> Result<()> {
-        if self.credentials.is_some() {
-            let request =
-                self.authenticated_request(reqwest::Method::POST, "/_matrix/client/v3/logout")?;
-            let response = request.json(&serde_json::json!({})).send().await?;
-
-            if !response.status().is_success() {
-                let error_text = response.text().await?;
-                return Err(anyhow::anyhow!("Logout failed: {}", error_text));
+        if let Some(session) = self.session() {
+            if let Some(oauth) = &session.oauth {
+                self.revoke_oauth_session(oauth, &session).await?;
+            } else {
+                let request = self
+                    .authenticated_request(reqwest::Method::POST, "/_matrix/client/v3/logout")?;
+                let response = self.send(request.json(&serde_json::json!({}))).await?;
+
+                if !response.status().is_success() {
+                    let error_text = response.text().await?;
+                    return Err(anyhow::anyhow!("Logout failed: {}", error_text));
+                }
             }
         }
 
-        self.credentials = None;
+        self.set_session(None);
+        self.olm_machine = None;
+        self.verification_machine = None;
+        self.reset_send_queue();
+        self.timelines.lock().await.clear();
+        self.room_list.clear().await;
+
+        // The stored state belongs to the old session
+        if let Some(store) = &self.store {
+            store.clear().await?;
+        }
 
         // Reset state
         {
@@ -483,6 +727,10 @@ pub struct LoginResponse {
     pub access_token: String,
     /// Device ID assigned by the server
     pub device_id: Option<String>,
+    /// Token to renew the access token with, if the server supports refresh
+    pub refresh_token: Option<String>,
+    /// Lifetime of the access token in milliseconds
+    pub expires_in_ms: Option<u64>,
     /// Well-known discovery information
     pub well_known: Option<serde_json::Value>,
 }
@@ -493,6 +741,7 @@ pub struct SyncResponse {
     /// Token to use for next sync
     pub next_batch: String,
     /// Room updates
+    #[serde(default, deserialize_with = "null_as_default")]
     pub rooms: RoomUpdates,
     /// Presence updates
     pub presence: Option<serde_json::Value>,
@@ -507,13 +756,16 @@ pub struct SyncResponse {
 }
 
 /// Room updates in sync response
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct RoomUpdates {
     /// Joined rooms
+    #[serde(default, deserialize_with = "null_as_default")]
     pub join: std::collections::HashMap<String, JoinedRoom>,
     /// Invited rooms
+    #[serde(default, deserialize_with = "null_as_default")]
     pub invite: std::collections::HashMap<String, InvitedRoom>,
     /// Left rooms
+    #[serde(default, deserialize_with = "null_as_default")]
     pub leave: std::collections::HashMap<String, LeftRoom>,
 }
 
@@ -530,6 +782,8 @@ pub struct JoinedRoom {
     pub account_data: Option<serde_json::Value>,
     /// Unread notification counts
     pub unread_notifications: Option<serde_json::Value>,
+    /// Room summary (heroes and member counts)
+    pub summary: Option<serde_json::Value>,
 }
 
 /// Invited room data
@@ -568,6 +822,17 @@ pub struct TimelineUpdates {
     pub prev_batch: Option<String>,
 }
 
+/// Treat an explicit `null` like a missing field
+///
+/// Streamed sync updates send `null` for sections without changes.
+fn null_as_default<'de, D, T>(deserializer: D) -> std::result::Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
 // Re-export commonly used types from matryx_entity
 pub use matryx_entity::{Credentials, Event, MembershipState, Room, Session, User};
 
@@ -0,0 +1,523 @@
+//! Login flows and sessions for the Matrix client
+//!
+//! Finds the homeserver of a user ID through `/.well-known/matrix/client`,
+//! checks its `/versions`, and logs in with a password, an `m.login.token` or
+//! SSO through a loopback redirect listener. A logged-in client holds a
+//! [`MatrixSession`], which can be saved and restored so the same device is
+//! reused across runs. Requests answered with `M_UNKNOWN_TOKEN` and
+//! `soft_logout` refresh the access token and are sent again.
+
+use anyhow::Result;
+use matryx_entity::types::UserId;
+use reqwest::StatusCode;
+use reqwest::header::AUTHORIZATION;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+use std::path::Path;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
+use url::Url;
+
+use crate::_matrix::_well_known::matrix::client::WellKnownClient;
+use crate::_matrix::client::v3::login::sso::sso_redirect_url;
+use crate::_matrix::client::v3::login::{LoginFlow, LoginFlowsResponse, RefreshResponse};
+use crate::_matrix::client::versions::VersionsResponse;
+use crate::http_client::HttpClientError;
+use crate::oauth::OAuthSession;
+use crate::{LoginResponse, MatrixClient};
+
+/// Query parameter carrying the login token of an SSO redirect
+const LOGIN_TOKEN_PARAM: &str = "loginToken";
+
+/// A logged-in session, enough to resume without logging in again
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatrixSession {
+    pub homeserver_url: Url,
+    pub user_id: String,
+    pub device_id: Option<String>,
+    pub access_token: String,
+    /// Token to renew the access token with, if the server issued one
+    pub refresh_token: Option<String>,
+    /// OAuth 2.0 client registration, for sessions from [`MatrixClient::login_with_oauth`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oauth: Option<OAuthSession>,
+}
+
+impl MatrixSession {
+    /// Write the session to a JSON file readable only by the current user
+    pub async fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let mut options = tokio::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+
+        let mut file = options.open(path).await?;
+        file.write_all(&serde_json::to_vec_pretty(self)?).await?;
+        file.flush().await?;
+        Ok(())
+    }
+
+    /// Read a session written by [`Self::save`]
+    pub async fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(serde_json::from_slice(&tokio::fs::read(path).await?)?)
+    }
+}
+
+/// Body of an unsuccessful response, as far as token refresh cares
+#[derive(Debug, Deserialize)]
+struct AuthErrorBody {
+    errcode: String,
+    #[serde(default)]
+    soft_logout: bool,
+}
+
+/// Login and session functionality
+impl MatrixClient {
+    /// Login with a full user ID and password, discovering its homeserver
+    ///
+    /// The homeserver is looked up through the server name's
+    /// `/.well-known/matrix/client` document, falling back to the server name
+    /// itself, and must support a `v1.x` version of the Client-Server API.
+    pub async fn login_with_user_id(
+        &mut self,
+        user_id: &str,
+        password: &str,
+        device_id: Option<String>,
+    ) -> Result<()> {
+        let user_id = UserId::parse(user_id)?;
+        let homeserver_url = discover_homeserver(user_id.server_name().as_str()).await?;
+        self.use_homeserver(homeserver_url).await?;
+        self.login(user_id.as_str(), password, device_id).await
+    }
+
+    /// Switch to another homeserver after checking the versions it supports
+    ///
+    /// Must be called before logging in, as the session belongs to the
+    /// homeserver it was created on.
+    pub async fn use_homeserver(&mut self, homeserver_url: Url) -> Result<&VersionsResponse> {
+        if self.is_authenticated() {
+            return Err(anyhow::anyhow!("Cannot change the homeserver of a logged-in client"));
+        }
+
+        let url = homeserver_url.join("/_matrix/client/versions")?;
+        let response = self.http_client.get(url).send().await?;
+
+        if !response.status().is_success() {
+            let status = response.status().as_u16();
+            let error_text = response.text().await?;
+            return Err(HttpClientError::from_response(status, &error_text).into());
+        }
+
+        let versions: VersionsResponse = response.json().await?;
+        if !supports_client_api(&versions) {
+            return Err(anyhow::anyhow!(
+                "Homeserver {} does not support a v1.x Client-Server API (advertises {:?})",
+                homeserver_url,
+                versions.versions
+            ));
+        }
+
+        debug!("Using homeserver {} with versions {:?}", homeserver_url, versions.versions);
+        self.config.homeserver_url = homeserver_url;
+        Ok(&*self.server_versions.insert(versions))
+    }
+
+    /// Get the versions advertised by the homeserver (if checked)
+    pub fn server_versions(&self) -> Option<&VersionsResponse> {
+        self.server_versions.as_ref()
+    }
+
+    /// Get the login flows the homeserver supports
+    pub async fn login_flows(&self) -> Result<Vec<LoginFlow>> {
+        let url = self.config.homeserver_url.join("/_matrix/client/v3/login")?;
+        let response = self.http_client.get(url).send().await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Failed to get login flows: {}", error_text));
+        }
+
+        let flows: LoginFlowsResponse = response.json().await?;
+        Ok(flows.flows)
+    }
+
+    /// Login with an `m.login.token`, e.g. from an SSO redirect
+    pub async fn login_with_token(&mut self, token: &str, device_id: Option<String>) -> Result<()> {
+        let url = self.config.homeserver_url.join("/_matrix/client/v3/login")?;
+
+        let mut login_data = serde_json::json!({
+            "type": "m.login.token",
+            "token": token,
+            "refresh_token": true
+        });
+
+        if let Some(device_id) = device_id {
+            login_data["device_id"] = serde_json::Value::String(device_id);
+        }
+
+        let response = self.http_client.post(url).json(&login_data).send().await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Token login failed: {}", error_text));
+        }
+
+        let login_response: LoginResponse = response.json().await?;
+        self.finish_login(login_response)
+    }
+
+    /// Login through the homeserver's SSO
+    ///
+    /// `open_url` is given the URL to open in the user's browser. After the
+    /// user authenticates, the browser is redirected to a listener on a
+    /// loopback port, whose login token completes the login.
+    ///
+    /// # Arguments
+    /// * `idp_id` - Identity provider from the `m.login.sso` flow, or `None` to
+    ///   let the homeserver ask the user
+    pub async fn login_with_sso(
+        &mut self,
+        idp_id: Option<&str>,
+        device_id: Option<String>,
+        open_url: impl FnOnce(&Url),
+    ) -> Result<()> {
+        let listener = RedirectListener::bind().await?;
+        let url = sso_redirect_url(&self.config.homeserver_url, listener.url(), idp_id)?;
+
+        info!("Waiting for SSO login on {}", listener.url());
+        open_url(&url);
+
+        let query = listener.wait_for(LOGIN_TOKEN_PARAM).await?;
+        let token = query
+            .get(LOGIN_TOKEN_PARAM)
+            .ok_or_else(|| anyhow::anyhow!("SSO redirect did not carry a login token"))?;
+
+        self.login_with_token(token, device_id).await
+    }
+
+    /// Get the current session (if logged in)
+    ///
+    /// Save it to resume later with [`Self::restore_session`]. Refreshing the
+    /// access token replaces the session; see [`Self::session_changes`].
+    pub fn session(&self) -> Option<MatrixSession> {
+        self.read_session().clone()
+    }
+
+    /// Resume a saved session without logging in again
+    ///
+    /// The client keeps using the session's homeserver and device.
+    pub fn restore_session(&mut self, session: MatrixSession) {
+        self.config.homeserver_url = session.homeserver_url.clone();
+        self.set_session(Some(session));
+        self.reset_send_queue();
+    }
+
+    /// Subscribe to new sessions, e.g. after the access token was refreshed
+    pub fn session_changes(&self) -> broadcast::Receiver<MatrixSession> {
+        self.session_sender.subscribe()
+    }
+
+    pub(crate) fn finish_login(&mut self, response: LoginResponse) -> Result<()> {
+        // The homeserver may point us at the base URL to use from now on
+        if let Some(base_url) = response
+            .well_known
+            .as_ref()
+            .and_then(|well_known| well_known["m.homeserver"]["base_url"].as_str())
+        {
+            match Url::parse(base_url) {
+                Ok(url) => self.config.homeserver_url = url,
+                Err(e) => warn!("Ignoring invalid homeserver base URL {}: {}", base_url, e),
+            }
+        }
+
+        self.set_session(Some(MatrixSession {
+            homeserver_url: self.config.homeserver_url.clone(),
+            user_id: response.user_id,
+            device_id: response.device_id,
+            access_token: response.access_token,
+            refresh_token: response.refresh_token,
+            oauth: None,
+        }));
+        self.reset_send_queue();
+
+        Ok(())
+    }
+
+    pub(crate) fn read_session(&self) -> std::sync::RwLockReadGuard<'_, Option<MatrixSession>> {
+        self.session.read().unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    pub(crate) fn set_session(&self, session: Option<MatrixSession>) {
+        *self.session.write().unwrap_or_else(std::sync::PoisonError::into_inner) = session.clone();
+
+        // Nobody listening is fine
+        if let Some(session) = session {
+            let _ = self.session_sender.send(session);
+        }
+    }
+
+    /// Send an authenticated request, refreshing the access token once if the
+    /// homeserver soft-logged us out
+    ///
+    /// Unsuccessful responses are returned as they are, except for a `401`
+    /// that could not be recovered from, which is returned as an error.
+    pub(crate) async fn send(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, HttpClientError> {
+        let retry = request.try_clone();
+        let response = request.send().await?;
+
+        if response.status() != StatusCode::UNAUTHORIZED {
+            return Ok(response);
+        }
+
+        let status = response.status().as_u16();
+        let body = response.text().await?;
+        let soft_logout = serde_json::from_str::<AuthErrorBody>(&body)
+            .is_ok_and(|error| error.errcode == "M_UNKNOWN_TOKEN" && error.soft_logout);
+        let can_refresh = self.read_session().as_ref().is_some_and(|s| s.refresh_token.is_some());
+
+        let (Some(retry), true, true) = (retry, soft_logout, can_refresh) else {
+            return Err(HttpClientError::from_response(status, &body));
+        };
+
+        let mut request = retry.build()?;
+        let stale_token = request
+            .headers_mut()
+            .remove(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok()?.strip_prefix("Bearer ").map(str::to_owned))
+            .ok_or(HttpClientError::AuthenticationRequired)?;
+
+        let access_token = self.refresh_access_token(&stale_token).await?;
+        let response = reqwest::RequestBuilder::from_parts(self.http_client.clone(), request)
+            .bearer_auth(access_token)
+            .send()
+            .await?;
+        Ok(response)
+    }
+
+    /// Get a fresh access token in place of `stale_token`
+    ///
+    /// Refresh tokens are single-use, so concurrent callers wait for one
+    /// refresh and share its result.
+    async fn refresh_access_token(&self, stale_token: &str) -> Result<String, HttpClientError> {
+        let _guard = self.refresh_lock.lock().await;
+
+        let mut session = self.session().ok_or(HttpClientError::AuthenticationRequired)?;
+        if session.access_token != stale_token {
+            return Ok(session.access_token);
+        }
+        let refresh_token = session
+            .refresh_token
+            .clone()
+            .ok_or(HttpClientError::AuthenticationRequired)?;
+
+        let (access_token, new_refresh_token) = match &session.oauth {
+            Some(oauth) => {
+                let tokens = self.refresh_oauth_tokens(oauth, &refresh_token).await?;
+                (tokens.access_token, tokens.refresh_token)
+            },
+            None => {
+                let refreshed = self.refresh_matrix_tokens(&refresh_token).await?;
+                (refreshed.access_token, refreshed.refresh_token)
+            },
+        };
+
+        debug!("Refreshed access token of {}", session.user_id);
+        session.access_token = access_token.clone();
+        // Without a new refresh token the old one stays valid
+        if new_refresh_token.is_some() {
+            session.refresh_token = new_refresh_token;
+        }
+        self.set_session(Some(session));
+
+        Ok(access_token)
+    }
+
+    async fn refresh_matrix_tokens(
+        &self,
+        refresh_token: &str,
+    ) -> Result<RefreshResponse, HttpClientError> {
+        let url = self.config.homeserver_url.join("/_matrix/client/v3/refresh")?;
+        let response = self
+            .http_client
+            .post(url)
+            .json(&serde_json::json!({ "refresh_token": refresh_token }))
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await?;
+            return Err(HttpClientError::from_response(status.as_u16(), &error_text));
+        }
+
+        Ok(response.json().await?)
+    }
+}
+
+/// Find the homeserver base URL of a server name
+///
+/// Uses `m.homeserver` from `/.well-known/matrix/client` when the server name
+/// publishes one, and `https://{server_name}` otherwise.
+pub async fn discover_homeserver(server_name: &str) -> Result<Url> {
+    match WellKnownClient::new(server_name)?.get_client_info().await {
+        Ok(well_known) => {
+            let base_url = Url::parse(well_known.homeserver.base_url.trim_end_matches('/'))?;
+            debug!("Discovered homeserver {} for {}", base_url, server_name);
+            Ok(base_url)
+        },
+        Err(e) if e.status_code() == Some(404) => {
+            debug!("No well-known document for {}, using it as the homeserver", server_name);
+            Ok(Url::parse(&format!("https://{}", server_name))?)
+        },
+        Err(e) => Err(anyhow::anyhow!("Homeserver discovery for {} failed: {}", server_name, e)),
+    }
+}
+
+/// Whether the homeserver speaks a version of the Client-Server API we support
+fn supports_client_api(versions: &VersionsResponse) -> bool {
+    versions.versions.iter().any(|version| version.starts_with("v1."))
+}
+
+/// HTTP listener on a loopback port that a browser is redirected back to
+pub(crate) struct RedirectListener {
+    listener: TcpListener,
+    url: Url,
+}
+
+impl RedirectListener {
+    pub(crate) async fn bind() -> Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+        let url = Url::parse(&format!("http://{}/", listener.local_addr()?))?;
+        Ok(Self { listener, url })
+    }
+
+    /// URL to redirect the browser to
+    pub(crate) fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Wait for a redirect with the `param` or `error` query parameter and
+    /// return its query
+    ///
+    /// Other requests, such as for a favicon, are answered and ignored.
+    pub(crate) async fn wait_for(&self, param: &str) -> Result<HashMap<String, String>> {
+        loop {
+            let (mut stream, _) = self.listener.accept().await?;
+
+            let mut buf = vec![0u8; 8192];
+            let len = stream.read(&mut buf).await?;
+            let query = parse_redirect_query(&self.url, &String::from_utf8_lossy(&buf[..len]));
+
+            let done = query.contains_key(param) || query.contains_key("error");
+            let body = if done {
+                "Login finished. You can close this window and return to the application."
+            } else {
+                "Waiting for login."
+            };
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\n\
+                 Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            if let Err(e) = stream.write_all(response.as_bytes()).await {
+                warn!("Failed to answer redirect request: {}", e);
+            }
+
+            if done {
+                return Ok(query);
+            }
+        }
+    }
+}
+
+/// Query parameters of the request target in an HTTP request head
+fn parse_redirect_query(base: &Url, request: &str) -> HashMap<String, String> {
+    request
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|target| base.join(target).ok())
+        .map(|url| url.query_pairs().into_owned().collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_redirect_query() {
+        let base = Url::parse("http://127.0.0.1:4321/").unwrap();
+
+        let query = parse_redirect_query(
+            &base,
+            "GET /?loginToken=abc%2Bdef&state=1 HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n",
+        );
+        assert_eq!(query.get("loginToken").map(String::as_str), Some("abc+def"));
+        assert_eq!(query.get("state").map(String::as_str), Some("1"));
+
+        assert!(parse_redirect_query(&base, "GET /favicon.ico HTTP/1.1\r\n").is_empty());
+        assert!(parse_redirect_query(&base, "").is_empty());
+    }
+
+    #[test]
+    fn test_supports_client_api() {
+        let versions = |versions: &[&str]| VersionsResponse {
+            versions: versions.iter().map(|v| v.to_string()).collect(),
+            unstable_features: None,
+        };
+
+        assert!(supports_client_api(&versions(&["r0.6.1", "v1.1", "v1.8"])));
+        assert!(!supports_client_api(&versions(&["r0.5.0", "r0.6.1"])));
+        assert!(!supports_client_api(&versions(&[])));
+    }
+
+    #[tokio::test]
+    async fn test_session_save_and_load() {
+        let session = MatrixSession {
+            homeserver_url: Url::parse("https://matrix.example.org").unwrap(),
+            user_id: "@alice:example.org".to_string(),
+            device_id: Some("ABCDEFGH".to_string()),
+            access_token: "syt_access".to_string(),
+            refresh_token: Some("syr_refresh".to_string()),
+            oauth: None,
+        };
+
+        let path =
+            std::env::temp_dir().join(format!("matryx-session-{}.json", uuid::Uuid::new_v4()));
+        session.save(&path).await.unwrap();
+        let loaded = MatrixSession::load(&path).await;
+        let _ = tokio::fs::remove_file(&path).await;
+
+        assert_eq!(loaded.unwrap(), session);
+    }
+
+    #[tokio::test]
+    async fn test_redirect_listener() {
+        let listener = RedirectListener::bind().await.unwrap();
+        let addr =
+            format!("{}:{}", listener.url().host_str().unwrap(), listener.url().port().unwrap());
+
+        let client = tokio::spawn(async move {
+            for target in ["/favicon.ico", "/?loginToken=token"] {
+                let mut stream = tokio::net::TcpStream::connect(&addr).await.unwrap();
+                let request = format!("GET {} HTTP/1.1\r\nHost: {}\r\n\r\n", target, addr);
+                stream.write_all(request.as_bytes()).await.unwrap();
+                let mut response = String::new();
+                stream.read_to_string(&mut response).await.unwrap();
+                assert!(response.starts_with("HTTP/1.1 200 OK"));
+            }
+        });
+
+        let query = listener.wait_for(LOGIN_TOKEN_PARAM).await.unwrap();
+        client.await.unwrap();
+        assert_eq!(query.get(LOGIN_TOKEN_PARAM).map(String::as_str), Some("token"));
+    }
+}
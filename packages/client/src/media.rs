@@ -0,0 +1,585 @@
+//! Media for the Matrix client
+//!
+//! Uploads to the content repository, either in one request or to an
+//! `mxc://` URI created beforehand, and downloads and thumbnails through the
+//! authenticated `/_matrix/client/v1/media` endpoints. The `m.image`,
+//! `m.file`, `m.audio` and `m.video` senders fill in the `info` block from
+//! the file itself and, in encrypted rooms, encrypt the file and its
+//! thumbnail before upload.
+
+use anyhow::Result;
+use matryx_entity::types::{
+    EncryptedFile, MediaInfo, MediaMessage, MessageType, MxcUri, RoomMessageContent,
+};
+use reqwest::Method;
+use reqwest::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use serde::Deserialize;
+
+use crate::MatrixClient;
+use crate::crypto::{decrypt_attachment, encrypt_attachment};
+
+const DEFAULT_MIMETYPE: &str = "application/octet-stream";
+
+/// A file to send as a media message
+#[derive(Debug, Clone)]
+pub struct Attachment {
+    /// Shown as the message body
+    pub filename: String,
+    pub data: Vec<u8>,
+    /// Sniffed from `data` when not set
+    pub mimetype: Option<String>,
+    /// Duration in milliseconds, for audio and video
+    pub duration: Option<u64>,
+    /// An encoded thumbnail image, e.g. a JPEG of the first video frame
+    pub thumbnail: Option<Vec<u8>>,
+}
+
+impl Attachment {
+    pub fn new(filename: impl Into<String>, data: Vec<u8>) -> Self {
+        Self {
+            filename: filename.into(),
+            data,
+            mimetype: None,
+            duration: None,
+            thumbnail: None,
+        }
+    }
+
+    pub fn with_mimetype(mut self, mimetype: impl Into<String>) -> Self {
+        self.mimetype = Some(mimetype.into());
+        self
+    }
+
+    pub fn with_duration(mut self, duration: u64) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    pub fn with_thumbnail(mut self, thumbnail: Vec<u8>) -> Self {
+        self.thumbnail = Some(thumbnail);
+        self
+    }
+}
+
+/// A downloaded file or thumbnail
+#[derive(Debug, Clone)]
+pub struct DownloadedMedia {
+    pub content_type: Option<String>,
+    /// Filename from the `Content-Disposition` header
+    pub filename: Option<String>,
+    pub data: Vec<u8>,
+}
+
+/// An `mxc://` URI reserved for a later upload
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreatedMedia {
+    pub content_uri: MxcUri,
+    /// Time in milliseconds after which the unused URI expires
+    pub unused_expires_at: Option<i64>,
+}
+
+/// Content repository limits of the homeserver
+#[derive(Debug, Clone, Deserialize)]
+pub struct MediaConfig {
+    /// Maximum upload size in bytes
+    #[serde(rename = "m.upload.size")]
+    pub upload_size: Option<u64>,
+}
+
+/// How a thumbnail is fitted to the requested size
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThumbnailMethod {
+    /// Fill the size exactly, cropping the image
+    Crop,
+    /// Fit the image inside the size, keeping its aspect ratio
+    Scale,
+}
+
+impl ThumbnailMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ThumbnailMethod::Crop => "crop",
+            ThumbnailMethod::Scale => "scale",
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct UploadResponse {
+    content_uri: MxcUri,
+}
+
+/// Where an attachment ended up after upload
+enum UploadedAttachment {
+    Plain(MxcUri),
+    Encrypted(Box<EncryptedFile>),
+}
+
+/// Media functionality
+impl MatrixClient {
+    /// Upload a file to the content repository
+    ///
+    /// `body` may be a `reqwest::Body::wrap_stream` to upload without
+    /// buffering the whole file.
+    pub async fn upload_media(
+        &self,
+        content_type: &str,
+        filename: Option<&str>,
+        body: impl Into<reqwest::Body>,
+    ) -> Result<MxcUri> {
+        let mut path = "/_matrix/media/v3/upload".to_string();
+        if let Some(filename) = filename {
+            path.push_str(&format!("?filename={}", urlencoding::encode(filename)));
+        }
+
+        let request = self.authenticated_request(Method::POST, &path)?;
+        let response = self.send(request.header(CONTENT_TYPE, content_type).body(body)).await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Failed to upload media: {}", error_text));
+        }
+
+        let upload_response: UploadResponse = response.json().await?;
+        Ok(upload_response.content_uri)
+    }
+
+    /// Reserve an `mxc://` URI to upload to later with [`Self::upload_media_to`]
+    ///
+    /// The URI can be sent in an event before the upload has finished.
+    pub async fn create_media(&self) -> Result<CreatedMedia> {
+        let request = self.authenticated_request(Method::POST, "/_matrix/media/v1/create")?;
+        let response = self.send(request.json(&serde_json::json!({}))).await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Failed to create media: {}", error_text));
+        }
+
+        Ok(response.json().await?)
+    }
+
+    /// Upload the content of an `mxc://` URI reserved with [`Self::create_media`]
+    pub async fn upload_media_to(
+        &self,
+        content_uri: &MxcUri,
+        content_type: &str,
+        filename: Option<&str>,
+        body: impl Into<reqwest::Body>,
+    ) -> Result<()> {
+        let mut path = format!("/_matrix/media/v3/upload/{}", media_path(content_uri));
+        if let Some(filename) = filename {
+            path.push_str(&format!("?filename={}", urlencoding::encode(filename)));
+        }
+
+        let request = self.authenticated_request(Method::PUT, &path)?;
+        let response = self.send(request.header(CONTENT_TYPE, content_type).body(body)).await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Failed to upload media: {}", error_text));
+        }
+
+        Ok(())
+    }
+
+    /// Download a file from the content repository
+    pub async fn download_media(&self, content_uri: &str) -> Result<DownloadedMedia> {
+        let content_uri = MxcUri::parse(content_uri)?;
+        let path = format!("/_matrix/client/v1/media/download/{}", media_path(&content_uri));
+        self.get_media(&path).await
+    }
+
+    /// Download and decrypt a file sent to an encrypted room
+    ///
+    /// Fails if the download does not match the hash in `file`.
+    pub async fn download_encrypted_media(&self, file: &EncryptedFile) -> Result<Vec<u8>> {
+        let media = self.download_media(&file.url).await?;
+        Ok(decrypt_attachment(&media.data, file)?)
+    }
+
+    /// Download a thumbnail of an image or video
+    pub async fn media_thumbnail(
+        &self,
+        content_uri: &str,
+        width: u32,
+        height: u32,
+        method: ThumbnailMethod,
+    ) -> Result<DownloadedMedia> {
+        let content_uri = MxcUri::parse(content_uri)?;
+        let path = format!(
+            "/_matrix/client/v1/media/thumbnail/{}?width={}&height={}&method={}",
+            media_path(&content_uri),
+            width,
+            height,
+            method.as_str()
+        );
+        self.get_media(&path).await
+    }
+
+    /// Get the content repository limits of the homeserver
+    pub async fn media_config(&self) -> Result<MediaConfig> {
+        let request = self.authenticated_request(Method::GET, "/_matrix/client/v1/media/config")?;
+        let response = self.send(request).await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Failed to get media config: {}", error_text));
+        }
+
+        Ok(response.json().await?)
+    }
+
+    /// Send an image as an `m.image` message
+    pub async fn send_image(&self, room_id: &str, attachment: Attachment) -> Result<String> {
+        self.send_media_message(room_id, MessageType::Image, attachment).await
+    }
+
+    /// Send a file as an `m.file` message
+    pub async fn send_file(&self, room_id: &str, attachment: Attachment) -> Result<String> {
+        self.send_media_message(room_id, MessageType::File, attachment).await
+    }
+
+    /// Send a video as an `m.video` message
+    pub async fn send_video(&self, room_id: &str, attachment: Attachment) -> Result<String> {
+        self.send_media_message(room_id, MessageType::Video, attachment).await
+    }
+
+    /// Send an audio clip as an `m.audio` message
+    pub async fn send_audio(&self, room_id: &str, attachment: Attachment) -> Result<String> {
+        self.send_media_message(room_id, MessageType::Audio, attachment).await
+    }
+
+    /// Send a file as the media message matching its MIME type
+    pub async fn send_attachment(&self, room_id: &str, attachment: Attachment) -> Result<String> {
+        let mimetype = attachment_mimetype(&attachment);
+        let msgtype = match mimetype.split('/').next() {
+            Some("image") => MessageType::Image,
+            Some("video") => MessageType::Video,
+            Some("audio") => MessageType::Audio,
+            _ => MessageType::File,
+        };
+        self.send_media_message(room_id, msgtype, attachment).await
+    }
+
+    async fn send_media_message(
+        &self,
+        room_id: &str,
+        msgtype: fn(MediaMessage) -> MessageType,
+        attachment: Attachment,
+    ) -> Result<String> {
+        let encrypted = self.room_encryption(room_id).await?.is_some();
+        let mimetype = attachment_mimetype(&attachment);
+        let (w, h) = dimensions(&attachment.data);
+
+        let mut info = MediaInfo {
+            mimetype: Some(mimetype.clone()),
+            size: Some(attachment.data.len() as u64),
+            w,
+            h,
+            duration: attachment.duration,
+            ..Default::default()
+        };
+
+        if let Some(thumbnail) = attachment.thumbnail {
+            let thumbnail_mimetype = sniff_mime(&thumbnail).unwrap_or(DEFAULT_MIMETYPE);
+            let (w, h) = dimensions(&thumbnail);
+            info.thumbnail_info = Some(Box::new(MediaInfo {
+                mimetype: Some(thumbnail_mimetype.to_string()),
+                size: Some(thumbnail.len() as u64),
+                w,
+                h,
+                ..Default::default()
+            }));
+            match self
+                .upload_attachment(thumbnail, thumbnail_mimetype, None, encrypted)
+                .await?
+            {
+                UploadedAttachment::Plain(url) => info.thumbnail_url = Some(url.into_string()),
+                UploadedAttachment::Encrypted(file) => info.thumbnail_file = Some(*file),
+            }
+        }
+
+        let mut media = MediaMessage {
+            body: attachment.filename.clone(),
+            info: Some(info),
+            ..Default::default()
+        };
+        let uploaded = self
+            .upload_attachment(attachment.data, &mimetype, Some(&attachment.filename), encrypted)
+            .await?;
+        match uploaded {
+            UploadedAttachment::Plain(url) => media.url = Some(url.into_string()),
+            UploadedAttachment::Encrypted(file) => media.file = Some(*file),
+        }
+
+        let content = serde_json::to_value(RoomMessageContent::new(msgtype(media)))?;
+        let txn_id = uuid::Uuid::new_v4().to_string();
+        self.send_event(room_id, RoomMessageContent::EVENT_TYPE, content, &txn_id)
+            .await
+    }
+
+    /// Upload a file, encrypting it first for an encrypted room
+    ///
+    /// The MIME type and name of an encrypted file are only revealed in the
+    /// encrypted event, not to the content repository.
+    async fn upload_attachment(
+        &self,
+        data: Vec<u8>,
+        mimetype: &str,
+        filename: Option<&str>,
+        encrypted: bool,
+    ) -> Result<UploadedAttachment> {
+        if !encrypted {
+            let url = self.upload_media(mimetype, filename, data).await?;
+            return Ok(UploadedAttachment::Plain(url));
+        }
+
+        let (ciphertext, key) = encrypt_attachment(&data)?;
+        let url = self.upload_media(DEFAULT_MIMETYPE, None, ciphertext).await?;
+        let file = key.into_encrypted_file(url.into_string());
+        Ok(UploadedAttachment::Encrypted(Box::new(file)))
+    }
+
+    async fn get_media(&self, path: &str) -> Result<DownloadedMedia> {
+        let request = self.authenticated_request(Method::GET, path)?;
+        let response = self.send(request).await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Failed to download media: {}", error_text));
+        }
+
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value: &reqwest::header::HeaderValue| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let content_type = header(CONTENT_TYPE);
+        let filename = header(CONTENT_DISPOSITION).and_then(|value| disposition_filename(&value));
+
+        Ok(DownloadedMedia {
+            content_type,
+            filename,
+            data: response.bytes().await?.to_vec(),
+        })
+    }
+}
+
+/// `{serverName}/{mediaId}` path segments of an `mxc://` URI
+fn media_path(content_uri: &MxcUri) -> String {
+    format!(
+        "{}/{}",
+        urlencoding::encode(content_uri.server_name().as_str()),
+        urlencoding::encode(content_uri.media_id())
+    )
+}
+
+fn attachment_mimetype(attachment: &Attachment) -> String {
+    attachment
+        .mimetype
+        .clone()
+        .unwrap_or_else(|| sniff_mime(&attachment.data).unwrap_or(DEFAULT_MIMETYPE).to_string())
+}
+
+fn dimensions(data: &[u8]) -> (Option<u64>, Option<u64>) {
+    match image_dimensions(data) {
+        Some((w, h)) => (Some(w.into()), Some(h.into())),
+        None => (None, None),
+    }
+}
+
+/// Filename of a `Content-Disposition` header, preferring `filename*`
+fn disposition_filename(header: &str) -> Option<String> {
+    let mut filename = None;
+    for parameter in header.split(';').map(str::trim) {
+        if let Some(value) = parameter.strip_prefix("filename*=") {
+            // RFC 5987: charset'language'percent-encoded-value
+            let encoded = value.splitn(3, '\'').nth(2)?;
+            return urlencoding::decode(encoded).ok().map(|name| name.into_owned());
+        }
+        if let Some(value) = parameter.strip_prefix("filename=") {
+            filename = Some(value.trim_matches('"').to_string());
+        }
+    }
+    filename
+}
+
+/// Guess the MIME type of a file from its leading bytes
+pub fn sniff_mime(data: &[u8]) -> Option<&'static str> {
+    let starts = |magic: &[u8]| data.starts_with(magic);
+    let at = |offset: usize, magic: &[u8]| data.get(offset..offset + magic.len()) == Some(magic);
+
+    let mime = if starts(b"\x89PNG\r\n\x1a\n") {
+        "image/png"
+    } else if starts(&[0xff, 0xd8, 0xff]) {
+        "image/jpeg"
+    } else if starts(b"GIF87a") || starts(b"GIF89a") {
+        "image/gif"
+    } else if starts(b"RIFF") && at(8, b"WEBP") {
+        "image/webp"
+    } else if starts(b"RIFF") && at(8, b"WAVE") {
+        "audio/wav"
+    } else if starts(b"BM") && data.len() >= 26 {
+        "image/bmp"
+    } else if at(4, b"ftyp") {
+        match data.get(8..12) {
+            Some(b"qt  ") => "video/quicktime",
+            Some(b"M4A ") => "audio/mp4",
+            Some(b"heic") | Some(b"heix") => "image/heic",
+            Some(b"avif") => "image/avif",
+            _ => "video/mp4",
+        }
+    } else if starts(&[0x1a, 0x45, 0xdf, 0xa3]) {
+        "video/webm"
+    } else if starts(b"OggS") {
+        "audio/ogg"
+    } else if starts(b"fLaC") {
+        "audio/flac"
+    } else if starts(b"ID3") || (data.len() > 1 && data[0] == 0xff && data[1] & 0xe0 == 0xe0) {
+        "audio/mpeg"
+    } else if starts(b"%PDF-") {
+        "application/pdf"
+    } else if starts(b"PK\x03\x04") {
+        "application/zip"
+    } else if starts(&[0x1f, 0x8b]) {
+        "application/gzip"
+    } else if is_text(data) {
+        "text/plain"
+    } else {
+        return None;
+    };
+    Some(mime)
+}
+
+fn is_text(data: &[u8]) -> bool {
+    let prefix = &data[..data.len().min(512)];
+    if prefix.is_empty() || prefix.contains(&0) {
+        return false;
+    }
+    // A multi-byte character may be cut off at the end of the prefix
+    match std::str::from_utf8(prefix) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    }
+}
+
+/// Width and height of a PNG, JPEG, GIF, WebP or BMP image, read from its header
+pub fn image_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    let be16 = |offset: usize| -> Option<u32> {
+        Some(u16::from_be_bytes(data.get(offset..offset + 2)?.try_into().ok()?).into())
+    };
+    let le16 = |offset: usize| -> Option<u32> {
+        Some(u16::from_le_bytes(data.get(offset..offset + 2)?.try_into().ok()?).into())
+    };
+    let le24 = |offset: usize| -> Option<u32> {
+        let bytes = data.get(offset..offset + 3)?;
+        Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]))
+    };
+
+    match sniff_mime(data)? {
+        "image/png" => {
+            // The IHDR chunk always comes first
+            let w = u32::from_be_bytes(data.get(16..20)?.try_into().ok()?);
+            let h = u32::from_be_bytes(data.get(20..24)?.try_into().ok()?);
+            Some((w, h))
+        },
+        "image/gif" => Some((le16(6)?, le16(8)?)),
+        "image/bmp" => {
+            let w = i32::from_le_bytes(data.get(18..22)?.try_into().ok()?);
+            // A negative height marks a top-down bitmap
+            let h = i32::from_le_bytes(data.get(22..26)?.try_into().ok()?);
+            Some((w.unsigned_abs(), h.unsigned_abs()))
+        },
+        "image/webp" => match data.get(12..16)? {
+            b"VP8 " if data.get(23..26)? == [0x9d, 0x01, 0x2a] => {
+                Some((le16(26)? & 0x3fff, le16(28)? & 0x3fff))
+            },
+            b"VP8L" if *data.get(20)? == 0x2f => {
+                let bits = u32::from_le_bytes(data.get(21..25)?.try_into().ok()?);
+                Some(((bits & 0x3fff) + 1, ((bits >> 14) & 0x3fff) + 1))
+            },
+            b"VP8X" => Some((le24(24)? + 1, le24(27)? + 1)),
+            _ => None,
+        },
+        "image/jpeg" => {
+            let mut offset = 2;
+            loop {
+                if *data.get(offset)? != 0xff {
+                    return None;
+                }
+                let marker = *data.get(offset + 1)?;
+                match marker {
+                    // Fill byte before a marker
+                    0xff => offset += 1,
+                    0x01 | 0xd0..=0xd8 => offset += 2,
+                    // Start of frame, except DHT, JPG and DAC which share the range
+                    0xc0..=0xcf if !matches!(marker, 0xc4 | 0xc8 | 0xcc) => {
+                        return Some((be16(offset + 7)?, be16(offset + 5)?));
+                    },
+                    _ => offset += 2 + be16(offset + 2)? as usize,
+                }
+            }
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(w: u32, h: u32) -> Vec<u8> {
+        let mut data = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        data.extend_from_slice(&w.to_be_bytes());
+        data.extend_from_slice(&h.to_be_bytes());
+        data.extend_from_slice(&[8, 6, 0, 0, 0]);
+        data
+    }
+
+    #[test]
+    fn test_sniff_mime() {
+        assert_eq!(sniff_mime(&png(1, 1)), Some("image/png"));
+        assert_eq!(sniff_mime(b"GIF89a\x01\x00\x01\x00"), Some("image/gif"));
+        assert_eq!(sniff_mime(b"RIFF\0\0\0\0WEBPVP8 "), Some("image/webp"));
+        assert_eq!(sniff_mime(b"\0\0\0\x18ftypisom"), Some("video/mp4"));
+        assert_eq!(sniff_mime(b"\0\0\0\x18ftypM4A "), Some("audio/mp4"));
+        assert_eq!(sniff_mime(b"%PDF-1.7"), Some("application/pdf"));
+        assert_eq!(sniff_mime("héllo wörld".as_bytes()), Some("text/plain"));
+        assert_eq!(sniff_mime(&[0, 1, 2, 3]), None);
+        assert_eq!(sniff_mime(&[]), None);
+    }
+
+    #[test]
+    fn test_image_dimensions() {
+        assert_eq!(image_dimensions(&png(640, 480)), Some((640, 480)));
+        assert_eq!(image_dimensions(b"GIF89a\x20\x03\x58\x02"), Some((800, 600)));
+
+        // SOI, an APP0 segment, then a baseline SOF0 frame header
+        let mut jpeg = vec![0xff, 0xd8, 0xff, 0xe0, 0x00, 0x04, 0x00, 0x00];
+        jpeg.extend_from_slice(&[0xff, 0xc0, 0x00, 0x11, 0x08, 0x01, 0xe0, 0x02, 0x80]);
+        assert_eq!(image_dimensions(&jpeg), Some((640, 480)));
+
+        let mut webp = b"RIFF\0\0\0\0WEBPVP8X\x0a\0\0\0\0\0\0\0".to_vec();
+        webp.extend_from_slice(&[0x7f, 0x02, 0x00, 0xdf, 0x01, 0x00]);
+        assert_eq!(image_dimensions(&webp), Some((640, 480)));
+
+        assert_eq!(image_dimensions(b"%PDF-1.7"), None);
+        assert_eq!(image_dimensions(&png(1, 1)[..20]), None);
+    }
+
+    #[test]
+    fn test_disposition_filename() {
+        assert_eq!(
+            disposition_filename("inline; filename=\"cat.png\""),
+            Some("cat.png".to_string())
+        );
+        assert_eq!(
+            disposition_filename("attachment; filename=cat.png; filename*=utf-8''ch%C3%A2t.png"),
+            Some("chât.png".to_string())
+        );
+        assert_eq!(disposition_filename("inline"), None);
+    }
+}
@@ -0,0 +1,93 @@
+//! Local notifications for the Matrix client
+//!
+//! Evaluates the user's push rules from `m.push_rules` account data against
+//! timeline events on the device. The server cannot look into encrypted
+//! events, so they are decrypted first when encryption is enabled, and the
+//! rules then see the real event type and content.
+
+use anyhow::Result;
+use matryx_entity::types::RoomEncryptedContent;
+use matryx_entity::utils::{PushContext, PushOutcome};
+use matryx_entity::{Event, MembershipState, Ruleset};
+use serde_json::Value;
+use tracing::debug;
+
+use crate::MatrixClient;
+
+const PUSH_RULES_EVENT_TYPE: &str = "m.push_rules";
+const POWER_LEVELS_EVENT_TYPE: &str = "m.room.power_levels";
+
+/// Notification functionality
+impl MatrixClient {
+    /// The user's push rules, with any missing server-default rules added
+    ///
+    /// Read from the state store, which keeps `m.push_rules` from sync, or
+    /// fetched from the homeserver if the store has none.
+    pub async fn push_rules(&self) -> Result<Ruleset> {
+        let user_id = self
+            .user_id()
+            .ok_or_else(|| anyhow::anyhow!("Client is not authenticated"))?;
+
+        let content = match &self.store {
+            Some(store) => store.account_data(PUSH_RULES_EVENT_TYPE).await?,
+            None => None,
+        };
+        let content = match content {
+            Some(content) => Some(content),
+            None => self.get_account_data(PUSH_RULES_EVENT_TYPE).await?,
+        };
+
+        let ruleset = match content.and_then(|content| content.get("global").cloned()) {
+            Some(global) => serde_json::from_value(global)?,
+            None => Ruleset::server_default(&user_id),
+        };
+        Ok(ruleset.with_server_defaults(&user_id))
+    }
+
+    /// What the push rules need to know about a room, from the state store
+    pub async fn push_context(&self, room_id: &str) -> Result<PushContext> {
+        let user_id = self
+            .user_id()
+            .ok_or_else(|| anyhow::anyhow!("Client is not authenticated"))?;
+        let store = self.require_state_store()?;
+
+        let members = store.members(room_id).await?;
+        let display_name = members
+            .iter()
+            .find(|member| member.user_id == user_id)
+            .and_then(|member| member.display_name.clone());
+        let member_count = members
+            .iter()
+            .filter(|member| member.membership == MembershipState::Join)
+            .count() as u64;
+        let power_levels = store
+            .state_event(room_id, POWER_LEVELS_EVENT_TYPE, "")
+            .await?
+            .map(|event| serde_json::to_value(&event.content))
+            .transpose()?;
+
+        Ok(PushContext { user_id, display_name, member_count, power_levels })
+    }
+
+    /// Whether and how a timeline event should notify the user
+    ///
+    /// Encrypted events are evaluated after decryption; events that cannot be
+    /// decrypted (yet) are evaluated as `m.room.encrypted`.
+    pub async fn evaluate_push_rules(&self, event: &Event) -> Result<PushOutcome> {
+        let rules = self.push_rules().await?;
+        let context = self.push_context(&event.room_id).await?;
+
+        let mut event_json = serde_json::to_value(event)?;
+        if event.event_type == RoomEncryptedContent::EVENT_TYPE && self.is_encryption_enabled() {
+            match self.decrypt_event(event).await {
+                Ok(decrypted) => {
+                    event_json["type"] = Value::String(decrypted.event_type);
+                    event_json["content"] = decrypted.content;
+                },
+                Err(e) => debug!("Evaluating push rules on encrypted {}: {}", event.event_id, e),
+            }
+        }
+
+        Ok(rules.evaluate(&event_json, &context))
+    }
+}
@@ -0,0 +1,562 @@
+//! OAuth 2.0 login for the Matrix client
+//!
+//! Homeservers that delegate authentication to an OAuth 2.0 authorization
+//! server advertise it at `/_matrix/client/v1/auth_metadata`. The client
+//! registers itself there as a public native client and logs in with the
+//! authorization code grant and PKCE, redirecting the browser to a loopback
+//! listener, or with the device authorization grant where there is no
+//! browser. The device ID is requested as a scope, so passing the device of
+//! an earlier session keeps using it.
+
+use anyhow::Result;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use rand::distr::{Alphanumeric, SampleString};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+use url::Url;
+
+use crate::_matrix::client::v3::account::WhoamiResponse;
+use crate::MatrixClient;
+use crate::http_client::HttpClientError;
+use crate::login::{MatrixSession, RedirectListener};
+
+/// Scope granting access to the whole Client-Server API
+const API_SCOPE: &str = "urn:matrix:client:api:*";
+
+/// Scope prefix binding the session to a device ID
+const DEVICE_SCOPE_PREFIX: &str = "urn:matrix:client:device:";
+
+const AUTHORIZATION_CODE_GRANT: &str = "authorization_code";
+const REFRESH_TOKEN_GRANT: &str = "refresh_token";
+const DEVICE_CODE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+/// Length of generated device IDs
+const DEVICE_ID_LENGTH: usize = 10;
+
+/// Length of the PKCE code verifier, within the 43 to 128 characters of RFC 7636
+const CODE_VERIFIER_LENGTH: usize = 64;
+
+/// Polling interval of the device authorization grant when the server names none
+const DEFAULT_POLL_INTERVAL_SECS: u64 = 5;
+
+/// Metadata of the authorization server a homeserver delegates to (RFC 8414)
+#[derive(Debug, Clone, Deserialize)]
+pub struct AuthorizationServerMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: Url,
+    pub token_endpoint: Url,
+    /// Dynamic client registration endpoint (RFC 7591)
+    pub registration_endpoint: Option<Url>,
+    pub revocation_endpoint: Option<Url>,
+    pub device_authorization_endpoint: Option<Url>,
+    #[serde(default)]
+    pub grant_types_supported: Vec<String>,
+    #[serde(default)]
+    pub code_challenge_methods_supported: Vec<String>,
+}
+
+/// How the client presents itself to the authorization server
+#[derive(Debug, Clone)]
+pub struct OAuthClientMetadata {
+    /// Shown to the user when asked to grant access
+    pub client_name: String,
+    /// Homepage of the client
+    pub client_uri: Url,
+}
+
+impl OAuthClientMetadata {
+    pub fn new(client_name: impl Into<String>, client_uri: Url) -> Self {
+        Self { client_name: client_name.into(), client_uri }
+    }
+}
+
+/// Registration of a session's client with the authorization server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OAuthSession {
+    pub issuer: String,
+    pub client_id: String,
+    pub token_endpoint: Url,
+    pub revocation_endpoint: Option<Url>,
+}
+
+/// A pending device authorization grant (RFC 8628)
+///
+/// Show [`Self::user_code`] and [`Self::verification_uri`] to the user, who
+/// approves the login on another device.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeviceAuthorization {
+    device_code: String,
+    pub user_code: String,
+    pub verification_uri: Url,
+    /// `verification_uri` with the user code filled in, e.g. for a QR code
+    pub verification_uri_complete: Option<Url>,
+    /// Seconds until the user code expires
+    pub expires_in: u64,
+    /// Seconds to wait between polls of the token endpoint
+    #[serde(default = "default_poll_interval")]
+    pub interval: u64,
+}
+
+fn default_poll_interval() -> u64 {
+    DEFAULT_POLL_INTERVAL_SECS
+}
+
+/// Error response of an OAuth 2.0 endpoint (RFC 6749 section 5.2)
+#[derive(Debug, Clone, Deserialize, thiserror::Error)]
+#[error("OAuth 2.0 error {error}: {}", .error_description.as_deref().unwrap_or_default())]
+pub struct OAuthError {
+    pub error: String,
+    pub error_description: Option<String>,
+}
+
+/// Response of the token endpoint
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct TokenResponse {
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClientRegistrationResponse {
+    client_id: String,
+}
+
+/// OAuth 2.0 login functionality
+impl MatrixClient {
+    /// Get the metadata of the authorization server the homeserver delegates to
+    ///
+    /// Fails if the homeserver does not use OAuth 2.0 for authentication.
+    pub async fn oauth_metadata(&self) -> Result<AuthorizationServerMetadata> {
+        let mut response = self.get_auth_metadata("/_matrix/client/v1/auth_metadata").await?;
+
+        // Servers predating Matrix v1.15 only serve the MSC2965 path
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            response = self
+                .get_auth_metadata("/_matrix/client/unstable/org.matrix.msc2965/auth_metadata")
+                .await?;
+        }
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Failed to get OAuth 2.0 metadata: {}", error_text));
+        }
+
+        Ok(response.json().await?)
+    }
+
+    /// Login with the OAuth 2.0 authorization code grant and PKCE
+    ///
+    /// `open_url` is given the URL to open in the user's browser. After the
+    /// user grants access, the browser is redirected to a listener on a
+    /// loopback port with the authorization code.
+    pub async fn login_with_oauth(
+        &mut self,
+        client: &OAuthClientMetadata,
+        device_id: Option<String>,
+        open_url: impl FnOnce(&Url),
+    ) -> Result<()> {
+        let metadata = self.oauth_metadata().await?;
+        if !metadata
+            .code_challenge_methods_supported
+            .iter()
+            .any(|method| method == "S256")
+        {
+            return Err(anyhow::anyhow!("Authorization server does not support PKCE with S256"));
+        }
+
+        let listener = RedirectListener::bind().await?;
+        let redirect_uri = listener.url().as_str();
+        let client_id = self
+            .register_oauth_client(
+                &metadata,
+                client,
+                &[AUTHORIZATION_CODE_GRANT, REFRESH_TOKEN_GRANT],
+                Some(redirect_uri),
+            )
+            .await?;
+
+        let device_id = device_id.unwrap_or_else(generate_device_id);
+        let scope = scope(&device_id);
+        let code_verifier = Alphanumeric.sample_string(&mut rand::rng(), CODE_VERIFIER_LENGTH);
+        let state = Alphanumeric.sample_string(&mut rand::rng(), 16);
+
+        let url = authorization_url(
+            &metadata.authorization_endpoint,
+            &client_id,
+            redirect_uri,
+            &scope,
+            &state,
+            &code_challenge(&code_verifier),
+        );
+        info!("Waiting for OAuth 2.0 authorization on {}", redirect_uri);
+        open_url(&url);
+
+        let query = listener.wait_for("code").await?;
+        if query.get("state") != Some(&state) {
+            return Err(anyhow::anyhow!("OAuth 2.0 redirect has a mismatching state"));
+        }
+        if let Some(error) = query.get("error") {
+            return Err(OAuthError {
+                error: error.clone(),
+                error_description: query.get("error_description").cloned(),
+            }
+            .into());
+        }
+        let code = query
+            .get("code")
+            .ok_or_else(|| anyhow::anyhow!("OAuth 2.0 redirect did not carry a code"))?;
+
+        let tokens = self
+            .post_token_endpoint(
+                &metadata.token_endpoint,
+                &[
+                    ("grant_type", AUTHORIZATION_CODE_GRANT),
+                    ("code", code.as_str()),
+                    ("redirect_uri", redirect_uri),
+                    ("client_id", client_id.as_str()),
+                    ("code_verifier", code_verifier.as_str()),
+                ],
+            )
+            .await??;
+
+        self.finish_oauth_login(metadata, client_id, device_id, tokens).await
+    }
+
+    /// Login with the OAuth 2.0 device authorization grant
+    ///
+    /// `show_code` is given the code the user has to enter at the verification
+    /// URI on another device. Returns once the user approved the login, or
+    /// fails when they denied it or the code expired.
+    pub async fn login_with_device_code(
+        &mut self,
+        client: &OAuthClientMetadata,
+        device_id: Option<String>,
+        show_code: impl FnOnce(&DeviceAuthorization),
+    ) -> Result<()> {
+        let metadata = self.oauth_metadata().await?;
+        let device_authorization_endpoint =
+            metadata.device_authorization_endpoint.clone().ok_or_else(|| {
+                anyhow::anyhow!("Authorization server does not support device authorization")
+            })?;
+
+        let client_id = self
+            .register_oauth_client(
+                &metadata,
+                client,
+                &[DEVICE_CODE_GRANT, REFRESH_TOKEN_GRANT],
+                None,
+            )
+            .await?;
+
+        let device_id = device_id.unwrap_or_else(generate_device_id);
+        let response = self
+            .http_client
+            .post(device_authorization_endpoint)
+            .form(&[
+                ("client_id", client_id.as_str()),
+                ("scope", scope(&device_id).as_str()),
+            ])
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Device authorization failed: {}", error_text));
+        }
+
+        let authorization: DeviceAuthorization = response.json().await?;
+        show_code(&authorization);
+
+        let deadline = Instant::now() + Duration::from_secs(authorization.expires_in);
+        let mut interval = Duration::from_secs(authorization.interval);
+        let tokens = loop {
+            tokio::time::sleep(interval).await;
+            if Instant::now() >= deadline {
+                return Err(anyhow::anyhow!("Device authorization expired"));
+            }
+
+            let result = self
+                .post_token_endpoint(
+                    &metadata.token_endpoint,
+                    &[
+                        ("grant_type", DEVICE_CODE_GRANT),
+                        ("device_code", authorization.device_code.as_str()),
+                        ("client_id", client_id.as_str()),
+                    ],
+                )
+                .await?;
+
+            match result {
+                Ok(tokens) => break tokens,
+                Err(e) if e.error == "authorization_pending" => {},
+                Err(e) if e.error == "slow_down" => interval += Duration::from_secs(5),
+                Err(e) => return Err(e.into()),
+            }
+        };
+
+        self.finish_oauth_login(metadata, client_id, device_id, tokens).await
+    }
+
+    /// Exchange a refresh token of an OAuth 2.0 session for new tokens
+    ///
+    /// A refresh token the authorization server rejects ends the session, and
+    /// fails with [`HttpClientError::AuthenticationRequired`].
+    pub(crate) async fn refresh_oauth_tokens(
+        &self,
+        oauth: &OAuthSession,
+        refresh_token: &str,
+    ) -> Result<TokenResponse, HttpClientError> {
+        let result = self
+            .post_token_endpoint(
+                &oauth.token_endpoint,
+                &[
+                    ("grant_type", REFRESH_TOKEN_GRANT),
+                    ("refresh_token", refresh_token),
+                    ("client_id", oauth.client_id.as_str()),
+                ],
+            )
+            .await?;
+
+        result.map_err(|e| {
+            warn!("Failed to refresh OAuth 2.0 tokens: {}", e);
+            HttpClientError::AuthenticationRequired
+        })
+    }
+
+    /// End an OAuth 2.0 session by revoking its tokens (RFC 7009)
+    pub(crate) async fn revoke_oauth_session(
+        &self,
+        oauth: &OAuthSession,
+        session: &MatrixSession,
+    ) -> Result<()> {
+        let Some(revocation_endpoint) = &oauth.revocation_endpoint else {
+            warn!("Authorization server {} cannot revoke tokens", oauth.issuer);
+            return Ok(());
+        };
+
+        // Revoking the refresh token also revokes the access tokens issued with it
+        let (token, token_type_hint) = match &session.refresh_token {
+            Some(refresh_token) => (refresh_token, "refresh_token"),
+            None => (&session.access_token, "access_token"),
+        };
+
+        let response = self
+            .http_client
+            .post(revocation_endpoint.clone())
+            .form(&[
+                ("token", token.as_str()),
+                ("token_type_hint", token_type_hint),
+                ("client_id", oauth.client_id.as_str()),
+            ])
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Token revocation failed: {}", error_text));
+        }
+
+        Ok(())
+    }
+
+    async fn get_auth_metadata(&self, path: &str) -> Result<reqwest::Response> {
+        let url = self.config.homeserver_url.join(path)?;
+        Ok(self.http_client.get(url).send().await?)
+    }
+
+    /// Register as a public native client (RFC 7591) and return the client ID
+    async fn register_oauth_client(
+        &self,
+        metadata: &AuthorizationServerMetadata,
+        client: &OAuthClientMetadata,
+        grant_types: &[&str],
+        redirect_uri: Option<&str>,
+    ) -> Result<String> {
+        let registration_endpoint = metadata.registration_endpoint.clone().ok_or_else(|| {
+            anyhow::anyhow!("Authorization server does not support client registration")
+        })?;
+
+        let mut registration = serde_json::json!({
+            "client_name": client.client_name,
+            "client_uri": client.client_uri,
+            "application_type": "native",
+            "token_endpoint_auth_method": "none",
+            "grant_types": grant_types,
+            "response_types": [],
+        });
+        if let Some(redirect_uri) = redirect_uri {
+            registration["redirect_uris"] = serde_json::json!([redirect_uri]);
+            registration["response_types"] = serde_json::json!(["code"]);
+        }
+
+        let response = self
+            .http_client
+            .post(registration_endpoint)
+            .json(&registration)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("OAuth 2.0 client registration failed: {}", error_text));
+        }
+
+        let registered: ClientRegistrationResponse = response.json().await?;
+        debug!("Registered OAuth 2.0 client {}", registered.client_id);
+        Ok(registered.client_id)
+    }
+
+    /// Make a token request
+    ///
+    /// Errors the authorization server reports in OAuth 2.0 format are
+    /// returned as the inner error, e.g. so device authorization can keep
+    /// polling on `authorization_pending`.
+    async fn post_token_endpoint(
+        &self,
+        token_endpoint: &Url,
+        form: &[(&str, &str)],
+    ) -> Result<Result<TokenResponse, OAuthError>, HttpClientError> {
+        let response = self.http_client.post(token_endpoint.clone()).form(form).send().await?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(Ok(response.json().await?));
+        }
+
+        let error_text = response.text().await?;
+        match serde_json::from_str::<OAuthError>(&error_text) {
+            Ok(error) => Ok(Err(error)),
+            Err(_) => Err(HttpClientError::from_response(status.as_u16(), &error_text)),
+        }
+    }
+
+    async fn finish_oauth_login(
+        &mut self,
+        metadata: AuthorizationServerMetadata,
+        client_id: String,
+        device_id: String,
+        tokens: TokenResponse,
+    ) -> Result<()> {
+        let url = self.config.homeserver_url.join("/_matrix/client/v3/account/whoami")?;
+        let response = self.http_client.get(url).bearer_auth(&tokens.access_token).send().await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Failed to get the logged-in user: {}", error_text));
+        }
+
+        let whoami: WhoamiResponse = response.json().await?;
+        debug!("Logged in as {} through {}", whoami.user_id, metadata.issuer);
+
+        self.set_session(Some(MatrixSession {
+            homeserver_url: self.config.homeserver_url.clone(),
+            user_id: whoami.user_id,
+            device_id: Some(whoami.device_id.unwrap_or(device_id)),
+            access_token: tokens.access_token,
+            refresh_token: tokens.refresh_token,
+            oauth: Some(OAuthSession {
+                issuer: metadata.issuer,
+                client_id,
+                token_endpoint: metadata.token_endpoint,
+                revocation_endpoint: metadata.revocation_endpoint,
+            }),
+        }));
+
+        Ok(())
+    }
+}
+
+fn generate_device_id() -> String {
+    Alphanumeric
+        .sample_string(&mut rand::rng(), DEVICE_ID_LENGTH)
+        .to_uppercase()
+}
+
+/// Scope requesting API access for a device
+fn scope(device_id: &str) -> String {
+    format!("{} {}{}", API_SCOPE, DEVICE_SCOPE_PREFIX, device_id)
+}
+
+/// PKCE `S256` code challenge of a code verifier (RFC 7636)
+fn code_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+fn authorization_url(
+    authorization_endpoint: &Url,
+    client_id: &str,
+    redirect_uri: &str,
+    scope: &str,
+    state: &str,
+    code_challenge: &str,
+) -> Url {
+    let mut url = authorization_endpoint.clone();
+    url.query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("response_mode", "query")
+        .append_pair("client_id", client_id)
+        .append_pair("redirect_uri", redirect_uri)
+        .append_pair("scope", scope)
+        .append_pair("state", state)
+        .append_pair("code_challenge", code_challenge)
+        .append_pair("code_challenge_method", "S256");
+    url
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_code_challenge() {
+        // RFC 7636 appendix B
+        assert_eq!(
+            code_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+
+    #[test]
+    fn test_authorization_url() {
+        let endpoint = Url::parse("https://auth.example.org/authorize").unwrap();
+        let url = authorization_url(
+            &endpoint,
+            "client",
+            "http://127.0.0.1:4321/",
+            &scope("ABCDEFGHIJ"),
+            "state",
+            "challenge",
+        );
+
+        let query: HashMap<_, _> = url.query_pairs().into_owned().collect();
+        assert_eq!(query["response_type"], "code");
+        assert_eq!(query["redirect_uri"], "http://127.0.0.1:4321/");
+        assert_eq!(query["scope"], "urn:matrix:client:api:* urn:matrix:client:device:ABCDEFGHIJ");
+        assert_eq!(query["code_challenge_method"], "S256");
+    }
+
+    #[test]
+    fn test_device_authorization_default_interval() {
+        let authorization: DeviceAuthorization = serde_json::from_value(serde_json::json!({
+            "device_code": "device",
+            "user_code": "ABCD-EFGH",
+            "verification_uri": "https://auth.example.org/link",
+            "expires_in": 600
+        }))
+        .unwrap();
+
+        assert_eq!(authorization.interval, DEFAULT_POLL_INTERVAL_SECS);
+        assert!(authorization.verification_uri_complete.is_none());
+    }
+
+    #[test]
+    fn test_generate_device_id() {
+        let device_id = generate_device_id();
+        assert_eq!(device_id.len(), DEVICE_ID_LENGTH);
+        assert!(device_id.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit()));
+    }
+}
@@ -1,20 +1,35 @@
 //! Real-time Matrix client with WebSocket and LiveQuery integration
 //!
-//! This module provides a high-level real-time Matrix client that combines
-//! traditional Matrix Client-Server API with SurrealDB LiveQuery for superior
-//! real-time performance and reduced server load.
+//! This module provides a high-level real-time Matrix client on top of the
+//! Matrix Client-Server API. Sync updates come from a `/sync` loop, either
+//! long-polling or streamed as Server-Sent Events; clients embedded in the
+//! homeserver deployment can opt into SurrealDB LiveQuery instead.
 
 use crate::repositories::ClientRepositoryService;
 use anyhow::Result;
 use futures_util::{SinkExt, StreamExt};
-use matryx_entity::{ConnectionStatus, Event, Membership, RealtimeConfig, RealtimeCredentials};
+use matryx_entity::{
+    ConnectionStatus, Event, Membership, RealtimeConfig, RealtimeCredentials, RoomMessageContent,
+    SyncTransport,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::{RwLock, broadcast};
+use tokio::task::JoinHandle;
 use tokio_tungstenite::tungstenite::Message;
 use tracing::{debug, error, info, warn};
-
+use url::Url;
+
+use crate::crypto::{OlmMachinePickle, VerificationEvent};
+use crate::http_sync::HttpSync;
+use crate::login::MatrixSession;
+use crate::room_list::RoomListService;
+use crate::send_queue::{SendQueue, SendQueueUpdate};
+use crate::store::StateStore;
 use crate::sync::{LiveQuerySync, SyncState, SyncUpdate};
+use crate::{ClientConfig, MatrixClient};
 
 /// Real-time Matrix client event
 #[derive(Debug, Clone)]
@@ -52,11 +67,46 @@ pub enum RealtimeEvent {
     },
     /// Device list update
     DeviceListUpdate { changed: Vec<String>, left: Vec<String> },
+    /// Interactive verification progress
+    Verification(VerificationEvent),
+    /// Progress of queued outgoing events
+    SendQueue(SendQueueUpdate),
     /// Error occurred
     Error { message: String, recoverable: bool },
 }
 
-/// Real-time Matrix client with LiveQuery integration
+/// Source of sync updates
+enum SyncManager {
+    /// `/sync` over the Client-Server API
+    Http(HttpSync),
+    /// LiveQuery subscriptions on the homeserver database
+    LiveQuery(LiveQuerySync),
+}
+
+impl SyncManager {
+    fn subscribe_to_updates(&self) -> broadcast::Receiver<SyncUpdate> {
+        match self {
+            SyncManager::Http(sync) => sync.subscribe_to_updates(),
+            SyncManager::LiveQuery(sync) => sync.subscribe_to_updates(),
+        }
+    }
+
+    async fn get_sync_state(&self) -> SyncState {
+        match self {
+            SyncManager::Http(sync) => sync.get_sync_state().await,
+            SyncManager::LiveQuery(sync) => sync.get_sync_state().await,
+        }
+    }
+
+    async fn stop(&self) -> Result<()> {
+        match self {
+            SyncManager::Http(sync) => sync.stop().await,
+            SyncManager::LiveQuery(sync) => sync.stop().await,
+        }
+    }
+}
+
+/// Real-time Matrix client
 pub struct RealtimeMatrixClient {
     /// Client configuration
     config: RealtimeConfig,
@@ -70,8 +120,25 @@ pub struct RealtimeMatrixClient {
     db: Option<surrealdb::Surreal<surrealdb::engine::any::Any>>,
     /// Client repository service
     repository_service: Option<ClientRepositoryService>,
-    /// LiveQuery sync manager
-    sync_manager: Option<LiveQuerySync>,
+    /// Sync manager for the configured transport
+    sync_manager: Option<SyncManager>,
+    /// Task turning sync updates into real-time events
+    forward_task: Option<JoinHandle<()>>,
+    /// Persistent client state for HTTP sync and the send queue
+    store: Option<Arc<dyn StateStore>>,
+    /// Sorted joined and invited rooms, kept up to date by HTTP sync
+    room_list: Arc<RoomListService>,
+    /// Queue of outgoing room events
+    send_queue: Option<SendQueue>,
+    /// Task turning send queue progress into real-time events
+    send_queue_task: Option<JoinHandle<()>>,
+    /// Client the send queue sends through, holding the Olm machine when
+    /// encryption is enabled
+    matrix_client: Option<Arc<MatrixClient>>,
+    /// Enable end-to-end encryption when connecting
+    encryption: bool,
+    /// Encryption state to restore instead of creating a new device identity
+    encryption_pickle: Option<OlmMachinePickle>,
     /// Event broadcast channel
     event_sender: broadcast::Sender<RealtimeEvent>,
     /// Event receiver
@@ -105,12 +172,56 @@ impl RealtimeMatrixClient {
             db: None,
             repository_service: None,
             sync_manager: None,
+            forward_task: None,
+            store: None,
+            room_list: Arc::new(RoomListService::new()),
+            send_queue: None,
+            send_queue_task: None,
+            matrix_client: None,
+            encryption: false,
+            encryption_pickle: None,
             event_sender,
             event_receiver,
             websocket_tx: None,
         })
     }
 
+    /// Persist sync state in `store` and resume from it after a restart
+    ///
+    /// Sync state is only stored by the Client-Server API transports; the
+    /// send queue is stored with any transport.
+    pub fn with_state_store(mut self, store: Arc<dyn StateStore>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Enable end-to-end encryption when logging in
+    ///
+    /// Messages to encrypted rooms are then Megolm-encrypted, and
+    /// `m.room.encrypted` events reach the event stream decrypted. This needs
+    /// the to-device events only the Client-Server API transports carry. A
+    /// new device identity is created unless one is restored with
+    /// [`Self::with_encryption_pickle`].
+    pub fn with_encryption(mut self) -> Self {
+        self.encryption = true;
+        self
+    }
+
+    /// Enable end-to-end encryption with state saved by [`Self::encryption_pickle`]
+    pub fn with_encryption_pickle(mut self, pickle: OlmMachinePickle) -> Self {
+        self.encryption = true;
+        self.encryption_pickle = Some(pickle);
+        self
+    }
+
+    /// Snapshot the encryption state for persistence, if enabled
+    pub async fn encryption_pickle(&self) -> Option<OlmMachinePickle> {
+        match &self.matrix_client {
+            Some(client) => client.encryption_pickle().await,
+            None => None,
+        }
+    }
+
     /// Login and establish real-time connections
     pub async fn login(
         &mut self,
@@ -133,16 +244,55 @@ impl RealtimeMatrixClient {
             session_id: uuid::Uuid::new_v4().to_string(),
         });
 
-        self.set_status(ConnectionStatus::HttpOnly).await;
+        self.connect().await?;
 
-        // Step 2: Connect to SurrealDB
-        self.connect_surrealdb().await?;
-        self.set_status(ConnectionStatus::DatabaseConnected).await;
+        info!("Real-time Matrix client logged in successfully: {}", login_response.user_id);
+        Ok(())
+    }
 
-        // Step 3: Initialize LiveQuery sync
-        self.initialize_sync().await?;
+    /// Resume a session from an earlier login and establish real-time
+    /// connections
+    pub async fn restore_session(
+        &mut self,
+        user_id: &str,
+        access_token: &str,
+        device_id: &str,
+    ) -> Result<()> {
+        self.set_status(ConnectionStatus::Connecting).await;
 
-        // Note: Sync stream connection handled internally by LiveQuerySync
+        self.credentials = Some(RealtimeCredentials {
+            user_id: user_id.to_string(),
+            access_token: access_token.to_string(),
+            device_id: device_id.to_string(),
+            session_id: uuid::Uuid::new_v4().to_string(),
+        });
+
+        self.connect().await?;
+
+        info!("Real-time Matrix client restored session of {}", user_id);
+        Ok(())
+    }
+
+    /// Start the send queue, sync and WebSocket for the current credentials
+    async fn connect(&mut self) -> Result<()> {
+        self.set_status(ConnectionStatus::HttpOnly).await;
+        self.initialize_matrix_client().await?;
+        self.initialize_send_queue().await?;
+
+        match self.config.sync_transport {
+            SyncTransport::LongPoll | SyncTransport::ServerSentEvents => {
+                // Step 2: Start syncing over the Client-Server API
+                self.initialize_http_sync().await?;
+            },
+            SyncTransport::LiveQuery => {
+                // Step 2: Connect to SurrealDB
+                self.connect_surrealdb().await?;
+                self.set_status(ConnectionStatus::DatabaseConnected).await;
+
+                // Step 3: Initialize LiveQuery sync
+                self.initialize_sync().await?;
+            },
+        }
 
         // Step 4: Connect WebSocket if configured
         if self.config.websocket_url.is_some() {
@@ -150,8 +300,6 @@ impl RealtimeMatrixClient {
         }
 
         self.set_status(ConnectionStatus::FullyConnected).await;
-
-        info!("Real-time Matrix client logged in successfully: {}", login_response.user_id);
         Ok(())
     }
 
@@ -250,19 +398,157 @@ impl RealtimeMatrixClient {
             db.clone()
         );
 
-        // Start the sync manager
+        // Forward updates before starting so none are missed
+        self.forward_sync_updates(sync_manager.subscribe_to_updates());
         sync_manager.start().await?;
 
         // Store sync manager
-        self.sync_manager = Some(sync_manager);
-
-        // Note: Sync stream connection will be established after initialization
-        // The sync manager is now running and will handle LiveQuery subscriptions internally
+        self.sync_manager = Some(SyncManager::LiveQuery(sync_manager));
 
         debug!("Initialized LiveQuery sync manager");
         Ok(())
     }
 
+    /// Initialize the `/sync` loop over the Client-Server API
+    async fn initialize_http_sync(&mut self) -> Result<()> {
+        let credentials = self
+            .credentials
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No credentials available"))?;
+
+        let mut sync_manager = HttpSync::new(
+            &self.config.homeserver_url,
+            credentials.access_token.clone(),
+            self.config.sync_transport,
+        )?
+        .with_timeout(Duration::from_secs(self.config.sync_timeout_secs))
+        .with_retries(
+            self.config.max_reconnect_attempts,
+            Duration::from_secs(self.config.reconnect_delay_secs),
+        )
+        .with_room_list(credentials.user_id.clone(), self.room_list.clone());
+        if let Some(store) = &self.store {
+            sync_manager = sync_manager.with_store(store.clone());
+        }
+        if let Some(client) = &self.matrix_client
+            && client.is_encryption_enabled()
+        {
+            sync_manager = sync_manager.with_encryption(client.clone());
+        }
+
+        // Forward updates before starting so none are missed
+        self.forward_sync_updates(sync_manager.subscribe_to_updates());
+        sync_manager.start().await?;
+
+        self.sync_manager = Some(SyncManager::Http(sync_manager));
+
+        debug!("Initialized {:?} sync", self.config.sync_transport);
+        Ok(())
+    }
+
+    /// Set up the client the send queue sends through, enabling encryption
+    /// if configured
+    async fn initialize_matrix_client(&mut self) -> Result<()> {
+        let credentials = self
+            .credentials
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No credentials available"))?;
+        if self.encryption && self.config.sync_transport == SyncTransport::LiveQuery {
+            return Err(anyhow::anyhow!(
+                "End-to-end encryption requires syncing over the Client-Server API"
+            ));
+        }
+
+        let mut client = MatrixClient::new(ClientConfig {
+            homeserver_url: self.config.homeserver_url.clone(),
+            timeout_secs: self.config.timeout_secs,
+            ..Default::default()
+        })?;
+        client.restore_session(MatrixSession {
+            homeserver_url: self.config.homeserver_url.clone(),
+            user_id: credentials.user_id.clone(),
+            device_id: Some(credentials.device_id.clone()),
+            access_token: credentials.access_token.clone(),
+            refresh_token: None,
+            oauth: None,
+        });
+        if self.encryption {
+            match self.encryption_pickle.take() {
+                Some(pickle) => client.restore_encryption(pickle).await?,
+                None => client.enable_encryption().await?,
+            }
+        }
+
+        self.matrix_client = Some(Arc::new(client));
+        Ok(())
+    }
+
+    /// Start the send queue, resuming events left in the state store
+    async fn initialize_send_queue(&mut self) -> Result<()> {
+        let matrix_client = self
+            .matrix_client
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("No credentials available"))?;
+        let send_queue = SendQueue::new(matrix_client, self.store.clone());
+
+        let mut updates = send_queue.subscribe();
+        let event_sender = self.event_sender.clone();
+        self.send_queue_task = Some(tokio::spawn(async move {
+            loop {
+                match updates.recv().await {
+                    Ok(update) => {
+                        let _ = event_sender.send(RealtimeEvent::SendQueue(update));
+                    },
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Dropped {} send queue updates", skipped);
+                    },
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        }));
+
+        send_queue.load().await?;
+        self.send_queue = Some(send_queue);
+
+        debug!("Initialized send queue");
+        Ok(())
+    }
+
+    /// Turn sync updates into real-time events
+    ///
+    /// A sync that gives up moves the connection status to an error. The
+    /// send queue pauses while sync fails and resumes once it recovers.
+    fn forward_sync_updates(&mut self, mut updates: broadcast::Receiver<SyncUpdate>) {
+        let event_sender = self.event_sender.clone();
+        let status = self.status.clone();
+        let send_queue = self.send_queue.clone();
+
+        self.forward_task = Some(tokio::spawn(async move {
+            let mut typing = HashMap::new();
+            loop {
+                match updates.recv().await {
+                    Ok(update) => {
+                        if let Some(send_queue) = &send_queue {
+                            send_queue.set_online(!matches!(update, SyncUpdate::Error { .. }));
+                        }
+                        for event in realtime_events(&update, &mut typing) {
+                            let _ = event_sender.send(event);
+                        }
+                        if let SyncUpdate::Error { message, recoverable: false } = &update {
+                            let new_status = ConnectionStatus::Error(message.clone());
+                            update_status(&status, &event_sender, new_status).await;
+                        }
+                        let _ = event_sender.send(RealtimeEvent::SyncUpdate(update));
+                    },
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Dropped {} sync updates", skipped);
+                    },
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        }));
+    }
+
     /// Connect WebSocket for additional real-time features
     async fn connect_websocket(&mut self) -> Result<()> {
         if let Some(ws_url) = &self.config.websocket_url {
@@ -306,17 +592,7 @@ impl RealtimeMatrixClient {
 
     /// Set connection status and notify listeners
     async fn set_status(&self, new_status: ConnectionStatus) {
-        let old_status = {
-            let mut status = self.status.write().await;
-            let old = status.clone();
-            *status = new_status.clone();
-            old
-        };
-
-        if old_status != new_status {
-            let event = RealtimeEvent::ConnectionStatusChanged { old_status, new_status };
-            let _ = self.event_sender.send(event);
-        }
+        update_status(&self.status, &self.event_sender, new_status).await;
     }
 
     /// Get current connection status
@@ -340,42 +616,48 @@ impl RealtimeMatrixClient {
         })
     }
 
-    /// Send a message to a room
+    /// Send a message to a room through the send queue
+    ///
+    /// Waits until the homeserver accepted the message. Transient failures
+    /// are retried with the same transaction ID; a message that fails for
+    /// good is dropped from the queue.
     pub async fn send_message(&self, room_id: &str, message: &str) -> Result<String> {
-        let credentials = self
-            .credentials
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Not authenticated"))?;
-
-        let txn_id = uuid::Uuid::new_v4().to_string();
-        let path = format!("/_matrix/client/v3/rooms/{}/send/m.room.message/{}", room_id, txn_id);
-        let url = self.config.homeserver_url.join(&path)?;
-
-        let message_data = serde_json::json!({
-            "msgtype": "m.text",
-            "body": message
-        });
+        let content = serde_json::to_value(RoomMessageContent::text_plain(message))?;
+        self.require_send_queue()?
+            .send(room_id, RoomMessageContent::EVENT_TYPE, content)
+            .await
+    }
 
-        let response = self
-            .http_client
-            .put(url)
-            .bearer_auth(&credentials.access_token)
-            .json(&message_data)
-            .send()
+    /// Queue a message for a room without waiting for it to be sent
+    ///
+    /// Returns the transaction ID, which identifies the message in
+    /// [`RealtimeEvent::SendQueue`] updates and for [`SendQueue::edit`] and
+    /// [`SendQueue::cancel`].
+    pub async fn queue_message(&self, room_id: &str, message: &str) -> Result<String> {
+        let content = serde_json::to_value(RoomMessageContent::text_plain(message))?;
+        let txn_id = self
+            .require_send_queue()?
+            .enqueue(room_id, RoomMessageContent::EVENT_TYPE, content)
             .await?;
+        Ok(txn_id)
+    }
 
-        if !response.status().is_success() {
-            let error_text = response.text().await?;
-            return Err(anyhow::anyhow!("Failed to send message: {}", error_text));
-        }
+    /// Sorted list of the joined and invited rooms
+    ///
+    /// Only the Client-Server API transports keep it up to date.
+    pub fn room_list(&self) -> Arc<RoomListService> {
+        self.room_list.clone()
+    }
 
-        #[derive(Deserialize)]
-        struct SendEventResponse {
-            event_id: String,
-        }
+    /// The send queue (once logged in)
+    pub fn send_queue(&self) -> Option<&SendQueue> {
+        self.send_queue.as_ref()
+    }
 
-        let send_response: SendEventResponse = response.json().await?;
-        Ok(send_response.event_id)
+    fn require_send_queue(&self) -> Result<&SendQueue> {
+        self.send_queue
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Not authenticated"))
     }
 
     /// Join a room
@@ -408,8 +690,8 @@ impl RealtimeMatrixClient {
 
         let join_response: JoinRoomResponse = response.json().await?;
 
-        // Subscribe to the new room for real-time updates
-        if let Some(sync_manager) = &self.sync_manager {
+        // LiveQuery subscribes per room; `/sync` picks the room up by itself
+        if let Some(SyncManager::LiveQuery(sync_manager)) = &self.sync_manager {
             sync_manager.subscribe_to_room(&join_response.room_id).await?;
         }
 
@@ -442,6 +724,42 @@ impl RealtimeMatrixClient {
         Ok(())
     }
 
+    /// Restart sync with the current credentials, e.g. after it gave up
+    ///
+    /// `/sync` resumes from the state store when there is one; without it the
+    /// next sync is an initial one again.
+    pub async fn reconnect(&mut self) -> Result<()> {
+        if self.credentials.is_none() {
+            return Err(anyhow::anyhow!("Not authenticated"));
+        }
+
+        if let Some(sync_manager) = self.sync_manager.take()
+            && let Err(e) = sync_manager.stop().await
+        {
+            warn!("Failed to stop sync before reconnecting: {}", e);
+        }
+        if let Some(forward_task) = self.forward_task.take() {
+            forward_task.abort();
+        }
+
+        self.set_status(ConnectionStatus::Connecting).await;
+        match self.config.sync_transport {
+            SyncTransport::LongPoll | SyncTransport::ServerSentEvents => {
+                self.initialize_http_sync().await?;
+            },
+            SyncTransport::LiveQuery => {
+                if self.db.is_none() {
+                    self.connect_surrealdb().await?;
+                }
+                self.initialize_sync().await?;
+            },
+        }
+        self.set_status(ConnectionStatus::FullyConnected).await;
+
+        info!("Reconnected sync");
+        Ok(())
+    }
+
     /// Get current sync state
     pub async fn sync_state(&self) -> Option<SyncState> {
         if let Some(sync_manager) = &self.sync_manager {
@@ -476,6 +794,19 @@ impl RealtimeMatrixClient {
         if let Some(sync_manager) = &self.sync_manager {
             sync_manager.stop().await?;
         }
+        if let Some(forward_task) = self.forward_task.take() {
+            forward_task.abort();
+        }
+
+        self.room_list.clear().await;
+
+        // Stop sending; what is still queued stays in the store
+        if let Some(send_queue) = self.send_queue.take() {
+            send_queue.stop().await;
+        }
+        if let Some(send_queue_task) = self.send_queue_task.take() {
+            send_queue_task.abort();
+        }
 
         // Close WebSocket
         if let Some(mut ws_tx) = self.websocket_tx.take() {
@@ -486,6 +817,7 @@ impl RealtimeMatrixClient {
         self.credentials = None;
         self.sync_manager = None;
         self.repository_service = None;
+        self.matrix_client = None;
 
         self.set_status(ConnectionStatus::Disconnected).await;
 
@@ -498,6 +830,16 @@ impl RealtimeMatrixClient {
         self.credentials.as_ref().map(|c| c.user_id.as_str())
     }
 
+    /// Get the access token if authenticated
+    pub fn access_token(&self) -> Option<&str> {
+        self.credentials.as_ref().map(|c| c.access_token.as_str())
+    }
+
+    /// Get the homeserver URL
+    pub fn homeserver_url(&self) -> &Url {
+        &self.config.homeserver_url
+    }
+
     /// Check if client is authenticated
     pub fn is_authenticated(&self) -> bool {
         self.credentials.is_some()
@@ -508,6 +850,109 @@ impl RealtimeMatrixClient {
     pub fn subscribe_to_events(&self) -> broadcast::Receiver<RealtimeEvent> {
         self.event_receiver.resubscribe()
     }
+
+    /// Forward verification progress onto the event stream
+    ///
+    /// Pass the receiver from [`crate::MatrixClient::verification_events`].
+    pub fn forward_verification_events(
+        &self,
+        mut events: broadcast::Receiver<VerificationEvent>,
+    ) -> tokio::task::JoinHandle<()> {
+        let event_sender = self.event_sender.clone();
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) => {
+                        let _ = event_sender.send(RealtimeEvent::Verification(event));
+                    },
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Dropped {} verification events", skipped);
+                    },
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+}
+
+/// Set connection status and notify listeners
+async fn update_status(
+    status: &RwLock<ConnectionStatus>,
+    event_sender: &broadcast::Sender<RealtimeEvent>,
+    new_status: ConnectionStatus,
+) {
+    let old_status = {
+        let mut status = status.write().await;
+        let old = status.clone();
+        *status = new_status.clone();
+        old
+    };
+
+    if old_status != new_status {
+        let event = RealtimeEvent::ConnectionStatusChanged { old_status, new_status };
+        let _ = event_sender.send(event);
+    }
+}
+
+/// Real-time events for a sync update
+///
+/// `typing` holds who was typing per room, so a typing update can be turned
+/// into notifications for the users who started and stopped.
+fn realtime_events(
+    update: &SyncUpdate,
+    typing: &mut HashMap<String, HashSet<String>>,
+) -> Vec<RealtimeEvent> {
+    match update {
+        SyncUpdate::RoomEvent { room_id, event } => {
+            vec![RealtimeEvent::RoomEvent { room_id: room_id.clone(), event: event.clone() }]
+        },
+        SyncUpdate::MembershipUpdate { room_id, membership, .. } => {
+            vec![RealtimeEvent::MembershipChanged {
+                room_id: room_id.clone(),
+                user_id: membership.user_id.clone(),
+                membership: membership.clone(),
+            }]
+        },
+        SyncUpdate::PresenceUpdate { user_id, presence } => {
+            vec![RealtimeEvent::PresenceUpdate {
+                user_id: user_id.clone(),
+                presence: presence.presence.clone(),
+                status_msg: presence.status_msg.clone(),
+                last_active_ago: presence.last_active_ago,
+            }]
+        },
+        SyncUpdate::DeviceListUpdate { changed, left } => {
+            vec![RealtimeEvent::DeviceListUpdate { changed: changed.clone(), left: left.clone() }]
+        },
+        SyncUpdate::Typing { room_id, user_ids } => {
+            let now: HashSet<String> = user_ids.iter().cloned().collect();
+            let before = typing.insert(room_id.clone(), now.clone()).unwrap_or_default();
+
+            let notification = |user_id: &String, typing: bool| RealtimeEvent::TypingNotification {
+                room_id: room_id.clone(),
+                user_id: user_id.clone(),
+                typing,
+            };
+            let started = now.difference(&before).map(|user_id| notification(user_id, true));
+            let stopped = before.difference(&now).map(|user_id| notification(user_id, false));
+            started.chain(stopped).collect()
+        },
+        SyncUpdate::Receipt { room_id, receipt } => {
+            vec![RealtimeEvent::ReadReceipt {
+                room_id: room_id.clone(),
+                user_id: receipt.user_id.clone(),
+                event_id: receipt.event_id.clone(),
+                timestamp: receipt.ts.and_then(|ts| u64::try_from(ts).ok()).unwrap_or(0),
+            }]
+        },
+        SyncUpdate::Error { message, recoverable } => {
+            vec![RealtimeEvent::Error {
+                message: message.clone(),
+                recoverable: *recoverable,
+            }]
+        },
+        SyncUpdate::StateUpdate { .. } | SyncUpdate::AccountDataUpdate { .. } => Vec::new(),
+    }
 }
 
 /// Login response from Matrix server
@@ -547,6 +992,32 @@ mod tests {
         assert_eq!(client.connection_status().await, ConnectionStatus::Disconnected);
     }
 
+    #[test]
+    fn test_typing_notifications() {
+        let mut typing = HashMap::new();
+        let update = |user_ids: &[&str]| SyncUpdate::Typing {
+            room_id: "!room:example.org".to_string(),
+            user_ids: user_ids.iter().map(|id| id.to_string()).collect(),
+        };
+
+        let events = realtime_events(&update(&["@alice:example.org"]), &mut typing);
+        assert!(matches!(
+            events.as_slice(),
+            [RealtimeEvent::TypingNotification { user_id, typing: true, .. }]
+                if user_id == "@alice:example.org"
+        ));
+
+        let events = realtime_events(&update(&["@bob:example.org"]), &mut typing);
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().any(|event| matches!(
+            event,
+            RealtimeEvent::TypingNotification { user_id, typing: false, .. }
+                if user_id == "@alice:example.org"
+        )));
+
+        assert!(realtime_events(&update(&["@bob:example.org"]), &mut typing).is_empty());
+    }
+
     #[tokio::test]
     async fn test_connection_status_changes() {
         let config = RealtimeConfig::default();
@@ -0,0 +1,324 @@
+//! Room list entries and the per-room state they are computed from
+
+use matryx_entity::{Event, MembershipState};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+const NAME_EVENT_TYPE: &str = "m.room.name";
+const CANONICAL_ALIAS_EVENT_TYPE: &str = "m.room.canonical_alias";
+const AVATAR_EVENT_TYPE: &str = "m.room.avatar";
+const MEMBER_EVENT_TYPE: &str = "m.room.member";
+
+/// State event types the room list is computed from
+pub(crate) const STATE_EVENT_TYPES: [&str; 4] = [
+    NAME_EVENT_TYPE,
+    CANONICAL_ALIAS_EVENT_TYPE,
+    AVATAR_EVENT_TYPE,
+    MEMBER_EVENT_TYPE,
+];
+
+const TAG_EVENT_TYPE: &str = "m.tag";
+const MARKED_UNREAD_EVENT_TYPE: &str = "m.marked_unread";
+/// Event type of `m.marked_unread` before MSC2867 was merged
+const UNSTABLE_MARKED_UNREAD_EVENT_TYPE: &str = "com.famedly.marked_unread";
+
+/// Room account data types the room list is computed from
+pub(crate) const ACCOUNT_DATA_EVENT_TYPES: [&str; 3] = [
+    TAG_EVENT_TYPE,
+    MARKED_UNREAD_EVENT_TYPE,
+    UNSTABLE_MARKED_UNREAD_EVENT_TYPE,
+];
+
+pub const FAVOURITE_TAG: &str = "m.favourite";
+pub const LOW_PRIORITY_TAG: &str = "m.lowpriority";
+
+/// Heroes picked from the member list when the server sends none
+const MAX_HEROES: usize = 5;
+
+/// A room as shown in the room list
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RoomListEntry {
+    pub room_id: String,
+    /// Our membership: `Join` or `Invite`
+    pub membership: MembershipState,
+    /// Display name, computed as the spec describes
+    pub name: String,
+    /// `mxc://` URI of the room avatar, or of the other user's in a DM
+    pub avatar_url: Option<String>,
+    /// Whether `m.direct` (or the invite) marks the room as a DM
+    pub is_direct: bool,
+    /// Users `m.direct` lists the room for
+    pub direct_user_ids: Vec<String>,
+    /// Tag names from `m.tag`, sorted
+    pub tags: Vec<String>,
+    pub notification_count: u64,
+    pub highlight_count: u64,
+    /// Whether the user marked the room as unread (`m.marked_unread`)
+    pub marked_unread: bool,
+    /// Timestamp of the latest timeline event we have seen
+    pub latest_event_ts: Option<i64>,
+}
+
+impl RoomListEntry {
+    pub fn is_favourite(&self) -> bool {
+        self.tags.iter().any(|tag| tag == FAVOURITE_TAG)
+    }
+
+    pub fn is_low_priority(&self) -> bool {
+        self.tags.iter().any(|tag| tag == LOW_PRIORITY_TAG)
+    }
+
+    /// Whether the room has notifications or was marked as unread
+    pub fn is_unread(&self) -> bool {
+        self.notification_count > 0 || self.highlight_count > 0 || self.marked_unread
+    }
+
+    pub fn is_invite(&self) -> bool {
+        self.membership == MembershipState::Invite
+    }
+}
+
+/// What the room list knows about one room
+#[derive(Debug, Clone)]
+pub(crate) struct RoomInfo {
+    pub membership: MembershipState,
+    /// State event content by type and state key
+    pub state: HashMap<(String, String), Value>,
+    /// `m.heroes` of the room summary; the summary only carries changes
+    pub heroes: Option<Vec<String>>,
+    pub joined_member_count: Option<u64>,
+    pub invited_member_count: Option<u64>,
+    pub notification_count: u64,
+    pub highlight_count: u64,
+    pub tags: Vec<String>,
+    pub marked_unread: bool,
+    pub latest_event_ts: Option<i64>,
+}
+
+impl RoomInfo {
+    pub fn new(membership: MembershipState) -> Self {
+        Self {
+            membership,
+            state: HashMap::new(),
+            heroes: None,
+            joined_member_count: None,
+            invited_member_count: None,
+            notification_count: 0,
+            highlight_count: 0,
+            tags: Vec::new(),
+            marked_unread: false,
+            latest_event_ts: None,
+        }
+    }
+
+    pub fn apply_state_event(&mut self, event: &Event) {
+        let Some(state_key) = &event.state_key else {
+            return;
+        };
+        let content = serde_json::to_value(&event.content).unwrap_or_default();
+        self.state.insert((event.event_type.clone(), state_key.clone()), content);
+    }
+
+    /// Apply the `summary` section of a joined room
+    pub fn apply_summary(&mut self, summary: &Value) {
+        if let Some(heroes) = summary.get("m.heroes").and_then(Value::as_array) {
+            self.heroes =
+                Some(heroes.iter().filter_map(Value::as_str).map(str::to_string).collect());
+        }
+        if let Some(count) = summary.get("m.joined_member_count").and_then(Value::as_u64) {
+            self.joined_member_count = Some(count);
+        }
+        if let Some(count) = summary.get("m.invited_member_count").and_then(Value::as_u64) {
+            self.invited_member_count = Some(count);
+        }
+    }
+
+    /// Apply room account data content by type
+    pub fn apply_account_data(&mut self, event_type: &str, content: &Value) {
+        match event_type {
+            TAG_EVENT_TYPE => {
+                let mut tags: Vec<String> = content
+                    .get("tags")
+                    .and_then(Value::as_object)
+                    .map(|tags| tags.keys().cloned().collect())
+                    .unwrap_or_default();
+                tags.sort();
+                self.tags = tags;
+            },
+            MARKED_UNREAD_EVENT_TYPE | UNSTABLE_MARKED_UNREAD_EVENT_TYPE => {
+                self.marked_unread =
+                    content.get("unread").and_then(Value::as_bool).unwrap_or(false);
+            },
+            _ => {},
+        }
+    }
+
+    pub fn apply_timeline_event(&mut self, event: &Event) {
+        if self.latest_event_ts.is_none_or(|ts| ts < event.origin_server_ts) {
+            self.latest_event_ts = Some(event.origin_server_ts);
+        }
+    }
+
+    fn state_content(&self, event_type: &str, state_key: &str) -> Option<&Value> {
+        self.state.get(&(event_type.to_string(), state_key.to_string()))
+    }
+
+    fn state_string(&self, event_type: &str, field: &str) -> Option<&str> {
+        self.state_content(event_type, "")?
+            .get(field)?
+            .as_str()
+            .filter(|value| !value.is_empty())
+    }
+
+    /// Members by user ID and membership
+    fn members(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.state
+            .iter()
+            .filter(|((event_type, _), _)| event_type == MEMBER_EVENT_TYPE)
+            .map(|((_, user_id), content)| (user_id.as_str(), content))
+    }
+
+    fn member_count(&self, membership: &str) -> u64 {
+        self.members()
+            .filter(|(_, content)| {
+                content.get("membership").and_then(Value::as_str) == Some(membership)
+            })
+            .count() as u64
+    }
+
+    /// Heroes from the summary, or up to five other members by user ID
+    fn heroes(&self, own_user_id: &str) -> Vec<String> {
+        if let Some(heroes) = &self.heroes {
+            return heroes.clone();
+        }
+
+        let other_members = |memberships: &[&str]| {
+            let mut user_ids: Vec<String> = self
+                .members()
+                .filter(|(user_id, content)| {
+                    *user_id != own_user_id
+                        && content
+                            .get("membership")
+                            .and_then(Value::as_str)
+                            .is_some_and(|membership| memberships.contains(&membership))
+                })
+                .map(|(user_id, _)| user_id.to_string())
+                .collect();
+            user_ids.sort();
+            user_ids.truncate(MAX_HEROES);
+            user_ids
+        };
+
+        // Former members name an empty room
+        let heroes = other_members(&["join", "invite"]);
+        if heroes.is_empty() {
+            other_members(&["leave", "ban"])
+        } else {
+            heroes
+        }
+    }
+
+    fn member_name(&self, user_id: &str) -> String {
+        self.state_content(MEMBER_EVENT_TYPE, user_id)
+            .and_then(|content| content.get("displayname"))
+            .and_then(Value::as_str)
+            .filter(|name| !name.is_empty())
+            .unwrap_or(user_id)
+            .to_string()
+    }
+
+    /// Display name of the room
+    ///
+    /// `m.room.name`, then `m.room.canonical_alias`, then the heroes:
+    /// "Alice", "Alice and Bob", "Alice, Bob and 3 others", or "Empty Room"
+    /// when nobody else is left.
+    pub fn display_name(&self, own_user_id: &str) -> String {
+        if let Some(name) = self.state_string(NAME_EVENT_TYPE, "name") {
+            return name.to_string();
+        }
+        if let Some(alias) = self.state_string(CANONICAL_ALIAS_EVENT_TYPE, "alias") {
+            return alias.to_string();
+        }
+
+        let heroes = self.heroes(own_user_id);
+        let names: Vec<String> = heroes.iter().map(|user_id| self.member_name(user_id)).collect();
+        let joined = self.joined_member_count.unwrap_or_else(|| self.member_count("join"));
+        let invited = self.invited_member_count.unwrap_or_else(|| self.member_count("invite"));
+        let others = (joined + invited).saturating_sub(1);
+
+        if others == 0 {
+            return if names.is_empty() {
+                "Empty Room".to_string()
+            } else {
+                format!("Empty Room (was {})", join_names(&names, 0))
+            };
+        }
+        if names.is_empty() {
+            return "Empty Room".to_string();
+        }
+
+        join_names(&names, others.saturating_sub(names.len() as u64))
+    }
+
+    /// `m.room.avatar`, or the avatar of the only other member
+    pub fn avatar_url(&self, own_user_id: &str) -> Option<String> {
+        if let Some(url) = self.state_string(AVATAR_EVENT_TYPE, "url") {
+            return Some(url.to_string());
+        }
+
+        match self.heroes(own_user_id).as_slice() {
+            [user_id] => self
+                .state_content(MEMBER_EVENT_TYPE, user_id)
+                .and_then(|content| content.get("avatar_url"))
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            _ => None,
+        }
+    }
+
+    /// Whether our own invite is marked `is_direct`
+    pub fn is_direct_invite(&self, own_user_id: &str) -> bool {
+        self.membership == MembershipState::Invite
+            && self
+                .state_content(MEMBER_EVENT_TYPE, own_user_id)
+                .and_then(|content| content.get("is_direct"))
+                .and_then(Value::as_bool)
+                .unwrap_or(false)
+    }
+
+    pub fn to_entry(
+        &self,
+        room_id: &str,
+        own_user_id: &str,
+        direct_user_ids: Vec<String>,
+    ) -> RoomListEntry {
+        RoomListEntry {
+            room_id: room_id.to_string(),
+            membership: self.membership.clone(),
+            name: self.display_name(own_user_id),
+            avatar_url: self.avatar_url(own_user_id),
+            is_direct: !direct_user_ids.is_empty() || self.is_direct_invite(own_user_id),
+            direct_user_ids,
+            tags: self.tags.clone(),
+            notification_count: self.notification_count,
+            highlight_count: self.highlight_count,
+            marked_unread: self.marked_unread,
+            latest_event_ts: self.latest_event_ts,
+        }
+    }
+}
+
+/// "Alice", "Alice and Bob", "Alice, Bob and Carol" or "Alice, Bob and 2 others"
+fn join_names(names: &[String], others: u64) -> String {
+    let mut parts: Vec<String> = names.to_vec();
+    if others > 0 {
+        parts.push(format!("{} other{}", others, if others == 1 { "" } else { "s" }));
+    }
+
+    match parts.as_slice() {
+        [] => String::new(),
+        [only] => only.clone(),
+        [init @ .., last] => format!("{} and {}", init.join(", "), last),
+    }
+}
@@ -0,0 +1,659 @@
+//! Room list
+//!
+//! A [`RoomListService`] keeps the joined and invited rooms up to date from
+//! `/sync`: display names from the room name, canonical alias or heroes,
+//! avatars, DMs from `m.direct`, `m.tag` tags, unread and highlight counts
+//! and `m.marked_unread`. Rooms are sorted by a list of [`RoomSortKey`]s, in
+//! the style of the `rooms` entry of `[settings.sort]`, and can be narrowed
+//! down with a [`RoomListFilter`].
+//!
+//! Every change to the visible rooms is emitted as a [`RoomListDiff`], so a
+//! UI can mirror the list without diffing it itself.
+
+pub mod entry;
+
+pub use entry::{FAVOURITE_TAG, LOW_PRIORITY_TAG, RoomListEntry};
+
+use entry::{ACCOUNT_DATA_EVENT_TYPES, RoomInfo, STATE_EVENT_TYPES};
+use matryx_entity::MembershipState;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use tokio::sync::{RwLock, broadcast};
+
+use crate::SyncResponse;
+use crate::store::{StateStore, StoreError, account_data_events};
+
+const DIRECT_EVENT_TYPE: &str = "m.direct";
+
+/// Sort order used until [`RoomListService::set_sort`] is called
+pub const DEFAULT_SORT: [RoomSortKey; 4] = [
+    RoomSortKey::Favorite,
+    RoomSortKey::LowPriority,
+    RoomSortKey::Unread,
+    RoomSortKey::Name,
+];
+
+/// A criterion to sort rooms by; later keys break ties of earlier ones
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RoomSortKey {
+    /// Favourites first
+    Favorite,
+    /// Low priority rooms last
+    LowPriority,
+    /// Rooms with highlights, then other unread rooms first
+    Unread,
+    /// By display name, ignoring case
+    Name,
+    /// Most recent activity first
+    Recency,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Unknown room sort key: {0}")]
+pub struct UnknownSortKey(pub String);
+
+impl FromStr for RoomSortKey {
+    type Err = UnknownSortKey;
+
+    fn from_str(key: &str) -> Result<Self, Self::Err> {
+        match key {
+            "favorite" => Ok(Self::Favorite),
+            "lowpriority" => Ok(Self::LowPriority),
+            "unread" => Ok(Self::Unread),
+            "name" => Ok(Self::Name),
+            "recency" => Ok(Self::Recency),
+            _ => Err(UnknownSortKey(key.to_string())),
+        }
+    }
+}
+
+/// Which rooms the list shows
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum RoomListFilter {
+    #[default]
+    All,
+    Unread,
+    Favourites,
+    LowPriority,
+    DirectMessages,
+    /// Rooms that are not DMs
+    Groups,
+    Invites,
+    /// Display name contains the text, ignoring case
+    Name(String),
+    /// Rooms matching every filter
+    AllOf(Vec<RoomListFilter>),
+}
+
+impl RoomListFilter {
+    pub fn matches(&self, entry: &RoomListEntry) -> bool {
+        match self {
+            Self::All => true,
+            Self::Unread => entry.is_unread(),
+            Self::Favourites => entry.is_favourite(),
+            Self::LowPriority => entry.is_low_priority(),
+            Self::DirectMessages => entry.is_direct,
+            Self::Groups => !entry.is_direct,
+            Self::Invites => entry.is_invite(),
+            Self::Name(text) => entry.name.to_lowercase().contains(&text.to_lowercase()),
+            Self::AllOf(filters) => filters.iter().all(|filter| filter.matches(entry)),
+        }
+    }
+}
+
+/// A change to the visible rooms
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum RoomListDiff {
+    Insert {
+        index: usize,
+        entry: RoomListEntry,
+    },
+    Set {
+        index: usize,
+        entry: RoomListEntry,
+    },
+    Remove {
+        index: usize,
+    },
+    /// All rooms were replaced, e.g. after the sort order or filter changed
+    Reset {
+        entries: Vec<RoomListEntry>,
+    },
+}
+
+/// Unread counts summed over all rooms, whatever the filter
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct UnreadSummary {
+    /// Rooms with notifications or marked as unread
+    pub unread_rooms: usize,
+    pub notification_count: u64,
+    pub highlight_count: u64,
+}
+
+/// Sorted, filtered list of the joined and invited rooms
+#[derive(Debug)]
+pub struct RoomListService {
+    state: RwLock<RoomListState>,
+    diff_sender: broadcast::Sender<RoomListDiff>,
+}
+
+#[derive(Debug)]
+struct RoomListState {
+    own_user_id: String,
+    rooms: HashMap<String, RoomInfo>,
+    /// Users each DM is with, by room ID, from `m.direct`
+    direct: HashMap<String, Vec<String>>,
+    sort: Vec<RoomSortKey>,
+    filter: RoomListFilter,
+    /// Visible rooms, in order
+    entries: Vec<RoomListEntry>,
+    loaded_from_store: bool,
+}
+
+impl Default for RoomListService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RoomListService {
+    pub fn new() -> Self {
+        let (diff_sender, _) = broadcast::channel(1000);
+        Self {
+            state: RwLock::new(RoomListState {
+                own_user_id: String::new(),
+                rooms: HashMap::new(),
+                direct: HashMap::new(),
+                sort: DEFAULT_SORT.to_vec(),
+                filter: RoomListFilter::All,
+                entries: Vec::new(),
+                loaded_from_store: false,
+            }),
+            diff_sender,
+        }
+    }
+
+    /// Visible rooms, in order
+    pub async fn entries(&self) -> Vec<RoomListEntry> {
+        self.state.read().await.entries.clone()
+    }
+
+    /// A joined or invited room, whether the filter shows it or not
+    pub async fn entry(&self, room_id: &str) -> Option<RoomListEntry> {
+        let state = self.state.read().await;
+        state.rooms.get(room_id).map(|info| state.entry(room_id, info))
+    }
+
+    /// Receive a diff for every change to the visible rooms
+    pub fn subscribe(&self) -> broadcast::Receiver<RoomListDiff> {
+        self.diff_sender.subscribe()
+    }
+
+    /// Sort by `keys`, e.g. parsed from `[settings.sort]`
+    pub async fn set_sort(&self, keys: Vec<RoomSortKey>) {
+        let mut state = self.state.write().await;
+        state.sort = keys;
+        self.reset(&mut state);
+    }
+
+    pub async fn set_filter(&self, filter: RoomListFilter) {
+        let mut state = self.state.write().await;
+        state.filter = filter;
+        self.reset(&mut state);
+    }
+
+    /// Unread counts over all joined and invited rooms
+    pub async fn unread_summary(&self) -> UnreadSummary {
+        let state = self.state.read().await;
+        state.rooms.iter().map(|(room_id, info)| state.entry(room_id, info)).fold(
+            UnreadSummary::default(),
+            |mut summary, entry| {
+                if entry.is_unread() {
+                    summary.unread_rooms += 1;
+                }
+                summary.notification_count += entry.notification_count;
+                summary.highlight_count += entry.highlight_count;
+                summary
+            },
+        )
+    }
+
+    /// Apply a sync response of `own_user_id`
+    pub async fn handle_sync_response(&self, own_user_id: &str, response: &SyncResponse) {
+        let mut guard = self.state.write().await;
+        let state = &mut *guard;
+        state.own_user_id = own_user_id.to_string();
+
+        if let Some(direct) =
+            account_data_events(response.account_data.as_ref()).get(DIRECT_EVENT_TYPE)
+        {
+            state.direct = direct_rooms(direct);
+        }
+
+        for (room_id, room) in &response.rooms.join {
+            let info = state
+                .rooms
+                .entry(room_id.clone())
+                .or_insert_with(|| RoomInfo::new(MembershipState::Join));
+            info.membership = MembershipState::Join;
+
+            for event in room.state.iter().flat_map(|state| &state.events) {
+                info.apply_state_event(event);
+            }
+            for event in room.timeline.iter().flat_map(|timeline| &timeline.events) {
+                info.apply_state_event(event);
+                info.apply_timeline_event(event);
+            }
+            if let Some(summary) = &room.summary {
+                info.apply_summary(summary);
+            }
+            if let Some(counts) = &room.unread_notifications {
+                if let Some(count) = counts.get("notification_count").and_then(Value::as_u64) {
+                    info.notification_count = count;
+                }
+                if let Some(count) = counts.get("highlight_count").and_then(Value::as_u64) {
+                    info.highlight_count = count;
+                }
+            }
+            for (event_type, content) in account_data_events(room.account_data.as_ref()) {
+                info.apply_account_data(&event_type, &content);
+            }
+        }
+
+        // Invite state is sent in full
+        for (room_id, room) in &response.rooms.invite {
+            let mut info = RoomInfo::new(MembershipState::Invite);
+            for event in room.invite_state.iter().flat_map(|state| &state.events) {
+                info.apply_state_event(event);
+            }
+            state.rooms.insert(room_id.clone(), info);
+        }
+
+        for room_id in response.rooms.leave.keys() {
+            state.rooms.remove(room_id);
+        }
+
+        self.send(state.refresh());
+    }
+
+    /// Fill the list from a state store, e.g. before resuming sync
+    ///
+    /// Unread counts and room summaries are not stored, so they stay unknown
+    /// until the rooms show up in a sync. Does nothing once loaded.
+    pub async fn load_from_store(
+        &self,
+        own_user_id: &str,
+        store: &dyn StateStore,
+    ) -> Result<(), StoreError> {
+        let mut guard = self.state.write().await;
+        if guard.loaded_from_store {
+            return Ok(());
+        }
+        let state = &mut *guard;
+        state.own_user_id = own_user_id.to_string();
+
+        for room in store.rooms().await? {
+            if !matches!(room.membership, MembershipState::Join | MembershipState::Invite) {
+                continue;
+            }
+
+            let mut info = RoomInfo::new(room.membership.clone());
+            for event_type in STATE_EVENT_TYPES {
+                for event in store.state_events(&room.room_id, event_type).await? {
+                    info.apply_state_event(&event);
+                }
+            }
+            for event_type in ACCOUNT_DATA_EVENT_TYPES {
+                if let Some(content) = store.room_account_data(&room.room_id, event_type).await? {
+                    info.apply_account_data(event_type, &content);
+                }
+            }
+            for event in store.timeline(&room.room_id).await?.events {
+                info.apply_timeline_event(&event);
+            }
+
+            // Rooms already seen in a sync are more up to date
+            state.rooms.entry(room.room_id).or_insert(info);
+        }
+
+        if state.direct.is_empty()
+            && let Some(direct) = store.account_data(DIRECT_EVENT_TYPE).await?
+        {
+            state.direct = direct_rooms(&direct);
+        }
+
+        state.loaded_from_store = true;
+        self.send(state.refresh());
+        Ok(())
+    }
+
+    /// Forget all rooms, e.g. after logging out
+    pub async fn clear(&self) {
+        let mut state = self.state.write().await;
+        state.rooms.clear();
+        state.direct.clear();
+        state.loaded_from_store = false;
+        self.send(state.refresh());
+    }
+
+    /// Recompute the visible rooms and send them all, even when none are left
+    fn reset(&self, state: &mut RoomListState) {
+        state.refresh();
+        self.send(vec![RoomListDiff::Reset { entries: state.entries.clone() }]);
+    }
+
+    fn send(&self, diffs: Vec<RoomListDiff>) {
+        for diff in diffs {
+            // Nobody listening is fine
+            let _ = self.diff_sender.send(diff);
+        }
+    }
+}
+
+impl RoomListState {
+    fn entry(&self, room_id: &str, info: &RoomInfo) -> RoomListEntry {
+        let direct_user_ids = self.direct.get(room_id).cloned().unwrap_or_default();
+        info.to_entry(room_id, &self.own_user_id, direct_user_ids)
+    }
+
+    /// Recompute the visible rooms and return the diffs from the previous ones
+    fn refresh(&mut self) -> Vec<RoomListDiff> {
+        let mut entries: Vec<RoomListEntry> = self
+            .rooms
+            .iter()
+            .map(|(room_id, info)| self.entry(room_id, info))
+            .filter(|entry| self.filter.matches(entry))
+            .collect();
+        entries.sort_by(|a, b| compare(a, b, &self.sort));
+
+        let diffs = diff_entries(&self.entries, &entries);
+        self.entries = entries;
+        diffs
+    }
+}
+
+fn compare(a: &RoomListEntry, b: &RoomListEntry, keys: &[RoomSortKey]) -> Ordering {
+    keys.iter()
+        .map(|key| match key {
+            RoomSortKey::Favorite => b.is_favourite().cmp(&a.is_favourite()),
+            RoomSortKey::LowPriority => a.is_low_priority().cmp(&b.is_low_priority()),
+            RoomSortKey::Unread => {
+                (b.highlight_count > 0, b.is_unread()).cmp(&(a.highlight_count > 0, a.is_unread()))
+            },
+            RoomSortKey::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            RoomSortKey::Recency => b.latest_event_ts.cmp(&a.latest_event_ts),
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or_else(|| a.room_id.cmp(&b.room_id))
+}
+
+/// Diffs turning `old` into `new`
+fn diff_entries(old: &[RoomListEntry], new: &[RoomListEntry]) -> Vec<RoomListDiff> {
+    if old.is_empty() {
+        return match new.is_empty() {
+            true => Vec::new(),
+            false => vec![RoomListDiff::Reset { entries: new.to_vec() }],
+        };
+    }
+
+    let mut diffs = Vec::new();
+    let mut current = old.to_vec();
+
+    let kept: HashSet<&str> = new.iter().map(|entry| entry.room_id.as_str()).collect();
+    for index in (0..current.len()).rev() {
+        if !kept.contains(current[index].room_id.as_str()) {
+            current.remove(index);
+            diffs.push(RoomListDiff::Remove { index });
+        }
+    }
+
+    for (index, entry) in new.iter().enumerate() {
+        if current
+            .get(index)
+            .is_some_and(|existing| existing.room_id == entry.room_id)
+        {
+            if current[index] != *entry {
+                current[index] = entry.clone();
+                diffs.push(RoomListDiff::Set { index, entry: entry.clone() });
+            }
+            continue;
+        }
+
+        // Moved up from further down
+        if let Some(from) = current
+            .iter()
+            .skip(index)
+            .position(|existing| existing.room_id == entry.room_id)
+        {
+            current.remove(index + from);
+            diffs.push(RoomListDiff::Remove { index: index + from });
+        }
+        current.insert(index, entry.clone());
+        diffs.push(RoomListDiff::Insert { index, entry: entry.clone() });
+    }
+
+    diffs
+}
+
+/// Users per DM room from `m.direct` content, which lists rooms per user
+fn direct_rooms(content: &Value) -> HashMap<String, Vec<String>> {
+    let mut direct: HashMap<String, Vec<String>> = HashMap::new();
+    for (user_id, room_ids) in content.as_object().into_iter().flatten() {
+        for room_id in room_ids.as_array().into_iter().flatten().filter_map(Value::as_str) {
+            direct.entry(room_id.to_string()).or_default().push(user_id.clone());
+        }
+    }
+    for user_ids in direct.values_mut() {
+        user_ids.sort();
+    }
+    direct
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const ALICE: &str = "@alice:example.org";
+
+    fn member(user_id: &str, membership: &str, displayname: Option<&str>) -> Value {
+        json!({
+            "event_id": format!("$member-{}", user_id),
+            "sender": user_id,
+            "origin_server_ts": 0,
+            "type": "m.room.member",
+            "room_id": "!room:example.org",
+            "content": { "membership": membership, "displayname": displayname },
+            "state_key": user_id,
+        })
+    }
+
+    fn state(event_type: &str, content: Value) -> Value {
+        json!({
+            "event_id": format!("${}", event_type),
+            "sender": ALICE,
+            "origin_server_ts": 0,
+            "type": event_type,
+            "room_id": "!room:example.org",
+            "content": content,
+            "state_key": "",
+        })
+    }
+
+    fn message(ts: i64) -> Value {
+        json!({
+            "event_id": format!("$message-{}", ts),
+            "sender": ALICE,
+            "origin_server_ts": ts,
+            "type": "m.room.message",
+            "room_id": "!room:example.org",
+            "content": { "msgtype": "m.text", "body": "hi" },
+        })
+    }
+
+    fn sync(rooms: Value, account_data: Value) -> SyncResponse {
+        serde_json::from_value(json!({
+            "next_batch": "s1",
+            "rooms": rooms,
+            "account_data": { "events": account_data },
+        }))
+        .expect("sync response should deserialize")
+    }
+
+    fn joined(state_events: Vec<Value>, extra: Value) -> Value {
+        let mut room = json!({ "state": { "events": state_events } });
+        for (key, value) in extra.as_object().into_iter().flatten() {
+            room[key] = value.clone();
+        }
+        room
+    }
+
+    async fn names(list: &RoomListService) -> Vec<String> {
+        list.entries().await.into_iter().map(|entry| entry.name).collect()
+    }
+
+    #[tokio::test]
+    async fn test_display_names() {
+        let list = RoomListService::new();
+        let response = sync(
+            json!({ "join": {
+                "!named:example.org": joined(vec![state("m.room.name", json!({ "name": "Named" }))], json!({})),
+                "!alias:example.org": joined(
+                    vec![state("m.room.canonical_alias", json!({ "alias": "#alias:example.org" }))],
+                    json!({}),
+                ),
+                "!dm:example.org": joined(
+                    vec![member(ALICE, "join", None), member("@bob:example.org", "join", Some("Bob"))],
+                    json!({}),
+                ),
+                "!group:example.org": joined(
+                    vec![member(ALICE, "join", None)],
+                    json!({ "summary": {
+                        "m.heroes": ["@bob:example.org", "@carol:example.org"],
+                        "m.joined_member_count": 5,
+                        "m.invited_member_count": 0,
+                    }}),
+                ),
+                "!empty:example.org": joined(
+                    vec![member(ALICE, "join", None), member("@dave:example.org", "leave", Some("Dave"))],
+                    json!({}),
+                ),
+            }}),
+            json!([]),
+        );
+        list.handle_sync_response(ALICE, &response).await;
+
+        assert_eq!(
+            names(&list).await,
+            vec![
+                "#alias:example.org",
+                "@bob:example.org, @carol:example.org and 2 others",
+                "Bob",
+                "Empty Room (was Dave)",
+                "Named",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sort_tags_unread_and_dm() {
+        let list = RoomListService::new();
+        let named = |name: &str| vec![state("m.room.name", json!({ "name": name }))];
+        let response = sync(
+            json!({ "join": {
+                "!a:example.org": joined(named("A"), json!({ "timeline": { "events": [message(3)] } })),
+                "!b:example.org": joined(named("B"), json!({
+                    "account_data": { "events": [
+                        { "type": "m.tag", "content": { "tags": { "m.lowpriority": {} } } },
+                    ]},
+                })),
+                "!c:example.org": joined(named("C"), json!({
+                    "unread_notifications": { "notification_count": 2, "highlight_count": 0 },
+                })),
+                "!d:example.org": joined(named("D"), json!({
+                    "account_data": { "events": [
+                        { "type": "m.tag", "content": { "tags": { "m.favourite": { "order": 0.5 } } } },
+                    ]},
+                })),
+                "!e:example.org": joined(named("E"), json!({
+                    "account_data": { "events": [
+                        { "type": "m.marked_unread", "content": { "unread": true } },
+                    ]},
+                    "timeline": { "events": [message(5)] },
+                })),
+            }}),
+            json!([{ "type": "m.direct", "content": { "@bob:example.org": ["!a:example.org"] } }]),
+        );
+        list.handle_sync_response(ALICE, &response).await;
+
+        assert_eq!(names(&list).await, vec!["D", "C", "E", "A", "B"]);
+        let dm = list.entry("!a:example.org").await.expect("room should be listed");
+        assert!(dm.is_direct);
+        assert_eq!(dm.direct_user_ids, vec!["@bob:example.org"]);
+
+        list.set_sort(vec![RoomSortKey::Recency, RoomSortKey::Name]).await;
+        assert_eq!(names(&list).await, vec!["E", "A", "B", "C", "D"]);
+
+        list.set_filter(RoomListFilter::Unread).await;
+        assert_eq!(names(&list).await, vec!["E", "C"]);
+
+        assert_eq!(
+            list.unread_summary().await,
+            UnreadSummary {
+                unread_rooms: 2,
+                notification_count: 2,
+                highlight_count: 0
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_diffs() {
+        let list = RoomListService::new();
+        let mut diffs = list.subscribe();
+        let named = |name: &str| vec![state("m.room.name", json!({ "name": name }))];
+
+        let response = sync(
+            json!({ "join": {
+                "!a:example.org": joined(named("A"), json!({})),
+                "!b:example.org": joined(named("B"), json!({})),
+            }}),
+            json!([]),
+        );
+        list.handle_sync_response(ALICE, &response).await;
+        assert!(
+            matches!(diffs.try_recv(), Ok(RoomListDiff::Reset { entries }) if entries.len() == 2)
+        );
+
+        // B becomes unread and moves to the top, then A is left
+        let response = sync(
+            json!({ "join": { "!b:example.org": {
+                "unread_notifications": { "notification_count": 1, "highlight_count": 1 },
+            }}}),
+            json!([]),
+        );
+        list.handle_sync_response(ALICE, &response).await;
+        assert!(matches!(diffs.try_recv(), Ok(RoomListDiff::Remove { index: 1 })));
+        assert!(matches!(diffs.try_recv(), Ok(RoomListDiff::Insert { index: 0, .. })));
+
+        let response = sync(json!({ "leave": { "!a:example.org": {} } }), json!([]));
+        list.handle_sync_response(ALICE, &response).await;
+        assert!(matches!(diffs.try_recv(), Ok(RoomListDiff::Remove { index: 1 })));
+        assert_eq!(names(&list).await, vec!["B"]);
+    }
+
+    #[test]
+    fn test_sort_key_from_str() {
+        let keys: Result<Vec<RoomSortKey>, _> = ["favorite", "lowpriority", "unread", "name"]
+            .iter()
+            .map(|key| key.parse())
+            .collect();
+        assert_eq!(keys.expect("keys should parse"), DEFAULT_SORT);
+        assert!("power".parse::<RoomSortKey>().is_err());
+    }
+}
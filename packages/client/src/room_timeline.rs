@@ -0,0 +1,271 @@
+//! Room timelines for the Matrix client
+//!
+//! Hands out one shared [`Timeline`] per room (or thread), feeds it the
+//! timeline events of every sync and fills it backwards from `/messages`, or
+//! from `/relations` for a thread. Encrypted events are decrypted on the way
+//! in when encryption is enabled.
+
+use anyhow::Result;
+use matryx_entity::Event;
+use reqwest::Method;
+use serde::Deserialize;
+use serde_json::Value;
+use std::sync::Arc;
+use tracing::debug;
+
+use crate::timeline::{Timeline, TimelineItem};
+use crate::{MatrixClient, SyncResponse};
+
+const ENCRYPTED_EVENT_TYPE: &str = "m.room.encrypted";
+
+/// A page of the threads in a room
+#[derive(Debug, Clone)]
+pub struct ThreadList {
+    /// Thread roots, most recently active first, with their thread summary
+    pub threads: Vec<TimelineItem>,
+    /// Token for the next page, `None` on the last one
+    pub next_batch: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct MessagesResponse {
+    chunk: Vec<Event>,
+    end: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ChunkResponse {
+    chunk: Vec<Event>,
+    next_batch: Option<String>,
+}
+
+/// Timeline functionality
+impl MatrixClient {
+    /// The timeline of a room
+    ///
+    /// A new timeline starts out with the events cached in the state store.
+    pub async fn timeline(&self, room_id: &str) -> Result<Arc<Timeline>> {
+        let key = (room_id.to_string(), None);
+        if let Some(timeline) = self.timelines.lock().await.get(&key) {
+            return Ok(timeline.clone());
+        }
+
+        let timeline = Arc::new(Timeline::new(room_id));
+        if let Some(store) = &self.store {
+            let cached = store.timeline(room_id).await?;
+            let events = self.decrypt_timeline_events(cached.events).await;
+            timeline.add_sync_events(&events, true, cached.prev_batch.as_deref()).await;
+        }
+        Ok(self.timelines.lock().await.entry(key).or_insert(timeline).clone())
+    }
+
+    /// The timeline of the thread rooted at `root_event_id`
+    pub async fn thread_timeline(
+        &self,
+        room_id: &str,
+        root_event_id: &str,
+    ) -> Result<Arc<Timeline>> {
+        let key = (room_id.to_string(), Some(root_event_id.to_string()));
+        let mut timelines = self.timelines.lock().await;
+        let timeline = timelines
+            .entry(key)
+            .or_insert_with(|| Arc::new(Timeline::for_thread(room_id, root_event_id)));
+        Ok(timeline.clone())
+    }
+
+    /// Load up to `limit` earlier events into a timeline
+    ///
+    /// Returns whether the timeline reached the start of the room or thread.
+    pub async fn paginate_backwards(&self, timeline: &Timeline, limit: u32) -> Result<bool> {
+        if timeline.is_at_start().await {
+            return Ok(true);
+        }
+
+        let room_id = urlencoding::encode(timeline.room_id());
+        let from = timeline.pagination_token().await;
+        let mut path = match timeline.thread_root() {
+            Some(root) => format!(
+                "/_matrix/client/v1/rooms/{}/relations/{}/m.thread?dir=b&limit={}",
+                room_id,
+                urlencoding::encode(root),
+                limit
+            ),
+            None => format!("/_matrix/client/v3/rooms/{}/messages?dir=b&limit={}", room_id, limit),
+        };
+        if let Some(from) = &from {
+            path.push_str(&format!("&from={}", urlencoding::encode(from)));
+        }
+
+        let request = self.authenticated_request(Method::GET, &path)?;
+        let response = self.send(request).await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Failed to paginate timeline: {}", error_text));
+        }
+
+        let (mut events, end) = match timeline.thread_root() {
+            Some(root) => {
+                let page: ChunkResponse = response.json().await?;
+                let mut events = page.chunk;
+                // The relations of a thread do not include its root
+                if page.next_batch.is_none() {
+                    events.push(self.room_event(timeline.room_id(), root).await?);
+                }
+                (events, page.next_batch)
+            },
+            None => {
+                let page: MessagesResponse = response.json().await?;
+                (page.chunk, page.end)
+            },
+        };
+        events = self.decrypt_timeline_events(events).await;
+
+        if !timeline
+            .add_paginated_events(from.as_deref(), &events, end.as_deref())
+            .await
+        {
+            debug!("Timeline of {} changed while paginating", timeline.room_id());
+        }
+        Ok(timeline.is_at_start().await)
+    }
+
+    /// Threads in a room, most recently active first
+    pub async fn threads(&self, room_id: &str, from: Option<&str>) -> Result<ThreadList> {
+        let mut path = format!("/_matrix/client/v1/rooms/{}/threads", urlencoding::encode(room_id));
+        if let Some(from) = from {
+            path.push_str(&format!("?from={}", urlencoding::encode(from)));
+        }
+
+        let request = self.authenticated_request(Method::GET, &path)?;
+        let response = self.send(request).await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Failed to get threads: {}", error_text));
+        }
+
+        let page: ChunkResponse = response.json().await?;
+        let events = self.decrypt_timeline_events(page.chunk).await;
+        Ok(ThreadList {
+            threads: events.iter().map(TimelineItem::from_event).collect(),
+            next_batch: page.next_batch,
+        })
+    }
+
+    /// Get a single event of a room
+    pub async fn room_event(&self, room_id: &str, event_id: &str) -> Result<Event> {
+        let path = format!(
+            "/_matrix/client/v3/rooms/{}/event/{}",
+            urlencoding::encode(room_id),
+            urlencoding::encode(event_id)
+        );
+        let request = self.authenticated_request(Method::GET, &path)?;
+        let response = self.send(request).await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Failed to get event: {}", error_text));
+        }
+
+        let event: Event = response.json().await?;
+        Ok(self.decrypt_timeline_event(event).await)
+    }
+
+    /// Send an event to the room of a timeline, showing it as a local echo
+    ///
+    /// The echo is marked sent or failed once the homeserver answered. Edits
+    /// and reactions get no echo of their own; they show up on their target
+    /// with the remote echo.
+    pub async fn send_timeline_event(
+        &self,
+        timeline: &Timeline,
+        event_type: &str,
+        content: Value,
+    ) -> Result<String> {
+        let sender = self
+            .user_id()
+            .ok_or_else(|| anyhow::anyhow!("Client is not authenticated"))?;
+        let txn_id = uuid::Uuid::new_v4().to_string();
+
+        let modifies_target = content
+            .get("m.relates_to")
+            .and_then(|relates_to| relates_to.get("rel_type"))
+            .and_then(Value::as_str)
+            .is_some_and(|rel_type| rel_type == "m.replace" || rel_type == "m.annotation");
+        if !modifies_target {
+            let echo = TimelineItem::local_echo(&txn_id, &sender, event_type, content.clone());
+            timeline.add_local_echo(echo).await;
+        }
+
+        match self.send_event(timeline.room_id(), event_type, content, &txn_id).await {
+            Ok(event_id) => {
+                timeline.mark_sent(&txn_id, &event_id).await;
+                Ok(event_id)
+            },
+            Err(e) => {
+                timeline.mark_failed(&txn_id, &e.to_string()).await;
+                Err(e)
+            },
+        }
+    }
+
+    /// Feed the timeline events of a sync to the open timelines
+    pub(crate) async fn update_timelines(&self, response: &SyncResponse) {
+        let timelines: Vec<Arc<Timeline>> = self.timelines.lock().await.values().cloned().collect();
+        if timelines.is_empty() {
+            return;
+        }
+
+        for (room_id, room) in &response.rooms.join {
+            let Some(chunk) = &room.timeline else {
+                continue;
+            };
+            let room_timelines: Vec<&Arc<Timeline>> =
+                timelines.iter().filter(|timeline| timeline.room_id() == room_id).collect();
+            if room_timelines.is_empty() {
+                continue;
+            }
+
+            let events = self.decrypt_timeline_events(chunk.events.clone()).await;
+            for timeline in room_timelines {
+                timeline
+                    .add_sync_events(
+                        &events,
+                        chunk.limited.unwrap_or(false),
+                        chunk.prev_batch.as_deref(),
+                    )
+                    .await;
+            }
+        }
+    }
+
+    async fn decrypt_timeline_events(&self, events: Vec<Event>) -> Vec<Event> {
+        let mut decrypted_events = Vec::with_capacity(events.len());
+        for event in events {
+            decrypted_events.push(self.decrypt_timeline_event(event).await);
+        }
+        decrypted_events
+    }
+
+    /// Replace an encrypted event with its decrypted form where possible
+    ///
+    /// Events that cannot be decrypted are kept as `m.room.encrypted`.
+    pub(crate) async fn decrypt_timeline_event(&self, mut event: Event) -> Event {
+        if self.olm_machine.is_none() || event.event_type != ENCRYPTED_EVENT_TYPE {
+            return event;
+        }
+
+        match self.decrypt_event(&event).await {
+            Ok(decrypted) => match serde_json::from_value(decrypted.content) {
+                Ok(content) => {
+                    event.event_type = decrypted.event_type;
+                    event.content = content;
+                },
+                Err(e) => debug!("Decrypted event {} is malformed: {}", event.event_id, e),
+            },
+            Err(e) => debug!("Failed to decrypt event {}: {}", event.event_id, e),
+        }
+        event
+    }
+}
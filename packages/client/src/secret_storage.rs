@@ -0,0 +1,171 @@
+//! Secret storage for the Matrix client
+//!
+//! Keeps the private cross-signing keys and the key backup decryption key in
+//! account data, encrypted with the user's default `m.secret_storage` key so
+//! a new device can recover them from a recovery key or passphrase.
+
+use anyhow::Result;
+use serde_json::json;
+use tracing::debug;
+
+use crate::MatrixClient;
+use crate::crypto::CrossSigningAccountPickle;
+use crate::crypto::secret_storage::{
+    DEFAULT_KEY_EVENT_TYPE, DefaultKeyContent, KEY_EVENT_TYPE_PREFIX, MASTER_KEY_SECRET,
+    SELF_SIGNING_KEY_SECRET, USER_SIGNING_KEY_SECRET,
+};
+use crate::crypto::{SecretContent, SecretStorageKey, SecretStorageKeyDescription};
+
+/// Secret storage functionality
+impl MatrixClient {
+    /// ID and description of the default secret storage key, if one is set up
+    pub async fn secret_storage_default_key(
+        &self,
+    ) -> Result<Option<(String, SecretStorageKeyDescription)>> {
+        let Some(default_key) = self.get_account_data(DEFAULT_KEY_EVENT_TYPE).await? else {
+            return Ok(None);
+        };
+        let default_key: DefaultKeyContent = serde_json::from_value(default_key)?;
+
+        let event_type = format!("{}{}", KEY_EVENT_TYPE_PREFIX, default_key.key);
+        let description = self.get_account_data(&event_type).await?.ok_or_else(|| {
+            anyhow::anyhow!("Secret storage key {} has no description", default_key.key)
+        })?;
+        Ok(Some((default_key.key, serde_json::from_value(description)?)))
+    }
+
+    /// Create a new default secret storage key
+    ///
+    /// Without a passphrase the key is random and must be shown to the user
+    /// as [`SecretStorageKey::recovery_key`]. Cross-signing keys held by this
+    /// device are stored under the new key.
+    pub async fn setup_secret_storage(&self, passphrase: Option<&str>) -> Result<SecretStorageKey> {
+        let key = match passphrase {
+            Some(passphrase) => SecretStorageKey::new_from_passphrase(passphrase, None)?,
+            None => SecretStorageKey::new(None)?,
+        };
+
+        self.set_account_data(&key.event_type(), &serde_json::to_value(key.description())?)
+            .await?;
+        self.set_account_data(DEFAULT_KEY_EVENT_TYPE, &json!({ "key": key.key_id() }))
+            .await?;
+        debug!("Created default secret storage key {}", key.key_id());
+
+        let holds_cross_signing_keys = match &self.olm_machine {
+            Some(olm) => olm.lock().await.cross_signing().is_some(),
+            None => false,
+        };
+        if holds_cross_signing_keys {
+            self.store_cross_signing_keys(&key).await?;
+        }
+
+        Ok(key)
+    }
+
+    /// Open the default secret storage key with a recovery key
+    pub async fn open_secret_storage_with_recovery_key(
+        &self,
+        recovery_key: &str,
+    ) -> Result<SecretStorageKey> {
+        let (key_id, description) = self.require_default_key().await?;
+        Ok(SecretStorageKey::from_recovery_key(&key_id, description, recovery_key)?)
+    }
+
+    /// Open the default secret storage key with its passphrase
+    pub async fn open_secret_storage_with_passphrase(
+        &self,
+        passphrase: &str,
+    ) -> Result<SecretStorageKey> {
+        let (key_id, description) = self.require_default_key().await?;
+        Ok(SecretStorageKey::from_passphrase(&key_id, description, passphrase)?)
+    }
+
+    /// Encrypt a secret and store it in account data under its name
+    ///
+    /// Encryptions under other keys are kept.
+    pub async fn store_secret(
+        &self,
+        key: &SecretStorageKey,
+        secret_name: &str,
+        secret: &str,
+    ) -> Result<()> {
+        let mut content: SecretContent = match self.get_account_data(secret_name).await? {
+            Some(content) => serde_json::from_value(content)?,
+            None => SecretContent::default(),
+        };
+        content
+            .encrypted
+            .insert(key.key_id().to_string(), key.encrypt(secret_name, secret)?);
+
+        self.set_account_data(secret_name, &serde_json::to_value(content)?).await
+    }
+
+    /// Fetch and decrypt a secret, if it is stored under `key`
+    pub async fn get_secret(
+        &self,
+        key: &SecretStorageKey,
+        secret_name: &str,
+    ) -> Result<Option<String>> {
+        let Some(content) = self.get_account_data(secret_name).await? else {
+            return Ok(None);
+        };
+        let content: SecretContent = serde_json::from_value(content)?;
+
+        match content.encrypted.get(key.key_id()) {
+            Some(encrypted) => Ok(Some(key.decrypt(secret_name, encrypted)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Store this device's private cross-signing keys in secret storage
+    pub async fn store_cross_signing_keys(&self, key: &SecretStorageKey) -> Result<()> {
+        let keys = self
+            .require_olm_machine()?
+            .lock()
+            .await
+            .cross_signing()
+            .map(|account| account.pickle())
+            .ok_or_else(|| anyhow::anyhow!("This device holds no cross-signing keys"))?;
+
+        self.store_secret(key, MASTER_KEY_SECRET, &keys.master_key).await?;
+        self.store_secret(key, SELF_SIGNING_KEY_SECRET, &keys.self_signing_key)
+            .await?;
+        self.store_secret(key, USER_SIGNING_KEY_SECRET, &keys.user_signing_key)
+            .await
+    }
+
+    /// Load the private cross-signing keys from secret storage
+    ///
+    /// The device list of our own user should be current so the master key
+    /// can be checked against the published one.
+    pub async fn restore_cross_signing_keys(&self, key: &SecretStorageKey) -> Result<()> {
+        let olm = self.require_olm_machine()?;
+        let user_id = olm.lock().await.user_id().to_string();
+
+        let keys = CrossSigningAccountPickle {
+            user_id,
+            master_key: self.require_secret(key, MASTER_KEY_SECRET).await?,
+            self_signing_key: self.require_secret(key, SELF_SIGNING_KEY_SECRET).await?,
+            user_signing_key: self.require_secret(key, USER_SIGNING_KEY_SECRET).await?,
+        };
+
+        olm.lock().await.import_cross_signing_keys(&keys)?;
+        Ok(())
+    }
+
+    pub(crate) async fn require_secret(
+        &self,
+        key: &SecretStorageKey,
+        secret_name: &str,
+    ) -> Result<String> {
+        self.get_secret(key, secret_name)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Secret {} is not in secret storage", secret_name))
+    }
+
+    async fn require_default_key(&self) -> Result<(String, SecretStorageKeyDescription)> {
+        self.secret_storage_default_key()
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Secret storage is not set up"))
+    }
+}
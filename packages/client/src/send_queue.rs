@@ -0,0 +1,674 @@
+//! Send queue for room events
+//!
+//! Events are queued per room and sent in order by one worker per room. An
+//! event keeps its transaction ID across retries, so the homeserver drops a
+//! repeated request that already got through before the connection failed.
+//! Rate limits (`M_LIMIT_EXCEEDED`) are waited out for the `retry_after_ms`
+//! the homeserver asks for, other transient errors are retried with
+//! exponential backoff, and nothing is sent while the queue is offline. An
+//! event that fails for good holds up its room until it is retried or
+//! cancelled, so later events never overtake it.
+//!
+//! [`MatrixClient`] sends events to encrypted rooms Megolm-encrypted; a bare
+//! [`MatrixHttpClient`] sends everything as it is.
+//!
+//! With a [`StateStore`] the queue survives restarts.
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::sync::{Mutex, broadcast, watch};
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use crate::MatrixClient;
+use crate::http_client::{HttpClientError, MatrixHttpClient};
+use crate::store::{QueuedEvent, StateStore, StoreError};
+
+/// First delay before retrying a transient error
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Longest delay between retries
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Sends queued events to the homeserver
+#[async_trait]
+pub trait EventTransport: Send + Sync {
+    /// Send an event with `PUT /rooms/{roomId}/send/{eventType}/{txnId}`
+    async fn send_event(
+        &self,
+        room_id: &str,
+        event_type: &str,
+        content: &Value,
+        transaction_id: &str,
+    ) -> Result<String, HttpClientError>;
+}
+
+#[async_trait]
+impl EventTransport for MatrixHttpClient {
+    async fn send_event(
+        &self,
+        room_id: &str,
+        event_type: &str,
+        content: &Value,
+        transaction_id: &str,
+    ) -> Result<String, HttpClientError> {
+        #[derive(Deserialize)]
+        struct SendEventResponse {
+            event_id: String,
+        }
+
+        let path = format!(
+            "/_matrix/client/v3/rooms/{}/send/{}/{}",
+            urlencoding::encode(room_id),
+            urlencoding::encode(event_type),
+            urlencoding::encode(transaction_id)
+        );
+        let response: SendEventResponse = self.put(&path, content).await?;
+        Ok(response.event_id)
+    }
+}
+
+#[async_trait]
+impl EventTransport for MatrixClient {
+    /// Events to encrypted rooms go out through `send_encrypted_event_with_txn_id`
+    async fn send_event(
+        &self,
+        room_id: &str,
+        event_type: &str,
+        content: &Value,
+        transaction_id: &str,
+    ) -> Result<String, HttpClientError> {
+        MatrixClient::send_event(self, room_id, event_type, content.clone(), transaction_id)
+            .await
+            .map_err(transport_error)
+    }
+}
+
+/// Keep request failures retryable; anything else failed while encrypting
+fn transport_error(error: anyhow::Error) -> HttpClientError {
+    match error.downcast::<HttpClientError>() {
+        Ok(error) => error,
+        Err(error) => match error.downcast::<reqwest::Error>() {
+            Ok(error) => HttpClientError::Network(error),
+            Err(error) => HttpClientError::Encryption(error.to_string()),
+        },
+    }
+}
+
+/// Progress of the send queue
+#[derive(Debug, Clone, PartialEq)]
+pub enum SendQueueUpdate {
+    Queued {
+        room_id: String,
+        transaction_id: String,
+    },
+    Sending {
+        room_id: String,
+        transaction_id: String,
+    },
+    /// Sending failed and is tried again after `delay`
+    Retrying {
+        room_id: String,
+        transaction_id: String,
+        error: String,
+        delay: Duration,
+    },
+    Sent {
+        room_id: String,
+        transaction_id: String,
+        event_id: String,
+    },
+    /// Sending failed for good; the room waits for a retry or cancel
+    Failed {
+        room_id: String,
+        transaction_id: String,
+        error: String,
+    },
+    Edited {
+        room_id: String,
+        transaction_id: String,
+    },
+    Cancelled {
+        room_id: String,
+        transaction_id: String,
+    },
+    /// The queue was paused or resumed
+    Connectivity {
+        online: bool,
+    },
+}
+
+/// Per-room queue of events to send
+#[derive(Clone)]
+pub struct SendQueue {
+    inner: Arc<SendQueueInner>,
+}
+
+struct SendQueueInner {
+    transport: Arc<dyn EventTransport>,
+    store: Option<Arc<dyn StateStore>>,
+    rooms: Mutex<HashMap<String, RoomQueue>>,
+    online: watch::Sender<bool>,
+    update_sender: broadcast::Sender<SendQueueUpdate>,
+    next_position: AtomicU64,
+}
+
+#[derive(Default)]
+struct RoomQueue {
+    events: VecDeque<QueuedEvent>,
+    /// Transaction ID of the event on its way to the homeserver
+    sending: Option<String>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl std::fmt::Debug for SendQueue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SendQueue")
+            .field("online", &*self.inner.online.borrow())
+            .finish_non_exhaustive()
+    }
+}
+
+impl SendQueue {
+    /// Create an empty queue, online, that persists to `store` if given
+    pub fn new(transport: Arc<dyn EventTransport>, store: Option<Arc<dyn StateStore>>) -> Self {
+        let (update_sender, _) = broadcast::channel(1000);
+        Self {
+            inner: Arc::new(SendQueueInner {
+                transport,
+                store,
+                rooms: Mutex::new(HashMap::new()),
+                online: watch::Sender::new(true),
+                update_sender,
+                next_position: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Load the events left in the store and start sending them
+    pub async fn load(&self) -> Result<(), StoreError> {
+        let Some(store) = &self.inner.store else {
+            return Ok(());
+        };
+        let events = store.queued_events().await?;
+
+        let mut rooms = self.inner.rooms.lock().await;
+        for event in events {
+            self.inner.next_position.fetch_max(event.position + 1, Ordering::SeqCst);
+            let room = rooms.entry(event.room_id.clone()).or_default();
+            if !room
+                .events
+                .iter()
+                .any(|queued| queued.transaction_id == event.transaction_id)
+            {
+                room.events.push_back(event);
+            }
+        }
+        let room_ids: Vec<String> = rooms.keys().cloned().collect();
+        for room_id in room_ids {
+            self.inner.start_worker(&mut rooms, &room_id);
+        }
+        Ok(())
+    }
+
+    /// Queue an event, returning its transaction ID
+    pub async fn enqueue(
+        &self,
+        room_id: &str,
+        event_type: &str,
+        content: Value,
+    ) -> Result<String, StoreError> {
+        let event = QueuedEvent {
+            room_id: room_id.to_string(),
+            transaction_id: uuid::Uuid::new_v4().to_string(),
+            event_type: event_type.to_string(),
+            content,
+            position: self.inner.next_position.fetch_add(1, Ordering::SeqCst),
+            error: None,
+        };
+        if let Some(store) = &self.inner.store {
+            store.save_queued_event(&event).await?;
+        }
+
+        let transaction_id = event.transaction_id.clone();
+        let mut rooms = self.inner.rooms.lock().await;
+        rooms.entry(room_id.to_string()).or_default().events.push_back(event);
+        self.inner.notify(SendQueueUpdate::Queued {
+            room_id: room_id.to_string(),
+            transaction_id: transaction_id.clone(),
+        });
+        self.inner.start_worker(&mut rooms, room_id);
+        Ok(transaction_id)
+    }
+
+    /// Queue an event and wait until the homeserver accepted it
+    ///
+    /// An event that fails for good is cancelled, so it does not hold up
+    /// the events after it.
+    pub async fn send(
+        &self,
+        room_id: &str,
+        event_type: &str,
+        content: Value,
+    ) -> anyhow::Result<String> {
+        // Subscribe before queueing so the outcome cannot be missed
+        let mut updates = self.subscribe();
+        let transaction_id = self.enqueue(room_id, event_type, content).await?;
+
+        loop {
+            match updates.recv().await {
+                Ok(SendQueueUpdate::Sent { transaction_id: sent, event_id, .. })
+                    if sent == transaction_id =>
+                {
+                    return Ok(event_id);
+                },
+                Ok(SendQueueUpdate::Failed { transaction_id: failed, error, .. })
+                    if failed == transaction_id =>
+                {
+                    self.cancel(room_id, &transaction_id).await?;
+                    return Err(anyhow::anyhow!("Failed to send {}: {}", event_type, error));
+                },
+                Ok(SendQueueUpdate::Cancelled { transaction_id: cancelled, .. })
+                    if cancelled == transaction_id =>
+                {
+                    return Err(anyhow::anyhow!("Sending {} was cancelled", event_type));
+                },
+                Ok(_) => {},
+                Err(broadcast::error::RecvError::Lagged(_)) => {
+                    let queued = self.queued(room_id).await;
+                    if !queued.iter().any(|event| event.transaction_id == transaction_id) {
+                        return Err(anyhow::anyhow!(
+                            "Lost track of {} {} in the send queue",
+                            event_type,
+                            transaction_id
+                        ));
+                    }
+                },
+                Err(broadcast::error::RecvError::Closed) => {
+                    return Err(anyhow::anyhow!("Send queue stopped"));
+                },
+            }
+        }
+    }
+
+    /// Replace the content of a queued event
+    ///
+    /// Returns false if the event is being sent or no longer queued.
+    pub async fn edit(
+        &self,
+        room_id: &str,
+        transaction_id: &str,
+        content: Value,
+    ) -> Result<bool, StoreError> {
+        let mut rooms = self.inner.rooms.lock().await;
+        let Some(room) = rooms.get_mut(room_id) else {
+            return Ok(false);
+        };
+        if room.sending.as_deref() == Some(transaction_id) {
+            return Ok(false);
+        }
+        let Some(event) =
+            room.events.iter_mut().find(|event| event.transaction_id == transaction_id)
+        else {
+            return Ok(false);
+        };
+
+        event.content = content;
+        if let Some(store) = &self.inner.store {
+            store.save_queued_event(event).await?;
+        }
+        self.inner.notify(SendQueueUpdate::Edited {
+            room_id: room_id.to_string(),
+            transaction_id: transaction_id.to_string(),
+        });
+        Ok(true)
+    }
+
+    /// Drop a queued event
+    ///
+    /// Returns false if the event is being sent or no longer queued.
+    pub async fn cancel(&self, room_id: &str, transaction_id: &str) -> Result<bool, StoreError> {
+        let mut rooms = self.inner.rooms.lock().await;
+        let Some(room) = rooms.get_mut(room_id) else {
+            return Ok(false);
+        };
+        if room.sending.as_deref() == Some(transaction_id) {
+            return Ok(false);
+        }
+        let Some(index) =
+            room.events.iter().position(|event| event.transaction_id == transaction_id)
+        else {
+            return Ok(false);
+        };
+
+        room.events.remove(index);
+        if let Some(store) = &self.inner.store {
+            store.remove_queued_event(room_id, transaction_id).await?;
+        }
+        self.inner.notify(SendQueueUpdate::Cancelled {
+            room_id: room_id.to_string(),
+            transaction_id: transaction_id.to_string(),
+        });
+        // Cancelling a failed event unblocks the events after it
+        self.inner.start_worker(&mut rooms, room_id);
+        Ok(true)
+    }
+
+    /// Try again to send the event that failed in a room
+    pub async fn retry(&self, room_id: &str) -> Result<(), StoreError> {
+        let mut rooms = self.inner.rooms.lock().await;
+        let Some(event) = rooms.get_mut(room_id).and_then(|room| room.events.front_mut()) else {
+            return Ok(());
+        };
+        if event.error.take().is_some()
+            && let Some(store) = &self.inner.store
+        {
+            store.save_queued_event(event).await?;
+        }
+        self.inner.start_worker(&mut rooms, room_id);
+        Ok(())
+    }
+
+    /// Events queued for a room, in sending order
+    pub async fn queued(&self, room_id: &str) -> Vec<QueuedEvent> {
+        let rooms = self.inner.rooms.lock().await;
+        rooms
+            .get(room_id)
+            .map(|room| room.events.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Pause sending while offline, resume when back online
+    pub fn set_online(&self, online: bool) {
+        if self
+            .inner
+            .online
+            .send_if_modified(|current| std::mem::replace(current, online) != online)
+        {
+            debug!("Send queue is {}", if online { "online" } else { "offline" });
+            self.inner.notify(SendQueueUpdate::Connectivity { online });
+        }
+    }
+
+    pub fn is_online(&self) -> bool {
+        *self.inner.online.borrow()
+    }
+
+    /// Subscribe to the progress of queued events
+    pub fn subscribe(&self) -> broadcast::Receiver<SendQueueUpdate> {
+        self.inner.update_sender.subscribe()
+    }
+
+    /// Stop sending; queued events stay in the store
+    pub async fn stop(&self) {
+        let mut rooms = self.inner.rooms.lock().await;
+        for room in rooms.values_mut() {
+            if let Some(worker) = room.worker.take() {
+                worker.abort();
+            }
+        }
+        rooms.clear();
+    }
+}
+
+impl SendQueueInner {
+    fn notify(&self, update: SendQueueUpdate) {
+        // Nobody listening is fine
+        let _ = self.update_sender.send(update);
+    }
+
+    fn start_worker(self: &Arc<Self>, rooms: &mut HashMap<String, RoomQueue>, room_id: &str) {
+        let Some(room) = rooms.get_mut(room_id) else {
+            return;
+        };
+        if room.worker.is_none() && !room.events.is_empty() {
+            room.worker = Some(tokio::spawn(self.clone().run_room(room_id.to_string())));
+        }
+    }
+
+    /// Send the events of a room one after the other
+    ///
+    /// Exits when the room is empty or its first event failed for good.
+    async fn run_room(self: Arc<Self>, room_id: String) {
+        let mut online = self.online.subscribe();
+        let mut backoff = INITIAL_BACKOFF;
+
+        loop {
+            // Only fails once the queue is dropped
+            if online.wait_for(|online| *online).await.is_err() {
+                return;
+            }
+
+            let event = {
+                let mut rooms = self.rooms.lock().await;
+                let Some(room) = rooms.get_mut(&room_id) else {
+                    return;
+                };
+                match room.events.front() {
+                    Some(event) if event.error.is_none() => {
+                        room.sending = Some(event.transaction_id.clone());
+                        event.clone()
+                    },
+                    _ => {
+                        room.worker = None;
+                        return;
+                    },
+                }
+            };
+            let room_id = event.room_id.clone();
+            let transaction_id = event.transaction_id.clone();
+
+            self.notify(SendQueueUpdate::Sending {
+                room_id: room_id.clone(),
+                transaction_id: transaction_id.clone(),
+            });
+            let result = self
+                .transport
+                .send_event(&room_id, &event.event_type, &event.content, &transaction_id)
+                .await;
+
+            let mut rooms = self.rooms.lock().await;
+            let Some(room) = rooms.get_mut(&room_id) else {
+                return;
+            };
+            room.sending = None;
+
+            match result {
+                Ok(event_id) => {
+                    room.events.pop_front();
+                    backoff = INITIAL_BACKOFF;
+                    if let Some(store) = &self.store
+                        && let Err(e) = store.remove_queued_event(&room_id, &transaction_id).await
+                    {
+                        warn!("Failed to remove sent event from the store: {}", e);
+                    }
+                    self.notify(SendQueueUpdate::Sent { room_id, transaction_id, event_id });
+                },
+                Err(e) if e.is_retryable() => {
+                    drop(rooms);
+                    let delay = match &e {
+                        HttpClientError::Matrix { retry_after_ms: Some(_), .. } => {
+                            e.retry_delay().unwrap_or(backoff)
+                        },
+                        _ => backoff,
+                    };
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                    debug!("Retrying {} in {} after {:?}: {}", transaction_id, room_id, delay, e);
+                    self.notify(SendQueueUpdate::Retrying {
+                        room_id,
+                        transaction_id,
+                        error: e.to_string(),
+                        delay,
+                    });
+                    tokio::time::sleep(delay).await;
+                },
+                Err(e) => {
+                    let error = e.to_string();
+                    if let Some(event) = room.events.front_mut() {
+                        event.error = Some(error.clone());
+                        if let Some(store) = &self.store
+                            && let Err(e) = store.save_queued_event(event).await
+                        {
+                            warn!("Failed to save failed event to the store: {}", e);
+                        }
+                    }
+                    room.worker = None;
+                    self.notify(SendQueueUpdate::Failed { room_id, transaction_id, error });
+                    return;
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryStateStore;
+    use serde_json::json;
+
+    /// Answers with the queued results, then with success
+    #[derive(Default)]
+    struct MockTransport {
+        results: std::sync::Mutex<VecDeque<Result<String, HttpClientError>>>,
+        /// (transaction ID, body) of every request
+        requests: std::sync::Mutex<Vec<(String, String)>>,
+    }
+
+    #[async_trait]
+    impl EventTransport for MockTransport {
+        async fn send_event(
+            &self,
+            _room_id: &str,
+            _event_type: &str,
+            content: &Value,
+            transaction_id: &str,
+        ) -> Result<String, HttpClientError> {
+            let body = content["body"].as_str().unwrap_or_default().to_string();
+            self.requests
+                .lock()
+                .expect("lock")
+                .push((transaction_id.to_string(), body.clone()));
+            let result = self.results.lock().expect("lock").pop_front();
+            result.unwrap_or(Ok(format!("${}", body)))
+        }
+    }
+
+    fn matrix_error(status: u16, errcode: &str, retry_after_ms: Option<u64>) -> HttpClientError {
+        HttpClientError::Matrix {
+            status,
+            errcode: errcode.to_string(),
+            error: errcode.to_string(),
+            retry_after_ms,
+        }
+    }
+
+    async fn wait_for(
+        updates: &mut broadcast::Receiver<SendQueueUpdate>,
+        matches: impl Fn(&SendQueueUpdate) -> bool,
+    ) -> SendQueueUpdate {
+        loop {
+            let update = updates.recv().await.expect("queue should keep sending updates");
+            if matches(&update) {
+                return update;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retries_keep_transaction_id_and_order() {
+        let transport = Arc::new(MockTransport::default());
+        transport.results.lock().expect("lock").extend([
+            Err(matrix_error(429, "M_LIMIT_EXCEEDED", Some(10))),
+            Err(matrix_error(502, "M_UNKNOWN", None)),
+        ]);
+        let store = Arc::new(MemoryStateStore::new());
+        let queue = SendQueue::new(transport.clone(), Some(store.clone()));
+        let mut updates = queue.subscribe();
+
+        let first = queue.enqueue("!room:example.org", "m.room.message", json!({ "body": "one" }));
+        let first = first.await.expect("event should queue");
+        let second = queue.enqueue("!room:example.org", "m.room.message", json!({ "body": "two" }));
+        let second = second.await.expect("event should queue");
+
+        let update = wait_for(&mut updates, |update| {
+            matches!(update, SendQueueUpdate::Sent { transaction_id, .. } if *transaction_id == second)
+        })
+        .await;
+        assert_eq!(
+            update,
+            SendQueueUpdate::Sent {
+                room_id: "!room:example.org".to_string(),
+                transaction_id: second.clone(),
+                event_id: "$two".to_string(),
+            }
+        );
+
+        let requests = transport.requests.lock().expect("lock").clone();
+        let expected: Vec<(String, String)> = [
+            (&first, "one"),
+            (&first, "one"),
+            (&first, "one"),
+            (&second, "two"),
+        ]
+        .iter()
+        .map(|(txn_id, body)| (txn_id.to_string(), body.to_string()))
+        .collect();
+        assert_eq!(requests, expected);
+        assert!(queue.queued("!room:example.org").await.is_empty());
+        assert!(store.queued_events().await.expect("queue should load").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failure_blocks_room_until_cancelled() {
+        let transport = Arc::new(MockTransport::default());
+        transport.results.lock().expect("lock").push_back(Err(matrix_error(
+            403,
+            "M_FORBIDDEN",
+            None,
+        )));
+        let store = Arc::new(MemoryStateStore::new());
+        let queue = SendQueue::new(transport.clone(), Some(store.clone()));
+        let mut updates = queue.subscribe();
+
+        let room_id = "!room:example.org";
+        let failing = queue.enqueue(room_id, "m.room.message", json!({ "body": "one" }));
+        let failing = failing.await.expect("event should queue");
+        wait_for(&mut updates, |update| matches!(update, SendQueueUpdate::Failed { .. })).await;
+
+        queue.set_online(false);
+        let later = queue.enqueue(room_id, "m.room.message", json!({ "body": "two" }));
+        let later = later.await.expect("event should queue");
+        assert!(
+            queue
+                .edit(room_id, &later, json!({ "body": "edited" }))
+                .await
+                .expect("edit")
+        );
+
+        // A restarted queue picks up both events where they were
+        let restored = SendQueue::new(transport.clone(), Some(store.clone()));
+        restored.set_online(false);
+        restored.load().await.expect("queue should load");
+        let queued = restored.queued(room_id).await;
+        assert_eq!(queued.len(), 2);
+        assert!(queued[0].error.is_some());
+        assert_eq!(queued[1].content["body"], "edited");
+        queue.stop().await;
+
+        let mut updates = restored.subscribe();
+        restored.set_online(true);
+        assert!(restored.cancel(room_id, &failing).await.expect("cancel"));
+        wait_for(&mut updates, |update| matches!(update, SendQueueUpdate::Sent { .. })).await;
+
+        let requests = transport.requests.lock().expect("lock").clone();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1], (later, "edited".to_string()));
+        assert!(store.queued_events().await.expect("queue should load").is_empty());
+    }
+}
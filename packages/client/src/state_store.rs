@@ -0,0 +1,81 @@
+//! Persistent state for the Matrix client
+//!
+//! Saves every sync response to the configured [`StateStore`] and restores
+//! the sync token from it, so a restarted client continues with incremental
+//! syncs. Rooms, members, state and the recent timeline can be read from the
+//! store without contacting the homeserver.
+
+use anyhow::Result;
+use matryx_entity::Event;
+use std::sync::Arc;
+use tracing::debug;
+
+use crate::store::{RoomMember, StateChanges, StateStore, StoredRoom, StoredTimeline};
+use crate::{MatrixClient, SyncResponse};
+
+/// State store functionality
+impl MatrixClient {
+    /// Persist client state in `store`
+    ///
+    /// If the store holds a sync token, the next [`Self::sync_once`] resumes
+    /// from it instead of doing an initial sync.
+    pub async fn set_state_store(&mut self, store: Arc<dyn StateStore>) -> Result<()> {
+        if let Some(token) = store.sync_token().await? {
+            debug!("Resuming sync from stored token {}", token);
+            self.state.write().await.next_batch = Some(token);
+        }
+        self.store = Some(store);
+        self.reset_send_queue();
+        Ok(())
+    }
+
+    /// Get the state store (if one is set)
+    pub fn state_store(&self) -> Option<Arc<dyn StateStore>> {
+        self.store.clone()
+    }
+
+    /// Sync from the last known token, or do an initial sync without one
+    pub async fn sync_once(&mut self, timeout: Option<u64>) -> Result<SyncResponse> {
+        let since = self.state.read().await.next_batch.clone();
+        self.sync(since.as_deref(), timeout).await
+    }
+
+    /// Rooms we have been in, with our membership
+    pub async fn rooms(&self) -> Result<Vec<StoredRoom>> {
+        Ok(self.require_state_store()?.rooms().await?)
+    }
+
+    /// Members of a room, from its stored `m.room.member` state
+    pub async fn room_members(&self, room_id: &str) -> Result<Vec<RoomMember>> {
+        Ok(self.require_state_store()?.members(room_id).await?)
+    }
+
+    /// A stored state event of a room
+    pub async fn room_state_event(
+        &self,
+        room_id: &str,
+        event_type: &str,
+        state_key: &str,
+    ) -> Result<Option<Event>> {
+        Ok(self
+            .require_state_store()?
+            .state_event(room_id, event_type, state_key)
+            .await?)
+    }
+
+    /// Recent timeline events of a room, as far as they are cached
+    pub async fn room_timeline(&self, room_id: &str) -> Result<StoredTimeline> {
+        Ok(self.require_state_store()?.timeline(room_id).await?)
+    }
+
+    pub(crate) async fn save_sync_response(&self, response: &SyncResponse) -> Result<()> {
+        if let Some(store) = &self.store {
+            store.save_changes(&StateChanges::from_sync_response(response)).await?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn require_state_store(&self) -> Result<Arc<dyn StateStore>> {
+        self.store.clone().ok_or_else(|| anyhow::anyhow!("No state store is set"))
+    }
+}
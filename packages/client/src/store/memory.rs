@@ -0,0 +1,288 @@
+//! In-memory state store
+
+use async_trait::async_trait;
+use matryx_entity::{Event, MembershipState};
+use serde_json::Value;
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+
+use super::{
+    DEFAULT_TIMELINE_LIMIT, QueuedEvent, Receipt, StateChanges, StateStore, StoreError, StoredRoom,
+    StoredTimeline,
+};
+
+/// State store that lives as long as the process
+#[derive(Debug)]
+pub struct MemoryStateStore {
+    inner: RwLock<MemoryState>,
+    timeline_limit: usize,
+}
+
+/// (user ID, receipt type, thread ID) of a receipt
+type ReceiptKey = (String, String, Option<String>);
+
+#[derive(Debug, Default)]
+struct MemoryState {
+    sync_token: Option<String>,
+    rooms: HashMap<String, MembershipState>,
+    /// Room ID -> (event type, state key) -> event
+    state: HashMap<String, HashMap<(String, String), Event>>,
+    account_data: HashMap<String, Value>,
+    room_account_data: HashMap<String, HashMap<String, Value>>,
+    receipts: HashMap<String, HashMap<ReceiptKey, Receipt>>,
+    timelines: HashMap<String, StoredTimeline>,
+    /// (room ID, transaction ID) -> queued event
+    send_queue: HashMap<(String, String), QueuedEvent>,
+}
+
+impl Default for MemoryStateStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryStateStore {
+    pub fn new() -> Self {
+        Self::with_timeline_limit(DEFAULT_TIMELINE_LIMIT)
+    }
+
+    /// Keep at most `timeline_limit` timeline events per room
+    pub fn with_timeline_limit(timeline_limit: usize) -> Self {
+        Self {
+            inner: RwLock::new(MemoryState::default()),
+            timeline_limit,
+        }
+    }
+}
+
+#[async_trait]
+impl StateStore for MemoryStateStore {
+    async fn save_changes(&self, changes: &StateChanges) -> Result<(), StoreError> {
+        let mut inner = self.inner.write().await;
+
+        for (room_id, membership) in &changes.rooms {
+            inner.rooms.insert(room_id.clone(), membership.clone());
+        }
+
+        for (room_id, events) in &changes.state {
+            let room_state = inner.state.entry(room_id.clone()).or_default();
+            for event in events {
+                if let Some(state_key) = &event.state_key {
+                    room_state.insert((event.event_type.clone(), state_key.clone()), event.clone());
+                }
+            }
+        }
+
+        inner.account_data.extend(changes.account_data.clone());
+        for (room_id, account_data) in &changes.room_account_data {
+            inner
+                .room_account_data
+                .entry(room_id.clone())
+                .or_default()
+                .extend(account_data.clone());
+        }
+
+        for (room_id, receipts) in &changes.receipts {
+            let room_receipts = inner.receipts.entry(room_id.clone()).or_default();
+            for receipt in receipts {
+                let key = (
+                    receipt.user_id.clone(),
+                    receipt.receipt_type.clone(),
+                    receipt.thread_id.clone(),
+                );
+                room_receipts.insert(key, receipt.clone());
+            }
+        }
+
+        for (room_id, chunk) in &changes.timelines {
+            inner
+                .timelines
+                .entry(room_id.clone())
+                .or_default()
+                .extend(chunk, self.timeline_limit);
+        }
+
+        if let Some(token) = &changes.sync_token {
+            inner.sync_token = Some(token.clone());
+        }
+
+        Ok(())
+    }
+
+    async fn sync_token(&self) -> Result<Option<String>, StoreError> {
+        Ok(self.inner.read().await.sync_token.clone())
+    }
+
+    async fn rooms(&self) -> Result<Vec<StoredRoom>, StoreError> {
+        Ok(self
+            .inner
+            .read()
+            .await
+            .rooms
+            .iter()
+            .map(|(room_id, membership)| StoredRoom {
+                room_id: room_id.clone(),
+                membership: membership.clone(),
+            })
+            .collect())
+    }
+
+    async fn room(&self, room_id: &str) -> Result<Option<StoredRoom>, StoreError> {
+        Ok(self.inner.read().await.rooms.get(room_id).map(|membership| StoredRoom {
+            room_id: room_id.to_string(),
+            membership: membership.clone(),
+        }))
+    }
+
+    async fn state_event(
+        &self,
+        room_id: &str,
+        event_type: &str,
+        state_key: &str,
+    ) -> Result<Option<Event>, StoreError> {
+        let inner = self.inner.read().await;
+        Ok(inner
+            .state
+            .get(room_id)
+            .and_then(|state| state.get(&(event_type.to_string(), state_key.to_string())))
+            .cloned())
+    }
+
+    async fn state_events(
+        &self,
+        room_id: &str,
+        event_type: &str,
+    ) -> Result<Vec<Event>, StoreError> {
+        let inner = self.inner.read().await;
+        Ok(inner
+            .state
+            .get(room_id)
+            .into_iter()
+            .flatten()
+            .filter(|((stored_type, _), _)| stored_type == event_type)
+            .map(|(_, event)| event.clone())
+            .collect())
+    }
+
+    async fn account_data(&self, event_type: &str) -> Result<Option<Value>, StoreError> {
+        Ok(self.inner.read().await.account_data.get(event_type).cloned())
+    }
+
+    async fn room_account_data(
+        &self,
+        room_id: &str,
+        event_type: &str,
+    ) -> Result<Option<Value>, StoreError> {
+        let inner = self.inner.read().await;
+        Ok(inner
+            .room_account_data
+            .get(room_id)
+            .and_then(|account_data| account_data.get(event_type))
+            .cloned())
+    }
+
+    async fn receipts(&self, room_id: &str) -> Result<Vec<Receipt>, StoreError> {
+        let inner = self.inner.read().await;
+        Ok(inner
+            .receipts
+            .get(room_id)
+            .into_iter()
+            .flat_map(|r| r.values().cloned())
+            .collect())
+    }
+
+    async fn timeline(&self, room_id: &str) -> Result<StoredTimeline, StoreError> {
+        Ok(self.inner.read().await.timelines.get(room_id).cloned().unwrap_or_default())
+    }
+
+    async fn save_queued_event(&self, event: &QueuedEvent) -> Result<(), StoreError> {
+        let key = (event.room_id.clone(), event.transaction_id.clone());
+        self.inner.write().await.send_queue.insert(key, event.clone());
+        Ok(())
+    }
+
+    async fn queued_events(&self) -> Result<Vec<QueuedEvent>, StoreError> {
+        let mut events: Vec<QueuedEvent> =
+            self.inner.read().await.send_queue.values().cloned().collect();
+        events.sort_by_key(|event| event.position);
+        Ok(events)
+    }
+
+    async fn remove_queued_event(
+        &self,
+        room_id: &str,
+        transaction_id: &str,
+    ) -> Result<(), StoreError> {
+        let key = (room_id.to_string(), transaction_id.to_string());
+        self.inner.write().await.send_queue.remove(&key);
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<(), StoreError> {
+        *self.inner.write().await = MemoryState::default();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::tests::{event, sync_response};
+
+    #[tokio::test]
+    async fn test_save_and_query_sync() {
+        let store = MemoryStateStore::with_timeline_limit(2);
+        assert_eq!(store.sync_token().await.expect("token should load"), None);
+
+        let changes = StateChanges::from_sync_response(&sync_response());
+        store.save_changes(&changes).await.expect("changes should save");
+        store.save_changes(&changes).await.expect("changes should save again");
+
+        assert_eq!(store.sync_token().await.expect("token should load").as_deref(), Some("s2"));
+        assert_eq!(store.rooms().await.expect("rooms should load").len(), 2);
+        let room = store.room("!invite:example.org").await.expect("room should load");
+        assert_eq!(room.map(|room| room.membership), Some(MembershipState::Invite));
+
+        let create = store
+            .state_event("!room:example.org", "m.room.create", "")
+            .await
+            .expect("state should load");
+        assert_eq!(create.map(|event| event.event_id).as_deref(), Some("$create"));
+
+        let members = store.members("!room:example.org").await.expect("members should load");
+        assert_eq!(members.len(), 1);
+        assert_eq!(members[0].user_id, "@alice:example.org");
+
+        assert!(store.account_data("m.direct").await.expect("account data").is_some());
+        assert!(
+            store
+                .room_account_data("!room:example.org", "m.tag")
+                .await
+                .expect("room account data")
+                .is_some()
+        );
+        assert_eq!(store.receipts("!room:example.org").await.expect("receipts").len(), 1);
+
+        let timeline = store.timeline("!room:example.org").await.expect("timeline should load");
+        assert_eq!(timeline.events.len(), 2);
+        assert_eq!(timeline.events[1].event_id, "$message");
+
+        let mut changes = StateChanges::default();
+        changes.state.insert(
+            "!room:example.org".to_string(),
+            vec![event(
+                "$rename",
+                "m.room.member",
+                Some("@alice:example.org"),
+            )],
+        );
+        store.save_changes(&changes).await.expect("changes should save");
+        let members = store.members("!room:example.org").await.expect("members should load");
+        assert_eq!(members.len(), 1);
+        assert_eq!(store.sync_token().await.expect("token").as_deref(), Some("s2"));
+
+        store.clear().await.expect("store should clear");
+        assert_eq!(store.sync_token().await.expect("token should load"), None);
+        assert!(store.rooms().await.expect("rooms should load").is_empty());
+    }
+}
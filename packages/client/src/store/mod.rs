@@ -0,0 +1,478 @@
+//! Persistent client state
+//!
+//! A [`StateStore`] keeps what the client learned from `/sync` between runs:
+//! the `next_batch` token, room state (and the member lists derived from
+//! it), account data, read receipts and a bounded cache of recent timeline
+//! events. A restarted client resumes incremental sync from the stored token
+//! and can answer room and member queries without the network.
+//!
+//! [`MemoryStateStore`] is lost with the process, [`SurrealKvStateStore`]
+//! persists to an embedded SurrealKV database.
+
+pub mod memory;
+pub mod surrealkv;
+
+pub use memory::MemoryStateStore;
+pub use surrealkv::SurrealKvStateStore;
+
+use async_trait::async_trait;
+use matryx_entity::{Event, MembershipState};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::SyncResponse;
+
+/// Timeline events kept per room unless configured otherwise
+pub const DEFAULT_TIMELINE_LIMIT: usize = 100;
+
+const MEMBER_EVENT_TYPE: &str = "m.room.member";
+const RECEIPT_EVENT_TYPE: &str = "m.receipt";
+
+#[derive(Debug, thiserror::Error)]
+pub enum StoreError {
+    #[error("Database error: {0}")]
+    Database(String),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+/// Storage backend for client state
+///
+/// Implementations must apply [`StateChanges`] so that the sync token is
+/// written last: a sync interrupted while saving is fetched again, and
+/// re-applying it is harmless.
+#[async_trait]
+pub trait StateStore: std::fmt::Debug + Send + Sync {
+    /// Persist everything learned from one sync response
+    async fn save_changes(&self, changes: &StateChanges) -> Result<(), StoreError>;
+
+    /// The `next_batch` token of the last saved sync
+    async fn sync_token(&self) -> Result<Option<String>, StoreError>;
+
+    /// Every room we have been in, with our membership
+    async fn rooms(&self) -> Result<Vec<StoredRoom>, StoreError>;
+
+    /// Our membership of a room, if we know it
+    async fn room(&self, room_id: &str) -> Result<Option<StoredRoom>, StoreError>;
+
+    /// The current state event of a room for `event_type` and `state_key`
+    async fn state_event(
+        &self,
+        room_id: &str,
+        event_type: &str,
+        state_key: &str,
+    ) -> Result<Option<Event>, StoreError>;
+
+    /// The current state events of a room with the given type
+    async fn state_events(&self, room_id: &str, event_type: &str)
+    -> Result<Vec<Event>, StoreError>;
+
+    /// Global account data content of the given type
+    async fn account_data(&self, event_type: &str) -> Result<Option<Value>, StoreError>;
+
+    /// Room account data content of the given type
+    async fn room_account_data(
+        &self,
+        room_id: &str,
+        event_type: &str,
+    ) -> Result<Option<Value>, StoreError>;
+
+    /// The latest receipt of each user, type and thread in a room
+    async fn receipts(&self, room_id: &str) -> Result<Vec<Receipt>, StoreError>;
+
+    /// The cached recent timeline of a room
+    async fn timeline(&self, room_id: &str) -> Result<StoredTimeline, StoreError>;
+
+    /// Save an event of the send queue, replacing the one with its transaction ID
+    async fn save_queued_event(&self, event: &QueuedEvent) -> Result<(), StoreError>;
+
+    /// Events of the send queue in all rooms, in queue order
+    async fn queued_events(&self) -> Result<Vec<QueuedEvent>, StoreError>;
+
+    /// Drop an event from the send queue
+    async fn remove_queued_event(
+        &self,
+        room_id: &str,
+        transaction_id: &str,
+    ) -> Result<(), StoreError>;
+
+    /// Forget everything, e.g. after logging out
+    async fn clear(&self) -> Result<(), StoreError>;
+
+    /// Members of a room, from its `m.room.member` state
+    async fn members(&self, room_id: &str) -> Result<Vec<RoomMember>, StoreError> {
+        Ok(self
+            .state_events(room_id, MEMBER_EVENT_TYPE)
+            .await?
+            .iter()
+            .filter_map(RoomMember::from_event)
+            .collect())
+    }
+}
+
+/// A room and our membership of it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredRoom {
+    pub room_id: String,
+    pub membership: MembershipState,
+}
+
+/// A room member, from an `m.room.member` state event
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoomMember {
+    pub user_id: String,
+    pub membership: MembershipState,
+    pub display_name: Option<String>,
+    pub avatar_url: Option<String>,
+}
+
+impl RoomMember {
+    /// Read a member from its state event; `None` if it is not one
+    pub fn from_event(event: &Event) -> Option<Self> {
+        if event.event_type != MEMBER_EVENT_TYPE {
+            return None;
+        }
+        let user_id = event.state_key.clone()?;
+        let content = serde_json::to_value(&event.content).ok()?;
+        let membership = serde_json::from_value(content.get("membership")?.clone()).ok()?;
+        let string_field =
+            |field: &str| content.get(field).and_then(Value::as_str).map(str::to_string);
+
+        Some(Self {
+            user_id,
+            membership,
+            display_name: string_field("displayname"),
+            avatar_url: string_field("avatar_url"),
+        })
+    }
+}
+
+/// A receipt from an `m.receipt` ephemeral event
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Receipt {
+    pub user_id: String,
+    /// `m.read`, `m.read.private`, ...
+    pub receipt_type: String,
+    pub event_id: String,
+    pub ts: Option<i64>,
+    /// Thread the receipt is for; `None` for unthreaded receipts
+    pub thread_id: Option<String>,
+}
+
+/// Recent timeline events of a room, oldest first
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StoredTimeline {
+    pub events: Vec<Event>,
+    /// Token to paginate backwards from the first event, when known
+    pub prev_batch: Option<String>,
+}
+
+impl StoredTimeline {
+    /// Append a sync's timeline, keeping the last `limit` events
+    ///
+    /// A limited timeline leaves a gap, so the cache restarts from it. Once
+    /// events are dropped from the front `prev_batch` no longer matches the
+    /// first event and is cleared. Events already cached are skipped.
+    pub fn extend(&mut self, chunk: &TimelineChunk, limit: usize) {
+        if chunk.limited || self.events.is_empty() {
+            self.events.clear();
+            self.prev_batch = chunk.prev_batch.clone();
+        }
+        for event in &chunk.events {
+            if !self.events.iter().any(|known| known.event_id == event.event_id) {
+                self.events.push(event.clone());
+            }
+        }
+
+        if self.events.len() > limit {
+            self.events.drain(..self.events.len() - limit);
+            self.prev_batch = None;
+        }
+    }
+}
+
+/// An event waiting in the send queue
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueuedEvent {
+    pub room_id: String,
+    /// Kept across retries, so the homeserver sends the event only once
+    pub transaction_id: String,
+    pub event_type: String,
+    pub content: Value,
+    /// Order of the event in the queue
+    pub position: u64,
+    /// Why sending failed for good; the room's queue waits for a retry
+    pub error: Option<String>,
+}
+
+/// Timeline section of one room in a sync response
+#[derive(Debug, Clone, Default)]
+pub struct TimelineChunk {
+    pub events: Vec<Event>,
+    pub limited: bool,
+    pub prev_batch: Option<String>,
+}
+
+/// Everything a sync response changes in the store
+#[derive(Debug, Clone, Default)]
+pub struct StateChanges {
+    /// `next_batch` of the response
+    pub sync_token: Option<String>,
+    /// Our membership per room
+    pub rooms: HashMap<String, MembershipState>,
+    /// State events per room, in order; later events replace earlier ones
+    pub state: HashMap<String, Vec<Event>>,
+    /// Global account data content by type
+    pub account_data: HashMap<String, Value>,
+    /// Room account data content by room and type
+    pub room_account_data: HashMap<String, HashMap<String, Value>>,
+    pub receipts: HashMap<String, Vec<Receipt>>,
+    pub timelines: HashMap<String, TimelineChunk>,
+}
+
+impl StateChanges {
+    /// Collect the changes of a sync response
+    ///
+    /// State events in the timeline update room state after the `state`
+    /// section, as the spec orders them.
+    pub fn from_sync_response(response: &SyncResponse) -> Self {
+        let mut changes = Self {
+            sync_token: Some(response.next_batch.clone()),
+            account_data: account_data_events(response.account_data.as_ref()),
+            ..Self::default()
+        };
+
+        for (room_id, room) in &response.rooms.join {
+            changes.rooms.insert(room_id.clone(), MembershipState::Join);
+            changes.add_room_updates(
+                room_id,
+                room.state.as_ref().map(|state| state.events.as_slice()),
+                room.timeline.as_ref(),
+                room.account_data.as_ref(),
+            );
+
+            let receipts = receipt_events(room.ephemeral.as_ref());
+            if !receipts.is_empty() {
+                changes.receipts.insert(room_id.clone(), receipts);
+            }
+        }
+
+        for (room_id, room) in &response.rooms.invite {
+            changes.rooms.insert(room_id.clone(), MembershipState::Invite);
+            changes.add_room_updates(
+                room_id,
+                room.invite_state.as_ref().map(|state| state.events.as_slice()),
+                None,
+                None,
+            );
+        }
+
+        for (room_id, room) in &response.rooms.leave {
+            changes.rooms.insert(room_id.clone(), MembershipState::Leave);
+            changes.add_room_updates(
+                room_id,
+                room.state.as_ref().map(|state| state.events.as_slice()),
+                room.timeline.as_ref(),
+                room.account_data.as_ref(),
+            );
+        }
+
+        changes
+    }
+
+    fn add_room_updates(
+        &mut self,
+        room_id: &str,
+        state: Option<&[Event]>,
+        timeline: Option<&crate::TimelineUpdates>,
+        account_data: Option<&Value>,
+    ) {
+        let mut state_events: Vec<Event> = state.unwrap_or_default().to_vec();
+
+        if let Some(timeline) = timeline {
+            state_events
+                .extend(timeline.events.iter().filter(|event| event.state_key.is_some()).cloned());
+            self.timelines.insert(
+                room_id.to_string(),
+                TimelineChunk {
+                    events: timeline.events.clone(),
+                    limited: timeline.limited.unwrap_or(false),
+                    prev_batch: timeline.prev_batch.clone(),
+                },
+            );
+        }
+
+        if !state_events.is_empty() {
+            self.state.insert(room_id.to_string(), state_events);
+        }
+
+        let account_data = account_data_events(account_data);
+        if !account_data.is_empty() {
+            self.room_account_data.insert(room_id.to_string(), account_data);
+        }
+    }
+}
+
+/// Content by type of an `account_data` section
+pub(crate) fn account_data_events(section: Option<&Value>) -> HashMap<String, Value> {
+    section_events(section)
+        .filter_map(|event| {
+            let event_type = event.get("type")?.as_str()?;
+            Some((event_type.to_string(), event.get("content")?.clone()))
+        })
+        .collect()
+}
+
+/// Receipts of the `m.receipt` events in an `ephemeral` section
+pub(crate) fn receipt_events(section: Option<&Value>) -> Vec<Receipt> {
+    let mut receipts = Vec::new();
+
+    let contents = section_events(section)
+        .filter(|event| event.get("type").and_then(Value::as_str) == Some(RECEIPT_EVENT_TYPE))
+        .filter_map(|event| event.get("content")?.as_object());
+
+    for content in contents {
+        for (event_id, types) in content {
+            for (receipt_type, users) in types.as_object().into_iter().flatten() {
+                for (user_id, receipt) in users.as_object().into_iter().flatten() {
+                    receipts.push(Receipt {
+                        user_id: user_id.clone(),
+                        receipt_type: receipt_type.clone(),
+                        event_id: event_id.clone(),
+                        ts: receipt.get("ts").and_then(Value::as_i64),
+                        thread_id: receipt
+                            .get("thread_id")
+                            .and_then(Value::as_str)
+                            .map(str::to_string),
+                    });
+                }
+            }
+        }
+    }
+
+    receipts
+}
+
+pub(crate) fn section_events(section: Option<&Value>) -> impl Iterator<Item = &Value> {
+    section
+        .and_then(|section| section.get("events"))
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    pub(super) fn event(event_id: &str, event_type: &str, state_key: Option<&str>) -> Event {
+        let content = match event_type {
+            MEMBER_EVENT_TYPE => json!({ "membership": "join", "displayname": "Alice" }),
+            _ => json!({ "body": event_id }),
+        };
+        serde_json::from_value(json!({
+            "event_id": event_id,
+            "sender": "@alice:example.org",
+            "origin_server_ts": 0,
+            "type": event_type,
+            "room_id": "!room:example.org",
+            "content": content,
+            "state_key": state_key,
+        }))
+        .expect("event should deserialize")
+    }
+
+    pub(super) fn sync_response() -> SyncResponse {
+        serde_json::from_value(json!({
+            "next_batch": "s2",
+            "rooms": {
+                "join": {
+                    "!room:example.org": {
+                        "state": { "events": [event("$create", "m.room.create", Some(""))] },
+                        "timeline": {
+                            "events": [
+                                event("$member", MEMBER_EVENT_TYPE, Some("@alice:example.org")),
+                                event("$message", "m.room.message", None),
+                            ],
+                            "limited": false,
+                            "prev_batch": "p1",
+                        },
+                        "ephemeral": { "events": [{
+                            "type": RECEIPT_EVENT_TYPE,
+                            "content": { "$message": { "m.read": {
+                                "@bob:example.org": { "ts": 5, "thread_id": "main" },
+                            } } },
+                        }] },
+                        "account_data": { "events": [
+                            { "type": "m.tag", "content": { "tags": {} } },
+                        ] },
+                    },
+                },
+                "invite": {
+                    "!invite:example.org": { "invite_state": { "events": [] } },
+                },
+                "leave": {},
+            },
+            "account_data": { "events": [
+                { "type": "m.direct", "content": { "@bob:example.org": [] } },
+            ] },
+        }))
+        .expect("sync response should deserialize")
+    }
+
+    #[test]
+    fn test_changes_from_sync_response() {
+        let changes = StateChanges::from_sync_response(&sync_response());
+
+        assert_eq!(changes.sync_token.as_deref(), Some("s2"));
+        assert_eq!(changes.rooms["!room:example.org"], MembershipState::Join);
+        assert_eq!(changes.rooms["!invite:example.org"], MembershipState::Invite);
+
+        let state: Vec<&str> = changes.state["!room:example.org"]
+            .iter()
+            .map(|e| e.event_id.as_str())
+            .collect();
+        assert_eq!(state, ["$create", "$member"]);
+        assert_eq!(changes.timelines["!room:example.org"].events.len(), 2);
+
+        let receipt = &changes.receipts["!room:example.org"][0];
+        assert_eq!(receipt.user_id, "@bob:example.org");
+        assert_eq!(receipt.event_id, "$message");
+        assert_eq!(receipt.ts, Some(5));
+        assert_eq!(receipt.thread_id.as_deref(), Some("main"));
+
+        assert!(changes.account_data.contains_key("m.direct"));
+        assert!(changes.room_account_data["!room:example.org"].contains_key("m.tag"));
+
+        let member = RoomMember::from_event(&changes.state["!room:example.org"][1])
+            .expect("member event should parse");
+        assert_eq!(member.membership, MembershipState::Join);
+        assert_eq!(member.display_name.as_deref(), Some("Alice"));
+    }
+
+    #[test]
+    fn test_timeline_is_bounded() {
+        let mut timeline = StoredTimeline::default();
+        let chunk = |ids: &[&str], limited: bool| TimelineChunk {
+            events: ids.iter().map(|id| event(id, "m.room.message", None)).collect(),
+            limited,
+            prev_batch: Some(format!("before {}", ids[0])),
+        };
+
+        timeline.extend(&chunk(&["$1", "$2"], false), 3);
+        assert_eq!(timeline.prev_batch.as_deref(), Some("before $1"));
+        timeline.extend(&chunk(&["$3"], false), 3);
+        assert_eq!(timeline.events.len(), 3);
+        assert_eq!(timeline.prev_batch.as_deref(), Some("before $1"));
+
+        timeline.extend(&chunk(&["$4"], false), 3);
+        assert_eq!(timeline.events[0].event_id, "$2");
+        assert_eq!(timeline.prev_batch, None);
+
+        timeline.extend(&chunk(&["$9"], true), 3);
+        assert_eq!(timeline.events.len(), 1);
+        assert_eq!(timeline.prev_batch.as_deref(), Some("before $9"));
+    }
+}
@@ -0,0 +1,325 @@
+//! SurrealKV-backed state store
+//!
+//! Keeps client state in an embedded SurrealKV database, one record per
+//! room, state event, account data event, receipt and room timeline.
+
+use async_trait::async_trait;
+use matryx_entity::Event;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::path::Path;
+use surrealdb::{Surreal, engine::any::Any};
+
+use super::{
+    DEFAULT_TIMELINE_LIMIT, QueuedEvent, Receipt, StateChanges, StateStore, StoreError, StoredRoom,
+    StoredTimeline,
+};
+
+const SYNC_TOKEN_ID: &str = "token";
+
+impl From<surrealdb::Error> for StoreError {
+    fn from(error: surrealdb::Error) -> Self {
+        StoreError::Database(error.to_string())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SyncTokenRecord {
+    token: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StateRecord {
+    room_id: String,
+    event_type: String,
+    event: Event,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AccountDataRecord {
+    room_id: Option<String>,
+    event_type: String,
+    content: Value,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ReceiptRecord {
+    room_id: String,
+    receipt: Receipt,
+}
+
+#[derive(Serialize, Deserialize)]
+struct QueuedEventRecord {
+    room_id: String,
+    event: QueuedEvent,
+}
+
+/// State store persisted in an embedded SurrealKV database
+#[derive(Clone)]
+pub struct SurrealKvStateStore {
+    db: Surreal<Any>,
+    timeline_limit: usize,
+}
+
+impl std::fmt::Debug for SurrealKvStateStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SurrealKvStateStore")
+            .field("timeline_limit", &self.timeline_limit)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SurrealKvStateStore {
+    /// Open or create the store at `path`
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self, StoreError> {
+        let db =
+            surrealdb::engine::any::connect(format!("surrealkv://{}", path.as_ref().display()))
+                .await?;
+        db.use_ns("matryx_client").use_db("state").await?;
+        Ok(Self::new(db))
+    }
+
+    /// Use an already connected database
+    pub fn new(db: Surreal<Any>) -> Self {
+        Self { db, timeline_limit: DEFAULT_TIMELINE_LIMIT }
+    }
+
+    /// Keep at most `timeline_limit` timeline events per room
+    pub fn with_timeline_limit(mut self, timeline_limit: usize) -> Self {
+        self.timeline_limit = timeline_limit;
+        self
+    }
+}
+
+/// Record ID for a composite key, unambiguous whatever the parts contain
+fn record_key(parts: &[&str]) -> String {
+    json!(parts).to_string()
+}
+
+#[async_trait]
+impl StateStore for SurrealKvStateStore {
+    async fn save_changes(&self, changes: &StateChanges) -> Result<(), StoreError> {
+        for (room_id, membership) in &changes.rooms {
+            let room = StoredRoom {
+                room_id: room_id.clone(),
+                membership: membership.clone(),
+            };
+            let _: Option<StoredRoom> =
+                self.db.upsert(("client_room", room_id.as_str())).content(room).await?;
+        }
+
+        for (room_id, events) in &changes.state {
+            for event in events {
+                let Some(state_key) = &event.state_key else {
+                    continue;
+                };
+                let record = StateRecord {
+                    room_id: room_id.clone(),
+                    event_type: event.event_type.clone(),
+                    event: event.clone(),
+                };
+                let key = record_key(&[room_id.as_str(), event.event_type.as_str(), state_key]);
+                let _: Option<StateRecord> =
+                    self.db.upsert(("client_state", key)).content(record).await?;
+            }
+        }
+
+        let global = changes.account_data.iter().map(|data| (None, data));
+        let rooms = changes.room_account_data.iter().flat_map(|(room_id, account_data)| {
+            account_data.iter().map(move |data| (Some(room_id), data))
+        });
+        for (room_id, (event_type, content)) in global.chain(rooms) {
+            let key = record_key(&[
+                room_id.map(String::as_str).unwrap_or(""),
+                event_type.as_str(),
+            ]);
+            let record = AccountDataRecord {
+                room_id: room_id.cloned(),
+                event_type: event_type.clone(),
+                content: content.clone(),
+            };
+            let _: Option<AccountDataRecord> =
+                self.db.upsert(("client_account_data", key)).content(record).await?;
+        }
+
+        for (room_id, receipts) in &changes.receipts {
+            for receipt in receipts {
+                let key = record_key(&[
+                    room_id.as_str(),
+                    receipt.user_id.as_str(),
+                    receipt.receipt_type.as_str(),
+                    receipt.thread_id.as_deref().unwrap_or(""),
+                ]);
+                let record = ReceiptRecord { room_id: room_id.clone(), receipt: receipt.clone() };
+                let _: Option<ReceiptRecord> =
+                    self.db.upsert(("client_receipt", key)).content(record).await?;
+            }
+        }
+
+        for (room_id, chunk) in &changes.timelines {
+            let mut timeline = self.timeline(room_id).await?;
+            timeline.extend(chunk, self.timeline_limit);
+            let _: Option<StoredTimeline> = self
+                .db
+                .upsert(("client_timeline", room_id.as_str()))
+                .content(timeline)
+                .await?;
+        }
+
+        if let Some(token) = &changes.sync_token {
+            let record = SyncTokenRecord { token: token.clone() };
+            let _: Option<SyncTokenRecord> =
+                self.db.upsert(("client_sync", SYNC_TOKEN_ID)).content(record).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn sync_token(&self) -> Result<Option<String>, StoreError> {
+        let record: Option<SyncTokenRecord> =
+            self.db.select(("client_sync", SYNC_TOKEN_ID)).await?;
+        Ok(record.map(|record| record.token))
+    }
+
+    async fn rooms(&self) -> Result<Vec<StoredRoom>, StoreError> {
+        let mut result = self.db.query("SELECT room_id, membership FROM client_room").await?;
+        let rooms: Vec<StoredRoom> = result.take(0)?;
+        Ok(rooms)
+    }
+
+    async fn room(&self, room_id: &str) -> Result<Option<StoredRoom>, StoreError> {
+        Ok(self.db.select(("client_room", room_id)).await?)
+    }
+
+    async fn state_event(
+        &self,
+        room_id: &str,
+        event_type: &str,
+        state_key: &str,
+    ) -> Result<Option<Event>, StoreError> {
+        let key = record_key(&[room_id, event_type, state_key]);
+        let record: Option<StateRecord> = self.db.select(("client_state", key)).await?;
+        Ok(record.map(|record| record.event))
+    }
+
+    async fn state_events(
+        &self,
+        room_id: &str,
+        event_type: &str,
+    ) -> Result<Vec<Event>, StoreError> {
+        let query = "SELECT VALUE event FROM client_state WHERE room_id = $room_id AND event_type = $event_type";
+        let mut result = self
+            .db
+            .query(query)
+            .bind(("room_id", room_id.to_string()))
+            .bind(("event_type", event_type.to_string()))
+            .await?;
+        let events: Vec<Event> = result.take(0)?;
+        Ok(events)
+    }
+
+    async fn account_data(&self, event_type: &str) -> Result<Option<Value>, StoreError> {
+        let key = record_key(&["", event_type]);
+        let record: Option<AccountDataRecord> =
+            self.db.select(("client_account_data", key)).await?;
+        Ok(record.map(|record| record.content))
+    }
+
+    async fn room_account_data(
+        &self,
+        room_id: &str,
+        event_type: &str,
+    ) -> Result<Option<Value>, StoreError> {
+        let key = record_key(&[room_id, event_type]);
+        let record: Option<AccountDataRecord> =
+            self.db.select(("client_account_data", key)).await?;
+        Ok(record.map(|record| record.content))
+    }
+
+    async fn receipts(&self, room_id: &str) -> Result<Vec<Receipt>, StoreError> {
+        let query = "SELECT VALUE receipt FROM client_receipt WHERE room_id = $room_id";
+        let mut result = self.db.query(query).bind(("room_id", room_id.to_string())).await?;
+        let receipts: Vec<Receipt> = result.take(0)?;
+        Ok(receipts)
+    }
+
+    async fn timeline(&self, room_id: &str) -> Result<StoredTimeline, StoreError> {
+        let timeline: Option<StoredTimeline> = self.db.select(("client_timeline", room_id)).await?;
+        Ok(timeline.unwrap_or_default())
+    }
+
+    async fn save_queued_event(&self, event: &QueuedEvent) -> Result<(), StoreError> {
+        let key = record_key(&[event.room_id.as_str(), event.transaction_id.as_str()]);
+        let record = QueuedEventRecord {
+            room_id: event.room_id.clone(),
+            event: event.clone(),
+        };
+        let _: Option<QueuedEventRecord> =
+            self.db.upsert(("client_send_queue", key)).content(record).await?;
+        Ok(())
+    }
+
+    async fn queued_events(&self) -> Result<Vec<QueuedEvent>, StoreError> {
+        let mut result = self.db.query("SELECT VALUE event FROM client_send_queue").await?;
+        let mut events: Vec<QueuedEvent> = result.take(0)?;
+        events.sort_by_key(|event| event.position);
+        Ok(events)
+    }
+
+    async fn remove_queued_event(
+        &self,
+        room_id: &str,
+        transaction_id: &str,
+    ) -> Result<(), StoreError> {
+        let key = record_key(&[room_id, transaction_id]);
+        let _: Option<QueuedEventRecord> = self.db.delete(("client_send_queue", key)).await?;
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<(), StoreError> {
+        self.db
+            .query(
+                "DELETE client_sync; DELETE client_room; DELETE client_state; \
+                 DELETE client_account_data; DELETE client_receipt; DELETE client_timeline; \
+                 DELETE client_send_queue;",
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::tests::sync_response;
+    use matryx_entity::MembershipState;
+
+    async fn setup_test_store() -> SurrealKvStateStore {
+        let db = surrealdb::engine::any::connect("memory").await.expect("db should connect");
+        db.use_ns("test").use_db("test").await.expect("namespace should select");
+        SurrealKvStateStore::new(db)
+    }
+
+    #[tokio::test]
+    async fn test_save_and_query_sync() {
+        let store = setup_test_store().await;
+
+        let changes = StateChanges::from_sync_response(&sync_response());
+        store.save_changes(&changes).await.expect("changes should save");
+
+        assert_eq!(store.sync_token().await.expect("token should load").as_deref(), Some("s2"));
+        let room = store.room("!room:example.org").await.expect("room should load");
+        assert_eq!(room.map(|room| room.membership), Some(MembershipState::Join));
+
+        let members = store.members("!room:example.org").await.expect("members should load");
+        assert_eq!(members.len(), 1);
+        assert!(store.account_data("m.direct").await.expect("account data").is_some());
+        assert_eq!(store.receipts("!room:example.org").await.expect("receipts").len(), 1);
+        let timeline = store.timeline("!room:example.org").await.expect("timeline should load");
+        assert_eq!(timeline.events.len(), 2);
+
+        store.clear().await.expect("store should clear");
+        assert_eq!(store.sync_token().await.expect("token should load"), None);
+        assert!(store.rooms().await.expect("rooms should load").is_empty());
+    }
+}
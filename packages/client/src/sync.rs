@@ -17,6 +17,7 @@ use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
 use crate::repositories::ClientRepositoryService;
+use crate::store::Receipt;
 
 /// Matrix sync state for tracking synchronization progress
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -103,8 +104,10 @@ pub struct PresenceState {
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct DeviceListUpdates {
     /// Users whose device lists have changed
+    #[serde(default)]
     pub changed: Vec<String>,
     /// Users who have left encrypted rooms
+    #[serde(default)]
     pub left: Vec<String>,
 }
 
@@ -147,6 +150,12 @@ pub enum SyncUpdate {
     AccountDataUpdate { data_type: String, content: serde_json::Value },
     /// Device list update
     DeviceListUpdate { changed: Vec<String>, left: Vec<String> },
+    /// Users currently typing in a room
+    Typing { room_id: String, user_ids: Vec<String> },
+    /// Read receipt update
+    Receipt { room_id: String, receipt: Receipt },
+    /// Sync failed; not recoverable once retries are exhausted
+    Error { message: String, recoverable: bool },
 }
 
 /// LiveQuery-based Matrix sync manager
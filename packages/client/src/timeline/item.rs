@@ -0,0 +1,147 @@
+//! Items shown in a room timeline
+
+use matryx_entity::Event;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Delivery state of an event we sent ourselves
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum SendState {
+    /// Waiting for the homeserver to accept the event
+    Pending,
+    /// Accepted by the homeserver, remote echo not received yet
+    Sent { event_id: String },
+    /// The homeserver rejected the event or could not be reached
+    Failed { error: String },
+}
+
+/// A reaction (`m.annotation`) to a timeline item
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Reaction {
+    pub sender: String,
+    /// ID of the reaction event, needed to redact it
+    pub event_id: String,
+}
+
+/// Replies in the thread rooted at a timeline item
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThreadSummary {
+    pub count: u64,
+    pub latest_event_id: Option<String>,
+}
+
+/// An event as shown in a timeline, with edits, reactions and redaction applied
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimelineItem {
+    /// Event ID, `None` until a local echo has been sent
+    pub event_id: Option<String>,
+    /// Transaction ID of an event we sent from this device
+    pub transaction_id: Option<String>,
+    pub sender: String,
+    pub origin_server_ts: i64,
+    pub event_type: String,
+    pub state_key: Option<String>,
+    /// Current content, the latest edit's `m.new_content` if edited
+    pub content: Value,
+    pub edited: bool,
+    pub redacted: bool,
+    /// Reaction key -> reactions with that key, in arrival order
+    pub reactions: BTreeMap<String, Vec<Reaction>>,
+    /// Set on thread roots
+    pub thread: Option<ThreadSummary>,
+    /// Set on local echoes
+    pub send_state: Option<SendState>,
+}
+
+impl TimelineItem {
+    /// Build an item from a remote event
+    ///
+    /// An edit and thread summary bundled in `unsigned.m.relations` are applied.
+    pub fn from_event(event: &Event) -> Self {
+        let mut item = Self {
+            event_id: Some(event.event_id.clone()),
+            transaction_id: transaction_id(event),
+            sender: event.sender.clone(),
+            origin_server_ts: event.origin_server_ts,
+            event_type: event.event_type.clone(),
+            state_key: event.state_key.clone(),
+            content: serde_json::to_value(&event.content).unwrap_or(Value::Null),
+            edited: false,
+            redacted: false,
+            reactions: BTreeMap::new(),
+            thread: None,
+            send_state: None,
+        };
+
+        let relations = event.unsigned.as_ref().and_then(|unsigned| unsigned.get("m.relations"));
+        if let Some(relations) = relations {
+            let new_content = relations.get("m.replace").and_then(|edit| {
+                edit.get("content").and_then(|content| content.get("m.new_content"))
+            });
+            if let Some(new_content) = new_content {
+                item.content = new_content.clone();
+                item.edited = true;
+            }
+            if let Some(thread) = relations.get("m.thread") {
+                item.thread = Some(ThreadSummary {
+                    count: thread.get("count").and_then(Value::as_u64).unwrap_or(0),
+                    latest_event_id: thread
+                        .get("latest_event")
+                        .and_then(|latest| latest.get("event_id"))
+                        .and_then(Value::as_str)
+                        .map(str::to_string),
+                });
+            }
+        }
+
+        item
+    }
+
+    /// Build a local echo for an event we are about to send
+    pub fn local_echo(
+        transaction_id: &str,
+        sender: &str,
+        event_type: &str,
+        content: Value,
+    ) -> Self {
+        Self {
+            event_id: None,
+            transaction_id: Some(transaction_id.to_string()),
+            sender: sender.to_string(),
+            origin_server_ts: chrono::Utc::now().timestamp_millis(),
+            event_type: event_type.to_string(),
+            state_key: None,
+            content,
+            edited: false,
+            redacted: false,
+            reactions: BTreeMap::new(),
+            thread: None,
+            send_state: Some(SendState::Pending),
+        }
+    }
+
+    /// Whether this is a local echo not yet replaced by its remote echo
+    pub fn is_local_echo(&self) -> bool {
+        self.send_state.is_some()
+    }
+
+    /// Strip the content as a redaction does
+    pub(crate) fn redact(&mut self) {
+        self.content = Value::Object(Default::default());
+        self.redacted = true;
+        self.edited = false;
+        self.reactions.clear();
+    }
+}
+
+/// Transaction ID the homeserver echoes back to the sending device
+pub(crate) fn transaction_id(event: &Event) -> Option<String> {
+    event
+        .unsigned
+        .as_ref()
+        .and_then(|unsigned| unsigned.get("transaction_id"))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
@@ -0,0 +1,718 @@
+//! Room timelines
+//!
+//! A [`Timeline`] merges the events of a room from `/sync` and from
+//! back-pagination into a list of [`TimelineItem`]s: edits (`m.replace`)
+//! replace the content of their target, reactions (`m.annotation`) are
+//! collected on it and redactions strip it. Events we send show up at once as
+//! local echoes with a [`SendState`] and are swapped for the remote echo when
+//! it arrives. Replies in a thread only bump the thread summary of their root;
+//! a thread timeline shows the root and its replies.
+//!
+//! Every change is emitted as a [`TimelineDiff`], so a UI can mirror the items
+//! without diffing them itself.
+
+pub mod item;
+
+pub use item::{Reaction, SendState, ThreadSummary, TimelineItem};
+
+use matryx_entity::Event;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::{RwLock, broadcast};
+
+const REDACTION_EVENT_TYPE: &str = "m.room.redaction";
+const REL_REPLACE: &str = "m.replace";
+const REL_ANNOTATION: &str = "m.annotation";
+const REL_THREAD: &str = "m.thread";
+
+/// Open timelines by room ID and thread root
+pub(crate) type Timelines = HashMap<(String, Option<String>), Arc<Timeline>>;
+
+/// A change to the items of a timeline
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum TimelineDiff {
+    PushBack {
+        item: TimelineItem,
+    },
+    PushFront {
+        item: TimelineItem,
+    },
+    Insert {
+        index: usize,
+        item: TimelineItem,
+    },
+    Set {
+        index: usize,
+        item: TimelineItem,
+    },
+    Remove {
+        index: usize,
+    },
+    /// All items were replaced, e.g. after a gap in `/sync`
+    Reset {
+        items: Vec<TimelineItem>,
+    },
+}
+
+/// Where new events go: live events at the back, paginated ones at the front
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Position {
+    Back,
+    Front,
+}
+
+/// Timeline of a room, or of a thread in it
+#[derive(Debug)]
+pub struct Timeline {
+    room_id: String,
+    thread_root: Option<String>,
+    state: RwLock<TimelineState>,
+    diff_sender: broadcast::Sender<TimelineDiff>,
+}
+
+impl Timeline {
+    /// Main timeline of a room
+    pub fn new(room_id: &str) -> Self {
+        Self::with_thread_root(room_id, None)
+    }
+
+    /// Timeline of the thread rooted at `root_event_id`
+    pub fn for_thread(room_id: &str, root_event_id: &str) -> Self {
+        Self::with_thread_root(room_id, Some(root_event_id.to_string()))
+    }
+
+    fn with_thread_root(room_id: &str, thread_root: Option<String>) -> Self {
+        let (diff_sender, _) = broadcast::channel(1000);
+        let state = TimelineState {
+            is_thread: thread_root.is_some(),
+            ..Default::default()
+        };
+        Self {
+            room_id: room_id.to_string(),
+            thread_root,
+            state: RwLock::new(state),
+            diff_sender,
+        }
+    }
+
+    pub fn room_id(&self) -> &str {
+        &self.room_id
+    }
+
+    /// Root event of a thread timeline
+    pub fn thread_root(&self) -> Option<&str> {
+        self.thread_root.as_deref()
+    }
+
+    /// Current items, oldest first, local echoes last
+    pub async fn items(&self) -> Vec<TimelineItem> {
+        self.state.read().await.items.clone()
+    }
+
+    /// The item of an event, if loaded
+    pub async fn item(&self, event_id: &str) -> Option<TimelineItem> {
+        let state = self.state.read().await;
+        state.index_of(event_id).map(|index| state.items[index].clone())
+    }
+
+    /// Receive a diff for every change to the items
+    pub fn subscribe(&self) -> broadcast::Receiver<TimelineDiff> {
+        self.diff_sender.subscribe()
+    }
+
+    /// Whether back-pagination reached the start of the room or thread
+    pub async fn is_at_start(&self) -> bool {
+        self.state.read().await.at_start
+    }
+
+    /// Token to paginate backwards from
+    pub(crate) async fn pagination_token(&self) -> Option<String> {
+        self.state.read().await.pagination_token.clone()
+    }
+
+    /// Add a chunk of live timeline events from `/sync`
+    ///
+    /// A `limited` chunk leaves a gap to the loaded events in a room
+    /// timeline, so they are dropped and pagination restarts at `prev_batch`.
+    pub(crate) async fn add_sync_events(
+        &self,
+        events: &[Event],
+        limited: bool,
+        prev_batch: Option<&str>,
+    ) {
+        let mut state = self.state.write().await;
+        if self.thread_root.is_none() {
+            if limited {
+                state.reset();
+            }
+            if limited || state.items.iter().all(TimelineItem::is_local_echo) {
+                state.pagination_token = prev_batch.map(str::to_string);
+            }
+        }
+        for event in events.iter().filter(|event| self.belongs(event)) {
+            state.handle_event(event, Position::Back);
+        }
+        self.publish(&mut state);
+    }
+
+    /// Add events returned by back-pagination from `from`, newest first
+    ///
+    /// Returns false without changes if the timeline moved on since the
+    /// request was made, e.g. because a gap reset it.
+    pub(crate) async fn add_paginated_events(
+        &self,
+        from: Option<&str>,
+        events: &[Event],
+        end: Option<&str>,
+    ) -> bool {
+        let mut state = self.state.write().await;
+        if state.pagination_token.as_deref() != from {
+            return false;
+        }
+        for event in events.iter().filter(|event| self.belongs(event)) {
+            state.handle_event(event, Position::Front);
+        }
+        state.pagination_token = end.map(str::to_string);
+        state.at_start = end.is_none();
+        self.publish(&mut state);
+        true
+    }
+
+    /// Show an event we are sending before the homeserver accepted it
+    pub(crate) async fn add_local_echo(&self, item: TimelineItem) {
+        let mut state = self.state.write().await;
+        let index = state.items.len();
+        state.insert_at(index, item);
+        self.publish(&mut state);
+    }
+
+    /// The homeserver accepted a local echo as `event_id`
+    pub(crate) async fn mark_sent(&self, transaction_id: &str, event_id: &str) {
+        let mut state = self.state.write().await;
+        if let Some(index) = state.local_echo_index(transaction_id) {
+            if state.seen.contains(event_id) {
+                // The remote echo came without our transaction ID
+                state.items.remove(index);
+                state.diffs.push(TimelineDiff::Remove { index });
+            } else {
+                let item = &mut state.items[index];
+                item.event_id = Some(event_id.to_string());
+                item.send_state = Some(SendState::Sent { event_id: event_id.to_string() });
+                let item = item.clone();
+                state.diffs.push(TimelineDiff::Set { index, item });
+            }
+        }
+        self.publish(&mut state);
+    }
+
+    /// Sending a local echo failed
+    pub(crate) async fn mark_failed(&self, transaction_id: &str, error: &str) {
+        let mut state = self.state.write().await;
+        if let Some(index) = state.local_echo_index(transaction_id) {
+            let item = &mut state.items[index];
+            item.send_state = Some(SendState::Failed { error: error.to_string() });
+            let item = item.clone();
+            state.diffs.push(TimelineDiff::Set { index, item });
+        }
+        self.publish(&mut state);
+    }
+
+    /// Whether an event is shown in (or modifies items of) this timeline
+    fn belongs(&self, event: &Event) -> bool {
+        let Some(root) = &self.thread_root else {
+            return true;
+        };
+        if event.event_id == *root || event.event_type == REDACTION_EVENT_TYPE {
+            return true;
+        }
+        match relation(event) {
+            Some((rel_type, target)) if rel_type == REL_THREAD => target == *root,
+            Some((rel_type, _)) => rel_type == REL_REPLACE || rel_type == REL_ANNOTATION,
+            None => false,
+        }
+    }
+
+    fn publish(&self, state: &mut TimelineState) {
+        for diff in state.diffs.drain(..) {
+            // Nobody listening is fine
+            let _ = self.diff_sender.send(diff);
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct TimelineState {
+    items: Vec<TimelineItem>,
+    pagination_token: Option<String>,
+    at_start: bool,
+    is_thread: bool,
+    /// IDs of all handled events, items or not
+    seen: HashSet<String>,
+    /// Target event ID -> redactions and relations waiting for it to load
+    pending: HashMap<String, Vec<Event>>,
+    /// Reaction event ID -> (target event ID, key)
+    reactions: HashMap<String, (String, String)>,
+    /// Edited event ID -> timestamp of the applied edit
+    edits: HashMap<String, i64>,
+    /// Roots whose thread summary was bundled by the homeserver
+    bundled_threads: HashSet<String>,
+    /// Changes not published yet
+    diffs: Vec<TimelineDiff>,
+}
+
+impl TimelineState {
+    /// Drop all remote items, keeping local echoes
+    fn reset(&mut self) {
+        let echoes: Vec<TimelineItem> =
+            self.items.drain(..).filter(TimelineItem::is_local_echo).collect();
+        let mut diffs = std::mem::take(&mut self.diffs);
+        diffs.push(TimelineDiff::Reset { items: echoes.clone() });
+        *self = TimelineState {
+            items: echoes,
+            is_thread: self.is_thread,
+            diffs,
+            ..Default::default()
+        };
+    }
+
+    fn handle_event(&mut self, event: &Event, position: Position) {
+        if self.replace_local_echo(event) || !self.seen.insert(event.event_id.clone()) {
+            return;
+        }
+
+        let rel_type = relation(event).map(|(rel_type, _)| rel_type);
+        let is_related = event.event_type == REDACTION_EVENT_TYPE
+            || matches!(rel_type.as_deref(), Some(REL_REPLACE | REL_ANNOTATION))
+            || (!self.is_thread && rel_type.as_deref() == Some(REL_THREAD));
+        if !is_related {
+            self.insert_event(event, position);
+            return;
+        }
+
+        // Redacted before it loaded, which happens when paginating backwards
+        let redacted = self.pending.remove(&event.event_id).is_some_and(|events| {
+            events.iter().any(|pending| pending.event_type == REDACTION_EVENT_TYPE)
+        });
+        if redacted {
+            return;
+        }
+
+        if !self.apply_related(event, position == Position::Back)
+            && !self.is_thread
+            && let Some(target) = target_event_id(event)
+        {
+            self.pending.entry(target).or_default().push(event.clone());
+        }
+    }
+
+    /// Swap a local echo for its remote echo
+    fn replace_local_echo(&mut self, event: &Event) -> bool {
+        let transaction_id = item::transaction_id(event);
+        let Some(index) = self.items.iter().position(|item| {
+            item.is_local_echo()
+                && ((transaction_id.is_some() && item.transaction_id == transaction_id)
+                    || item.event_id.as_deref() == Some(event.event_id.as_str()))
+        }) else {
+            return false;
+        };
+
+        self.seen.insert(event.event_id.clone());
+        self.items.remove(index);
+        let new_index = self.first_local_echo_index();
+        let mut item = TimelineItem::from_event(event);
+        item.transaction_id = transaction_id;
+        if new_index == index {
+            self.items.insert(index, item.clone());
+            self.diffs.push(TimelineDiff::Set { index, item });
+        } else {
+            self.diffs.push(TimelineDiff::Remove { index });
+            self.insert_at(new_index, item);
+        }
+        self.apply_pending(&event.event_id);
+        true
+    }
+
+    fn insert_event(&mut self, event: &Event, position: Position) {
+        let item = TimelineItem::from_event(event);
+        if item.thread.is_some() {
+            self.bundled_threads.insert(event.event_id.clone());
+        }
+        let index = match position {
+            Position::Back => self.first_local_echo_index(),
+            Position::Front => 0,
+        };
+        self.insert_at(index, item);
+        self.apply_pending(&event.event_id);
+    }
+
+    fn insert_at(&mut self, index: usize, item: TimelineItem) {
+        let diff = if index == self.items.len() {
+            TimelineDiff::PushBack { item: item.clone() }
+        } else if index == 0 {
+            TimelineDiff::PushFront { item: item.clone() }
+        } else {
+            TimelineDiff::Insert { index, item: item.clone() }
+        };
+        self.items.insert(index, item);
+        self.diffs.push(diff);
+    }
+
+    /// Apply relations and redactions that arrived before their target
+    fn apply_pending(&mut self, event_id: &str) {
+        for event in self.pending.remove(event_id).unwrap_or_default() {
+            self.apply_related(&event, false);
+        }
+    }
+
+    /// Apply a redaction or relation, false if its target is not loaded
+    ///
+    /// `live` is set for events newer than everything loaded.
+    fn apply_related(&mut self, event: &Event, live: bool) -> bool {
+        let Some(target) = target_event_id(event) else {
+            return true;
+        };
+        if event.event_type == REDACTION_EVENT_TYPE {
+            return self.apply_redaction(&target);
+        }
+        let Some(index) = self.index_of(&target) else {
+            return false;
+        };
+
+        match relation(event).map(|(rel_type, _)| rel_type).as_deref() {
+            Some(REL_REPLACE) => {
+                let newest = self.edits.get(&target).is_none_or(|ts| *ts <= event.origin_server_ts);
+                let item = &self.items[index];
+                if !newest || item.redacted || item.sender != event.sender {
+                    return true;
+                }
+                let content = content_value(event);
+                let Some(new_content) = content.get("m.new_content") else {
+                    return true;
+                };
+                let item = &mut self.items[index];
+                item.content = new_content.clone();
+                item.edited = true;
+                self.edits.insert(target, event.origin_server_ts);
+            },
+            Some(REL_ANNOTATION) => {
+                let Some(key) = annotation_key(event) else {
+                    return true;
+                };
+                let reactions = self.items[index].reactions.entry(key.clone()).or_default();
+                if reactions.iter().any(|reaction| reaction.sender == event.sender) {
+                    return true;
+                }
+                reactions.push(Reaction {
+                    sender: event.sender.clone(),
+                    event_id: event.event_id.clone(),
+                });
+                self.reactions.insert(event.event_id.clone(), (target, key));
+            },
+            Some(REL_THREAD) => {
+                let bundled = self.bundled_threads.contains(&target);
+                let thread = self.items[index].thread.get_or_insert_with(Default::default);
+                if live {
+                    thread.count += 1;
+                    thread.latest_event_id = Some(event.event_id.clone());
+                } else if !bundled {
+                    // Older replies arrive newest first
+                    thread.count += 1;
+                    thread.latest_event_id.get_or_insert_with(|| event.event_id.clone());
+                } else {
+                    return true;
+                }
+            },
+            _ => return true,
+        }
+
+        let item = self.items[index].clone();
+        self.diffs.push(TimelineDiff::Set { index, item });
+        true
+    }
+
+    fn apply_redaction(&mut self, target: &str) -> bool {
+        if let Some((reacted_to, key)) = self.reactions.remove(target) {
+            if let Some(index) = self.index_of(&reacted_to) {
+                let item = &mut self.items[index];
+                if let Some(reactions) = item.reactions.get_mut(&key) {
+                    reactions.retain(|reaction| reaction.event_id != target);
+                    if reactions.is_empty() {
+                        item.reactions.remove(&key);
+                    }
+                }
+                let item = item.clone();
+                self.diffs.push(TimelineDiff::Set { index, item });
+            }
+            return true;
+        }
+
+        let Some(index) = self.index_of(target) else {
+            return false;
+        };
+        let item = &mut self.items[index];
+        item.redact();
+        let item = item.clone();
+        self.reactions.retain(|_, (reacted_to, _)| reacted_to != target);
+        self.diffs.push(TimelineDiff::Set { index, item });
+        true
+    }
+
+    fn index_of(&self, event_id: &str) -> Option<usize> {
+        self.items
+            .iter()
+            .position(|item| item.event_id.as_deref() == Some(event_id))
+    }
+
+    fn local_echo_index(&self, transaction_id: &str) -> Option<usize> {
+        self.items.iter().position(|item| {
+            item.is_local_echo() && item.transaction_id.as_deref() == Some(transaction_id)
+        })
+    }
+
+    /// Remote events go before the local echoes
+    fn first_local_echo_index(&self) -> usize {
+        self.items
+            .iter()
+            .position(TimelineItem::is_local_echo)
+            .unwrap_or(self.items.len())
+    }
+}
+
+fn content_value(event: &Event) -> Value {
+    serde_json::to_value(&event.content).unwrap_or(Value::Null)
+}
+
+/// (`rel_type`, target event ID) of an event's `m.relates_to`
+fn relation(event: &Event) -> Option<(String, String)> {
+    let content = content_value(event);
+    let relates_to = content.get("m.relates_to")?;
+    let rel_type = relates_to.get("rel_type")?.as_str()?;
+    let event_id = relates_to.get("event_id")?.as_str()?;
+    Some((rel_type.to_string(), event_id.to_string()))
+}
+
+/// Event a redaction or relation applies to
+fn target_event_id(event: &Event) -> Option<String> {
+    if event.event_type == REDACTION_EVENT_TYPE {
+        // Top-level `redacts` before room version 11, in the content since
+        return event.redacts.clone().or_else(|| {
+            content_value(event)
+                .get("redacts")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        });
+    }
+    relation(event).map(|(_, event_id)| event_id)
+}
+
+fn annotation_key(event: &Event) -> Option<String> {
+    let content = content_value(event);
+    content.get("m.relates_to")?.get("key")?.as_str().map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const ROOM_ID: &str = "!room:example.org";
+
+    fn event(event_id: &str, sender: &str, ts: i64, event_type: &str, content: Value) -> Event {
+        serde_json::from_value(json!({
+            "event_id": event_id,
+            "sender": sender,
+            "origin_server_ts": ts,
+            "type": event_type,
+            "room_id": ROOM_ID,
+            "content": content,
+        }))
+        .expect("event should deserialize")
+    }
+
+    fn message(event_id: &str, ts: i64, body: &str) -> Event {
+        event(
+            event_id,
+            "@alice:example.org",
+            ts,
+            "m.room.message",
+            json!({
+                "msgtype": "m.text",
+                "body": body,
+            }),
+        )
+    }
+
+    fn relation_event(event_id: &str, ts: i64, relates_to: Value, extra: Value) -> Event {
+        let mut content = extra;
+        content["m.relates_to"] = relates_to;
+        event(event_id, "@alice:example.org", ts, "m.room.message", content)
+    }
+
+    fn reaction(event_id: &str, sender: &str, target: &str, key: &str) -> Event {
+        event(
+            event_id,
+            sender,
+            5,
+            "m.reaction",
+            json!({
+                "m.relates_to": { "rel_type": "m.annotation", "event_id": target, "key": key },
+            }),
+        )
+    }
+
+    fn redaction(event_id: &str, target: &str) -> Event {
+        event(event_id, "@alice:example.org", 9, "m.room.redaction", json!({ "redacts": target }))
+    }
+
+    fn bodies(items: &[TimelineItem]) -> Vec<String> {
+        items
+            .iter()
+            .map(|item| item.content["body"].as_str().unwrap_or_default().to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_edits_reactions_and_redactions() {
+        let timeline = Timeline::new(ROOM_ID);
+        let mut diffs = timeline.subscribe();
+        let edit = relation_event(
+            "$edit",
+            2,
+            json!({ "rel_type": "m.replace", "event_id": "$first" }),
+            json!({ "body": "* edited", "m.new_content": { "body": "edited" } }),
+        );
+        let mut foreign_edit = edit.clone();
+        foreign_edit.event_id = "$foreign".to_string();
+        foreign_edit.sender = "@mallory:example.org".to_string();
+
+        timeline
+            .add_sync_events(
+                &[
+                    message("$first", 1, "first"),
+                    message("$second", 1, "second"),
+                    edit,
+                    foreign_edit,
+                    reaction("$like", "@bob:example.org", "$first", "👍"),
+                    reaction("$like2", "@carol:example.org", "$first", "👍"),
+                    redaction("$unlike", "$like"),
+                    redaction("$redact", "$second"),
+                ],
+                false,
+                Some("t1"),
+            )
+            .await;
+
+        let items = timeline.items().await;
+        assert_eq!(items.len(), 2);
+        assert_eq!(bodies(&items), vec!["edited", ""]);
+        assert!(items[0].edited);
+        assert_eq!(items[0].reactions["👍"].len(), 1);
+        assert_eq!(items[0].reactions["👍"][0].sender, "@carol:example.org");
+        assert!(items[1].redacted);
+        assert_eq!(timeline.pagination_token().await.as_deref(), Some("t1"));
+
+        assert!(matches!(diffs.recv().await, Ok(TimelineDiff::PushBack { .. })));
+        assert!(matches!(diffs.recv().await, Ok(TimelineDiff::PushBack { .. })));
+        assert!(matches!(diffs.recv().await, Ok(TimelineDiff::Set { index: 0, .. })));
+    }
+
+    #[tokio::test]
+    async fn test_local_echo() {
+        let timeline = Timeline::new(ROOM_ID);
+        let echo = |txn_id: &str| {
+            TimelineItem::local_echo(
+                txn_id,
+                "@alice:example.org",
+                "m.room.message",
+                json!({
+                    "body": txn_id,
+                }),
+            )
+        };
+        timeline.add_local_echo(echo("txn1")).await;
+        timeline.add_local_echo(echo("txn2")).await;
+        timeline.mark_failed("txn2", "offline").await;
+        timeline
+            .add_sync_events(&[message("$other", 1, "other")], false, None)
+            .await;
+
+        let items = timeline.items().await;
+        assert_eq!(bodies(&items), vec!["other", "txn1", "txn2"]);
+        assert_eq!(items[1].send_state, Some(SendState::Pending));
+        assert_eq!(items[2].send_state, Some(SendState::Failed { error: "offline".into() }));
+
+        timeline.mark_sent("txn1", "$sent").await;
+        let mut remote = message("$sent", 2, "txn1");
+        remote.unsigned = Some(json!({ "transaction_id": "txn1" }));
+        timeline.add_sync_events(&[remote], false, None).await;
+
+        let items = timeline.items().await;
+        assert_eq!(bodies(&items), vec!["other", "txn1", "txn2"]);
+        assert_eq!(items[1].event_id.as_deref(), Some("$sent"));
+        assert_eq!(items[1].send_state, None);
+
+        // A gap drops remote events but keeps what we are still sending
+        timeline
+            .add_sync_events(&[message("$new", 3, "new")], true, Some("t2"))
+            .await;
+        assert_eq!(bodies(&timeline.items().await), vec!["new", "txn2"]);
+        assert_eq!(timeline.pagination_token().await.as_deref(), Some("t2"));
+    }
+
+    #[tokio::test]
+    async fn test_back_pagination_and_threads() {
+        let timeline = Timeline::new(ROOM_ID);
+        timeline
+            .add_sync_events(&[message("$live", 10, "live")], true, Some("t1"))
+            .await;
+
+        let reply = |event_id: &str, ts: i64| {
+            relation_event(
+                event_id,
+                ts,
+                json!({ "rel_type": "m.thread", "event_id": "$root" }),
+                json!({ "body": event_id }),
+            )
+        };
+        // Newest first, so relations come before their targets
+        let chunk = [
+            reply("$reply2", 8),
+            redaction("$unlike", "$like"),
+            reaction("$like", "@bob:example.org", "$root", "👍"),
+            reaction("$heart", "@bob:example.org", "$root", "❤️"),
+            reply("$reply1", 7),
+            message("$root", 5, "root"),
+        ];
+        assert!(!timeline.add_paginated_events(Some("stale"), &chunk, None).await);
+        assert!(timeline.add_paginated_events(Some("t1"), &chunk, Some("t0")).await);
+        assert!(!timeline.is_at_start().await);
+
+        let items = timeline.items().await;
+        assert_eq!(bodies(&items), vec!["root", "live"]);
+        let thread = items[0].thread.clone().expect("root should have a thread summary");
+        assert_eq!(thread.count, 2);
+        assert_eq!(thread.latest_event_id.as_deref(), Some("$reply2"));
+        assert_eq!(items[0].reactions.keys().collect::<Vec<_>>(), vec!["❤️"]);
+
+        timeline.add_sync_events(&[reply("$reply3", 11)], false, None).await;
+        let root = timeline.item("$root").await.expect("root should be loaded");
+        assert_eq!(root.thread.map(|thread| thread.count), Some(3));
+
+        let thread = Timeline::for_thread(ROOM_ID, "$root");
+        thread
+            .add_sync_events(
+                &[message("$unrelated", 12, "unrelated"), reply("$reply4", 12)],
+                false,
+                None,
+            )
+            .await;
+        thread
+            .add_paginated_events(None, &[reply("$reply1", 7), message("$root", 5, "root")], None)
+            .await;
+        assert_eq!(bodies(&thread.items().await), vec!["root", "$reply1", "$reply4"]);
+        assert!(thread.is_at_start().await);
+    }
+}
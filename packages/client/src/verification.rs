@@ -0,0 +1,323 @@
+//! Interactive device verification for the Matrix client
+//!
+//! Drives the [`VerificationMachine`] over the client-server API: sends the
+//! queued `m.key.verification.*` messages over to-device or as room events,
+//! feeds verification events from sync back into it and uploads the
+//! cross-signing signatures of successfully verified devices and users.
+
+use anyhow::Result;
+use matryx_entity::types::{RoomEncryptedContent, RoomMessageContent};
+use reqwest::Method;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::{Mutex, broadcast};
+use tracing::{debug, warn};
+
+use crate::crypto::verification::{CancelCode, QrCode, SasEmoji};
+use crate::crypto::{
+    DecryptedToDeviceEvent, OutgoingVerificationMessage, VerificationEvent, VerificationMachine,
+};
+use crate::{MatrixClient, SyncResponse};
+
+/// Verification functionality
+impl MatrixClient {
+    /// Subscribe to verification requests and progress
+    pub async fn verification_events(&self) -> Result<broadcast::Receiver<VerificationEvent>> {
+        Ok(self.require_verification_machine()?.lock().await.subscribe())
+    }
+
+    /// Ask a user's devices to verify over to-device messages
+    ///
+    /// Use the user's own ID to verify another of our devices. Without
+    /// `device_ids` all of the user's devices are asked. Returns the flow ID.
+    pub async fn request_verification(
+        &self,
+        user_id: &str,
+        device_ids: Option<Vec<String>>,
+    ) -> Result<String> {
+        let flow_id = self
+            .require_verification_machine()?
+            .lock()
+            .await
+            .request_to_device(user_id, device_ids);
+        self.process_verification_requests().await?;
+        Ok(flow_id)
+    }
+
+    /// Ask another user to verify in a shared room; returns the flow ID
+    pub async fn request_verification_in_room(
+        &self,
+        room_id: &str,
+        user_id: &str,
+    ) -> Result<String> {
+        let machine = self.require_verification_machine()?;
+        let content = machine.lock().await.request_in_room_content(user_id);
+        let event_id = self
+            .send_verification_room_event(
+                room_id,
+                RoomMessageContent::EVENT_TYPE,
+                serde_json::to_value(content)?,
+            )
+            .await?;
+
+        machine.lock().await.room_request_sent(room_id, &event_id, user_id);
+        Ok(event_id)
+    }
+
+    /// Accept a verification request by sending ready
+    pub async fn accept_verification_request(&self, flow_id: &str) -> Result<()> {
+        self.require_verification_machine()?.lock().await.accept_request(flow_id)?;
+        self.process_verification_requests().await
+    }
+
+    /// Start SAS verification on a ready flow
+    pub async fn start_sas_verification(&self, flow_id: &str) -> Result<()> {
+        self.require_verification_machine()?.lock().await.start_sas(flow_id)?;
+        self.process_verification_requests().await
+    }
+
+    /// Accept SAS verification started by the other side
+    pub async fn accept_sas_verification(&self, flow_id: &str) -> Result<()> {
+        self.require_verification_machine()?.lock().await.accept_sas(flow_id)?;
+        self.process_verification_requests().await
+    }
+
+    /// Emojis to compare once the SAS keys were exchanged
+    pub async fn sas_emojis(&self, flow_id: &str) -> Result<Option<Vec<SasEmoji>>> {
+        let machine = self.require_verification_machine()?;
+        let machine = machine.lock().await;
+        Ok(machine.sas(flow_id).and_then(|sas| sas.emojis()))
+    }
+
+    /// Decimals to compare once the SAS keys were exchanged
+    pub async fn sas_decimals(&self, flow_id: &str) -> Result<Option<(u16, u16, u16)>> {
+        let machine = self.require_verification_machine()?;
+        let machine = machine.lock().await;
+        Ok(machine.sas(flow_id).and_then(|sas| sas.decimals()))
+    }
+
+    /// Confirm the short authentication strings match on both devices
+    pub async fn confirm_sas_verification(&self, flow_id: &str) -> Result<()> {
+        let olm = self.require_olm_machine()?;
+        let mut olm = olm.lock().await;
+        self.require_verification_machine()?
+            .lock()
+            .await
+            .confirm_sas(&mut olm, flow_id)?;
+        drop(olm);
+        self.process_verification_requests().await
+    }
+
+    /// Report that the short authentication strings differ, cancelling the flow
+    pub async fn mismatch_sas_verification(&self, flow_id: &str) -> Result<()> {
+        self.require_verification_machine()?.lock().await.mismatch_sas(flow_id)?;
+        self.process_verification_requests().await
+    }
+
+    /// Build the QR code to display on a ready flow
+    ///
+    /// Render [`QrCode::to_bytes`] as a byte-mode QR code.
+    pub async fn generate_verification_qr_code(&self, flow_id: &str) -> Result<QrCode> {
+        let olm = self.require_olm_machine()?;
+        let olm = olm.lock().await;
+        let code = self
+            .require_verification_machine()?
+            .lock()
+            .await
+            .generate_qr_code(&olm, flow_id)?;
+        Ok(code)
+    }
+
+    /// Check a QR code scanned from the other device; returns the flow ID
+    pub async fn scan_verification_qr_code(&self, bytes: &[u8]) -> Result<String> {
+        let olm = self.require_olm_machine()?;
+        let mut olm = olm.lock().await;
+        let flow_id = self
+            .require_verification_machine()?
+            .lock()
+            .await
+            .scan_qr_code(&mut olm, bytes)?;
+        drop(olm);
+        self.process_verification_requests().await?;
+        Ok(flow_id)
+    }
+
+    /// Confirm the other device shows that it scanned our QR code
+    pub async fn confirm_verification_qr_scanned(&self, flow_id: &str) -> Result<()> {
+        let olm = self.require_olm_machine()?;
+        let mut olm = olm.lock().await;
+        self.require_verification_machine()?
+            .lock()
+            .await
+            .confirm_qr_scanned(&mut olm, flow_id)?;
+        drop(olm);
+        self.process_verification_requests().await
+    }
+
+    /// Cancel a verification flow
+    pub async fn cancel_verification(&self, flow_id: &str) -> Result<()> {
+        self.require_verification_machine()?
+            .lock()
+            .await
+            .cancel(flow_id, CancelCode::User)?;
+        self.process_verification_requests().await
+    }
+
+    /// Create and publish cross-signing keys, signing this device with them
+    ///
+    /// The server usually requires user-interactive authentication; pass the
+    /// `auth` dict once the flow is completed.
+    pub async fn bootstrap_cross_signing(&self, auth_data: Option<Value>) -> Result<()> {
+        let olm = self.require_olm_machine()?;
+        let bootstrap = olm.lock().await.bootstrap_cross_signing()?;
+
+        self.upload_cross_signing_keys(&bootstrap.public_keys, auth_data).await?;
+        self.upload_signatures(&bootstrap.signatures).await
+    }
+
+    /// Send queued verification messages and signature uploads
+    pub async fn process_verification_requests(&self) -> Result<()> {
+        let machine = self.require_verification_machine()?;
+        let (messages, signatures) = {
+            let mut machine = machine.lock().await;
+            (machine.outgoing_messages(), machine.signature_uploads())
+        };
+
+        for message in messages {
+            match message {
+                OutgoingVerificationMessage::ToDevice {
+                    user_id,
+                    device_id,
+                    event_type,
+                    content,
+                } => {
+                    let mut devices = HashMap::new();
+                    devices.insert(device_id, content);
+                    let mut messages = HashMap::new();
+                    messages.insert(user_id, devices);
+                    self.send_to_device(&event_type, messages).await?;
+                },
+                OutgoingVerificationMessage::InRoom { room_id, event_type, content } => {
+                    self.send_verification_room_event(&room_id, &event_type, content).await?;
+                },
+            }
+        }
+
+        for signatures in signatures {
+            self.upload_signatures(&signatures).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Feed verification events from a sync response to the machine
+    ///
+    /// To-device events must already be decrypted; encrypted in-room
+    /// verification events are decrypted here.
+    pub(crate) async fn receive_sync_verification(
+        &self,
+        to_device: &[DecryptedToDeviceEvent],
+        response: &SyncResponse,
+    ) -> Result<()> {
+        let olm = self.require_olm_machine()?;
+        let machine = self.require_verification_machine()?;
+
+        {
+            let mut olm = olm.lock().await;
+            let mut machine = machine.lock().await;
+
+            for event in to_device {
+                machine.receive_to_device_event(
+                    &mut olm,
+                    &event.sender,
+                    &event.event_type,
+                    &event.content,
+                );
+            }
+
+            for (room_id, room) in &response.rooms.join {
+                let events = room.timeline.iter().flat_map(|timeline| &timeline.events);
+                for event in events {
+                    let content = serde_json::to_value(&event.content)?;
+                    let (event_type, content) = if event.event_type
+                        == RoomEncryptedContent::EVENT_TYPE
+                    {
+                        match olm.decrypt_room_event(
+                            room_id,
+                            &event.event_id,
+                            &event.sender,
+                            &content,
+                        ) {
+                            Ok(decrypted) => (decrypted.event_type, decrypted.content),
+                            Err(e) => {
+                                debug!("Skipping undecryptable event {}: {}", event.event_id, e);
+                                continue;
+                            },
+                        }
+                    } else {
+                        (event.event_type.clone(), content)
+                    };
+
+                    machine.receive_room_event(
+                        &mut olm,
+                        room_id,
+                        &event.event_id,
+                        &event.sender,
+                        &event_type,
+                        &content,
+                    );
+                }
+            }
+
+            machine.cancel_stale(chrono::Utc::now());
+        }
+
+        if let Err(e) = self.process_verification_requests().await {
+            warn!("Failed to send verification messages: {}", e);
+        }
+        Ok(())
+    }
+
+    fn require_verification_machine(&self) -> Result<Arc<Mutex<VerificationMachine>>> {
+        self.verification_machine
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("End-to-end encryption is not enabled"))
+    }
+
+    /// Send a verification event to a room, encrypting it if the room is encrypted
+    async fn send_verification_room_event(
+        &self,
+        room_id: &str,
+        event_type: &str,
+        content: Value,
+    ) -> Result<String> {
+        if let Some(settings) = self.room_encryption(room_id).await? {
+            return self.send_encrypted_event(room_id, event_type, content, &settings).await;
+        }
+
+        let txn_id = uuid::Uuid::new_v4().to_string();
+        let path = format!(
+            "/_matrix/client/v3/rooms/{}/send/{}/{}",
+            urlencoding::encode(room_id),
+            urlencoding::encode(event_type),
+            txn_id
+        );
+        let request = self.authenticated_request(Method::PUT, &path)?;
+        let response = self.send(request.json(&content)).await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Failed to send verification event: {}", error_text));
+        }
+
+        #[derive(Deserialize)]
+        struct SendEventResponse {
+            event_id: String,
+        }
+
+        let send_response: SendEventResponse = response.json().await?;
+        Ok(send_response.event_id)
+    }
+}
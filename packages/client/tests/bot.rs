@@ -0,0 +1,221 @@
+//! Bots driven end-to-end against an in-process matryxd
+
+mod common;
+
+use anyhow::Result;
+use matryx_client::bot::{Bot, BotConfig, Command};
+use serde_json::{Value, json};
+use tokio::task::JoinHandle;
+
+use common::{TestServer, TestUser, wait_for};
+
+/// Runs a [`Bot`] as its own user against a [`TestServer`]
+struct BotHarness {
+    server: TestServer,
+    bot_user: TestUser,
+    task: JoinHandle<Result<()>>,
+}
+
+impl BotHarness {
+    async fn start(bot: Bot) -> Result<Self> {
+        Self::start_with(bot, false).await
+    }
+
+    /// Start a bot with end-to-end encryption enabled
+    async fn start_encrypted(bot: Bot) -> Result<Self> {
+        Self::start_with(bot, true).await
+    }
+
+    async fn start_with(bot: Bot, encryption: bool) -> Result<Self> {
+        let server = TestServer::start().await?;
+        let bot_user = server.create_user("bot").await?;
+        let client = if encryption {
+            bot_user.encrypted_realtime_client().await?
+        } else {
+            bot_user.realtime_client().await?
+        };
+        let task = tokio::spawn(bot.run(client));
+        Ok(Self { server, bot_user, task })
+    }
+
+    fn bot_id(&self) -> &str {
+        &self.bot_user.user_id
+    }
+
+    async fn user(&self, localpart: &str) -> Result<TestUser> {
+        self.server.create_user(localpart).await
+    }
+
+    /// Create a room owned by `owner`, inviting the bot and `invite`, and
+    /// wait for the bot to accept
+    async fn room_with_bot(&self, owner: &TestUser, invite: &[&str]) -> Result<String> {
+        let mut invite = invite.to_vec();
+        invite.push(self.bot_id());
+        let room_id = owner.create_room(&invite).await?;
+        owner.wait_for_member(&room_id, self.bot_id()).await?;
+        Ok(room_id)
+    }
+
+    /// Wait for `count` events the bot sent after `event_id`
+    async fn replies(
+        &self,
+        user: &TestUser,
+        room_id: &str,
+        event_id: &str,
+        count: usize,
+    ) -> Result<Vec<Value>> {
+        wait_for(&format!("{} bot events after {}", count, event_id), || async {
+            let events = user.messages(room_id).await?;
+            let replies: Vec<Value> = events
+                .into_iter()
+                .skip_while(|event| event["event_id"] != event_id)
+                .filter(|event| event["sender"] == self.bot_id())
+                .collect();
+            Ok((replies.len() >= count).then_some(replies))
+        })
+        .await
+    }
+}
+
+impl Drop for BotHarness {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+fn bot() -> Bot {
+    Bot::new(BotConfig {
+        invite_allowlist: vec!["@alice:localhost".to_string(), "@bob:localhost".to_string()],
+        ..Default::default()
+    })
+    .with_command(
+        Command::new("add", |mut context| async move {
+            let a: i64 = context.args.next("a")?;
+            let b: i64 = context.args.next("b")?;
+            context.args.finish()?;
+            context.reply(&(a + b).to_string()).await?;
+            Ok(())
+        })
+        .with_usage("<a> <b>"),
+    )
+    .with_command(
+        Command::new("kick", |context| async move {
+            context.react("👍").await?;
+            Ok(())
+        })
+        .with_power_level(50),
+    )
+    .with_command(Command::new("count", |context| async move {
+        let count: u64 = context.bot.state().get("count").await?.unwrap_or(0);
+        context.bot.state().set("count", &(count + 1)).await?;
+        context.send(&(count + 1).to_string()).await?;
+        Ok(())
+    }))
+}
+
+#[tokio::test]
+async fn test_command_reply() -> Result<()> {
+    let harness = BotHarness::start(bot()).await?;
+    let alice = harness.user("alice").await?;
+    let room_id = harness.room_with_bot(&alice, &[]).await?;
+
+    let event_id = alice.send_message(&room_id, "!add 2 40").await?;
+    let replies = harness.replies(&alice, &room_id, &event_id, 1).await?;
+    assert_eq!(replies[0]["content"]["body"], "42");
+    assert_eq!(replies[0]["content"]["m.relates_to"]["m.in_reply_to"]["event_id"], event_id);
+
+    let event_id = alice.send_message(&room_id, "!add 2 two").await?;
+    let replies = harness.replies(&alice, &room_id, &event_id, 1).await?;
+    assert_eq!(
+        replies[0]["content"]["body"],
+        "Invalid argument <b>: expected a whole number, got \"two\"\nUsage: !add <a> <b>"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_command_reply_in_encrypted_room() -> Result<()> {
+    let harness = BotHarness::start_encrypted(bot()).await?;
+    let alice_user = harness.user("alice").await?;
+    let mut alice = alice_user.matrix_client()?;
+    alice.enable_encryption().await?;
+
+    let room_id = alice_user.create_encrypted_room(&[harness.bot_id()]).await?;
+    alice_user.wait_for_member(&room_id, harness.bot_id()).await?;
+    let since = alice.sync(None, Some(0)).await?.next_batch;
+
+    // The bot has to decrypt the command to answer it
+    let event_id = alice.send_message(&room_id, "!add 2 40").await?;
+    let replies = harness.replies(&alice_user, &room_id, &event_id, 1).await?;
+    assert_eq!(replies[0]["type"], "m.room.encrypted");
+    assert!(replies[0]["content"].get("body").is_none());
+
+    // Syncing hands Alice the room key the bot shared with her
+    alice.sync(Some(&since), Some(0)).await?;
+    let reply_id = replies[0]["event_id"].as_str().unwrap_or_default();
+    let reply = alice.room_event(&room_id, reply_id).await?;
+    assert_eq!(reply.event_type, "m.room.message");
+    let content = serde_json::to_value(&reply.content)?;
+    assert_eq!(content["body"], "42");
+    assert_eq!(content["m.relates_to"]["m.in_reply_to"]["event_id"], event_id);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_auto_join_allowlist() -> Result<()> {
+    let harness = BotHarness::start(bot()).await?;
+    let mallory = harness.user("mallory").await?;
+    let alice = harness.user("alice").await?;
+
+    let ignored = mallory.create_room(&[harness.bot_id()]).await?;
+    // By the time the bot joined a later room it has seen the earlier invite
+    harness.room_with_bot(&alice, &[]).await?;
+    let members = mallory.joined_members(&ignored).await?;
+    assert_eq!(members, vec![mallory.user_id.clone()]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_power_level_check() -> Result<()> {
+    let harness = BotHarness::start(bot()).await?;
+    let alice = harness.user("alice").await?;
+    let bob = harness.user("bob").await?;
+    let room_id = harness.room_with_bot(&alice, &[&bob.user_id]).await?;
+    bob.join(&room_id).await?;
+
+    let event_id = bob.send_message(&room_id, "!kick").await?;
+    let replies = harness.replies(&bob, &room_id, &event_id, 1).await?;
+    assert_eq!(replies[0]["content"]["body"], "You need power level 50 to use !kick");
+
+    // The room creator has power level 100
+    let event_id = alice.send_message(&room_id, "!kick").await?;
+    let replies = harness.replies(&alice, &room_id, &event_id, 1).await?;
+    assert_eq!(replies[0]["type"], "m.reaction");
+    assert_eq!(
+        replies[0]["content"]["m.relates_to"],
+        json!({ "rel_type": "m.annotation", "event_id": event_id, "key": "👍" })
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_state_in_account_data() -> Result<()> {
+    let harness = BotHarness::start(bot()).await?;
+    let alice = harness.user("alice").await?;
+    let room_id = harness.room_with_bot(&alice, &[]).await?;
+
+    let first = alice.send_message(&room_id, "!count").await?;
+    harness.replies(&alice, &room_id, &first, 1).await?;
+    alice.send_message(&room_id, "!count").await?;
+    let replies = harness.replies(&alice, &room_id, &first, 2).await?;
+    assert_eq!(replies[0]["content"]["body"], "1");
+    assert_eq!(replies[1]["content"]["body"], "2");
+
+    let path = format!(
+        "/_matrix/client/v3/user/{}/account_data/org.matryx.bot.state",
+        urlencoding::encode(harness.bot_id())
+    );
+    let stored: Value = harness.bot_user.http().get(&path).await?;
+    assert_eq!(stored, json!({ "count": 2 }));
+    Ok(())
+}
@@ -0,0 +1,285 @@
+//! An in-process matryxd for integration tests
+//!
+//! [`TestServer`] serves the real homeserver router on an ephemeral port
+//! with a throwaway database. Accounts are created directly in the
+//! database, and [`TestUser`] talks to the server over the Client-Server
+//! API like any other client.
+
+// Each test binary uses a different part of the helpers
+#![allow(dead_code)]
+
+use anyhow::Result;
+use matryx_client::http_client::MatrixHttpClient;
+use matryx_client::login::MatrixSession;
+use matryx_client::realtime::RealtimeMatrixClient;
+use matryx_client::send_queue::EventTransport;
+use matryx_client::{ClientConfig, MatrixClient};
+use matryx_entity::{Device, RealtimeConfig, User};
+use matryx_server::modules::ModuleFactories;
+use matryx_server::server::Homeserver;
+use matryx_server::{AppState, ServerConfig};
+use matryx_surrealdb::repository::{DeviceRepository, UserRepository};
+use serde_json::{Value, json};
+use std::future::Future;
+use std::sync::Once;
+use std::time::Duration;
+use surrealdb::engine::any;
+use tempfile::TempDir;
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use url::Url;
+
+/// How long to wait for something to show up on the server
+const WAIT_TIMEOUT: Duration = Duration::from_secs(10);
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The server configuration every test server in this binary shares
+fn server_config() -> Result<&'static ServerConfig> {
+    static ENV: Once = Once::new();
+    ENV.call_once(|| {
+        // SAFETY: runs once, before any test server reads its configuration
+        unsafe {
+            std::env::set_var("HOMESERVER_NAME", "localhost");
+            std::env::set_var("USE_HTTPS", "false");
+            std::env::set_var("ALLOW_INSECURE_CONFIG", "true");
+            std::env::set_var("EMAIL_ENABLED", "false");
+            std::env::set_var("RATE_LIMIT_ENABLED", "false");
+            std::env::set_var("RATE_LIMIT_CLIENT_PER_MINUTE", "100000");
+        }
+    });
+    Ok(ServerConfig::init()?)
+}
+
+/// Run `check` until it returns a value or [`WAIT_TIMEOUT`] passes
+pub async fn wait_for<T, F, Fut>(what: &str, mut check: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<Option<T>>>,
+{
+    let deadline = tokio::time::Instant::now() + WAIT_TIMEOUT;
+    loop {
+        if let Some(value) = check().await? {
+            return Ok(value);
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return Err(anyhow::anyhow!("Timed out waiting for {}", what));
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// A homeserver served in-process
+pub struct TestServer {
+    url: Url,
+    state: AppState,
+    task: JoinHandle<()>,
+    _data_dir: TempDir,
+}
+
+impl TestServer {
+    pub async fn start() -> Result<Self> {
+        let config = server_config()?;
+
+        let data_dir = tempfile::tempdir()?;
+        let db =
+            any::connect(format!("surrealkv://{}", data_dir.path().join("matrix.db").display()))
+                .await?;
+        db.use_ns("matrix").use_db("homeserver").await?;
+
+        let homeserver = Homeserver::start(db, config, &ModuleFactories::builtin())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to start homeserver: {}", e))?;
+        let state = homeserver.state().clone();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = Url::parse(&format!("http://{}", listener.local_addr()?))?;
+        let task = tokio::spawn(async move {
+            if let Err(e) = homeserver.serve(listener).await {
+                tracing::error!("Test homeserver stopped: {}", e);
+            }
+        });
+
+        Ok(Self { url, state, task, _data_dir: data_dir })
+    }
+
+    /// Create an account with one device and log it in
+    pub async fn create_user(&self, localpart: &str) -> Result<TestUser> {
+        let user_id = format!("@{}:{}", localpart, self.state.homeserver_name);
+        let device_id = format!("{}DEVICE", localpart.to_uppercase());
+
+        // Accounts have no password; clients resume the session instead
+        UserRepository::new(self.state.db.clone())
+            .create(&User::new(user_id.clone(), String::new()))
+            .await?;
+        DeviceRepository::new(self.state.db.clone())
+            .create(&Device {
+                device_id: device_id.clone(),
+                user_id: user_id.clone(),
+                display_name: None,
+                last_seen_ip: None,
+                last_seen_ts: None,
+                created_at: chrono::Utc::now(),
+                hidden: Some(false),
+                device_keys: None,
+                one_time_keys: None,
+                fallback_keys: None,
+                user_agent: None,
+                initial_device_display_name: None,
+            })
+            .await?;
+        let access_token = self
+            .state
+            .session_service
+            .create_access_token(&user_id, &device_id)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to create access token: {:?}", e))?;
+
+        let http = MatrixHttpClient::new(self.url.clone())?;
+        http.set_access_token(access_token.clone()).await;
+        Ok(TestUser {
+            user_id,
+            device_id,
+            access_token,
+            homeserver_url: self.url.clone(),
+            http,
+        })
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// An account on a [`TestServer`]
+pub struct TestUser {
+    pub user_id: String,
+    pub device_id: String,
+    pub access_token: String,
+    homeserver_url: Url,
+    http: MatrixHttpClient,
+}
+
+impl TestUser {
+    pub fn http(&self) -> &MatrixHttpClient {
+        &self.http
+    }
+
+    /// A client logged in as this user
+    pub fn matrix_client(&self) -> Result<MatrixClient> {
+        let mut client = MatrixClient::new(ClientConfig {
+            homeserver_url: self.homeserver_url.clone(),
+            ..Default::default()
+        })?;
+        client.restore_session(MatrixSession {
+            homeserver_url: self.homeserver_url.clone(),
+            user_id: self.user_id.clone(),
+            device_id: Some(self.device_id.clone()),
+            access_token: self.access_token.clone(),
+            refresh_token: None,
+            oauth: None,
+        });
+        Ok(client)
+    }
+
+    /// A realtime client syncing as this user
+    pub async fn realtime_client(&self) -> Result<RealtimeMatrixClient> {
+        self.connect_realtime_client(false).await
+    }
+
+    /// A realtime client syncing as this user with end-to-end encryption
+    pub async fn encrypted_realtime_client(&self) -> Result<RealtimeMatrixClient> {
+        self.connect_realtime_client(true).await
+    }
+
+    async fn connect_realtime_client(&self, encryption: bool) -> Result<RealtimeMatrixClient> {
+        let mut client = RealtimeMatrixClient::new(RealtimeConfig {
+            homeserver_url: self.homeserver_url.clone(),
+            sync_timeout_secs: 1,
+            ..Default::default()
+        })?;
+        if encryption {
+            client = client.with_encryption();
+        }
+        client
+            .restore_session(&self.user_id, &self.access_token, &self.device_id)
+            .await?;
+        Ok(client)
+    }
+
+    /// Create a room, inviting `invite`
+    pub async fn create_room(&self, invite: &[&str]) -> Result<String> {
+        let response: Value = self
+            .http
+            .post("/_matrix/client/v3/createRoom", &json!({ "invite": invite }))
+            .await?;
+        response["room_id"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow::anyhow!("createRoom returned no room ID"))
+    }
+
+    /// Create a Megolm-encrypted room, inviting `invite`
+    pub async fn create_encrypted_room(&self, invite: &[&str]) -> Result<String> {
+        let body = json!({
+            "invite": invite,
+            "initial_state": [{
+                "type": "m.room.encryption",
+                "state_key": "",
+                "content": { "algorithm": "m.megolm.v1.aes-sha2" },
+            }],
+        });
+        let response: Value = self.http.post("/_matrix/client/v3/createRoom", &body).await?;
+        response["room_id"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow::anyhow!("createRoom returned no room ID"))
+    }
+
+    pub async fn join(&self, room_id: &str) -> Result<()> {
+        let path = format!("/_matrix/client/v3/rooms/{}/join", urlencoding::encode(room_id));
+        let _: Value = self.http.post(&path, &json!({})).await?;
+        Ok(())
+    }
+
+    /// Send a text message, returning its event ID
+    pub async fn send_message(&self, room_id: &str, body: &str) -> Result<String> {
+        let content = json!({ "msgtype": "m.text", "body": body });
+        Ok(self
+            .http
+            .send_event(room_id, "m.room.message", &content, &uuid::Uuid::new_v4().to_string())
+            .await?)
+    }
+
+    pub async fn joined_members(&self, room_id: &str) -> Result<Vec<String>> {
+        let path =
+            format!("/_matrix/client/v3/rooms/{}/joined_members", urlencoding::encode(room_id));
+        let response: Value = self.http.get(&path).await?;
+        Ok(response["joined"]
+            .as_object()
+            .map(|joined| joined.keys().cloned().collect())
+            .unwrap_or_default())
+    }
+
+    /// The latest timeline events of a room, oldest first
+    pub async fn messages(&self, room_id: &str) -> Result<Vec<Value>> {
+        let path = format!(
+            "/_matrix/client/v3/rooms/{}/messages?dir=b&limit=50",
+            urlencoding::encode(room_id)
+        );
+        let response: Value = self.http.get(&path).await?;
+        let mut events = response["chunk"].as_array().cloned().unwrap_or_default();
+        events.reverse();
+        Ok(events)
+    }
+
+    /// Wait until `user_id` joined a room
+    pub async fn wait_for_member(&self, room_id: &str, user_id: &str) -> Result<()> {
+        wait_for(&format!("{} to join {}", user_id, room_id), || async {
+            let members = self.joined_members(room_id).await?;
+            Ok(members.iter().any(|member| member == user_id).then_some(()))
+        })
+        .await
+    }
+}
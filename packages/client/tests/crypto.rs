@@ -0,0 +1,140 @@
+//! End-to-end encryption between two clients through an in-process matryxd
+
+mod common;
+
+use anyhow::Result;
+use matryx_client::crypto::MEGOLM_V1_ALGORITHM;
+use matryx_client::{MatrixClient, SyncResponse};
+use matryx_entity::Event;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use common::TestServer;
+
+/// Sync until `event_id` shows up in a room's timeline, keeping every
+/// to-device event seen on the way
+async fn sync_until_event(
+    client: &mut MatrixClient,
+    since: &str,
+    room_id: &str,
+    event_id: &str,
+) -> Result<(Event, Vec<Value>)> {
+    let mut since = since.to_string();
+    let mut to_device = Vec::new();
+    for _ in 0..50 {
+        let response: SyncResponse = client.sync(Some(&since), Some(0)).await?;
+        since = response.next_batch.clone();
+        if let Some(events) = response
+            .to_device
+            .as_ref()
+            .and_then(|to_device| to_device.get("events"))
+            .and_then(Value::as_array)
+        {
+            to_device.extend(events.iter().cloned());
+        }
+
+        let event = response
+            .rooms
+            .join
+            .get(room_id)
+            .and_then(|room| room.timeline.as_ref())
+            .and_then(|timeline| timeline.events.iter().find(|event| event.event_id == event_id));
+        if let Some(event) = event {
+            return Ok((event.clone(), to_device));
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    Err(anyhow::anyhow!("{} never arrived through /sync", event_id))
+}
+
+#[tokio::test]
+async fn test_megolm_message_through_matryxd() -> Result<()> {
+    let server = TestServer::start().await?;
+    let alice_user = server.create_user("alice").await?;
+    let bob_user = server.create_user("bob").await?;
+
+    // Enabling encryption uploads device keys and signed one-time keys
+    let mut alice = alice_user.matrix_client()?;
+    let mut bob = bob_user.matrix_client()?;
+    alice.enable_encryption().await?;
+    bob.enable_encryption().await?;
+
+    let bob_machine = bob.olm_machine().ok_or_else(|| anyhow::anyhow!("No Olm machine"))?;
+    let bob_curve25519_key = bob_machine.lock().await.curve25519_key();
+    let query = HashMap::from([(bob_user.user_id.clone(), Vec::new())]);
+    let response = alice.query_keys(query).await?;
+    let bob_keys = &response.device_keys[&bob_user.user_id][&bob_user.device_id];
+    assert_eq!(bob_keys.keys[&format!("curve25519:{}", bob_user.device_id)], bob_curve25519_key);
+
+    let room_id = alice_user.create_encrypted_room(&[&bob_user.user_id]).await?;
+    bob.join_room(&room_id).await?;
+    let since = bob.sync(None, Some(0)).await?.next_batch;
+
+    // Sending queries Bob's devices, claims one of his one-time keys and
+    // shares the Megolm key with him over to-device before encrypting
+    let event_id = alice.send_message(&room_id, "Hello, Bob").await?;
+
+    // The server only ever sees the ciphertext
+    let stored = alice_user.messages(&room_id).await?;
+    let stored = stored
+        .iter()
+        .find(|event| event["event_id"] == event_id.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Sent event is not in the room"))?;
+    assert_eq!(stored["type"], "m.room.encrypted");
+    assert_eq!(stored["content"]["algorithm"], MEGOLM_V1_ALGORITHM);
+    assert!(stored["content"].get("body").is_none());
+
+    let (event, to_device) = sync_until_event(&mut bob, &since, &room_id, &event_id).await?;
+    let room_key = to_device
+        .iter()
+        .find(|event| event["type"] == "m.room_key")
+        .ok_or_else(|| anyhow::anyhow!("Bob received no room key"))?;
+    assert_eq!(room_key["sender"], alice_user.user_id.as_str());
+    assert_eq!(room_key["content"]["room_id"], room_id.as_str());
+
+    // Knowing Alice's device lets Bob tell the key really came from it
+    bob_machine.lock().await.update_tracked_users([alice_user.user_id.clone()]);
+    bob.process_crypto_requests().await?;
+
+    let decrypted = bob.decrypt_event(&event).await?;
+    assert_eq!(decrypted.event_type, "m.room.message");
+    assert_eq!(decrypted.content["body"], "Hello, Bob");
+    assert_eq!(decrypted.content["msgtype"], "m.text");
+    assert_eq!(decrypted.message_index, 0);
+    assert!(decrypted.verified_sender_device);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_queued_message_to_encrypted_room_is_encrypted() -> Result<()> {
+    let server = TestServer::start().await?;
+    let alice_user = server.create_user("alice").await?;
+    let bob_user = server.create_user("bob").await?;
+
+    let alice = alice_user.encrypted_realtime_client().await?;
+    let mut bob = bob_user.matrix_client()?;
+    bob.enable_encryption().await?;
+
+    let room_id = alice_user.create_encrypted_room(&[&bob_user.user_id]).await?;
+    bob.join_room(&room_id).await?;
+    let since = bob.sync(None, Some(0)).await?.next_batch;
+
+    // The send queue encrypts for the room before handing the event over
+    let event_id = alice.send_message(&room_id, "Hello, Bob").await?;
+
+    let stored = alice_user.messages(&room_id).await?;
+    let stored = stored
+        .iter()
+        .find(|event| event["event_id"] == event_id.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Sent event is not in the room"))?;
+    assert_eq!(stored["type"], "m.room.encrypted");
+    assert_eq!(stored["content"]["algorithm"], MEGOLM_V1_ALGORITHM);
+    assert!(stored["content"].get("body").is_none());
+
+    let (event, _) = sync_until_event(&mut bob, &since, &room_id, &event_id).await?;
+    let decrypted = bob.decrypt_event(&event).await?;
+    assert_eq!(decrypted.event_type, "m.room.message");
+    assert_eq!(decrypted.content["body"], "Hello, Bob");
+    Ok(())
+}
@@ -0,0 +1,127 @@
+/// Generates an `Any*Event` enum over typed event envelopes
+///
+/// Each variant lists the event types it is parsed from. Types without a
+/// variant land in `Custom` with their content kept as raw JSON, so unknown
+/// events round-trip unchanged. The generated `from_value_unchecked` and
+/// `validate_typed_content` only dispatch on the type; room-version checks
+/// that need the raw JSON are done by the public constructors of each enum.
+macro_rules! any_event_enum {
+    (
+        $(#[$meta:meta])*
+        $name:ident over $envelope:ident {
+            $(
+                $(#[$variant_meta:meta])*
+                $variant:ident($content:ty) = [$($event_type:literal),+ $(,)?],
+            )*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone)]
+        #[allow(clippy::large_enum_variant)]
+        pub enum $name {
+            $(
+                $(#[$variant_meta])*
+                $variant($envelope<$content>),
+            )*
+
+            /// Event type without a typed content model; content is raw JSON
+            Custom($envelope<serde_json::Value>),
+        }
+
+        impl $name {
+            fn from_value_unchecked(
+                event_type: &str,
+                value: serde_json::Value,
+                rules: &$crate::types::RoomVersionRules,
+            ) -> Result<Self, $crate::types::EventContentError> {
+                let deserialization_error =
+                    |e: serde_json::Error| $crate::types::EventContentError::Deserialization {
+                        event_type: event_type.to_string(),
+                        message: e.to_string(),
+                    };
+
+                match event_type {
+                    $(
+                        $($event_type)|+ => {
+                            let event: $envelope<$content> =
+                                serde_json::from_value(value).map_err(deserialization_error)?;
+                            $crate::types::TypedEventContent::validate(&event.content, rules)?;
+                            Ok(Self::$variant(event))
+                        },
+                    )*
+                    _ => Ok(Self::Custom(
+                        serde_json::from_value(value).map_err(deserialization_error)?,
+                    )),
+                }
+            }
+
+            fn validate_typed_content(
+                event_type: &str,
+                content: &serde_json::Value,
+                rules: &$crate::types::RoomVersionRules,
+            ) -> Result<(), $crate::types::EventContentError> {
+                match event_type {
+                    $(
+                        $($event_type)|+ => {
+                            let content: $content = serde_json::from_value(content.clone())
+                                .map_err(|e| $crate::types::EventContentError::Deserialization {
+                                    event_type: event_type.to_string(),
+                                    message: e.to_string(),
+                                })?;
+                            $crate::types::TypedEventContent::validate(&content, rules)
+                        },
+                    )*
+                    _ => Ok(()),
+                }
+            }
+
+            /// Whether `event_type` has a typed content model in this enum
+            pub fn is_known_type(event_type: &str) -> bool {
+                matches!(event_type, $($($event_type)|+)|*)
+            }
+
+            /// Common envelope fields of the wrapped event
+            pub fn envelope(&self) -> &dyn $crate::types::EventEnvelope {
+                match self {
+                    $(Self::$variant(event) => event,)*
+                    Self::Custom(event) => event,
+                }
+            }
+
+            pub fn event_type(&self) -> &str {
+                self.envelope().event_type()
+            }
+
+            pub fn event_id(&self) -> Option<&str> {
+                self.envelope().event_id()
+            }
+
+            pub fn sender(&self) -> &str {
+                self.envelope().sender()
+            }
+
+            pub fn origin_server_ts(&self) -> i64 {
+                self.envelope().origin_server_ts()
+            }
+
+            pub fn room_id(&self) -> Option<&str> {
+                self.envelope().room_id()
+            }
+
+            pub fn unsigned(&self) -> Option<&serde_json::Value> {
+                self.envelope().unsigned()
+            }
+        }
+
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                match self {
+                    $(Self::$variant(event) => event.serialize(serializer),)*
+                    Self::Custom(event) => event.serialize(serializer),
+                }
+            }
+        }
+    };
+}
+
+pub(crate) use any_event_enum;
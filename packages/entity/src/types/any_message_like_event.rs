@@ -0,0 +1,93 @@
+use crate::types::{
+    any_event_enum::any_event_enum, CallAnswerContent, CallCandidatesContent, CallHangupContent,
+    CallInviteContent, CallNegotiateContent, CallRejectContent, CallSelectAnswerContent, Event,
+    EventContentError, MessageLikeEvent, ReactionContent, RoomEncryptedContent, RoomMessageContent,
+    RoomRedactionContent, RoomVersionRules, StickerContent, VerificationAccept, VerificationCancel,
+    VerificationDone, VerificationKey, VerificationMAC, VerificationReady, VerificationStart,
+};
+use serde_json::Value;
+
+any_event_enum! {
+    /// AnyMessageLikeEvent - A non-state timeline event with typed content
+    ///
+    /// Parse with [`AnyMessageLikeEvent::parse`], which applies the event
+    /// format rules of the room's version.
+    AnyMessageLikeEvent over MessageLikeEvent {
+        RoomMessage(RoomMessageContent) = ["m.room.message"],
+        RoomEncrypted(RoomEncryptedContent) = ["m.room.encrypted"],
+        RoomRedaction(RoomRedactionContent) = ["m.room.redaction"],
+        Reaction(ReactionContent) = ["m.reaction"],
+        Sticker(StickerContent) = ["m.sticker"],
+        CallInvite(CallInviteContent) = ["m.call.invite"],
+        CallCandidates(CallCandidatesContent) = ["m.call.candidates"],
+        CallAnswer(CallAnswerContent) = ["m.call.answer"],
+        CallSelectAnswer(CallSelectAnswerContent) = ["m.call.select_answer"],
+        CallNegotiate(CallNegotiateContent) = ["m.call.negotiate"],
+        CallReject(CallRejectContent) = ["m.call.reject"],
+        CallHangup(CallHangupContent) = ["m.call.hangup"],
+        KeyVerificationReady(VerificationReady) = ["m.key.verification.ready"],
+        KeyVerificationStart(VerificationStart) = ["m.key.verification.start"],
+        KeyVerificationAccept(VerificationAccept) = ["m.key.verification.accept"],
+        KeyVerificationKey(VerificationKey) = ["m.key.verification.key"],
+        KeyVerificationMac(VerificationMAC) = ["m.key.verification.mac"],
+        KeyVerificationCancel(VerificationCancel) = ["m.key.verification.cancel"],
+        KeyVerificationDone(VerificationDone) = ["m.key.verification.done"],
+    }
+}
+
+impl AnyMessageLikeEvent {
+    /// Parse and validate a message-like event from its JSON form
+    pub fn parse(value: Value, rules: &RoomVersionRules) -> Result<Self, EventContentError> {
+        let event_type = value
+            .get("type")
+            .and_then(Value::as_str)
+            .ok_or_else(|| EventContentError::MissingField("type".to_string()))?
+            .to_string();
+
+        if value.get("state_key").is_some() {
+            return Err(EventContentError::invalid_field(
+                "state_key",
+                "message-like events must not have a state_key",
+            ));
+        }
+
+        // Before room version 11 the redaction target is a top-level key
+        if event_type == RoomRedactionContent::EVENT_TYPE
+            && !rules.redacts_in_content
+            && value.get("redacts").and_then(Value::as_str).is_none()
+        {
+            return Err(EventContentError::MissingField("redacts".to_string()));
+        }
+
+        Self::from_value_unchecked(&event_type, value, rules)
+    }
+
+    /// Parse and validate a stored event
+    pub fn from_event(event: &Event, rules: &RoomVersionRules) -> Result<Self, EventContentError> {
+        let value =
+            serde_json::to_value(event).map_err(|e| EventContentError::Deserialization {
+                event_type: event.event_type.clone(),
+                message: e.to_string(),
+            })?;
+        Self::parse(value, rules)
+    }
+
+    /// Validate content for a message-like event about to be sent
+    pub fn validate_content(
+        event_type: &str,
+        content: &Value,
+        rules: &RoomVersionRules,
+    ) -> Result<(), EventContentError> {
+        Self::validate_typed_content(event_type, content, rules)
+    }
+
+    /// Event ID targeted by a redaction, wherever the room version puts it
+    pub fn redacts(&self) -> Option<&str> {
+        match self {
+            AnyMessageLikeEvent::RoomRedaction(event) => {
+                event.content.redacts.as_deref().or(event.redacts.as_deref())
+            },
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use crate::types::{
+    any_event_enum::any_event_enum, Event, EventContentError, MembershipEventContent,
+    PolicyRuleContent, PowerLevels, RoomAvatarContent, RoomCanonicalAliasContent,
+    RoomCreateContent, RoomEncryptionContent, RoomGuestAccessContent, RoomHistoryVisibilityContent,
+    RoomJoinRulesContent, RoomNameContent, RoomPinnedEventsContent, RoomRetentionContent,
+    RoomServerAclContent, RoomThirdPartyInviteContent, RoomTombstoneContent, RoomTopicContent,
+    RoomVersionRules, SpaceChildEvent, SpaceParentEvent, StateEvent,
+};
+use serde_json::Value;
+
+any_event_enum! {
+    /// AnyStateEvent - A state event of any type with typed content
+    ///
+    /// Parse with [`AnyStateEvent::parse`], which applies the event format
+    /// rules of the room's version.
+    AnyStateEvent over StateEvent {
+        RoomCreate(RoomCreateContent) = ["m.room.create"],
+        RoomMember(MembershipEventContent) = ["m.room.member"],
+        RoomPowerLevels(PowerLevels) = ["m.room.power_levels"],
+        RoomJoinRules(RoomJoinRulesContent) = ["m.room.join_rules"],
+        RoomHistoryVisibility(RoomHistoryVisibilityContent) = ["m.room.history_visibility"],
+        RoomGuestAccess(RoomGuestAccessContent) = ["m.room.guest_access"],
+        RoomName(RoomNameContent) = ["m.room.name"],
+        RoomTopic(RoomTopicContent) = ["m.room.topic"],
+        RoomAvatar(RoomAvatarContent) = ["m.room.avatar"],
+        RoomCanonicalAlias(RoomCanonicalAliasContent) = ["m.room.canonical_alias"],
+        RoomEncryption(RoomEncryptionContent) = ["m.room.encryption"],
+        RoomServerAcl(RoomServerAclContent) = ["m.room.server_acl"],
+        RoomRetention(RoomRetentionContent) = ["m.room.retention"],
+        RoomTombstone(RoomTombstoneContent) = ["m.room.tombstone"],
+        RoomPinnedEvents(RoomPinnedEventsContent) = ["m.room.pinned_events"],
+        RoomThirdPartyInvite(RoomThirdPartyInviteContent) = ["m.room.third_party_invite"],
+        SpaceChild(SpaceChildEvent) = ["m.space.child"],
+        SpaceParent(SpaceParentEvent) = ["m.space.parent"],
+        /// `m.policy.rule.user`, `m.policy.rule.room` and `m.policy.rule.server`
+        PolicyRule(PolicyRuleContent) = [
+            "m.policy.rule.user",
+            "m.policy.rule.room",
+            "m.policy.rule.server",
+        ],
+    }
+}
+
+impl AnyStateEvent {
+    /// Parse and validate a state event from its JSON form
+    pub fn parse(value: Value, rules: &RoomVersionRules) -> Result<Self, EventContentError> {
+        let event_type = value
+            .get("type")
+            .and_then(Value::as_str)
+            .ok_or_else(|| EventContentError::MissingField("type".to_string()))?
+            .to_string();
+
+        if value.get("state_key").and_then(Value::as_str).is_none() {
+            return Err(EventContentError::MissingStateKey(event_type));
+        }
+
+        if let Some(content) = value.get("content") {
+            Self::check_raw_content(&event_type, content, rules)?;
+        }
+
+        Self::from_value_unchecked(&event_type, value, rules)
+    }
+
+    /// Parse and validate a stored event
+    pub fn from_event(event: &Event, rules: &RoomVersionRules) -> Result<Self, EventContentError> {
+        let value =
+            serde_json::to_value(event).map_err(|e| EventContentError::Deserialization {
+                event_type: event.event_type.clone(),
+                message: e.to_string(),
+            })?;
+        Self::parse(value, rules)
+    }
+
+    /// Validate content for a state event about to be sent
+    pub fn validate_content(
+        event_type: &str,
+        content: &Value,
+        rules: &RoomVersionRules,
+    ) -> Result<(), EventContentError> {
+        Self::check_raw_content(event_type, content, rules)?;
+        Self::validate_typed_content(event_type, content, rules)
+    }
+
+    pub fn state_key(&self) -> &str {
+        self.envelope().state_key().unwrap_or_default()
+    }
+
+    /// Room-version rules that can only be checked on the original JSON
+    fn check_raw_content(
+        event_type: &str,
+        content: &Value,
+        rules: &RoomVersionRules,
+    ) -> Result<(), EventContentError> {
+        if rules.integer_power_levels && event_type == PowerLevels::EVENT_TYPE {
+            PowerLevels::check_integer_values(content)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,253 @@
+use crate::types::{
+    AnyMessageLikeEvent, AnyStateEvent, Event, EventContentError, RoomVersionRules,
+};
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+
+/// AnyTimelineEvent - Any room event that can appear in a timeline
+///
+/// Events carrying a `state_key` are state events; everything else is
+/// message-like. Unknown event types are kept in the `Custom` variants of
+/// the inner enums and serialize back unchanged.
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+pub enum AnyTimelineEvent {
+    MessageLike(AnyMessageLikeEvent),
+    State(AnyStateEvent),
+}
+
+impl AnyTimelineEvent {
+    /// Parse and validate a timeline event from its JSON form
+    pub fn parse(value: Value, rules: &RoomVersionRules) -> Result<Self, EventContentError> {
+        if value.get("state_key").is_some() {
+            AnyStateEvent::parse(value, rules).map(AnyTimelineEvent::State)
+        } else {
+            AnyMessageLikeEvent::parse(value, rules).map(AnyTimelineEvent::MessageLike)
+        }
+    }
+
+    /// Parse and validate a stored event
+    pub fn from_event(event: &Event, rules: &RoomVersionRules) -> Result<Self, EventContentError> {
+        if event.state_key.is_some() {
+            AnyStateEvent::from_event(event, rules).map(AnyTimelineEvent::State)
+        } else {
+            AnyMessageLikeEvent::from_event(event, rules).map(AnyTimelineEvent::MessageLike)
+        }
+    }
+
+    /// Validate content for an event about to be sent
+    pub fn validate_content(
+        event_type: &str,
+        state_key: Option<&str>,
+        content: &Value,
+        rules: &RoomVersionRules,
+    ) -> Result<(), EventContentError> {
+        match state_key {
+            Some(_) => AnyStateEvent::validate_content(event_type, content, rules),
+            None => AnyMessageLikeEvent::validate_content(event_type, content, rules),
+        }
+    }
+
+    pub fn event_type(&self) -> &str {
+        match self {
+            AnyTimelineEvent::MessageLike(event) => event.event_type(),
+            AnyTimelineEvent::State(event) => event.event_type(),
+        }
+    }
+
+    pub fn event_id(&self) -> Option<&str> {
+        match self {
+            AnyTimelineEvent::MessageLike(event) => event.event_id(),
+            AnyTimelineEvent::State(event) => event.event_id(),
+        }
+    }
+
+    pub fn sender(&self) -> &str {
+        match self {
+            AnyTimelineEvent::MessageLike(event) => event.sender(),
+            AnyTimelineEvent::State(event) => event.sender(),
+        }
+    }
+
+    pub fn origin_server_ts(&self) -> i64 {
+        match self {
+            AnyTimelineEvent::MessageLike(event) => event.origin_server_ts(),
+            AnyTimelineEvent::State(event) => event.origin_server_ts(),
+        }
+    }
+
+    pub fn room_id(&self) -> Option<&str> {
+        match self {
+            AnyTimelineEvent::MessageLike(event) => event.room_id(),
+            AnyTimelineEvent::State(event) => event.room_id(),
+        }
+    }
+
+    pub fn state_key(&self) -> Option<&str> {
+        match self {
+            AnyTimelineEvent::MessageLike(_) => None,
+            AnyTimelineEvent::State(event) => Some(event.state_key()),
+        }
+    }
+
+    pub fn is_state(&self) -> bool {
+        matches!(self, AnyTimelineEvent::State(_))
+    }
+}
+
+impl Serialize for AnyTimelineEvent {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            AnyTimelineEvent::MessageLike(event) => event.serialize(serializer),
+            AnyTimelineEvent::State(event) => event.serialize(serializer),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::MessageType;
+    use serde_json::json;
+
+    type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+    fn rules(version: &str) -> Result<RoomVersionRules, Box<dyn std::error::Error>> {
+        RoomVersionRules::for_version(version).ok_or_else(|| "unknown room version".into())
+    }
+
+    #[test]
+    fn test_message_round_trips_unknown_fields() -> TestResult {
+        let value = json!({
+            "type": "m.room.message",
+            "event_id": "$abc:example.org",
+            "sender": "@alice:example.org",
+            "origin_server_ts": 1,
+            "room_id": "!room:example.org",
+            "content": {
+                "msgtype": "m.text",
+                "body": "hello",
+                "com.example.extra": {"nested": true},
+                "m.relates_to": {"m.in_reply_to": {"event_id": "$parent:example.org"}},
+            },
+            "hashes": {"sha256": "abc"},
+        });
+
+        let event = AnyTimelineEvent::parse(value.clone(), &RoomVersionRules::latest())?;
+        let AnyTimelineEvent::MessageLike(AnyMessageLikeEvent::RoomMessage(message)) = &event
+        else {
+            return Err("expected m.room.message".into());
+        };
+        assert!(matches!(message.content.msgtype, MessageType::Text(_)));
+        assert_eq!(message.content.body(), "hello");
+        assert_eq!(message.content.in_reply_to(), Some("$parent:example.org"));
+        assert_eq!(serde_json::to_value(&event)?, value);
+        Ok(())
+    }
+
+    #[test]
+    fn test_custom_events_and_msgtypes_are_preserved() -> TestResult {
+        let custom = json!({
+            "type": "com.example.event",
+            "sender": "@alice:example.org",
+            "origin_server_ts": 1,
+            "content": {"anything": [1, 2, 3]},
+        });
+        let event = AnyTimelineEvent::parse(custom.clone(), &RoomVersionRules::latest())?;
+        assert!(matches!(event, AnyTimelineEvent::MessageLike(AnyMessageLikeEvent::Custom(_))));
+        assert_eq!(serde_json::to_value(&event)?, custom);
+
+        let unknown_msgtype = json!({
+            "type": "m.room.message",
+            "sender": "@alice:example.org",
+            "origin_server_ts": 1,
+            "content": {"msgtype": "com.example.poll", "body": "?", "answers": ["a"]},
+        });
+        let event = AnyTimelineEvent::parse(unknown_msgtype.clone(), &RoomVersionRules::latest())?;
+        assert_eq!(serde_json::to_value(&event)?, unknown_msgtype);
+        Ok(())
+    }
+
+    #[test]
+    fn test_redaction_location_depends_on_room_version() -> TestResult {
+        let legacy = json!({
+            "type": "m.room.redaction",
+            "sender": "@alice:example.org",
+            "origin_server_ts": 1,
+            "redacts": "$target:example.org",
+            "content": {"reason": "spam"},
+        });
+        let event = AnyMessageLikeEvent::parse(legacy.clone(), &rules("10")?)?;
+        assert_eq!(event.redacts(), Some("$target:example.org"));
+        assert!(AnyMessageLikeEvent::parse(legacy, &rules("11")?).is_err());
+
+        let v11 = json!({
+            "type": "m.room.redaction",
+            "sender": "@alice:example.org",
+            "origin_server_ts": 1,
+            "content": {"redacts": "$target:example.org"},
+        });
+        assert_eq!(
+            AnyMessageLikeEvent::parse(v11.clone(), &rules("11")?)?.redacts(),
+            Some("$target:example.org")
+        );
+        assert!(matches!(
+            AnyMessageLikeEvent::parse(v11, &rules("9")?),
+            Err(EventContentError::MissingField(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_state_event_validation() -> TestResult {
+        let power_levels = json!({
+            "type": "m.room.power_levels",
+            "sender": "@alice:example.org",
+            "origin_server_ts": 1,
+            "state_key": "",
+            "content": {"users": {"@alice:example.org": "100"}, "ban": "50"},
+        });
+        let event = AnyStateEvent::parse(power_levels.clone(), &rules("9")?)?;
+        let AnyStateEvent::RoomPowerLevels(levels) = &event else {
+            return Err("expected m.room.power_levels".into());
+        };
+        assert_eq!(levels.content.get_user_level("@alice:example.org"), 100);
+        assert!(AnyStateEvent::parse(power_levels, &rules("10")?).is_err());
+
+        let create_without_creator = json!({
+            "type": "m.room.create",
+            "sender": "@alice:example.org",
+            "origin_server_ts": 1,
+            "state_key": "",
+            "content": {"room_version": "11"},
+        });
+        assert!(AnyStateEvent::parse(create_without_creator.clone(), &rules("11")?).is_ok());
+        assert!(AnyStateEvent::parse(create_without_creator, &rules("10")?).is_err());
+
+        let knock = json!({"join_rule": "knock"});
+        assert!(AnyStateEvent::validate_content("m.room.join_rules", &knock, &rules("7")?).is_ok());
+        assert!(AnyStateEvent::validate_content("m.room.join_rules", &knock, &rules("6")?).is_err());
+
+        let missing_state_key = json!({
+            "type": "m.room.name",
+            "sender": "@alice:example.org",
+            "origin_server_ts": 1,
+            "content": {"name": "Room"},
+        });
+        assert!(matches!(
+            AnyStateEvent::parse(missing_state_key, &RoomVersionRules::latest()),
+            Err(EventContentError::MissingStateKey(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_reaction_validation() -> TestResult {
+        let valid = json!({"m.relates_to": {"rel_type": "m.annotation", "event_id": "$e:example.org", "key": "👍"}});
+        let wrong_rel = json!({"m.relates_to": {"rel_type": "m.reference", "event_id": "$e:example.org", "key": "👍"}});
+        let rules = RoomVersionRules::latest();
+        assert!(AnyTimelineEvent::validate_content("m.reaction", None, &valid, &rules).is_ok());
+        assert!(AnyTimelineEvent::validate_content("m.reaction", None, &wrong_rel, &rules).is_err());
+        Ok(())
+    }
+}
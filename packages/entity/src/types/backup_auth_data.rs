@@ -1,16 +1,18 @@
-use crate::types::SignatureMap;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// BackupAuthData
 /// Source: spec/client/04_security_md:1895-1897
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BackupAuthData {
     pub public_key: String,
-    pub signatures: SignatureMap,
+    /// Signatures by user ID and key ID
+    #[serde(default)]
+    pub signatures: HashMap<String, HashMap<String, String>>,
 }
 
 impl BackupAuthData {
-    pub fn new(public_key: String, signatures: SignatureMap) -> Self {
+    pub fn new(public_key: String, signatures: HashMap<String, HashMap<String, String>>) -> Self {
         Self { public_key, signatures }
     }
 }
@@ -0,0 +1,34 @@
+use crate::types::{
+    call_version::validate_call_fields, CallSessionDescription, CallVersion, EventContentError,
+    RoomVersionRules, TypedEventContent,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// CallAnswerContent - Content of `m.call.answer`
+/// Source: spec/client/06_modules_md (m.call.answer)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallAnswerContent {
+    pub call_id: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub party_id: Option<String>,
+
+    pub version: CallVersion,
+
+    pub answer: CallSessionDescription,
+
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
+}
+
+impl CallAnswerContent {
+    pub const EVENT_TYPE: &'static str = "m.call.answer";
+}
+
+impl TypedEventContent for CallAnswerContent {
+    fn validate(&self, _rules: &RoomVersionRules) -> Result<(), EventContentError> {
+        validate_call_fields(&self.call_id, &self.version, self.party_id.as_deref())
+    }
+}
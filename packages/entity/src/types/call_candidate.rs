@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// CallCandidate - An ICE candidate in `m.call.candidates`
+/// Source: spec/client/06_modules_md (m.call.candidates)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallCandidate {
+    pub candidate: String,
+
+    #[serde(rename = "sdpMid", skip_serializing_if = "Option::is_none")]
+    pub sdp_mid: Option<String>,
+
+    #[serde(rename = "sdpMLineIndex", skip_serializing_if = "Option::is_none")]
+    pub sdp_m_line_index: Option<u64>,
+
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
+}
@@ -0,0 +1,34 @@
+use crate::types::{
+    call_version::validate_call_fields, CallCandidate, CallVersion, EventContentError,
+    RoomVersionRules, TypedEventContent,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// CallCandidatesContent - Content of `m.call.candidates`
+/// Source: spec/client/06_modules_md (m.call.candidates)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallCandidatesContent {
+    pub call_id: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub party_id: Option<String>,
+
+    pub version: CallVersion,
+
+    pub candidates: Vec<CallCandidate>,
+
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
+}
+
+impl CallCandidatesContent {
+    pub const EVENT_TYPE: &'static str = "m.call.candidates";
+}
+
+impl TypedEventContent for CallCandidatesContent {
+    fn validate(&self, _rules: &RoomVersionRules) -> Result<(), EventContentError> {
+        validate_call_fields(&self.call_id, &self.version, self.party_id.as_deref())
+    }
+}
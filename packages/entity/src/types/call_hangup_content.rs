@@ -0,0 +1,36 @@
+use crate::types::{
+    call_version::validate_call_fields, CallVersion, EventContentError, RoomVersionRules,
+    TypedEventContent,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// CallHangupContent - Content of `m.call.hangup`
+/// Source: spec/client/06_modules_md (m.call.hangup)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallHangupContent {
+    pub call_id: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub party_id: Option<String>,
+
+    pub version: CallVersion,
+
+    /// Why the call ended, e.g. `ice_failed` or `user_hangup`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
+}
+
+impl CallHangupContent {
+    pub const EVENT_TYPE: &'static str = "m.call.hangup";
+}
+
+impl TypedEventContent for CallHangupContent {
+    fn validate(&self, _rules: &RoomVersionRules) -> Result<(), EventContentError> {
+        validate_call_fields(&self.call_id, &self.version, self.party_id.as_deref())
+    }
+}
@@ -0,0 +1,41 @@
+use crate::types::{
+    call_version::validate_call_fields, CallSessionDescription, CallVersion, EventContentError,
+    RoomVersionRules, TypedEventContent,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// CallInviteContent - Content of `m.call.invite`
+/// Source: spec/client/06_modules_md (m.call.invite)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallInviteContent {
+    pub call_id: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub party_id: Option<String>,
+
+    pub version: CallVersion,
+
+    /// Milliseconds the invite is valid for
+    pub lifetime: u64,
+
+    pub offer: CallSessionDescription,
+
+    /// Intended recipient; absent means anyone in the room may answer
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub invitee: Option<String>,
+
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
+}
+
+impl CallInviteContent {
+    pub const EVENT_TYPE: &'static str = "m.call.invite";
+}
+
+impl TypedEventContent for CallInviteContent {
+    fn validate(&self, _rules: &RoomVersionRules) -> Result<(), EventContentError> {
+        validate_call_fields(&self.call_id, &self.version, self.party_id.as_deref())
+    }
+}
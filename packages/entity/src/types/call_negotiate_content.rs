@@ -0,0 +1,36 @@
+use crate::types::{
+    call_version::validate_call_fields, CallSessionDescription, CallVersion, EventContentError,
+    RoomVersionRules, TypedEventContent,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// CallNegotiateContent - Content of `m.call.negotiate`
+/// Source: spec/client/06_modules_md (m.call.negotiate)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallNegotiateContent {
+    pub call_id: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub party_id: Option<String>,
+
+    pub version: CallVersion,
+
+    pub lifetime: u64,
+
+    pub description: CallSessionDescription,
+
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
+}
+
+impl CallNegotiateContent {
+    pub const EVENT_TYPE: &'static str = "m.call.negotiate";
+}
+
+impl TypedEventContent for CallNegotiateContent {
+    fn validate(&self, _rules: &RoomVersionRules) -> Result<(), EventContentError> {
+        validate_call_fields(&self.call_id, &self.version, self.party_id.as_deref())
+    }
+}
@@ -0,0 +1,32 @@
+use crate::types::{
+    call_version::validate_call_fields, CallVersion, EventContentError, RoomVersionRules,
+    TypedEventContent,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// CallRejectContent - Content of `m.call.reject`
+/// Source: spec/client/06_modules_md (m.call.reject)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallRejectContent {
+    pub call_id: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub party_id: Option<String>,
+
+    pub version: CallVersion,
+
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
+}
+
+impl CallRejectContent {
+    pub const EVENT_TYPE: &'static str = "m.call.reject";
+}
+
+impl TypedEventContent for CallRejectContent {
+    fn validate(&self, _rules: &RoomVersionRules) -> Result<(), EventContentError> {
+        validate_call_fields(&self.call_id, &self.version, self.party_id.as_deref())
+    }
+}
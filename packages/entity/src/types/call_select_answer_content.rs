@@ -0,0 +1,34 @@
+use crate::types::{
+    call_version::validate_call_fields, CallVersion, EventContentError, RoomVersionRules,
+    TypedEventContent,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// CallSelectAnswerContent - Content of `m.call.select_answer`
+/// Source: spec/client/06_modules_md (m.call.select_answer)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallSelectAnswerContent {
+    pub call_id: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub party_id: Option<String>,
+
+    pub version: CallVersion,
+
+    pub selected_party_id: String,
+
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
+}
+
+impl CallSelectAnswerContent {
+    pub const EVENT_TYPE: &'static str = "m.call.select_answer";
+}
+
+impl TypedEventContent for CallSelectAnswerContent {
+    fn validate(&self, _rules: &RoomVersionRules) -> Result<(), EventContentError> {
+        validate_call_fields(&self.call_id, &self.version, self.party_id.as_deref())
+    }
+}
@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+/// CallSessionDescription - An SDP offer or answer in VoIP events
+/// Source: spec/client/06_modules_md (Voice over IP)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CallSessionDescription {
+    /// `offer` or `answer`
+    #[serde(rename = "type")]
+    pub session_type: String,
+
+    pub sdp: String,
+}
+
+impl CallSessionDescription {
+    pub fn offer(sdp: String) -> Self {
+        Self { session_type: "offer".to_string(), sdp }
+    }
+
+    pub fn answer(sdp: String) -> Self {
+        Self { session_type: "answer".to_string(), sdp }
+    }
+}
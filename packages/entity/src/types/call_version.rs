@@ -0,0 +1,42 @@
+use crate::types::EventContentError;
+use serde::{Deserialize, Serialize};
+
+/// CallVersion - The `version` field of VoIP events
+///
+/// Version 0 calls send the integer `0`; later versions send a string.
+/// Source: spec/client/06_modules_md (Voice over IP)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CallVersion {
+    Integer(u64),
+    String(String),
+}
+
+impl CallVersion {
+    pub fn v1() -> Self {
+        CallVersion::String("1".to_string())
+    }
+
+    /// Whether this is a legacy version 0 call without party IDs
+    pub fn is_v0(&self) -> bool {
+        match self {
+            CallVersion::Integer(version) => *version == 0,
+            CallVersion::String(version) => version == "0",
+        }
+    }
+}
+
+/// Shared checks for the fields every `m.call.*` event carries
+pub(crate) fn validate_call_fields(
+    call_id: &str,
+    version: &CallVersion,
+    party_id: Option<&str>,
+) -> Result<(), EventContentError> {
+    if call_id.is_empty() {
+        return Err(EventContentError::invalid_field("call_id", "must not be empty"));
+    }
+    if !version.is_v0() && party_id.is_none() {
+        return Err(EventContentError::MissingField("party_id".to_string()));
+    }
+    Ok(())
+}
@@ -1,6 +1,22 @@
 use serde::{Deserialize, Serialize};
 use url::Url;
 
+/// How the real-time client receives sync updates
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncTransport {
+    /// Long-polling `/sync` over the Client-Server API
+    #[default]
+    LongPoll,
+    /// `/sync` as a `text/event-stream` of updates
+    ServerSentEvents,
+    /// LiveQuery subscriptions on the homeserver's database
+    ///
+    /// Only for clients embedded in the homeserver deployment, as it needs
+    /// the database credentials.
+    LiveQuery,
+}
+
 /// Real-time Matrix client configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RealtimeConfig {
@@ -8,8 +24,14 @@ pub struct RealtimeConfig {
     pub homeserver_url: Url,
     /// WebSocket URL for real-time connections
     pub websocket_url: Option<Url>,
-    /// SurrealDB connection URL
+    /// SurrealDB connection URL, used by [`SyncTransport::LiveQuery`]
     pub surrealdb_url: Url,
+    /// Sync transport
+    #[serde(default)]
+    pub sync_transport: SyncTransport,
+    /// Long-poll timeout of `/sync` requests in seconds
+    #[serde(default = "default_sync_timeout_secs")]
+    pub sync_timeout_secs: u64,
     /// Connection timeout in seconds
     pub timeout_secs: u64,
     /// Reconnection attempts
@@ -26,9 +48,15 @@ impl Default for RealtimeConfig {
             websocket_url: None,
             surrealdb_url: Url::parse("ws://127.0.0.1:8000")
                 .unwrap_or_else(|e| panic!("BUG: Default SurrealDB URL 'ws://127.0.0.1:8000' failed to parse: {}", e)),
+            sync_transport: SyncTransport::default(),
+            sync_timeout_secs: default_sync_timeout_secs(),
             timeout_secs: 30,
             max_reconnect_attempts: 5,
             reconnect_delay_secs: 5,
         }
     }
 }
+
+fn default_sync_timeout_secs() -> u64 {
+    30
+}
@@ -0,0 +1,26 @@
+use crate::types::identifier::{MAX_IDENTIFIER_BYTES, matrix_identifier};
+use crate::types::IdentifierError;
+
+matrix_identifier! {
+    /// DeviceId - opaque Matrix device identifier
+    /// Source: Matrix Client-Server API - Relationship between access tokens and devices
+    owned: DeviceId,
+    /// Borrowed, non-allocating form of [`DeviceId`]
+    borrowed: DeviceIdRef,
+    validate: validate_device_id,
+}
+
+/// Validate a device ID: non-empty, bounded, and free of control characters
+pub fn validate_device_id(device_id: &str) -> Result<(), IdentifierError> {
+    if device_id.is_empty() {
+        return Err(IdentifierError::Empty);
+    }
+    if device_id.len() > MAX_IDENTIFIER_BYTES {
+        return Err(IdentifierError::TooLong { max: MAX_IDENTIFIER_BYTES });
+    }
+
+    match device_id.chars().find(|c| c.is_control()) {
+        Some(c) => Err(IdentifierError::InvalidCharacter(c)),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,30 @@
+use crate::types::IdentifierError;
+use thiserror::Error;
+
+/// Errors produced when parsing or validating typed event content
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum EventContentError {
+    #[error("Event is missing required field '{0}'")]
+    MissingField(String),
+
+    #[error("Failed to deserialize {event_type} event: {message}")]
+    Deserialization { event_type: String, message: String },
+
+    #[error("Invalid value for '{field}': {message}")]
+    InvalidField { field: String, message: String },
+
+    #[error("State event {0} is missing a state_key")]
+    MissingStateKey(String),
+
+    #[error("{event_type} is not allowed in room version rules in use: {message}")]
+    NotAllowedInRoomVersion { event_type: String, message: String },
+
+    #[error("Invalid identifier: {0}")]
+    Identifier(#[from] IdentifierError),
+}
+
+impl EventContentError {
+    pub fn invalid_field(field: &str, message: impl Into<String>) -> Self {
+        Self::InvalidField { field: field.to_string(), message: message.into() }
+    }
+}
@@ -0,0 +1,15 @@
+use serde_json::Value;
+
+/// EventEnvelope - Accessors shared by typed state and message-like events
+///
+/// Lets the `Any*Event` enums expose the common envelope fields without
+/// matching on every variant at each call site.
+pub trait EventEnvelope {
+    fn event_type(&self) -> &str;
+    fn event_id(&self) -> Option<&str>;
+    fn sender(&self) -> &str;
+    fn origin_server_ts(&self) -> i64;
+    fn room_id(&self) -> Option<&str>;
+    fn state_key(&self) -> Option<&str>;
+    fn unsigned(&self) -> Option<&Value>;
+}
@@ -0,0 +1,59 @@
+use crate::types::identifier::{MAX_IDENTIFIER_BYTES, matrix_identifier, split_sigil_id};
+use crate::types::{IdentifierError, ServerNameRef};
+
+matrix_identifier! {
+    /// EventId - validated Matrix event ID
+    ///
+    /// Room versions 1 and 2 use `$opaque_id:server_name`; room version 3
+    /// onwards derive the ID from the reference hash and encode it as
+    /// (URL-safe) unpadded base64 with no server name.
+    /// Source: Matrix Appendices - Event IDs
+    owned: EventId,
+    /// Borrowed, non-allocating form of [`EventId`]
+    borrowed: EventIdRef,
+    validate: validate_event_id,
+}
+
+/// Validate an event ID in either the server-scoped or the hash-based format
+pub fn validate_event_id(event_id: &str) -> Result<(), IdentifierError> {
+    if event_id.contains(':') {
+        split_sigil_id(event_id, '$')?;
+        return Ok(());
+    }
+
+    if event_id.is_empty() {
+        return Err(IdentifierError::Empty);
+    }
+    if event_id.len() > MAX_IDENTIFIER_BYTES {
+        return Err(IdentifierError::TooLong { max: MAX_IDENTIFIER_BYTES });
+    }
+
+    let hash = event_id
+        .strip_prefix('$')
+        .ok_or(IdentifierError::MissingSigil { expected: '$' })?;
+    if hash.is_empty() {
+        return Err(IdentifierError::EmptyLocalpart);
+    }
+
+    match hash
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '+' | '/' | '-' | '_')))
+    {
+        Some(c) => Err(IdentifierError::InvalidCharacter(c)),
+        None => Ok(()),
+    }
+}
+
+impl EventId {
+    /// The server name, present only for room version 1 and 2 event IDs
+    pub fn server_name(&self) -> Option<ServerNameRef<'_>> {
+        self.as_borrowed().server_name()
+    }
+}
+
+impl<'a> EventIdRef<'a> {
+    /// The server name, present only for room version 1 and 2 event IDs
+    pub fn server_name(&self) -> Option<ServerNameRef<'a>> {
+        self.0.split_once(':').map(|(_, server)| ServerNameRef::new_unchecked(server))
+    }
+}
@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// HistoryVisibility - Who may read room history
+/// Source: spec/client/05_advanced_md:47
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryVisibility {
+    Invited,
+    Joined,
+    Shared,
+    WorldReadable,
+}
+
+impl fmt::Display for HistoryVisibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            HistoryVisibility::Invited => "invited",
+            HistoryVisibility::Joined => "joined",
+            HistoryVisibility::Shared => "shared",
+            HistoryVisibility::WorldReadable => "world_readable",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl From<&str> for HistoryVisibility {
+    fn from(s: &str) -> Self {
+        match s {
+            "invited" => HistoryVisibility::Invited,
+            "joined" => HistoryVisibility::Joined,
+            "world_readable" => HistoryVisibility::WorldReadable,
+            _ => HistoryVisibility::Shared, // Default per Matrix spec
+        }
+    }
+}
+
+impl From<String> for HistoryVisibility {
+    fn from(s: String) -> Self {
+        HistoryVisibility::from(s.as_str())
+    }
+}
@@ -0,0 +1,460 @@
+//! Shared grammar and code generation for the Matrix identifier newtypes
+//!
+//! Every identifier comes in two forms: an owned type (`UserId`) that stores
+//! the validated string, and a `Copy` borrowed form (`UserIdRef<'a>`) that
+//! wraps a validated `&str` without allocating. Both forms are produced by the
+//! `matrix_identifier!` macro so they share parsing, comparison and serde
+//! behaviour.
+//!
+//! Source: Matrix Appendices - Identifier Grammar
+
+use crate::types::IdentifierError;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// Maximum length in bytes of user IDs, room IDs, room aliases and event IDs
+pub const MAX_IDENTIFIER_BYTES: usize = 255;
+
+/// Validate a server name: `hostname [ ":" port ]`
+///
+/// `hostname` is an IPv4 literal, a bracketed IPv6 literal or a DNS name of
+/// 1-255 characters from `[A-Za-z0-9.-]`.
+pub fn validate_server_name(server_name: &str) -> Result<(), IdentifierError> {
+    let invalid = || IdentifierError::InvalidServerName(server_name.to_string());
+
+    if server_name.is_empty() {
+        return Err(invalid());
+    }
+
+    let (host, port) = split_host_port(server_name).ok_or_else(invalid)?;
+
+    if let Some(port) = port {
+        if port.is_empty() || port.len() > 5 || port.parse::<u16>().is_err() {
+            return Err(invalid());
+        }
+    }
+
+    if let Some(ipv6) = host.strip_prefix('[') {
+        let ipv6 = ipv6.strip_suffix(']').ok_or_else(invalid)?;
+        ipv6.parse::<Ipv6Addr>().map_err(|_| invalid())?;
+        return Ok(());
+    }
+
+    if host.is_empty() || host.len() > 255 {
+        return Err(invalid());
+    }
+
+    if host.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        // Anything made of digits and dots must be a well-formed IPv4 literal
+        host.parse::<Ipv4Addr>().map_err(|_| invalid())?;
+        return Ok(());
+    }
+
+    if host.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.') {
+        Ok(())
+    } else {
+        Err(invalid())
+    }
+}
+
+/// Split a server name into its host and optional port, honouring IPv6 brackets
+pub(crate) fn split_host_port(server_name: &str) -> Option<(&str, Option<&str>)> {
+    if server_name.starts_with('[') {
+        let end = server_name.find(']')?;
+        let (host, rest) = server_name.split_at(end + 1);
+        return match rest {
+            "" => Some((host, None)),
+            _ => rest.strip_prefix(':').map(|port| (host, Some(port))),
+        };
+    }
+
+    match server_name.rsplit_once(':') {
+        Some((host, port)) => Some((host, Some(port))),
+        None => Some((server_name, None)),
+    }
+}
+
+/// Split `<sigil>localpart:server_name` into its localpart and server name
+///
+/// The localpart is everything up to the first `:`; the remainder may itself
+/// contain colons (ports, IPv6 literals).
+pub(crate) fn split_sigil_id(id: &str, sigil: char) -> Result<(&str, &str), IdentifierError> {
+    if id.is_empty() {
+        return Err(IdentifierError::Empty);
+    }
+    if id.len() > MAX_IDENTIFIER_BYTES {
+        return Err(IdentifierError::TooLong { max: MAX_IDENTIFIER_BYTES });
+    }
+
+    let rest = id
+        .strip_prefix(sigil)
+        .ok_or(IdentifierError::MissingSigil { expected: sigil })?;
+    let (localpart, server_name) =
+        rest.split_once(':').ok_or(IdentifierError::MissingDelimiter)?;
+
+    if localpart.is_empty() {
+        return Err(IdentifierError::EmptyLocalpart);
+    }
+    validate_server_name(server_name)?;
+
+    Ok((localpart, server_name))
+}
+
+/// Generate an owned and a borrowed identifier type sharing one validator
+///
+/// The validator is a `fn(&str) -> Result<(), IdentifierError>`.
+macro_rules! matrix_identifier {
+    (
+        $(#[$owned_meta:meta])*
+        owned: $owned:ident,
+        $(#[$borrowed_meta:meta])*
+        borrowed: $borrowed:ident,
+        validate: $validate:path $(,)?
+    ) => {
+        $(#[$owned_meta])*
+        #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $owned(Box<str>);
+
+        $(#[$borrowed_meta])*
+        #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $borrowed<'a>(&'a str);
+
+        impl $owned {
+            /// Parse and validate an identifier, copying it into an owned value
+            pub fn parse(value: impl AsRef<str>) -> Result<Self, $crate::types::IdentifierError> {
+                let value = value.as_ref();
+                $validate(value)?;
+                Ok(Self(value.into()))
+            }
+
+            /// The identifier as a string slice
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+
+            /// Borrow this identifier without copying it
+            pub fn as_borrowed(&self) -> $borrowed<'_> {
+                $borrowed(&self.0)
+            }
+
+            /// Convert into the underlying `String`
+            pub fn into_string(self) -> String {
+                self.0.into()
+            }
+        }
+
+        impl<'a> $borrowed<'a> {
+            /// Parse and validate an identifier without allocating
+            pub fn parse(value: &'a str) -> Result<Self, $crate::types::IdentifierError> {
+                $validate(value)?;
+                Ok(Self(value))
+            }
+
+            /// The identifier as a string slice with the original lifetime
+            pub fn as_str(&self) -> &'a str {
+                self.0
+            }
+
+            /// Copy this identifier into its owned form
+            pub fn into_owned(self) -> $owned {
+                $owned(self.0.into())
+            }
+        }
+
+        impl std::fmt::Display for $owned {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+
+        impl std::fmt::Debug for $owned {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                std::fmt::Debug::fmt(&*self.0, f)
+            }
+        }
+
+        impl std::fmt::Display for $borrowed<'_> {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.0)
+            }
+        }
+
+        impl std::fmt::Debug for $borrowed<'_> {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                std::fmt::Debug::fmt(self.0, f)
+            }
+        }
+
+        impl std::str::FromStr for $owned {
+            type Err = $crate::types::IdentifierError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Self::parse(s)
+            }
+        }
+
+        impl TryFrom<&str> for $owned {
+            type Error = $crate::types::IdentifierError;
+
+            fn try_from(value: &str) -> Result<Self, Self::Error> {
+                Self::parse(value)
+            }
+        }
+
+        impl TryFrom<String> for $owned {
+            type Error = $crate::types::IdentifierError;
+
+            fn try_from(value: String) -> Result<Self, Self::Error> {
+                $validate(&value)?;
+                Ok(Self(value.into_boxed_str()))
+            }
+        }
+
+        impl<'a> TryFrom<&'a str> for $borrowed<'a> {
+            type Error = $crate::types::IdentifierError;
+
+            fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+                Self::parse(value)
+            }
+        }
+
+        impl From<$owned> for String {
+            fn from(id: $owned) -> Self {
+                id.into_string()
+            }
+        }
+
+        impl From<$borrowed<'_>> for $owned {
+            fn from(id: $borrowed<'_>) -> Self {
+                id.into_owned()
+            }
+        }
+
+        impl<'a> From<&'a $owned> for $borrowed<'a> {
+            fn from(id: &'a $owned) -> Self {
+                id.as_borrowed()
+            }
+        }
+
+        impl AsRef<str> for $owned {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl AsRef<str> for $borrowed<'_> {
+            fn as_ref(&self) -> &str {
+                self.0
+            }
+        }
+
+        impl std::ops::Deref for $owned {
+            type Target = str;
+
+            fn deref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl std::ops::Deref for $borrowed<'_> {
+            type Target = str;
+
+            fn deref(&self) -> &str {
+                self.0
+            }
+        }
+
+        impl std::borrow::Borrow<str> for $owned {
+            fn borrow(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl PartialEq<str> for $owned {
+            fn eq(&self, other: &str) -> bool {
+                &*self.0 == other
+            }
+        }
+
+        impl PartialEq<&str> for $owned {
+            fn eq(&self, other: &&str) -> bool {
+                &*self.0 == *other
+            }
+        }
+
+        impl PartialEq<String> for $owned {
+            fn eq(&self, other: &String) -> bool {
+                &*self.0 == other.as_str()
+            }
+        }
+
+        impl PartialEq<$borrowed<'_>> for $owned {
+            fn eq(&self, other: &$borrowed<'_>) -> bool {
+                &*self.0 == other.0
+            }
+        }
+
+        impl PartialEq<str> for $borrowed<'_> {
+            fn eq(&self, other: &str) -> bool {
+                self.0 == other
+            }
+        }
+
+        impl PartialEq<&str> for $borrowed<'_> {
+            fn eq(&self, other: &&str) -> bool {
+                self.0 == *other
+            }
+        }
+
+        impl serde::Serialize for $owned {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(&self.0)
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $owned {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = String::deserialize(deserializer)?;
+                Self::try_from(value).map_err(serde::de::Error::custom)
+            }
+        }
+
+        impl serde::Serialize for $borrowed<'_> {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.0)
+            }
+        }
+
+        impl<'de: 'a, 'a> serde::Deserialize<'de> for $borrowed<'a> {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = <&'de str>::deserialize(deserializer)?;
+                Self::parse(value).map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+pub(crate) use matrix_identifier;
+
+#[cfg(test)]
+mod tests {
+    use crate::types::{
+        DeviceId, EventId, IdentifierError, MxcUri, RoomAliasId, RoomId, ServerName, UserId,
+        UserIdRef,
+    };
+
+    #[test]
+    fn test_server_names() -> Result<(), IdentifierError> {
+        for valid in [
+            "matrix.org",
+            "matrix.org:8888",
+            "localhost",
+            "1.2.3.4",
+            "1.2.3.4:1234",
+            "[1234:5678::abcd]",
+            "[1234:5678::abcd]:5678",
+            "[::1]",
+        ] {
+            assert!(ServerName::parse(valid).is_ok(), "{valid} should be valid");
+        }
+
+        for invalid in [
+            "",
+            ":8448",
+            "matrix.org:",
+            "matrix.org:99999",
+            "matrix.org:abc",
+            "under_score.org",
+            "[::1",
+            "[not:ipv6]",
+            "1.2.3.256",
+            "[::1]8448",
+        ] {
+            assert!(ServerName::parse(invalid).is_err(), "{invalid} should be invalid");
+        }
+
+        let server = ServerName::parse("[::1]:8080")?;
+        assert_eq!(server.host(), "[::1]");
+        assert_eq!(server.port(), Some(8080));
+        assert!(server.is_ip_literal());
+        Ok(())
+    }
+
+    #[test]
+    fn test_user_ids() -> Result<(), IdentifierError> {
+        let user = UserId::parse("@alice:example.org")?;
+        assert_eq!(user.localpart(), "alice");
+        assert_eq!(user.server_name(), "example.org");
+        assert!(!user.is_historical());
+
+        // Historical user IDs allow any printable ASCII except ':'
+        let historical = UserId::parse("@Alice!Legacy:example.org")?;
+        assert!(historical.is_historical());
+
+        let ipv6 = UserId::parse("@bob:[2001:db8::1]:8448")?;
+        assert_eq!(ipv6.localpart(), "bob");
+        assert_eq!(ipv6.server_name(), "[2001:db8::1]:8448");
+
+        assert_eq!(
+            UserId::parse("alice:example.org"),
+            Err(IdentifierError::MissingSigil { expected: '@' })
+        );
+        assert_eq!(UserId::parse("@alice"), Err(IdentifierError::MissingDelimiter));
+        assert_eq!(UserId::parse("@:example.org"), Err(IdentifierError::EmptyLocalpart));
+        assert!(UserId::parse("@al ice:example.org").is_err());
+        assert!(UserId::parse(format!("@{}:example.org", "a".repeat(255))).is_err());
+
+        let server = ServerName::parse("example.org")?;
+        assert!(UserId::new("alice", &server).is_ok());
+        assert!(UserId::new("Alice", &server).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_other_identifiers() -> Result<(), IdentifierError> {
+        let room = RoomId::parse("!abc123:example.org")?;
+        assert_eq!(room.server_name().map(|s| s.as_str()), Some("example.org"));
+        assert!(RoomId::parse("#abc:example.org").is_err());
+
+        let alias = RoomAliasId::parse("#general:example.org")?;
+        assert_eq!(alias.alias(), "general");
+
+        let v1_event = EventId::parse("$abc:example.org")?;
+        assert_eq!(v1_event.server_name().map(|s| s.as_str()), Some("example.org"));
+        let v4_event = EventId::parse("$acR1l0raoZnm60CBwAVgqbZqoO_mYU81xysh1u7XcJk")?;
+        assert!(v4_event.server_name().is_none());
+        assert!(EventId::parse("$").is_err());
+
+        assert!(DeviceId::parse("ABCDEFGH").is_ok());
+        assert!(DeviceId::parse("").is_err());
+
+        let mxc = MxcUri::parse("mxc://example.org/SEsfnsuifSDFSSEF")?;
+        assert_eq!(mxc.server_name(), "example.org");
+        assert_eq!(mxc.media_id(), "SEsfnsuifSDFSSEF");
+        assert!(MxcUri::parse("https://example.org/abc").is_err());
+        assert!(MxcUri::parse("mxc://example.org/a/b").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_borrowed_forms_and_serde() -> Result<(), Box<dyn std::error::Error>> {
+        let raw = "@alice:example.org";
+        let borrowed = UserIdRef::parse(raw)?;
+        assert_eq!(borrowed.as_str().as_ptr(), raw.as_ptr());
+
+        let owned = borrowed.into_owned();
+        assert_eq!(owned, borrowed);
+        assert_eq!(owned, "@alice:example.org");
+
+        let json = serde_json::to_string(&owned)?;
+        assert_eq!(json, "\"@alice:example.org\"");
+        let back: UserId = serde_json::from_str(&json)?;
+        assert_eq!(back, owned);
+
+        let bad: Result<UserId, _> = serde_json::from_str("\"not-a-user\"");
+        assert!(bad.is_err());
+
+        let borrowed: UserIdRef<'_> = serde_json::from_str(&json)?;
+        assert_eq!(borrowed.localpart(), "alice");
+        Ok(())
+    }
+}
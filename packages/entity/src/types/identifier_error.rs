@@ -0,0 +1,34 @@
+use thiserror::Error;
+
+/// Errors produced when parsing Matrix identifiers
+///
+/// Source: Matrix Appendices - Identifier Grammar
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum IdentifierError {
+    #[error("Identifier is empty")]
+    Empty,
+
+    #[error("Identifier exceeds the maximum length of {max} bytes")]
+    TooLong { max: usize },
+
+    #[error("Identifier must start with '{expected}'")]
+    MissingSigil { expected: char },
+
+    #[error("Identifier is missing the ':' server name delimiter")]
+    MissingDelimiter,
+
+    #[error("Identifier localpart is empty")]
+    EmptyLocalpart,
+
+    #[error("Identifier localpart contains an invalid character: {0:?}")]
+    InvalidLocalpart(char),
+
+    #[error("Invalid server name: {0}")]
+    InvalidServerName(String),
+
+    #[error("Invalid MXC URI: {0}")]
+    InvalidMxcUri(String),
+
+    #[error("Identifier contains an invalid character: {0:?}")]
+    InvalidCharacter(char),
+}
@@ -0,0 +1,40 @@
+use crate::types::EncryptedFile;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// MediaInfo - The `info` object of image, video, audio and file messages
+///
+/// The union of the per-msgtype info objects; fields that do not apply to a
+/// given msgtype are simply absent.
+/// Source: spec/client/08_instant_messaging_md (m.image, m.file, m.audio, m.video)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MediaInfo {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mimetype: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub w: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub h: Option<u64>,
+
+    /// Duration in milliseconds (audio and video)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail_url: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail_file: Option<EncryptedFile>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail_info: Option<Box<MediaInfo>>,
+
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
+}
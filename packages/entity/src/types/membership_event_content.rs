@@ -1,5 +1,10 @@
-use crate::types::ThirdPartyInvite;
+use crate::types::{
+    EventContentError, MembershipState, RoomVersionRules, ThirdPartyInvite, TypedEventContent,
+    UserId,
+};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
 
 /// Content for membership events (join/leave/invite/ban/knock)
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -8,7 +13,11 @@ pub struct MembershipEventContent {
     pub membership: String,
 
     /// Display name
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        rename = "displayname",
+        alias = "display_name",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub display_name: Option<String>,
 
     /// Avatar URL
@@ -22,9 +31,23 @@ pub struct MembershipEventContent {
     /// Third party invite information
     #[serde(skip_serializing_if = "Option::is_none")]
     pub third_party_invite: Option<ThirdPartyInvite>,
+
+    /// Whether an invite is for a direct chat
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_direct: Option<bool>,
+
+    /// Resident user that authorised a restricted join
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub join_authorised_via_users_server: Option<String>,
+
+    /// Fields not modelled above, preserved for round-tripping
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
 }
 
 impl MembershipEventContent {
+    pub const EVENT_TYPE: &'static str = "m.room.member";
+
     pub fn new(membership: String) -> Self {
         Self {
             membership,
@@ -32,6 +55,44 @@ impl MembershipEventContent {
             avatar_url: None,
             reason: None,
             third_party_invite: None,
+            is_direct: None,
+            join_authorised_via_users_server: None,
+            unknown_fields: BTreeMap::new(),
+        }
+    }
+
+    /// Parsed membership state, or `None` for values outside the spec
+    pub fn membership_state(&self) -> Option<MembershipState> {
+        match self.membership.as_str() {
+            "invite" | "join" | "leave" | "ban" | "knock" => {
+                Some(MembershipState::from(self.membership.clone()))
+            },
+            _ => None,
+        }
+    }
+}
+
+impl TypedEventContent for MembershipEventContent {
+    fn validate(&self, rules: &RoomVersionRules) -> Result<(), EventContentError> {
+        match self.membership_state() {
+            None => {
+                return Err(EventContentError::invalid_field(
+                    "membership",
+                    format!("unknown membership '{}'", self.membership),
+                ));
+            },
+            Some(MembershipState::Knock) if !rules.knocking => {
+                return Err(EventContentError::NotAllowedInRoomVersion {
+                    event_type: Self::EVENT_TYPE.to_string(),
+                    message: "knock membership is not supported".to_string(),
+                });
+            },
+            Some(_) => {},
+        }
+
+        if let Some(authoriser) = &self.join_authorised_via_users_server {
+            UserId::parse(authoriser)?;
         }
+        Ok(())
     }
 }
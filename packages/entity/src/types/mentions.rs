@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+
+/// Mentions - The `m.mentions` property of a message-like event
+/// Source: spec/client/08_instant_messaging_md (User and room mentions)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Mentions {
+    /// User IDs that are mentioned in the event
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_ids: Option<Vec<String>>,
+
+    /// Whether this is a room-wide mention (@room)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub room: Option<bool>,
+}
+
+impl Mentions {
+    pub fn new(user_ids: Vec<String>, room: bool) -> Self {
+        Self {
+            user_ids: if user_ids.is_empty() {
+                None
+            } else {
+                Some(user_ids)
+            },
+            room: if room { Some(true) } else { None },
+        }
+    }
+
+    /// Whether the given user is mentioned explicitly
+    pub fn mentions_user(&self, user_id: &str) -> bool {
+        self.user_ids
+            .as_ref()
+            .is_some_and(|ids| ids.iter().any(|id| id == user_id))
+    }
+
+    /// Whether this is an @room mention
+    pub fn mentions_room(&self) -> bool {
+        self.room.unwrap_or(false)
+    }
+}
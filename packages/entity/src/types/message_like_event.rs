@@ -0,0 +1,66 @@
+use crate::types::EventEnvelope;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// MessageLikeEvent - A non-state timeline event with typed content
+///
+/// `redacts` is the top-level redaction target used by `m.room.redaction`
+/// in room versions before 11. Envelope fields not modelled here are
+/// preserved in `unknown_fields`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageLikeEvent<C> {
+    #[serde(rename = "type")]
+    pub event_type: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_id: Option<String>,
+
+    pub sender: String,
+
+    pub origin_server_ts: i64,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub room_id: Option<String>,
+
+    pub content: C,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redacts: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unsigned: Option<Value>,
+
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
+}
+
+impl<C> EventEnvelope for MessageLikeEvent<C> {
+    fn event_type(&self) -> &str {
+        &self.event_type
+    }
+
+    fn event_id(&self) -> Option<&str> {
+        self.event_id.as_deref()
+    }
+
+    fn sender(&self) -> &str {
+        &self.sender
+    }
+
+    fn origin_server_ts(&self) -> i64 {
+        self.origin_server_ts
+    }
+
+    fn room_id(&self) -> Option<&str> {
+        self.room_id.as_deref()
+    }
+
+    fn state_key(&self) -> Option<&str> {
+        None
+    }
+
+    fn unsigned(&self) -> Option<&Value> {
+        self.unsigned.as_ref()
+    }
+}
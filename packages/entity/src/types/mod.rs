@@ -3,9 +3,13 @@
 //! This module contains all Matrix Protocol domain entities as defined in MATRIX_DOMAIN.md.
 //! Each entity is in its own file with a 1:1 mapping between struct and file.
 
+mod any_event_enum;
 pub mod account_data;
 pub mod account_data_content;
 pub mod account_data_sync;
+pub mod any_message_like_event;
+pub mod any_state_event;
+pub mod any_timeline_event;
 pub mod auth_chain_response;
 pub mod authentication_content;
 pub mod authentication_data;
@@ -16,6 +20,16 @@ pub mod backed_up_session_data;
 pub mod backfill_response;
 pub mod backup_auth_data;
 pub mod broadcast_event;
+pub mod call_answer_content;
+pub mod call_candidate;
+pub mod call_candidates_content;
+pub mod call_hangup_content;
+pub mod call_invite_content;
+pub mod call_negotiate_content;
+pub mod call_reject_content;
+pub mod call_select_answer_content;
+pub mod call_session_description;
+pub mod call_version;
 pub mod cipher_text;
 pub mod client_config;
 pub mod client_credentials;
@@ -26,6 +40,7 @@ pub mod crypto_device;
 pub mod delete_device_request;
 pub mod delete_devices_request;
 pub mod device;
+pub mod device_id;
 pub mod device_info;
 pub mod device_key;
 pub mod device_keys;
@@ -44,6 +59,9 @@ pub mod encrypted_file;
 pub mod ephemeral_event;
 pub mod event;
 pub mod event_content;
+pub mod event_content_error;
+pub mod event_envelope;
+pub mod event_id;
 pub mod event_relates_to;
 pub mod event_replacement_content;
 pub mod event_retrieval_transaction;
@@ -55,7 +73,10 @@ pub mod filter;
 pub mod flow_information;
 pub mod global_account_data;
 pub mod guest_access;
+pub mod history_visibility;
 pub mod history_visibility_event;
+pub mod identifier;
+pub mod identifier_error;
 pub mod invite_event;
 pub mod invite_event_container;
 pub mod invite_membership_event_content;
@@ -88,11 +109,15 @@ pub mod leave_membership_event_content;
 pub mod make_join_response;
 pub mod make_knock_response;
 pub mod make_leave_response;
+pub mod media_info;
 pub mod membership;
 pub mod membership_event_content;
 pub mod membership_state;
+pub mod mentions;
+pub mod message_like_event;
 pub mod missing_events_request;
 pub mod missing_events_response;
+pub mod mxc_uri;
 pub mod old_verify_key;
 pub mod one_time_key_claim_request;
 pub mod one_time_key_claim_response;
@@ -100,6 +125,7 @@ pub mod one_time_key_object;
 pub mod open_id_error_response;
 pub mod open_id_user_info_response;
 pub mod pdu;
+pub mod policy_rule_content;
 pub mod power_levels;
 pub mod presence_edu;
 pub mod presence_update;
@@ -129,15 +155,27 @@ pub mod query_criteria;
 pub mod query_request;
 pub mod query_response;
 pub mod rate_limit_response;
+pub mod reaction_content;
 pub mod read_receipt_metadata;
 pub mod receipt_edu;
+pub mod relation;
 pub mod report;
 pub mod room;
 pub mod room_event_filter;
 pub mod room_account_data;
+pub mod room_alias_id;
 pub mod room_alias_mapping;
 pub mod room_alias_response;
 pub mod room_aliases_response;
+pub mod room_avatar_content;
+pub mod room_canonical_alias_content;
+pub mod room_create_content;
+pub mod room_encrypted_content;
+pub mod room_encryption_content;
+pub mod room_guest_access_content;
+pub mod room_history_visibility_content;
+pub mod room_id;
+pub mod room_join_rules_content;
 pub mod room_key_backup;
 pub mod room_keys_by_room_get_response;
 pub mod room_keys_by_room_put_request;
@@ -146,9 +184,19 @@ pub mod room_keys_delete_response;
 pub mod room_keys_get_response;
 pub mod room_keys_put_request;
 pub mod room_keys_put_response;
+pub mod room_message_content;
+pub mod room_name_content;
+pub mod room_pinned_events_content;
 pub mod room_receipts;
+pub mod room_redaction_content;
+pub mod room_retention_content;
+pub mod room_server_acl_content;
 pub mod room_state_response;
 pub mod room_tag;
+pub mod room_third_party_invite_content;
+pub mod room_tombstone_content;
+pub mod room_topic_content;
+pub mod room_version_rules;
 pub mod ruleset;
 pub mod sas_verification_start;
 pub mod send_join_request;
@@ -163,6 +211,7 @@ pub mod send_to_device_request;
 pub mod server_details;
 pub mod server_info;
 pub mod server_keys_response;
+pub mod server_name;
 pub mod server_notice_content;
 pub mod session;
 pub mod session_data;
@@ -181,6 +230,7 @@ pub mod space_hierarchy_response;
 pub mod space_hierarchy_room;
 pub mod space_hierarchy_stripped_state_event;
 pub mod space_parent_event;
+pub mod state_event;
 pub mod state_retrieval_request;
 pub mod sticker_content;
 pub mod stripped_state_event;
@@ -201,6 +251,7 @@ pub mod to_device_message;
 pub mod transaction;
 pub mod transaction_response;
 pub mod transaction_result;
+pub mod typed_event_content;
 pub mod typing_notification;
 pub mod typing_notification_edu;
 pub mod unsigned_data;
@@ -208,11 +259,11 @@ pub mod unsigned_device_info;
 pub mod update_device_request;
 pub mod user;
 pub mod user_directory_response;
+pub mod user_id;
 pub mod user_info;
 pub mod user_presence_update;
 pub mod user_profile;
 pub mod user_read_receipt;
-
 pub mod verification_accept;
 pub mod verification_cancel;
 pub mod verification_done;
@@ -240,7 +291,7 @@ pub use backfill_response::BackfillResponse;
 pub use backup_auth_data::BackupAuthData;
 pub use broadcast_event::BroadcastEvent;
 pub use cipher_text::CipherText;
-pub use client_config::RealtimeConfig;
+pub use client_config::{RealtimeConfig, SyncTransport};
 pub use client_credentials::{Credentials, RealtimeCredentials};
 pub use connection_status::ConnectionStatus;
 pub use cross_signing_key::CrossSigningKey;
@@ -465,3 +516,64 @@ pub use profile_response::{
     ThirdPartyResponse,
     WhoAmIResponse,
 };
+
+// Matrix identifier types
+pub use device_id::{DeviceId, DeviceIdRef};
+pub use event_id::{EventId, EventIdRef};
+pub use identifier::{MAX_IDENTIFIER_BYTES, validate_server_name};
+pub use identifier_error::IdentifierError;
+pub use mxc_uri::{MxcUri, MxcUriRef};
+pub use room_alias_id::{RoomAliasId, RoomAliasIdRef};
+pub use room_id::{RoomId, RoomIdRef};
+pub use server_name::{ServerName, ServerNameRef};
+pub use user_id::{UserId, UserIdRef, is_valid_user_localpart};
+
+// Typed event content
+pub use any_message_like_event::AnyMessageLikeEvent;
+pub use any_state_event::AnyStateEvent;
+pub use any_timeline_event::AnyTimelineEvent;
+pub use call_answer_content::CallAnswerContent;
+pub use call_candidate::CallCandidate;
+pub use call_candidates_content::CallCandidatesContent;
+pub use call_hangup_content::CallHangupContent;
+pub use call_invite_content::CallInviteContent;
+pub use call_negotiate_content::CallNegotiateContent;
+pub use call_reject_content::CallRejectContent;
+pub use call_select_answer_content::CallSelectAnswerContent;
+pub use call_session_description::CallSessionDescription;
+pub use call_version::CallVersion;
+pub use event_content_error::EventContentError;
+pub use event_envelope::EventEnvelope;
+pub use history_visibility::HistoryVisibility;
+pub use media_info::MediaInfo;
+pub use mentions::Mentions;
+pub use message_like_event::MessageLikeEvent;
+pub use policy_rule_content::PolicyRuleContent;
+pub use reaction_content::ReactionContent;
+pub use relation::{InReplyTo, Relation};
+pub use room_avatar_content::RoomAvatarContent;
+pub use room_canonical_alias_content::RoomCanonicalAliasContent;
+pub use room_create_content::{PreviousRoom, RoomCreateContent};
+pub use room_encrypted_content::{EncryptedCiphertext, RoomEncryptedContent};
+pub use room_encryption_content::RoomEncryptionContent;
+pub use room_guest_access_content::RoomGuestAccessContent;
+pub use room_history_visibility_content::RoomHistoryVisibilityContent;
+pub use room_join_rules_content::{AllowCondition, RoomJoinRulesContent};
+pub use room_message_content::{
+    LocationMessage,
+    MediaMessage,
+    MessageType,
+    RoomMessageContent,
+    TextMessage,
+};
+pub use room_name_content::RoomNameContent;
+pub use room_pinned_events_content::RoomPinnedEventsContent;
+pub use room_redaction_content::RoomRedactionContent;
+pub use room_retention_content::RoomRetentionContent;
+pub use room_server_acl_content::RoomServerAclContent;
+pub use room_third_party_invite_content::{RoomThirdPartyInviteContent, ThirdPartyPublicKey};
+pub use room_tombstone_content::RoomTombstoneContent;
+pub use room_topic_content::RoomTopicContent;
+pub use room_version_rules::RoomVersionRules;
+pub use state_event::StateEvent;
+pub use typed_event_content::TypedEventContent;
@@ -0,0 +1,67 @@
+use crate::types::identifier::{matrix_identifier, validate_server_name};
+use crate::types::{IdentifierError, ServerName, ServerNameRef};
+
+matrix_identifier! {
+    /// MxcUri - content repository URI, `mxc://server_name/media_id`
+    /// Source: Matrix Client-Server API - Matrix Content (mxc://) URIs
+    owned: MxcUri,
+    /// Borrowed, non-allocating form of [`MxcUri`]
+    borrowed: MxcUriRef,
+    validate: validate_mxc_uri,
+}
+
+/// Validate an MXC URI; the media ID is restricted to `[A-Za-z0-9_-]+`
+pub fn validate_mxc_uri(uri: &str) -> Result<(), IdentifierError> {
+    let invalid = |reason: &str| IdentifierError::InvalidMxcUri(format!("{}: {}", reason, uri));
+
+    let rest = uri.strip_prefix("mxc://").ok_or_else(|| invalid("missing mxc:// scheme"))?;
+    let (server_name, media_id) =
+        rest.split_once('/').ok_or_else(|| invalid("missing media ID"))?;
+
+    validate_server_name(server_name)?;
+
+    if media_id.is_empty() {
+        return Err(invalid("empty media ID"));
+    }
+    if !media_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return Err(invalid("invalid media ID"));
+    }
+
+    Ok(())
+}
+
+impl MxcUri {
+    /// Build an MXC URI from its parts
+    pub fn new(server_name: &ServerName, media_id: &str) -> Result<Self, IdentifierError> {
+        Self::parse(format!("mxc://{}/{}", server_name, media_id))
+    }
+
+    /// The server hosting the media
+    pub fn server_name(&self) -> ServerNameRef<'_> {
+        self.as_borrowed().server_name()
+    }
+
+    /// The media ID on the hosting server
+    pub fn media_id(&self) -> &str {
+        self.as_borrowed().media_id()
+    }
+}
+
+impl<'a> MxcUriRef<'a> {
+    fn parts(&self) -> (&'a str, &'a str) {
+        self.0
+            .strip_prefix("mxc://")
+            .and_then(|rest| rest.split_once('/'))
+            .unwrap_or_default()
+    }
+
+    /// The server hosting the media
+    pub fn server_name(&self) -> ServerNameRef<'a> {
+        ServerNameRef::new_unchecked(self.parts().0)
+    }
+
+    /// The media ID on the hosting server
+    pub fn media_id(&self) -> &'a str {
+        self.parts().1
+    }
+}
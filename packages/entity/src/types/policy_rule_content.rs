@@ -0,0 +1,63 @@
+use crate::types::{EventContentError, RoomVersionRules, TypedEventContent};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// PolicyRuleContent - Content of `m.policy.rule.user`, `m.policy.rule.room`
+/// and `m.policy.rule.server`
+///
+/// `entity` is a glob matched against user IDs, room IDs/aliases or server
+/// names depending on the event type. An empty content object revokes a rule.
+/// Source: spec/client/05_advanced_md (Moderation policy lists)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PolicyRuleContent {
+    #[serde(default)]
+    pub entity: String,
+
+    #[serde(default)]
+    pub recommendation: String,
+
+    #[serde(default)]
+    pub reason: String,
+
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
+}
+
+impl PolicyRuleContent {
+    pub const USER_EVENT_TYPE: &'static str = "m.policy.rule.user";
+    pub const ROOM_EVENT_TYPE: &'static str = "m.policy.rule.room";
+    pub const SERVER_EVENT_TYPE: &'static str = "m.policy.rule.server";
+    pub const RECOMMENDATION_BAN: &'static str = "m.ban";
+
+    pub fn ban(entity: String, reason: String) -> Self {
+        Self {
+            entity,
+            recommendation: Self::RECOMMENDATION_BAN.to_string(),
+            reason,
+            unknown_fields: BTreeMap::new(),
+        }
+    }
+
+    /// Whether this content revokes a previously published rule
+    pub fn is_revoked(&self) -> bool {
+        self.entity.is_empty() && self.recommendation.is_empty()
+    }
+
+    /// Whether this rule recommends a ban (`m.ban` or the legacy `org.matrix.mjolnir.ban`)
+    pub fn is_ban(&self) -> bool {
+        matches!(self.recommendation.as_str(), "m.ban" | "org.matrix.mjolnir.ban")
+    }
+}
+
+impl TypedEventContent for PolicyRuleContent {
+    fn validate(&self, _rules: &RoomVersionRules) -> Result<(), EventContentError> {
+        if !self.is_revoked() && self.entity.is_empty() {
+            return Err(EventContentError::invalid_field("entity", "must not be empty"));
+        }
+        if !self.is_revoked() && self.recommendation.is_empty() {
+            return Err(EventContentError::invalid_field("recommendation", "must not be empty"));
+        }
+        Ok(())
+    }
+}
@@ -1,58 +1,109 @@
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use crate::types::{EventContentError, RoomVersionRules, TypedEventContent};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
 
 /// Power levels for a Matrix room
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PowerLevels {
     /// Default power level for users
-    #[serde(default = "default_users_default")]
+    #[serde(
+        default = "default_users_default",
+        deserialize_with = "deserialize_power_level"
+    )]
     pub users_default: i64,
 
     /// Default power level for events
-    #[serde(default = "default_events_default")]
+    #[serde(
+        default = "default_events_default",
+        deserialize_with = "deserialize_power_level"
+    )]
     pub events_default: i64,
 
     /// Power levels for specific users
-    #[serde(default)]
+    #[serde(default, deserialize_with = "deserialize_power_level_map")]
     pub users: HashMap<String, i64>,
 
     /// Power levels for specific event types
-    #[serde(default)]
+    #[serde(default, deserialize_with = "deserialize_power_level_map")]
     pub events: HashMap<String, i64>,
 
     /// Power level required to ban users
-    #[serde(default = "default_ban")]
+    #[serde(default = "default_ban", deserialize_with = "deserialize_power_level")]
     pub ban: i64,
 
     /// Power level required to kick users
-    #[serde(default = "default_kick")]
+    #[serde(default = "default_kick", deserialize_with = "deserialize_power_level")]
     pub kick: i64,
 
     /// Power level required to redact events
-    #[serde(default = "default_redact")]
+    #[serde(
+        default = "default_redact",
+        deserialize_with = "deserialize_power_level"
+    )]
     pub redact: i64,
 
     /// Power level required to invite users
-    #[serde(default = "default_invite")]
+    #[serde(
+        default = "default_invite",
+        deserialize_with = "deserialize_power_level"
+    )]
     pub invite: i64,
 
     /// Power level required to send state events
-    #[serde(default = "default_state_default")]
+    #[serde(
+        default = "default_state_default",
+        deserialize_with = "deserialize_power_level"
+    )]
     pub state_default: i64,
 
     /// Notifications power levels
     #[serde(default)]
     pub notifications: NotificationPowerLevels,
+
+    /// Fields not modelled above, preserved for round-tripping
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
 }
 
 /// Notification power levels
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct NotificationPowerLevels {
     /// Power level required for room-wide notifications
-    #[serde(default = "default_room")]
+    #[serde(default = "default_room", deserialize_with = "deserialize_power_level")]
     pub room: i64,
 }
 
+/// Accept integers and, for room versions before 10, integer strings
+fn power_level_from_value(value: &Value) -> Option<i64> {
+    match value {
+        Value::Number(number) => number.as_i64(),
+        Value::String(string) => string.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn deserialize_power_level<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+    let value = Value::deserialize(deserializer)?;
+    power_level_from_value(&value)
+        .ok_or_else(|| serde::de::Error::custom(format!("invalid power level: {}", value)))
+}
+
+fn deserialize_power_level_map<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<HashMap<String, i64>, D::Error> {
+    let values = HashMap::<String, Value>::deserialize(deserializer)?;
+    values
+        .into_iter()
+        .map(|(key, value)| match power_level_from_value(&value) {
+            Some(level) => Ok((key, level)),
+            None => {
+                Err(serde::de::Error::custom(format!("invalid power level for {}: {}", key, value)))
+            },
+        })
+        .collect()
+}
+
 fn default_users_default() -> i64 {
     0
 }
@@ -97,11 +148,14 @@ impl Default for PowerLevels {
             invite: default_invite(),
             state_default: default_state_default(),
             notifications: NotificationPowerLevels::default(),
+            unknown_fields: BTreeMap::new(),
         }
     }
 }
 
 impl PowerLevels {
+    pub const EVENT_TYPE: &'static str = "m.room.power_levels";
+
     /// Create new power levels with a room creator
     pub fn new_with_creator(creator_user_id: &str) -> Self {
         let mut power_levels = Self::default();
@@ -130,4 +184,58 @@ impl PowerLevels {
         let required_level = self.get_event_level(event_type);
         user_level >= required_level
     }
+
+    /// Get power level required to send a state event type
+    pub fn get_state_event_level(&self, event_type: &str) -> i64 {
+        self.events.get(event_type).copied().unwrap_or(self.state_default)
+    }
+
+    /// Check raw content against the integer-only rule of room version 10+
+    ///
+    /// Serde accepts string power levels for older rooms, so this has to run
+    /// on the original JSON before it is deserialized.
+    pub fn check_integer_values(content: &Value) -> Result<(), EventContentError> {
+        let Some(object) = content.as_object() else {
+            return Ok(());
+        };
+
+        let not_integer = |field: &str| {
+            EventContentError::invalid_field(
+                field,
+                "power levels must be integers in this room version",
+            )
+        };
+
+        for field in [
+            "users_default",
+            "events_default",
+            "state_default",
+            "ban",
+            "kick",
+            "redact",
+            "invite",
+        ] {
+            if object.get(field).is_some_and(|value| !value.is_i64()) {
+                return Err(not_integer(field));
+            }
+        }
+
+        for field in ["users", "events", "notifications"] {
+            if let Some(Value::Object(map)) = object.get(field) {
+                if map.values().any(|value| !value.is_i64()) {
+                    return Err(not_integer(field));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl TypedEventContent for PowerLevels {
+    fn validate(&self, _rules: &RoomVersionRules) -> Result<(), EventContentError> {
+        for user_id in self.users.keys() {
+            crate::types::UserId::parse(user_id)?;
+        }
+        Ok(())
+    }
 }
@@ -1,29 +1,32 @@
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 /// Push action object for Matrix push rules
 /// Represents object-type push actions with additional parameters
+/// The tweak value is a string for `sound` and a boolean for `highlight`
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PushActionObject {
     pub set_tweak: Option<String>,
-    pub value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
 }
 
 impl PushActionObject {
-    pub fn new(set_tweak: Option<String>, value: Option<String>) -> Self {
+    pub fn new(set_tweak: Option<String>, value: Option<Value>) -> Self {
         Self { set_tweak, value }
     }
 
     pub fn sound(sound: String) -> Self {
         Self {
             set_tweak: Some("sound".to_string()),
-            value: Some(sound),
+            value: Some(Value::String(sound)),
         }
     }
 
     pub fn highlight() -> Self {
         Self {
             set_tweak: Some("highlight".to_string()),
-            value: Some("true".to_string()),
+            value: Some(Value::Bool(true)),
         }
     }
 }
@@ -1,13 +1,14 @@
 use serde::{Deserialize, Serialize};
 
 /// Push condition value for Matrix push rules
-/// Represents the value field in push conditions which can be string, number, or boolean
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Represents the value field in push conditions which can be string, number, boolean or null
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum PushConditionValue {
     String(String),
     Number(f64),
     Boolean(bool),
+    Null,
 }
 
 impl PushConditionValue {
@@ -22,4 +23,14 @@ impl PushConditionValue {
     pub fn boolean(value: bool) -> Self {
         Self::Boolean(value)
     }
+
+    /// Whether a JSON value from an event equals this one
+    pub fn matches(&self, value: &serde_json::Value) -> bool {
+        match self {
+            Self::String(expected) => value.as_str() == Some(expected.as_str()),
+            Self::Number(expected) => value.as_f64() == Some(*expected),
+            Self::Boolean(expected) => value.as_bool() == Some(*expected),
+            Self::Null => value.is_null(),
+        }
+    }
 }
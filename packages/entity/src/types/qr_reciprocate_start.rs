@@ -1,18 +1,27 @@
-use crate::types::VerificationRelatesTo;
+use crate::types::{TypedEventContent, VerificationRelatesTo};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
 
 /// QRReciprocateStart
 /// Source: spec/client/04_security_md:1818-1822
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QRReciprocateStart {
     pub from_device: String,
+    #[serde(rename = "m.relates_to", skip_serializing_if = "Option::is_none")]
     pub m_relates_to: Option<VerificationRelatesTo>,
     pub method: String,
     pub secret: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub transaction_id: Option<String>,
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
 }
 
 impl QRReciprocateStart {
+    pub const EVENT_TYPE: &'static str = "m.key.verification.start";
+    pub const METHOD: &'static str = "m.reciprocate.v1";
+
     pub fn new(
         from_device: String,
         m_relates_to: Option<VerificationRelatesTo>,
@@ -26,6 +35,9 @@ impl QRReciprocateStart {
             method,
             secret,
             transaction_id,
+            unknown_fields: BTreeMap::new(),
         }
     }
 }
+
+impl TypedEventContent for QRReciprocateStart {}
@@ -0,0 +1,57 @@
+use crate::types::{EventContentError, EventId, Relation, RoomVersionRules, TypedEventContent};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// ReactionContent - Content of `m.reaction`
+/// Source: spec/client/07_relationship_md (Event annotations and reactions)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReactionContent {
+    #[serde(rename = "m.relates_to")]
+    pub relates_to: Relation,
+
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
+}
+
+impl ReactionContent {
+    pub const EVENT_TYPE: &'static str = "m.reaction";
+
+    pub fn new(event_id: String, key: String) -> Self {
+        Self {
+            relates_to: Relation::annotation(event_id, key),
+            unknown_fields: BTreeMap::new(),
+        }
+    }
+
+    /// The annotated event
+    pub fn event_id(&self) -> Option<&str> {
+        self.relates_to.event_id.as_deref()
+    }
+
+    /// The reaction key, usually an emoji
+    pub fn key(&self) -> Option<&str> {
+        self.relates_to.key.as_deref()
+    }
+}
+
+impl TypedEventContent for ReactionContent {
+    fn validate(&self, _rules: &RoomVersionRules) -> Result<(), EventContentError> {
+        if !self.relates_to.is(Relation::ANNOTATION) {
+            return Err(EventContentError::invalid_field(
+                "m.relates_to.rel_type",
+                "reactions must use m.annotation",
+            ));
+        }
+
+        let event_id = self
+            .event_id()
+            .ok_or_else(|| EventContentError::MissingField("m.relates_to.event_id".to_string()))?;
+        EventId::parse(event_id)?;
+
+        match self.key() {
+            Some(key) if !key.is_empty() => Ok(()),
+            _ => Err(EventContentError::MissingField("m.relates_to.key".to_string())),
+        }
+    }
+}
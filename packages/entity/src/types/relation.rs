@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Relation - The `m.relates_to` object of a message-like event
+///
+/// Covers annotations, references, replacements, threads and rich replies.
+/// Fields that are not modelled are kept in `unknown_fields`.
+/// Source: spec/client/07_relationship_md
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Relation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rel_type: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_id: Option<String>,
+
+    /// Annotation key (`m.annotation` only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+
+    /// Thread fallback marker (`m.thread` only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_falling_back: Option<bool>,
+
+    #[serde(rename = "m.in_reply_to", skip_serializing_if = "Option::is_none")]
+    pub in_reply_to: Option<InReplyTo>,
+
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
+}
+
+/// Rich reply target of a `Relation`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InReplyTo {
+    pub event_id: String,
+}
+
+impl Relation {
+    pub const ANNOTATION: &'static str = "m.annotation";
+    pub const REFERENCE: &'static str = "m.reference";
+    pub const REPLACE: &'static str = "m.replace";
+    pub const THREAD: &'static str = "m.thread";
+
+    pub fn annotation(event_id: String, key: String) -> Self {
+        Self {
+            rel_type: Some(Self::ANNOTATION.to_string()),
+            event_id: Some(event_id),
+            key: Some(key),
+            ..Default::default()
+        }
+    }
+
+    pub fn reference(event_id: String) -> Self {
+        Self {
+            rel_type: Some(Self::REFERENCE.to_string()),
+            event_id: Some(event_id),
+            ..Default::default()
+        }
+    }
+
+    pub fn replace(event_id: String) -> Self {
+        Self {
+            rel_type: Some(Self::REPLACE.to_string()),
+            event_id: Some(event_id),
+            ..Default::default()
+        }
+    }
+
+    pub fn thread(thread_root: String, latest_event_id: String) -> Self {
+        Self {
+            rel_type: Some(Self::THREAD.to_string()),
+            event_id: Some(thread_root),
+            is_falling_back: Some(true),
+            in_reply_to: Some(InReplyTo { event_id: latest_event_id }),
+            ..Default::default()
+        }
+    }
+
+    pub fn reply(event_id: String) -> Self {
+        Self {
+            in_reply_to: Some(InReplyTo { event_id }),
+            ..Default::default()
+        }
+    }
+
+    /// Whether this relation has the given `rel_type`
+    pub fn is(&self, rel_type: &str) -> bool {
+        self.rel_type.as_deref() == Some(rel_type)
+    }
+}
@@ -0,0 +1,52 @@
+use crate::types::identifier::{matrix_identifier, split_sigil_id};
+use crate::types::{IdentifierError, ServerName, ServerNameRef};
+
+matrix_identifier! {
+    /// RoomAliasId - validated Matrix room alias, `#alias:server_name`
+    /// Source: Matrix Appendices - Room Aliases
+    owned: RoomAliasId,
+    /// Borrowed, non-allocating form of [`RoomAliasId`]
+    borrowed: RoomAliasIdRef,
+    validate: validate_room_alias_id,
+}
+
+/// Validate a room alias; the alias part may be any Unicode except `:` and NUL
+pub fn validate_room_alias_id(alias: &str) -> Result<(), IdentifierError> {
+    let (localpart, _) = split_sigil_id(alias, '#')?;
+
+    match localpart.chars().find(|c| *c == '\0') {
+        Some(c) => Err(IdentifierError::InvalidLocalpart(c)),
+        None => Ok(()),
+    }
+}
+
+impl RoomAliasId {
+    /// Build a room alias from its local alias name and server name
+    pub fn new(alias: &str, server_name: &ServerName) -> Result<Self, IdentifierError> {
+        Self::parse(format!("#{}:{}", alias, server_name))
+    }
+
+    /// The alias part, without the `#` sigil
+    pub fn alias(&self) -> &str {
+        self.as_borrowed().alias()
+    }
+
+    /// The server that owns the alias
+    pub fn server_name(&self) -> ServerNameRef<'_> {
+        self.as_borrowed().server_name()
+    }
+}
+
+impl<'a> RoomAliasIdRef<'a> {
+    /// The alias part, without the `#` sigil
+    pub fn alias(&self) -> &'a str {
+        self.0[1..].split_once(':').map(|(alias, _)| alias).unwrap_or_default()
+    }
+
+    /// The server that owns the alias
+    pub fn server_name(&self) -> ServerNameRef<'a> {
+        ServerNameRef::new_unchecked(
+            self.0.split_once(':').map(|(_, server)| server).unwrap_or_default(),
+        )
+    }
+}
@@ -0,0 +1,37 @@
+use crate::types::{EventContentError, MediaInfo, MxcUri, RoomVersionRules, TypedEventContent};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// RoomAvatarContent - Content of `m.room.avatar`
+///
+/// An absent `url` removes the room avatar.
+/// Source: spec/client/08_instant_messaging_md (m.room.avatar)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RoomAvatarContent {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub info: Option<MediaInfo>,
+
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
+}
+
+impl RoomAvatarContent {
+    pub const EVENT_TYPE: &'static str = "m.room.avatar";
+
+    pub fn new(url: Option<String>) -> Self {
+        Self { url, ..Default::default() }
+    }
+}
+
+impl TypedEventContent for RoomAvatarContent {
+    fn validate(&self, _rules: &RoomVersionRules) -> Result<(), EventContentError> {
+        if let Some(url) = &self.url {
+            MxcUri::parse(url)?;
+        }
+        Ok(())
+    }
+}
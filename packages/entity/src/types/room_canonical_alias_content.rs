@@ -0,0 +1,51 @@
+use crate::types::{EventContentError, RoomAliasId, RoomVersionRules, TypedEventContent};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// RoomCanonicalAliasContent - Content of `m.room.canonical_alias`
+/// Source: spec/client/08_instant_messaging_md (m.room.canonical_alias)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RoomCanonicalAliasContent {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alt_aliases: Option<Vec<String>>,
+
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
+}
+
+impl RoomCanonicalAliasContent {
+    pub const EVENT_TYPE: &'static str = "m.room.canonical_alias";
+
+    pub fn new(alias: Option<String>, alt_aliases: Vec<String>) -> Self {
+        Self {
+            alias,
+            alt_aliases: if alt_aliases.is_empty() {
+                None
+            } else {
+                Some(alt_aliases)
+            },
+            unknown_fields: BTreeMap::new(),
+        }
+    }
+
+    /// All aliases advertised by this event, canonical alias first
+    pub fn all_aliases(&self) -> impl Iterator<Item = &str> {
+        self.alias
+            .iter()
+            .chain(self.alt_aliases.iter().flatten())
+            .map(String::as_str)
+    }
+}
+
+impl TypedEventContent for RoomCanonicalAliasContent {
+    fn validate(&self, _rules: &RoomVersionRules) -> Result<(), EventContentError> {
+        for alias in self.all_aliases() {
+            RoomAliasId::parse(alias)?;
+        }
+        Ok(())
+    }
+}
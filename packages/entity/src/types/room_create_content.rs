@@ -0,0 +1,85 @@
+use crate::types::{EventContentError, RoomId, RoomVersionRules, TypedEventContent, UserId};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// RoomCreateContent - Content of `m.room.create`
+///
+/// `creator` is required up to room version 10 and removed in version 11,
+/// where the event sender is the creator.
+/// Source: spec/client/08_instant_messaging_md (m.room.create)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RoomCreateContent {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub creator: Option<String>,
+
+    #[serde(rename = "m.federate", skip_serializing_if = "Option::is_none")]
+    pub federate: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub room_version: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub predecessor: Option<PreviousRoom>,
+
+    /// Room type, e.g. `m.space`
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub room_type: Option<String>,
+
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
+}
+
+/// Reference to the room an upgraded room replaces
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreviousRoom {
+    pub room_id: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_id: Option<String>,
+}
+
+impl RoomCreateContent {
+    pub const EVENT_TYPE: &'static str = "m.room.create";
+    pub const SPACE_ROOM_TYPE: &'static str = "m.space";
+
+    pub fn new(room_version: String) -> Self {
+        Self {
+            room_version: Some(room_version),
+            ..Default::default()
+        }
+    }
+
+    /// Room version, defaulting to "1" when absent per the spec
+    pub fn room_version(&self) -> &str {
+        self.room_version.as_deref().unwrap_or("1")
+    }
+
+    /// Whether users on other homeservers may join, defaulting to true
+    pub fn is_federated(&self) -> bool {
+        self.federate.unwrap_or(true)
+    }
+
+    pub fn is_space(&self) -> bool {
+        self.room_type.as_deref() == Some(Self::SPACE_ROOM_TYPE)
+    }
+}
+
+impl TypedEventContent for RoomCreateContent {
+    fn validate(&self, rules: &RoomVersionRules) -> Result<(), EventContentError> {
+        match &self.creator {
+            Some(creator) => {
+                UserId::parse(creator)?;
+            },
+            None if rules.create_requires_creator => {
+                return Err(EventContentError::MissingField("creator".to_string()));
+            },
+            None => {},
+        }
+
+        if let Some(predecessor) = &self.predecessor {
+            RoomId::parse(&predecessor.room_id)?;
+        }
+        Ok(())
+    }
+}
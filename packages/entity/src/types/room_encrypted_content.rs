@@ -0,0 +1,86 @@
+use crate::types::{CipherText, EventContentError, Relation, RoomVersionRules, TypedEventContent};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// RoomEncryptedContent - Content of `m.room.encrypted`
+///
+/// Megolm payloads carry a single ciphertext string and a session ID; Olm
+/// payloads carry one ciphertext per recipient Curve25519 key.
+/// Source: spec/client/04_security_md (m.room.encrypted)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomEncryptedContent {
+    pub algorithm: String,
+
+    pub ciphertext: EncryptedCiphertext,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sender_key: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_id: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+
+    /// Unencrypted relation, kept in the clear so servers can aggregate
+    #[serde(rename = "m.relates_to", skip_serializing_if = "Option::is_none")]
+    pub relates_to: Option<Relation>,
+
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
+}
+
+/// Ciphertext of an `m.room.encrypted` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum EncryptedCiphertext {
+    /// `m.megolm.v1.aes-sha2`
+    Megolm(String),
+
+    /// `m.olm.v1.curve25519-aes-sha2`, keyed by recipient identity key
+    Olm(BTreeMap<String, CipherText>),
+}
+
+impl RoomEncryptedContent {
+    pub const EVENT_TYPE: &'static str = "m.room.encrypted";
+    pub const MEGOLM_V1: &'static str = "m.megolm.v1.aes-sha2";
+    pub const OLM_V1: &'static str = "m.olm.v1.curve25519-aes-sha2";
+
+    pub fn megolm(ciphertext: String, session_id: String) -> Self {
+        Self {
+            algorithm: Self::MEGOLM_V1.to_string(),
+            ciphertext: EncryptedCiphertext::Megolm(ciphertext),
+            sender_key: None,
+            device_id: None,
+            session_id: Some(session_id),
+            relates_to: None,
+            unknown_fields: BTreeMap::new(),
+        }
+    }
+}
+
+impl TypedEventContent for RoomEncryptedContent {
+    fn validate(&self, _rules: &RoomVersionRules) -> Result<(), EventContentError> {
+        match (self.algorithm.as_str(), &self.ciphertext) {
+            (Self::MEGOLM_V1, EncryptedCiphertext::Megolm(_)) => {
+                if self.session_id.is_none() {
+                    return Err(EventContentError::MissingField("session_id".to_string()));
+                }
+                Ok(())
+            },
+            (Self::OLM_V1, EncryptedCiphertext::Olm(_)) => {
+                if self.sender_key.is_none() {
+                    return Err(EventContentError::MissingField("sender_key".to_string()));
+                }
+                Ok(())
+            },
+            (Self::MEGOLM_V1, _) | (Self::OLM_V1, _) => Err(EventContentError::invalid_field(
+                "ciphertext",
+                format!("does not match algorithm {}", self.algorithm),
+            )),
+            // Unknown algorithms are passed through untouched
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use crate::types::{EventContentError, RoomVersionRules, TypedEventContent};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// RoomEncryptionContent - Content of `m.room.encryption`
+/// Source: spec/client/04_security_md (m.room.encryption)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomEncryptionContent {
+    pub algorithm: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rotation_period_ms: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rotation_period_msgs: Option<u64>,
+
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
+}
+
+impl RoomEncryptionContent {
+    pub const EVENT_TYPE: &'static str = "m.room.encryption";
+    pub const MEGOLM_V1: &'static str = "m.megolm.v1.aes-sha2";
+
+    /// Default Megolm rotation period in milliseconds (one week)
+    pub const DEFAULT_ROTATION_PERIOD_MS: u64 = 604_800_000;
+
+    /// Default Megolm rotation period in messages
+    pub const DEFAULT_ROTATION_PERIOD_MSGS: u64 = 100;
+
+    pub fn megolm() -> Self {
+        Self {
+            algorithm: Self::MEGOLM_V1.to_string(),
+            rotation_period_ms: None,
+            rotation_period_msgs: None,
+            unknown_fields: BTreeMap::new(),
+        }
+    }
+
+    pub fn rotation_period_ms(&self) -> u64 {
+        self.rotation_period_ms.unwrap_or(Self::DEFAULT_ROTATION_PERIOD_MS)
+    }
+
+    pub fn rotation_period_msgs(&self) -> u64 {
+        self.rotation_period_msgs.unwrap_or(Self::DEFAULT_ROTATION_PERIOD_MSGS)
+    }
+}
+
+impl TypedEventContent for RoomEncryptionContent {
+    fn validate(&self, _rules: &RoomVersionRules) -> Result<(), EventContentError> {
+        if self.algorithm.is_empty() {
+            return Err(EventContentError::invalid_field("algorithm", "must not be empty"));
+        }
+        Ok(())
+    }
+}
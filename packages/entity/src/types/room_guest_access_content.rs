@@ -0,0 +1,24 @@
+use crate::types::{GuestAccess, TypedEventContent};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// RoomGuestAccessContent - Content of `m.room.guest_access`
+/// Source: spec/client/05_advanced_md (Guest access)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomGuestAccessContent {
+    pub guest_access: GuestAccess,
+
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
+}
+
+impl RoomGuestAccessContent {
+    pub const EVENT_TYPE: &'static str = "m.room.guest_access";
+
+    pub fn new(guest_access: GuestAccess) -> Self {
+        Self { guest_access, unknown_fields: BTreeMap::new() }
+    }
+}
+
+impl TypedEventContent for RoomGuestAccessContent {}
@@ -0,0 +1,27 @@
+use crate::types::{HistoryVisibility, TypedEventContent};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// RoomHistoryVisibilityContent - Content of `m.room.history_visibility`
+/// Source: spec/client/05_advanced_md:47
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomHistoryVisibilityContent {
+    pub history_visibility: HistoryVisibility,
+
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
+}
+
+impl RoomHistoryVisibilityContent {
+    pub const EVENT_TYPE: &'static str = "m.room.history_visibility";
+
+    pub fn new(history_visibility: HistoryVisibility) -> Self {
+        Self {
+            history_visibility,
+            unknown_fields: BTreeMap::new(),
+        }
+    }
+}
+
+impl TypedEventContent for RoomHistoryVisibilityContent {}
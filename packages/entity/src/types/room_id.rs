@@ -0,0 +1,52 @@
+use crate::types::identifier::{matrix_identifier, split_sigil_id};
+use crate::types::{IdentifierError, ServerName, ServerNameRef};
+
+matrix_identifier! {
+    /// RoomId - validated Matrix room ID, `!opaque_id:server_name`
+    /// Source: Matrix Appendices - Room IDs
+    owned: RoomId,
+    /// Borrowed, non-allocating form of [`RoomId`]
+    borrowed: RoomIdRef,
+    validate: validate_room_id,
+}
+
+/// Validate a room ID
+pub fn validate_room_id(room_id: &str) -> Result<(), IdentifierError> {
+    let (opaque_id, _) = split_sigil_id(room_id, '!')?;
+
+    match opaque_id.chars().find(|c| c.is_control()) {
+        Some(c) => Err(IdentifierError::InvalidLocalpart(c)),
+        None => Ok(()),
+    }
+}
+
+impl RoomId {
+    /// Build a room ID from an opaque localpart and the creating server's name
+    pub fn new(opaque_id: &str, server_name: &ServerName) -> Result<Self, IdentifierError> {
+        Self::parse(format!("!{}:{}", opaque_id, server_name))
+    }
+
+    /// The opaque localpart, without the `!` sigil
+    pub fn opaque_id(&self) -> &str {
+        self.as_borrowed().opaque_id()
+    }
+
+    /// The server name embedded in the room ID
+    ///
+    /// This is the server that created the room; it carries no authority.
+    pub fn server_name(&self) -> Option<ServerNameRef<'_>> {
+        self.as_borrowed().server_name()
+    }
+}
+
+impl<'a> RoomIdRef<'a> {
+    /// The opaque localpart, without the `!` sigil
+    pub fn opaque_id(&self) -> &'a str {
+        self.0[1..].split_once(':').map(|(opaque, _)| opaque).unwrap_or_default()
+    }
+
+    /// The server name embedded in the room ID
+    pub fn server_name(&self) -> Option<ServerNameRef<'a>> {
+        self.0.split_once(':').map(|(_, server)| ServerNameRef::new_unchecked(server))
+    }
+}
@@ -0,0 +1,86 @@
+use crate::types::{EventContentError, JoinRules, RoomId, RoomVersionRules, TypedEventContent};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// RoomJoinRulesContent - Content of `m.room.join_rules`
+/// Source: spec/client/08_instant_messaging_md (m.room.join_rules)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomJoinRulesContent {
+    pub join_rule: JoinRules,
+
+    /// Conditions for `restricted` and `knock_restricted` joins
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow: Option<Vec<AllowCondition>>,
+
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
+}
+
+/// A single entry of the `allow` list of restricted join rules
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AllowCondition {
+    #[serde(rename = "type")]
+    pub condition_type: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub room_id: Option<String>,
+
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
+}
+
+impl AllowCondition {
+    pub const ROOM_MEMBERSHIP: &'static str = "m.room_membership";
+
+    pub fn room_membership(room_id: String) -> Self {
+        Self {
+            condition_type: Self::ROOM_MEMBERSHIP.to_string(),
+            room_id: Some(room_id),
+            unknown_fields: BTreeMap::new(),
+        }
+    }
+}
+
+impl RoomJoinRulesContent {
+    pub const EVENT_TYPE: &'static str = "m.room.join_rules";
+
+    pub fn new(join_rule: JoinRules) -> Self {
+        Self {
+            join_rule,
+            allow: None,
+            unknown_fields: BTreeMap::new(),
+        }
+    }
+
+    /// Room IDs whose members may join under a restricted join rule
+    pub fn allowed_room_ids(&self) -> impl Iterator<Item = &str> {
+        self.allow
+            .iter()
+            .flatten()
+            .filter(|condition| condition.condition_type == AllowCondition::ROOM_MEMBERSHIP)
+            .filter_map(|condition| condition.room_id.as_deref())
+    }
+}
+
+impl TypedEventContent for RoomJoinRulesContent {
+    fn validate(&self, rules: &RoomVersionRules) -> Result<(), EventContentError> {
+        let allowed = match self.join_rule {
+            JoinRules::Knock => rules.knocking,
+            JoinRules::Restricted => rules.restricted_join_rule,
+            JoinRules::KnockRestricted => rules.knock_restricted_join_rule,
+            JoinRules::Public | JoinRules::Invite | JoinRules::Private => true,
+        };
+        if !allowed {
+            return Err(EventContentError::NotAllowedInRoomVersion {
+                event_type: Self::EVENT_TYPE.to_string(),
+                message: format!("join rule '{}' is not supported", self.join_rule),
+            });
+        }
+
+        for room_id in self.allowed_room_ids() {
+            RoomId::parse(room_id)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,363 @@
+use crate::types::{
+    EncryptedFile, EventContentError, MediaInfo, Mentions, MxcUri, Relation, RoomVersionRules,
+    TypedEventContent, VerificationRequestInRoom,
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// RoomMessageContent - Content of `m.room.message`
+///
+/// The msgtype-specific body lives in `msgtype`; relations, mentions and
+/// edits are common to every msgtype and are lifted out of it.
+/// Source: spec/client/08_instant_messaging_md (m.room.message msgtypes)
+#[derive(Debug, Clone)]
+pub struct RoomMessageContent {
+    pub msgtype: MessageType,
+
+    /// `m.relates_to` - replies, threads and edits
+    pub relates_to: Option<Relation>,
+
+    /// `m.mentions` - intentional mentions
+    pub mentions: Option<Mentions>,
+
+    /// `m.new_content` - replacement content of an edit
+    pub new_content: Option<Box<RoomMessageContent>>,
+}
+
+/// The msgtype-specific part of an `m.room.message`
+#[derive(Debug, Clone)]
+pub enum MessageType {
+    Text(TextMessage),
+    Emote(TextMessage),
+    Notice(TextMessage),
+    Image(MediaMessage),
+    File(MediaMessage),
+    Audio(MediaMessage),
+    Video(MediaMessage),
+    Location(LocationMessage),
+    VerificationRequest(VerificationRequestInRoom),
+
+    /// A msgtype this crate does not model; all fields are kept as-is
+    Unknown {
+        msgtype: String,
+        fields: BTreeMap<String, Value>,
+    },
+}
+
+/// Body of `m.text`, `m.emote` and `m.notice` messages
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TextMessage {
+    pub body: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub formatted_body: Option<String>,
+
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
+}
+
+/// Body of `m.image`, `m.file`, `m.audio` and `m.video` messages
+///
+/// Exactly one of `url` (plaintext media) or `file` (encrypted media) is set.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MediaMessage {
+    pub body: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<EncryptedFile>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub info: Option<MediaInfo>,
+
+    /// Original filename when `body` is used as a caption
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub formatted_body: Option<String>,
+
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
+}
+
+/// Body of `m.location` messages
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LocationMessage {
+    pub body: String,
+
+    pub geo_uri: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub info: Option<MediaInfo>,
+
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
+}
+
+impl TextMessage {
+    pub fn plain(body: String) -> Self {
+        Self { body, ..Default::default() }
+    }
+
+    pub fn html(body: String, formatted_body: String) -> Self {
+        Self {
+            body,
+            format: Some("org.matrix.custom.html".to_string()),
+            formatted_body: Some(formatted_body),
+            unknown_fields: BTreeMap::new(),
+        }
+    }
+}
+
+impl MessageType {
+    pub fn msgtype(&self) -> &str {
+        match self {
+            MessageType::Text(_) => "m.text",
+            MessageType::Emote(_) => "m.emote",
+            MessageType::Notice(_) => "m.notice",
+            MessageType::Image(_) => "m.image",
+            MessageType::File(_) => "m.file",
+            MessageType::Audio(_) => "m.audio",
+            MessageType::Video(_) => "m.video",
+            MessageType::Location(_) => "m.location",
+            MessageType::VerificationRequest(_) => VerificationRequestInRoom::MSGTYPE,
+            MessageType::Unknown { msgtype, .. } => msgtype,
+        }
+    }
+
+    /// Plain-text body, present on every msgtype
+    pub fn body(&self) -> &str {
+        match self {
+            MessageType::Text(text) | MessageType::Emote(text) | MessageType::Notice(text) => {
+                &text.body
+            },
+            MessageType::Image(media)
+            | MessageType::File(media)
+            | MessageType::Audio(media)
+            | MessageType::Video(media) => &media.body,
+            MessageType::Location(location) => &location.body,
+            MessageType::VerificationRequest(request) => &request.body,
+            MessageType::Unknown { fields, .. } => {
+                fields.get("body").and_then(Value::as_str).unwrap_or_default()
+            },
+        }
+    }
+
+    fn from_fields(msgtype: String, fields: Map<String, Value>) -> Result<Self, serde_json::Error> {
+        let value = Value::Object(fields);
+        Ok(match msgtype.as_str() {
+            "m.text" => MessageType::Text(serde_json::from_value(value)?),
+            "m.emote" => MessageType::Emote(serde_json::from_value(value)?),
+            "m.notice" => MessageType::Notice(serde_json::from_value(value)?),
+            "m.image" => MessageType::Image(serde_json::from_value(value)?),
+            "m.file" => MessageType::File(serde_json::from_value(value)?),
+            "m.audio" => MessageType::Audio(serde_json::from_value(value)?),
+            "m.video" => MessageType::Video(serde_json::from_value(value)?),
+            "m.location" => MessageType::Location(serde_json::from_value(value)?),
+            VerificationRequestInRoom::MSGTYPE => {
+                // VerificationRequestInRoom models msgtype as a field of its own
+                let mut fields = match value {
+                    Value::Object(fields) => fields,
+                    _ => Map::new(),
+                };
+                fields.insert("msgtype".to_string(), Value::String(msgtype));
+                MessageType::VerificationRequest(serde_json::from_value(Value::Object(fields))?)
+            },
+            _ => {
+                let fields = match value {
+                    Value::Object(fields) => fields.into_iter().collect(),
+                    _ => BTreeMap::new(),
+                };
+                MessageType::Unknown { msgtype, fields }
+            },
+        })
+    }
+
+    fn to_fields(&self) -> Result<Map<String, Value>, serde_json::Error> {
+        let value = match self {
+            MessageType::Text(text) | MessageType::Emote(text) | MessageType::Notice(text) => {
+                serde_json::to_value(text)?
+            },
+            MessageType::Image(media)
+            | MessageType::File(media)
+            | MessageType::Audio(media)
+            | MessageType::Video(media) => serde_json::to_value(media)?,
+            MessageType::Location(location) => serde_json::to_value(location)?,
+            MessageType::VerificationRequest(request) => serde_json::to_value(request)?,
+            MessageType::Unknown { fields, .. } => {
+                Value::Object(fields.clone().into_iter().collect())
+            },
+        };
+
+        let mut fields = match value {
+            Value::Object(fields) => fields,
+            _ => Map::new(),
+        };
+        fields.insert("msgtype".to_string(), Value::String(self.msgtype().to_string()));
+        Ok(fields)
+    }
+}
+
+impl RoomMessageContent {
+    pub const EVENT_TYPE: &'static str = "m.room.message";
+
+    pub fn new(msgtype: MessageType) -> Self {
+        Self {
+            msgtype,
+            relates_to: None,
+            mentions: None,
+            new_content: None,
+        }
+    }
+
+    pub fn text_plain(body: impl Into<String>) -> Self {
+        Self::new(MessageType::Text(TextMessage::plain(body.into())))
+    }
+
+    pub fn text_html(body: impl Into<String>, formatted_body: impl Into<String>) -> Self {
+        Self::new(MessageType::Text(TextMessage::html(body.into(), formatted_body.into())))
+    }
+
+    pub fn notice_plain(body: impl Into<String>) -> Self {
+        Self::new(MessageType::Notice(TextMessage::plain(body.into())))
+    }
+
+    pub fn body(&self) -> &str {
+        self.msgtype.body()
+    }
+
+    /// Event ID this message replaces, if it is an edit
+    pub fn replaces(&self) -> Option<&str> {
+        self.relates_to
+            .as_ref()
+            .filter(|relation| relation.is(Relation::REPLACE))
+            .and_then(|relation| relation.event_id.as_deref())
+    }
+
+    /// Root event ID of the thread this message belongs to
+    pub fn thread_root(&self) -> Option<&str> {
+        self.relates_to
+            .as_ref()
+            .filter(|relation| relation.is(Relation::THREAD))
+            .and_then(|relation| relation.event_id.as_deref())
+    }
+
+    /// Event ID this message replies to
+    pub fn in_reply_to(&self) -> Option<&str> {
+        self.relates_to
+            .as_ref()
+            .and_then(|relation| relation.in_reply_to.as_ref())
+            .map(|reply| reply.event_id.as_str())
+    }
+}
+
+impl Serialize for RoomMessageContent {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::Error;
+
+        let mut fields = self.msgtype.to_fields().map_err(S::Error::custom)?;
+        if let Some(relates_to) = &self.relates_to {
+            fields.insert(
+                "m.relates_to".to_string(),
+                serde_json::to_value(relates_to).map_err(S::Error::custom)?,
+            );
+        }
+        if let Some(mentions) = &self.mentions {
+            fields.insert(
+                "m.mentions".to_string(),
+                serde_json::to_value(mentions).map_err(S::Error::custom)?,
+            );
+        }
+        if let Some(new_content) = &self.new_content {
+            fields.insert(
+                "m.new_content".to_string(),
+                serde_json::to_value(new_content).map_err(S::Error::custom)?,
+            );
+        }
+        fields.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for RoomMessageContent {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let mut fields = Map::deserialize(deserializer)?;
+        let msgtype = match fields.remove("msgtype") {
+            Some(Value::String(msgtype)) => msgtype,
+            Some(_) => return Err(D::Error::custom("msgtype must be a string")),
+            None => return Err(D::Error::missing_field("msgtype")),
+        };
+
+        let relates_to = fields
+            .remove("m.relates_to")
+            .map(serde_json::from_value)
+            .transpose()
+            .map_err(D::Error::custom)?;
+        let mentions = fields
+            .remove("m.mentions")
+            .map(serde_json::from_value)
+            .transpose()
+            .map_err(D::Error::custom)?;
+        let new_content = fields
+            .remove("m.new_content")
+            .map(serde_json::from_value::<RoomMessageContent>)
+            .transpose()
+            .map_err(D::Error::custom)?
+            .map(Box::new);
+
+        let msgtype = MessageType::from_fields(msgtype, fields).map_err(D::Error::custom)?;
+        Ok(Self { msgtype, relates_to, mentions, new_content })
+    }
+}
+
+impl TypedEventContent for RoomMessageContent {
+    fn validate(&self, _rules: &RoomVersionRules) -> Result<(), EventContentError> {
+        validate_msgtype(&self.msgtype)?;
+        if let Some(new_content) = &self.new_content {
+            validate_msgtype(&new_content.msgtype)?;
+        }
+
+        if let Some(relation) = &self.relates_to {
+            if relation.is(Relation::REPLACE) && relation.event_id.is_none() {
+                return Err(EventContentError::MissingField("m.relates_to.event_id".to_string()));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Media messages must reference exactly one valid plaintext or encrypted MXC URI
+fn validate_msgtype(msgtype: &MessageType) -> Result<(), EventContentError> {
+    if let MessageType::Image(media)
+    | MessageType::File(media)
+    | MessageType::Audio(media)
+    | MessageType::Video(media) = msgtype
+    {
+        match (&media.url, &media.file) {
+            (Some(url), None) => {
+                MxcUri::parse(url)?;
+            },
+            (None, Some(file)) => {
+                MxcUri::parse(&file.url)?;
+            },
+            _ => {
+                return Err(EventContentError::invalid_field(
+                    "url",
+                    "exactly one of url or file must be present",
+                ));
+            },
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use crate::types::{EventContentError, RoomVersionRules, TypedEventContent};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// RoomNameContent - Content of `m.room.name`
+/// Source: spec/client/08_instant_messaging_md (m.room.name)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomNameContent {
+    pub name: String,
+
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
+}
+
+impl RoomNameContent {
+    pub const EVENT_TYPE: &'static str = "m.room.name";
+
+    /// Maximum length of a room name in bytes
+    pub const MAX_LENGTH: usize = 255;
+
+    pub fn new(name: String) -> Self {
+        Self { name, unknown_fields: BTreeMap::new() }
+    }
+}
+
+impl TypedEventContent for RoomNameContent {
+    fn validate(&self, _rules: &RoomVersionRules) -> Result<(), EventContentError> {
+        if self.name.len() > Self::MAX_LENGTH {
+            return Err(EventContentError::invalid_field(
+                "name",
+                format!("must not exceed {} bytes", Self::MAX_LENGTH),
+            ));
+        }
+        Ok(())
+    }
+}
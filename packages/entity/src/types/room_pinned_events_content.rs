@@ -0,0 +1,24 @@
+use crate::types::TypedEventContent;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// RoomPinnedEventsContent - Content of `m.room.pinned_events`
+/// Source: spec/client/08_instant_messaging_md (Pinned events)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RoomPinnedEventsContent {
+    pub pinned: Vec<String>,
+
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
+}
+
+impl RoomPinnedEventsContent {
+    pub const EVENT_TYPE: &'static str = "m.room.pinned_events";
+
+    pub fn new(pinned: Vec<String>) -> Self {
+        Self { pinned, unknown_fields: BTreeMap::new() }
+    }
+}
+
+impl TypedEventContent for RoomPinnedEventsContent {}
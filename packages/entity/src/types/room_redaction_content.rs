@@ -0,0 +1,48 @@
+use crate::types::{EventContentError, EventId, RoomVersionRules, TypedEventContent};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// RoomRedactionContent - Content of `m.room.redaction`
+///
+/// From room version 11 the redacted event ID lives in `content.redacts`;
+/// earlier versions carry it as a top-level `redacts` key on the event.
+/// Source: spec/client/06_modules_md (Redactions)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RoomRedactionContent {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redacts: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
+}
+
+impl RoomRedactionContent {
+    pub const EVENT_TYPE: &'static str = "m.room.redaction";
+
+    pub fn new(redacts: String, reason: Option<String>) -> Self {
+        Self {
+            redacts: Some(redacts),
+            reason,
+            unknown_fields: BTreeMap::new(),
+        }
+    }
+}
+
+impl TypedEventContent for RoomRedactionContent {
+    fn validate(&self, rules: &RoomVersionRules) -> Result<(), EventContentError> {
+        match &self.redacts {
+            Some(redacts) => {
+                EventId::parse(redacts)?;
+            },
+            None if rules.redacts_in_content => {
+                return Err(EventContentError::MissingField("redacts".to_string()));
+            },
+            None => {},
+        }
+        Ok(())
+    }
+}
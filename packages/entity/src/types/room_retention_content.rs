@@ -0,0 +1,46 @@
+use crate::types::{EventContentError, RoomVersionRules, TypedEventContent};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// RoomRetentionContent - Content of `m.room.retention`
+/// Source: MSC1763 (Proposal for specifying configurable message retention periods)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RoomRetentionContent {
+    /// Milliseconds events must be kept for at minimum
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_lifetime: Option<u64>,
+
+    /// Milliseconds after which events may be purged
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_lifetime: Option<u64>,
+
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
+}
+
+impl RoomRetentionContent {
+    pub const EVENT_TYPE: &'static str = "m.room.retention";
+
+    pub fn new(min_lifetime: Option<u64>, max_lifetime: Option<u64>) -> Self {
+        Self {
+            min_lifetime,
+            max_lifetime,
+            unknown_fields: BTreeMap::new(),
+        }
+    }
+}
+
+impl TypedEventContent for RoomRetentionContent {
+    fn validate(&self, _rules: &RoomVersionRules) -> Result<(), EventContentError> {
+        if let (Some(min), Some(max)) = (self.min_lifetime, self.max_lifetime) {
+            if min > max {
+                return Err(EventContentError::invalid_field(
+                    "min_lifetime",
+                    "must not be greater than max_lifetime",
+                ));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+use crate::types::TypedEventContent;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// RoomServerAclContent - Content of `m.room.server_acl`
+/// Source: spec/client/05_advanced_md (Server Access Control Lists)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RoomServerAclContent {
+    /// Server name globs that may participate; an empty list denies everyone
+    #[serde(default)]
+    pub allow: Vec<String>,
+
+    /// Server name globs that may not participate, checked before `allow`
+    #[serde(default)]
+    pub deny: Vec<String>,
+
+    /// Whether servers identified by an IP literal may participate
+    #[serde(default = "default_allow_ip_literals")]
+    pub allow_ip_literals: bool,
+
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
+}
+
+fn default_allow_ip_literals() -> bool {
+    true
+}
+
+impl RoomServerAclContent {
+    pub const EVENT_TYPE: &'static str = "m.room.server_acl";
+
+    pub fn new(allow: Vec<String>, deny: Vec<String>, allow_ip_literals: bool) -> Self {
+        Self {
+            allow,
+            deny,
+            allow_ip_literals,
+            unknown_fields: BTreeMap::new(),
+        }
+    }
+}
+
+impl TypedEventContent for RoomServerAclContent {}
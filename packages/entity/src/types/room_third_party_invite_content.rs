@@ -0,0 +1,44 @@
+use crate::types::TypedEventContent;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// RoomThirdPartyInviteContent - Content of `m.room.third_party_invite`
+/// Source: spec/client/08_instant_messaging_md (Third-party invites)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomThirdPartyInviteContent {
+    pub display_name: String,
+    pub key_validity_url: String,
+    pub public_key: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_keys: Option<Vec<ThirdPartyPublicKey>>,
+
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
+}
+
+/// Public key advertised by an identity server for a third-party invite
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThirdPartyPublicKey {
+    pub public_key: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_validity_url: Option<String>,
+}
+
+impl RoomThirdPartyInviteContent {
+    pub const EVENT_TYPE: &'static str = "m.room.third_party_invite";
+
+    pub fn new(display_name: String, key_validity_url: String, public_key: String) -> Self {
+        Self {
+            display_name,
+            key_validity_url,
+            public_key,
+            public_keys: None,
+            unknown_fields: BTreeMap::new(),
+        }
+    }
+}
+
+impl TypedEventContent for RoomThirdPartyInviteContent {}
@@ -0,0 +1,34 @@
+use crate::types::{EventContentError, RoomId, RoomVersionRules, TypedEventContent};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// RoomTombstoneContent - Content of `m.room.tombstone`
+/// Source: spec/client/05_advanced_md (Room Upgrades)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomTombstoneContent {
+    pub body: String,
+    pub replacement_room: String,
+
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
+}
+
+impl RoomTombstoneContent {
+    pub const EVENT_TYPE: &'static str = "m.room.tombstone";
+
+    pub fn new(body: String, replacement_room: String) -> Self {
+        Self {
+            body,
+            replacement_room,
+            unknown_fields: BTreeMap::new(),
+        }
+    }
+}
+
+impl TypedEventContent for RoomTombstoneContent {
+    fn validate(&self, _rules: &RoomVersionRules) -> Result<(), EventContentError> {
+        RoomId::parse(&self.replacement_room)?;
+        Ok(())
+    }
+}
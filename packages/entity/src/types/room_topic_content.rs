@@ -0,0 +1,24 @@
+use crate::types::TypedEventContent;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// RoomTopicContent - Content of `m.room.topic`
+/// Source: spec/client/08_instant_messaging_md (m.room.topic)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomTopicContent {
+    pub topic: String,
+
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
+}
+
+impl RoomTopicContent {
+    pub const EVENT_TYPE: &'static str = "m.room.topic";
+
+    pub fn new(topic: String) -> Self {
+        Self { topic, unknown_fields: BTreeMap::new() }
+    }
+}
+
+impl TypedEventContent for RoomTopicContent {}
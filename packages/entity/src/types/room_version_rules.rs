@@ -0,0 +1,72 @@
+/// RoomVersionRules - Event format differences between room versions
+///
+/// Only the rules that affect how event content is parsed and validated are
+/// tracked here; state resolution and event ID formats live in the server.
+/// Source: spec/rooms/*.md
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoomVersionRules {
+    /// `m.room.redaction` carries `redacts` inside `content` (v11+)
+    pub redacts_in_content: bool,
+
+    /// `m.room.create` requires a `creator` field (v1-v10)
+    pub create_requires_creator: bool,
+
+    /// `m.room.power_levels` values must be JSON integers (v10+)
+    pub integer_power_levels: bool,
+
+    /// The `knock` join rule and membership are allowed (v7+)
+    pub knocking: bool,
+
+    /// The `restricted` join rule is allowed (v8+)
+    pub restricted_join_rule: bool,
+
+    /// The `knock_restricted` join rule is allowed (v10+)
+    pub knock_restricted_join_rule: bool,
+}
+
+impl RoomVersionRules {
+    /// Rules for a stable room version, or `None` for unknown versions
+    pub fn for_version(room_version: &str) -> Option<Self> {
+        let version: u8 = match room_version {
+            "1" => 1,
+            "2" => 2,
+            "3" => 3,
+            "4" => 4,
+            "5" => 5,
+            "6" => 6,
+            "7" => 7,
+            "8" => 8,
+            "9" => 9,
+            "10" => 10,
+            "11" => 11,
+            _ => return None,
+        };
+
+        Some(Self {
+            redacts_in_content: version >= 11,
+            create_requires_creator: version < 11,
+            integer_power_levels: version >= 10,
+            knocking: version >= 7,
+            restricted_join_rule: version >= 8,
+            knock_restricted_join_rule: version >= 10,
+        })
+    }
+
+    /// Rules for the newest stable room version
+    pub fn latest() -> Self {
+        Self {
+            redacts_in_content: true,
+            create_requires_creator: false,
+            integer_power_levels: true,
+            knocking: true,
+            restricted_join_rule: true,
+            knock_restricted_join_rule: true,
+        }
+    }
+}
+
+impl Default for RoomVersionRules {
+    fn default() -> Self {
+        Self::latest()
+    }
+}
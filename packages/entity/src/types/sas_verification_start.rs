@@ -1,5 +1,7 @@
-use crate::types::VerificationRelatesTo;
+use crate::types::{TypedEventContent, VerificationRelatesTo};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
 
 /// Parameters for creating a SAS verification start
 #[derive(Debug, Clone)]
@@ -21,14 +23,21 @@ pub struct SASVerificationStart {
     pub from_device: String,
     pub hashes: Vec<String>,
     pub key_agreement_protocols: Vec<String>,
+    #[serde(rename = "m.relates_to", skip_serializing_if = "Option::is_none")]
     pub m_relates_to: Option<VerificationRelatesTo>,
     pub message_authentication_codes: Vec<String>,
     pub method: String,
     pub short_authentication_string: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub transaction_id: Option<String>,
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
 }
 
 impl SASVerificationStart {
+    pub const EVENT_TYPE: &'static str = "m.key.verification.start";
+    pub const METHOD: &'static str = "m.sas.v1";
+
     pub fn new(params: SasVerificationParams) -> Self {
         Self {
             from_device: params.from_device,
@@ -39,6 +48,9 @@ impl SASVerificationStart {
             method: params.method,
             short_authentication_string: params.short_authentication_string,
             transaction_id: params.transaction_id,
+            unknown_fields: BTreeMap::new(),
         }
     }
 }
+
+impl TypedEventContent for SASVerificationStart {}
@@ -0,0 +1,52 @@
+use crate::types::identifier::{matrix_identifier, split_host_port, validate_server_name};
+
+matrix_identifier! {
+    /// ServerName - validated Matrix server name, `hostname [ ":" port ]`
+    /// Source: Matrix Appendices - Server Name
+    owned: ServerName,
+    /// Borrowed, non-allocating form of [`ServerName`]
+    borrowed: ServerNameRef,
+    validate: validate_server_name,
+}
+
+impl ServerName {
+    /// The hostname part (IPv6 literals keep their brackets)
+    pub fn host(&self) -> &str {
+        self.as_borrowed().host()
+    }
+
+    /// The explicit port, if one was given
+    pub fn port(&self) -> Option<u16> {
+        self.as_borrowed().port()
+    }
+
+    /// Whether the hostname is an IPv4 or IPv6 literal
+    pub fn is_ip_literal(&self) -> bool {
+        self.as_borrowed().is_ip_literal()
+    }
+}
+
+impl<'a> ServerNameRef<'a> {
+    /// Wrap a server name that was already validated as part of a larger identifier
+    pub(crate) fn new_unchecked(server_name: &'a str) -> Self {
+        Self(server_name)
+    }
+
+    /// The hostname part (IPv6 literals keep their brackets)
+    pub fn host(&self) -> &'a str {
+        split_host_port(self.0).map(|(host, _)| host).unwrap_or(self.0)
+    }
+
+    /// The explicit port, if one was given
+    pub fn port(&self) -> Option<u16> {
+        split_host_port(self.0)
+            .and_then(|(_, port)| port)
+            .and_then(|port| port.parse().ok())
+    }
+
+    /// Whether the hostname is an IPv4 or IPv6 literal
+    pub fn is_ip_literal(&self) -> bool {
+        let host = self.host();
+        host.starts_with('[') || host.parse::<std::net::Ipv4Addr>().is_ok()
+    }
+}
@@ -1,16 +1,46 @@
+use crate::types::{EventContentError, RoomVersionRules, TypedEventContent};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
 
 /// SpaceChildEvent
 /// Source: spec/client/07_relationship_md:102-106
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SpaceChildEvent {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub order: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub suggested: Option<bool>,
+    #[serde(default)]
     pub via: Vec<String>,
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
 }
 
 impl SpaceChildEvent {
+    pub const EVENT_TYPE: &'static str = "m.space.child";
+
     pub fn new(order: Option<String>, suggested: Option<bool>, via: Vec<String>) -> Self {
-        Self { order, suggested, via }
+        Self {
+            order,
+            suggested,
+            via,
+            unknown_fields: BTreeMap::new(),
+        }
+    }
+}
+
+impl TypedEventContent for SpaceChildEvent {
+    fn validate(&self, _rules: &RoomVersionRules) -> Result<(), EventContentError> {
+        // Order strings are limited to 50 characters in the printable ASCII range
+        if let Some(order) = &self.order {
+            if order.len() > 50 || !order.chars().all(|c| ('\x20'..='\x7E').contains(&c)) {
+                return Err(EventContentError::invalid_field(
+                    "order",
+                    "must be at most 50 printable ASCII characters",
+                ));
+            }
+        }
+        Ok(())
     }
 }
@@ -1,15 +1,26 @@
+use crate::types::TypedEventContent;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
 
 /// SpaceParentEvent
 /// Source: spec/client/07_relationship_md:182-185
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SpaceParentEvent {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub canonical: Option<bool>,
+    #[serde(default)]
     pub via: Vec<String>,
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
 }
 
 impl SpaceParentEvent {
+    pub const EVENT_TYPE: &'static str = "m.space.parent";
+
     pub fn new(canonical: Option<bool>, via: Vec<String>) -> Self {
-        Self { canonical, via }
+        Self { canonical, via, unknown_fields: BTreeMap::new() }
     }
 }
+
+impl TypedEventContent for SpaceParentEvent {}
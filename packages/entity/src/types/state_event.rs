@@ -0,0 +1,66 @@
+use crate::types::EventEnvelope;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// StateEvent - A state event with typed content
+///
+/// `room_id` is absent for events delivered inside a room's `/sync` section
+/// and `event_id` is absent for room version 3+ PDUs. Fields that are not
+/// part of the client-visible envelope (hashes, signatures, prev_events, ...)
+/// are preserved in `unknown_fields`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateEvent<C> {
+    #[serde(rename = "type")]
+    pub event_type: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_id: Option<String>,
+
+    pub sender: String,
+
+    pub origin_server_ts: i64,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub room_id: Option<String>,
+
+    pub state_key: String,
+
+    pub content: C,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unsigned: Option<Value>,
+
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
+}
+
+impl<C> EventEnvelope for StateEvent<C> {
+    fn event_type(&self) -> &str {
+        &self.event_type
+    }
+
+    fn event_id(&self) -> Option<&str> {
+        self.event_id.as_deref()
+    }
+
+    fn sender(&self) -> &str {
+        &self.sender
+    }
+
+    fn origin_server_ts(&self) -> i64 {
+        self.origin_server_ts
+    }
+
+    fn room_id(&self) -> Option<&str> {
+        self.room_id.as_deref()
+    }
+
+    fn state_key(&self) -> Option<&str> {
+        Some(&self.state_key)
+    }
+
+    fn unsigned(&self) -> Option<&Value> {
+        self.unsigned.as_ref()
+    }
+}
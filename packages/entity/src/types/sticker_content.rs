@@ -1,4 +1,7 @@
+use crate::types::{EventContentError, MxcUri, Relation, RoomVersionRules, TypedEventContent};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
 
 /// Content for m.sticker events
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,6 +14,14 @@ pub struct StickerContent {
 
     /// The URL to the sticker image. This must be a valid mxc:// URI
     pub url: String,
+
+    /// Relation to another event (e.g. a reply or thread)
+    #[serde(rename = "m.relates_to", skip_serializing_if = "Option::is_none")]
+    pub relates_to: Option<Relation>,
+
+    /// Fields not modelled above, preserved for round-tripping
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
 }
 
 /// Image information for sticker content
@@ -52,9 +63,17 @@ pub struct ThumbnailInfo {
 }
 
 impl StickerContent {
+    pub const EVENT_TYPE: &'static str = "m.sticker";
+
     /// Create a new sticker content
     pub fn new(body: String, url: String, info: StickerImageInfo) -> Self {
-        Self { body, url, info }
+        Self {
+            body,
+            url,
+            info,
+            relates_to: None,
+            unknown_fields: BTreeMap::new(),
+        }
     }
 
     /// Validate sticker content according to Matrix specification
@@ -79,3 +98,10 @@ impl StickerContent {
         Ok(())
     }
 }
+
+impl TypedEventContent for StickerContent {
+    fn validate(&self, _rules: &RoomVersionRules) -> Result<(), EventContentError> {
+        MxcUri::parse(&self.url)?;
+        Ok(())
+    }
+}
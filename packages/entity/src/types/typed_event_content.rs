@@ -0,0 +1,14 @@
+use crate::types::{EventContentError, RoomVersionRules};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// TypedEventContent - Common behaviour of strongly typed event content
+///
+/// Implemented by every content struct that `AnyStateEvent` and
+/// `AnyMessageLikeEvent` can hold. Serde handles the shape of the JSON;
+/// `validate` enforces the remaining spec rules that serde cannot express.
+pub trait TypedEventContent: Serialize + DeserializeOwned {
+    /// Check spec constraints on the content for the given room version
+    fn validate(&self, _rules: &RoomVersionRules) -> Result<(), EventContentError> {
+        Ok(())
+    }
+}
@@ -0,0 +1,82 @@
+use crate::types::identifier::{matrix_identifier, split_sigil_id};
+use crate::types::{IdentifierError, ServerName, ServerNameRef};
+
+matrix_identifier! {
+    /// UserId - validated Matrix user ID, `@localpart:server_name`
+    ///
+    /// Parsing accepts historical user IDs whose localparts use any printable
+    /// ASCII except `:`, as servers must still interoperate with them. New IDs
+    /// created with [`UserId::new`] must use the current grammar.
+    /// Source: Matrix Appendices - User Identifiers
+    owned: UserId,
+    /// Borrowed, non-allocating form of [`UserId`]
+    borrowed: UserIdRef,
+    validate: validate_user_id,
+}
+
+/// Validate a user ID, allowing historical localparts
+pub fn validate_user_id(user_id: &str) -> Result<(), IdentifierError> {
+    let (localpart, _) = split_sigil_id(user_id, '@')?;
+
+    match localpart.chars().find(|c| !is_historical_localpart_char(*c)) {
+        Some(c) => Err(IdentifierError::InvalidLocalpart(c)),
+        None => Ok(()),
+    }
+}
+
+/// Whether a localpart conforms to the current grammar: `[a-z0-9._=\-/+]+`
+pub fn is_valid_user_localpart(localpart: &str) -> bool {
+    !localpart.is_empty() && localpart.chars().all(is_localpart_char)
+}
+
+fn is_localpart_char(c: char) -> bool {
+    matches!(c, 'a'..='z' | '0'..='9' | '.' | '_' | '=' | '-' | '/' | '+')
+}
+
+fn is_historical_localpart_char(c: char) -> bool {
+    matches!(c, '\u{21}'..='\u{39}' | '\u{3B}'..='\u{7E}')
+}
+
+impl UserId {
+    /// Build a new user ID, enforcing the current (non-historical) localpart grammar
+    pub fn new(localpart: &str, server_name: &ServerName) -> Result<Self, IdentifierError> {
+        if let Some(c) = localpart.chars().find(|c| !is_localpart_char(*c)) {
+            return Err(IdentifierError::InvalidLocalpart(c));
+        }
+        Self::parse(format!("@{}:{}", localpart, server_name))
+    }
+
+    /// The localpart, without the `@` sigil
+    pub fn localpart(&self) -> &str {
+        self.as_borrowed().localpart()
+    }
+
+    /// The server name the user belongs to
+    pub fn server_name(&self) -> ServerNameRef<'_> {
+        self.as_borrowed().server_name()
+    }
+
+    /// Whether the localpart only conforms to the historical grammar
+    pub fn is_historical(&self) -> bool {
+        self.as_borrowed().is_historical()
+    }
+}
+
+impl<'a> UserIdRef<'a> {
+    /// The localpart, without the `@` sigil
+    pub fn localpart(&self) -> &'a str {
+        self.0[1..].split_once(':').map(|(localpart, _)| localpart).unwrap_or_default()
+    }
+
+    /// The server name the user belongs to
+    pub fn server_name(&self) -> ServerNameRef<'a> {
+        ServerNameRef::new_unchecked(
+            self.0.split_once(':').map(|(_, server)| server).unwrap_or_default(),
+        )
+    }
+
+    /// Whether the localpart only conforms to the historical grammar
+    pub fn is_historical(&self) -> bool {
+        !is_valid_user_localpart(self.localpart())
+    }
+}
@@ -1,5 +1,7 @@
-use crate::types::VerificationRelatesTo;
+use crate::types::{TypedEventContent, VerificationRelatesTo};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
 
 /// VerificationAccept
 /// Source: spec/client/04_security_md:1188-1196
@@ -8,13 +10,19 @@ pub struct VerificationAccept {
     pub commitment: String,
     pub hash: String,
     pub key_agreement_protocol: String,
+    #[serde(rename = "m.relates_to", skip_serializing_if = "Option::is_none")]
     pub m_relates_to: Option<VerificationRelatesTo>,
     pub message_authentication_code: String,
     pub short_authentication_string: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub transaction_id: Option<String>,
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
 }
 
 impl VerificationAccept {
+    pub const EVENT_TYPE: &'static str = "m.key.verification.accept";
+
     pub fn new(
         commitment: String,
         hash: String,
@@ -32,6 +40,9 @@ impl VerificationAccept {
             message_authentication_code,
             short_authentication_string,
             transaction_id,
+            unknown_fields: BTreeMap::new(),
         }
     }
 }
+
+impl TypedEventContent for VerificationAccept {}
@@ -1,23 +1,39 @@
-use crate::types::VerificationRelatesTo;
+use crate::types::{TypedEventContent, VerificationRelatesTo};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
 
 /// VerificationCancel
 /// Source: spec/client/04_security_md:878-882
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VerificationCancel {
     pub code: String,
+    #[serde(rename = "m.relates_to", skip_serializing_if = "Option::is_none")]
     pub m_relates_to: Option<VerificationRelatesTo>,
     pub reason: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub transaction_id: Option<String>,
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
 }
 
 impl VerificationCancel {
+    pub const EVENT_TYPE: &'static str = "m.key.verification.cancel";
+
     pub fn new(
         code: String,
         m_relates_to: Option<VerificationRelatesTo>,
         reason: String,
         transaction_id: Option<String>,
     ) -> Self {
-        Self { code, m_relates_to, reason, transaction_id }
+        Self {
+            code,
+            m_relates_to,
+            reason,
+            transaction_id,
+            unknown_fields: BTreeMap::new(),
+        }
     }
 }
+
+impl TypedEventContent for VerificationCancel {}
@@ -1,19 +1,33 @@
-use crate::types::VerificationRelatesTo;
+use crate::types::{TypedEventContent, VerificationRelatesTo};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
 
 /// VerificationDone
 /// Source: spec/client/04_security_md:861-863
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VerificationDone {
+    #[serde(rename = "m.relates_to", skip_serializing_if = "Option::is_none")]
     pub m_relates_to: Option<VerificationRelatesTo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub transaction_id: Option<String>,
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
 }
 
 impl VerificationDone {
+    pub const EVENT_TYPE: &'static str = "m.key.verification.done";
+
     pub fn new(
         m_relates_to: Option<VerificationRelatesTo>,
         transaction_id: Option<String>,
     ) -> Self {
-        Self { m_relates_to, transaction_id }
+        Self {
+            m_relates_to,
+            transaction_id,
+            unknown_fields: BTreeMap::new(),
+        }
     }
 }
+
+impl TypedEventContent for VerificationDone {}
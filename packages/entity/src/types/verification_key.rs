@@ -1,21 +1,36 @@
-use crate::types::VerificationRelatesTo;
+use crate::types::{TypedEventContent, VerificationRelatesTo};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
 
 /// VerificationKey
 /// Source: spec/client/04_security_md:1223-1226
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VerificationKey {
     pub key: String,
+    #[serde(rename = "m.relates_to", skip_serializing_if = "Option::is_none")]
     pub m_relates_to: Option<VerificationRelatesTo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub transaction_id: Option<String>,
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
 }
 
 impl VerificationKey {
+    pub const EVENT_TYPE: &'static str = "m.key.verification.key";
+
     pub fn new(
         key: String,
         m_relates_to: Option<VerificationRelatesTo>,
         transaction_id: Option<String>,
     ) -> Self {
-        Self { key, m_relates_to, transaction_id }
+        Self {
+            key,
+            m_relates_to,
+            transaction_id,
+            unknown_fields: BTreeMap::new(),
+        }
     }
 }
+
+impl TypedEventContent for VerificationKey {}
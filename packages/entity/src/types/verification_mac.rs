@@ -1,24 +1,39 @@
-use crate::types::VerificationRelatesTo;
+use crate::types::{TypedEventContent, VerificationRelatesTo};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
 
 /// VerificationMAC
 /// Source: spec/client/04_security_md:1245-1249
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VerificationMAC {
     pub keys: String,
+    #[serde(rename = "m.relates_to", skip_serializing_if = "Option::is_none")]
     pub m_relates_to: Option<VerificationRelatesTo>,
     pub mac: HashMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub transaction_id: Option<String>,
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
 }
 
 impl VerificationMAC {
+    pub const EVENT_TYPE: &'static str = "m.key.verification.mac";
+
     pub fn new(
         keys: String,
         m_relates_to: Option<VerificationRelatesTo>,
         mac: HashMap<String, String>,
         transaction_id: Option<String>,
     ) -> Self {
-        Self { keys, m_relates_to, mac, transaction_id }
+        Self {
+            keys,
+            m_relates_to,
+            mac,
+            transaction_id,
+            unknown_fields: BTreeMap::new(),
+        }
     }
 }
+
+impl TypedEventContent for VerificationMAC {}
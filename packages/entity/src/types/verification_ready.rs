@@ -1,23 +1,39 @@
-use crate::types::VerificationRelatesTo;
+use crate::types::{TypedEventContent, VerificationRelatesTo};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
 
 /// VerificationReady
 /// Source: spec/client/04_security_md:806-810
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VerificationReady {
     pub from_device: String,
+    #[serde(rename = "m.relates_to", skip_serializing_if = "Option::is_none")]
     pub m_relates_to: Option<VerificationRelatesTo>,
     pub methods: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub transaction_id: Option<String>,
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
 }
 
 impl VerificationReady {
+    pub const EVENT_TYPE: &'static str = "m.key.verification.ready";
+
     pub fn new(
         from_device: String,
         m_relates_to: Option<VerificationRelatesTo>,
         methods: Vec<String>,
         transaction_id: Option<String>,
     ) -> Self {
-        Self { from_device, m_relates_to, methods, transaction_id }
+        Self {
+            from_device,
+            m_relates_to,
+            methods,
+            transaction_id,
+            unknown_fields: BTreeMap::new(),
+        }
     }
 }
+
+impl TypedEventContent for VerificationReady {}
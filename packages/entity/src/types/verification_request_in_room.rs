@@ -1,19 +1,27 @@
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
 
 /// VerificationRequestInRoom
 /// Source: spec/client/04_security_md:763-770
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VerificationRequestInRoom {
     pub body: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub format: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub formatted_body: Option<String>,
     pub from_device: String,
     pub methods: Vec<String>,
     pub msgtype: String,
     pub to: String,
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
 }
 
 impl VerificationRequestInRoom {
+    pub const MSGTYPE: &'static str = "m.key.verification.request";
+
     pub fn new(
         body: String,
         format: Option<String>,
@@ -31,6 +39,7 @@ impl VerificationRequestInRoom {
             methods,
             msgtype,
             to,
+            unknown_fields: BTreeMap::new(),
         }
     }
 }
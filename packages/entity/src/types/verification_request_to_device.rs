@@ -11,6 +11,8 @@ pub struct VerificationRequestToDevice {
 }
 
 impl VerificationRequestToDevice {
+    pub const EVENT_TYPE: &'static str = "m.key.verification.request";
+
     pub fn new(
         from_device: String,
         methods: Vec<String>,
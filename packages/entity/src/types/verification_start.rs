@@ -1,18 +1,27 @@
-use crate::types::VerificationRelatesTo;
+use crate::types::{TypedEventContent, VerificationRelatesTo};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
 
 /// VerificationStart
 /// Source: spec/client/04_security_md:828-834
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VerificationStart {
     pub from_device: String,
+    #[serde(rename = "m.relates_to", skip_serializing_if = "Option::is_none")]
     pub m_relates_to: Option<VerificationRelatesTo>,
     pub method: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub next_method: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub transaction_id: Option<String>,
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, Value>,
 }
 
 impl VerificationStart {
+    pub const EVENT_TYPE: &'static str = "m.key.verification.start";
+
     pub fn new(
         from_device: String,
         m_relates_to: Option<VerificationRelatesTo>,
@@ -26,6 +35,9 @@ impl VerificationStart {
             method,
             next_method,
             transaction_id,
+            unknown_fields: BTreeMap::new(),
         }
     }
 }
+
+impl TypedEventContent for VerificationStart {}
@@ -1,3 +1,5 @@
 pub mod canonical_json;
+pub mod push_rules;
 
 pub use canonical_json::*;
+pub use push_rules::*;
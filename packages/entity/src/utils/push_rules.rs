@@ -0,0 +1,732 @@
+use crate::types::{
+    PushAction, PushActionObject, PushCondition, PushConditionValue, PushRule, Ruleset,
+};
+use serde_json::Value;
+
+/// What the evaluator knows about the user and room an event is evaluated for
+///
+/// The event itself is passed as its client format JSON, after decryption for
+/// encrypted events.
+#[derive(Debug, Clone, Default)]
+pub struct PushContext {
+    /// The user whose push rules are evaluated
+    pub user_id: String,
+    /// The user's display name in the room, for `contains_display_name`
+    pub display_name: Option<String>,
+    /// Joined members of the room, for `room_member_count`
+    pub member_count: u64,
+    /// Content of the room's `m.room.power_levels` event, if any
+    pub power_levels: Option<Value>,
+}
+
+/// Result of evaluating push rules against an event
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PushOutcome {
+    /// ID of the first matching rule, `None` if no rule matched
+    pub rule_id: Option<String>,
+    pub notify: bool,
+    pub highlight: bool,
+    /// Value of the `sound` tweak
+    pub sound: Option<String>,
+}
+
+impl PushOutcome {
+    /// Interpret the actions of a matched rule
+    ///
+    /// The deprecated `dont_notify` and `coalesce` actions are treated as no
+    /// action and `notify` respectively.
+    pub fn from_actions(rule_id: Option<String>, actions: &[PushAction]) -> Self {
+        let mut outcome = Self { rule_id, ..Self::default() };
+
+        for action in actions {
+            match action {
+                PushAction::String(action) => match action.as_str() {
+                    "notify" | "coalesce" => outcome.notify = true,
+                    _ => {},
+                },
+                PushAction::Object(PushActionObject { set_tweak: Some(tweak), value }) => {
+                    match tweak.as_str() {
+                        // A highlight tweak without value means true
+                        "highlight" => {
+                            outcome.highlight = value.as_ref().is_none_or(|v| v == &true)
+                        },
+                        "sound" => {
+                            outcome.sound =
+                                value.as_ref().and_then(Value::as_str).map(str::to_string)
+                        },
+                        _ => {},
+                    }
+                },
+                PushAction::Object(_) => {},
+            }
+        }
+
+        outcome
+    }
+}
+
+impl Ruleset {
+    /// The server-default push rules of the specification for `user_id`
+    pub fn server_default(user_id: &str) -> Self {
+        let localpart = user_id.trim_start_matches('@').split(':').next().unwrap_or_default();
+
+        let mut contains_user_name = default_rule(
+            ".m.rule.contains_user_name",
+            Vec::new(),
+            vec![notify(), sound("default"), highlight()],
+        );
+        contains_user_name.conditions = None;
+        contains_user_name.pattern = Some(localpart.to_string());
+
+        let mut master = default_rule(".m.rule.master", Vec::new(), Vec::new());
+        master.enabled = false;
+
+        Self {
+            override_rules: vec![
+                master,
+                default_rule(
+                    ".m.rule.suppress_notices",
+                    vec![event_match("content.msgtype", "m.notice")],
+                    Vec::new(),
+                ),
+                default_rule(
+                    ".m.rule.invite_for_me",
+                    vec![
+                        event_match("type", "m.room.member"),
+                        event_match("content.membership", "invite"),
+                        event_match("state_key", user_id),
+                    ],
+                    vec![notify(), sound("default")],
+                ),
+                default_rule(
+                    ".m.rule.member_event",
+                    vec![event_match("type", "m.room.member")],
+                    Vec::new(),
+                ),
+                default_rule(
+                    ".m.rule.is_user_mention",
+                    vec![event_property_contains(
+                        "content.m\\.mentions.user_ids",
+                        PushConditionValue::string(user_id),
+                    )],
+                    vec![notify(), sound("default"), highlight()],
+                ),
+                default_rule(
+                    ".m.rule.contains_display_name",
+                    vec![condition("contains_display_name")],
+                    vec![notify(), sound("default"), highlight()],
+                ),
+                default_rule(
+                    ".m.rule.is_room_mention",
+                    vec![
+                        event_property_is(
+                            "content.m\\.mentions.room",
+                            PushConditionValue::boolean(true),
+                        ),
+                        sender_notification_permission("room"),
+                    ],
+                    vec![notify(), highlight()],
+                ),
+                default_rule(
+                    ".m.rule.roomnotif",
+                    vec![
+                        sender_notification_permission("room"),
+                        event_match("content.body", "@room"),
+                    ],
+                    vec![notify(), highlight()],
+                ),
+                default_rule(
+                    ".m.rule.tombstone",
+                    vec![
+                        event_match("type", "m.room.tombstone"),
+                        event_match("state_key", ""),
+                    ],
+                    vec![notify(), highlight()],
+                ),
+                default_rule(
+                    ".m.rule.reaction",
+                    vec![event_match("type", "m.reaction")],
+                    Vec::new(),
+                ),
+                default_rule(
+                    ".m.rule.server_acl",
+                    vec![
+                        event_match("type", "m.room.server_acl"),
+                        event_match("state_key", ""),
+                    ],
+                    Vec::new(),
+                ),
+                default_rule(
+                    ".m.rule.suppress_edits",
+                    vec![event_property_is(
+                        "content.m\\.relates_to.rel_type",
+                        PushConditionValue::string("m.replace"),
+                    )],
+                    Vec::new(),
+                ),
+            ],
+            content: vec![contains_user_name],
+            room: Vec::new(),
+            sender: Vec::new(),
+            underride: vec![
+                default_rule(
+                    ".m.rule.call",
+                    vec![event_match("type", "m.call.invite")],
+                    vec![notify(), sound("ring")],
+                ),
+                default_rule(
+                    ".m.rule.encrypted_room_one_to_one",
+                    vec![
+                        room_member_count("2"),
+                        event_match("type", "m.room.encrypted"),
+                    ],
+                    vec![notify(), sound("default")],
+                ),
+                default_rule(
+                    ".m.rule.room_one_to_one",
+                    vec![
+                        room_member_count("2"),
+                        event_match("type", "m.room.message"),
+                    ],
+                    vec![notify(), sound("default")],
+                ),
+                default_rule(
+                    ".m.rule.message",
+                    vec![event_match("type", "m.room.message")],
+                    vec![notify()],
+                ),
+                default_rule(
+                    ".m.rule.encrypted",
+                    vec![event_match("type", "m.room.encrypted")],
+                    vec![notify()],
+                ),
+            ],
+        }
+    }
+
+    /// Add the server-default rules this ruleset lacks
+    ///
+    /// `.m.rule.master` goes first and the other defaults after the user's
+    /// rules of the same kind, as a server orders them.
+    pub fn with_server_defaults(mut self, user_id: &str) -> Self {
+        let defaults = Self::server_default(user_id);
+
+        let mut override_defaults = defaults.override_rules.into_iter();
+        if let Some(master) = override_defaults.next() {
+            if !has_rule(&self.override_rules, &master.rule_id) {
+                self.override_rules.insert(0, master);
+            }
+        }
+        merge_rules(&mut self.override_rules, override_defaults);
+        merge_rules(&mut self.content, defaults.content);
+        merge_rules(&mut self.underride, defaults.underride);
+
+        self
+    }
+
+    /// The first enabled rule matching `event`, in priority order
+    pub fn find_match(&self, event: &Value, context: &PushContext) -> Option<&PushRule> {
+        let room_id = event.get("room_id").and_then(Value::as_str);
+        let sender = event.get("sender").and_then(Value::as_str);
+
+        let override_rules = self.override_rules.iter().filter(|rule| {
+            conditions_match(rule.conditions.as_deref().unwrap_or_default(), event, context)
+        });
+        let content_rules = self.content.iter().filter(|rule| {
+            rule.pattern.as_deref().is_some_and(|pattern| {
+                string_at(event, "content.body")
+                    .is_some_and(|body| matches_at_word_boundary(body, pattern))
+            })
+        });
+        let room_rules = self.room.iter().filter(|rule| Some(rule.rule_id.as_str()) == room_id);
+        let sender_rules = self.sender.iter().filter(|rule| Some(rule.rule_id.as_str()) == sender);
+        let underride_rules = self.underride.iter().filter(|rule| {
+            conditions_match(rule.conditions.as_deref().unwrap_or_default(), event, context)
+        });
+
+        override_rules
+            .chain(content_rules)
+            .chain(room_rules)
+            .chain(sender_rules)
+            .chain(underride_rules)
+            .find(|rule| rule.enabled)
+    }
+
+    /// Evaluate the rules against `event`
+    ///
+    /// Events sent by the user themselves never notify.
+    pub fn evaluate(&self, event: &Value, context: &PushContext) -> PushOutcome {
+        if event.get("sender").and_then(Value::as_str) == Some(context.user_id.as_str()) {
+            return PushOutcome::default();
+        }
+
+        match self.find_match(event, context) {
+            Some(rule) => PushOutcome::from_actions(Some(rule.rule_id.clone()), &rule.actions),
+            None => PushOutcome::default(),
+        }
+    }
+}
+
+fn has_rule(rules: &[PushRule], rule_id: &str) -> bool {
+    rules.iter().any(|rule| rule.rule_id == rule_id)
+}
+
+fn merge_rules(rules: &mut Vec<PushRule>, defaults: impl IntoIterator<Item = PushRule>) {
+    for rule in defaults {
+        if !has_rule(rules, &rule.rule_id) {
+            rules.push(rule);
+        }
+    }
+}
+
+fn default_rule(
+    rule_id: &str,
+    conditions: Vec<PushCondition>,
+    actions: Vec<PushAction>,
+) -> PushRule {
+    let mut rule = PushRule::new(rule_id.to_string(), true, true, actions);
+    rule.conditions = Some(conditions);
+    rule
+}
+
+fn condition(kind: &str) -> PushCondition {
+    PushCondition::new(None, None, kind.to_string(), None, None)
+}
+
+fn event_match(key: &str, pattern: &str) -> PushCondition {
+    PushCondition {
+        key: Some(key.to_string()),
+        pattern: Some(pattern.to_string()),
+        ..condition("event_match")
+    }
+}
+
+fn event_property_is(key: &str, value: PushConditionValue) -> PushCondition {
+    PushCondition {
+        key: Some(key.to_string()),
+        value: Some(value),
+        ..condition("event_property_is")
+    }
+}
+
+fn event_property_contains(key: &str, value: PushConditionValue) -> PushCondition {
+    PushCondition {
+        key: Some(key.to_string()),
+        value: Some(value),
+        ..condition("event_property_contains")
+    }
+}
+
+fn room_member_count(is: &str) -> PushCondition {
+    PushCondition {
+        is: Some(is.to_string()),
+        ..condition("room_member_count")
+    }
+}
+
+fn sender_notification_permission(key: &str) -> PushCondition {
+    PushCondition {
+        key: Some(key.to_string()),
+        ..condition("sender_notification_permission")
+    }
+}
+
+fn notify() -> PushAction {
+    PushAction::notify()
+}
+
+fn sound(sound: &str) -> PushAction {
+    PushAction::object(PushActionObject::sound(sound.to_string()))
+}
+
+fn highlight() -> PushAction {
+    PushAction::object(PushActionObject::highlight())
+}
+
+/// Whether every condition holds; conditions of unknown kinds never do
+pub fn conditions_match(
+    conditions: &[PushCondition],
+    event: &Value,
+    context: &PushContext,
+) -> bool {
+    conditions
+        .iter()
+        .all(|condition| condition_matches(condition, event, context))
+}
+
+fn condition_matches(condition: &PushCondition, event: &Value, context: &PushContext) -> bool {
+    let key = condition.key.as_deref();
+
+    match condition.kind.as_str() {
+        "event_match" => match (key, condition.pattern.as_deref()) {
+            (Some("content.body"), Some(pattern)) => string_at(event, "content.body")
+                .is_some_and(|body| matches_at_word_boundary(body, pattern)),
+            (Some(key), Some(pattern)) => {
+                string_at(event, key).is_some_and(|value| glob_matches(pattern, value))
+            },
+            _ => false,
+        },
+        "event_property_is" => match (key.and_then(|key| value_at(event, key)), &condition.value) {
+            (Some(value), Some(expected)) => expected.matches(value),
+            _ => false,
+        },
+        "event_property_contains" => {
+            match (key.and_then(|key| value_at(event, key)), &condition.value) {
+                (Some(Value::Array(values)), Some(expected)) => {
+                    values.iter().any(|value| expected.matches(value))
+                },
+                _ => false,
+            }
+        },
+        "contains_display_name" => match &context.display_name {
+            Some(name) if !name.is_empty() => string_at(event, "content.body")
+                .is_some_and(|body| contains_at_word_boundary(body, name)),
+            _ => false,
+        },
+        "room_member_count" => condition
+            .is
+            .as_deref()
+            .is_some_and(|is| member_count_matches(is, context.member_count)),
+        "sender_notification_permission" => match key {
+            Some(key) => event
+                .get("sender")
+                .and_then(Value::as_str)
+                .is_some_and(|sender| has_notification_permission(context, sender, key)),
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+/// Split a dotted key into path segments, honouring `\.` and `\\` escapes
+pub fn split_key(key: &str) -> Vec<String> {
+    let mut segments = vec![String::new()];
+    let mut chars = key.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(next @ ('.' | '\\')) => push_char(&mut segments, next),
+                Some(next) => {
+                    push_char(&mut segments, '\\');
+                    push_char(&mut segments, next);
+                },
+                None => push_char(&mut segments, '\\'),
+            },
+            '.' => segments.push(String::new()),
+            c => push_char(&mut segments, c),
+        }
+    }
+
+    segments
+}
+
+fn push_char(segments: &mut [String], c: char) {
+    if let Some(segment) = segments.last_mut() {
+        segment.push(c);
+    }
+}
+
+fn value_at<'a>(event: &'a Value, key: &str) -> Option<&'a Value> {
+    split_key(key).iter().try_fold(event, |value, segment| value.get(segment))
+}
+
+fn string_at<'a>(event: &'a Value, key: &str) -> Option<&'a str> {
+    value_at(event, key)?.as_str()
+}
+
+fn member_count_matches(is: &str, member_count: u64) -> bool {
+    let (operator, count) = match is.find(|c: char| c.is_ascii_digit()) {
+        Some(index) => is.split_at(index),
+        None => return false,
+    };
+    let Ok(count) = count.parse::<u64>() else {
+        return false;
+    };
+
+    match operator {
+        "" | "==" => member_count == count,
+        "<" => member_count < count,
+        ">" => member_count > count,
+        "<=" => member_count <= count,
+        ">=" => member_count >= count,
+        _ => false,
+    }
+}
+
+/// Whether `sender`'s power level reaches `notifications.<key>` (50 by default)
+fn has_notification_permission(context: &PushContext, sender: &str, key: &str) -> bool {
+    let power_levels = context.power_levels.as_ref();
+    let level = |value: Option<&Value>| value.and_then(Value::as_i64);
+
+    let required =
+        level(power_levels.and_then(|levels| levels.get("notifications")?.get(key))).unwrap_or(50);
+    let sender_level = level(power_levels.and_then(|levels| levels.get("users")?.get(sender)))
+        .or_else(|| level(power_levels.and_then(|levels| levels.get("users_default"))))
+        .unwrap_or(0);
+
+    sender_level >= required
+}
+
+/// Case-insensitive glob match of the whole value, with `*` and `?`
+pub fn glob_matches(pattern: &str, value: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let value: Vec<char> = value.to_lowercase().chars().collect();
+    glob_matches_chars(&pattern, &value)
+}
+
+/// Case-insensitive glob match of any run of whole words in `value`
+///
+/// This is how `content.body` is matched, so `@room` matches "hi @room!" but
+/// `foo` does not match "foobar".
+pub fn matches_at_word_boundary(value: &str, pattern: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    any_word_run(value, |run| glob_matches_chars(&pattern, run))
+}
+
+/// Case-insensitive literal match of any run of whole words in `value`
+fn contains_at_word_boundary(value: &str, needle: &str) -> bool {
+    let needle: Vec<char> = needle.to_lowercase().chars().collect();
+    any_word_run(value, |run| run == needle.as_slice())
+}
+
+/// Whether `matches` holds for a lowercased run of `value` that starts and
+/// ends at word boundaries
+fn any_word_run(value: &str, matches: impl Fn(&[char]) -> bool) -> bool {
+    let value: Vec<char> = value.to_lowercase().chars().collect();
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+
+    (0..=value.len()).any(|start| {
+        (start == 0 || !is_word(value[start - 1]))
+            && (start..=value.len()).any(|end| {
+                (end == value.len() || !is_word(value[end])) && matches(&value[start..end])
+            })
+    })
+}
+
+fn glob_matches_chars(pattern: &[char], value: &[char]) -> bool {
+    let (mut p, mut v) = (0, 0);
+    // Position after the last `*` and the value position it is matched up to
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while v < value.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p + 1, v));
+                p += 1;
+            },
+            Some('?') => {
+                p += 1;
+                v += 1;
+            },
+            Some(c) if *c == value[v] => {
+                p += 1;
+                v += 1;
+            },
+            _ => match backtrack {
+                Some((star_p, star_v)) => {
+                    backtrack = Some((star_p, star_v + 1));
+                    p = star_p;
+                    v = star_v + 1;
+                },
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const ALICE: &str = "@alice:example.org";
+
+    fn context() -> PushContext {
+        PushContext {
+            user_id: ALICE.to_string(),
+            display_name: Some("Alice Liddell".to_string()),
+            member_count: 5,
+            power_levels: Some(json!({ "users": { "@mod:example.org": 50 } })),
+        }
+    }
+
+    fn message(sender: &str, content: Value) -> Value {
+        json!({
+            "event_id": "$event",
+            "type": "m.room.message",
+            "sender": sender,
+            "room_id": "!room:example.org",
+            "origin_server_ts": 0,
+            "content": content,
+        })
+    }
+
+    fn rule_id(ruleset: &Ruleset, event: &Value, context: &PushContext) -> Option<String> {
+        ruleset.evaluate(event, context).rule_id
+    }
+
+    #[test]
+    fn test_default_rules() {
+        let rules = Ruleset::server_default(ALICE);
+        let context = context();
+        let text =
+            |body: &str| message("@bob:example.org", json!({ "msgtype": "m.text", "body": body }));
+
+        let outcome = rules.evaluate(&text("hello"), &context);
+        assert_eq!(outcome.rule_id.as_deref(), Some(".m.rule.message"));
+        assert!(outcome.notify && !outcome.highlight);
+
+        let outcome = rules.evaluate(&text("hey ALICE, look"), &context);
+        assert_eq!(outcome.rule_id.as_deref(), Some(".m.rule.contains_user_name"));
+        assert!(outcome.highlight);
+        assert_eq!(outcome.sound.as_deref(), Some("default"));
+        assert_eq!(rule_id(&rules, &text("alicent"), &context).as_deref(), Some(".m.rule.message"));
+
+        assert_eq!(
+            rule_id(&rules, &text("ask alice liddell!"), &context).as_deref(),
+            Some(".m.rule.contains_display_name")
+        );
+
+        let mention = message(
+            "@bob:example.org",
+            json!({ "body": "hi", "m.mentions": { "user_ids": [ALICE] } }),
+        );
+        assert_eq!(rule_id(&rules, &mention, &context).as_deref(), Some(".m.rule.is_user_mention"));
+
+        let notice = message("@bob:example.org", json!({ "msgtype": "m.notice", "body": "alice" }));
+        let outcome = rules.evaluate(&notice, &context);
+        assert_eq!(outcome.rule_id.as_deref(), Some(".m.rule.suppress_notices"));
+        assert!(!outcome.notify);
+
+        // Own events never notify
+        let own = PushContext { user_id: "@bob:example.org".to_string(), ..context };
+        assert_eq!(rules.evaluate(&text("hi"), &own), PushOutcome::default());
+    }
+
+    #[test]
+    fn test_room_mention_needs_permission() {
+        let rules = Ruleset::server_default(ALICE);
+        let context = context();
+        let content = json!({ "body": "@room lunch", "m.mentions": { "room": true } });
+
+        let outcome = rules.evaluate(&message("@mod:example.org", content.clone()), &context);
+        assert_eq!(outcome.rule_id.as_deref(), Some(".m.rule.is_room_mention"));
+        assert!(outcome.highlight);
+
+        let outcome = rules.evaluate(&message("@bob:example.org", content), &context);
+        assert_eq!(outcome.rule_id.as_deref(), Some(".m.rule.message"));
+    }
+
+    #[test]
+    fn test_one_to_one_and_encrypted() {
+        let rules = Ruleset::server_default(ALICE);
+        let mut context = context();
+        context.member_count = 2;
+
+        let mut event = message("@bob:example.org", json!({ "algorithm": "m.megolm.v1.aes-sha2" }));
+        event["type"] = json!("m.room.encrypted");
+        assert_eq!(
+            rule_id(&rules, &event, &context).as_deref(),
+            Some(".m.rule.encrypted_room_one_to_one")
+        );
+
+        context.member_count = 3;
+        assert_eq!(rule_id(&rules, &event, &context).as_deref(), Some(".m.rule.encrypted"));
+    }
+
+    #[test]
+    fn test_user_rules_and_defaults() -> Result<(), serde_json::Error> {
+        let ruleset: Ruleset = serde_json::from_value(json!({
+            "override": [],
+            "content": [],
+            "room": [{
+                "rule_id": "!room:example.org",
+                "default": false,
+                "enabled": true,
+                "actions": [],
+            }],
+            "sender": [],
+            "underride": [],
+        }))?;
+        let rules = ruleset.with_server_defaults(ALICE);
+        assert_eq!(rules.override_rules[0].rule_id, ".m.rule.master");
+
+        // The muted room wins over the underride rules, not over mentions
+        let text = message("@bob:example.org", json!({ "msgtype": "m.text", "body": "hi" }));
+        let outcome = rules.evaluate(&text, &context());
+        assert_eq!(outcome.rule_id.as_deref(), Some("!room:example.org"));
+        assert!(!outcome.notify);
+
+        let mention = message("@bob:example.org", json!({ "body": "hi alice" }));
+        assert!(rules.evaluate(&mention, &context()).highlight);
+        Ok(())
+    }
+
+    #[test]
+    fn test_actions() -> Result<(), serde_json::Error> {
+        let actions: Vec<PushAction> = serde_json::from_value(json!([
+            "notify",
+            { "set_tweak": "sound", "value": "bing" },
+            { "set_tweak": "highlight" },
+        ]))?;
+        let outcome = PushOutcome::from_actions(None, &actions);
+        assert!(outcome.notify && outcome.highlight);
+        assert_eq!(outcome.sound.as_deref(), Some("bing"));
+
+        let actions: Vec<PushAction> = serde_json::from_value(
+            json!(["dont_notify", { "set_tweak": "highlight", "value": false }]),
+        )?;
+        assert_eq!(PushOutcome::from_actions(None, &actions), PushOutcome::default());
+        Ok(())
+    }
+
+    #[test]
+    fn test_conditions() {
+        let event = json!({
+            "type": "m.room.message",
+            "content": { "m.relates_to": { "rel_type": "m.replace" }, "tags": ["a", 1, null] },
+        });
+
+        assert_eq!(
+            split_key("content.m\\.relates_to.rel_type"),
+            vec!["content", "m.relates_to", "rel_type"]
+        );
+        assert!(glob_matches("m.room.*", "M.Room.Message"));
+        assert!(glob_matches("m.?oom.message", "m.room.message"));
+        assert!(!glob_matches("m.room", "m.room.message"));
+        assert!(matches_at_word_boundary("ping @room!", "@room"));
+        assert!(matches_at_word_boundary("a cat sat", "c*t"));
+        assert!(!matches_at_word_boundary("concat", "cat"));
+
+        let contains = |value| {
+            let condition = event_property_contains("content.tags", value);
+            conditions_match(&[condition], &event, &PushContext::default())
+        };
+        assert!(contains(PushConditionValue::string("a")));
+        assert!(contains(PushConditionValue::number(1.0)));
+        assert!(contains(PushConditionValue::Null));
+        assert!(!contains(PushConditionValue::boolean(true)));
+
+        for (is, matches) in [
+            ("2", false),
+            ("==5", true),
+            (">4", true),
+            ("<=4", false),
+            (">=5", true),
+        ] {
+            let context = PushContext { member_count: 5, ..PushContext::default() };
+            assert_eq!(
+                conditions_match(&[room_member_count(is)], &event, &context),
+                matches,
+                "{}",
+                is
+            );
+        }
+
+        let unknown = condition("org.example.unknown");
+        assert!(!conditions_match(&[unknown], &event, &PushContext::default()));
+    }
+}
@@ -2,6 +2,7 @@ pub mod admin;
 pub mod auth_metadata;
 pub mod login;
 pub mod media;
+pub mod registration_token;
 pub mod room_summary;
 pub mod rooms;
 pub mod user;
@@ -0,0 +1,40 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::state::AppState;
+use matryx_surrealdb::repository::RegistrationRepository;
+
+#[derive(Deserialize)]
+pub struct ValidityQuery {
+    pub token: String,
+}
+
+#[derive(Serialize)]
+pub struct ValidityResponse {
+    pub valid: bool,
+}
+
+/// GET /_matrix/client/v1/register/m.login.registration_token/validity
+///
+/// Lets a client check a registration token before starting registration.
+/// The check takes no pending use of the token.
+pub async fn get(
+    State(state): State<AppState>,
+    Query(query): Query<ValidityQuery>,
+) -> Result<Json<ValidityResponse>, StatusCode> {
+    let registration_repo = RegistrationRepository::new(state.db.clone());
+    let valid = registration_repo
+        .validate_registration_token(&query.token)
+        .await
+        .map_err(|e| {
+            error!("Failed to check registration token validity: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(ValidityResponse { valid }))
+}
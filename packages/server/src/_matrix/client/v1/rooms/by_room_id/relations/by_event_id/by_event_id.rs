@@ -97,8 +97,16 @@ pub async fn get(
         query.to.as_deref(),
         query.dir.as_deref(),
     ).await {
-        Ok(relations_response) => {
+        Ok(mut relations_response) => {
             info!("Successfully retrieved event relations for event {} in room {}", event_id, room_id);
+            relations_response.chunk = state
+                .event_visibility
+                .filter_events_for_client(&user_id, relations_response.chunk)
+                .await
+                .map_err(|e| {
+                    error!("Failed to apply event visibility: {}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?;
             Ok(Json(relations_response))
         },
         Err(e) => {
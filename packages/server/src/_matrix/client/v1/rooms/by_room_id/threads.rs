@@ -11,6 +11,7 @@ use crate::{
     auth::{MatrixAuth, extract_matrix_auth},
 };
 use matryx_entity::types::ThreadSummary;
+use matryx_surrealdb::repository::EventRepository;
 use matryx_surrealdb::repository::threads::ThreadInclude;
 
 #[derive(Deserialize)]
@@ -94,10 +95,34 @@ pub async fn get(
                 room_id
             );
 
+            // Only list threads whose root the user may see
+            let event_repo = EventRepository::new(state.db.clone());
+            let mut threads = Vec::new();
+            let mut roots = Vec::new();
+            for thread_root in thread_roots_response.threads {
+                let root = event_repo.get_by_id(&thread_root.event_id).await.map_err(|e| {
+                    error!("Failed to get thread root {}: {}", thread_root.event_id, e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?;
+                if let Some(root) = root {
+                    roots.push(root);
+                    threads.push(thread_root);
+                }
+            }
+            let visible =
+                state.event_visibility.visible_to_client(&user_id, &roots).await.map_err(|e| {
+                    error!("Failed to apply event visibility: {}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?;
+
             // Convert ThreadRootsResponse to ThreadsResponse (Matrix spec compliant)
             let mut chunk: Vec<matryx_entity::types::ThreadSummary> = Vec::new();
 
-            for thread_root in thread_roots_response.threads {
+            let visible_threads = threads
+                .into_iter()
+                .zip(visible)
+                .filter_map(|(thread, visible)| visible.then_some(thread));
+            for thread_root in visible_threads {
                 // Convert from repository ThreadSummary to entity ThreadSummary per Matrix spec
                 let repo_summary = thread_root.unsigned.thread;
                 let thread_root_id = thread_root.event_id.clone();
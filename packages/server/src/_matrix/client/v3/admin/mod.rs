@@ -1,2 +1,30 @@
+use axum::http::StatusCode;
+use tracing::{error, warn};
+
+use crate::auth::AuthenticatedUser;
+use crate::state::AppState;
+
 pub mod health;
+pub mod policy_lists;
+pub mod registration_tokens;
+pub mod reports;
+pub mod rooms;
+pub mod users;
 pub mod whois;
+
+/// Reject callers that are not server admins
+pub(crate) async fn require_admin(
+    state: &AppState,
+    auth_user: &AuthenticatedUser,
+) -> Result<(), StatusCode> {
+    let is_admin = auth_user.is_admin(state).await.map_err(|e| {
+        error!("Failed to check admin status for user {}: {}", auth_user.user_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if !is_admin {
+        warn!("User {} attempted an admin operation without admin privileges", auth_user.user_id);
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Ok(())
+}
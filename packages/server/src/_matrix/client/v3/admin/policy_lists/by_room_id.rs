@@ -0,0 +1,57 @@
+use axum::{extract::State, http::StatusCode, response::Json};
+use serde_json::{Value, json};
+use tracing::{error, info, warn};
+
+use super::require_admin;
+use crate::auth::AuthenticatedUser;
+use crate::state::AppState;
+use crate::utils::matrix_path::MatrixPath;
+use matryx_entity::types::RoomId;
+use matryx_surrealdb::repository::PolicyListSubscription;
+
+/// PUT /_matrix/client/v3/admin/policy_lists/{roomId}
+///
+/// Subscribe the server to a policy list room. The room's `m.policy.rule.*`
+/// state must reach this server, so a local user should be joined to it.
+pub async fn put(
+    State(state): State<AppState>,
+    auth_user: AuthenticatedUser,
+    MatrixPath(room_id): MatrixPath<RoomId>,
+) -> Result<Json<PolicyListSubscription>, StatusCode> {
+    require_admin(&state, &auth_user).await?;
+
+    let subscription =
+        state
+            .policy_lists
+            .subscribe(&room_id, &auth_user.user_id)
+            .await
+            .map_err(|e| {
+                error!("Failed to subscribe to policy list {}: {}", room_id, e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+
+    info!("Admin {} subscribed the server to policy list {}", auth_user.user_id, room_id);
+    Ok(Json(subscription))
+}
+
+/// DELETE /_matrix/client/v3/admin/policy_lists/{roomId}
+pub async fn delete(
+    State(state): State<AppState>,
+    auth_user: AuthenticatedUser,
+    MatrixPath(room_id): MatrixPath<RoomId>,
+) -> Result<Json<Value>, StatusCode> {
+    require_admin(&state, &auth_user).await?;
+
+    let removed = state.policy_lists.unsubscribe(&room_id).await.map_err(|e| {
+        error!("Failed to unsubscribe from policy list {}: {}", room_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if !removed {
+        warn!("Policy list {} is not subscribed", room_id);
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    info!("Admin {} unsubscribed the server from policy list {}", auth_user.user_id, room_id);
+    Ok(Json(json!({})))
+}
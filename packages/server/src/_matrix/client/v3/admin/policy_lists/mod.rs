@@ -0,0 +1,35 @@
+use axum::{extract::State, http::StatusCode, response::Json};
+use serde::Serialize;
+use tracing::error;
+
+use super::require_admin;
+use crate::auth::AuthenticatedUser;
+use crate::state::AppState;
+use matryx_surrealdb::repository::PolicyListSubscription;
+
+pub mod by_room_id;
+
+#[derive(Serialize)]
+pub struct PolicyListsResponse {
+    pub subscriptions: Vec<PolicyListSubscription>,
+    /// Number of ban rules currently enforced across all lists
+    pub active_bans: usize,
+}
+
+/// GET /_matrix/client/v3/admin/policy_lists
+pub async fn get(
+    State(state): State<AppState>,
+    auth_user: AuthenticatedUser,
+) -> Result<Json<PolicyListsResponse>, StatusCode> {
+    require_admin(&state, &auth_user).await?;
+
+    let subscriptions = state.policy_lists.subscriptions().await.map_err(|e| {
+        error!("Failed to list policy list subscriptions: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(PolicyListsResponse {
+        subscriptions,
+        active_bans: state.policy_lists.rules().len(),
+    }))
+}
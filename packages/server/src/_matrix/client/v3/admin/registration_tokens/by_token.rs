@@ -0,0 +1,111 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde::{Deserialize, Deserializer};
+use serde_json::{Value, json};
+use tracing::{error, info, warn};
+
+use super::{RegistrationTokenResponse, parse_expiry_time, validate_uses_allowed};
+use crate::_matrix::client::v3::admin::require_admin;
+use crate::auth::AuthenticatedUser;
+use crate::state::AppState;
+use matryx_surrealdb::repository::RegistrationRepository;
+
+#[derive(Deserialize)]
+pub struct UpdateTokenRequest {
+    /// A new use limit, or `null` to lift it; unchanged when absent
+    #[serde(default, deserialize_with = "present")]
+    pub uses_allowed: Option<Option<i32>>,
+    /// A new expiry time in milliseconds, or `null` to never expire;
+    /// unchanged when absent
+    #[serde(default, deserialize_with = "present")]
+    pub expiry_time: Option<Option<i64>>,
+}
+
+/// Tells a field set to `null` apart from one left out
+fn present<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// GET /_matrix/client/v3/admin/registration_tokens/{token}
+pub async fn get(
+    State(state): State<AppState>,
+    auth_user: AuthenticatedUser,
+    Path(token): Path<String>,
+) -> Result<Json<RegistrationTokenResponse>, StatusCode> {
+    require_admin(&state, &auth_user).await?;
+
+    let registration_repo = RegistrationRepository::new(state.db.clone());
+    let registration_token =
+        registration_repo.get_registration_token(&token).await.map_err(|e| {
+            error!("Failed to get registration token: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    registration_token
+        .map(|token| Json(token.into()))
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// PUT /_matrix/client/v3/admin/registration_tokens/{token}
+///
+/// Change how many registrations a token allows and when it expires.
+pub async fn put(
+    State(state): State<AppState>,
+    auth_user: AuthenticatedUser,
+    Path(token): Path<String>,
+    Json(request): Json<UpdateTokenRequest>,
+) -> Result<Json<RegistrationTokenResponse>, StatusCode> {
+    require_admin(&state, &auth_user).await?;
+
+    validate_uses_allowed(request.uses_allowed.flatten())?;
+    let expires_at = match request.expiry_time {
+        Some(expiry_time) => Some(parse_expiry_time(expiry_time)?),
+        None => None,
+    };
+
+    let registration_repo = RegistrationRepository::new(state.db.clone());
+    let updated = registration_repo
+        .update_registration_token(&token, request.uses_allowed, expires_at)
+        .await
+        .map_err(|e| {
+            error!("Failed to update registration token: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or_else(|| {
+            warn!("Cannot update unknown registration token");
+            StatusCode::NOT_FOUND
+        })?;
+
+    info!("Admin {} updated a registration token", auth_user.user_id);
+    Ok(Json(updated.into()))
+}
+
+/// DELETE /_matrix/client/v3/admin/registration_tokens/{token}
+pub async fn delete(
+    State(state): State<AppState>,
+    auth_user: AuthenticatedUser,
+    Path(token): Path<String>,
+) -> Result<Json<Value>, StatusCode> {
+    require_admin(&state, &auth_user).await?;
+
+    let registration_repo = RegistrationRepository::new(state.db.clone());
+    let deleted = registration_repo.delete_registration_token(&token).await.map_err(|e| {
+        error!("Failed to delete registration token: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if !deleted {
+        warn!("Cannot delete unknown registration token");
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    info!("Admin {} deleted a registration token", auth_user.user_id);
+    Ok(Json(json!({})))
+}
@@ -0,0 +1,192 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::{DateTime, Utc};
+use rand::{Rng, rng};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+use super::require_admin;
+use crate::auth::AuthenticatedUser;
+use crate::state::AppState;
+use matryx_surrealdb::repository::{
+    RegistrationRepository, RegistrationToken, error::RepositoryError,
+};
+
+pub mod by_token;
+
+const TOKEN_CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789._~-";
+const DEFAULT_TOKEN_LENGTH: usize = 16;
+const MAX_TOKEN_LENGTH: usize = 64;
+
+#[derive(Deserialize)]
+pub struct TokensQuery {
+    /// Only tokens that can still be used, or only those that cannot
+    pub valid: Option<bool>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateTokenRequest {
+    /// The token to create; a random one of `length` characters when absent
+    pub token: Option<String>,
+    pub length: Option<usize>,
+    /// How many registrations the token allows; unlimited when absent
+    pub uses_allowed: Option<i32>,
+    /// When the token stops working, in milliseconds since the epoch
+    pub expiry_time: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct RegistrationTokenResponse {
+    pub token: String,
+    pub uses_allowed: Option<i32>,
+    pub pending: i32,
+    pub completed: i32,
+    pub expiry_time: Option<i64>,
+}
+
+impl From<RegistrationToken> for RegistrationTokenResponse {
+    fn from(token: RegistrationToken) -> Self {
+        Self {
+            token: token.token,
+            uses_allowed: token.uses_allowed,
+            pending: token.pending,
+            completed: token.completed,
+            expiry_time: token.expires_at.map(|expires_at| expires_at.timestamp_millis()),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct TokensResponse {
+    pub registration_tokens: Vec<RegistrationTokenResponse>,
+}
+
+/// GET /_matrix/client/v3/admin/registration_tokens
+pub async fn get(
+    State(state): State<AppState>,
+    auth_user: AuthenticatedUser,
+    Query(query): Query<TokensQuery>,
+) -> Result<Json<TokensResponse>, StatusCode> {
+    require_admin(&state, &auth_user).await?;
+
+    let registration_repo = RegistrationRepository::new(state.db.clone());
+    let tokens = registration_repo.list_registration_tokens(query.valid).await.map_err(|e| {
+        error!("Failed to list registration tokens: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(TokensResponse {
+        registration_tokens: tokens.into_iter().map(Into::into).collect(),
+    }))
+}
+
+/// POST /_matrix/client/v3/admin/registration_tokens
+///
+/// Create a registration token, generating a random one when none is given.
+pub async fn post(
+    State(state): State<AppState>,
+    auth_user: AuthenticatedUser,
+    Json(request): Json<CreateTokenRequest>,
+) -> Result<Json<RegistrationTokenResponse>, StatusCode> {
+    require_admin(&state, &auth_user).await?;
+
+    let token = match request.token {
+        Some(token) => {
+            if !is_valid_token(&token) {
+                warn!("Rejected registration token with invalid characters or length");
+                return Err(StatusCode::BAD_REQUEST);
+            }
+            token
+        },
+        None => {
+            let length = request.length.unwrap_or(DEFAULT_TOKEN_LENGTH);
+            if !(1..=MAX_TOKEN_LENGTH).contains(&length) {
+                warn!("Rejected registration token length {}", length);
+                return Err(StatusCode::BAD_REQUEST);
+            }
+            generate_token(length)
+        },
+    };
+    validate_uses_allowed(request.uses_allowed)?;
+    let expires_at = parse_expiry_time(request.expiry_time)?;
+
+    let registration_repo = RegistrationRepository::new(state.db.clone());
+    let created = registration_repo
+        .create_registration_token(&token, request.uses_allowed, expires_at, &auth_user.user_id)
+        .await
+        .map_err(|e| match e {
+            RepositoryError::Conflict { message } => {
+                warn!("{}", message);
+                StatusCode::CONFLICT
+            },
+            e => {
+                error!("Failed to create registration token: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            },
+        })?;
+
+    info!("Admin {} created a registration token", auth_user.user_id);
+    Ok(Json(created.into()))
+}
+
+/// Tokens may only use the URL-safe characters `[A-Za-z0-9._~-]`
+fn is_valid_token(token: &str) -> bool {
+    !token.is_empty() &&
+        token.len() <= MAX_TOKEN_LENGTH &&
+        token.bytes().all(|b| TOKEN_CHARS.contains(&b))
+}
+
+fn generate_token(length: usize) -> String {
+    let mut rng = rng();
+    (0..length)
+        .map(|_| TOKEN_CHARS[rng.random_range(0..TOKEN_CHARS.len())] as char)
+        .collect()
+}
+
+fn validate_uses_allowed(uses_allowed: Option<i32>) -> Result<(), StatusCode> {
+    if uses_allowed.is_some_and(|uses_allowed| uses_allowed < 0) {
+        warn!("Rejected negative registration token use limit");
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    Ok(())
+}
+
+/// An expiry time in milliseconds, which must lie in the future
+fn parse_expiry_time(expiry_time: Option<i64>) -> Result<Option<DateTime<Utc>>, StatusCode> {
+    let Some(expiry_time) = expiry_time else {
+        return Ok(None);
+    };
+
+    match DateTime::from_timestamp_millis(expiry_time) {
+        Some(expires_at) if expires_at > Utc::now() => Ok(Some(expires_at)),
+        _ => {
+            warn!("Rejected registration token expiry time {}", expiry_time);
+            Err(StatusCode::BAD_REQUEST)
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_tokens_are_valid() {
+        let token = generate_token(DEFAULT_TOKEN_LENGTH);
+        assert_eq!(token.len(), DEFAULT_TOKEN_LENGTH);
+        assert!(is_valid_token(&token));
+        assert!(is_valid_token(&generate_token(MAX_TOKEN_LENGTH)));
+    }
+
+    #[test]
+    fn test_tokens_are_limited_to_url_safe_characters() {
+        assert!(is_valid_token("abc-DEF_123.~"));
+        assert!(!is_valid_token(""));
+        assert!(!is_valid_token("has space"));
+        assert!(!is_valid_token("slash/"));
+        assert!(!is_valid_token(&"a".repeat(MAX_TOKEN_LENGTH + 1)));
+    }
+}
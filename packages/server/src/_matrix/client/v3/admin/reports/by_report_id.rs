@@ -0,0 +1,75 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde::Deserialize;
+use tracing::{error, info, warn};
+
+use crate::_matrix::client::v3::admin::require_admin;
+use crate::auth::AuthenticatedUser;
+use crate::state::AppState;
+use matryx_surrealdb::repository::{
+    ModerationReport, ModerationReportStatus, error::RepositoryError,
+};
+
+#[derive(Deserialize)]
+pub struct UpdateReportRequest {
+    /// `acknowledged` or `resolved`
+    pub status: String,
+    /// What was done about the report, such as a redaction or a ban
+    pub action_taken: Option<String>,
+}
+
+/// GET /_matrix/client/v3/admin/reports/{reportId}
+pub async fn get(
+    State(state): State<AppState>,
+    auth_user: AuthenticatedUser,
+    Path(report_id): Path<String>,
+) -> Result<Json<ModerationReport>, StatusCode> {
+    require_admin(&state, &auth_user).await?;
+
+    let report = state.reports.get(&report_id).await.map_err(|e| {
+        error!("Failed to get report {}: {}", report_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    report.map(Json).ok_or(StatusCode::NOT_FOUND)
+}
+
+/// PUT /_matrix/client/v3/admin/reports/{reportId}
+///
+/// Acknowledge or resolve a report. Reports only move forward, from open to
+/// acknowledged to resolved.
+pub async fn put(
+    State(state): State<AppState>,
+    auth_user: AuthenticatedUser,
+    Path(report_id): Path<String>,
+    Json(request): Json<UpdateReportRequest>,
+) -> Result<Json<ModerationReport>, StatusCode> {
+    require_admin(&state, &auth_user).await?;
+
+    let status = ModerationReportStatus::parse(&request.status).ok_or_else(|| {
+        warn!("Unknown report status '{}'", request.status);
+        StatusCode::BAD_REQUEST
+    })?;
+
+    let report = state
+        .reports
+        .transition(&report_id, status, request.action_taken.as_deref(), &auth_user.user_id)
+        .await
+        .map_err(|e| match e {
+            RepositoryError::NotFound { .. } => StatusCode::NOT_FOUND,
+            RepositoryError::Validation { message, .. } => {
+                warn!("Rejected update of report {}: {}", report_id, message);
+                StatusCode::BAD_REQUEST
+            },
+            e => {
+                error!("Failed to update report {}: {}", report_id, e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            },
+        })?;
+
+    info!("Admin {} moved report {} to {}", auth_user.user_id, report_id, status.as_str());
+    Ok(Json(report))
+}
@@ -0,0 +1,54 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{error, warn};
+
+use super::require_admin;
+use crate::auth::AuthenticatedUser;
+use crate::state::AppState;
+use matryx_surrealdb::repository::{ModerationReport, ModerationReportStatus};
+
+pub mod by_report_id;
+
+const DEFAULT_LIMIT: u32 = 100;
+const MAX_LIMIT: u32 = 1000;
+
+#[derive(Deserialize)]
+pub struct ReportsQuery {
+    /// `open`, `acknowledged` or `resolved`; all reports when absent
+    pub status: Option<String>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Serialize)]
+pub struct ReportsResponse {
+    pub reports: Vec<ModerationReport>,
+}
+
+/// GET /_matrix/client/v3/admin/reports
+pub async fn get(
+    State(state): State<AppState>,
+    auth_user: AuthenticatedUser,
+    Query(query): Query<ReportsQuery>,
+) -> Result<Json<ReportsResponse>, StatusCode> {
+    require_admin(&state, &auth_user).await?;
+
+    let status = match query.status.as_deref() {
+        Some(status) => Some(ModerationReportStatus::parse(status).ok_or_else(|| {
+            warn!("Unknown report status '{}'", status);
+            StatusCode::BAD_REQUEST
+        })?),
+        None => None,
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+
+    let reports = state.reports.list(status, limit).await.map_err(|e| {
+        error!("Failed to list reports: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(ReportsResponse { reports }))
+}
@@ -0,0 +1,2 @@
+pub mod purge_history;
+pub mod shutdown;
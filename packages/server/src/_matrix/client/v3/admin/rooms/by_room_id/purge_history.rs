@@ -0,0 +1,68 @@
+use axum::{extract::State, http::StatusCode, response::Json};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+use crate::_matrix::client::v3::admin::require_admin;
+use crate::auth::AuthenticatedUser;
+use crate::state::AppState;
+use crate::utils::matrix_path::MatrixPath;
+use matryx_entity::types::RoomId;
+
+/// Where to stop purging; exactly one must be given
+#[derive(Deserialize)]
+pub struct PurgeHistoryRequest {
+    /// Purge events sent before this timestamp, in milliseconds
+    pub purge_up_to_ts: Option<i64>,
+    /// Purge events sent before this event, keeping the event itself
+    pub purge_up_to_event_id: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct PurgeHistoryResponse {
+    pub events_deleted: usize,
+    pub media_deleted: usize,
+}
+
+/// POST /_matrix/client/v3/admin/rooms/{roomId}/purge_history
+///
+/// Delete a room's messages up to a point, regardless of its retention
+/// policy. State events and the room's latest event are kept.
+pub async fn post(
+    State(state): State<AppState>,
+    auth_user: AuthenticatedUser,
+    MatrixPath(room_id): MatrixPath<RoomId>,
+    Json(request): Json<PurgeHistoryRequest>,
+) -> Result<Json<PurgeHistoryResponse>, StatusCode> {
+    require_admin(&state, &auth_user).await?;
+
+    let purged = match (request.purge_up_to_ts, request.purge_up_to_event_id.as_deref()) {
+        (Some(before_ts), None) => state.retention.purge_history(&room_id, before_ts).await,
+        (None, Some(event_id)) => {
+            match state.retention.purge_history_before_event(&room_id, event_id).await {
+                Ok(Some(purged)) => Ok(purged),
+                Ok(None) => {
+                    warn!("Cannot purge room {} up to unknown event {}", room_id, event_id);
+                    return Err(StatusCode::NOT_FOUND);
+                },
+                Err(e) => Err(e),
+            }
+        },
+        _ => {
+            warn!("Purge request for room {} must give exactly one end point", room_id);
+            return Err(StatusCode::BAD_REQUEST);
+        },
+    }
+    .map_err(|e| {
+        error!("Failed to purge history of room {}: {}", room_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    info!(
+        "Admin {} purged {} events from room {}",
+        auth_user.user_id, purged.events_deleted, room_id
+    );
+    Ok(Json(PurgeHistoryResponse {
+        events_deleted: purged.events_deleted,
+        media_deleted: purged.media_uris.len(),
+    }))
+}
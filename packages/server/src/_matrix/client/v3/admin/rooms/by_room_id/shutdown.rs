@@ -0,0 +1,95 @@
+use axum::{extract::State, http::StatusCode, response::Json};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tracing::{error, info, warn};
+
+use crate::_matrix::client::v3::admin::require_admin;
+use crate::auth::AuthenticatedUser;
+use crate::moderation::{NoticeRoom, ShutdownSummary};
+use crate::state::AppState;
+use crate::utils::matrix_path::MatrixPath;
+use matryx_entity::types::RoomId;
+
+const DEFAULT_ROOM_NAME: &str = "Content Violation Notification";
+const DEFAULT_MESSAGE: &str = "Sharing illegal content on this server is not permitted and rooms in violation will be blocked.";
+
+#[derive(Deserialize)]
+pub struct ShutdownRequest {
+    /// Local user who creates a room the evicted users are moved into
+    pub new_room_user_id: Option<String>,
+    pub room_name: Option<String>,
+    /// Notice sent in the new room
+    pub message: Option<String>,
+    /// Reason given on the evicted users' leave events
+    pub reason: Option<String>,
+}
+
+/// POST /_matrix/client/v3/admin/rooms/{roomId}/shutdown
+///
+/// Block the room and remove every local user from it, optionally moving
+/// them into a new room with a notice.
+pub async fn post(
+    State(state): State<AppState>,
+    auth_user: AuthenticatedUser,
+    MatrixPath(room_id): MatrixPath<RoomId>,
+    Json(request): Json<ShutdownRequest>,
+) -> Result<Json<ShutdownSummary>, StatusCode> {
+    require_admin(&state, &auth_user).await?;
+
+    let notice_room = match request.new_room_user_id {
+        Some(creator) => {
+            if !creator.ends_with(&format!(":{}", state.homeserver_name)) {
+                warn!("Notice room creator {} is not a local user", creator);
+                return Err(StatusCode::BAD_REQUEST);
+            }
+            Some(NoticeRoom {
+                creator,
+                name: request.room_name.unwrap_or_else(|| DEFAULT_ROOM_NAME.to_string()),
+                message: request.message.unwrap_or_else(|| DEFAULT_MESSAGE.to_string()),
+            })
+        },
+        None => None,
+    };
+
+    let summary = state
+        .room_shutdown
+        .shutdown(
+            &state.room_operations,
+            &room_id,
+            &auth_user.user_id,
+            request.reason.as_deref(),
+            notice_room.as_ref(),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to shut down room {}: {}", room_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    info!("Admin {} shut down room {}", auth_user.user_id, room_id);
+    Ok(Json(summary))
+}
+
+/// DELETE /_matrix/client/v3/admin/rooms/{roomId}/shutdown
+///
+/// Unblock a shut down room. Evicted users and removed aliases stay gone.
+pub async fn delete(
+    State(state): State<AppState>,
+    auth_user: AuthenticatedUser,
+    MatrixPath(room_id): MatrixPath<RoomId>,
+) -> Result<Json<Value>, StatusCode> {
+    require_admin(&state, &auth_user).await?;
+
+    let unblocked = state.room_shutdown.unblock(&room_id).await.map_err(|e| {
+        error!("Failed to unblock room {}: {}", room_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if !unblocked {
+        warn!("Room {} is not shut down", room_id);
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    info!("Admin {} unblocked room {}", auth_user.user_id, room_id);
+    Ok(Json(json!({})))
+}
@@ -0,0 +1,117 @@
+use axum::{extract::State, http::StatusCode, response::Json};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tracing::{error, info, warn};
+
+use crate::_matrix::client::v3::admin::require_admin;
+use crate::auth::AuthenticatedUser;
+use crate::state::AppState;
+use crate::utils::matrix_path::MatrixPath;
+use matryx_entity::types::UserId;
+use matryx_surrealdb::repository::{UserRepository, UserRestriction, UserRestrictionKind};
+
+#[derive(Deserialize)]
+pub struct RestrictRequest {
+    pub reason: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct RestrictionsResponse {
+    pub restrictions: Vec<UserRestriction>,
+}
+
+/// GET /_matrix/client/v3/admin/users/{userId}/restrictions
+///
+/// List the shadow ban, suspension and lock applied to a user, with who
+/// applied each and why.
+pub async fn get(
+    State(state): State<AppState>,
+    auth_user: AuthenticatedUser,
+    MatrixPath(user_id): MatrixPath<UserId>,
+) -> Result<Json<RestrictionsResponse>, StatusCode> {
+    require_admin(&state, &auth_user).await?;
+
+    let restrictions = state.user_restrictions.list(&user_id).await.map_err(|e| {
+        error!("Failed to list restrictions for {}: {}", user_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(RestrictionsResponse { restrictions }))
+}
+
+/// PUT /_matrix/client/v3/admin/users/{userId}/restrictions/{kind}
+///
+/// Shadow ban (`shadow_ban`), suspend (`suspension`) or lock (`lock`) a
+/// local user.
+pub async fn put(
+    State(state): State<AppState>,
+    auth_user: AuthenticatedUser,
+    MatrixPath((user_id, kind)): MatrixPath<(UserId, String)>,
+    Json(request): Json<RestrictRequest>,
+) -> Result<Json<UserRestriction>, StatusCode> {
+    require_admin(&state, &auth_user).await?;
+    let kind = parse_kind(&kind)?;
+
+    if !user_id.ends_with(&format!(":{}", state.homeserver_name)) {
+        warn!("Cannot restrict remote user {}", user_id);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let user_repo = UserRepository::new(state.db.clone());
+    let user = user_repo.get_by_id(&user_id).await.map_err(|e| {
+        error!("Failed to look up user {}: {}", user_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if user.is_none() {
+        warn!("Cannot restrict unknown user {}", user_id);
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let restriction = state
+        .user_restrictions
+        .restrict(&user_id, kind, &auth_user.user_id, request.reason.as_deref())
+        .await
+        .map_err(|e| {
+            error!("Failed to apply {} to {}: {}", kind.as_str(), user_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    info!("Admin {} applied {} to {}", auth_user.user_id, kind.as_str(), user_id);
+    Ok(Json(restriction))
+}
+
+/// DELETE /_matrix/client/v3/admin/users/{userId}/restrictions/{kind}
+///
+/// Lift a restriction from a user.
+pub async fn delete(
+    State(state): State<AppState>,
+    auth_user: AuthenticatedUser,
+    MatrixPath((user_id, kind)): MatrixPath<(UserId, String)>,
+) -> Result<Json<Value>, StatusCode> {
+    require_admin(&state, &auth_user).await?;
+    let kind = parse_kind(&kind)?;
+
+    let lifted = state
+        .user_restrictions
+        .lift(&user_id, kind, &auth_user.user_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to lift {} from {}: {}", kind.as_str(), user_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if !lifted {
+        warn!("User {} has no {} to lift", user_id, kind.as_str());
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    info!("Admin {} lifted {} from {}", auth_user.user_id, kind.as_str(), user_id);
+    Ok(Json(json!({})))
+}
+
+fn parse_kind(kind: &str) -> Result<UserRestrictionKind, StatusCode> {
+    UserRestrictionKind::parse(kind).ok_or_else(|| {
+        warn!("Unknown user restriction kind '{}'", kind);
+        StatusCode::BAD_REQUEST
+    })
+}
@@ -14,9 +14,9 @@ use chrono::Utc;
 use crate::{
     AppState,
     auth::{MatrixAuth, extract_matrix_auth},
-    utils::matrix_identifiers::generate_room_id,
+    modules::{EventRuling, SpamCheck},
 };
-use matryx_entity::types::{SignedThirdPartyInvite, Event, EventContent};
+use matryx_entity::types::{SignedThirdPartyInvite, Event, EventContent, RoomId, ServerName};
 use matryx_surrealdb::repository::{
     EventRepository, MembershipRepository, PowerLevelsRepository, RoomAliasRepository, RoomManagementService,
     RoomRepository, error::RepositoryError, room::RoomCreationConfig,
@@ -207,8 +207,22 @@ pub async fn post(
         return Err(StatusCode::BAD_REQUEST);
     }
 
+    if let SpamCheck::Deny { reason } = state.modules.user_may_create_room(&user_id).await {
+        warn!("Room creation failed - rejected by spam checker: {}", reason);
+        return Err(StatusCode::FORBIDDEN);
+    }
+
     // Generate room ID for the new room
-    let room_id = generate_room_id();
+    let server_name = ServerName::parse(&state.homeserver_name).map_err(|e| {
+        error!("Room creation failed - invalid homeserver name: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let room_id = RoomId::new(&Uuid::new_v4().to_string(), &server_name)
+        .map_err(|e| {
+            error!("Room creation failed - could not generate room ID: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .into_string();
     info!("Generated room ID: {} for user: {}", room_id, user_id);
 
     // Create repository instances
@@ -225,6 +239,61 @@ pub async fn post(
     let visibility = request.visibility.clone().unwrap_or_else(|| "private".to_string());
     let is_public = visibility == "public";
 
+    if is_public
+        && let SpamCheck::Deny { reason } =
+            state.modules.user_may_publish_room(&user_id, &room_id).await
+    {
+        warn!("Room creation failed - publishing rejected by spam checker: {}", reason);
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    for invitee in request.invite.iter().flatten() {
+        if let SpamCheck::Deny { reason } =
+            state.modules.user_may_invite(&user_id, invitee, &room_id).await
+        {
+            warn!("Room creation failed - invite rejected by spam checker: {}", reason);
+            return Err(StatusCode::FORBIDDEN);
+        }
+    }
+
+    // Shadow-banned users get their room, but nobody is invited to it
+    let shadow_banned = state.user_restrictions.is_shadow_banned(&user_id).await?;
+    let (invite, invite_3pid) = if shadow_banned {
+        info!("Dropping invites to room {} from shadow-banned user {}", room_id, user_id);
+        (None, None)
+    } else {
+        (request.invite.clone(), request.invite_3pid.clone())
+    };
+
+    // Run the initial state through spam-checker and third-party-rules modules
+    let mut initial_state = Vec::new();
+    for state_event in request.initial_state.iter().flatten() {
+        let mut draft = serde_json::json!({
+            "type": state_event.event_type,
+            "room_id": room_id,
+            "sender": user_id,
+            "state_key": state_event.state_key.clone().unwrap_or_default(),
+            "content": state_event.content
+        });
+        if let SpamCheck::Deny { reason } = state.modules.check_event_for_spam(&draft).await {
+            warn!("Room creation failed - initial state rejected by spam checker: {}", reason);
+            return Err(StatusCode::FORBIDDEN);
+        }
+        let content = match state.modules.check_state_event_allowed(&draft).await {
+            EventRuling::Allow => draft["content"].take(),
+            EventRuling::Replace { content } => content,
+            EventRuling::Deny { reason } => {
+                warn!("Room creation failed - initial state rejected by module: {}", reason);
+                return Err(StatusCode::FORBIDDEN);
+            },
+        };
+        initial_state.push(serde_json::json!({
+            "type": state_event.event_type,
+            "state_key": state_event.state_key,
+            "content": content
+        }));
+    }
+
     // Create room configuration
     let room_config = RoomCreationConfig {
         name: request.name.clone(),
@@ -233,25 +302,10 @@ pub async fn post(
         is_public,
         is_direct: request.is_direct.unwrap_or(false),
         preset: request.preset.clone(),
-        invite_users: request.invite.clone().unwrap_or_default(),
-        initial_state: request
-            .initial_state
-            .as_ref()
-            .map(|states| {
-                states
-                    .iter()
-                    .map(|state| {
-                        serde_json::json!({
-                            "type": state.event_type,
-                            "state_key": state.state_key,
-                            "content": state.content
-                        })
-                    })
-                    .collect()
-            })
-            .unwrap_or_default(),
+        invite_users: invite.unwrap_or_default(),
+        initial_state,
         power_level_content_override: request.power_level_content_override.clone(),
-        invite_3pid: request.invite_3pid.clone().unwrap_or_default(),
+        invite_3pid: invite_3pid.clone().unwrap_or_default(),
         creation_content: request.creation_content.clone(),
     };
 
@@ -265,7 +319,7 @@ pub async fn post(
             );
 
             // Handle third party invites if provided
-            if let Some(ref invite_3pid) = request.invite_3pid {
+            if let Some(ref invite_3pid) = invite_3pid {
                 let event_repo = EventRepository::new(state.db.clone());
 
                 for invite in invite_3pid {
@@ -9,6 +9,7 @@ use tracing::error;
 use crate::{
     AppState,
     auth::{MatrixAuth, extract_matrix_auth},
+    modules::SpamCheck,
 };
 use matryx_surrealdb::repository::{PublicRoomsRepository, RoomDirectoryVisibility, PowerLevelsRepository};
 
@@ -95,6 +96,17 @@ pub async fn put(
         _ => return Err(StatusCode::BAD_REQUEST),
     };
 
+    if matches!(visibility, RoomDirectoryVisibility::Public)
+        && let SpamCheck::Deny { reason } =
+            state.modules.user_may_publish_room(&user_id, &room_id).await
+    {
+        tracing::warn!("User {} may not publish room {}: {}", user_id, room_id, reason);
+        return Ok(Json(json!({
+            "errcode": "M_FORBIDDEN",
+            "error": reason
+        })));
+    }
+
     // Use PublicRoomsRepository to set room directory visibility
     let public_rooms_repo = PublicRoomsRepository::new(state.db.clone());
 
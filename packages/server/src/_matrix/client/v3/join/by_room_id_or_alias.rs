@@ -14,6 +14,7 @@ use tracing::{error, info, warn};
 use crate::{
     AppState,
     auth::{MatrixAuth, extract_matrix_auth},
+    modules::SpamCheck,
     utils::canonical_json::to_canonical_json,
 };
 use matryx_entity::types::ThirdPartySigned;
@@ -78,6 +79,17 @@ pub async fn post(
         },
     };
 
+    // Users and rooms banned by a subscribed policy list cannot be joined
+    if state.policy_lists.is_user_banned(&user_id) ||
+        state.policy_lists.is_room_banned(&room_id_or_alias)
+    {
+        warn!(
+            "Room join failed - {} or {} is banned by a policy list",
+            user_id, room_id_or_alias
+        );
+        return Err(StatusCode::FORBIDDEN);
+    }
+
     // Handle optional join reason for audit logging
     if let Some(reason) = &request.reason {
         info!(
@@ -114,6 +126,35 @@ pub async fn post(
     let event_repo = EventRepository::new(state.db.clone());
     let user_repo = UserRepository::new(state.db.clone());
 
+    // Spam checkers see the room ID, so resolve an alias first
+    let room_id = if room_id_or_alias.starts_with('#') {
+        room_repo.resolve_room_alias(&room_id_or_alias).await.map_err(|e| {
+            error!("Failed to resolve room alias {}: {}", room_id_or_alias, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+    } else {
+        Some(room_id_or_alias.clone())
+    };
+    if let Some(room_id) = room_id {
+        // Rooms shut down by an admin cannot be joined
+        state.room_shutdown.check_not_blocked(&room_id).await?;
+
+        let is_invited = membership_repo
+            .get_user_membership_status(&room_id, &user_id)
+            .await
+            .map_err(|e| {
+                error!("Failed to check membership of {} in {}: {}", user_id, room_id, e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?
+            .is_some_and(|membership| membership == "invite");
+        if let SpamCheck::Deny { reason } =
+            state.modules.user_may_join_room(&user_id, &room_id, is_invited).await
+        {
+            warn!("Room join failed - rejected by spam checker: {}", reason);
+            return Err(StatusCode::FORBIDDEN);
+        }
+    }
+
     // Create room join service
     let join_service = RoomJoinService::new(room_repo, membership_repo, event_repo, user_repo);
 
@@ -124,6 +165,15 @@ pub async fn post(
                 "Successfully joined user {} to room {} with event {}",
                 user_id, result.room_id, result.event_id
             );
+
+            // Carry tags, push rules and m.direct over if this room replaced another
+            if let Err(e) = state.room_upgrades.migrate_user_data(&user_id, &result.room_id).await
+            {
+                warn!(
+                    "Failed to migrate data of {} into upgraded room {}: {}",
+                    user_id, result.room_id, e
+                );
+            }
             Ok(Json(JoinResponse { room_id: result.room_id }))
         },
         Err(e) => match e {
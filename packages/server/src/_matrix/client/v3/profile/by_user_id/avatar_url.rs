@@ -6,6 +6,7 @@ use axum::{
 use matryx_surrealdb::repository::ProfileManagementService;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use tracing::debug;
 use url::Url;
 
 use crate::AppState;
@@ -100,6 +101,13 @@ pub async fn set_avatar_url(
         validate_avatar_url(avatar_url).map_err(|_| StatusCode::BAD_REQUEST)?;
     }
 
+    // Profile changes from shadow-banned users appear to succeed but are not stored
+    let shadow_banned = state.user_restrictions.is_shadow_banned(&user_id).await?;
+    if shadow_banned {
+        debug!("Dropping avatar change from shadow-banned user {}", user_id);
+        return Ok(Json(serde_json::json!({})));
+    }
+
     let profile_service = ProfileManagementService::new(state.db.clone());
 
     // Update avatar URL using ProfileManagementService
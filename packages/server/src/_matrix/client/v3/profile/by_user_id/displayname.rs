@@ -1,17 +1,79 @@
-use axum::{Json, extract::Path, http::StatusCode};
-use serde_json::{Value, json};
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::Json,
+};
+use matryx_surrealdb::repository::ProfileManagementService;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::debug;
+
+use crate::AppState;
+
+#[derive(Serialize)]
+pub struct DisplayNameResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub displayname: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct SetDisplayNameRequest {
+    pub displayname: Option<String>,
+}
 
 /// GET /_matrix/client/v3/profile/{userId}/displayname
-pub async fn get(Path(_user_id): Path<String>) -> Result<Json<Value>, StatusCode> {
-    Ok(Json(json!({
-        "displayname": "Example User"
-    })))
+pub async fn get(
+    State(state): State<AppState>,
+    Path(user_id): Path<String>,
+) -> Result<Json<DisplayNameResponse>, StatusCode> {
+    let profile_service = ProfileManagementService::new(state.db.clone());
+
+    match profile_service.get_user_profile(&user_id, &user_id).await {
+        Ok(profile) => Ok(Json(DisplayNameResponse { displayname: profile.displayname })),
+        Err(_) => {
+            // If no profile exists, return null display name
+            Ok(Json(DisplayNameResponse { displayname: None }))
+        },
+    }
 }
 
 /// PUT /_matrix/client/v3/profile/{userId}/displayname
 pub async fn put(
-    Path(_user_id): Path<String>,
-    Json(_payload): Json<Value>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(user_id): Path<String>,
+    Json(request): Json<SetDisplayNameRequest>,
 ) -> Result<Json<Value>, StatusCode> {
-    Ok(Json(json!({})))
+    // Extract and validate access token
+    let access_token = headers
+        .get("authorization")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    // Validate token and get user context
+    let token_info = state
+        .session_service
+        .validate_access_token(access_token)
+        .await
+        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+
+    // Verify user authorization
+    if token_info.user_id != user_id {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    // Profile changes from shadow-banned users appear to succeed but are not stored
+    let shadow_banned = state.user_restrictions.is_shadow_banned(&user_id).await?;
+    if shadow_banned {
+        debug!("Dropping display name change from shadow-banned user {}", user_id);
+        return Ok(Json(serde_json::json!({})));
+    }
+
+    let profile_service = ProfileManagementService::new(state.db.clone());
+
+    match profile_service.update_display_name(&user_id, request.displayname).await {
+        Ok(()) => Ok(Json(serde_json::json!({}))),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
 }
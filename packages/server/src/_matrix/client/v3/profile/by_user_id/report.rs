@@ -1,10 +1,73 @@
-use axum::{Json, extract::Path, http::StatusCode};
-use serde_json::{Value, json};
+use axum::{extract::State, http::StatusCode, response::Json};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error, info, warn};
 
-/// POST /_matrix/client/v3/profile/{userId}/report
+use crate::auth::AuthenticatedUser;
+use crate::moderation::ReportSubmission;
+use crate::state::AppState;
+use crate::utils::matrix_path::MatrixPath;
+use matryx_entity::types::UserId;
+use matryx_surrealdb::repository::{ModerationReportKind, UserRepository};
+
+#[derive(Deserialize)]
+pub struct ReportUserRequest {
+    pub reason: Option<String>,
+    pub score: Option<i32>, // -100 to 0 (most offensive)
+}
+
+#[derive(Serialize)]
+pub struct ReportResponse {
+    // Empty response per Matrix spec
+}
+
+/// POST /_matrix/client/v3/users/{userId}/report
+///
+/// Reports of unknown local users succeed without being stored, so the
+/// endpoint cannot be used to find out which accounts exist.
 pub async fn post(
-    Path(_user_id): Path<String>,
-    Json(_payload): Json<Value>,
-) -> Result<Json<Value>, StatusCode> {
-    Ok(Json(json!({})))
+    State(state): State<AppState>,
+    auth_user: AuthenticatedUser,
+    MatrixPath(reported_user_id): MatrixPath<UserId>,
+    Json(request): Json<ReportUserRequest>,
+) -> Result<Json<ReportResponse>, StatusCode> {
+    if let Some(score) = request.score
+        && !(-100..=0).contains(&score)
+    {
+        warn!("User report failed - score {} out of range", score);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    if auth_user.user_id == *reported_user_id {
+        warn!("User report failed - {} reported themselves", auth_user.user_id);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    if reported_user_id.ends_with(&format!(":{}", state.homeserver_name)) {
+        let user_repo = UserRepository::new(state.db.clone());
+        let user = user_repo.get_by_id(&reported_user_id).await.map_err(|e| {
+            error!("Failed to look up reported user {}: {}", reported_user_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+        if user.is_none() {
+            debug!("Ignoring report of unknown user {}", reported_user_id);
+            return Ok(Json(ReportResponse {}));
+        }
+    }
+
+    let submission = ReportSubmission {
+        kind: ModerationReportKind::User,
+        room_id: None,
+        event_id: None,
+        reported_user_id: Some(reported_user_id.to_string()),
+        reporter_id: auth_user.user_id.clone(),
+        reason: request.reason,
+        score: request.score,
+    };
+    state.reports.submit(&state, submission).await.map_err(|e| {
+        error!("Failed to report user {}: {}", reported_user_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    info!("User {} reported by user {}", reported_user_id, auth_user.user_id);
+    Ok(Json(ReportResponse {}))
 }
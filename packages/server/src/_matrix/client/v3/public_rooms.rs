@@ -99,10 +99,14 @@ pub async fn get(
         },
     };
 
-    // Convert to response format
+    // Convert to response format, hiding rooms banned by a policy list
+    let policy_rules = state.policy_lists.rules();
     let chunk: Vec<PublicRoom> = public_rooms_response
         .chunk
         .into_iter()
+        .filter(|entry| {
+            !policy_rules.is_room_banned(&entry.room_id, entry.canonical_alias.as_deref())
+        })
         .map(|entry| PublicRoom {
             room_id: entry.room_id,
             name: entry.name,
@@ -225,10 +229,14 @@ pub async fn post(
         }
     };
 
-    // Convert to response format
+    // Convert to response format, hiding rooms banned by a policy list
+    let policy_rules = state.policy_lists.rules();
     let chunk: Vec<PublicRoom> = search_response
         .chunk
         .into_iter()
+        .filter(|entry| {
+            !policy_rules.is_room_banned(&entry.room_id, entry.canonical_alias.as_deref())
+        })
         .map(|entry| PublicRoom {
             room_id: entry.room_id,
             name: entry.name,
@@ -1,12 +1,17 @@
 use axum::Json;
 use axum::extract::State;
 use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Value, json};
+use std::collections::HashMap;
 use tracing::{error, info, warn};
 
 use crate::AppState;
-use matryx_surrealdb::repository::InfrastructureService;
+use crate::auth::consent::TERMS_STAGE;
+use crate::auth::uia::{REGISTRATION_TOKEN_AUTH_DATA, UiaAuth};
+use crate::modules::{RegistrationInfo, SpamCheck};
+use matryx_surrealdb::repository::{InfrastructureService, RegistrationRepository};
 
 /// Matrix Client-Server API Registration Request
 #[derive(Debug, Deserialize)]
@@ -101,7 +106,7 @@ pub async fn post_register(
     State(state): State<AppState>,
     headers: HeaderMap,
     Json(request): Json<RegistrationRequest>,
-) -> Result<Json<RegistrationResponse>, StatusCode> {
+) -> Result<Json<RegistrationResponse>, Response> {
     // Extract client information for audit logging
     let client_ip = extract_client_ip(&headers);
     let user_agent = extract_user_agent(&headers);
@@ -118,7 +123,7 @@ pub async fn post_register(
         // Validate auth data format
         if !auth_data.is_object() {
             error!("Invalid UIA auth data format");
-            return Err(StatusCode::BAD_REQUEST);
+            return Err(StatusCode::BAD_REQUEST.into_response());
         }
 
         // Check if CAPTCHA is required for registration from this IP/client
@@ -152,17 +157,17 @@ pub async fn post_register(
                                     "CAPTCHA validation failed for registration from IP: {}",
                                     client_ip
                                 );
-                                return Err(StatusCode::UNAUTHORIZED);
+                                return Err(StatusCode::UNAUTHORIZED.into_response());
                             }
                         },
                         Err(e) => {
                             error!("CAPTCHA validation error: {:?}", e);
-                            return Err(StatusCode::BAD_REQUEST);
+                            return Err(StatusCode::BAD_REQUEST.into_response());
                         },
                     }
                 } else {
                     error!("Missing CAPTCHA session in auth data");
-                    return Err(StatusCode::BAD_REQUEST);
+                    return Err(StatusCode::BAD_REQUEST.into_response());
                 }
             } else {
                 // CAPTCHA required but not provided - return challenge
@@ -173,19 +178,36 @@ pub async fn post_register(
                     Ok(_challenge) => {
                         info!("Created CAPTCHA challenge for registration");
                         // Return UIA flow indicating CAPTCHA is required
-                        return Err(StatusCode::UNAUTHORIZED); // Matrix spec: 401 with flows
+                        // (Matrix spec: 401 with flows)
+                        return Err(StatusCode::UNAUTHORIZED.into_response());
                     },
                     Err(e) => {
                         error!("Failed to create CAPTCHA challenge: {:?}", e);
-                        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+                        return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response());
                     },
                 }
             }
         }
     }
 
+    // Token-gated registration and policy consent run as UIA stages first
+    let uia_session_id = registration_uia(&state, request.auth.as_ref()).await?;
+
     // Validate registration request
-    let username = validate_registration_request(&request)?;
+    let username =
+        validate_registration_request(&request).map_err(|e| StatusCode::from(e).into_response())?;
+
+    let registration = RegistrationInfo {
+        username: &username,
+        client_ip: Some(client_ip.as_str()).filter(|ip| *ip != "unknown"),
+        user_agent: Some(user_agent.as_str()).filter(|agent| *agent != "unknown"),
+    };
+    if let SpamCheck::Deny { reason } =
+        state.modules.check_registration_for_spam(registration).await
+    {
+        warn!("Registration of {} rejected by spam checker: {}", username, reason);
+        return Err(StatusCode::FORBIDDEN.into_response());
+    }
 
     info!("Registering new user: {}", username);
 
@@ -209,6 +231,9 @@ pub async fn post_register(
         {
             Ok(_) => {
                 info!("User registration completed without login for: {}", user_id);
+                if let Some(session_id) = &uia_session_id {
+                    finish_registration_uia(&state, session_id, &user_id).await;
+                }
                 return Ok(Json(RegistrationResponse {
                     user_id,
                     access_token: None,
@@ -220,7 +245,7 @@ pub async fn post_register(
             },
             Err(e) => {
                 error!("Failed to register user {}: {:?}", username, e);
-                return Err(RegistrationError::DatabaseError.into());
+                return Err(StatusCode::from(RegistrationError::DatabaseError).into_response());
             },
         }
     }
@@ -241,6 +266,9 @@ pub async fn post_register(
     {
         Ok(registration_result) => {
             info!("User registration completed successfully for: {}", registration_result.user_id);
+            if let Some(session_id) = &uia_session_id {
+                finish_registration_uia(&state, session_id, &registration_result.user_id).await;
+            }
 
             // Return registration response with refresh token if requested and supported
             let refresh_token = if request.refresh_token {
@@ -264,11 +292,104 @@ pub async fn post_register(
         },
         Err(e) => {
             error!("Failed to register user {}: {:?}", username, e);
-            Err(RegistrationError::DatabaseError.into())
+            Err(StatusCode::from(RegistrationError::DatabaseError).into_response())
         },
     }
 }
 
+/// Run the registration UIA stages, the registration token when tokens are
+/// required and the policies when consent is tracked, returning the session
+/// once every stage passed
+async fn registration_uia(
+    state: &AppState,
+    auth: Option<&Value>,
+) -> Result<Option<String>, Response> {
+    let require_terms = state.consent.is_enabled();
+    let flows = state.uia_service.registration_flows(require_terms);
+    if flows.is_empty() {
+        return Ok(None);
+    }
+
+    let session_id = auth.and_then(|auth| auth.get("session")).and_then(Value::as_str);
+    let (Some(auth), Some(session_id)) = (auth, session_id) else {
+        let mut params = HashMap::new();
+        if require_terms {
+            params.insert(TERMS_STAGE.to_string(), state.consent.policies());
+        }
+        let session = state
+            .uia_service
+            .start_session(None, None, flows.clone(), params.clone())
+            .await
+            .map_err(|e| {
+                error!("Failed to start registration UIA session: {:?}", e);
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            })?;
+
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(json!({
+                "flows": flows,
+                "params": params,
+                "session": session.session_id,
+            })),
+        )
+            .into_response());
+    };
+
+    let uia_auth: UiaAuth = serde_json::from_value(auth.clone()).map_err(|e| {
+        warn!("Invalid UIA auth data for registration: {}", e);
+        StatusCode::BAD_REQUEST.into_response()
+    })?;
+
+    if let Err(uia_error) = state.uia_service.process_auth(session_id, uia_auth).await {
+        warn!("Registration UIA not completed: {}", uia_error.error);
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(json!({
+                "flows": uia_error.flows,
+                "params": uia_error.params,
+                "session": uia_error.session,
+                "completed": uia_error.completed,
+                "error": uia_error.error,
+                "errcode": uia_error.errcode,
+            })),
+        )
+            .into_response());
+    }
+
+    Ok(Some(session_id.to_string()))
+}
+
+/// Count the registration against its token, record the accepted policies
+/// and close the UIA session so a token use is not released again when the
+/// session expires
+async fn finish_registration_uia(state: &AppState, session_id: &str, user_id: &str) {
+    let session_data = match state.uia_service.get_session_data(session_id).await {
+        Ok(session_data) => session_data,
+        Err(e) => {
+            error!("Failed to read registration UIA session {}: {:?}", session_id, e);
+            return;
+        },
+    };
+
+    if let Some(token) = session_data.get(REGISTRATION_TOKEN_AUTH_DATA).and_then(Value::as_str) {
+        let registration_repo = RegistrationRepository::new(state.db.clone());
+        if let Err(e) = registration_repo.complete_registration_token(token).await {
+            error!("Failed to record use of registration token: {}", e);
+        }
+    }
+
+    if session_data.contains_key("terms_accepted")
+        && let Err(e) = state.consent.accept_all(user_id).await
+    {
+        error!("Failed to record policy consent of {}: {}", user_id, e);
+    }
+
+    if let Err(e) = state.uia_service.uia_repo.delete_session(session_id).await {
+        warn!("Failed to delete registration UIA session {}: {}", session_id, e);
+    }
+}
+
 async fn create_infrastructure_service(
     state: &AppState,
 ) -> InfrastructureService<surrealdb::engine::any::Any> {
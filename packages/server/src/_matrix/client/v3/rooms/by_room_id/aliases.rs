@@ -1,6 +1,6 @@
 use axum::{
     Json,
-    extract::{Path, State},
+    extract::State,
     http::{HeaderMap, StatusCode},
 };
 use serde::Serialize;
@@ -9,7 +9,9 @@ use tracing::{error, info, warn};
 use crate::{
     AppState,
     auth::{MatrixAuth, extract_matrix_auth},
+    utils::matrix_path::MatrixPath,
 };
+use matryx_entity::types::RoomId;
 
 #[derive(Serialize)]
 pub struct RoomAliasesResponse {
@@ -20,7 +22,7 @@ pub struct RoomAliasesResponse {
 pub async fn get(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Path(room_id): Path<String>,
+    MatrixPath(room_id): MatrixPath<RoomId>,
 ) -> Result<Json<RoomAliasesResponse>, StatusCode> {
     // Extract and validate Matrix authentication
     let auth = extract_matrix_auth(&headers, &state.session_service).await.map_err(|e| {
@@ -48,12 +50,6 @@ pub async fn get(
 
     info!("Processing room aliases request for room: {} by user: {}", room_id, user_id);
 
-    // Validate room ID format
-    if !room_id.starts_with('!') {
-        warn!("Room aliases request failed - invalid room ID format: {}", room_id);
-        return Err(StatusCode::BAD_REQUEST);
-    }
-
     // Use RoomOperationsService to get room aliases with all validation
     match state.room_operations.get_room_aliases(&room_id, &user_id).await {
         Ok(aliases_response) => {
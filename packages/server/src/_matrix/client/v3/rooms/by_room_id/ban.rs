@@ -2,7 +2,7 @@ use std::net::SocketAddr;
 
 use axum::{
     Json,
-    extract::{ConnectInfo, Path, State},
+    extract::{ConnectInfo, State},
     http::{HeaderMap, StatusCode},
 };
 use serde::{Deserialize, Serialize};
@@ -11,7 +11,9 @@ use tracing::{error, info, warn};
 use crate::{
     AppState,
     auth::{MatrixAuth, extract_matrix_auth},
+    utils::matrix_path::MatrixPath,
 };
+use matryx_entity::types::{RoomId, UserId};
 
 #[derive(Deserialize)]
 pub struct BanRequest {
@@ -42,7 +44,7 @@ pub async fn post(
     State(state): State<AppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
-    Path(room_id): Path<String>,
+    MatrixPath(room_id): MatrixPath<RoomId>,
     Json(request): Json<BanRequest>,
 ) -> Result<Json<BanResponse>, StatusCode> {
     // Extract and validate Matrix authentication
@@ -74,14 +76,8 @@ pub async fn post(
         banner_id, request.user_id, room_id, addr
     );
 
-    // Validate room ID format
-    if !room_id.starts_with('!') {
-        warn!("Room ban failed - invalid room ID format: {}", room_id);
-        return Err(StatusCode::BAD_REQUEST);
-    }
-
     // Validate user ID format
-    if !request.user_id.starts_with('@') || !request.user_id.contains(':') {
+    if UserId::parse(&request.user_id).is_err() {
         warn!("Room ban failed - invalid user ID format: {}", request.user_id);
         return Err(StatusCode::BAD_REQUEST);
     }
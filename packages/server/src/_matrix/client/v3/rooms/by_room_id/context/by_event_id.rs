@@ -98,6 +98,32 @@ pub async fn get(
         Ok(mut context_response) => {
             info!("Successfully retrieved context for event {} in room {}", event_id, room_id);
 
+            // Hide the event and its surroundings where the user may not see them
+            let visibility_error = |e: matryx_surrealdb::repository::error::RepositoryError| {
+                error!("Failed to apply event visibility: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            };
+            if let Some(event) = &context_response.event {
+                let visible = state
+                    .event_visibility
+                    .can_user_see_event(&user_id, event)
+                    .await
+                    .map_err(visibility_error)?;
+                if !visible {
+                    return Err(StatusCode::NOT_FOUND);
+                }
+            }
+            context_response.events_before = state
+                .event_visibility
+                .filter_events_for_client(&user_id, context_response.events_before)
+                .await
+                .map_err(visibility_error)?;
+            context_response.events_after = state
+                .event_visibility
+                .filter_events_for_client(&user_id, context_response.events_after)
+                .await
+                .map_err(visibility_error)?;
+
             // Apply filter to context response if provided (Matrix spec compliance)
             if let Some(ref filter) = matrix_filter {
                 // Apply room event filter to events_before, events_after, and state
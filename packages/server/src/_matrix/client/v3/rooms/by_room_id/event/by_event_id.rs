@@ -1,19 +1,75 @@
-use axum::{Json, extract::Path, http::StatusCode};
-use serde_json::{Value, json};
+use axum::{
+    Json,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+};
+use serde_json::Value;
+use tracing::{error, info, warn};
+
+use crate::{
+    AppState,
+    auth::{MatrixAuth, extract_matrix_auth},
+    utils::matrix_path::MatrixPath,
+};
+use matryx_entity::types::{EventId, RoomId};
+use matryx_surrealdb::repository::EventRepository;
 
 /// GET /_matrix/client/v3/rooms/{roomId}/event/{eventId}
+///
+/// Unknown events and events the user may not see both return 404, so the
+/// response does not reveal which events exist.
 pub async fn get(
-    Path((_room_id, _event_id)): Path<(String, String)>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    MatrixPath((room_id, event_id)): MatrixPath<(RoomId, EventId)>,
 ) -> Result<Json<Value>, StatusCode> {
-    Ok(Json(json!({
-        "type": "m.room.message",
-        "content": {
-            "msgtype": "m.text",
-            "body": "Example message"
+    let auth = extract_matrix_auth(&headers, &state.session_service).await.map_err(|e| {
+        warn!("Room event request failed - authentication extraction failed: {}", e);
+        StatusCode::UNAUTHORIZED
+    })?;
+
+    let user_id = match auth {
+        MatrixAuth::User(token_info) => {
+            if token_info.is_expired() {
+                warn!("Room event request failed - access token expired");
+                return Err(StatusCode::UNAUTHORIZED);
+            }
+            token_info.user_id
         },
-        "event_id": "$example_event_id:example.com",
-        "sender": "@example:example.com",
-        "origin_server_ts": 1234567890,
-        "room_id": "!example_room:example.com"
-    })))
+        _ => {
+            warn!("Room event request failed - user authentication required");
+            return Err(StatusCode::UNAUTHORIZED);
+        },
+    };
+
+    let event_repo = EventRepository::new(state.db.clone());
+    let event = event_repo
+        .get_by_id(&event_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to get event {}: {}", event_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .filter(|event| room_id == event.room_id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let visible = state
+        .event_visibility
+        .can_user_see_event(&user_id, &event)
+        .await
+        .map_err(|e| {
+            error!("Failed to apply event visibility: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if !visible {
+        info!("Event {} in room {} is not visible to {}", event_id, room_id, user_id);
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let event = serde_json::to_value(event).map_err(|e| {
+        error!("Failed to serialize event {}: {}", event_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(event))
 }
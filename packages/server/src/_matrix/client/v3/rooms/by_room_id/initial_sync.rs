@@ -76,6 +76,19 @@ pub async fn get(
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
+    // Anonymous previews are only allowed in world readable rooms, where every event is visible
+    let messages = match user_id {
+        Some(ref user_id) => state
+            .event_visibility
+            .filter_events_for_client(user_id, messages)
+            .await
+            .map_err(|e| {
+                error!("Failed to apply event visibility: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?,
+        None => messages,
+    };
+
     // Get room presence (empty for preview)
     let presence: Vec<Value> = vec![];
 
@@ -2,7 +2,7 @@ use std::net::SocketAddr;
 
 use axum::{
     Json,
-    extract::{ConnectInfo, Path, State},
+    extract::{ConnectInfo, State},
     http::{HeaderMap, StatusCode},
 };
 use serde::{Deserialize, Serialize};
@@ -11,7 +11,10 @@ use tracing::{error, info, warn};
 use crate::{
     AppState,
     auth::{MatrixAuth, extract_matrix_auth},
+    modules::SpamCheck,
+    utils::matrix_path::MatrixPath,
 };
+use matryx_entity::types::{RoomId, UserId};
 
 #[derive(Deserialize)]
 pub struct InviteRequest {
@@ -41,7 +44,7 @@ pub async fn post(
     State(state): State<AppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
-    Path(room_id): Path<String>,
+    MatrixPath(room_id): MatrixPath<RoomId>,
     Json(request): Json<InviteRequest>,
 ) -> Result<Json<InviteResponse>, StatusCode> {
     // Extract and validate Matrix authentication
@@ -73,18 +76,40 @@ pub async fn post(
         inviter_id, request.user_id, room_id, addr
     );
 
-    // Validate room ID format
-    if !room_id.starts_with('!') {
-        warn!("Room invite failed - invalid room ID format: {}", room_id);
-        return Err(StatusCode::BAD_REQUEST);
-    }
-
     // Validate user ID format
-    if !request.user_id.starts_with('@') || !request.user_id.contains(':') {
+    if UserId::parse(&request.user_id).is_err() {
         warn!("Room invite failed - invalid user ID format: {}", request.user_id);
         return Err(StatusCode::BAD_REQUEST);
     }
 
+    // Neither side of an invite may be banned by a subscribed policy list
+    if state.policy_lists.is_user_banned(&inviter_id) ||
+        state.policy_lists.is_user_banned(&request.user_id)
+    {
+        warn!(
+            "Room invite failed - {} or {} is banned by a policy list",
+            inviter_id, request.user_id
+        );
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    if let SpamCheck::Deny { reason } =
+        state.modules.user_may_invite(&inviter_id, &request.user_id, &room_id).await
+    {
+        warn!("Room invite failed - rejected by spam checker: {}", reason);
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    // Nobody may be invited into a room shut down by an admin
+    state.room_shutdown.check_not_blocked(&room_id).await?;
+
+    // Invites from shadow-banned users appear to succeed but are never sent
+    let shadow_banned = state.user_restrictions.is_shadow_banned(&inviter_id).await?;
+    if shadow_banned {
+        info!("Dropping invite of {} from shadow-banned user {}", request.user_id, inviter_id);
+        return Ok(Json(InviteResponse {}));
+    }
+
     // Use RoomOperationsService to invite user with all validation
     match state
         .room_operations
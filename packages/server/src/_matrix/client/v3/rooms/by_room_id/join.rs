@@ -15,6 +15,7 @@ use tracing::{error, info, warn};
 use crate::{
     AppState,
     auth::{MatrixAuth, extract_matrix_auth},
+    modules::SpamCheck,
 };
 use matryx_entity::types::{Membership, MembershipState, Room};
 use matryx_surrealdb::repository::{
@@ -94,7 +95,17 @@ pub async fn post(
         return Err(StatusCode::BAD_REQUEST);
     }
 
+    // Users and rooms banned by a subscribed policy list cannot be joined
+    if state.policy_lists.is_user_banned(&user_id) || state.policy_lists.is_room_banned(&room_id) {
+        warn!("Room join failed - {} or {} is banned by a policy list", user_id, room_id);
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    // Rooms shut down by an admin cannot be joined
+    state.room_shutdown.check_not_blocked(&room_id).await?;
+
     // Check if user is already in the room
+    let mut is_invited = false;
     if let Ok(Some(current_membership)) = membership_repo.get_by_room_user(&room_id, &user_id).await
     {
         match current_membership.membership {
@@ -106,12 +117,20 @@ pub async fn post(
                 warn!("Room join failed - user {} is banned from room {}", user_id, room_id);
                 return Err(StatusCode::FORBIDDEN);
             },
+            MembershipState::Invite => is_invited = true,
             _ => {
-                // User has some other membership state (invite, leave, knock) - proceed with join
+                // User has some other membership state (leave, knock) - proceed with join
             },
         }
     }
 
+    if let SpamCheck::Deny { reason } =
+        state.modules.user_may_join_room(&user_id, &room_id, is_invited).await
+    {
+        warn!("Room join failed - rejected by spam checker: {}", reason);
+        return Err(StatusCode::FORBIDDEN);
+    }
+
     // Get room information to check join rules
     let room = room_repo
         .get_by_id(&room_id)
@@ -197,6 +216,11 @@ pub async fn post(
         join_event_id.event_id, user_id, room_id
     );
 
+    // Carry tags, push rules and m.direct over if this room replaced another
+    if let Err(e) = state.room_upgrades.migrate_user_data(&user_id, &room_id).await {
+        warn!("Failed to migrate data of {} into upgraded room {}: {}", user_id, room_id, e);
+    }
+
     Ok(Json(JoinResponse { room_id }))
 }
 
@@ -2,7 +2,7 @@ use std::net::SocketAddr;
 
 use axum::{
     Json,
-    extract::{ConnectInfo, Path, State},
+    extract::{ConnectInfo, State},
     http::{HeaderMap, StatusCode},
 };
 
@@ -13,8 +13,9 @@ use tracing::{error, info, warn};
 use crate::{
     AppState,
     auth::{MatrixAuth, extract_matrix_auth},
+    utils::matrix_path::MatrixPath,
 };
-use matryx_entity::types::MembershipState;
+use matryx_entity::types::{MembershipState, RoomId, UserId};
 use matryx_surrealdb::repository::{MembershipRepository, RoomRepository};
 
 #[derive(Deserialize)]
@@ -46,7 +47,7 @@ pub async fn post(
     State(state): State<AppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
-    Path(room_id): Path<String>,
+    MatrixPath(room_id): MatrixPath<RoomId>,
     Json(request): Json<KickRequest>,
 ) -> Result<Json<KickResponse>, StatusCode> {
     // Extract and validate Matrix authentication
@@ -78,14 +79,8 @@ pub async fn post(
         kicker_id, request.user_id, room_id, addr
     );
 
-    // Validate room ID format
-    if !room_id.starts_with('!') {
-        warn!("Room kick failed - invalid room ID format: {}", room_id);
-        return Err(StatusCode::BAD_REQUEST);
-    }
-
     // Validate user ID format
-    if !request.user_id.starts_with('@') || !request.user_id.contains(':') {
+    if UserId::parse(&request.user_id).is_err() {
         warn!("Room kick failed - invalid user ID format: {}", request.user_id);
         return Err(StatusCode::BAD_REQUEST);
     }
@@ -2,7 +2,7 @@ use std::net::SocketAddr;
 
 use axum::{
     Json,
-    extract::{ConnectInfo, Path, State},
+    extract::{ConnectInfo, State},
     http::{HeaderMap, StatusCode},
 };
 use serde::{Deserialize, Serialize};
@@ -11,7 +11,9 @@ use tracing::{error, info, warn};
 use crate::{
     AppState,
     auth::{MatrixAuth, extract_matrix_auth},
+    utils::matrix_path::MatrixPath,
 };
+use matryx_entity::types::RoomId;
 
 #[derive(Deserialize)]
 pub struct LeaveRequest {
@@ -39,7 +41,7 @@ pub async fn post(
     State(state): State<AppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
-    Path(room_id): Path<String>,
+    MatrixPath(room_id): MatrixPath<RoomId>,
     Json(request): Json<LeaveRequest>,
 ) -> Result<Json<LeaveResponse>, StatusCode> {
     // Extract and validate Matrix authentication
@@ -71,12 +73,6 @@ pub async fn post(
         user_id, room_id, addr
     );
 
-    // Validate room ID format
-    if !room_id.starts_with('!') {
-        warn!("Room leave failed - invalid room ID format: {}", room_id);
-        return Err(StatusCode::BAD_REQUEST);
-    }
-
     // Use RoomOperationsService to leave room with all validation
     match state.room_operations.leave_room(&room_id, &user_id, request.reason).await {
         Ok(()) => {
@@ -1,6 +1,6 @@
 use axum::{
     Json,
-    extract::{Path, Query, State},
+    extract::{Query, State},
     http::{HeaderMap, StatusCode},
 };
 use serde::{Deserialize, Serialize};
@@ -11,8 +11,9 @@ use uuid;
 use crate::{
     AppState,
     auth::{MatrixAuth, extract_matrix_auth},
+    utils::matrix_path::MatrixPath,
 };
-use matryx_entity::types::MembershipState;
+use matryx_entity::types::{MembershipState, RoomId};
 
 #[derive(Deserialize)]
 pub struct MembersQuery {
@@ -76,7 +77,7 @@ pub struct MembersResponse {
 /// may still appear in this list, depending on the filtering parameters.
 pub async fn get(
     State(state): State<AppState>,
-    Path(room_id): Path<String>,
+    MatrixPath(room_id): MatrixPath<RoomId>,
     headers: HeaderMap,
     Query(query): Query<MembersQuery>,
 ) -> Result<Json<MembersResponse>, StatusCode> {
@@ -106,12 +107,6 @@ pub async fn get(
 
     info!("Processing room members request for room: {} by user: {}", room_id, user_id);
 
-    // Validate room ID format
-    if !room_id.starts_with('!') {
-        warn!("Room members request failed - invalid room ID format: {}", room_id);
-        return Err(StatusCode::BAD_REQUEST);
-    }
-
     // Convert query parameters to appropriate types
     let membership_filter = query.membership.map(MembershipState::from);
     let not_membership_filter = query.not_membership.map(MembershipState::from);
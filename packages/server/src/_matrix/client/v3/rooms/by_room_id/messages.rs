@@ -205,6 +205,16 @@ pub async fn get(
         filter.as_ref(),
     ).await {
         Ok((events, start_token, end_token)) => {
+            // Drop events the user may not see at their point in history
+            let events = state
+                .event_visibility
+                .filter_events_for_client(&user_id, events)
+                .await
+                .map_err(|e| {
+                    error!("Failed to apply event visibility: {}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?;
+
             // Convert events to JSON
             let chunk: Vec<Value> = events
                 .into_iter()
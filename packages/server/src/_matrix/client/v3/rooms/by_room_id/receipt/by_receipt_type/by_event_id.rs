@@ -59,6 +59,16 @@ pub async fn post(
                 .filter(|server| server != &state.homeserver_name)
                 .collect();
 
+            // Servers denied by the room's server ACL do not receive its EDUs
+            let remote_servers = state
+                .server_acls
+                .filter_destinations(&room_id, remote_servers, "receipt")
+                .await
+                .map_err(|e| {
+                    error!("Failed to apply server ACL for room {}: {}", room_id, e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?;
+
             if !remote_servers.is_empty() {
                 // Build receipt content according to Matrix spec
                 let mut receipt_data = json!({
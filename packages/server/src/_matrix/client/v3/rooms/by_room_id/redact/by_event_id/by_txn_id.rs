@@ -1,12 +1,40 @@
-use axum::{Json, extract::Path, http::StatusCode};
-use serde_json::{Value, json};
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::_matrix::client::v3::rooms::by_room_id::send::by_event_type::by_txn_id as send;
+use crate::auth::AuthenticatedUser;
+use crate::state::AppState;
+
+#[derive(Deserialize)]
+pub struct RedactRequest {
+    pub reason: Option<String>,
+}
 
 /// PUT /_matrix/client/v3/rooms/{roomId}/redact/{eventId}/{txnId}
+///
+/// Sent as an `m.room.redaction` event, so redactions share the send
+/// endpoint's idempotency, permission checks and shadow-ban handling.
 pub async fn put(
-    Path((_room_id, _event_id, _txn_id)): Path<(String, String, String)>,
-    Json(_payload): Json<Value>,
-) -> Result<Json<Value>, StatusCode> {
-    Ok(Json(json!({
-        "event_id": "$redacted_event_id:example.com"
-    })))
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+    Path((room_id, event_id, txn_id)): Path<(String, String, String)>,
+    Json(request): Json<RedactRequest>,
+) -> Result<Json<send::SendEventResponse>, StatusCode> {
+    let mut content = json!({ "redacts": event_id });
+    if let Some(reason) = request.reason {
+        content["reason"] = json!(reason);
+    }
+
+    send::put(
+        State(state),
+        auth,
+        Path((room_id, "m.room.redaction".to_string(), txn_id)),
+        Json(send::SendEventRequest { content }),
+    )
+    .await
 }
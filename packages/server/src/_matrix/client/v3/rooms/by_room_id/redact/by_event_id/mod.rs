@@ -1,14 +1,32 @@
-use axum::{Json, extract::Path, http::StatusCode};
-use serde_json::{Value, json};
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use uuid::Uuid;
+
+use crate::_matrix::client::v3::rooms::by_room_id::send::by_event_type::by_txn_id as send;
+use crate::auth::AuthenticatedUser;
+use crate::state::AppState;
+
+use self::by_txn_id::RedactRequest;
 
 /// PUT /_matrix/client/v3/rooms/{roomId}/redact/{eventId}
+///
+/// Like the `{txnId}` form, with a transaction ID of the server's choosing.
 pub async fn put(
-    Path((_room_id, _event_id)): Path<(String, String)>,
-    Json(_payload): Json<Value>,
-) -> Result<Json<Value>, StatusCode> {
-    Ok(Json(json!({
-        "event_id": "$redacted_event_id:example.com"
-    })))
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+    Path((room_id, event_id)): Path<(String, String)>,
+    Json(request): Json<RedactRequest>,
+) -> Result<Json<send::SendEventResponse>, StatusCode> {
+    by_txn_id::put(
+        State(state),
+        auth,
+        Path((room_id, event_id, Uuid::new_v4().to_string())),
+        Json(request),
+    )
+    .await
 }
 
 pub mod by_txn_id;
@@ -9,11 +9,13 @@ use tracing::{error, info, warn};
 use crate::{
     AppState,
     auth::{MatrixAuth, extract_matrix_auth},
+    moderation::ReportSubmission,
 };
+use matryx_surrealdb::repository::{EventRepository, MembershipRepository, ModerationReportKind};
 
 #[derive(Deserialize)]
 pub struct ReportEventRequest {
-    pub reason: String,
+    pub reason: Option<String>,
     pub score: Option<i32>, // -100 to 0 (most offensive)
 }
 
@@ -67,33 +69,49 @@ pub async fn post(
         return Err(StatusCode::BAD_REQUEST);
     }
 
-    // Use RoomOperationsService to report event with all validation
-    match state
-        .room_operations
-        .report_event(&room_id, &event_id, &user_id, &request.reason, request.score)
-        .await
+    if let Some(score) = request.score
+        && !(-100..=0).contains(&score)
     {
-        Ok(()) => {
-            info!(
-                "Successfully reported event {} in room {} by user {}",
-                event_id, room_id, user_id
-            );
-            Ok(Json(ReportResponse {}))
-        },
-        Err(e) => {
-            error!("Failed to report event {} in room {}: {}", event_id, room_id, e);
-            match e {
-                matryx_surrealdb::repository::error::RepositoryError::NotFound { .. } => {
-                    Err(StatusCode::NOT_FOUND)
-                },
-                matryx_surrealdb::repository::error::RepositoryError::Unauthorized { .. } => {
-                    Err(StatusCode::FORBIDDEN)
-                },
-                matryx_surrealdb::repository::error::RepositoryError::Validation { .. } => {
-                    Err(StatusCode::BAD_REQUEST)
-                },
-                _ => Err(StatusCode::INTERNAL_SERVER_ERROR),
-            }
-        },
+        warn!("Event report failed - score {} out of range", score);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    // A missing event and a room the reporter is not in look the same, so
+    // reports cannot be used to probe for events
+    let event_repo = EventRepository::new(state.db.clone());
+    let event = event_repo.get_by_id(&event_id).await.map_err(|e| {
+        error!("Failed to look up reported event {}: {}", event_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let Some(event) = event.filter(|event| event.room_id == room_id) else {
+        warn!("Event report failed - event {} not found in room {}", event_id, room_id);
+        return Err(StatusCode::NOT_FOUND);
+    };
+
+    let membership_repo = MembershipRepository::new(state.db.clone());
+    let is_member = membership_repo.is_user_in_room(&room_id, &user_id).await.map_err(|e| {
+        error!("Failed to check membership of {} in room {}: {}", user_id, room_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if !is_member {
+        warn!("Event report failed - {} is not joined to room {}", user_id, room_id);
+        return Err(StatusCode::NOT_FOUND);
     }
+
+    let submission = ReportSubmission {
+        kind: ModerationReportKind::Event,
+        room_id: Some(room_id.clone()),
+        event_id: Some(event_id.clone()),
+        reported_user_id: Some(event.sender),
+        reporter_id: user_id.clone(),
+        reason: request.reason,
+        score: request.score,
+    };
+    state.reports.submit(&state, submission).await.map_err(|e| {
+        error!("Failed to report event {} in room {}: {}", event_id, room_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    info!("Successfully reported event {} in room {} by user {}", event_id, room_id, user_id);
+    Ok(Json(ReportResponse {}))
 }
@@ -1,12 +1,57 @@
-use axum::{Json, extract::Path, http::StatusCode};
-use serde_json::{Value, json};
+use axum::{extract::State, http::StatusCode, response::Json};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+use crate::auth::AuthenticatedUser;
+use crate::moderation::ReportSubmission;
+use crate::state::AppState;
+use crate::utils::matrix_path::MatrixPath;
+use matryx_entity::types::RoomId;
+use matryx_surrealdb::repository::{ModerationReportKind, RoomRepository};
+
+#[derive(Deserialize)]
+pub struct ReportRoomRequest {
+    pub reason: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ReportResponse {
+    // Empty response per Matrix spec
+}
 
 /// POST /_matrix/client/v3/rooms/{roomId}/report
 pub async fn post(
-    Path(_room_id): Path<String>,
-    Json(_payload): Json<Value>,
-) -> Result<Json<Value>, StatusCode> {
-    Ok(Json(json!({})))
+    State(state): State<AppState>,
+    auth_user: AuthenticatedUser,
+    MatrixPath(room_id): MatrixPath<RoomId>,
+    Json(request): Json<ReportRoomRequest>,
+) -> Result<Json<ReportResponse>, StatusCode> {
+    let room_repo = RoomRepository::new(state.db.clone());
+    let room = room_repo.get_by_id(&room_id).await.map_err(|e| {
+        error!("Failed to look up reported room {}: {}", room_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if room.is_none() {
+        warn!("Room report failed - room {} not found", room_id);
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let submission = ReportSubmission {
+        kind: ModerationReportKind::Room,
+        room_id: Some(room_id.to_string()),
+        event_id: None,
+        reported_user_id: None,
+        reporter_id: auth_user.user_id.clone(),
+        reason: request.reason,
+        score: None,
+    };
+    state.reports.submit(&state, submission).await.map_err(|e| {
+        error!("Failed to report room {}: {}", room_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    info!("Room {} reported by user {}", room_id, auth_user.user_id);
+    Ok(Json(ReportResponse {}))
 }
 
 pub mod by_event_id;
@@ -13,9 +13,10 @@ use crate::auth::AuthenticatedUser;
 use crate::event_replacements::ReplacementValidator;
 use crate::federation::outbound_queue::OutboundEvent;
 use crate::mentions::MentionsProcessor;
+use crate::modules::SpamCheck;
 use crate::state::AppState;
 
-use matryx_entity::types::{MembershipState, PDU};
+use matryx_entity::types::{AnyMessageLikeEvent, MembershipState, PDU, RoomVersionRules};
 use matryx_surrealdb::repository::{
     EventRepository, MembershipRepository, PowerLevelsRepository, RoomRepository,
 };
@@ -23,7 +24,7 @@ use matryx_surrealdb::repository::{
 #[derive(Deserialize)]
 pub struct SendEventRequest {
     #[serde(flatten)]
-    content: Value,
+    pub content: Value,
 }
 
 #[derive(Serialize)]
@@ -54,7 +55,7 @@ pub async fn put(
     }
 
     // Verify room exists
-    let _room = room_repo
+    let room = room_repo
         .get_by_id(&room_id)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
@@ -106,6 +107,13 @@ pub async fn put(
         return Err(StatusCode::BAD_REQUEST);
     }
 
+    // Validate content of known event types against the room version's rules
+    let rules = RoomVersionRules::for_version(&room.room_version).unwrap_or_default();
+    if let Err(e) = AnyMessageLikeEvent::validate_content(&event_type, &request.content, &rules) {
+        debug!("Rejecting invalid {} content in room {}: {}", event_type, room_id, e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
     // Get room's current power levels for permission check
     let power_levels = power_levels_repo
         .get_power_levels(&room_id)
@@ -156,14 +164,8 @@ pub async fn put(
 
     // Add spec-compliant m.mentions
     if let Some(mentions) = mentions_metadata {
-        let mut mentions_json = serde_json::Map::new();
-        if let Some(user_ids) = mentions.user_ids {
-            mentions_json.insert("user_ids".to_string(), serde_json::json!(user_ids));
-        }
-        if let Some(room) = mentions.room {
-            mentions_json.insert("room".to_string(), serde_json::json!(room));
-        }
-        event_content["m.mentions"] = serde_json::Value::Object(mentions_json);
+        event_content["m.mentions"] =
+            serde_json::to_value(&mentions).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
         tracing::info!("Processed user/room mentions for event in room {}", room_id);
     }
 
@@ -174,6 +176,26 @@ pub async fn put(
         tracing::info!("Processed room alias mentions for event in room {}", room_id);
     }
 
+    // Let spam-checker modules reject the event before it is created
+    let draft = serde_json::json!({
+        "type": event_type,
+        "room_id": room_id,
+        "sender": auth.user_id,
+        "content": event_content,
+    });
+    if let SpamCheck::Deny { reason } = state.modules.check_event_for_spam(&draft).await {
+        debug!("Rejecting {} event in room {}: {}", event_type, room_id, reason);
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    // Shadow-banned users are told the event was sent, but it goes nowhere
+    let shadow_banned = state.user_restrictions.is_shadow_banned(&auth.user_id).await?;
+    if shadow_banned {
+        debug!("Dropping {} event from shadow-banned user {}", event_type, auth.user_id);
+        let event_id = state.user_restrictions.fake_event_id_for_txn(&auth.user_id, &txn_id).await;
+        return Ok(Json(SendEventResponse { event_id }));
+    }
+
     // Create complete event with DAG relationships using repository
     let mut event = event_repo
         .create_complete_event(
@@ -221,6 +243,16 @@ pub async fn put(
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
+    // Servers denied by the room's server ACL do not receive its events
+    let remote_servers = state
+        .server_acls
+        .filter_destinations(&room_id, remote_servers, "pdu")
+        .await
+        .map_err(|e| {
+            error!("Failed to apply server ACL for room {}: {}", room_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
     // Convert Event to PDU for federation
     let pdu = PDU {
         content: event.content.clone(),
@@ -9,8 +9,10 @@ use tracing::{debug, error, warn};
 
 use crate::auth::AuthenticatedUser;
 use crate::federation::event_signer::EventSigner;
+use crate::moderation::user_restrictions::fake_event_id;
+use crate::modules::{EventRuling, SpamCheck};
 use crate::state::AppState;
-use matryx_entity::types::Event;
+use matryx_entity::types::{AnyStateEvent, Event, PowerLevels, RoomVersionRules};
 use matryx_surrealdb::repository::{EventRepository, MembershipRepository, RoomRepository};
 
 /// GET /_matrix/client/v3/rooms/{roomId}/state/{eventType}/{stateKey}
@@ -86,7 +88,7 @@ pub async fn put(
 
     // Validate room exists
     let room_repo = Arc::new(RoomRepository::new(state.db.clone()));
-    let _room = room_repo
+    let room = room_repo
         .get_by_id(&room_id)
         .await
         .map_err(|e| {
@@ -112,11 +114,45 @@ pub async fn put(
     }
 
     // Validate state event content based on type
-    if let Err(validation_error) = validate_state_event_content(&event_type, &content) {
+    let rules = RoomVersionRules::for_version(&room.room_version).unwrap_or_default();
+    if let Err(validation_error) = AnyStateEvent::validate_content(&event_type, &content, &rules) {
         warn!("Invalid state event content: {}", validation_error);
         return Err(StatusCode::BAD_REQUEST);
     }
 
+    // Let spam-checker and third-party-rules modules reject or rewrite the event
+    let mut draft = json!({
+        "type": event_type,
+        "room_id": room_id,
+        "sender": auth.user_id,
+        "state_key": state_key,
+        "content": content,
+    });
+    if let SpamCheck::Deny { reason } = state.modules.check_event_for_spam(&draft).await {
+        warn!("Rejecting state event {}:{} in room {}: {}", event_type, state_key, room_id, reason);
+        return Err(StatusCode::FORBIDDEN);
+    }
+    let content = match state.modules.check_state_event_allowed(&draft).await {
+        EventRuling::Allow => draft["content"].take(),
+        EventRuling::Replace { content } => content,
+        EventRuling::Deny { reason } => {
+            warn!("Rejecting state event {}:{} in room {}: {}", event_type, state_key, room_id, reason);
+            return Err(StatusCode::FORBIDDEN);
+        },
+    };
+
+    // Shadow-banned users are told the event was sent, but it goes nowhere
+    let shadow_banned = state.user_restrictions.is_shadow_banned(&auth.user_id).await?;
+    if shadow_banned {
+        debug!(
+            "Dropping state event {}:{} from shadow-banned user {}",
+            event_type, state_key, auth.user_id
+        );
+        return Ok(Json(json!({
+            "event_id": fake_event_id()
+        })));
+    }
+
     // Create and send the state event
     let event_id =
         send_state_event(&state, &room_id, &auth.user_id, &event_type, &state_key, content)
@@ -126,6 +162,8 @@ pub async fn put(
                 StatusCode::INTERNAL_SERVER_ERROR
             })?;
 
+    state.server_acls.observe_state_event(&room_id, &event_type, Some(&state_key)).await;
+
     debug!(
         "Successfully sent state event {}:{} for room {} with event_id: {}",
         event_type, state_key, room_id, event_id
@@ -183,8 +221,8 @@ async fn check_state_send_permission(
 
     // Get user's power level and required power level for this event type
     let power_levels = get_room_power_levels(state, room_id).await?;
-    let user_power = get_user_power_level(&power_levels, user_id)?;
-    let required_power = get_required_power_level(&power_levels, event_type)?;
+    let user_power = power_levels.get_user_level(user_id);
+    let required_power = power_levels.get_state_event_level(event_type);
 
     Ok(user_power >= required_power)
 }
@@ -223,85 +261,13 @@ async fn get_current_state_event(
 async fn get_room_power_levels(
     state: &AppState,
     room_id: &str,
-) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+) -> Result<PowerLevels, Box<dyn std::error::Error + Send + Sync>> {
     let event_repo = Arc::new(EventRepository::new(state.db.clone()));
     let power_levels = event_repo
         .get_room_power_levels(room_id)
         .await
         .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?;
-    Ok(power_levels)
-}
-
-/// Get user's power level from power_levels content
-fn get_user_power_level(
-    power_levels: &Value,
-    user_id: &str,
-) -> Result<i64, Box<dyn std::error::Error + Send + Sync>> {
-    // Check explicit user power levels
-    if let Some(users) = power_levels.get("users").and_then(|u| u.as_object())
-        && let Some(user_power) = users.get(user_id).and_then(|p| p.as_i64())
-    {
-        return Ok(user_power);
-    }
-
-    // Default user power level
-    Ok(power_levels.get("users_default").and_then(|d| d.as_i64()).unwrap_or(0))
-}
-
-/// Get required power level for an event type
-fn get_required_power_level(
-    power_levels: &Value,
-    event_type: &str,
-) -> Result<i64, Box<dyn std::error::Error + Send + Sync>> {
-    // Check specific event type power levels
-    if let Some(events) = power_levels.get("events").and_then(|e| e.as_object())
-        && let Some(required_power) = events.get(event_type).and_then(|p| p.as_i64())
-    {
-        return Ok(required_power);
-    }
-
-    // Default state event power level
-    Ok(power_levels.get("state_default").and_then(|d| d.as_i64()).unwrap_or(50))
-}
-
-/// Validate state event content based on event type
-fn validate_state_event_content(event_type: &str, content: &Value) -> Result<(), String> {
-    match event_type {
-        "m.room.name" => {
-            if let Some(name) = content.get("name")
-                && !name.is_string()
-            {
-                return Err("name must be a string".to_string());
-            }
-        },
-        "m.room.topic" => {
-            if let Some(topic) = content.get("topic")
-                && !topic.is_string()
-            {
-                return Err("topic must be a string".to_string());
-            }
-        },
-        "m.room.avatar" => {
-            if let Some(url) = content.get("url")
-                && !url.is_string()
-            {
-                return Err("url must be a string".to_string());
-            }
-        },
-        "m.room.canonical_alias" => {
-            if let Some(alias) = content.get("alias")
-                && !alias.is_string()
-                && !alias.is_null()
-            {
-                return Err("alias must be a string or null".to_string());
-            }
-        },
-        _ => {
-            // Allow other event types without validation
-        },
-    }
-
-    Ok(())
+    Ok(serde_json::from_value(power_levels)?)
 }
 
 /// Send a state event to the room
@@ -17,6 +17,13 @@ pub async fn put(
         return Err(StatusCode::FORBIDDEN);
     }
 
+    // Typing from shadow-banned users is accepted but never shown to anyone
+    let shadow_banned = state.user_restrictions.is_shadow_banned(&user_id).await?;
+    if shadow_banned {
+        debug!("Dropping typing notification from shadow-banned user {}", user_id);
+        return Ok(Json(json!({})));
+    }
+
     // Extract typing state and timeout
     let typing = payload.get("typing").and_then(|v| v.as_bool()).unwrap_or(false);
 
@@ -48,6 +55,16 @@ pub async fn put(
         .filter(|server| server != &state.homeserver_name)
         .collect();
 
+    // Servers denied by the room's server ACL do not receive its EDUs
+    let remote_servers = state
+        .server_acls
+        .filter_destinations(&room_id, remote_servers, "typing")
+        .await
+        .map_err(|e| {
+            error!("Failed to apply server ACL for room {}: {}", room_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
     // Send typing EDU to each remote server
     if !remote_servers.is_empty() {
         let typing_content = json!({
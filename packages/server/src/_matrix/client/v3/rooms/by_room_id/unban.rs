@@ -2,7 +2,7 @@ use std::net::SocketAddr;
 
 use axum::{
     Json,
-    extract::{ConnectInfo, Path, State},
+    extract::{ConnectInfo, State},
     http::{HeaderMap, StatusCode},
 };
 use chrono::Utc;
@@ -16,9 +16,12 @@ use uuid::Uuid;
 use crate::{
     AppState,
     auth::{MatrixAuth, extract_matrix_auth},
-    utils::matrix_events::{calculate_content_hashes, sign_event},
+    utils::{
+        matrix_events::{calculate_content_hashes, sign_event},
+        matrix_path::MatrixPath,
+    },
 };
-use matryx_entity::types::{Event, EventContent, Membership, MembershipState, Room};
+use matryx_entity::types::{Event, EventContent, Membership, MembershipState, Room, RoomId, UserId};
 use matryx_surrealdb::repository::{EventRepository, MembershipRepository, RoomRepository};
 use std::sync::Arc;
 
@@ -51,7 +54,7 @@ pub async fn post(
     State(state): State<AppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
-    Path(room_id): Path<String>,
+    MatrixPath(room_id): MatrixPath<RoomId>,
     Json(request): Json<UnbanRequest>,
 ) -> Result<Json<UnbanResponse>, StatusCode> {
     // Extract and validate Matrix authentication
@@ -83,14 +86,8 @@ pub async fn post(
         unbanner_id, request.user_id, room_id, addr
     );
 
-    // Validate room ID format
-    if !room_id.starts_with('!') {
-        warn!("Room unban failed - invalid room ID format: {}", room_id);
-        return Err(StatusCode::BAD_REQUEST);
-    }
-
     // Validate user ID format
-    if !request.user_id.starts_with('@') || !request.user_id.contains(':') {
+    if UserId::parse(&request.user_id).is_err() {
         warn!("Room unban failed - invalid user ID format: {}", request.user_id);
         return Err(StatusCode::BAD_REQUEST);
     }
@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Path, State},
+    extract::State,
     http::{HeaderMap, StatusCode},
     response::Json,
 };
@@ -9,7 +9,9 @@ use tracing::{error, info, warn};
 use crate::{
     AppState,
     auth::{MatrixAuth, extract_matrix_auth},
+    utils::matrix_path::MatrixPath,
 };
+use matryx_entity::types::RoomId;
 
 #[derive(Deserialize)]
 pub struct RoomUpgradeRequest {
@@ -25,7 +27,7 @@ pub struct RoomUpgradeResponse {
 pub async fn post(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Path(room_id): Path<String>,
+    MatrixPath(room_id): MatrixPath<RoomId>,
     Json(request): Json<RoomUpgradeRequest>,
 ) -> Result<Json<RoomUpgradeResponse>, StatusCode> {
     // Extract and validate Matrix authentication
@@ -57,26 +59,18 @@ pub async fn post(
         room_id, request.new_version, user_id
     );
 
-    // Validate room ID format
-    if !room_id.starts_with('!') {
-        warn!("Room upgrade failed - invalid room ID format: {}", room_id);
-        return Err(StatusCode::BAD_REQUEST);
-    }
-
-    // Use RoomOperationsService to upgrade room with all validation
+    // Upgrade the room and migrate its aliases, state and directory listing
     match state
-        .room_operations
-        .upgrade_room(&room_id, &user_id, &request.new_version)
+        .room_upgrades
+        .upgrade(&state.room_operations, &room_id, &request.new_version, &user_id)
         .await
     {
-        Ok(upgrade_response) => {
+        Ok(replacement_room) => {
             info!(
-                "Successfully upgraded room {} to {} (new room: {:?})",
-                room_id, request.new_version, upgrade_response
+                "Successfully upgraded room {} to {} (new room: {})",
+                room_id, request.new_version, replacement_room
             );
-            Ok(Json(RoomUpgradeResponse {
-                replacement_room: upgrade_response.replacement_room,
-            }))
+            Ok(Json(RoomUpgradeResponse { replacement_room }))
         },
         Err(e) => {
             error!("Failed to upgrade room {}: {}", room_id, e);
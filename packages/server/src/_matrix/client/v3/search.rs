@@ -11,9 +11,10 @@ use tracing::{error, info};
 
 use crate::state::AppState;
 use matryx_surrealdb::repository::client_api_service::SearchCategories as RequestSearchCategories;
+use matryx_surrealdb::repository::error::RepositoryError;
 use matryx_surrealdb::repository::search::{
-    EventContext, GroupBy, RoomEventFilter, SearchCategories as ResponseSearchCategories,
-    SearchGroupings,
+    EventContext, GroupBy, RoomEventFilter, RoomEventsResults,
+    SearchCategories as ResponseSearchCategories, SearchGroupings,
 };
 use matryx_surrealdb::repository::search::{SearchCriteria, SearchRepository};
 
@@ -98,7 +99,10 @@ pub async fn post(
 
         // Perform search using repository
         match search_repo.search_events(&user_id, &search_criteria).await {
-            Ok(search_results) => {
+            Ok(mut search_results) => {
+                if let Some(room_events) = search_results.search_categories.room_events.as_mut() {
+                    filter_search_results(&state, &user_id, room_events).await?;
+                }
                 response.search_categories = search_results.search_categories;
             },
             Err(e) => {
@@ -110,3 +114,42 @@ pub async fn post(
 
     Ok(Json(response))
 }
+
+/// Drop results the user may not see and the context events around the rest
+/// they may not see
+async fn filter_search_results(
+    state: &AppState,
+    user_id: &str,
+    room_events: &mut RoomEventsResults,
+) -> Result<(), StatusCode> {
+    let visibility_error = |e: RepositoryError| {
+        error!("Failed to apply event visibility: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+
+    let results: Vec<_> = room_events.results.iter().map(|result| result.result.clone()).collect();
+    let visible = state
+        .event_visibility
+        .visible_to_client(user_id, &results)
+        .await
+        .map_err(visibility_error)?;
+    let mut visible = visible.into_iter();
+    room_events.results.retain(|_| visible.next().unwrap_or(false));
+
+    for result in &mut room_events.results {
+        if let Some(context) = result.context.as_mut() {
+            context.events_before = state
+                .event_visibility
+                .filter_events_for_client(user_id, std::mem::take(&mut context.events_before))
+                .await
+                .map_err(visibility_error)?;
+            context.events_after = state
+                .event_visibility
+                .filter_events_for_client(user_id, std::mem::take(&mut context.events_after))
+                .await
+                .map_err(visibility_error)?;
+        }
+    }
+
+    Ok(())
+}
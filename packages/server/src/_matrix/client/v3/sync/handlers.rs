@@ -337,6 +337,10 @@ pub async fn build_joined_room_response(
             get_room_timeline_events(state, room_id, None, since_ts).await?
         };
 
+    // Drop events the user may not see at their point in history
+    let timeline_events =
+        state.event_visibility.filter_events_for_client(user_id, timeline_events).await?;
+
     // Get state events
     let state_events = get_room_state_events(state, room_id).await?;
 
@@ -428,12 +432,16 @@ pub async fn build_invited_room_response(
 pub async fn build_left_room_response(
     state: &AppState,
     room_id: &str,
-    _user_id: &str,
+    user_id: &str,
 ) -> Result<LeftRoomResponse, Box<dyn std::error::Error + Send + Sync>> {
     // Get limited state for left rooms
     let state_events = get_room_state_events(state, room_id).await?;
     let timeline_events = get_room_timeline_events(state, room_id, None, None).await?;
 
+    // Events after the user left stay hidden unless the room is world readable
+    let timeline_events =
+        state.event_visibility.filter_events_for_client(user_id, timeline_events).await?;
+
     Ok(LeftRoomResponse {
         state: StateResponse {
             events: convert_events_to_matrix_format(state_events),
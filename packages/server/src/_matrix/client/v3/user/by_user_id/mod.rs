@@ -1,5 +1,4 @@
 pub mod account_data;
 pub mod filter;
 pub mod openid;
-pub mod report;
 pub mod rooms;
@@ -11,7 +11,6 @@ use std::sync::Arc;
 use tracing::{debug, error, info, warn};
 
 use crate::state::AppState;
-use crate::federation::authorization::matches_server_pattern;
 use matryx_entity::types::Room;
 use matryx_surrealdb::repository::{EventRepository, MembershipRepository, RoomRepository};
 
@@ -166,23 +165,10 @@ pub async fn get(
     }
 
     // Validate Server ACL restrictions per Matrix spec Section 20
-    let acl_validation_result = validate_server_acl(&state, &room, &x_matrix_auth.origin).await;
-    match acl_validation_result {
-        Ok(true) => {
-            debug!("Server {} passed ACL validation for room {}", x_matrix_auth.origin, room_id);
-        },
-        Ok(false) => {
-            warn!(
-                "Server {} denied by room ACL for backfill in room {}",
-                x_matrix_auth.origin, room_id
-            );
-            return Err(StatusCode::FORBIDDEN);
-        },
-        Err(e) => {
-            error!("Failed to validate server ACL for {}: {}", x_matrix_auth.origin, e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
-        },
-    }
+    state
+        .server_acls
+        .check_request(&room_id, &x_matrix_auth.origin, "backfill")
+        .await?;
 
     // Additional room-specific validation based on room properties
     validate_room_access_for_backfill(&room, &x_matrix_auth.origin)?;
@@ -242,12 +228,19 @@ pub async fn get(
     }
 
     // Perform backfill traversal
-    let backfilled_events = backfill_events(&state, &room_id, &query.v, query.limit as usize)
-        .await
-        .map_err(|e| {
-            error!("Failed to backfill events: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
+    let backfilled_events = backfill_events(
+        &state,
+        &room_id,
+        &room.room_version,
+        &x_matrix_auth.origin,
+        &query.v,
+        query.limit as usize,
+    )
+    .await
+    .map_err(|e| {
+        error!("Failed to backfill events: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
 
     let response = json!({
         "origin": state.homeserver_name,
@@ -266,9 +259,14 @@ pub async fn get(
 }
 
 /// Backfill events using breadth-first traversal
+///
+/// Events the requesting server may not see are sent redacted, so it can
+/// still follow the DAG through them.
 async fn backfill_events(
     state: &AppState,
     room_id: &str,
+    room_version: &str,
+    origin: &str,
     starting_event_ids: &[String],
     limit: usize,
 ) -> Result<Vec<Value>, Box<dyn std::error::Error + Send + Sync>> {
@@ -287,14 +285,19 @@ async fn backfill_events(
         // Fetch current batch of events
         let event_repo = EventRepository::new(state.db.clone());
         let events = event_repo.get_events_by_ids_for_backfill(&current_batch, room_id).await?;
+        let visible = state.event_visibility.visible_to_server(origin, &events).await?;
 
         // Add events to result
-        for event in events {
+        for (event, visible) in events.into_iter().zip(visible) {
             if result_events.len() >= limit {
                 break;
             }
 
-            let event_json = serde_json::to_value(&event)?;
+            let event_json = if visible {
+                serde_json::to_value(&event)?
+            } else {
+                state.event_signer.redact_event(&event, room_version)?
+            };
             result_events.push(event_json);
 
             // Add prev_events to the next batch if not visited
@@ -327,109 +330,6 @@ async fn backfill_events(
     Ok(result_events)
 }
 
-/// Validate server against room's Server ACL restrictions per Matrix specification
-///
-/// Implements Matrix Server ACL validation according to the Matrix Server-Server API
-/// specification Section 20. Server ACLs enable room administrators to control
-/// which homeservers can participate in federation for specific rooms.
-async fn validate_server_acl(
-    state: &AppState,
-    room: &Room,
-    requesting_server: &str,
-) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
-    // Query for m.room.server_acl state event in the room
-    let event_repo = EventRepository::new(state.db.clone());
-    let acl_event = event_repo.get_server_acl_event(&room.room_id).await?;
-
-    let acl_event = match acl_event {
-        Some(event) => event,
-        None => {
-            // No Server ACL configured - allow all servers
-            debug!(
-                "No Server ACL found for room {}, allowing server {}",
-                room.room_id, requesting_server
-            );
-            return Ok(true);
-        },
-    };
-    let acl_content = acl_event
-        .content
-        .as_object()
-        .ok_or("Server ACL content is not an object")?;
-
-    // Get allow and deny lists from the ACL content
-    let allow_list = acl_content
-        .get("allow")
-        .and_then(|v| v.as_array())
-        .map(|arr| arr.iter().filter_map(|v| v.as_str()).collect::<Vec<&str>>())
-        .unwrap_or_else(|| vec!["*"]); // Default: allow all
-
-    let deny_list = acl_content
-        .get("deny")
-        .and_then(|v| v.as_array())
-        .map(|arr| arr.iter().filter_map(|v| v.as_str()).collect::<Vec<&str>>())
-        .unwrap_or_default();
-
-    let allow_ip_literals = acl_content
-        .get("allow_ip_literals")
-        .and_then(|v| v.as_bool())
-        .unwrap_or(true);
-
-    // Check if server is an IP literal and IP literals are denied
-    if !allow_ip_literals && is_ip_literal(requesting_server) {
-        debug!(
-            "Server {} is IP literal and IP literals are not allowed in room {}",
-            requesting_server, room.room_id
-        );
-        return Ok(false);
-    }
-
-    // Check deny list first (takes precedence)
-    for deny_pattern in &deny_list {
-        if matches_server_pattern(requesting_server, deny_pattern) {
-            debug!(
-                "Server {} matches deny pattern '{}' in room {}",
-                requesting_server, deny_pattern, room.room_id
-            );
-            return Ok(false);
-        }
-    }
-
-    // Check allow list
-    for allow_pattern in &allow_list {
-        if matches_server_pattern(requesting_server, allow_pattern) {
-            debug!(
-                "Server {} matches allow pattern '{}' in room {}",
-                requesting_server, allow_pattern, room.room_id
-            );
-            return Ok(true);
-        }
-    }
-
-    // If no allow patterns matched, deny access
-    debug!(
-        "Server {} does not match any allow patterns in room {}",
-        requesting_server, room.room_id
-    );
-    Ok(false)
-}
-
-/// Check if a server name is an IP literal
-fn is_ip_literal(server_name: &str) -> bool {
-    use std::net::IpAddr;
-
-    // Split server name to handle port numbers
-    let host_part = server_name.split(':').next().unwrap_or(server_name);
-
-    // Remove brackets for IPv6 literals
-    let host_clean = host_part.trim_start_matches('[').trim_end_matches(']');
-
-    // Try parsing as IP address
-    host_clean.parse::<IpAddr>().is_ok()
-}
-
-
-
 /// Additional room access validation for backfill requests
 ///
 /// Performs Matrix specification-compliant validation of room access
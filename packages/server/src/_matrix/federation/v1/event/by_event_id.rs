@@ -113,6 +113,12 @@ pub async fn get(
         },
     };
 
+    // Servers denied by the room's server ACL may not take part in it
+    state
+        .server_acls
+        .check_request(&validated_event.room_id, &x_matrix_auth.origin, "event")
+        .await?;
+
     // Check if requesting server has permission to access this event
     let membership_repo = Arc::new(MembershipRepository::new(state.db.clone()));
     let has_users = membership_repo
@@ -145,6 +151,23 @@ pub async fn get(
         return Err(StatusCode::FORBIDDEN);
     }
 
+    // The history visibility at the event must also admit the server
+    let visible = state
+        .event_visibility
+        .can_server_see_event(&x_matrix_auth.origin, &validated_event)
+        .await
+        .map_err(|e| {
+            error!("Failed to apply event visibility: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if !visible {
+        warn!(
+            "Event {} in room {} is not visible to server {}",
+            event_id, validated_event.room_id, x_matrix_auth.origin
+        );
+        return Err(StatusCode::FORBIDDEN);
+    }
+
     // Convert validated Event to PDU format for federation response
     let pdu = PDU {
         event_id: validated_event.event_id.clone(),
@@ -1,4 +1,5 @@
 use axum::{
+    Extension,
     Json,
     extract::{Path, State},
     http::StatusCode,
@@ -8,6 +9,7 @@ use std::collections::{HashSet, VecDeque};
 use tracing::{debug, error, info, warn};
 
 use crate::AppState;
+use crate::auth::MatrixAuth;
 use matryx_surrealdb::repository::EventRepository;
 
 /// GET /_matrix/federation/v1/event_auth/{roomId}/{eventId}
@@ -17,6 +19,7 @@ use matryx_surrealdb::repository::EventRepository;
 /// following the DAG structure recursively.
 pub async fn get(
     State(state): State<AppState>,
+    Extension(auth): Extension<MatrixAuth>,
     Path((room_id, event_id)): Path<(String, String)>,
 ) -> Result<Json<Value>, StatusCode> {
     info!("Fetching auth chain for event {} in room {}", event_id, room_id);
@@ -27,6 +30,14 @@ pub async fn get(
         return Err(StatusCode::BAD_REQUEST);
     }
 
+    let origin = auth.server_name().ok_or_else(|| {
+        warn!("Auth chain request without server authentication");
+        StatusCode::UNAUTHORIZED
+    })?;
+
+    // Servers denied by the room's server ACL may not take part in it
+    state.server_acls.check_request(&room_id, origin, "event_auth").await?;
+
     let event_repo = EventRepository::new(state.db.clone());
 
     // Get the target event first
@@ -254,6 +254,9 @@ pub async fn post(
             StatusCode::NOT_FOUND
         })?;
 
+    // Servers denied by the room's server ACL may not take part in it
+    state.server_acls.check_request(&room_id, &x_matrix_auth.origin, "get_missing_events").await?;
+
     // Validate room version for get_missing_events compatibility
     validate_room_version_compatibility(&room)?;
 
@@ -330,7 +333,6 @@ pub async fn post(
         &room,
         &x_matrix_auth.origin,
         missing_events,
-        has_users,
     )
     .await
     .map_err(|e| {
@@ -448,17 +450,15 @@ async fn get_missing_events_traversal(
 /// Filter events based on room history visibility settings and advanced security features
 ///
 /// This function implements comprehensive server-side event visibility filtering with:
-/// - Basic history visibility filtering (world_readable, shared, invited, joined)
+/// - History visibility filtering against the state at each event
 /// - GDPR compliance: filters events from erased users (right to be forgotten)
 /// - Partial state protection: filters remote events when room is in partial state
-/// - Per-event membership verification: checks server membership at event depth
 ///
 /// # Arguments
 /// * `state` - Application state for database access
 /// * `room` - The room containing the events
 /// * `requesting_server` - The origin server making the request
 /// * `events` - The list of events to filter
-/// * `server_has_users` - Whether the requesting server has users in the room currently
 ///
 /// # Returns
 /// Filtered list of events the requesting server is authorized to see
@@ -467,59 +467,21 @@ async fn filter_events_for_server(
     room: &Room,
     requesting_server: &str,
     events: Vec<PDU>,
-    server_has_users: bool,
 ) -> Result<Vec<PDU>, Box<dyn std::error::Error + Send + Sync>> {
-    // Check room history visibility setting
-    let history_visibility = room.history_visibility.as_deref().unwrap_or("shared");
-
-    // Basic history visibility filtering
-    let events = match history_visibility {
-        "world_readable" => {
-            // World-readable rooms: all events visible to everyone
-            debug!(
-                "Room {} is world_readable, allowing all {} events for server {}",
-                room.room_id,
-                events.len(),
-                requesting_server
-            );
-            events
-        },
-        "shared" | "invited" | "joined" => {
-            // Non-world-readable rooms: only servers with users can see events
-            if server_has_users {
-                debug!(
-                    "Server {} has users in room {} (history_visibility: {}), allowing all {} events",
-                    requesting_server,
-                    room.room_id,
-                    history_visibility,
-                    events.len()
-                );
-                events
-            } else {
-                // Server has no users in room, deny all events
-                warn!(
-                    "Server {} has no users in room {} (history_visibility: {}), filtering all events",
-                    requesting_server,
-                    room.room_id,
-                    history_visibility
-                );
-                return Ok(Vec::new());
-            }
-        },
-        _ => {
-            // Unknown history_visibility setting, default to restrictive behavior
-            warn!(
-                "Unknown history_visibility '{}' for room {}, applying restrictive filtering",
-                history_visibility,
-                room.room_id
-            );
-            if server_has_users {
-                events
-            } else {
-                return Ok(Vec::new());
-            }
-        },
-    };
+    let events_len = events.len();
+
+    // History visibility and the server's membership at each event
+    let visible_events =
+        state.event_visibility.filter_events_for_server(requesting_server, events).await?;
+    if visible_events.len() < events_len {
+        debug!(
+            "Filtered {} events not visible to server {} in room {}",
+            events_len - visible_events.len(),
+            requesting_server,
+            room.room_id
+        );
+    }
+    let events = visible_events;
 
     // FEATURE A: Erased senders filtering (GDPR compliance)
     // Check if any event senders have been erased and filter those events
@@ -598,36 +560,5 @@ async fn filter_events_for_server(
         filtered_events = partial_state_filtered_events;
     }
 
-    // FEATURE C: Per-event membership verification
-    // Verify server had members at each event's specific depth for more precise access control
-    let membership_repo = MembershipRepository::new(state.db.clone());
-    let mut depth_filtered_events = Vec::new();
-    let mut depth_filtered_count = 0;
-
-    for event in filtered_events {
-        // Check if requesting server had users at this event's depth
-        let had_members_at_depth = membership_repo
-            .get_server_membership_at_depth(&room.room_id, requesting_server, event.depth)
-            .await?;
-
-        if !had_members_at_depth {
-            debug!(
-                "Server {} had no members at depth {} for event {}, filtering",
-                requesting_server, event.depth, event.event_id
-            );
-            depth_filtered_count += 1;
-            continue;
-        }
-
-        depth_filtered_events.push(event);
-    }
-
-    if depth_filtered_count > 0 {
-        info!(
-            "Filtered {} events based on per-depth membership verification in room {} for server {}",
-            depth_filtered_count, room.room_id, requesting_server
-        );
-    }
-
-    Ok(depth_filtered_events)
+    Ok(filtered_events)
 }
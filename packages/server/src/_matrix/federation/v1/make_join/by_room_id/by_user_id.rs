@@ -128,6 +128,15 @@ pub async fn get(
         return Err(StatusCode::BAD_REQUEST);
     }
 
+    // Users banned by a subscribed policy list cannot join
+    if state.policy_lists.is_user_banned(&user_id) {
+        warn!("User {} is banned by a policy list, refusing make_join", user_id);
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    // Rooms shut down by an admin cannot be joined through this server
+    state.room_shutdown.check_not_blocked(&room_id).await?;
+
     // Get room information from database
     let room_repo = Arc::new(RoomRepository::new(state.db.clone()));
     let membership_repo = Arc::new(MembershipRepository::new(state.db.clone()));
@@ -145,6 +154,9 @@ pub async fn get(
             StatusCode::NOT_FOUND
         })?;
 
+    // Servers denied by the room's server ACL may not take part in it
+    state.server_acls.check_request(&room_id, &x_matrix_auth.origin, "make_join").await?;
+
     // Validate room allows federation joins from this origin server
     if !validate_federation_join_allowed(&room, &x_matrix_auth.origin) {
         warn!(
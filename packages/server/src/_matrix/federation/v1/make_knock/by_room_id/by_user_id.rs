@@ -211,8 +211,9 @@ pub async fn get(
     }
 
     // Check server ACLs
-    let server_allowed = room_repo
-        .check_server_acls(&room_id, &x_matrix_auth.origin)
+    let server_allowed = state
+        .server_acls
+        .allows_request(&room_id, &x_matrix_auth.origin, "make_knock")
         .await
         .map_err(|e| {
             error!("Failed to check server ACLs: {}", e);
@@ -134,6 +134,9 @@ pub async fn get(
             StatusCode::NOT_FOUND
         })?;
 
+    // Servers denied by the room's server ACL may not take part in it
+    state.server_acls.check_request(&room_id, &x_matrix_auth.origin, "make_leave").await?;
+
     // Validate room allows leave events from this federation server
     if !validate_federation_leave_allowed(&room, &x_matrix_auth.origin) {
         warn!(
@@ -198,11 +198,15 @@ pub async fn get(
         x_matrix_auth.origin
     );
 
-    // Convert to federation response format
+    // Convert to federation response format, hiding rooms banned by a policy list
+    let policy_rules = state.policy_lists.rules();
     let federation_response = PublicRoomsResponse {
         chunk: public_rooms_response
             .chunk
             .into_iter()
+            .filter(|entry| {
+                !policy_rules.is_room_banned(&entry.room_id, entry.canonical_alias.as_deref())
+            })
             .map(|entry| PublishedRoom {
                 room_id: entry.room_id,
                 name: entry.name,
@@ -281,11 +285,15 @@ pub async fn post(
         x_matrix_auth.origin
     );
 
-    // Convert to federation response format
+    // Convert to federation response format, hiding rooms banned by a policy list
+    let policy_rules = state.policy_lists.rules();
     let federation_response = PublicRoomsResponse {
         chunk: public_rooms_response
             .chunk
             .into_iter()
+            .filter(|entry| {
+                !policy_rules.is_room_banned(&entry.room_id, entry.canonical_alias.as_deref())
+            })
             .map(|entry| PublishedRoom {
                 room_id: entry.room_id,
                 name: entry.name,
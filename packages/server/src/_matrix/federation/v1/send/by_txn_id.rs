@@ -13,7 +13,9 @@ use tracing::{debug, error, info, warn};
 use crate::auth::MatrixAuthError;
 use crate::federation::client::FederationClient;
 use crate::federation::pdu_validator::{PduValidator, PduValidatorParams, ValidationResult};
+use crate::modules::{EventRuling, SpamCheck};
 use crate::state::AppState;
+use matryx_entity::types::Event;
 use matryx_surrealdb::repository::{
     DeviceRepository, EventRepository, FederationRepository, KeyServerRepository,
     MembershipRepository, RoomRepository, TransactionRepository, UserRepository,
@@ -537,12 +539,65 @@ pub async fn put(
 
         debug!("Processing PDU: {}", event_id);
 
-        match pdu_validator.validate_pdu(pdu, &x_matrix_auth.origin).await {
+        // PDUs for rooms whose server ACL denies the origin are rejected outright
+        if let Some(room_id) = pdu.get("room_id").and_then(|v| v.as_str()) {
+            match state.server_acls.allows_request(room_id, &x_matrix_auth.origin, "send").await {
+                Ok(true) => {},
+                Ok(false) => {
+                    pdu_results.insert(
+                        event_id.to_string(),
+                        json!({
+                            "error": "Origin server is denied by the room's server ACL"
+                        }),
+                    );
+                    continue;
+                },
+                Err(e) => {
+                    error!("Failed to check server ACL for PDU {}: {}", event_id, e);
+                    pdu_results.insert(
+                        event_id.to_string(),
+                        json!({
+                            "error": format!("Server ACL check failed: {}", e)
+                        }),
+                    );
+                    continue;
+                },
+            }
+        }
+
+        // Events spam-checker and third-party-rules modules reject are
+        // soft-failed rather than dropped, so later events referencing them
+        // still resolve
+        let validation = match pdu_validator.validate_pdu(pdu, &x_matrix_auth.origin).await {
+            Ok(ValidationResult::Valid(mut event)) => {
+                match check_event_with_modules(&state, &event).await {
+                    Some(reason) => {
+                        event.soft_failed = Some(true);
+                        Ok(ValidationResult::SoftFailed {
+                            event,
+                            reason: format!("Rejected by module: {}", reason),
+                        })
+                    },
+                    None => Ok(ValidationResult::Valid(event)),
+                }
+            },
+            other => other,
+        };
+
+        match validation {
             Ok(ValidationResult::Valid(event)) => {
                 // Store valid event in database
                 match event_repo.create(&event).await {
                     Ok(stored_event) => {
                         info!("Successfully processed PDU: {}", event.event_id);
+                        state
+                            .server_acls
+                            .observe_state_event(
+                                &event.room_id,
+                                &event.event_type,
+                                event.state_key.as_deref(),
+                            )
+                            .await;
                         processed_events.push(stored_event);
                         pdu_results.insert(event.event_id, json!({}));
                     },
@@ -733,6 +788,24 @@ async fn cache_transaction_result(
     }
 }
 
+/// The reason a module rejects a federated event, if any
+///
+/// Third-party rules cannot rewrite federated events, which are already
+/// signed, so a replacement ruling is treated as allowing the event.
+async fn check_event_with_modules(state: &AppState, event: &Event) -> Option<String> {
+    let event_json = serde_json::to_value(event).ok()?;
+    if let SpamCheck::Deny { reason } = state.modules.check_event_for_spam(&event_json).await {
+        return Some(reason);
+    }
+    if event.state_key.is_some()
+        && let EventRuling::Deny { reason } =
+            state.modules.check_state_event_allowed(&event_json).await
+    {
+        return Some(reason);
+    }
+    None
+}
+
 /// Process typing EDU from federation
 ///
 /// Handles m.typing ephemeral events that indicate users typing in rooms.
@@ -778,6 +851,10 @@ async fn process_typing_edu(
         return Err(format!("Invalid user origin for typing EDU: {}", user_id).into());
     }
 
+    if !state.server_acls.allows_request(room_id, origin_server, "send_typing").await? {
+        return Ok(());
+    }
+
     // Verify user is in the room
     let room_repo = RoomRepository::new(state.db.clone());
 
@@ -826,6 +903,10 @@ async fn process_receipt_edu(
         let room_receipts_obj =
             room_receipts.as_object().ok_or("Room receipts must be an object")?;
 
+        if !state.server_acls.allows_request(room_id, origin_server, "send_receipt").await? {
+            continue;
+        }
+
         // Process each receipt type per Matrix 1.4 specification
         for (receipt_type, receipt_data) in room_receipts_obj {
             // Only process supported receipt types
@@ -164,6 +164,15 @@ pub async fn put(
         return Err(StatusCode::BAD_REQUEST);
     }
 
+    // Users banned by a subscribed policy list cannot join
+    if state.policy_lists.is_user_banned(sender) {
+        warn!("User {} is banned by a policy list, refusing send_join", sender);
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    // Rooms shut down by an admin cannot be joined through this server
+    state.room_shutdown.check_not_blocked(&room_id).await?;
+
     // Validate that event_id in path matches the event
     let payload_event_id = payload.get("event_id").and_then(|v| v.as_str()).unwrap_or("");
 
@@ -186,6 +195,9 @@ pub async fn put(
             StatusCode::NOT_FOUND
         })?;
 
+    // Servers denied by the room's server ACL may not take part in it
+    state.server_acls.check_request(&room_id, &x_matrix_auth.origin, "send_join").await?;
+
     // Validate room state and membership constraints per Matrix specification
     if room.room_version.as_str() < "6" {
         debug!("Processing join for room {} with version {}", room_id, room.room_version);
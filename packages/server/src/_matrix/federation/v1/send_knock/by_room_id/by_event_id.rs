@@ -287,13 +287,14 @@ pub async fn put(
     }
 
     // Check server ACLs
-    let server_allowed =
-        check_server_acls(&state, &room_id, &x_matrix_auth.origin)
-            .await
-            .map_err(|e| {
-                error!("Failed to check server ACLs: {}", e);
-                StatusCode::INTERNAL_SERVER_ERROR
-            })?;
+    let server_allowed = state
+        .server_acls
+        .allows_request(&room_id, &x_matrix_auth.origin, "send_knock")
+        .await
+        .map_err(|e| {
+            error!("Failed to check server ACLs: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
 
     if !server_allowed {
         warn!("Server {} is denied by room ACLs", x_matrix_auth.origin);
@@ -453,20 +454,6 @@ async fn check_room_allows_knocking(
     Ok(allows_knocking)
 }
 
-/// Check if server is allowed by room ACLs using repository
-async fn check_server_acls(
-    state: &AppState,
-    room_id: &str,
-    server_name: &str,
-) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
-    let room_repo = Arc::new(RoomRepository::new(state.db.clone()));
-    let server_allowed = room_repo
-        .check_server_acls(room_id, server_name)
-        .await
-        .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?;
-    Ok(server_allowed)
-}
-
 /// Validate event signatures
 async fn validate_event_signatures(
     state: &AppState,
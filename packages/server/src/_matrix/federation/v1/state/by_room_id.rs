@@ -129,6 +129,9 @@ pub async fn get(
             StatusCode::NOT_FOUND
         })?;
 
+    // Servers denied by the room's server ACL may not take part in it
+    state.server_acls.check_request(&room_id, &x_matrix_auth.origin, "state").await?;
+
     // Check if requesting server has permission to view room state
     let has_permission = check_state_permission(&state, &room, &x_matrix_auth.origin)
         .await
@@ -131,6 +131,9 @@ pub async fn get(
             StatusCode::NOT_FOUND
         })?;
 
+    // Servers denied by the room's server ACL may not take part in it
+    state.server_acls.check_request(&room_id, &x_matrix_auth.origin, "state_ids").await?;
+
     // Log room validation for audit trail
     debug!("Validating state request for room {} from server {}", room_id, x_matrix_auth.origin);
     debug!("Room found: {} (version: {})", room.room_id, &room.room_version);
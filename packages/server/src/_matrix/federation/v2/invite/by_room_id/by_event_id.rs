@@ -11,6 +11,7 @@ use tracing::{debug, error, info, warn};
 
 use crate::federation::client::FederationClient;
 use crate::federation::pdu_validator::{PduValidator, PduValidatorParams, ValidationResult};
+use crate::modules::SpamCheck;
 use crate::state::AppState;
 use matryx_entity::types::{Event, Membership, MembershipState};
 use matryx_surrealdb::repository::{
@@ -405,6 +406,54 @@ pub async fn put(
         return Err(StatusCode::BAD_REQUEST);
     }
 
+    // Invites from users, or into rooms, banned by a subscribed policy list are refused
+    if state.policy_lists.is_user_banned(sender) || state.policy_lists.is_room_banned(&room_id) {
+        warn!("Refusing invite from {} to room {} banned by a policy list", sender, room_id);
+        return Ok(Json(json!({
+            "errcode": "M_FORBIDDEN",
+            "error": "The inviter or room is banned on this server"
+        })));
+    }
+
+    // Local users cannot be invited into rooms shut down by an admin
+    let room_blocked = state.room_shutdown.is_room_blocked(&room_id).await.map_err(|e| {
+        error!("Failed to check whether room {} is blocked: {}", room_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if room_blocked {
+        warn!("Refusing invite from {} to shut down room {}", sender, room_id);
+        return Ok(Json(json!({
+            "errcode": "M_FORBIDDEN",
+            "error": "This room has been shut down on this server"
+        })));
+    }
+
+    // Servers denied by the room's server ACL may not take part in it
+    let server_allowed = state
+        .server_acls
+        .allows_request(&room_id, &x_matrix_auth.origin, "invite")
+        .await
+        .map_err(|e| {
+            error!("Failed to check server ACLs: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if !server_allowed {
+        return Ok(Json(json!({
+            "errcode": "M_FORBIDDEN",
+            "error": "Your server is not permitted to invite into this room"
+        })));
+    }
+
+    if let SpamCheck::Deny { reason } =
+        state.modules.user_may_invite(sender, state_key, &room_id).await
+    {
+        warn!("Refusing invite from {} to room {}: {}", sender, room_id, reason);
+        return Ok(Json(json!({
+            "errcode": "M_FORBIDDEN",
+            "error": reason
+        })));
+    }
+
     // Validate that event_id in path matches the event
     let payload_event_id = event.get("event_id").and_then(|v| v.as_str()).unwrap_or("");
     if payload_event_id != event_id {
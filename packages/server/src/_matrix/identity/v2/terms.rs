@@ -0,0 +1,42 @@
+use axum::{extract::State, http::StatusCode, response::Json};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tracing::{error, info};
+
+use crate::auth::AuthenticatedUser;
+use crate::state::AppState;
+
+#[derive(Deserialize)]
+pub struct AcceptTermsRequest {
+    /// URLs of the policy documents the user agreed to
+    pub user_accepts: Vec<String>,
+}
+
+/// GET /_matrix/identity/v2/terms
+///
+/// The current version of every policy, with a document URL per language.
+pub async fn get(State(state): State<AppState>) -> Json<Value> {
+    Json(state.consent.policies())
+}
+
+/// POST /_matrix/identity/v2/terms
+///
+/// Accept the policies whose current documents are listed. URLs of unknown
+/// or outdated documents are ignored.
+pub async fn post(
+    State(state): State<AppState>,
+    auth_user: AuthenticatedUser,
+    Json(request): Json<AcceptTermsRequest>,
+) -> Result<Json<Value>, StatusCode> {
+    let accepted = state
+        .consent
+        .accept_urls(&auth_user.user_id, &request.user_accepts)
+        .await
+        .map_err(|e| {
+            error!("Failed to record policy consent of {}: {}", auth_user.user_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    info!("{} accepted {} policies", auth_user.user_id, accepted);
+    Ok(Json(json!({})))
+}
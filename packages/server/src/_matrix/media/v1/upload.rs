@@ -1,4 +1,5 @@
 use crate::AppState;
+use crate::modules::{MediaFileInfo, SpamCheck};
 use axum::{
     body::Bytes,
     extract::{Multipart, State},
@@ -70,6 +71,17 @@ pub async fn post(
 
     let file_bytes = file_data.ok_or(StatusCode::BAD_REQUEST)?;
 
+    let file = MediaFileInfo {
+        uploader: &token_info.user_id,
+        content_type: &content_type,
+        upload_name: upload_name.as_deref(),
+        content: &file_bytes,
+    };
+    if let SpamCheck::Deny { reason } = state.modules.check_media_file_for_spam(file).await {
+        tracing::warn!("Upload rejected by spam checker: {}", reason);
+        return Err(StatusCode::FORBIDDEN);
+    }
+
     // Create MediaService instance
     let media_repo = Arc::new(MediaRepository::new(state.db.clone()));
     let room_repo = Arc::new(RoomRepository::new(state.db.clone()));
@@ -1,4 +1,5 @@
 use crate::AppState;
+use crate::modules::{MediaFileInfo, SpamCheck};
 use axum::{
     Json,
     body::Bytes,
@@ -46,6 +47,17 @@ pub async fn put(
         .and_then(|h| h.to_str().ok())
         .unwrap_or("application/octet-stream");
 
+    let file = MediaFileInfo {
+        uploader: &token_info.user_id,
+        content_type,
+        upload_name: None,
+        content: &body,
+    };
+    if let SpamCheck::Deny { reason } = state.modules.check_media_file_for_spam(file).await {
+        tracing::warn!("Upload rejected by spam checker: {}", reason);
+        return Err(StatusCode::FORBIDDEN);
+    }
+
     // Create MediaService instance
     let media_repo = Arc::new(MediaRepository::new(state.db.clone()));
     let room_repo = Arc::new(RoomRepository::new(state.db.clone()));
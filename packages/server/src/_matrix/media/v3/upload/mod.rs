@@ -10,6 +10,7 @@ use serde::{Deserialize, Serialize};
 use tracing::{debug, warn};
 
 use crate::AppState;
+use crate::modules::{MediaFileInfo, SpamCheck};
 use chrono::Utc;
 use matryx_surrealdb::repository::{
     media::MediaRepository, media_service::MediaService, membership::MembershipRepository,
@@ -159,6 +160,17 @@ pub async fn upload_media(
         return Err(StatusCode::UNSUPPORTED_MEDIA_TYPE);
     }
 
+    let file = MediaFileInfo {
+        uploader: &token_info.user_id,
+        content_type: &content_type,
+        upload_name: upload_name.as_deref(),
+        content: &file_bytes,
+    };
+    if let SpamCheck::Deny { reason } = state.modules.check_media_file_for_spam(file).await {
+        warn!("Upload rejected by spam checker: {}", reason);
+        return Err(StatusCode::FORBIDDEN);
+    }
+
     // Generate appropriate file extension based on content type
     let file_extension = get_file_extension(&content_type);
     debug!("Uploading {} file with extension: {}", content_type, file_extension);
@@ -0,0 +1,28 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Html,
+};
+use tracing::{error, warn};
+
+use crate::state::AppState;
+
+/// GET /_matrix/static/consent/{policy}/{version}/{language}
+///
+/// Serve the current version of a policy document in one language.
+pub async fn get(
+    State(state): State<AppState>,
+    Path((policy, version, language)): Path<(String, String, String)>,
+) -> Result<Html<String>, StatusCode> {
+    let Some(path) = state.consent.document_path(&policy, &version, &language) else {
+        warn!("No {} document for policy {} version {}", language, policy, version);
+        return Err(StatusCode::NOT_FOUND);
+    };
+
+    let document = tokio::fs::read_to_string(path).await.map_err(|e| {
+        error!("Failed to read policy document {}: {}", path, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Html(document))
+}
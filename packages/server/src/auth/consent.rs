@@ -0,0 +1,341 @@
+//! Terms of service and privacy policy consent
+//!
+//! Policies are versioned documents from `ConsentConfig`, served as static
+//! pages and presented at registration as the `m.login.terms` stage. Every
+//! acceptance is kept per user, policy and version. Once a policy's version
+//! changes, users who have not accepted it are sent a server notice and,
+//! unless configured otherwise, may not send, join or create anything until
+//! they accept it through the `/terms` endpoint.
+
+use std::time::Duration;
+
+use moka::future::Cache;
+use serde_json::{Value, json};
+use surrealdb::{Surreal, engine::any::Any};
+use tracing::{info, warn};
+
+use crate::config::{ConsentConfig, ConsentPolicyConfig};
+use crate::moderation::user_restrictions::suspension_allows;
+use crate::server_notices::ServerNoticesManager;
+use crate::state::AppState;
+use matryx_entity::ServerNoticeContent;
+use matryx_surrealdb::repository::{UserConsent, UserConsentRepository, error::RepositoryError};
+
+/// The UIA stage in which a registering user accepts the policies
+pub const TERMS_STAGE: &str = "m.login.terms";
+
+/// Path of the endpoint through which users accept updated policies
+pub const TERMS_PATH: &str = "/_matrix/identity/v2/terms";
+
+/// Tracks which policy versions users accepted and asks them to accept
+/// new ones
+pub struct ConsentService {
+    repo: UserConsentRepository,
+    config: &'static ConsentConfig,
+    base_url: String,
+    notices: ServerNoticesManager,
+    /// Policies each user has yet to accept in their current version
+    outstanding: Cache<String, Vec<String>>,
+    /// The `<policy>:<version>` pairs each user was last notified about
+    notified: Cache<String, Vec<String>>,
+}
+
+impl ConsentService {
+    pub fn new(
+        db: Surreal<Any>,
+        config: &'static ConsentConfig,
+        homeserver_name: String,
+        base_url: String,
+    ) -> Self {
+        Self {
+            repo: UserConsentRepository::new(db),
+            config,
+            base_url,
+            notices: ServerNoticesManager::new(homeserver_name),
+            outstanding: Cache::builder()
+                .max_capacity(100_000)
+                .time_to_live(Duration::from_secs(60))
+                .build(),
+            notified: Cache::builder().max_capacity(100_000).build(),
+        }
+    }
+
+    /// Whether any policy is configured; consent is not tracked otherwise
+    pub fn is_enabled(&self) -> bool {
+        !self.config.policies.is_empty()
+    }
+
+    /// Whether users with policies to accept are blocked from changing shared
+    /// state
+    pub fn blocks_until_consented(&self) -> bool {
+        self.config.block_until_consented
+    }
+
+    /// Public URL of a policy document
+    pub fn document_url(&self, policy: &str, version: &str, language: &str) -> String {
+        format!("{}/_matrix/static/consent/{}/{}/{}", self.base_url, policy, version, language)
+    }
+
+    /// The file a policy page is served from, for current versions only
+    pub fn document_path(&self, policy: &str, version: &str, language: &str) -> Option<&str> {
+        self.config
+            .policies
+            .iter()
+            .find(|p| p.name == policy && p.version == version)
+            .and_then(|p| p.documents.get(language))
+            .map(|document| document.path.as_str())
+    }
+
+    /// The current policies, shaped as `m.login.terms` params and as the
+    /// `/terms` response
+    pub fn policies(&self) -> Value {
+        let mut policies = serde_json::Map::new();
+        for policy in &self.config.policies {
+            let mut entry = serde_json::Map::new();
+            entry.insert("version".to_string(), json!(policy.version));
+            for (language, document) in &policy.documents {
+                entry.insert(
+                    language.clone(),
+                    json!({
+                        "name": document.name,
+                        "url": self.document_url(&policy.name, &policy.version, language),
+                    }),
+                );
+            }
+            policies.insert(policy.name.clone(), Value::Object(entry));
+        }
+        json!({ "policies": policies })
+    }
+
+    /// Names of the policies whose current version the user has not accepted
+    pub async fn outstanding_policies(
+        &self,
+        user_id: &str,
+    ) -> Result<Vec<String>, RepositoryError> {
+        if let Some(outstanding) = self.outstanding.get(user_id).await {
+            return Ok(outstanding);
+        }
+
+        let consents = self.repo.get_user_consents(user_id).await?;
+        let outstanding = outstanding_policies(&self.config.policies, &consents);
+        self.outstanding.insert(user_id.to_string(), outstanding.clone()).await;
+        Ok(outstanding)
+    }
+
+    /// A page for the first policy the user has yet to accept
+    pub fn consent_uri(&self, outstanding: &[String]) -> String {
+        self.config
+            .policies
+            .iter()
+            .filter(|policy| outstanding.contains(&policy.name))
+            .find_map(|policy| {
+                preferred_language(policy)
+                    .map(|language| self.document_url(&policy.name, &policy.version, language))
+            })
+            .unwrap_or_else(|| format!("{}{}", self.base_url, TERMS_PATH))
+    }
+
+    /// Record that the user accepted the current version of every policy
+    pub async fn accept_all(&self, user_id: &str) -> Result<(), RepositoryError> {
+        for policy in &self.config.policies {
+            self.repo.record_consent(user_id, &policy.name, &policy.version).await?;
+        }
+        self.outstanding.invalidate(user_id).await;
+        info!("{} accepted all current policies", user_id);
+        Ok(())
+    }
+
+    /// Record acceptance of the policies whose current documents are listed,
+    /// returning how many were accepted; other URLs are ignored
+    pub async fn accept_urls(
+        &self,
+        user_id: &str,
+        urls: &[String],
+    ) -> Result<usize, RepositoryError> {
+        let mut accepted = 0;
+        for policy in &self.config.policies {
+            let listed = policy.documents.keys().any(|language| {
+                let url = self.document_url(&policy.name, &policy.version, language);
+                urls.contains(&url)
+            });
+            if listed {
+                self.repo.record_consent(user_id, &policy.name, &policy.version).await?;
+                info!("{} accepted {} version {}", user_id, policy.name, policy.version);
+                accepted += 1;
+            }
+        }
+        self.outstanding.invalidate(user_id).await;
+        Ok(accepted)
+    }
+
+    /// Ask the user to accept their outstanding policies, at most once for
+    /// each set of versions; failures are logged and retried on a later
+    /// request
+    pub async fn notify(&self, state: &AppState, user_id: &str, outstanding: &[String]) {
+        let versions: Vec<String> = self
+            .config
+            .policies
+            .iter()
+            .filter(|policy| outstanding.contains(&policy.name))
+            .map(|policy| format!("{}:{}", policy.name, policy.version))
+            .collect();
+
+        let notified = match self.notified.get(user_id).await {
+            Some(notified) => notified,
+            None => match self.repo.get_notified_versions(user_id).await {
+                Ok(notified) => notified,
+                Err(e) => {
+                    warn!("Failed to load consent notices sent to {}: {}", user_id, e);
+                    return;
+                },
+            },
+        };
+        if notified == versions {
+            self.notified.insert(user_id.to_string(), notified).await;
+            return;
+        }
+
+        let consent_uri = self.consent_uri(outstanding);
+        let body = match &self.config.server_notice {
+            Some(notice) => notice.replace("%(consent_uri)s", &consent_uri),
+            None => format!(
+                "Our policies have changed. Please review and accept them to keep using \
+                 this server: {}",
+                consent_uri
+            ),
+        };
+        let mut notice = ServerNoticeContent::new(body, "com.maxtryx.consent".to_string());
+        notice
+            .additional_data
+            .insert("consent_uri".to_string(), json!(consent_uri));
+
+        if let Err(e) = self.notices.send_server_notice(user_id, notice, state).await {
+            warn!("Failed to send consent notice to {}: {}", user_id, e);
+            return;
+        }
+        if let Err(e) = self.repo.set_notified_versions(user_id, &versions).await {
+            warn!("Failed to record consent notice sent to {}: {}", user_id, e);
+        }
+        self.notified.insert(user_id.to_string(), versions).await;
+        info!("Sent consent notice to {}", user_id);
+    }
+}
+
+/// Policies whose current version is missing from a user's acceptances
+fn outstanding_policies(policies: &[ConsentPolicyConfig], consents: &[UserConsent]) -> Vec<String> {
+    policies
+        .iter()
+        .filter(|policy| {
+            !consents
+                .iter()
+                .any(|consent| consent.policy == policy.name && consent.version == policy.version)
+        })
+        .map(|policy| policy.name.clone())
+        .collect()
+}
+
+/// English when the policy has it, otherwise the first language by code
+fn preferred_language(policy: &ConsentPolicyConfig) -> Option<&str> {
+    if policy.documents.contains_key("en") {
+        return Some("en");
+    }
+    policy.documents.keys().min().map(String::as_str)
+}
+
+/// Whether a user with policies to accept may make a request
+///
+/// Like a suspension, outstanding consent only blocks requests that change
+/// what other users see, so clients can still sync, set up encryption and
+/// show the consent notice.
+pub fn consent_allows(method: &str, path: &str) -> bool {
+    suspension_allows(method, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PolicyDocumentConfig;
+    use std::collections::HashMap;
+
+    fn policy(name: &str, version: &str) -> ConsentPolicyConfig {
+        let mut documents = HashMap::new();
+        documents.insert(
+            "fr".to_string(),
+            PolicyDocumentConfig {
+                name: name.to_string(),
+                path: format!("/{}.fr.html", name),
+            },
+        );
+        ConsentPolicyConfig {
+            name: name.to_string(),
+            version: version.to_string(),
+            documents,
+        }
+    }
+
+    fn consent(policy: &str, version: &str) -> UserConsent {
+        UserConsent {
+            user_id: "@alice:example.com".to_string(),
+            policy: policy.to_string(),
+            version: version.to_string(),
+            accepted_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_new_policy_versions_need_consent_again() {
+        let policies = vec![
+            policy("terms_of_service", "2.0"),
+            policy("privacy_policy", "1.0"),
+        ];
+        let consents = vec![
+            consent("terms_of_service", "1.0"),
+            consent("privacy_policy", "1.0"),
+        ];
+
+        assert_eq!(outstanding_policies(&policies, &consents), vec!["terms_of_service"]);
+        assert_eq!(outstanding_policies(&policies, &[]).len(), 2);
+
+        let accepted = vec![
+            consent("terms_of_service", "2.0"),
+            consent("privacy_policy", "1.0"),
+        ];
+        assert!(outstanding_policies(&policies, &accepted).is_empty());
+    }
+
+    #[test]
+    fn test_falls_back_to_any_language_without_english() {
+        let mut terms = policy("terms_of_service", "1.0");
+        assert_eq!(preferred_language(&terms), Some("fr"));
+
+        terms.documents.insert(
+            "en".to_string(),
+            PolicyDocumentConfig {
+                name: "Terms".to_string(),
+                path: "/terms.html".to_string(),
+            },
+        );
+        assert_eq!(preferred_language(&terms), Some("en"));
+    }
+
+    #[test]
+    fn test_users_without_consent_may_not_change_shared_state() {
+        assert!(consent_allows("GET", "/_matrix/client/v3/sync"));
+        assert!(consent_allows("POST", "/_matrix/identity/v2/terms"));
+        assert!(consent_allows("POST", "/_matrix/client/v3/logout"));
+        assert!(consent_allows("POST", "/_matrix/client/v3/user/@u:b/filter"));
+        assert!(consent_allows("POST", "/_matrix/client/v3/keys/query"));
+        assert!(consent_allows("POST", "/_matrix/client/v3/keys/upload"));
+        assert!(consent_allows("POST", "/_matrix/client/v3/search"));
+        assert!(consent_allows("POST", "/_matrix/client/v3/rooms/!a:b/receipt/m.read/$e"));
+        assert!(consent_allows("POST", "/_matrix/client/v3/rooms/!a:b/read_markers"));
+
+        assert!(!consent_allows("PUT", "/_matrix/client/v3/rooms/!a:b/send/m.room.message/1"));
+        assert!(!consent_allows("PUT", "/_matrix/client/v3/rooms/!a:b/state/m.room.name/"));
+        assert!(!consent_allows("POST", "/_matrix/client/v3/join/#room:b"));
+        assert!(!consent_allows("POST", "/_matrix/client/v3/rooms/!a:b/invite"));
+        assert!(!consent_allows("POST", "/_matrix/client/v3/createRoom"));
+        assert!(!consent_allows("PUT", "/_matrix/client/v3/profile/@u:b/displayname"));
+        assert!(!consent_allows("POST", "/_matrix/media/v3/upload"));
+    }
+}
@@ -9,12 +9,14 @@ use axum::{
 use tracing::{debug, info, warn};
 
 use crate::auth::{
+    consent::consent_allows,
     errors::MatrixAuthError,
     matrix_auth::{MatrixAuth, MatrixServerAuth},
     session_service::MatrixSessionService,
     x_matrix_parser::parse_x_matrix_header,
 };
 use crate::error::matrix_errors::MatrixError;
+use crate::moderation::user_restrictions::{lock_allows, suspension_allows};
 use crate::state::AppState;
 
 use std::net::IpAddr;
@@ -102,9 +104,62 @@ pub async fn auth_middleware(
         return MatrixError::Unauthorized.into_response();
     }
 
+    // Servers banned by a subscribed policy list may not federate with us
+    if let Some(server_name) = matrix_auth.server_name()
+        && app_state.policy_lists.is_server_banned(server_name)
+    {
+        tracing::warn!("Rejecting federation request from policy-banned server {}", server_name);
+        return MatrixError::Forbidden.into_response();
+    }
+
     // Check resource-based authorization per endpoint
     let request_uri = request.uri().path();
     let request_method = request.method().as_str();
+
+    // Locked accounts may only log out, suspended ones may only read
+    if let MatrixAuth::User(access_token) = &matrix_auth {
+        let user_id = &access_token.user_id;
+        let restrictions = match app_state.user_restrictions.restrictions(user_id).await {
+            Ok(restrictions) => restrictions,
+            Err(e) => {
+                tracing::error!("Failed to load restrictions for {}: {}", user_id, e);
+                return MatrixError::Unknown.into_response();
+            },
+        };
+        if restrictions.locked && !lock_allows(request_uri) {
+            debug!("Rejecting {} {} from locked user {}", request_method, request_uri, user_id);
+            return MatrixError::UserLocked.into_response();
+        }
+        if restrictions.suspended && !suspension_allows(request_method, request_uri) {
+            debug!("Rejecting {} {} from suspended user {}", request_method, request_uri, user_id);
+            return MatrixError::UserSuspended.into_response();
+        }
+
+        // Users who have not accepted the current policies are told so once
+        // and, unless configured otherwise, may only read until they accept
+        if app_state.consent.is_enabled() {
+            let outstanding = match app_state.consent.outstanding_policies(user_id).await {
+                Ok(outstanding) => outstanding,
+                Err(e) => {
+                    tracing::error!("Failed to load policy consent for {}: {}", user_id, e);
+                    return MatrixError::Unknown.into_response();
+                },
+            };
+            if !outstanding.is_empty() {
+                app_state.consent.notify(&app_state, user_id, &outstanding).await;
+                if app_state.consent.blocks_until_consented() &&
+                    !consent_allows(request_method, request_uri)
+                {
+                    debug!(
+                        "Rejecting {} {} from {} pending policy consent",
+                        request_method, request_uri, user_id
+                    );
+                    let consent_uri = app_state.consent.consent_uri(&outstanding);
+                    return MatrixError::ConsentNotGiven { consent_uri }.into_response();
+                }
+            }
+        }
+    }
     
     // Check endpoint-specific permissions
     if !check_endpoint_authorization(&matrix_auth, request_uri, request_method, &app_state).await {
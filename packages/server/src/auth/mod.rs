@@ -1,5 +1,6 @@
 pub mod authenticated_user;
 pub mod captcha;
+pub mod consent;
 pub mod errors;
 pub mod matrix_auth;
 pub mod middleware;
@@ -6,17 +6,19 @@ use surrealdb::engine::any::Any;
 use tokio::task::JoinHandle;
 use tokio::time::{interval, Duration as TokioDuration};
 use tracing::{info, warn};
-use uuid::Uuid;
 
 use crate::auth::MatrixAuthError;
 use matryx_surrealdb::repository::{
-    AuthRepository,
+    AuthRepository, RegistrationRepository,
     uia::{UiaRepository, UiaSession},
 };
 
 // Re-export UiaFlow for use by other modules in the server crate
 pub use matryx_surrealdb::repository::uia::UiaFlow;
 
+/// Session auth data key holding the registration token a session reserved
+pub const REGISTRATION_TOKEN_AUTH_DATA: &str = "registration_token";
+
 /// UIA authentication request
 #[derive(Debug, Deserialize)]
 pub struct UiaAuthRequest {
@@ -103,6 +105,7 @@ pub struct UiaService {
     session_lifetime: Duration,
     require_captcha: bool,
     require_email_verification: bool,
+    require_registration_token: bool,
     cleanup_task: Option<JoinHandle<()>>,
 }
 
@@ -120,6 +123,7 @@ impl UiaService {
             session_lifetime: Duration::minutes(config.session_lifetime_minutes),
             require_captcha: config.require_captcha,
             require_email_verification: config.require_email_verification,
+            require_registration_token: config.require_registration_token,
             cleanup_task: None,
         }
     }
@@ -132,38 +136,15 @@ impl UiaService {
         flows: Vec<UiaFlow>,
         params: HashMap<String, serde_json::Value>,
     ) -> Result<UiaSession, MatrixAuthError> {
-        let session_id = format!("uia_{}", Uuid::new_v4());
-        let now = Utc::now();
-
-        let _session = self
+        let session = self
             .uia_repo
-            .create_session(
-                user_id,
-                device_id,
-                flows.clone(),
-                params.clone(),
-                self.session_lifetime,
-            )
+            .create_session(user_id, device_id, flows, params, self.session_lifetime)
             .await
             .map_err(|e| {
                 MatrixAuthError::DatabaseError(format!("Failed to create UIA session: {}", e))
             })?;
 
-        let session = UiaSession {
-            session_id: session_id.clone(),
-            user_id: user_id.map(|s| s.to_string()),
-            device_id: device_id.map(|s| s.to_string()),
-            flows,
-            completed_stages: Vec::new(),
-            current_stage: None,
-            auth_data: HashMap::new(),
-            params,
-            created_at: now,
-            expires_at: now + self.session_lifetime,
-            completed: false,
-        };
-
-        info!("Started UIA session: {} for user: {:?}", session_id, user_id);
+        info!("Started UIA session: {} for user: {:?}", session.session_id, user_id);
         Ok(session)
     }
 
@@ -305,6 +286,8 @@ impl UiaService {
     /// Clean up expired UIA sessions
     #[allow(dead_code)]
     pub async fn cleanup_expired_sessions(&self) -> Result<u64, MatrixAuthError> {
+        Self::release_abandoned_registration_tokens(&self.uia_repo).await;
+
         let count = self.uia_repo.cleanup_expired_sessions().await.map_err(|e| {
             MatrixAuthError::DatabaseError(format!("Failed to cleanup UIA sessions: {}", e))
         })?;
@@ -327,6 +310,9 @@ impl UiaService {
             "m.login.recaptcha" => self.process_captcha_auth(session, auth).await,
             "m.login.email.identity" => self.process_email_auth(session, auth).await,
             "m.login.terms" => self.process_terms_auth(session, auth).await,
+            "m.login.registration_token" => {
+                self.process_registration_token_auth(session, auth).await
+            },
             _ => {
                 warn!("Unknown UIA auth type: {}", auth.auth_type);
                 Err(MatrixAuthError::InvalidXMatrixFormat)
@@ -428,6 +414,12 @@ impl UiaService {
         session: &mut UiaSession,
         _auth: UiaAuth,
     ) -> Result<bool, MatrixAuthError> {
+        // Acceptance only counts for policies the session presented
+        if !session.params.contains_key("m.login.terms") {
+            warn!("No policies were presented to UIA session: {}", session.session_id);
+            return Ok(false);
+        }
+
         // Terms acceptance is typically just confirming the user has agreed
         session.completed_stages.push("m.login.terms".to_string());
         session
@@ -437,6 +429,43 @@ impl UiaService {
         Ok(true)
     }
 
+    /// Process registration token stage, holding a pending use of the token
+    /// until the registration completes or the session expires
+    async fn process_registration_token_auth(
+        &self,
+        session: &mut UiaSession,
+        auth: UiaAuth,
+    ) -> Result<bool, MatrixAuthError> {
+        let token = auth
+            .auth_data
+            .get("token")
+            .and_then(|token| token.as_str())
+            .ok_or(MatrixAuthError::InvalidXMatrixFormat)?;
+
+        // A session holds at most one use, however often the stage is retried
+        if session.auth_data.contains_key(REGISTRATION_TOKEN_AUTH_DATA) {
+            return Ok(true);
+        }
+
+        let registration_repo = RegistrationRepository::new(self.uia_repo.get_db().clone());
+        let reserved = registration_repo.reserve_registration_token(token).await.map_err(|e| {
+            MatrixAuthError::DatabaseError(format!("Failed to reserve registration token: {}", e))
+        })?;
+
+        if reserved {
+            session.completed_stages.push("m.login.registration_token".to_string());
+            session.auth_data.insert(
+                REGISTRATION_TOKEN_AUTH_DATA.to_string(),
+                serde_json::Value::String(token.to_string()),
+            );
+            info!("Registration token accepted for UIA session: {}", session.session_id);
+            Ok(true)
+        } else {
+            warn!("Invalid registration token for UIA session: {}", session.session_id);
+            Ok(false)
+        }
+    }
+
     /// Check if any flow is completed
     fn is_flow_completed(&self, session: &UiaSession) -> bool {
         session
@@ -585,6 +614,49 @@ impl UiaService {
         }
     }
 
+    /// UIA flows offered for registration; empty when registration needs no
+    /// interactive stages
+    pub fn registration_flows(&self, require_terms: bool) -> Vec<UiaFlow> {
+        let mut stages = Vec::new();
+        if self.require_registration_token {
+            stages.push("m.login.registration_token".to_string());
+        }
+        if require_terms {
+            stages.push("m.login.terms".to_string());
+        }
+
+        if stages.is_empty() {
+            return Vec::new();
+        }
+        vec![UiaFlow { stages }]
+    }
+
+    /// Give back the token uses held by expired sessions whose registration
+    /// never completed; completed registrations delete their session
+    async fn release_abandoned_registration_tokens(repo: &UiaRepository<Any>) {
+        let sessions = match repo.get_expired_sessions().await {
+            Ok(sessions) => sessions,
+            Err(e) => {
+                warn!("Failed to list expired UIA sessions: {}", e);
+                return;
+            },
+        };
+
+        let registration_repo = RegistrationRepository::new(repo.get_db().clone());
+        for session in sessions {
+            let Some(token) = session
+                .auth_data
+                .get(REGISTRATION_TOKEN_AUTH_DATA)
+                .and_then(|token| token.as_str())
+            else {
+                continue;
+            };
+            if let Err(e) = registration_repo.release_registration_token(token).await {
+                warn!("Failed to release registration token use: {}", e);
+            }
+        }
+    }
+
     /// Get default UIA flows for different operations
     fn get_default_flows(&self) -> Vec<UiaFlow> {
         let mut flows = vec![UiaFlow { stages: vec!["m.login.password".to_string()] }];
@@ -622,7 +694,8 @@ impl UiaService {
             
             loop {
                 interval.tick().await;
-                
+
+                Self::release_abandoned_registration_tokens(&repo).await;
                 match repo.cleanup_expired_sessions().await {
                     Ok(count) => {
                         tracing::info!("UIA cleanup: deleted {} expired sessions", count);
@@ -660,6 +733,7 @@ pub struct UiaConfig {
     pub session_lifetime_minutes: i64,
     pub require_captcha: bool,
     pub require_email_verification: bool,
+    pub require_registration_token: bool,
     pub cleanup_interval_hours: u64,
 }
 
@@ -678,6 +752,10 @@ impl UiaConfig {
                 .ok()
                 .and_then(|s| s.parse().ok())
                 .unwrap_or(false),
+            require_registration_token: std::env::var("UIA_REQUIRE_REGISTRATION_TOKEN")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(false),
             cleanup_interval_hours: std::env::var("UIA_CLEANUP_INTERVAL_HOURS")
                 .ok()
                 .and_then(|s| s.parse().ok())
@@ -5,6 +5,7 @@ use crate::auth::captcha::CaptchaConfig;
 use crate::middleware::TransactionConfig;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::env;
 use std::sync::OnceLock;
 use tracing::{error, info, warn};
@@ -177,6 +178,203 @@ impl MediaConfig {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyListConfig {
+    /// Local user whose room power is used to apply policy list bans
+    pub auto_ban_moderator: Option<String>,
+    /// How often policy list rules are reloaded, in seconds
+    pub refresh_interval_secs: u64,
+}
+
+impl Default for PolicyListConfig {
+    fn default() -> Self {
+        Self { auto_ban_moderator: None, refresh_interval_secs: 60 }
+    }
+}
+
+impl PolicyListConfig {
+    pub fn from_env() -> Self {
+        Self {
+            auto_ban_moderator: env::var("POLICY_LIST_AUTO_BAN_MODERATOR")
+                .ok()
+                .filter(|s| !s.is_empty()),
+            refresh_interval_secs: env::var("POLICY_LIST_REFRESH_INTERVAL_SECS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(60),
+        }
+    }
+}
+
+/// Message retention (MSC1763) settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionConfig {
+    /// Whether the purge job deletes expired history
+    pub enabled: bool,
+    /// Lifetime in milliseconds for rooms without `m.room.retention`
+    pub default_max_lifetime_ms: Option<u64>,
+    /// Minimum lifetime in milliseconds for rooms without `m.room.retention`
+    pub default_min_lifetime_ms: Option<u64>,
+    /// How often the purge job runs, in seconds
+    pub purge_interval_secs: u64,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            default_max_lifetime_ms: None,
+            default_min_lifetime_ms: None,
+            purge_interval_secs: 24 * 60 * 60,
+        }
+    }
+}
+
+impl RetentionConfig {
+    pub fn from_env() -> Self {
+        Self {
+            enabled: env::var("RETENTION_ENABLED")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(false),
+            default_max_lifetime_ms: env::var("RETENTION_DEFAULT_MAX_LIFETIME_MS")
+                .ok()
+                .and_then(|s| s.parse().ok()),
+            default_min_lifetime_ms: env::var("RETENTION_DEFAULT_MIN_LIFETIME_MS")
+                .ok()
+                .and_then(|s| s.parse().ok()),
+            purge_interval_secs: env::var("RETENTION_PURGE_INTERVAL_SECS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(24 * 60 * 60),
+        }
+    }
+}
+
+/// Where content reports are routed once stored
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportRoutingConfig {
+    /// Room the server notices user posts every new report into
+    pub moderation_room_id: Option<String>,
+    /// Whether room-scoped reports also go to the room's moderators who opted in
+    pub notify_room_moderators: bool,
+    /// Whether new reports are emailed to `admin_email`
+    pub email_admin: bool,
+}
+
+impl Default for ReportRoutingConfig {
+    fn default() -> Self {
+        Self {
+            moderation_room_id: None,
+            notify_room_moderators: true,
+            email_admin: true,
+        }
+    }
+}
+
+impl ReportRoutingConfig {
+    pub fn from_env() -> Self {
+        Self {
+            moderation_room_id: env::var("REPORT_MODERATION_ROOM_ID")
+                .ok()
+                .filter(|s| !s.is_empty()),
+            notify_room_moderators: env::var("REPORT_NOTIFY_ROOM_MODERATORS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(true),
+            email_admin: env::var("REPORT_EMAIL_ADMIN")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(true),
+        }
+    }
+}
+
+/// A policy document in one language
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyDocumentConfig {
+    /// Title shown to users, such as "Privacy Policy"
+    pub name: String,
+    /// HTML file served as the policy page
+    pub path: String,
+}
+
+/// A versioned policy users must accept, such as the terms of service
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsentPolicyConfig {
+    /// Identifier clients see, such as `privacy_policy`
+    pub name: String,
+    /// The current version; raising it asks every user to accept again
+    pub version: String,
+    /// The policy text keyed by language code
+    pub documents: HashMap<String, PolicyDocumentConfig>,
+}
+
+/// Terms of service and privacy policy consent (`m.login.terms`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConsentConfig {
+    /// Policies to accept at registration; consent tracking is off when empty
+    pub policies: Vec<ConsentPolicyConfig>,
+    /// Whether users who have not accepted the current versions are blocked
+    /// from sending, joining and creating rooms until they do
+    pub block_until_consented: bool,
+    /// Server notice sent once a user has policy versions to accept, with
+    /// `%(consent_uri)s` replaced by the policy page
+    pub server_notice: Option<String>,
+}
+
+impl ConsentConfig {
+    /// Read `CONSENT_POLICIES`, a JSON array of policies, and the options
+    /// around it
+    pub fn from_env() -> Self {
+        let policies = match env::var("CONSENT_POLICIES") {
+            Ok(policies) => match serde_json::from_str(&policies) {
+                Ok(policies) => policies,
+                Err(e) => {
+                    error!("CONSENT_POLICIES is not a valid JSON array of policies: {}", e);
+                    panic!("Invalid configuration: malformed CONSENT_POLICIES");
+                },
+            },
+            Err(_) => Vec::new(),
+        };
+
+        Self {
+            policies,
+            block_until_consented: env::var("CONSENT_BLOCK_UNTIL_CONSENTED")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(true),
+            server_notice: env::var("CONSENT_SERVER_NOTICE").ok().filter(|s| !s.is_empty()),
+        }
+    }
+}
+
+/// An in-process module to load at startup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModuleConfig {
+    /// Name the module's factory is registered under
+    pub module: String,
+    /// Module-specific settings passed to the factory
+    #[serde(default)]
+    pub config: serde_json::Value,
+}
+
+impl ModuleConfig {
+    /// Parse the `MODULES` environment variable, a JSON array of module entries
+    pub fn from_env() -> Vec<Self> {
+        let Ok(modules) = env::var("MODULES") else {
+            return Vec::new();
+        };
+        match serde_json::from_str(&modules) {
+            Ok(modules) => modules,
+            Err(e) => {
+                error!("MODULES is not a valid JSON array of modules: {}", e);
+                panic!("Invalid configuration: malformed MODULES");
+            },
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerConfig {
     pub homeserver_name: String,
@@ -195,6 +393,11 @@ pub struct ServerConfig {
     pub rate_limiting: RateLimitConfig,
     pub captcha: CaptchaConfig,
     pub media_config: MediaConfig,
+    pub policy_lists: PolicyListConfig,
+    pub retention: RetentionConfig,
+    pub reports: ReportRoutingConfig,
+    pub consent: ConsentConfig,
+    pub modules: Vec<ModuleConfig>,
 }
 
 impl ServerConfig {
@@ -308,6 +511,11 @@ impl ServerConfig {
                 rate_limiting: RateLimitConfig::from_env(),
                 captcha: CaptchaConfig::from_env(),
                 media_config: MediaConfig::from_env(),
+                policy_lists: PolicyListConfig::from_env(),
+                retention: RetentionConfig::from_env(),
+                reports: ReportRoutingConfig::from_env(),
+                consent: ConsentConfig::from_env(),
+                modules: ModuleConfig::from_env(),
             };
 
             // Enhanced validation - secure by default
@@ -331,14 +539,18 @@ impl ServerConfig {
                 }
 
                 // 2. Validate homeserver name format
-                if !crate::utils::matrix_identifiers::is_valid_server_name(&config.homeserver_name)
-                {
-                    error!("Invalid server name format: {}", config.homeserver_name);
-                    panic!("Invalid configuration: malformed server name");
-                }
+                let server_name = match matryx_entity::types::ServerName::parse(
+                    &config.homeserver_name,
+                ) {
+                    Ok(server_name) => server_name,
+                    Err(e) => {
+                        error!("Invalid server name format: {}: {}", config.homeserver_name, e);
+                        panic!("Invalid configuration: malformed server name");
+                    },
+                };
 
                 // 3. Validate homeserver name is not an IP literal
-                if crate::utils::matrix_identifiers::is_ip_literal(&config.homeserver_name) {
+                if server_name.is_ip_literal() {
                     error!("homeserver_name cannot be an IP address: {}", config.homeserver_name);
                     panic!("Invalid configuration: homeserver_name must be a domain name (FQDN)");
                 }
@@ -26,6 +26,8 @@ pub enum MatrixError {
     UserSuspended,
     #[error("User account deactivated")]
     UserDeactivated,
+    #[error("The current terms and policies have not been accepted")]
+    ConsentNotGiven { consent_uri: String },
 
     // Request Format
     #[error("Invalid JSON in request")]
@@ -110,14 +112,22 @@ impl MatrixError {
                 (StatusCode::UNAUTHORIZED, "M_UNAUTHORIZED", self.to_string(), None)
             },
             MatrixError::UserLocked => {
-                (StatusCode::UNAUTHORIZED, "M_USER_LOCKED", self.to_string(), None)
+                // Locked sessions stay valid so clients keep their state (MSC3939)
+                let mut extra = HashMap::new();
+                extra.insert("soft_logout".to_string(), Value::Bool(true));
+                (StatusCode::UNAUTHORIZED, "M_USER_LOCKED", self.to_string(), Some(extra))
             },
             MatrixError::UserSuspended => {
-                (StatusCode::UNAUTHORIZED, "M_USER_SUSPENDED", self.to_string(), None)
+                (StatusCode::FORBIDDEN, "M_USER_SUSPENDED", self.to_string(), None)
             },
             MatrixError::UserDeactivated => {
                 (StatusCode::FORBIDDEN, "M_USER_DEACTIVATED", self.to_string(), None)
             },
+            MatrixError::ConsentNotGiven { consent_uri } => {
+                let mut extra = HashMap::new();
+                extra.insert("consent_uri".to_string(), Value::String(consent_uri.clone()));
+                (StatusCode::FORBIDDEN, "M_CONSENT_NOT_GIVEN", self.to_string(), Some(extra))
+            },
             MatrixError::BadJson => (StatusCode::BAD_REQUEST, "M_BAD_JSON", self.to_string(), None),
             MatrixError::NotJson => (StatusCode::BAD_REQUEST, "M_NOT_JSON", self.to_string(), None),
             MatrixError::NotFound => (StatusCode::NOT_FOUND, "M_NOT_FOUND", self.to_string(), None),
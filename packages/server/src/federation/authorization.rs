@@ -1729,26 +1729,6 @@ impl ServerAcl {
     }
 }
 
-/// Server pattern matching for Matrix server ACL rules
-/// Spec-compliant glob pattern matching supporting * and ? wildcards
-/// Spec: ./spec/server/20-server-acls.md
-pub fn matches_server_pattern(server_name: &str, pattern: &str) -> bool {
-    match globset::Glob::new(pattern) {
-        Ok(glob) => {
-            let matcher = glob.compile_matcher();
-            matcher.is_match(server_name)
-        }
-        Err(e) => {
-            // Invalid glob pattern - fall back to literal match
-            tracing::warn!(
-                "Invalid Server ACL pattern '{}': {}. Using literal match.",
-                pattern, e
-            );
-            server_name == pattern
-        }
-    }
-}
-
 /// Check if a server name is an IP literal
 pub fn is_ip_literal(server_name: &str) -> bool {
     // Extract the host part (before port if present)
@@ -1769,110 +1749,12 @@ pub fn is_ip_literal(server_name: &str) -> bool {
     ipv6_host.parse::<std::net::Ipv6Addr>().is_ok()
 }
 
-/// Validate server against ACL rules
-/// Returns true if server is allowed, false if denied
-pub fn validate_server_against_acl(server_name: &str, acl: &ServerAcl) -> bool {
-    // If server is an IP literal and IP literals are not allowed, deny
-    if is_ip_literal(server_name) && !acl.allow_ip_literals {
-        debug!("Server {} denied: IP literals not allowed", server_name);
-        return false;
-    }
-
-    // Check deny rules first (they take precedence)
-    for deny_pattern in &acl.deny {
-        if matches_server_pattern(server_name, deny_pattern) {
-            debug!("Server {} denied by pattern: {}", server_name, deny_pattern);
-            return false;
-        }
-    }
-
-    // If no explicit allow rules, default to allow
-    if acl.allow.is_empty() {
-        return true;
-    }
-
-    // Check allow rules
-    for allow_pattern in &acl.allow {
-        if matches_server_pattern(server_name, allow_pattern) {
-            debug!("Server {} allowed by pattern: {}", server_name, allow_pattern);
-            return true;
-        }
-    }
-
-    // Not explicitly allowed
-    debug!("Server {} not matched by any allow pattern", server_name);
-    false
-}
-
-impl AuthorizationEngine {
-    /// Validate federation join against server ACL rules
-    /// Validates that a remote server is allowed to join events for a room
-    pub async fn validate_federation_join_allowed(
-        &self,
-        room: &matryx_entity::types::Room,
-        origin_server: &str,
-    ) -> AuthorizationResult<bool> {
-        debug!(
-            "Validating federation join for server {} in room {} (version {})",
-            origin_server, room.room_id, room.room_version
-        );
-
-        // Basic validation: reject if room version is too old for federation
-        if room.room_version.as_str() < "1" {
-            warn!("Rejecting federation join: room version {} too old", room.room_version);
-            return Ok(false);
-        }
-
-        // Get server ACL state event for the room
-        match self
-            .event_repo
-            .get_room_state_by_type_and_key(&room.room_id, "m.room.server_acl", "")
-            .await
-        {
-            Ok(Some(acl_event)) => {
-                // Parse ACL from event content
-                let content_value = serde_json::to_value(&acl_event.content).map_err(|e| {
-                    AuthorizationError::InvalidContent {
-                        reason: format!("Failed to serialize event content: {}", e),
-                    }
-                })?;
-                match serde_json::from_value::<ServerAcl>(content_value) {
-                    Ok(acl) => {
-                        let allowed = validate_server_against_acl(origin_server, &acl);
-                        debug!("Server ACL validation for {}: {}", origin_server, allowed);
-                        Ok(allowed)
-                    },
-                    Err(e) => {
-                        warn!("Failed to parse server ACL for room {}: {}", room.room_id, e);
-                        // Default to allow if ACL is malformed
-                        Ok(true)
-                    },
-                }
-            },
-            Ok(None) => {
-                // No server ACL configured, allow by default
-                debug!(
-                    "No server ACL configured for room {}, allowing server {}",
-                    room.room_id, origin_server
-                );
-                Ok(true)
-            },
-            Err(e) => {
-                warn!("Failed to retrieve server ACL for room {}: {}", room.room_id, e);
-                // Default to allow on database error to avoid breaking federation
-                Ok(true)
-            },
-        }
-    }
-}
-
 /// Federation join validation according to Matrix specification (Synchronous version)
 /// Validates that a remote server is allowed to join events for a room
 ///
 /// NOTE: This is a synchronous version that performs basic validation only.
-/// For complete server ACL checking with state event parsing, use:
-/// `AuthorizationEngine::validate_federation_join_allowed()` which implements
-/// full Matrix specification compliance including m.room.server_acl state events.
+/// Server ACLs are enforced separately through
+/// [`crate::federation::server_acl::ServerAclService::check_request`].
 pub fn validate_federation_join_allowed(
     room: &matryx_entity::types::Room,
     origin_server: &str,
@@ -1898,15 +1780,13 @@ pub fn validate_federation_join_allowed(
 
     // IP literal basic validation if needed
     if is_ip_literal(origin_server) {
-        // In absence of ACL configuration, we allow IP literals by default
-        // Full ACL checking requires the async method with state event access
+        // IP literals are allowed here; the room's ACL may still deny them
         debug!("IP literal server {} allowed (basic validation)", origin_server);
     }
 
-    // Allow join by default - production systems should use
-    // AuthorizationEngine::validate_federation_join_allowed() for complete ACL checking
+    // Allow join by default - the room's server ACL is checked by ServerAclService
     debug!(
-        "Federation join allowed for {} (basic validation - use async method for full ACL)",
+        "Federation join allowed for {} (basic validation - ACL checked separately)",
         origin_server
     );
     true
@@ -10,6 +10,7 @@ pub mod media_client;
 pub mod membership_federation;
 pub mod outbound_queue;
 pub mod pdu_validator;
+pub mod server_acl;
 pub mod server_discovery;
 pub mod well_known_client;
 
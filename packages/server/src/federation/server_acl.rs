@@ -0,0 +1,287 @@
+//! Room server ACL enforcement for federation
+//!
+//! Every federation endpoint that acts on a room checks the requesting
+//! server against the room's `m.room.server_acl`, and outbound sends skip
+//! denied destinations. ACLs are compiled once per room and cached until
+//! the room's ACL state changes.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use moka::future::Cache;
+use regex::{RegexSet, RegexSetBuilder};
+use surrealdb::{Surreal, engine::any::Any};
+use tracing::{debug, error, warn};
+
+use crate::federation::authorization::ServerAcl;
+use crate::metrics::server_acl_metrics::ServerAclMetrics;
+use crate::utils::matrix_glob::glob_to_regex;
+use matryx_surrealdb::repository::{EventRepository, error::RepositoryError};
+
+/// A room's server ACL with its patterns compiled
+pub struct CompiledServerAcl {
+    allow: RegexSet,
+    deny: RegexSet,
+    allow_ip_literals: bool,
+}
+
+/// Upper bound on the compiled size of each pattern list
+const ACL_SIZE_LIMIT: usize = 10 * (1 << 20);
+
+impl CompiledServerAcl {
+    pub fn new(acl: &ServerAcl) -> Self {
+        Self::with_size_limit(acl, ACL_SIZE_LIMIT)
+    }
+
+    /// Compile the ACL, denying every server when its patterns do not
+    /// compile rather than dropping any of them
+    fn with_size_limit(acl: &ServerAcl, size_limit: usize) -> Self {
+        let compiled = compile_patterns(&acl.allow, size_limit)
+            .and_then(|allow| Ok((allow, compile_patterns(&acl.deny, size_limit)?)));
+        match compiled {
+            Ok((allow, deny)) => Self {
+                allow,
+                deny,
+                allow_ip_literals: acl.allow_ip_literals,
+            },
+            Err(e) => {
+                warn!("Failed to compile server ACL, denying every server: {}", e);
+                Self {
+                    allow: RegexSet::empty(),
+                    deny: RegexSet::empty(),
+                    allow_ip_literals: false,
+                }
+            },
+        }
+    }
+
+    /// Whether the ACL lets a server participate in the room
+    ///
+    /// Patterns match the host only, so a port on the server name is ignored.
+    pub fn allows(&self, server_name: &str) -> bool {
+        let host = server_host(server_name);
+        if !self.allow_ip_literals && is_ip_host(host) {
+            return false;
+        }
+        if self.deny.is_match(host) {
+            return false;
+        }
+        self.allow.is_match(host)
+    }
+}
+
+fn compile_patterns(patterns: &[String], size_limit: usize) -> Result<RegexSet, regex::Error> {
+    RegexSetBuilder::new(patterns.iter().map(|pattern| glob_to_regex(pattern)))
+        .size_limit(size_limit)
+        .build()
+}
+
+/// The server name without its port, keeping bracketed IPv6 literals whole
+fn server_host(server_name: &str) -> &str {
+    if server_name.starts_with('[') {
+        return server_name.find(']').map_or(server_name, |end| &server_name[..=end]);
+    }
+    server_name.rsplit_once(':').map_or(server_name, |(host, _)| host)
+}
+
+fn is_ip_host(host: &str) -> bool {
+    let host = host.strip_prefix('[').and_then(|h| h.strip_suffix(']')).unwrap_or(host);
+    host.parse::<std::net::IpAddr>().is_ok()
+}
+
+/// Caches compiled room ACLs and answers whether servers may federate
+pub struct ServerAclService {
+    event_repo: EventRepository,
+    /// `None` for rooms without an ACL, which allow every server
+    acls: Cache<String, Option<Arc<CompiledServerAcl>>>,
+}
+
+impl ServerAclService {
+    pub fn new(db: Surreal<Any>) -> Self {
+        Self {
+            event_repo: EventRepository::new(db),
+            acls: Cache::builder()
+                .max_capacity(10_000)
+                .time_to_live(Duration::from_secs(10 * 60))
+                .build(),
+        }
+    }
+
+    /// The room's current ACL, compiled
+    async fn room_acl(
+        &self,
+        room_id: &str,
+    ) -> Result<Option<Arc<CompiledServerAcl>>, RepositoryError> {
+        if let Some(acl) = self.acls.get(room_id).await {
+            return Ok(acl);
+        }
+
+        let acl = match self.event_repo.get_server_acl_event(room_id).await? {
+            Some(event) => {
+                let content = serde_json::to_value(&event.content)?;
+                match serde_json::from_value::<ServerAcl>(content) {
+                    Ok(acl) => Some(Arc::new(CompiledServerAcl::new(&acl))),
+                    Err(e) => {
+                        warn!("Ignoring malformed server ACL in room {}: {}", room_id, e);
+                        None
+                    },
+                }
+            },
+            None => None,
+        };
+        self.acls.insert(room_id.to_string(), acl.clone()).await;
+        Ok(acl)
+    }
+
+    /// Whether a server may participate in a room
+    pub async fn is_server_allowed(
+        &self,
+        room_id: &str,
+        server_name: &str,
+    ) -> Result<bool, RepositoryError> {
+        Ok(self.room_acl(room_id).await?.is_none_or(|acl| acl.allows(server_name)))
+    }
+
+    /// Whether an inbound federation request passes the room's ACL
+    ///
+    /// Denials are logged and counted under `endpoint`.
+    pub async fn allows_request(
+        &self,
+        room_id: &str,
+        origin: &str,
+        endpoint: &str,
+    ) -> Result<bool, RepositoryError> {
+        let allowed = self.is_server_allowed(room_id, origin).await?;
+        if !allowed {
+            warn!("Server {} denied by ACL of room {} on {}", origin, room_id, endpoint);
+            ServerAclMetrics::record_denied(endpoint);
+        }
+        Ok(allowed)
+    }
+
+    /// [`Self::allows_request`] for handlers, failing with 403 when denied
+    pub async fn check_request(
+        &self,
+        room_id: &str,
+        origin: &str,
+        endpoint: &str,
+    ) -> Result<(), StatusCode> {
+        let allowed = self.allows_request(room_id, origin, endpoint).await.map_err(|e| {
+            error!("Failed to load server ACL for room {}: {}", room_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+        if allowed {
+            Ok(())
+        } else {
+            Err(StatusCode::FORBIDDEN)
+        }
+    }
+
+    /// Drop the destinations the room's ACL denies, for outbound sends
+    pub async fn filter_destinations(
+        &self,
+        room_id: &str,
+        destinations: Vec<String>,
+        kind: &str,
+    ) -> Result<Vec<String>, RepositoryError> {
+        let Some(acl) = self.room_acl(room_id).await? else {
+            return Ok(destinations);
+        };
+        Ok(destinations
+            .into_iter()
+            .filter(|destination| {
+                let allowed = acl.allows(destination);
+                if !allowed {
+                    debug!(
+                        "Not sending {} for room {} to {}: denied by ACL",
+                        kind, room_id, destination
+                    );
+                    ServerAclMetrics::record_skipped_destination(kind);
+                }
+                allowed
+            })
+            .collect())
+    }
+
+    /// Forget a room's compiled ACL after its `m.room.server_acl` changes
+    pub async fn invalidate(&self, room_id: &str) {
+        self.acls.invalidate(room_id).await;
+    }
+
+    /// Invalidate the room's ACL if the event is a server ACL change
+    pub async fn observe_state_event(
+        &self,
+        room_id: &str,
+        event_type: &str,
+        state_key: Option<&str>,
+    ) {
+        if event_type == "m.room.server_acl" && state_key == Some("") {
+            self.invalidate(room_id).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn acl(allow: &[&str], deny: &[&str], allow_ip_literals: bool) -> CompiledServerAcl {
+        CompiledServerAcl::new(&ServerAcl {
+            allow: allow.iter().map(|s| s.to_string()).collect(),
+            deny: deny.iter().map(|s| s.to_string()).collect(),
+            allow_ip_literals,
+        })
+    }
+
+    #[test]
+    fn test_deny_takes_precedence_over_allow() {
+        let acl = acl(&["*"], &["*.evil.org", "evil.org"], true);
+
+        assert!(acl.allows("example.org"));
+        assert!(!acl.allows("evil.org"));
+        assert!(!acl.allows("matrix.evil.org:8448"));
+    }
+
+    #[test]
+    fn test_empty_allow_denies_everyone() {
+        let acl = acl(&[], &[], true);
+
+        assert!(!acl.allows("example.org"));
+    }
+
+    #[test]
+    fn test_ip_literals_and_ports() {
+        let acl = acl(&["*"], &[], false);
+
+        assert!(!acl.allows("1.2.3.4"));
+        assert!(!acl.allows("[::1]:8448"));
+        assert!(acl.allows("example.org:8448"));
+        assert_eq!(server_host("[::1]:8448"), "[::1]");
+        assert_eq!(server_host("example.org"), "example.org");
+    }
+
+    #[test]
+    fn test_only_star_and_question_mark_are_wildcards() {
+        let acl = acl(&["matrix?.example.org", "[a-z].example.org"], &[], true);
+
+        assert!(acl.allows("matrix1.example.org"));
+        assert!(acl.allows("[a-z].example.org"));
+        assert!(!acl.allows("b.example.org"));
+        assert!(!acl.allows("matrix12.example.org"));
+    }
+
+    #[test]
+    fn test_acl_that_fails_to_compile_denies_everyone() {
+        let acl = ServerAcl {
+            allow: vec!["*".to_string()],
+            deny: vec!["*.evil.org".to_string()],
+            allow_ip_literals: true,
+        };
+
+        assert!(CompiledServerAcl::with_size_limit(&acl, ACL_SIZE_LIMIT).allows("example.org"));
+        let failed = CompiledServerAcl::with_size_limit(&acl, 1);
+        assert!(!failed.allows("example.org"));
+        assert!(!failed.allows("evil.org"));
+    }
+}
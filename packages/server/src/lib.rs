@@ -18,12 +18,17 @@ pub mod mentions;
 pub mod metrics;
 pub mod middleware;
 pub mod migration;
+pub mod moderation;
+pub mod modules;
 pub mod monitoring;
 pub mod performance;
+pub mod push;
 pub mod reactions;
 pub mod response;
 pub mod room;
+pub mod router;
 pub mod security;
+pub mod server;
 pub mod server_notices;
 pub mod state;
 pub mod tasks;
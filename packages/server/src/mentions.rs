@@ -9,6 +9,7 @@ use std::collections::HashSet;
 use tracing::{info, warn};
 
 use crate::state::AppState;
+use matryx_entity::types::Mentions;
 
 /// Errors that can occur during mentions processing
 #[derive(Debug, thiserror::Error)]
@@ -54,17 +55,8 @@ impl axum::response::IntoResponse for MentionsError {
     }
 }
 
-/// Mentions metadata for Matrix events
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct MentionsMetadata {
-    /// User IDs that are mentioned in the event
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub user_ids: Option<Vec<String>>,
-
-    /// Whether this is a room-wide mention (@room)
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub room: Option<bool>,
-}
+/// Mentions metadata for Matrix events (the `m.mentions` content property)
+pub type MentionsMetadata = Mentions;
 
 /// Room alias mention metadata (custom extension)
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -130,17 +122,11 @@ impl MentionsProcessor {
 
         // Check for existing m.mentions in content (client-provided)
         if let Some(existing_mentions) = event_content.get("m.mentions") {
-            if let Some(user_ids) = existing_mentions.get("user_ids").and_then(|v| v.as_array()) {
-                for user_id in user_ids {
-                    if let Some(user_id_str) = user_id.as_str() {
-                        mentioned_users.insert(user_id_str.to_string());
-                    }
-                }
-            }
+            let existing_mentions: Mentions = serde_json::from_value(existing_mentions.clone())
+                .map_err(|e| MentionsError::ValidationError(e.to_string()))?;
 
-            if let Some(room) = existing_mentions.get("room").and_then(|v| v.as_bool()) {
-                has_room_mention = room;
-            }
+            mentioned_users.extend(existing_mentions.user_ids.iter().flatten().cloned());
+            has_room_mention = existing_mentions.mentions_room();
         } else {
             // Fallback to text-based mention detection for backwards compatibility.
             //
@@ -176,14 +162,7 @@ impl MentionsProcessor {
 
         // Create mentions metadata if we have any mentions
         if !valid_mentions.is_empty() || has_room_mention {
-            let mentions = MentionsMetadata {
-                user_ids: if valid_mentions.is_empty() {
-                    None
-                } else {
-                    Some(valid_mentions)
-                },
-                room: if has_room_mention { Some(true) } else { None },
-            };
+            let mentions = Mentions::new(valid_mentions, has_room_mention);
             
             let room_aliases = if room_alias_mentions.is_empty() {
                 None
@@ -1,3 +1,4 @@
 pub mod filter_metrics;
 pub mod lazy_loading_benchmarks;
 pub mod lazy_loading_metrics;
+pub mod server_acl_metrics;
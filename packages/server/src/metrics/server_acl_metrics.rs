@@ -0,0 +1,36 @@
+//! Metrics for room server ACL enforcement
+//!
+//! Counts federation requests and outbound sends refused because a room's
+//! `m.room.server_acl` denies the remote server.
+
+use lazy_static::lazy_static;
+use prometheus::{IntCounterVec, register_int_counter_vec};
+
+lazy_static! {
+    static ref ACL_DENIED_REQUESTS: IntCounterVec = register_int_counter_vec!(
+        "matrix_federation_acl_denied_total",
+        "Total number of federation requests denied by room server ACLs",
+        &["endpoint"]
+    )
+    .unwrap_or_else(|e| panic!("Failed to register prometheus ACL denied requests metric - this indicates a duplicate metric name: {}", e));
+    static ref ACL_SKIPPED_DESTINATIONS: IntCounterVec = register_int_counter_vec!(
+        "matrix_federation_acl_skipped_destinations_total",
+        "Total number of outbound destinations skipped because room server ACLs deny them",
+        &["kind"]
+    )
+    .unwrap_or_else(|e| panic!("Failed to register prometheus ACL skipped destinations metric - this indicates a duplicate metric name: {}", e));
+}
+
+pub struct ServerAclMetrics;
+
+impl ServerAclMetrics {
+    /// Record an inbound request denied by a room's server ACL
+    pub fn record_denied(endpoint: &str) {
+        ACL_DENIED_REQUESTS.with_label_values(&[endpoint]).inc();
+    }
+
+    /// Record an outbound destination skipped because of a room's server ACL
+    pub fn record_skipped_destination(kind: &str) {
+        ACL_SKIPPED_DESTINATIONS.with_label_values(&[kind]).inc();
+    }
+}
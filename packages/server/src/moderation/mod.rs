@@ -0,0 +1,9 @@
+pub mod policy_lists;
+pub mod reports;
+pub mod room_shutdown;
+pub mod user_restrictions;
+
+pub use policy_lists::{PolicyListService, PolicyRules};
+pub use reports::{ReportService, ReportSubmission};
+pub use room_shutdown::{NoticeRoom, RoomShutdownService, ShutdownSummary};
+pub use user_restrictions::{UserRestrictionService, UserRestrictions};
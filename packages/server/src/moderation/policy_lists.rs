@@ -0,0 +1,298 @@
+//! Moderation policy list enforcement
+//!
+//! The server subscribes to policy list rooms (as published by Mjolnir and
+//! Draupnir) and enforces their `m.ban` recommendations: banned users cannot
+//! join or invite, banned servers cannot federate, and banned rooms are
+//! hidden from the directory. With a moderator configured, user bans are
+//! also applied in every room where that moderator has the power to ban.
+
+use std::sync::{Arc, RwLock};
+
+use regex::Regex;
+use surrealdb::{Surreal, engine::any::Any};
+use tracing::{debug, info, warn};
+
+use crate::utils::matrix_glob::compile_glob;
+use matryx_surrealdb::repository::{
+    MembershipRepository, PolicyListRepository, PolicyListSubscription, PolicyRule, PolicyRuleKind,
+    error::RepositoryError, room_operations::RoomOperationsService,
+};
+
+/// A rule with its entity glob compiled
+struct CompiledRule {
+    matcher: Option<Regex>,
+    rule: PolicyRule,
+}
+
+impl CompiledRule {
+    fn new(rule: PolicyRule) -> Self {
+        let matcher = match compile_glob(&rule.content.entity) {
+            Ok(regex) => Some(regex),
+            Err(e) => {
+                warn!(
+                    "Invalid policy rule entity '{}' in {}: {}. Using literal match.",
+                    rule.content.entity, rule.room_id, e
+                );
+                None
+            },
+        };
+        Self { matcher, rule }
+    }
+
+    fn matches(&self, value: &str) -> bool {
+        match &self.matcher {
+            Some(matcher) => matcher.is_match(value),
+            None => self.rule.content.entity == value,
+        }
+    }
+}
+
+/// The `m.ban` rules of all subscribed policy lists
+#[derive(Default)]
+pub struct PolicyRules {
+    users: Vec<CompiledRule>,
+    rooms: Vec<CompiledRule>,
+    servers: Vec<CompiledRule>,
+}
+
+impl PolicyRules {
+    /// Compile rules, keeping only ban recommendations
+    pub fn new(rules: Vec<PolicyRule>) -> Self {
+        let mut compiled = Self::default();
+        for rule in rules.into_iter().filter(|rule| rule.content.is_ban()) {
+            let list = match rule.kind {
+                PolicyRuleKind::User => &mut compiled.users,
+                PolicyRuleKind::Room => &mut compiled.rooms,
+                PolicyRuleKind::Server => &mut compiled.servers,
+            };
+            list.push(CompiledRule::new(rule));
+        }
+        compiled
+    }
+
+    pub fn len(&self) -> usize {
+        self.users.len() + self.rooms.len() + self.servers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The rule banning a user, directly or through their server
+    pub fn user_ban(&self, user_id: &str) -> Option<&PolicyRule> {
+        let user_rule = self.users.iter().find(|rule| rule.matches(user_id));
+        match user_rule {
+            Some(rule) => Some(&rule.rule),
+            None => user_id.split_once(':').and_then(|(_, server)| self.server_ban(server)),
+        }
+    }
+
+    /// The rule banning a room, matched against its ID or an alias
+    pub fn room_ban(&self, room_id_or_alias: &str) -> Option<&PolicyRule> {
+        self.rooms
+            .iter()
+            .find(|rule| rule.matches(room_id_or_alias))
+            .map(|rule| &rule.rule)
+    }
+
+    /// Whether a room is banned by its ID or canonical alias
+    pub fn is_room_banned(&self, room_id: &str, canonical_alias: Option<&str>) -> bool {
+        self.room_ban(room_id).is_some() ||
+            canonical_alias.is_some_and(|alias| self.room_ban(alias).is_some())
+    }
+
+    /// The rule banning a server
+    pub fn server_ban(&self, server_name: &str) -> Option<&PolicyRule> {
+        self.servers
+            .iter()
+            .find(|rule| rule.matches(server_name))
+            .map(|rule| &rule.rule)
+    }
+}
+
+/// Keeps the rules of subscribed policy lists and answers ban checks
+///
+/// Checks read an in-memory snapshot, so they are cheap enough for every
+/// federation request. The snapshot is reloaded by
+/// [`crate::tasks::policy_list_sync`] and whenever subscriptions change.
+pub struct PolicyListService {
+    policy_list_repo: PolicyListRepository,
+    rules: RwLock<Arc<PolicyRules>>,
+}
+
+impl PolicyListService {
+    pub fn new(db: Surreal<Any>) -> Self {
+        Self {
+            policy_list_repo: PolicyListRepository::new(db),
+            rules: RwLock::new(Arc::new(PolicyRules::default())),
+        }
+    }
+
+    /// The current rules
+    pub fn rules(&self) -> Arc<PolicyRules> {
+        self.rules.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+
+    /// Reload the rules of every subscribed policy list
+    pub async fn refresh(&self) -> Result<Arc<PolicyRules>, RepositoryError> {
+        let room_ids: Vec<String> = self
+            .policy_list_repo
+            .get_subscriptions()
+            .await?
+            .into_iter()
+            .map(|subscription| subscription.room_id)
+            .collect();
+        let rules = Arc::new(PolicyRules::new(self.policy_list_repo.get_rules(&room_ids).await?));
+        debug!("Loaded {} policy list bans from {} lists", rules.len(), room_ids.len());
+
+        *self.rules.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = rules.clone();
+        Ok(rules)
+    }
+
+    pub async fn subscriptions(&self) -> Result<Vec<PolicyListSubscription>, RepositoryError> {
+        self.policy_list_repo.get_subscriptions().await
+    }
+
+    /// Start enforcing a policy list room
+    pub async fn subscribe(
+        &self,
+        room_id: &str,
+        subscribed_by: &str,
+    ) -> Result<PolicyListSubscription, RepositoryError> {
+        let subscription = self.policy_list_repo.subscribe(room_id, subscribed_by).await?;
+        info!("Subscribed to policy list {} by {}", room_id, subscribed_by);
+        self.refresh().await?;
+        Ok(subscription)
+    }
+
+    /// Stop enforcing a policy list room, returning whether it was subscribed
+    pub async fn unsubscribe(&self, room_id: &str) -> Result<bool, RepositoryError> {
+        let removed = self.policy_list_repo.unsubscribe(room_id).await?;
+        if removed {
+            info!("Unsubscribed from policy list {}", room_id);
+            self.refresh().await?;
+        }
+        Ok(removed)
+    }
+
+    pub fn is_user_banned(&self, user_id: &str) -> bool {
+        self.rules().user_ban(user_id).is_some()
+    }
+
+    pub fn is_room_banned(&self, room_id_or_alias: &str) -> bool {
+        self.rules().room_ban(room_id_or_alias).is_some()
+    }
+
+    pub fn is_server_banned(&self, server_name: &str) -> bool {
+        self.rules().server_ban(server_name).is_some()
+    }
+
+    /// Ban the users matched by policy rules from every room the moderator
+    /// has joined, returning how many bans were issued
+    ///
+    /// Rooms where the moderator lacks the power to ban are skipped.
+    pub async fn apply_bans(
+        &self,
+        db: &Surreal<Any>,
+        room_operations: &RoomOperationsService<Any>,
+        moderator: &str,
+    ) -> Result<usize, RepositoryError> {
+        let rules = self.rules();
+        if rules.users.is_empty() && rules.servers.is_empty() {
+            return Ok(0);
+        }
+
+        let membership_repo = MembershipRepository::new(db.clone());
+        let mut issued = 0;
+
+        for room in membership_repo.get_user_rooms(moderator).await? {
+            for member in membership_repo.get_room_members(&room.room_id).await? {
+                if member.user_id == moderator {
+                    continue;
+                }
+                let Some(rule) = rules.user_ban(&member.user_id) else {
+                    continue;
+                };
+
+                let reason = if rule.content.reason.is_empty() {
+                    None
+                } else {
+                    Some(rule.content.reason.clone())
+                };
+                match room_operations
+                    .ban_user(&room.room_id, &member.user_id, moderator, reason)
+                    .await
+                {
+                    Ok(()) => {
+                        info!(
+                            "Banned {} from {} per policy list {}",
+                            member.user_id, room.room_id, rule.room_id
+                        );
+                        issued += 1;
+                    },
+                    Err(RepositoryError::Unauthorized { .. }) => {
+                        debug!("{} cannot ban in {}, skipping the room", moderator, room.room_id);
+                        break;
+                    },
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+
+        Ok(issued)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use matryx_entity::types::PolicyRuleContent;
+
+    fn rule(kind: PolicyRuleKind, entity: &str) -> PolicyRule {
+        PolicyRule {
+            room_id: "!bans:example.org".to_string(),
+            kind,
+            state_key: format!("rule:{}", entity),
+            content: PolicyRuleContent::ban(entity.to_string(), "spam".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_user_rules_match_globs() {
+        let rules = PolicyRules::new(vec![rule(PolicyRuleKind::User, "@spam*:example.org")]);
+
+        assert!(rules.user_ban("@spammer:example.org").is_some());
+        assert!(rules.user_ban("@spam:example.org").is_some());
+        assert!(rules.user_ban("@alice:example.org").is_none());
+        assert!(rules.user_ban("@spammer:other.org").is_none());
+    }
+
+    #[test]
+    fn test_brackets_and_braces_are_literal() {
+        let rules = PolicyRules::new(vec![rule(PolicyRuleKind::User, "@[bot]{1}*:example.org")]);
+
+        assert!(rules.user_ban("@[bot]{1}spam:example.org").is_some());
+        assert!(rules.user_ban("@b:example.org").is_none());
+    }
+
+    #[test]
+    fn test_server_rules_ban_their_users() {
+        let rules = PolicyRules::new(vec![rule(PolicyRuleKind::Server, "*.evil.org")]);
+
+        assert!(rules.server_ban("matrix.evil.org").is_some());
+        assert!(rules.server_ban("evil.org").is_none());
+        assert!(rules.user_ban("@anyone:matrix.evil.org").is_some());
+        assert!(rules.user_ban("@anyone:example.org").is_none());
+    }
+
+    #[test]
+    fn test_only_ban_recommendations_apply() {
+        let mut other = rule(PolicyRuleKind::Room, "!quiet:example.org");
+        other.content.recommendation = "org.example.mute".to_string();
+        let rules = PolicyRules::new(vec![other, rule(PolicyRuleKind::Room, "#spam:*")]);
+
+        assert_eq!(rules.len(), 1);
+        assert!(rules.room_ban("!quiet:example.org").is_none());
+        assert!(rules.room_ban("#spam:example.org").is_some());
+    }
+}
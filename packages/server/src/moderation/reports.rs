@@ -0,0 +1,369 @@
+//! Content report routing
+//!
+//! Room, event and user reports are stored as moderation reports, with
+//! repeated reports of the same target folded into its unresolved report so
+//! moderators are notified once. A new report is posted as a server notice
+//! into the configured moderation room, sent to the room's moderators who
+//! opted in when it concerns a room, and emailed to the server admin.
+
+use serde_json::{Value, json};
+use surrealdb::{Surreal, engine::any::Any};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::config::ReportRoutingConfig;
+use crate::server_notices::ServerNoticesManager;
+use crate::state::AppState;
+use matryx_entity::ServerNoticeContent;
+use matryx_surrealdb::repository::{
+    AccountDataRepository, MembershipRepository, ModerationReport, ModerationReportKind,
+    ModerationReportRepository, ModerationReportStatus, PowerLevelsRepository,
+    error::RepositoryError,
+};
+
+/// Account data a moderator sets to `{"enabled": true}` to receive reports
+/// about rooms they moderate
+pub const REPORT_NOTIFICATIONS_ACCOUNT_DATA: &str = "com.maxtryx.report_notifications";
+
+/// A report as filed by a user
+pub struct ReportSubmission {
+    pub kind: ModerationReportKind,
+    pub room_id: Option<String>,
+    pub event_id: Option<String>,
+    pub reported_user_id: Option<String>,
+    pub reporter_id: String,
+    pub reason: Option<String>,
+    pub score: Option<i32>,
+}
+
+impl ReportSubmission {
+    fn target(&self) -> String {
+        let id = match self.kind {
+            ModerationReportKind::Room => self.room_id.as_deref(),
+            ModerationReportKind::Event => self.event_id.as_deref(),
+            ModerationReportKind::User => self.reported_user_id.as_deref(),
+        };
+        ModerationReport::target_for(self.kind, id.unwrap_or_default())
+    }
+}
+
+/// Stores reports, folds duplicates together and notifies moderators
+pub struct ReportService {
+    db: Surreal<Any>,
+    reports: ModerationReportRepository,
+    config: &'static ReportRoutingConfig,
+    homeserver_name: String,
+    notices: ServerNoticesManager,
+}
+
+impl ReportService {
+    pub fn new(
+        db: Surreal<Any>,
+        config: &'static ReportRoutingConfig,
+        homeserver_name: String,
+    ) -> Self {
+        Self {
+            reports: ModerationReportRepository::new(db.clone()),
+            db,
+            config,
+            notices: ServerNoticesManager::new(homeserver_name.clone()),
+            homeserver_name,
+        }
+    }
+
+    /// File a report, notifying moderators unless the target already has an
+    /// unresolved report
+    pub async fn submit(
+        &self,
+        state: &AppState,
+        submission: ReportSubmission,
+    ) -> Result<ModerationReport, RepositoryError> {
+        let target = submission.target();
+
+        if let Some(existing) = self.reports.find_unresolved_report(&target).await? {
+            if existing.reporters.contains(&submission.reporter_id) {
+                debug!("{} already reported {}", submission.reporter_id, target);
+                return Ok(existing);
+            }
+            let report = self
+                .reports
+                .add_reporter(&existing.report_id, &submission.reporter_id)
+                .await?
+                .unwrap_or(existing);
+            debug!(
+                "Folded report of {} by {} into report {}",
+                target, submission.reporter_id, report.report_id
+            );
+            return Ok(report);
+        }
+
+        let now = chrono::Utc::now();
+        let report = ModerationReport {
+            report_id: Uuid::new_v4().to_string(),
+            target,
+            kind: submission.kind,
+            room_id: submission.room_id,
+            event_id: submission.event_id,
+            reported_user_id: submission.reported_user_id,
+            reporters: vec![submission.reporter_id.clone()],
+            reporter_id: submission.reporter_id,
+            reason: submission.reason,
+            score: submission.score,
+            status: ModerationReportStatus::Open,
+            action_taken: None,
+            handled_by: None,
+            created_at: now,
+            updated_at: now,
+        };
+        let report = self.reports.create_report(&report).await?;
+        info!("Stored report {} of {} by {}", report.report_id, report.target, report.reporter_id);
+
+        self.route(state, &report).await;
+        Ok(report)
+    }
+
+    /// Deliver a new report to every configured destination; failures are
+    /// logged since the report itself is already stored
+    async fn route(&self, state: &AppState, report: &ModerationReport) {
+        let notice = report_notice(report);
+
+        if let Some(room_id) = &self.config.moderation_room_id
+            && let Err(e) = self.notices.send_room_notice(room_id, notice.clone(), state).await
+        {
+            warn!(
+                "Failed to post report {} to moderation room {}: {}",
+                report.report_id, room_id, e
+            );
+        }
+
+        if self.config.notify_room_moderators
+            && let Some(room_id) = &report.room_id
+        {
+            match self.consenting_moderators(room_id, report).await {
+                Ok(moderators) => {
+                    for moderator in moderators {
+                        if let Err(e) =
+                            self.notices.send_server_notice(&moderator, notice.clone(), state).await
+                        {
+                            warn!(
+                                "Failed to notify {} of report {}: {}",
+                                moderator, report.report_id, e
+                            );
+                        }
+                    }
+                },
+                Err(e) => warn!("Failed to find moderators of room {}: {}", room_id, e),
+            }
+        }
+
+        if self.config.email_admin
+            && let Some(email_service) = &state.email_service
+        {
+            let reported = report_subject(report);
+            if let Err(e) = email_service
+                .send_moderator_notification(
+                    &state.config.admin_email,
+                    &report.reporter_id,
+                    &reported,
+                    report.reason.as_deref().unwrap_or_default(),
+                )
+                .await
+            {
+                warn!("Failed to email report {}: {}", report.report_id, e);
+            }
+        }
+    }
+
+    /// Local members able to kick in the room who opted in to report
+    /// notifications, excluding the reported user
+    async fn consenting_moderators(
+        &self,
+        room_id: &str,
+        report: &ModerationReport,
+    ) -> Result<Vec<String>, RepositoryError> {
+        let power_levels = PowerLevelsRepository::new(self.db.clone())
+            .get_power_levels(room_id)
+            .await?;
+        let members = MembershipRepository::new(self.db.clone())
+            .get_room_members(room_id)
+            .await?;
+        let account_data_repo = AccountDataRepository::new(self.db.clone());
+        let local_suffix = format!(":{}", self.homeserver_name);
+
+        let mut moderators = Vec::new();
+        for member in members {
+            if !member.user_id.ends_with(&local_suffix) ||
+                report.reported_user_id.as_ref() == Some(&member.user_id)
+            {
+                continue;
+            }
+
+            let level = power_levels
+                .users
+                .get(&member.user_id)
+                .copied()
+                .unwrap_or(power_levels.users_default);
+            if level < power_levels.kick {
+                continue;
+            }
+
+            let opted_in = account_data_repo
+                .get_by_user_and_type(&member.user_id, REPORT_NOTIFICATIONS_ACCOUNT_DATA)
+                .await?
+                .and_then(|data| data.content.get("enabled").and_then(Value::as_bool))
+                .unwrap_or(false);
+            if opted_in {
+                moderators.push(member.user_id);
+            }
+        }
+        Ok(moderators)
+    }
+
+    pub async fn list(
+        &self,
+        status: Option<ModerationReportStatus>,
+        limit: u32,
+    ) -> Result<Vec<ModerationReport>, RepositoryError> {
+        self.reports.list_reports(status, limit).await
+    }
+
+    pub async fn get(&self, report_id: &str) -> Result<Option<ModerationReport>, RepositoryError> {
+        self.reports.get_report(report_id).await
+    }
+
+    /// Move a report forward, recording who handled it and what was done
+    pub async fn transition(
+        &self,
+        report_id: &str,
+        status: ModerationReportStatus,
+        action_taken: Option<&str>,
+        handled_by: &str,
+    ) -> Result<ModerationReport, RepositoryError> {
+        let not_found = || RepositoryError::NotFound {
+            entity_type: "moderation_report".to_string(),
+            id: report_id.to_string(),
+        };
+
+        let report = self.reports.get_report(report_id).await?.ok_or_else(not_found)?;
+        if !report.status.can_transition_to(status) {
+            return Err(RepositoryError::Validation {
+                field: "status".to_string(),
+                message: format!(
+                    "Cannot move a {} report to {}",
+                    report.status.as_str(),
+                    status.as_str()
+                ),
+            });
+        }
+
+        let report = self
+            .reports
+            .update_status(report_id, status, action_taken, handled_by)
+            .await?
+            .ok_or_else(not_found)?;
+        info!("{} moved report {} to {}", handled_by, report_id, status.as_str());
+        Ok(report)
+    }
+}
+
+/// What a report is about, in words
+fn report_subject(report: &ModerationReport) -> String {
+    let room_id = report.room_id.as_deref().unwrap_or_default();
+    match report.kind {
+        ModerationReportKind::Room => format!("room {}", room_id),
+        ModerationReportKind::Event => {
+            format!("event {} in room {}", report.event_id.as_deref().unwrap_or_default(), room_id)
+        },
+        ModerationReportKind::User => {
+            format!("user {}", report.reported_user_id.as_deref().unwrap_or_default())
+        },
+    }
+}
+
+fn report_notice(report: &ModerationReport) -> ServerNoticeContent {
+    let body = format!(
+        "{} reported {}: {}",
+        report.reporter_id,
+        report_subject(report),
+        report.reason.as_deref().unwrap_or("no reason given")
+    );
+
+    let mut additional_data = serde_json::Map::new();
+    additional_data.insert(
+        "report".to_string(),
+        json!({
+            "report_id": report.report_id,
+            "kind": report.kind,
+            "room_id": report.room_id,
+            "event_id": report.event_id,
+            "reported_user_id": report.reported_user_id,
+            "score": report.score,
+        }),
+    );
+
+    ServerNoticeContent {
+        msgtype: "m.server_notice".to_string(),
+        body,
+        server_notice_type: "com.maxtryx.report".to_string(),
+        additional_data,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn submission(kind: ModerationReportKind) -> ReportSubmission {
+        ReportSubmission {
+            kind,
+            room_id: Some("!room:example.com".to_string()),
+            event_id: Some("$event".to_string()),
+            reported_user_id: Some("@spammer:example.com".to_string()),
+            reporter_id: "@alice:example.com".to_string(),
+            reason: Some("spam".to_string()),
+            score: None,
+        }
+    }
+
+    #[test]
+    fn test_reports_of_the_same_target_share_a_key() {
+        let event = submission(ModerationReportKind::Event);
+        let user = submission(ModerationReportKind::User);
+        let mut other_reporter = submission(ModerationReportKind::Event);
+        other_reporter.reporter_id = "@bob:example.com".to_string();
+
+        assert_eq!(event.target(), "event|$event");
+        assert_eq!(user.target(), "user|@spammer:example.com");
+        assert_eq!(event.target(), other_reporter.target());
+    }
+
+    #[test]
+    fn test_notices_describe_the_reported_content() {
+        let now = chrono::Utc::now();
+        let report = ModerationReport {
+            report_id: "r1".to_string(),
+            target: "event|$event".to_string(),
+            kind: ModerationReportKind::Event,
+            room_id: Some("!room:example.com".to_string()),
+            event_id: Some("$event".to_string()),
+            reported_user_id: Some("@spammer:example.com".to_string()),
+            reporter_id: "@alice:example.com".to_string(),
+            reporters: vec!["@alice:example.com".to_string()],
+            reason: None,
+            score: Some(-100),
+            status: ModerationReportStatus::Open,
+            action_taken: None,
+            handled_by: None,
+            created_at: now,
+            updated_at: now,
+        };
+
+        let notice = report_notice(&report);
+        assert!(notice.validate().is_ok());
+        assert_eq!(
+            notice.body,
+            "@alice:example.com reported event $event in room !room:example.com: no reason given"
+        );
+        assert_eq!(notice.additional_data["report"]["report_id"], "r1");
+    }
+}
@@ -0,0 +1,203 @@
+//! Room shutdown
+//!
+//! Shutting a room down blocks it, so local users can no longer join it
+//! or be invited and remote servers cannot join through this server. Every
+//! local member is made to leave, the room's local aliases are removed and
+//! it is taken out of the room directory. Optionally a local user creates
+//! a new room, the evicted users are moved into it and shown a notice.
+
+use axum::http::StatusCode;
+use serde::Serialize;
+use serde_json::json;
+use surrealdb::{Surreal, engine::any::Any};
+use tracing::{error, info, warn};
+
+use matryx_surrealdb::repository::directory::RoomDirectoryVisibility;
+use matryx_surrealdb::repository::room::RoomCreationConfig;
+use matryx_surrealdb::repository::{
+    BlockedRoomRepository, DirectoryRepository, EventRepository, MembershipRepository,
+    RoomRepository, error::RepositoryError, room_operations::RoomOperationsService,
+};
+
+/// The room evicted users are moved into
+#[derive(Debug, Clone)]
+pub struct NoticeRoom {
+    /// Local user who creates the room and sends the notice
+    pub creator: String,
+    pub name: String,
+    pub message: String,
+}
+
+/// What a shutdown did
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ShutdownSummary {
+    pub kicked_users: Vec<String>,
+    pub failed_to_kick_users: Vec<String>,
+    pub local_aliases: Vec<String>,
+    pub new_room_id: Option<String>,
+}
+
+pub struct RoomShutdownService {
+    blocked_rooms: BlockedRoomRepository,
+    membership_repo: MembershipRepository,
+    room_repo: RoomRepository,
+    directory_repo: DirectoryRepository<Any>,
+    event_repo: EventRepository,
+    homeserver_name: String,
+}
+
+impl RoomShutdownService {
+    pub fn new(db: Surreal<Any>, homeserver_name: String) -> Self {
+        Self {
+            blocked_rooms: BlockedRoomRepository::new(db.clone()),
+            membership_repo: MembershipRepository::new(db.clone()),
+            room_repo: RoomRepository::new(db.clone()),
+            directory_repo: DirectoryRepository::new(db.clone()),
+            event_repo: EventRepository::new(db),
+            homeserver_name,
+        }
+    }
+
+    pub async fn is_room_blocked(&self, room_id: &str) -> Result<bool, RepositoryError> {
+        self.blocked_rooms.is_room_blocked(room_id).await
+    }
+
+    /// Fail with 403 if the room has been shut down
+    pub async fn check_not_blocked(&self, room_id: &str) -> Result<(), StatusCode> {
+        let blocked = self.is_room_blocked(room_id).await.map_err(|e| {
+            error!("Failed to check whether room {} is blocked: {}", room_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+        if blocked {
+            warn!("Refusing membership change in shut down room {}", room_id);
+            Err(StatusCode::FORBIDDEN)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Lift a shutdown's block, returning whether the room was blocked
+    ///
+    /// Evicted users and removed aliases are not restored.
+    pub async fn unblock(&self, room_id: &str) -> Result<bool, RepositoryError> {
+        self.blocked_rooms.unblock_room(room_id).await
+    }
+
+    /// Shut a room down
+    ///
+    /// A member who cannot be removed is reported in
+    /// `failed_to_kick_users` rather than failing the shutdown.
+    pub async fn shutdown(
+        &self,
+        room_operations: &RoomOperationsService<Any>,
+        room_id: &str,
+        requested_by: &str,
+        reason: Option<&str>,
+        notice_room: Option<&NoticeRoom>,
+    ) -> Result<ShutdownSummary, RepositoryError> {
+        self.blocked_rooms.block_room(room_id, requested_by, reason).await?;
+
+        let mut summary = ShutdownSummary::default();
+        if let Some(notice_room) = notice_room {
+            summary.new_room_id =
+                Some(self.create_notice_room(room_operations, notice_room).await?);
+        }
+
+        let reason = reason.unwrap_or("This room has been shut down by the server administrator");
+        for member in self.membership_repo.get_room_members(room_id).await? {
+            if !self.is_local(&member.user_id) {
+                continue;
+            }
+            if let Err(e) = room_operations
+                .leave_room(room_id, &member.user_id, Some(reason.to_string()))
+                .await
+            {
+                warn!("Failed to remove {} from shut down room {}: {}", member.user_id, room_id, e);
+                summary.failed_to_kick_users.push(member.user_id);
+                continue;
+            }
+
+            if let (Some(new_room_id), Some(notice_room)) = (&summary.new_room_id, notice_room)
+                && let Err(e) = self
+                    .move_user(room_operations, new_room_id, &notice_room.creator, &member.user_id)
+                    .await
+            {
+                warn!("Failed to move {} into room {}: {}", member.user_id, new_room_id, e);
+            }
+            summary.kicked_users.push(member.user_id);
+        }
+
+        for alias in self.room_repo.get_room_aliases(room_id).await? {
+            if !self.is_local(&alias) {
+                continue;
+            }
+            self.room_repo.remove_room_alias(room_id, &alias).await?;
+            summary.local_aliases.push(alias);
+        }
+        self.directory_repo
+            .set_room_directory_visibility(room_id, RoomDirectoryVisibility::Private)
+            .await?;
+
+        info!(
+            "Room {} shut down by {}: {} users removed, {} aliases deleted",
+            room_id,
+            requested_by,
+            summary.kicked_users.len(),
+            summary.local_aliases.len()
+        );
+        Ok(summary)
+    }
+
+    async fn create_notice_room(
+        &self,
+        room_operations: &RoomOperationsService<Any>,
+        notice_room: &NoticeRoom,
+    ) -> Result<String, RepositoryError> {
+        let config = RoomCreationConfig {
+            name: Some(notice_room.name.clone()),
+            topic: None,
+            alias: None,
+            is_public: false,
+            is_direct: false,
+            preset: Some("private_chat".to_string()),
+            invite_users: Vec::new(),
+            invite_3pid: Vec::new(),
+            initial_state: Vec::new(),
+            power_level_content_override: None,
+            creation_content: None,
+        };
+        let room_id = room_operations.create_room(&notice_room.creator, &config).await?;
+
+        self.event_repo
+            .create_room_event(
+                &room_id,
+                "m.room.message",
+                &notice_room.creator,
+                json!({ "msgtype": "m.text", "body": notice_room.message }),
+                None,
+            )
+            .await?;
+        Ok(room_id)
+    }
+
+    async fn move_user(
+        &self,
+        room_operations: &RoomOperationsService<Any>,
+        new_room_id: &str,
+        creator: &str,
+        user_id: &str,
+    ) -> Result<(), RepositoryError> {
+        if user_id == creator {
+            return Ok(());
+        }
+        room_operations.invite_user(new_room_id, user_id, creator, None).await?;
+        room_operations
+            .join_room_with_validation(new_room_id, user_id, None, None)
+            .await
+    }
+
+    fn is_local(&self, id: &str) -> bool {
+        id.split_once(':')
+            .is_some_and(|(_, server)| server == self.homeserver_name)
+    }
+}
@@ -0,0 +1,218 @@
+//! Shadow bans, account suspension and account locking
+//!
+//! Admins apply these instead of deactivating an account. A shadow-banned
+//! user's events, redactions, invites, typing notifications and profile
+//! changes appear to succeed but are dropped before they are persisted or
+//! federated. A suspended user keeps read access but may not send, join or
+//! change anything others can see (MSC3823), and a locked user may only log
+//! out (MSC3939). Suspension and locking are enforced by `auth::middleware`
+//! for every client route; shadow bans by the handlers that make those
+//! changes.
+
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use moka::future::Cache;
+use surrealdb::{Surreal, engine::any::Any};
+use tracing::{error, info};
+use uuid::Uuid;
+
+use matryx_surrealdb::repository::{
+    UserRestriction, UserRestrictionKind, UserRestrictionRepository, error::RepositoryError,
+};
+
+/// The restrictions currently in force for a user
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UserRestrictions {
+    pub shadow_banned: bool,
+    pub suspended: bool,
+    pub locked: bool,
+}
+
+impl UserRestrictions {
+    fn from_records(records: &[UserRestriction]) -> Self {
+        let mut restrictions = Self::default();
+        for record in records {
+            match record.kind {
+                UserRestrictionKind::ShadowBan => restrictions.shadow_banned = true,
+                UserRestrictionKind::Suspension => restrictions.suspended = true,
+                UserRestrictionKind::Lock => restrictions.locked = true,
+            }
+        }
+        restrictions
+    }
+}
+
+/// Applies user restrictions and answers, from a cache, which are in force
+pub struct UserRestrictionService {
+    repo: UserRestrictionRepository,
+    restrictions: Cache<String, UserRestrictions>,
+    /// Fake event IDs handed out per user and transaction ID, so a retried
+    /// send gets the same answer as the first attempt
+    fake_event_ids: Cache<(String, String), String>,
+}
+
+impl UserRestrictionService {
+    pub fn new(db: Surreal<Any>) -> Self {
+        Self {
+            repo: UserRestrictionRepository::new(db),
+            restrictions: Cache::builder()
+                .max_capacity(100_000)
+                .time_to_live(Duration::from_secs(60))
+                .build(),
+            fake_event_ids: Cache::builder()
+                .max_capacity(10_000)
+                .time_to_live(Duration::from_secs(24 * 60 * 60))
+                .build(),
+        }
+    }
+
+    /// The restrictions in force for a user, consulted on every request
+    pub async fn restrictions(&self, user_id: &str) -> Result<UserRestrictions, RepositoryError> {
+        if let Some(restrictions) = self.restrictions.get(user_id).await {
+            return Ok(restrictions);
+        }
+
+        let records = self.repo.get_user_restrictions(user_id).await?;
+        let restrictions = UserRestrictions::from_records(&records);
+        self.restrictions.insert(user_id.to_string(), restrictions).await;
+        Ok(restrictions)
+    }
+
+    /// Whether a user's changes should appear to succeed but be dropped
+    ///
+    /// A failed lookup is logged and answered with a 500, as every handler
+    /// that asks would do.
+    pub async fn is_shadow_banned(&self, user_id: &str) -> Result<bool, StatusCode> {
+        match self.restrictions(user_id).await {
+            Ok(restrictions) => Ok(restrictions.shadow_banned),
+            Err(e) => {
+                error!("Failed to check shadow ban for {}: {}", user_id, e);
+                Err(StatusCode::INTERNAL_SERVER_ERROR)
+            },
+        }
+    }
+
+    /// The fake event ID for a shadow-banned user's transaction, the same
+    /// for every retry of it
+    pub async fn fake_event_id_for_txn(&self, user_id: &str, txn_id: &str) -> String {
+        self.fake_event_ids
+            .get_with((user_id.to_string(), txn_id.to_string()), async { fake_event_id() })
+            .await
+    }
+
+    /// Every restriction applied to a user, with who applied it and why
+    pub async fn list(&self, user_id: &str) -> Result<Vec<UserRestriction>, RepositoryError> {
+        self.repo.get_user_restrictions(user_id).await
+    }
+
+    pub async fn restrict(
+        &self,
+        user_id: &str,
+        kind: UserRestrictionKind,
+        restricted_by: &str,
+        reason: Option<&str>,
+    ) -> Result<UserRestriction, RepositoryError> {
+        let restriction = self.repo.restrict(user_id, kind, restricted_by, reason).await?;
+        self.restrictions.invalidate(user_id).await;
+        info!("{} applied {} to {}", restricted_by, kind.as_str(), user_id);
+        Ok(restriction)
+    }
+
+    /// Lift a restriction, returning whether it was in force
+    pub async fn lift(
+        &self,
+        user_id: &str,
+        kind: UserRestrictionKind,
+        lifted_by: &str,
+    ) -> Result<bool, RepositoryError> {
+        let lifted = self.repo.lift(user_id, kind).await?;
+        self.restrictions.invalidate(user_id).await;
+        if lifted {
+            info!("{} lifted {} from {}", lifted_by, kind.as_str(), user_id);
+        }
+        Ok(lifted)
+    }
+}
+
+/// An event ID indistinguishable from those of locally created events,
+/// handed to shadow-banned users in place of a real one
+pub fn fake_event_id() -> String {
+    format!("${}", Uuid::new_v4())
+}
+
+/// Whether a locked user may make a request; only logging out is allowed
+pub fn lock_allows(path: &str) -> bool {
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    match segments.as_slice() {
+        ["_matrix", "client", _, "logout"] => true,
+        ["_matrix", "client", _, "logout", "all"] => true,
+        _ => false,
+    }
+}
+
+/// Whether a suspended user may make a request
+///
+/// Reads are always allowed, as are leaving rooms, redacting, logging out and
+/// private changes such as account data. Sending events, joining, knocking,
+/// inviting, creating rooms or aliases, moderating, changing the profile and
+/// uploading media are not.
+pub fn suspension_allows(method: &str, path: &str) -> bool {
+    if matches!(method, "GET" | "HEAD" | "OPTIONS") {
+        return true;
+    }
+
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let forbidden = match segments.as_slice() {
+        ["_matrix", "media", _, "upload" | "create", ..] => true,
+        ["_matrix", "client", _, rest @ ..] => match rest {
+            ["createRoom"] | ["join" | "knock", _] | ["profile", ..] => true,
+            ["directory", "room", _] => true,
+            ["rooms", _, "send" | "state", ..] => true,
+            ["rooms", _, "join" | "invite" | "knock" | "upgrade"] => true,
+            ["rooms", _, "kick" | "ban" | "unban"] => true,
+            _ => false,
+        },
+        _ => false,
+    };
+    !forbidden
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locked_users_may_only_log_out() {
+        assert!(lock_allows("/_matrix/client/v3/logout"));
+        assert!(lock_allows("/_matrix/client/v3/logout/all"));
+        assert!(!lock_allows("/_matrix/client/v3/sync"));
+        assert!(!lock_allows("/_matrix/client/v3/account/whoami"));
+    }
+
+    #[test]
+    fn test_suspended_users_keep_read_access_only() {
+        assert!(suspension_allows("GET", "/_matrix/client/v3/rooms/!a:b/messages"));
+        assert!(suspension_allows("POST", "/_matrix/client/v3/rooms/!a:b/leave"));
+        assert!(suspension_allows("PUT", "/_matrix/client/v3/rooms/!a:b/redact/$e/txn"));
+        assert!(suspension_allows("PUT", "/_matrix/client/v3/user/@u:b/account_data/m.direct"));
+
+        assert!(!suspension_allows("PUT", "/_matrix/client/v3/rooms/!a:b/send/m.room.message/1"));
+        assert!(!suspension_allows("PUT", "/_matrix/client/v3/rooms/!a:b/state/m.room.name/"));
+        assert!(!suspension_allows("POST", "/_matrix/client/v3/rooms/!a:b/invite"));
+        assert!(!suspension_allows("POST", "/_matrix/client/v3/join/#room:b"));
+        assert!(!suspension_allows("POST", "/_matrix/client/v3/createRoom"));
+        assert!(!suspension_allows("PUT", "/_matrix/client/v3/profile/@u:b/displayname"));
+        assert!(!suspension_allows("POST", "/_matrix/media/v3/upload"));
+    }
+
+    #[tokio::test]
+    async fn test_retried_transactions_get_the_same_fake_event_id() {
+        let service = UserRestrictionService::new(Surreal::init());
+
+        let event_id = service.fake_event_id_for_txn("@u:b", "txn1").await;
+        assert_eq!(service.fake_event_id_for_txn("@u:b", "txn1").await, event_id);
+        assert_ne!(service.fake_event_id_for_txn("@u:b", "txn2").await, event_id);
+        assert_ne!(service.fake_event_id_for_txn("@v:b", "txn1").await, event_id);
+    }
+}
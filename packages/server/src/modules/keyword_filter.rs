@@ -0,0 +1,108 @@
+//! Built-in spam checker rejecting messages that contain blocked keywords
+//!
+//! Configured as:
+//!
+//! ```json
+//! {"module": "keyword_filter", "config": {"keywords": ["buy now"], "reason": "Spam"}}
+//! ```
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::{LoadedModule, ModuleError, SpamCheck, SpamChecker};
+
+#[derive(Debug, Deserialize)]
+struct KeywordFilterConfig {
+    keywords: Vec<String>,
+    #[serde(default = "default_reason")]
+    reason: String,
+}
+
+fn default_reason() -> String {
+    "Message contains a blocked keyword".to_string()
+}
+
+pub struct KeywordFilter {
+    keywords: Vec<String>,
+    reason: String,
+}
+
+impl KeywordFilter {
+    pub fn new(keywords: Vec<String>, reason: String) -> Self {
+        let keywords = keywords
+            .into_iter()
+            .map(|keyword| keyword.to_lowercase())
+            .filter(|keyword| !keyword.is_empty())
+            .collect();
+        Self { keywords, reason }
+    }
+
+    /// Factory for [`super::ModuleFactories`]
+    pub fn load(config: &Value) -> Result<LoadedModule, ModuleError> {
+        let config: KeywordFilterConfig =
+            serde_json::from_value(config.clone()).map_err(|e| ModuleError::InvalidConfig {
+                module: "keyword_filter".to_string(),
+                reason: e.to_string(),
+            })?;
+        Ok(LoadedModule::spam_checker(Self::new(config.keywords, config.reason)))
+    }
+
+    fn contains_keyword(&self, text: &str) -> bool {
+        let text = text.to_lowercase();
+        self.keywords.iter().any(|keyword| text.contains(keyword.as_str()))
+    }
+}
+
+#[async_trait]
+impl SpamChecker for KeywordFilter {
+    async fn check_event_for_spam(&self, event: &Value) -> SpamCheck {
+        let Some(content) = event.get("content") else {
+            return SpamCheck::Allow;
+        };
+        let blocked = ["body", "formatted_body", "name", "topic"]
+            .iter()
+            .filter_map(|field| content.get(field).and_then(|value| value.as_str()))
+            .any(|text| self.contains_keyword(text));
+
+        if blocked {
+            SpamCheck::deny(self.reason.clone())
+        } else {
+            SpamCheck::Allow
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn message(body: &str) -> Value {
+        json!({
+            "type": "m.room.message",
+            "room_id": "!room:example.org",
+            "sender": "@alice:example.org",
+            "content": {"msgtype": "m.text", "body": body}
+        })
+    }
+
+    #[tokio::test]
+    async fn test_blocks_keywords_case_insensitively() {
+        let filter = KeywordFilter::new(vec!["Buy Now".to_string()], default_reason());
+
+        assert!(
+            !filter
+                .check_event_for_spam(&message("please BUY NOW!!"))
+                .await
+                .is_allowed()
+        );
+        assert!(filter.check_event_for_spam(&message("hello there")).await.is_allowed());
+    }
+
+    #[test]
+    fn test_rejects_invalid_config() {
+        assert!(KeywordFilter::load(&json!({"keywords": "not a list"})).is_err());
+        assert!(KeywordFilter::load(&json!({"keywords": ["spam"]})).is_ok());
+    }
+}
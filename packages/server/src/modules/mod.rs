@@ -0,0 +1,346 @@
+//! In-process modules extending the server's anti-abuse decisions
+//!
+//! Modules are Rust types implementing [`SpamChecker`] and/or
+//! [`ThirdPartyRules`]. Each has a factory registered by name in
+//! [`ModuleFactories`], which a binary embedding the server extends with its
+//! own modules and passes to `Homeserver::start`; the `MODULES` setting
+//! lists which ones to load at startup and with what configuration. Checks run in the order modules are
+//! configured and the first denial wins.
+
+pub mod keyword_filter;
+pub mod spam_checker;
+pub mod third_party_rules;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde_json::Value;
+use tracing::{info, warn};
+
+use crate::config::server_config::ModuleConfig;
+
+pub use keyword_filter::KeywordFilter;
+pub use spam_checker::{MediaFileInfo, RegistrationInfo, SpamCheck, SpamChecker};
+pub use third_party_rules::{EventRuling, ThirdPartyRules};
+
+/// Errors raised while loading modules
+#[derive(Debug, thiserror::Error)]
+pub enum ModuleError {
+    #[error("Unknown module: {0}")]
+    UnknownModule(String),
+
+    #[error("Invalid configuration for module {module}: {reason}")]
+    InvalidConfig { module: String, reason: String },
+}
+
+/// The callbacks a module provides
+#[derive(Default)]
+pub struct LoadedModule {
+    pub spam_checker: Option<Arc<dyn SpamChecker>>,
+    pub third_party_rules: Option<Arc<dyn ThirdPartyRules>>,
+}
+
+impl LoadedModule {
+    pub fn spam_checker(checker: impl SpamChecker + 'static) -> Self {
+        Self {
+            spam_checker: Some(Arc::new(checker)),
+            third_party_rules: None,
+        }
+    }
+
+    pub fn third_party_rules(rules: impl ThirdPartyRules + 'static) -> Self {
+        Self {
+            spam_checker: None,
+            third_party_rules: Some(Arc::new(rules)),
+        }
+    }
+}
+
+/// Builds a module from its configuration
+pub type ModuleFactory = fn(&Value) -> Result<LoadedModule, ModuleError>;
+
+/// The modules that can be named in the `MODULES` setting
+#[derive(Default)]
+pub struct ModuleFactories {
+    factories: HashMap<String, ModuleFactory>,
+}
+
+impl ModuleFactories {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The modules shipped with the server
+    pub fn builtin() -> Self {
+        Self::new().with("keyword_filter", KeywordFilter::load)
+    }
+
+    /// Register a module under the name the `MODULES` setting uses for it
+    pub fn with(mut self, name: &str, factory: ModuleFactory) -> Self {
+        self.factories.insert(name.to_string(), factory);
+        self
+    }
+}
+
+/// The loaded modules, consulted by the client and federation handlers
+#[derive(Default)]
+pub struct ModuleRegistry {
+    spam_checkers: Vec<Arc<dyn SpamChecker>>,
+    third_party_rules: Vec<Arc<dyn ThirdPartyRules>>,
+}
+
+impl ModuleRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the configured modules, failing on unknown names or bad config
+    /// so a typo cannot silently disable a protection
+    pub fn load(
+        configs: &[ModuleConfig],
+        factories: &ModuleFactories,
+    ) -> Result<Self, ModuleError> {
+        let mut registry = Self::new();
+        for config in configs {
+            let factory = factories
+                .factories
+                .get(&config.module)
+                .ok_or_else(|| ModuleError::UnknownModule(config.module.clone()))?;
+            let module = factory(&config.config)?;
+            if let Some(checker) = module.spam_checker {
+                registry.spam_checkers.push(checker);
+            }
+            if let Some(rules) = module.third_party_rules {
+                registry.third_party_rules.push(rules);
+            }
+            info!("Loaded module {}", config.module);
+        }
+        Ok(registry)
+    }
+
+    pub fn with_spam_checker(mut self, checker: Arc<dyn SpamChecker>) -> Self {
+        self.spam_checkers.push(checker);
+        self
+    }
+
+    pub fn with_third_party_rules(mut self, rules: Arc<dyn ThirdPartyRules>) -> Self {
+        self.third_party_rules.push(rules);
+        self
+    }
+
+    pub async fn check_event_for_spam(&self, event: &Value) -> SpamCheck {
+        for checker in &self.spam_checkers {
+            let check = checker.check_event_for_spam(event).await;
+            if !check.is_allowed() {
+                return logged(check, "event", event.get("sender").and_then(|s| s.as_str()));
+            }
+        }
+        SpamCheck::Allow
+    }
+
+    pub async fn user_may_invite(&self, inviter: &str, invitee: &str, room_id: &str) -> SpamCheck {
+        for checker in &self.spam_checkers {
+            let check = checker.user_may_invite(inviter, invitee, room_id).await;
+            if !check.is_allowed() {
+                return logged(check, "invite", Some(inviter));
+            }
+        }
+        SpamCheck::Allow
+    }
+
+    pub async fn user_may_join_room(
+        &self,
+        user_id: &str,
+        room_id: &str,
+        is_invited: bool,
+    ) -> SpamCheck {
+        for checker in &self.spam_checkers {
+            let check = checker.user_may_join_room(user_id, room_id, is_invited).await;
+            if !check.is_allowed() {
+                return logged(check, "join", Some(user_id));
+            }
+        }
+        SpamCheck::Allow
+    }
+
+    pub async fn user_may_create_room(&self, user_id: &str) -> SpamCheck {
+        for checker in &self.spam_checkers {
+            let check = checker.user_may_create_room(user_id).await;
+            if !check.is_allowed() {
+                return logged(check, "room creation", Some(user_id));
+            }
+        }
+        SpamCheck::Allow
+    }
+
+    pub async fn user_may_publish_room(&self, user_id: &str, room_id: &str) -> SpamCheck {
+        for checker in &self.spam_checkers {
+            let check = checker.user_may_publish_room(user_id, room_id).await;
+            if !check.is_allowed() {
+                return logged(check, "room publication", Some(user_id));
+            }
+        }
+        SpamCheck::Allow
+    }
+
+    pub async fn check_registration_for_spam(
+        &self,
+        registration: RegistrationInfo<'_>,
+    ) -> SpamCheck {
+        for checker in &self.spam_checkers {
+            let check = checker.check_registration_for_spam(registration).await;
+            if !check.is_allowed() {
+                return logged(check, "registration", Some(registration.username));
+            }
+        }
+        SpamCheck::Allow
+    }
+
+    pub async fn check_media_file_for_spam(&self, file: MediaFileInfo<'_>) -> SpamCheck {
+        for checker in &self.spam_checkers {
+            let check = checker.check_media_file_for_spam(file).await;
+            if !check.is_allowed() {
+                return logged(check, "media upload", Some(file.uploader));
+            }
+        }
+        SpamCheck::Allow
+    }
+
+    /// Run every third-party rule over a state event
+    ///
+    /// Replacements are chained, so each rule sees the content produced by
+    /// the rules before it. The ruling is `Replace` if any rule changed the
+    /// content.
+    pub async fn check_state_event_allowed(&self, event: &Value) -> EventRuling {
+        let mut replaced: Option<Value> = None;
+        for rules in &self.third_party_rules {
+            let ruling = match &replaced {
+                Some(content) => {
+                    let mut event = event.clone();
+                    event["content"] = content.clone();
+                    rules.check_state_event_allowed(&event).await
+                },
+                None => rules.check_state_event_allowed(event).await,
+            };
+            match ruling {
+                EventRuling::Allow => {},
+                EventRuling::Deny { reason } => {
+                    warn!(
+                        "Third-party rules denied {} event in {}: {}",
+                        event.get("type").and_then(|t| t.as_str()).unwrap_or_default(),
+                        event.get("room_id").and_then(|r| r.as_str()).unwrap_or_default(),
+                        reason
+                    );
+                    return EventRuling::Deny { reason };
+                },
+                EventRuling::Replace { content } => replaced = Some(content),
+            }
+        }
+        match replaced {
+            Some(content) => EventRuling::Replace { content },
+            None => EventRuling::Allow,
+        }
+    }
+}
+
+fn logged(check: SpamCheck, action: &str, user_id: Option<&str>) -> SpamCheck {
+    if let SpamCheck::Deny { reason } = &check {
+        warn!("Spam checker denied {} by {}: {}", action, user_id.unwrap_or("unknown"), reason);
+    }
+    check
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use serde_json::json;
+
+    struct DenyInvites;
+
+    #[async_trait]
+    impl SpamChecker for DenyInvites {
+        async fn user_may_invite(
+            &self,
+            _inviter: &str,
+            _invitee: &str,
+            _room_id: &str,
+        ) -> SpamCheck {
+            SpamCheck::deny("No invites")
+        }
+    }
+
+    struct AppendToTopic(&'static str);
+
+    #[async_trait]
+    impl ThirdPartyRules for AppendToTopic {
+        async fn check_state_event_allowed(&self, event: &Value) -> EventRuling {
+            let topic = event["content"]["topic"].as_str().unwrap_or_default();
+            EventRuling::Replace {
+                content: json!({"topic": format!("{}{}", topic, self.0)}),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_first_denial_wins_and_defaults_allow() {
+        let registry = ModuleRegistry::new()
+            .with_spam_checker(Arc::new(DenyInvites))
+            .with_spam_checker(Arc::new(KeywordFilter::new(
+                vec!["spam".to_string()],
+                "Spam".to_string(),
+            )));
+
+        assert_eq!(
+            registry
+                .user_may_invite("@a:example.org", "@b:example.org", "!r:example.org")
+                .await,
+            SpamCheck::deny("No invites")
+        );
+        assert!(registry.user_may_create_room("@a:example.org").await.is_allowed());
+    }
+
+    #[tokio::test]
+    async fn test_replacements_are_chained() {
+        let registry = ModuleRegistry::new()
+            .with_third_party_rules(Arc::new(AppendToTopic(" one")))
+            .with_third_party_rules(Arc::new(AppendToTopic(" two")));
+        let event = json!({"type": "m.room.topic", "state_key": "", "content": {"topic": "zero"}});
+
+        assert_eq!(
+            registry.check_state_event_allowed(&event).await,
+            EventRuling::Replace { content: json!({"topic": "zero one two"}) }
+        );
+    }
+
+    #[test]
+    fn test_unknown_modules_fail_to_load() {
+        let configs = vec![ModuleConfig { module: "missing".to_string(), config: Value::Null }];
+
+        assert!(matches!(
+            ModuleRegistry::load(&configs, &ModuleFactories::builtin()),
+            Err(ModuleError::UnknownModule(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_custom_factories_are_loaded_by_name() -> Result<(), ModuleError> {
+        fn deny_invites(_config: &Value) -> Result<LoadedModule, ModuleError> {
+            Ok(LoadedModule::spam_checker(DenyInvites))
+        }
+        let factories = ModuleFactories::builtin().with("deny_invites", deny_invites);
+        let configs = vec![ModuleConfig {
+            module: "deny_invites".to_string(),
+            config: Value::Null,
+        }];
+
+        let registry = ModuleRegistry::load(&configs, &factories)?;
+        assert_eq!(
+            registry
+                .user_may_invite("@a:example.org", "@b:example.org", "!r:example.org")
+                .await,
+            SpamCheck::deny("No invites")
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+//! Spam-checker callbacks
+//!
+//! Every callback defaults to allowing, so a module only implements the
+//! checks it cares about.
+
+use async_trait::async_trait;
+use serde_json::Value;
+
+/// The outcome of a spam check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpamCheck {
+    Allow,
+    Deny { reason: String },
+}
+
+impl SpamCheck {
+    pub fn deny(reason: impl Into<String>) -> Self {
+        Self::Deny { reason: reason.into() }
+    }
+
+    pub fn is_allowed(&self) -> bool {
+        matches!(self, Self::Allow)
+    }
+}
+
+/// A registration attempt, before the account exists
+#[derive(Debug, Clone, Copy)]
+pub struct RegistrationInfo<'a> {
+    pub username: &'a str,
+    pub client_ip: Option<&'a str>,
+    pub user_agent: Option<&'a str>,
+}
+
+/// An uploaded file, before it is stored
+#[derive(Debug, Clone, Copy)]
+pub struct MediaFileInfo<'a> {
+    pub uploader: &'a str,
+    pub content_type: &'a str,
+    pub upload_name: Option<&'a str>,
+    pub content: &'a [u8],
+}
+
+/// Decides whether actions are spam before the server carries them out
+#[async_trait]
+pub trait SpamChecker: Send + Sync {
+    /// Called for events sent by local clients and received over federation
+    ///
+    /// The event is in client format: `type`, `room_id`, `sender`, `content`
+    /// and `state_key` for state events. A denied federated event is still
+    /// stored, soft-failed, so the room's history stays whole, but it is
+    /// kept out of sync and never becomes a forward extremity.
+    async fn check_event_for_spam(&self, _event: &Value) -> SpamCheck {
+        SpamCheck::Allow
+    }
+
+    async fn user_may_invite(&self, _inviter: &str, _invitee: &str, _room_id: &str) -> SpamCheck {
+        SpamCheck::Allow
+    }
+
+    async fn user_may_join_room(
+        &self,
+        _user_id: &str,
+        _room_id: &str,
+        _is_invited: bool,
+    ) -> SpamCheck {
+        SpamCheck::Allow
+    }
+
+    async fn user_may_create_room(&self, _user_id: &str) -> SpamCheck {
+        SpamCheck::Allow
+    }
+
+    /// Called when a room is added to the public room directory
+    async fn user_may_publish_room(&self, _user_id: &str, _room_id: &str) -> SpamCheck {
+        SpamCheck::Allow
+    }
+
+    async fn check_registration_for_spam(&self, _registration: RegistrationInfo<'_>) -> SpamCheck {
+        SpamCheck::Allow
+    }
+
+    async fn check_media_file_for_spam(&self, _file: MediaFileInfo<'_>) -> SpamCheck {
+        SpamCheck::Allow
+    }
+}
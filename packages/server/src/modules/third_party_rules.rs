@@ -0,0 +1,42 @@
+//! Third-party event rules
+//!
+//! Rules run after the room's own authorization, so they can only narrow
+//! what the Matrix auth rules already allow.
+
+use async_trait::async_trait;
+use serde_json::Value;
+
+/// The outcome of a third-party rule check
+#[derive(Debug, Clone, PartialEq)]
+pub enum EventRuling {
+    Allow,
+    Deny {
+        reason: String,
+    },
+    /// Allow the event with this content instead
+    ///
+    /// Only honoured for events created locally: a federated event is
+    /// already signed and hashed, so rewriting it would invalidate it.
+    Replace {
+        content: Value,
+    },
+}
+
+impl EventRuling {
+    pub fn deny(reason: impl Into<String>) -> Self {
+        Self::Deny { reason: reason.into() }
+    }
+}
+
+/// Server-specific rules over which state events may enter a room
+#[async_trait]
+pub trait ThirdPartyRules: Send + Sync {
+    /// Called for state events sent by local clients, in the initial state
+    /// of new rooms, and for state events received over federation
+    ///
+    /// The event is in the same client format given to
+    /// [`super::SpamChecker::check_event_for_spam`].
+    async fn check_state_event_allowed(&self, _event: &Value) -> EventRuling {
+        EventRuling::Allow
+    }
+}
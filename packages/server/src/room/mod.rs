@@ -4,9 +4,13 @@ pub mod live_membership;
 pub mod membership_errors;
 pub mod membership_validation;
 pub mod power_levels;
+pub mod retention;
+pub mod upgrade;
+pub mod visibility;
 
 pub use alias_resolution::*;
 pub use authorization::*;
 pub use live_membership::*;
 
 pub use power_levels::*;
+pub use visibility::*;
@@ -4,9 +4,9 @@
 use std::sync::Arc;
 
 use axum::http::StatusCode;
-use serde_json::Value;
 use tracing::{debug, error, info, warn};
 
+use matryx_entity::types::PowerLevels;
 use matryx_surrealdb::repository::{MembershipRepository, RoomRepository};
 
 /// Power Level Validation Engine for Matrix room authorization
@@ -71,8 +71,7 @@ impl PowerLevelValidator {
         let power_levels = self.get_room_power_levels(room_id).await?;
 
         let inviter_level = self.get_user_power_level(&power_levels, inviter_id);
-        let required_invite_level =
-            power_levels.get("invite").and_then(|v| v.as_i64()).unwrap_or(0); // Default invite level is 0
+        let required_invite_level = power_levels.invite;
 
         // Check if inviter has sufficient power to invite
         if inviter_level < required_invite_level {
@@ -132,7 +131,7 @@ impl PowerLevelValidator {
 
         let kicker_level = self.get_user_power_level(&power_levels, kicker_id);
         let target_level = self.get_user_power_level(&power_levels, target_id);
-        let required_kick_level = power_levels.get("kick").and_then(|v| v.as_i64()).unwrap_or(50); // Default kick level is 50
+        let required_kick_level = power_levels.kick;
 
         // Check if kicker has sufficient power to kick
         if kicker_level < required_kick_level {
@@ -190,7 +189,7 @@ impl PowerLevelValidator {
 
         let banner_level = self.get_user_power_level(&power_levels, banner_id);
         let target_level = self.get_user_power_level(&power_levels, target_id);
-        let required_ban_level = power_levels.get("ban").and_then(|v| v.as_i64()).unwrap_or(50); // Default ban level is 50
+        let required_ban_level = power_levels.ban;
 
         // Check if banner has sufficient power to ban
         if banner_level < required_ban_level {
@@ -333,8 +332,7 @@ impl PowerLevelValidator {
         let power_levels = self.get_room_power_levels(room_id).await?;
 
         let redactor_level = self.get_user_power_level(&power_levels, redactor_id);
-        let required_redact_level =
-            power_levels.get("redact").and_then(|v| v.as_i64()).unwrap_or(50); // Default redact level is 50
+        let required_redact_level = power_levels.redact;
 
         if redactor_level < required_redact_level {
             warn!(
@@ -362,21 +360,8 @@ impl PowerLevelValidator {
     ///
     /// # Returns
     /// * `i64` - The user's effective power level in the room
-    pub fn get_user_power_level(&self, power_levels: &Value, user_id: &str) -> i64 {
-        // Check for user-specific power level
-        if let Some(users) = power_levels.get("users").and_then(|u| u.as_object())
-            && let Some(level) = users.get(user_id).and_then(|v| v.as_i64())
-        {
-            return level;
-        }
-
-        // Fall back to users_default
-        if let Some(default_level) = power_levels.get("users_default").and_then(|v| v.as_i64()) {
-            return default_level;
-        }
-
-        // Final fallback to 0 (standard Matrix default)
-        0
+    pub fn get_user_power_level(&self, power_levels: &PowerLevels, user_id: &str) -> i64 {
+        power_levels.get_user_level(user_id)
     }
 
     /// Get the current power levels configuration for a room
@@ -388,29 +373,15 @@ impl PowerLevelValidator {
     /// * `room_id` - The room to get power levels for
     ///
     /// # Returns
-    /// * `Result<Value, StatusCode>` - The power levels configuration object
+    /// * `Result<PowerLevels, StatusCode>` - The power levels configuration
     ///
     /// # Errors
     /// * `StatusCode::INTERNAL_SERVER_ERROR` - Repository error
-    async fn get_room_power_levels(&self, room_id: &str) -> Result<Value, StatusCode> {
+    async fn get_room_power_levels(&self, room_id: &str) -> Result<PowerLevels, StatusCode> {
         match self.room_repo.get_room_power_levels(room_id).await {
             Ok(power_levels) => {
                 debug!("Found power levels configuration for room {}", room_id);
-
-                // Convert PowerLevels struct to JSON Value for compatibility
-                let power_levels_json = serde_json::json!({
-                    "users": power_levels.users,
-                    "users_default": power_levels.users_default,
-                    "events": power_levels.events,
-                    "events_default": power_levels.events_default,
-                    "state_default": power_levels.state_default,
-                    "ban": power_levels.ban,
-                    "kick": power_levels.kick,
-                    "redact": power_levels.redact,
-                    "invite": power_levels.invite
-                });
-
-                Ok(power_levels_json)
+                Ok(power_levels)
             },
             Err(e) => {
                 error!("Failed to get power levels for room {}: {:?}", room_id, e);
@@ -443,7 +414,7 @@ impl PowerLevelValidator {
 
         // First get room power levels configuration
         let power_levels = self.get_room_power_levels(&room_id).await?;
-        let users_default = power_levels.get("users_default").and_then(|v| v.as_i64()).unwrap_or(0);
+        let users_default = power_levels.users_default;
 
         // Use direct database connection for optimized bulk query
         let query = "
@@ -490,19 +461,9 @@ impl PowerLevelValidator {
     /// state event exists in a room.
     ///
     /// # Returns
-    /// * `Value` - Default power levels configuration per Matrix specification
-    fn get_default_power_levels(&self) -> Value {
-        serde_json::json!({
-            "users_default": 0,
-            "events_default": 0,
-            "state_default": 50,
-            "ban": 50,
-            "kick": 50,
-            "redact": 50,
-            "invite": 0,
-            "users": {},
-            "events": {}
-        })
+    /// * `PowerLevels` - Default power levels configuration per Matrix specification
+    fn get_default_power_levels(&self) -> PowerLevels {
+        PowerLevels { invite: 0, ..PowerLevels::default() }
     }
 
     /// Get required power level for a specific state event type
@@ -519,19 +480,11 @@ impl PowerLevelValidator {
     /// * `i64` - Required power level for the state event modification
     fn get_state_event_required_level(
         &self,
-        power_levels: &Value,
+        power_levels: &PowerLevels,
         event_type: &str,
         _state_key: &str,
     ) -> i64 {
-        // Check for event-type specific power level
-        if let Some(events) = power_levels.get("events").and_then(|e| e.as_object())
-            && let Some(level) = events.get(event_type).and_then(|v| v.as_i64())
-        {
-            return level;
-        }
-
-        // Fall back to state_default
-        power_levels.get("state_default").and_then(|v| v.as_i64()).unwrap_or(50) // Matrix default state_default is 50
+        power_levels.get_state_event_level(event_type)
     }
 
     /// Validate power level hierarchy for user-to-user operations
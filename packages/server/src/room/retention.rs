@@ -0,0 +1,199 @@
+//! Message retention and history purging (MSC1763)
+//!
+//! A room's `m.room.retention` state sets how long its messages are kept,
+//! falling back to the server's default policy field by field. The purge
+//! job deletes non-state events older than the room's lifetime, and admins
+//! can purge history before a timestamp or event on demand. State events
+//! and the room's latest event are never purged, so the room's state and
+//! the forward extremity of its DAG survive.
+
+use std::collections::HashSet;
+
+use matryx_entity::types::RoomRetentionContent;
+use matryx_surrealdb::repository::error::RepositoryError;
+use matryx_surrealdb::repository::{MediaRepository, PurgedHistory, RetentionRepository};
+use surrealdb::{Surreal, engine::any::Any};
+use tracing::{debug, info, warn};
+
+use crate::config::RetentionConfig;
+
+/// The retention policy in force in a room
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    pub min_lifetime: Option<u64>,
+    pub max_lifetime: Option<u64>,
+}
+
+impl RetentionPolicy {
+    /// Combine the room's policy with the server default
+    pub fn resolve(room: Option<&RoomRetentionContent>, config: &RetentionConfig) -> Self {
+        Self {
+            min_lifetime: room
+                .and_then(|policy| policy.min_lifetime)
+                .or(config.default_min_lifetime_ms),
+            max_lifetime: room
+                .and_then(|policy| policy.max_lifetime)
+                .or(config.default_max_lifetime_ms),
+        }
+    }
+
+    /// Timestamp before which events may be purged, if they expire at all
+    ///
+    /// An event is kept for at least `min_lifetime` even when a shorter
+    /// `max_lifetime` is set.
+    pub fn purge_before(&self, now_ms: i64) -> Option<i64> {
+        let lifetime = self.max_lifetime?.max(self.min_lifetime.unwrap_or(0));
+        let lifetime = i64::try_from(lifetime).unwrap_or(i64::MAX);
+        Some(now_ms.saturating_sub(lifetime))
+    }
+}
+
+pub struct RetentionService {
+    retention_repo: RetentionRepository,
+    media_repo: MediaRepository<Any>,
+    config: &'static RetentionConfig,
+    homeserver_name: String,
+}
+
+impl RetentionService {
+    pub fn new(
+        db: Surreal<Any>,
+        config: &'static RetentionConfig,
+        homeserver_name: String,
+    ) -> Self {
+        Self {
+            retention_repo: RetentionRepository::new(db.clone()),
+            media_repo: MediaRepository::new(db),
+            config,
+            homeserver_name,
+        }
+    }
+
+    pub async fn room_policy(&self, room_id: &str) -> Result<RetentionPolicy, RepositoryError> {
+        let room = self.retention_repo.get_room_retention(room_id).await?;
+        Ok(RetentionPolicy::resolve(room.as_ref(), self.config))
+    }
+
+    /// Delete the room's non-state events sent before `before_ts`, and
+    /// the local media only they referenced
+    pub async fn purge_history(
+        &self,
+        room_id: &str,
+        before_ts: i64,
+    ) -> Result<PurgedHistory, RepositoryError> {
+        let purged = self.retention_repo.purge_events_before(room_id, before_ts).await?;
+        if purged.events_deleted > 0 {
+            info!(
+                "Purged {} events sent before {} from room {}",
+                purged.events_deleted, before_ts, room_id
+            );
+        }
+        self.delete_local_media(&purged.media_uris).await;
+        Ok(purged)
+    }
+
+    /// [`Self::purge_history`] up to an event, which is itself kept
+    ///
+    /// Returns `None` if the event is not in the room.
+    pub async fn purge_history_before_event(
+        &self,
+        room_id: &str,
+        event_id: &str,
+    ) -> Result<Option<PurgedHistory>, RepositoryError> {
+        let Some(before_ts) = self.retention_repo.get_event_timestamp(room_id, event_id).await?
+        else {
+            return Ok(None);
+        };
+        self.purge_history(room_id, before_ts).await.map(Some)
+    }
+
+    /// Purge expired history in every room, returning the events deleted
+    pub async fn purge_expired(&self) -> Result<usize, RepositoryError> {
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        let mut deleted = 0;
+
+        for room_id in self.retention_repo.get_room_ids().await? {
+            let policy = self.room_policy(&room_id).await?;
+            let Some(before_ts) = policy.purge_before(now_ms) else {
+                continue;
+            };
+            match self.purge_history(&room_id, before_ts).await {
+                Ok(purged) => deleted += purged.events_deleted,
+                Err(e) => warn!("Failed to purge expired history in room {}: {}", room_id, e),
+            }
+        }
+
+        Ok(deleted)
+    }
+
+    /// Delete media uploaded to this server that nothing else references;
+    /// remote media is only cached and expires on its own
+    async fn delete_local_media(&self, media_uris: &[String]) {
+        let mut seen = HashSet::new();
+        let local: Vec<(&str, &str)> = media_uris
+            .iter()
+            .filter_map(|uri| {
+                let (server_name, media_id) = uri.strip_prefix("mxc://")?.split_once('/')?;
+                (server_name == self.homeserver_name && seen.insert(media_id))
+                    .then_some((uri.as_str(), media_id))
+            })
+            .collect();
+        if local.is_empty() {
+            return;
+        }
+
+        let uris: Vec<String> = local.iter().map(|(uri, _)| uri.to_string()).collect();
+        let referenced = match self.retention_repo.get_referenced_media(&uris).await {
+            Ok(referenced) => referenced,
+            Err(e) => {
+                warn!("Failed to check references to purged media, keeping it: {}", e);
+                return;
+            },
+        };
+
+        for (uri, media_id) in local {
+            if referenced.contains(uri) {
+                debug!("Keeping purged media {}: still referenced", uri);
+                continue;
+            }
+            match self.media_repo.delete_media(media_id, &self.homeserver_name).await {
+                Ok(()) => debug!("Deleted purged media {}", uri),
+                Err(e) => warn!("Failed to delete purged media {}: {}", uri, e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(min: Option<u64>, max: Option<u64>) -> RetentionConfig {
+        RetentionConfig {
+            default_min_lifetime_ms: min,
+            default_max_lifetime_ms: max,
+            ..RetentionConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_room_policy_overrides_default_per_field() {
+        let room = RoomRetentionContent::new(None, Some(1_000));
+        let policy = RetentionPolicy::resolve(Some(&room), &config(Some(10), Some(5_000)));
+
+        assert_eq!(policy, RetentionPolicy { min_lifetime: Some(10), max_lifetime: Some(1_000) });
+        assert_eq!(
+            RetentionPolicy::resolve(None, &config(None, Some(5_000))).max_lifetime,
+            Some(5_000)
+        );
+    }
+
+    #[test]
+    fn test_purge_before_respects_min_lifetime() {
+        let keep_forever = RetentionPolicy { min_lifetime: Some(100), max_lifetime: None };
+        let short = RetentionPolicy { min_lifetime: Some(500), max_lifetime: Some(100) };
+
+        assert_eq!(keep_forever.purge_before(10_000), None);
+        assert_eq!(short.purge_before(10_000), Some(9_500));
+    }
+}
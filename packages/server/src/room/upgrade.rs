@@ -0,0 +1,367 @@
+//! Room upgrades
+//!
+//! [`RoomUpgradeService::upgrade`] runs the server side of a room upgrade:
+//! the replacement room is created with a `predecessor` link to the old
+//! room's tombstone, the transferable state and ban list are copied over,
+//! local aliases and the canonical alias move to the new room, the
+//! directory listing follows, and the old room's power levels are raised so
+//! ordinary members can no longer talk or invite there. Only then is the
+//! tombstone sent.
+//!
+//! When a local user later joins the replacement room,
+//! [`RoomUpgradeService::migrate_user_data`] carries their room tags, room
+//! push rules and `m.direct` entries over from the predecessor.
+
+use matryx_entity::types::RoomVersionRules;
+use matryx_surrealdb::repository::directory::RoomDirectoryVisibility;
+use matryx_surrealdb::repository::push::{PushCondition, PushRepository};
+use matryx_surrealdb::repository::{
+    AccountDataRepository, DirectoryRepository, EventRepository, MembershipRepository,
+    RoomAliasRepository, RoomRepository, TagsRepository, error::RepositoryError,
+    room_operations::RoomOperationsService,
+};
+use serde_json::{Value, json};
+use surrealdb::{Surreal, engine::any::Any};
+use tracing::{debug, info, warn};
+
+/// State copied into the replacement room, as the spec requires
+const TRANSFERABLE_STATE: &[&str] = &[
+    "m.room.server_acl",
+    "m.room.encryption",
+    "m.room.name",
+    "m.room.avatar",
+    "m.room.topic",
+    "m.room.guest_access",
+    "m.room.history_visibility",
+    "m.room.join_rules",
+    "m.room.power_levels",
+];
+
+pub struct RoomUpgradeService {
+    event_repo: EventRepository,
+    room_repo: RoomRepository,
+    membership_repo: MembershipRepository,
+    alias_repo: RoomAliasRepository,
+    directory_repo: DirectoryRepository<Any>,
+    tags_repo: TagsRepository,
+    push_repo: PushRepository<Any>,
+    account_data_repo: AccountDataRepository,
+    homeserver_name: String,
+}
+
+impl RoomUpgradeService {
+    pub fn new(db: Surreal<Any>, homeserver_name: String) -> Self {
+        Self {
+            event_repo: EventRepository::new(db.clone()),
+            room_repo: RoomRepository::new(db.clone()),
+            membership_repo: MembershipRepository::new(db.clone()),
+            alias_repo: RoomAliasRepository::new(db.clone()),
+            directory_repo: DirectoryRepository::new(db.clone()),
+            tags_repo: TagsRepository::new(db.clone()),
+            push_repo: PushRepository::new(db.clone()),
+            account_data_repo: AccountDataRepository::new(db),
+            homeserver_name,
+        }
+    }
+
+    /// Upgrade a room, returning the replacement room's ID
+    pub async fn upgrade(
+        &self,
+        room_operations: &RoomOperationsService<Any>,
+        room_id: &str,
+        new_version: &str,
+        user_id: &str,
+    ) -> Result<String, RepositoryError> {
+        if RoomVersionRules::for_version(new_version).is_none() {
+            return Err(RepositoryError::Validation {
+                field: "new_version".to_string(),
+                message: format!("Unsupported room version {}", new_version),
+            });
+        }
+
+        let upgrade = room_operations.upgrade_room(room_id, new_version, user_id).await?;
+        let new_room_id = upgrade.replacement_room;
+
+        // The tombstone goes out last: until then clients keep using the old
+        // room, so a failed step never points them at a half-migrated one
+        let migrated = async {
+            self.copy_state(room_id, &new_room_id, user_id).await?;
+            self.copy_bans(room_id, &new_room_id, user_id).await?;
+            self.move_aliases(room_id, &new_room_id, user_id).await?;
+            self.move_directory_listing(room_id, &new_room_id).await?;
+            self.restrict_old_room(room_id, user_id).await
+        }
+        .await;
+        if let Err(e) = migrated {
+            warn!(
+                "Upgrade of room {} failed before it was tombstoned, leaving {} unused: {}",
+                room_id, new_room_id, e
+            );
+            return Err(e);
+        }
+        self.room_repo.tombstone_room(room_id, &upgrade.tombstone).await?;
+
+        info!("Upgraded room {} to {} (version {})", room_id, new_room_id, new_version);
+        Ok(new_room_id)
+    }
+
+    async fn copy_state(
+        &self,
+        room_id: &str,
+        new_room_id: &str,
+        user_id: &str,
+    ) -> Result<(), RepositoryError> {
+        for event_type in TRANSFERABLE_STATE {
+            let Some(event) =
+                self.event_repo.get_current_state_event(room_id, event_type, "").await?
+            else {
+                continue;
+            };
+            let content = serde_json::to_value(&event.content)?;
+            self.event_repo
+                .create_room_event(new_room_id, event_type, user_id, content, Some(String::new()))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn copy_bans(
+        &self,
+        room_id: &str,
+        new_room_id: &str,
+        user_id: &str,
+    ) -> Result<(), RepositoryError> {
+        for banned in self.membership_repo.get_banned_users(room_id).await? {
+            let ban = self
+                .event_repo
+                .get_current_state_event(room_id, "m.room.member", &banned)
+                .await?;
+            let reason = match ban {
+                Some(event) => serde_json::to_value(&event.content)?
+                    .get("reason")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+                None => None,
+            };
+
+            self.membership_repo
+                .ban_user_from_room(new_room_id, &banned, user_id, reason.clone())
+                .await?;
+            self.event_repo
+                .create_room_event(
+                    new_room_id,
+                    "m.room.member",
+                    user_id,
+                    json!({ "membership": "ban", "reason": reason }),
+                    Some(banned),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Move local aliases, and the canonical alias, to the new room
+    async fn move_aliases(
+        &self,
+        room_id: &str,
+        new_room_id: &str,
+        user_id: &str,
+    ) -> Result<(), RepositoryError> {
+        let local_suffix = format!(":{}", self.homeserver_name);
+        for alias in self.room_repo.get_room_aliases(room_id).await? {
+            if alias.ends_with(&local_suffix) {
+                self.alias_repo.move_alias(&alias, new_room_id).await?;
+                debug!("Moved alias {} to {}", alias, new_room_id);
+            }
+        }
+
+        let Some(canonical) = self
+            .event_repo
+            .get_current_state_event(room_id, "m.room.canonical_alias", "")
+            .await?
+        else {
+            return Ok(());
+        };
+        let content = serde_json::to_value(&canonical.content)?;
+        if content.as_object().is_some_and(|content| content.is_empty()) {
+            return Ok(());
+        }
+        self.event_repo
+            .create_room_event(
+                new_room_id,
+                "m.room.canonical_alias",
+                user_id,
+                content,
+                Some(String::new()),
+            )
+            .await?;
+        self.event_repo
+            .create_room_event(
+                room_id,
+                "m.room.canonical_alias",
+                user_id,
+                json!({}),
+                Some(String::new()),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn move_directory_listing(
+        &self,
+        room_id: &str,
+        new_room_id: &str,
+    ) -> Result<(), RepositoryError> {
+        let visibility = self.directory_repo.get_room_directory_visibility(room_id).await?;
+        if matches!(visibility, RoomDirectoryVisibility::Public) {
+            self.directory_repo
+                .set_room_directory_visibility(new_room_id, RoomDirectoryVisibility::Public)
+                .await?;
+            self.directory_repo
+                .set_room_directory_visibility(room_id, RoomDirectoryVisibility::Private)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Stop ordinary members from sending events or inviting in the old room
+    async fn restrict_old_room(&self, room_id: &str, user_id: &str) -> Result<(), RepositoryError> {
+        let Some(power_levels) = self
+            .event_repo
+            .get_current_state_event(room_id, "m.room.power_levels", "")
+            .await?
+        else {
+            warn!("Room {} has no power levels, leaving it unrestricted after upgrade", room_id);
+            return Ok(());
+        };
+
+        let mut content = serde_json::to_value(&power_levels.content)?;
+        restrict_power_levels(&mut content);
+        self.event_repo
+            .create_room_event(
+                room_id,
+                "m.room.power_levels",
+                user_id,
+                content,
+                Some(String::new()),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Carry a user's per-room data over when they join an upgraded room
+    ///
+    /// Does nothing for rooms without a predecessor.
+    pub async fn migrate_user_data(
+        &self,
+        user_id: &str,
+        room_id: &str,
+    ) -> Result<(), RepositoryError> {
+        let Some(create) = self
+            .event_repo
+            .get_current_state_event(room_id, "m.room.create", "")
+            .await?
+        else {
+            return Ok(());
+        };
+        let content = serde_json::to_value(&create.content)?;
+        let Some(predecessor) = content.pointer("/predecessor/room_id").and_then(Value::as_str)
+        else {
+            return Ok(());
+        };
+
+        for (tag, tag_content) in self.tags_repo.get_room_tags(user_id, predecessor).await? {
+            self.tags_repo
+                .set_room_tag(user_id, room_id, &tag, Some(tag_content))
+                .await?;
+        }
+
+        for rule in self.push_repo.get_user_push_rules(user_id).await? {
+            if !rule.rule_id.contains(predecessor) {
+                continue;
+            }
+            let mut rule = rule;
+            rule.rule_id = rule.rule_id.replace(predecessor, room_id);
+            if self.push_repo.get_push_rule_by_id(user_id, &rule.rule_id).await?.is_some() {
+                continue;
+            }
+            for condition in &mut rule.conditions {
+                if let PushCondition::EventMatch { pattern, .. } = condition
+                    && pattern == predecessor
+                {
+                    *pattern = room_id.to_string();
+                }
+            }
+            self.push_repo.create_push_rule(user_id, &rule).await?;
+        }
+
+        if let Some(mut direct) =
+            self.account_data_repo.get_global_account_data(user_id, "m.direct").await?
+            && add_direct_room(&mut direct, predecessor, room_id)
+        {
+            self.account_data_repo
+                .set_global_account_data(user_id, "m.direct", direct)
+                .await?;
+        }
+
+        debug!("Migrated data of {} from {} to {}", user_id, predecessor, room_id);
+        Ok(())
+    }
+}
+
+/// Raise `events_default` and `invite` above ordinary members' power
+fn restrict_power_levels(content: &mut Value) {
+    let users_default = content.get("users_default").and_then(Value::as_i64).unwrap_or(0);
+    let restricted = 50.max(users_default + 1);
+    for key in ["events_default", "invite"] {
+        let current = content.get(key).and_then(Value::as_i64).unwrap_or(0);
+        if current < restricted {
+            content[key] = json!(restricted);
+        }
+    }
+}
+
+/// Add `new_room_id` to every `m.direct` entry listing `old_room_id`,
+/// returning whether anything changed
+fn add_direct_room(direct: &mut Value, old_room_id: &str, new_room_id: &str) -> bool {
+    let Some(entries) = direct.as_object_mut() else {
+        return false;
+    };
+    let mut changed = false;
+    for rooms in entries.values_mut().filter_map(Value::as_array_mut) {
+        let lists_old = rooms.iter().any(|room| room == old_room_id);
+        let lists_new = rooms.iter().any(|room| room == new_room_id);
+        if lists_old && !lists_new {
+            rooms.push(json!(new_room_id));
+            changed = true;
+        }
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restrict_power_levels() {
+        let mut content = json!({ "users_default": 60, "events_default": 0, "invite": 100 });
+        restrict_power_levels(&mut content);
+
+        assert_eq!(content["events_default"], 61);
+        assert_eq!(content["invite"], 100);
+    }
+
+    #[test]
+    fn test_add_direct_room() {
+        let mut direct = json!({
+            "@alice:example.org": ["!old:example.org"],
+            "@bob:example.org": ["!other:example.org"]
+        });
+
+        assert!(add_direct_room(&mut direct, "!old:example.org", "!new:example.org"));
+        assert_eq!(direct["@alice:example.org"], json!(["!old:example.org", "!new:example.org"]));
+        assert_eq!(direct["@bob:example.org"], json!(["!other:example.org"]));
+        assert!(!add_direct_room(&mut direct, "!old:example.org", "!new:example.org"));
+    }
+}
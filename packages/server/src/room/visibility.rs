@@ -0,0 +1,636 @@
+//! Event visibility for clients and federation
+//!
+//! Every endpoint that returns room events asks [`EventVisibilityService`]
+//! which of them the requester may see. For a user this depends on
+//! `m.room.history_visibility` and the user's membership at each event,
+//! their current membership, and their `m.ignored_user_list`; for a server
+//! it depends on the history visibility and the membership of the server's
+//! users at each event.
+//!
+//! Decisions are cached per room, state group and viewer. The state group
+//! of an event is the set of visibility and membership events in force at
+//! it, so cached decisions never go stale: new state makes new groups.
+
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::Duration;
+
+use matryx_entity::types::{Event, HistoryVisibility, MembershipState, PDU};
+use matryx_surrealdb::repository::error::RepositoryError;
+use matryx_surrealdb::repository::{StateChange, VisibilityRepository};
+use moka::future::Cache;
+use serde_json::Value;
+use surrealdb::{Surreal, engine::any::Any};
+use tracing::debug;
+
+const HISTORY_VISIBILITY_EVENT_TYPE: &str = "m.room.history_visibility";
+const MEMBER_EVENT_TYPE: &str = "m.room.member";
+
+/// The parts of an event its visibility depends on
+///
+/// Implemented for stored [`Event`]s, federation [`PDU`]s and events
+/// already serialized to JSON, so endpoints can filter whatever they hold.
+pub trait VisibilityEvent {
+    fn event_id(&self) -> Option<&str>;
+    fn room_id(&self) -> Option<&str>;
+    fn sender(&self) -> Option<&str>;
+    fn event_type(&self) -> Option<&str>;
+    fn state_key(&self) -> Option<&str>;
+    /// Depth in the room DAG, if known
+    fn depth(&self) -> Option<i64>;
+    fn origin_server_ts(&self) -> i64;
+    /// A string field of the content, e.g. `membership`
+    fn content_field(&self, key: &str) -> Option<String>;
+}
+
+impl VisibilityEvent for Event {
+    fn event_id(&self) -> Option<&str> {
+        Some(&self.event_id)
+    }
+
+    fn room_id(&self) -> Option<&str> {
+        Some(&self.room_id)
+    }
+
+    fn sender(&self) -> Option<&str> {
+        Some(&self.sender)
+    }
+
+    fn event_type(&self) -> Option<&str> {
+        Some(&self.event_type)
+    }
+
+    fn state_key(&self) -> Option<&str> {
+        self.state_key.as_deref()
+    }
+
+    fn depth(&self) -> Option<i64> {
+        self.depth
+    }
+
+    fn origin_server_ts(&self) -> i64 {
+        self.origin_server_ts
+    }
+
+    fn content_field(&self, key: &str) -> Option<String> {
+        let content = serde_json::to_value(&self.content).ok()?;
+        content.get(key)?.as_str().map(str::to_string)
+    }
+}
+
+impl VisibilityEvent for PDU {
+    fn event_id(&self) -> Option<&str> {
+        Some(&self.event_id)
+    }
+
+    fn room_id(&self) -> Option<&str> {
+        Some(&self.room_id)
+    }
+
+    fn sender(&self) -> Option<&str> {
+        Some(&self.sender)
+    }
+
+    fn event_type(&self) -> Option<&str> {
+        Some(&self.event_type)
+    }
+
+    fn state_key(&self) -> Option<&str> {
+        self.state_key.as_deref()
+    }
+
+    fn depth(&self) -> Option<i64> {
+        Some(self.depth)
+    }
+
+    fn origin_server_ts(&self) -> i64 {
+        self.origin_server_ts
+    }
+
+    fn content_field(&self, key: &str) -> Option<String> {
+        let content = serde_json::to_value(&self.content).ok()?;
+        content.get(key)?.as_str().map(str::to_string)
+    }
+}
+
+impl VisibilityEvent for Value {
+    fn event_id(&self) -> Option<&str> {
+        self.get("event_id")?.as_str()
+    }
+
+    fn room_id(&self) -> Option<&str> {
+        self.get("room_id")?.as_str()
+    }
+
+    fn sender(&self) -> Option<&str> {
+        self.get("sender")?.as_str()
+    }
+
+    fn event_type(&self) -> Option<&str> {
+        self.get("type").or_else(|| self.get("event_type"))?.as_str()
+    }
+
+    fn state_key(&self) -> Option<&str> {
+        self.get("state_key")?.as_str()
+    }
+
+    fn depth(&self) -> Option<i64> {
+        self.get("depth")?.as_i64()
+    }
+
+    fn origin_server_ts(&self) -> i64 {
+        self.get("origin_server_ts").and_then(Value::as_i64).unwrap_or(0)
+    }
+
+    fn content_field(&self, key: &str) -> Option<String> {
+        self.get("content")?.get(key)?.as_str().map(str::to_string)
+    }
+}
+
+/// Whether a user may see an event
+///
+/// `membership` is the user's membership when the event was sent and
+/// `peeking` whether the user is not joined to the room now, following the
+/// history visibility rules of the Client-Server API.
+pub fn user_may_see_event(
+    visibility: HistoryVisibility,
+    membership: Option<&MembershipState>,
+    peeking: bool,
+) -> bool {
+    if visibility == HistoryVisibility::WorldReadable ||
+        membership == Some(&MembershipState::Join)
+    {
+        return true;
+    }
+
+    match visibility {
+        HistoryVisibility::Joined => false,
+        HistoryVisibility::Invited => membership == Some(&MembershipState::Invite),
+        // Shared history is for members, not for users who left or peek
+        HistoryVisibility::Shared => !peeking,
+        HistoryVisibility::WorldReadable => true,
+    }
+}
+
+/// Whether a server may see an event, given the memberships of its users
+/// when the event was sent
+pub fn server_may_see_event(
+    visibility: HistoryVisibility,
+    memberships: &[MembershipState],
+) -> bool {
+    match visibility {
+        HistoryVisibility::WorldReadable | HistoryVisibility::Shared => true,
+        HistoryVisibility::Invited => memberships.iter().any(|membership| {
+            matches!(membership, MembershipState::Join | MembershipState::Invite)
+        }),
+        HistoryVisibility::Joined => memberships.contains(&MembershipState::Join),
+    }
+}
+
+/// Rank of a visibility, higher is more permissive
+fn permissiveness(visibility: HistoryVisibility) -> u8 {
+    match visibility {
+        HistoryVisibility::Joined => 0,
+        HistoryVisibility::Invited => 1,
+        HistoryVisibility::Shared => 2,
+        HistoryVisibility::WorldReadable => 3,
+    }
+}
+
+/// The state an event's visibility depends on, by event ID
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct StateGroup {
+    history_visibility: Vec<String>,
+    memberships: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Viewer {
+    User { user_id: String, peeking: bool },
+    Server(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct VisibilityKey {
+    room_id: String,
+    state_group: StateGroup,
+    viewer: Viewer,
+}
+
+/// Visibility and membership history of a room, as seen by one viewer
+struct RoomHistory {
+    history_visibility: Vec<StateChange>,
+    /// Membership events of the user, or of the server's users
+    memberships: Vec<StateChange>,
+}
+
+/// Whether a state change happened before an event
+///
+/// Events are ordered by depth, then timestamp, then ID, as the state at an
+/// event is resolved elsewhere. Events without a depth, like those already
+/// in client format, are placed by timestamp.
+fn precedes(change: &StateChange, event: &impl VisibilityEvent) -> bool {
+    let event_id = event.event_id().unwrap_or_default();
+    match event.depth() {
+        Some(depth) => {
+            (change.position(), change.event_id.as_str()) <
+                ((depth, event.origin_server_ts()), event_id)
+        },
+        None => change.origin_server_ts < event.origin_server_ts(),
+    }
+}
+
+fn latest_before<'a>(
+    changes: &'a [StateChange],
+    event: &impl VisibilityEvent,
+) -> Option<&'a StateChange> {
+    changes.iter().filter(|change| precedes(change, event)).last()
+}
+
+fn membership_of(content: &Value) -> Option<MembershipState> {
+    serde_json::from_value(content.get("membership")?.clone()).ok()
+}
+
+fn visibility_of(content: &Value) -> HistoryVisibility {
+    content
+        .get("history_visibility")
+        .and_then(Value::as_str)
+        .map(HistoryVisibility::from)
+        .unwrap_or(HistoryVisibility::Shared)
+}
+
+impl RoomHistory {
+    /// Visibility in force at an event, and the events it comes from
+    ///
+    /// A visibility change is judged by the more permissive of the old and
+    /// new setting, so the change itself is seen on both sides of it.
+    fn visibility_at(&self, event: &impl VisibilityEvent) -> (HistoryVisibility, Vec<String>) {
+        let before = latest_before(&self.history_visibility, event);
+        let mut visibility = before
+            .map(|change| visibility_of(&change.content))
+            .unwrap_or(HistoryVisibility::Shared);
+        let mut group: Vec<String> =
+            before.map(|change| change.event_id.clone()).into_iter().collect();
+
+        if event.event_type() == Some(HISTORY_VISIBILITY_EVENT_TYPE) &&
+            event.state_key() == Some("")
+        {
+            let new = event
+                .content_field("history_visibility")
+                .map(HistoryVisibility::from)
+                .unwrap_or(HistoryVisibility::Shared);
+            if permissiveness(new) > permissiveness(visibility) {
+                visibility = new;
+            }
+            group.extend(event.event_id().map(str::to_string));
+        }
+
+        (visibility, group)
+    }
+
+    /// A user's membership at an event
+    ///
+    /// For the user's own membership events the membership after the event
+    /// counts, so users see their own joins, invites and leaves.
+    fn user_membership_at(
+        &self,
+        user_id: &str,
+        event: &impl VisibilityEvent,
+    ) -> (Option<MembershipState>, Vec<String>) {
+        if event.event_type() == Some(MEMBER_EVENT_TYPE) && event.state_key() == Some(user_id) {
+            let membership = event
+                .content_field("membership")
+                .and_then(|membership| serde_json::from_value(Value::String(membership)).ok());
+            return (membership, event.event_id().map(str::to_string).into_iter().collect());
+        }
+
+        match latest_before(&self.memberships, event) {
+            Some(change) => (membership_of(&change.content), vec![change.event_id.clone()]),
+            None => (None, Vec::new()),
+        }
+    }
+
+    /// Memberships of a server's users at an event
+    fn server_memberships_at(
+        &self,
+        event: &impl VisibilityEvent,
+    ) -> (Vec<MembershipState>, Vec<String>) {
+        let mut latest: BTreeMap<&str, &StateChange> = BTreeMap::new();
+        for change in self.memberships.iter().filter(|change| precedes(change, event)) {
+            latest.insert(&change.state_key, change);
+        }
+
+        let memberships = latest
+            .values()
+            .filter_map(|change| membership_of(&change.content))
+            .collect();
+        let group = latest.values().map(|change| change.event_id.clone()).collect();
+        (memberships, group)
+    }
+
+    /// Whether the user is not joined to the room now
+    fn peeking(&self) -> bool {
+        self.memberships
+            .last()
+            .and_then(|change| membership_of(&change.content))
+            .is_none_or(|membership| membership != MembershipState::Join)
+    }
+}
+
+/// Decides which room events users and servers may see
+pub struct EventVisibilityService {
+    visibility_repo: VisibilityRepository,
+    decisions: Cache<VisibilityKey, bool>,
+}
+
+impl EventVisibilityService {
+    pub fn new(db: Surreal<Any>) -> Self {
+        Self {
+            visibility_repo: VisibilityRepository::new(db),
+            decisions: Cache::builder()
+                .max_capacity(100_000)
+                .time_to_idle(Duration::from_secs(30 * 60))
+                .build(),
+        }
+    }
+
+    /// Keep the events a user may see, in order
+    pub async fn filter_events_for_client<E: VisibilityEvent>(
+        &self,
+        user_id: &str,
+        events: Vec<E>,
+    ) -> Result<Vec<E>, RepositoryError> {
+        let visible = self.visible_to_client(user_id, &events).await?;
+        Ok(events
+            .into_iter()
+            .zip(visible)
+            .filter_map(|(event, visible)| visible.then_some(event))
+            .collect())
+    }
+
+    /// Whether a user may see an event
+    pub async fn can_user_see_event<E: VisibilityEvent>(
+        &self,
+        user_id: &str,
+        event: &E,
+    ) -> Result<bool, RepositoryError> {
+        let visible = self.visible_to_client(user_id, std::slice::from_ref(event)).await?;
+        Ok(visible.first().copied().unwrap_or(false))
+    }
+
+    /// Which of the events a user may see
+    ///
+    /// Non-state events from users the viewer ignores are hidden; state
+    /// events are not, as clients need them to render the room.
+    pub async fn visible_to_client<E: VisibilityEvent>(
+        &self,
+        user_id: &str,
+        events: &[E],
+    ) -> Result<Vec<bool>, RepositoryError> {
+        let ignored = self.visibility_repo.get_ignored_users(user_id).await?;
+        let mut histories: HashMap<String, RoomHistory> = HashMap::new();
+        let mut visible = Vec::with_capacity(events.len());
+
+        for event in events {
+            let Some(room_id) = event.room_id() else {
+                visible.push(false);
+                continue;
+            };
+            if event.state_key().is_none() &&
+                event.sender().is_some_and(|sender| ignored.contains(sender))
+            {
+                visible.push(false);
+                continue;
+            }
+
+            let history = match histories.entry(room_id.to_string()) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(RoomHistory {
+                    history_visibility: self
+                        .visibility_repo
+                        .get_history_visibility_changes(room_id)
+                        .await?,
+                    memberships: self
+                        .visibility_repo
+                        .get_membership_changes(room_id, user_id)
+                        .await?,
+                }),
+            };
+
+            let (visibility, history_visibility) = history.visibility_at(event);
+            let (membership, memberships) = history.user_membership_at(user_id, event);
+            let peeking = history.peeking();
+            let key = VisibilityKey {
+                room_id: room_id.to_string(),
+                state_group: StateGroup { history_visibility, memberships },
+                viewer: Viewer::User { user_id: user_id.to_string(), peeking },
+            };
+
+            let decision = self
+                .decisions
+                .get_with(key, async {
+                    user_may_see_event(visibility, membership.as_ref(), peeking)
+                })
+                .await;
+            visible.push(decision);
+        }
+
+        Ok(visible)
+    }
+
+    /// Keep the events a server may see, in order
+    pub async fn filter_events_for_server<E: VisibilityEvent>(
+        &self,
+        server_name: &str,
+        events: Vec<E>,
+    ) -> Result<Vec<E>, RepositoryError> {
+        let visible = self.visible_to_server(server_name, &events).await?;
+        Ok(events
+            .into_iter()
+            .zip(visible)
+            .filter_map(|(event, visible)| visible.then_some(event))
+            .collect())
+    }
+
+    /// Whether a server may see an event
+    pub async fn can_server_see_event<E: VisibilityEvent>(
+        &self,
+        server_name: &str,
+        event: &E,
+    ) -> Result<bool, RepositoryError> {
+        let visible = self.visible_to_server(server_name, std::slice::from_ref(event)).await?;
+        Ok(visible.first().copied().unwrap_or(false))
+    }
+
+    /// Which of the events a server may see
+    pub async fn visible_to_server<E: VisibilityEvent>(
+        &self,
+        server_name: &str,
+        events: &[E],
+    ) -> Result<Vec<bool>, RepositoryError> {
+        let mut histories: HashMap<String, RoomHistory> = HashMap::new();
+        let mut visible = Vec::with_capacity(events.len());
+
+        for event in events {
+            let Some(room_id) = event.room_id() else {
+                visible.push(false);
+                continue;
+            };
+
+            let history = match histories.entry(room_id.to_string()) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(RoomHistory {
+                    history_visibility: self
+                        .visibility_repo
+                        .get_history_visibility_changes(room_id)
+                        .await?,
+                    memberships: self
+                        .visibility_repo
+                        .get_server_membership_changes(room_id, server_name)
+                        .await?,
+                }),
+            };
+
+            let (visibility, history_visibility) = history.visibility_at(event);
+            let (memberships, membership_ids) = history.server_memberships_at(event);
+            let key = VisibilityKey {
+                room_id: room_id.to_string(),
+                state_group: StateGroup { history_visibility, memberships: membership_ids },
+                viewer: Viewer::Server(server_name.to_string()),
+            };
+
+            let decision = self
+                .decisions
+                .get_with(key, async { server_may_see_event(visibility, &memberships) })
+                .await;
+            if !decision {
+                debug!("Event {:?} is not visible to server {}", event.event_id(), server_name);
+            }
+            visible.push(decision);
+        }
+
+        Ok(visible)
+    }
+
+    /// Users a user ignores
+    pub async fn ignored_users(&self, user_id: &str) -> Result<HashSet<String>, RepositoryError> {
+        self.visibility_repo.get_ignored_users(user_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn change(event_id: &str, state_key: &str, depth: i64, content: Value) -> StateChange {
+        StateChange {
+            event_id: event_id.to_string(),
+            state_key: state_key.to_string(),
+            depth: Some(depth),
+            origin_server_ts: depth * 1000,
+            content,
+        }
+    }
+
+    fn message(event_id: &str, depth: i64) -> Value {
+        json!({
+            "event_id": event_id,
+            "room_id": "!room:example.org",
+            "sender": "@bob:example.org",
+            "type": "m.room.message",
+            "depth": depth,
+            "origin_server_ts": depth * 1000,
+            "content": { "body": "hi" },
+        })
+    }
+
+    #[test]
+    fn test_user_visibility_rules() {
+        use HistoryVisibility::*;
+        use MembershipState::*;
+
+        assert!(user_may_see_event(WorldReadable, None, true));
+        assert!(user_may_see_event(Joined, Some(&Join), true));
+        assert!(!user_may_see_event(Joined, Some(&Invite), false));
+        assert!(user_may_see_event(Invited, Some(&Invite), false));
+        assert!(!user_may_see_event(Invited, None, false));
+        // Shared history before joining is visible to members only
+        assert!(user_may_see_event(Shared, None, false));
+        assert!(!user_may_see_event(Shared, Some(&Leave), true));
+    }
+
+    #[test]
+    fn test_server_visibility_rules() {
+        use HistoryVisibility::*;
+        use MembershipState::*;
+
+        assert!(server_may_see_event(Shared, &[]));
+        assert!(server_may_see_event(Invited, &[Leave, Invite]));
+        assert!(!server_may_see_event(Joined, &[Invite]));
+        assert!(server_may_see_event(Joined, &[Leave, Join]));
+    }
+
+    #[test]
+    fn test_state_at_event() {
+        let history = RoomHistory {
+            history_visibility: vec![
+                change("$hv1", "", 2, json!({ "history_visibility": "shared" })),
+                change("$hv2", "", 10, json!({ "history_visibility": "joined" })),
+            ],
+            memberships: vec![
+                change("$join", "@alice:example.org", 5, json!({ "membership": "join" })),
+                change("$leave", "@alice:example.org", 20, json!({ "membership": "leave" })),
+            ],
+        };
+        let user = "@alice:example.org";
+
+        let (visibility, group) = history.visibility_at(&message("$m1", 3));
+        assert_eq!((visibility, group), (HistoryVisibility::Shared, vec!["$hv1".to_string()]));
+        assert_eq!(history.user_membership_at(user, &message("$m1", 3)).0, None);
+        assert_eq!(
+            history.user_membership_at(user, &message("$m2", 15)).0,
+            Some(MembershipState::Join)
+        );
+        assert_eq!(
+            history.user_membership_at(user, &message("$m3", 25)).0,
+            Some(MembershipState::Leave)
+        );
+        assert!(history.peeking());
+
+        // The visibility change is judged by the more permissive setting
+        let mut restrict = message("$hv2", 10);
+        restrict["type"] = json!("m.room.history_visibility");
+        restrict["state_key"] = json!("");
+        restrict["content"] = json!({ "history_visibility": "joined" });
+        assert_eq!(history.visibility_at(&restrict).0, HistoryVisibility::Shared);
+
+        // The user's own membership event counts with its own membership
+        let mut leave = message("$leave", 20);
+        leave["type"] = json!("m.room.member");
+        leave["state_key"] = json!(user);
+        leave["content"] = json!({ "membership": "leave" });
+        assert_eq!(
+            history.user_membership_at(user, &leave),
+            (Some(MembershipState::Leave), vec!["$leave".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_server_memberships_at_event() {
+        let history = RoomHistory {
+            history_visibility: Vec::new(),
+            memberships: vec![
+                change("$a", "@a:remote.org", 1, json!({ "membership": "join" })),
+                change("$b", "@b:remote.org", 2, json!({ "membership": "invite" })),
+                change("$a2", "@a:remote.org", 3, json!({ "membership": "leave" })),
+            ],
+        };
+
+        let (memberships, group) = history.server_memberships_at(&message("$m", 4));
+        assert_eq!(memberships, vec![MembershipState::Leave, MembershipState::Invite]);
+        assert_eq!(group, vec!["$a2".to_string(), "$b".to_string()]);
+        assert!(!server_may_see_event(HistoryVisibility::Joined, &memberships));
+        assert!(server_may_see_event(HistoryVisibility::Invited, &memberships));
+    }
+}
@@ -0,0 +1,236 @@
+//! Homeserver assembly
+//!
+//! [`Homeserver`] wires the services, background tasks and router together
+//! on an open database. `matryxd` serves it on its configured address with
+//! the built-in modules; a binary embedding the server can pass its own
+//! [`ModuleFactories`], and integration tests serve it in-process on an
+//! ephemeral port.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::Router;
+use surrealdb::{Surreal, engine::any::Any};
+use tokio::net::TcpListener;
+
+use crate::auth::MatrixSessionService;
+use crate::config::ServerConfig;
+use crate::federation::dns_resolver::MatrixDnsResolver;
+use crate::federation::well_known_client::WellKnownClient;
+use crate::middleware::{RateLimitService, TransactionService};
+use crate::modules::ModuleFactories;
+use crate::router::create_router;
+use crate::state::AppState;
+use crate::tasks;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Parse Ed25519 private key from environment variable
+///
+/// Supports both base64 and hex encoded 32-byte raw Ed25519 keys.
+/// Returns tuple of (private_key_32_bytes, public_key_32_bytes).
+fn parse_private_key_from_env(key_str: &str) -> Result<(Vec<u8>, Vec<u8>), String> {
+    use base64::{Engine, engine::general_purpose};
+    use ed25519_dalek::SigningKey;
+
+    // Try base64 decoding first
+    let key_bytes = if let Ok(bytes) = general_purpose::STANDARD.decode(key_str) {
+        bytes
+    } else if let Ok(bytes) = hex::decode(key_str) {
+        // Try hex decoding as fallback
+        bytes
+    } else {
+        return Err("JWT_PRIVATE_KEY must be base64 or hex encoded".to_string());
+    };
+
+    // Validate raw Ed25519 key format (32 bytes)
+    if key_bytes.len() != 32 {
+        return Err(format!(
+            "Invalid key length: expected 32 bytes for raw Ed25519 key, got {}",
+            key_bytes.len()
+        ));
+    }
+
+    // Create SigningKey from 32-byte array
+    let mut key_array = [0u8; 32];
+    key_array.copy_from_slice(&key_bytes);
+    let signing_key = SigningKey::from_bytes(&key_array);
+
+    let public_key_bytes = signing_key.verifying_key().to_bytes().to_vec();
+
+    Ok((key_bytes, public_key_bytes))
+}
+
+/// Load the Ed25519 keypair for JWT signing from `JWT_PRIVATE_KEY`, or
+/// generate one
+fn load_signing_keypair() -> Result<(Vec<u8>, Vec<u8>), BoxError> {
+    use ed25519_dalek::SigningKey;
+
+    match std::env::var("JWT_PRIVATE_KEY") {
+        Ok(key_str) => {
+            // Parse the environment variable key
+            let (priv_key, pub_key) = parse_private_key_from_env(&key_str)
+                .map_err(|e| format!("Failed to parse JWT_PRIVATE_KEY: {}", e))?;
+
+            tracing::info!("Loaded Ed25519 keypair from JWT_PRIVATE_KEY environment variable");
+            Ok((priv_key, pub_key))
+        },
+        Err(_) => {
+            // Fallback: Generate new keypair using getrandom
+            tracing::warn!(
+                "JWT_PRIVATE_KEY not set, generating random keypair (tokens will not persist across restarts)"
+            );
+
+            let mut private_key_bytes = [0u8; 32];
+            getrandom::fill(&mut private_key_bytes)
+                .map_err(|e| format!("Failed to generate random bytes: {}", e))?;
+
+            let signing_key = SigningKey::from_bytes(&private_key_bytes);
+            let verifying_key = signing_key.verifying_key();
+            let public_key_bytes = verifying_key.to_bytes();
+
+            Ok((private_key_bytes.to_vec(), public_key_bytes.to_vec()))
+        },
+    }
+}
+
+/// A homeserver with its background tasks running, ready to serve
+pub struct Homeserver {
+    state: AppState,
+    router: Router,
+}
+
+impl Homeserver {
+    /// Build the homeserver on an open database and start its background
+    /// tasks
+    ///
+    /// `module_factories` are the modules the `MODULES` setting may name,
+    /// usually [`ModuleFactories::builtin`] plus any of the embedder's own.
+    pub async fn start(
+        db: Surreal<Any>,
+        config: &'static ServerConfig,
+        module_factories: &ModuleFactories,
+    ) -> Result<Self, BoxError> {
+        let (private_key_32, public_key_bytes) = load_signing_keypair()?;
+        let homeserver_name = config.homeserver_name.clone();
+
+        // Create repository instances
+        let session_repo = matryx_surrealdb::repository::SessionRepository::new(db.clone());
+        let key_server_repo = matryx_surrealdb::repository::KeyServerRepository::new(db.clone());
+
+        let session_service = Arc::new(MatrixSessionService::new(
+            &private_key_32[..],
+            &public_key_bytes[..],
+            homeserver_name.clone(),
+            session_repo,
+            key_server_repo,
+        ));
+
+        // Create HTTP client
+        let http_client = Arc::new(
+            crate::federation::create_federation_http_client()
+                .map_err(|e| format!("Failed to create HTTP client: {}", e))?,
+        );
+
+        // Create DNS resolver for Matrix federation
+        let well_known_client =
+            Arc::new(WellKnownClient::new(http_client.clone(), config.use_https));
+        let dns_resolver = Arc::new(
+            MatrixDnsResolver::new(well_known_client, config.use_https)
+                .map_err(|e| format!("Failed to create DNS resolver: {}", e))?,
+        );
+
+        // Create event signer
+        let event_signer = Arc::new(
+            crate::federation::event_signer::EventSigner::new(
+                session_service.clone(),
+                db.clone(),
+                dns_resolver.clone(),
+                homeserver_name.clone(),
+                "ed25519:auto".to_string(),
+            )
+            .map_err(|e| format!("Failed to create event signer: {}", e))?,
+        );
+
+        // Initialize rate limiting service with federation-specific limits
+        let rate_limit_service = Arc::new(
+            RateLimitService::new_with_federation_limits(
+                Some(config.rate_limiting.client_requests_per_minute),
+                Some(config.rate_limiting.federation_requests_per_minute),
+                Some(config.rate_limiting.media_requests_per_minute),
+            )
+            .map_err(|e| format!("Failed to create rate limiting service: {}", e))?,
+        );
+
+        // Initialize transaction service
+        let transaction_service = Arc::new(TransactionService::new(db.clone()));
+
+        // Create outbound transaction queue channel
+        let (outbound_tx, outbound_rx) = tokio::sync::mpsc::unbounded_channel();
+
+        // Create application state with the real outbound channel
+        let app_state = Arc::new(AppState::new(
+            db,
+            session_service,
+            homeserver_name.clone(),
+            config,
+            http_client.clone(),
+            event_signer.clone(),
+            dns_resolver.clone(),
+            outbound_tx,
+            module_factories,
+        )?);
+
+        // Spawn outbound transaction queue background task
+        let federation_client = Arc::new(crate::federation::client::FederationClient::new(
+            http_client.clone(),
+            event_signer.clone(),
+            homeserver_name.clone(),
+            config.use_https,
+        ));
+        let queue = crate::federation::outbound_queue::OutboundTransactionQueue::new(
+            outbound_rx,
+            federation_client,
+            homeserver_name.clone(),
+        );
+        tokio::spawn(async move {
+            queue.run().await;
+        });
+        tracing::info!("Started outbound transaction queue background task");
+
+        // Start key management background service for automatic key refresh
+        let key_management_service =
+            crate::federation::key_management::KeyManagementService::new(app_state.clone());
+        key_management_service.start();
+        tracing::info!("Started key management background service");
+
+        // Start typing cleanup background task
+        tokio::spawn(tasks::typing_cleanup::start_typing_cleanup_task((*app_state).clone()));
+        tracing::info!("Started typing cleanup background task");
+
+        // Start policy list sync background task
+        tokio::spawn(tasks::policy_list_sync::start_policy_list_sync_task((*app_state).clone()));
+        tracing::info!("Started policy list sync background task");
+
+        // Start message retention purge background task
+        tokio::spawn(tasks::retention_purge::start_retention_purge_task((*app_state).clone()));
+        tracing::info!("Started retention purge background task");
+
+        // Build our application with routes
+        let router = create_router((*app_state).clone(), rate_limit_service, transaction_service);
+
+        Ok(Self { state: (*app_state).clone(), router })
+    }
+
+    pub fn state(&self) -> &AppState {
+        &self.state
+    }
+
+    /// Serve requests on the listener until the server fails
+    pub async fn serve(self, listener: TcpListener) -> Result<(), BoxError> {
+        axum::serve(listener, self.router.into_make_service_with_connect_info::<SocketAddr>())
+            .await
+            .map_err(|e| format!("Failed to start axum server: {}", e))?;
+        Ok(())
+    }
+}
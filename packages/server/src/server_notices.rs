@@ -97,6 +97,26 @@ impl ServerNoticesManager {
         Ok(event_id)
     }
 
+    /// Post a notice into a shared room, such as the moderation room
+    pub async fn send_room_notice(
+        &self,
+        room_id: &str,
+        notice_content: ServerNoticeContent,
+        state: &AppState,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        notice_content.validate()?;
+
+        let event_id = state
+            .server_notice_repository
+            .send_room_notice(room_id, &serde_json::to_value(&notice_content)?, &self.server_name)
+            .await
+            .map_err(|e| Box::new(e) as Box<dyn std::error::Error>)?;
+
+        info!("Sent server notice {} to room {}", event_id, room_id);
+
+        Ok(event_id)
+    }
+
     /// Send a usage limit reached notice
     pub async fn send_usage_limit_notice(
         &self,
@@ -1,5 +1,6 @@
 use crate::auth::{
     MatrixSessionService,
+    consent::ConsentService,
     oauth2::OAuth2Service,
     uia::{UiaConfig, UiaService},
 };
@@ -13,13 +14,21 @@ use crate::federation::event_signer::EventSigner;
 use crate::federation::media_client::FederationMediaClient;
 use crate::federation::membership_federation::{FederationRetryManager, RetryConfig};
 use crate::federation::outbound_queue::OutboundEvent;
+use crate::federation::server_acl::ServerAclService;
 use crate::federation::server_discovery::ServerDiscoveryOrchestrator;
 use crate::metrics::lazy_loading_benchmarks::{LazyLoadingBenchmarkConfig, LazyLoadingBenchmarks};
 use crate::metrics::lazy_loading_metrics::LazyLoadingMetrics;
+use crate::moderation::{
+    PolicyListService, ReportService, RoomShutdownService, UserRestrictionService,
+};
+use crate::modules::{ModuleFactories, ModuleRegistry};
+use crate::room::retention::RetentionService;
+use crate::room::upgrade::RoomUpgradeService;
 use crate::monitoring::lazy_loading_alerts::{
     AlertingConfig, ConsoleNotificationSender, LazyLoadingAlerts,
 };
 use crate::monitoring::memory_tracker::LazyLoadingMemoryTracker;
+use crate::room::visibility::EventVisibilityService;
 use matryx_surrealdb::repository::push::PushRepository;
 use matryx_surrealdb::repository::push_service::PushService;
 use matryx_surrealdb::repository::{
@@ -71,6 +80,26 @@ pub struct AppState {
     pub lazy_loading_metrics: Option<Arc<LazyLoadingMetrics>>,
     /// Filter compilation and result cache for sync performance
     pub filter_cache: Arc<FilterCache>,
+    /// Decides which room events users and servers may see
+    pub event_visibility: Arc<EventVisibilityService>,
+    /// Enforces the bans of subscribed moderation policy lists
+    pub policy_lists: Arc<PolicyListService>,
+    /// Compiled room server ACLs applied to every federation request
+    pub server_acls: Arc<ServerAclService>,
+    /// Message retention policies and history purging
+    pub retention: Arc<RetentionService>,
+    /// Blocks and evicts rooms shut down by server admins
+    pub room_shutdown: Arc<RoomShutdownService>,
+    /// Room upgrade pipeline and per-user migration into replacement rooms
+    pub room_upgrades: Arc<RoomUpgradeService>,
+    /// Shadow bans, suspensions and locks applied to local users
+    pub user_restrictions: Arc<UserRestrictionService>,
+    /// Stores content reports and routes them to moderators
+    pub reports: Arc<ReportService>,
+    /// Policy versions users accepted and the re-consent flow
+    pub consent: Arc<ConsentService>,
+    /// Spam-checker and third-party-rules modules loaded from configuration
+    pub modules: Arc<ModuleRegistry>,
     /// Memory usage tracker for cache lifecycle management
     pub memory_tracker: Option<Arc<LazyLoadingMemoryTracker>>,
     /// Performance alerting system for lazy loading degradation detection
@@ -99,6 +128,7 @@ impl AppState {
         event_signer: Arc<EventSigner>,
         dns_resolver: Arc<MatrixDnsResolver>,
         outbound_tx: mpsc::UnboundedSender<OutboundEvent>,
+        module_factories: &ModuleFactories,
     ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
         // Initialize OAuth2 service
         let oauth2_repo = OAuth2Repository::new(db.clone());
@@ -202,6 +232,27 @@ impl AppState {
         // Initialize filter cache for sync optimization
         let filter_cache = Arc::new(FilterCache::new());
 
+        // Initialize event visibility for every read path
+        let event_visibility = Arc::new(EventVisibilityService::new(db.clone()));
+        let policy_lists = Arc::new(PolicyListService::new(db.clone()));
+        let server_acls = Arc::new(ServerAclService::new(db.clone()));
+        let retention =
+            Arc::new(RetentionService::new(db.clone(), &config.retention, homeserver_name.clone()));
+        let room_shutdown = Arc::new(RoomShutdownService::new(db.clone(), homeserver_name.clone()));
+        let room_upgrades = Arc::new(RoomUpgradeService::new(db.clone(), homeserver_name.clone()));
+        let user_restrictions = Arc::new(UserRestrictionService::new(db.clone()));
+        let reports =
+            Arc::new(ReportService::new(db.clone(), &config.reports, homeserver_name.clone()));
+        let consent = Arc::new(ConsentService::new(
+            db.clone(),
+            &config.consent,
+            homeserver_name.clone(),
+            config.base_url(),
+        ));
+
+        // Load the configured spam-checker and third-party-rules modules
+        let modules = Arc::new(ModuleRegistry::load(&config.modules, module_factories)?);
+
         // Use the provided outbound channel (no dummy creation needed)
 
         Ok(Self {
@@ -229,6 +280,16 @@ impl AppState {
             lazy_loading_cache: None,
             lazy_loading_metrics: None,
             filter_cache,
+            event_visibility,
+            policy_lists,
+            server_acls,
+            retention,
+            room_shutdown,
+            room_upgrades,
+            user_restrictions,
+            reports,
+            consent,
+            modules,
             memory_tracker: None,
             lazy_loading_alerts: None,
             lazy_loading_benchmarks: None,
@@ -250,6 +311,7 @@ impl AppState {
         event_signer: Arc<EventSigner>,
         dns_resolver: Arc<MatrixDnsResolver>,
         outbound_tx: mpsc::UnboundedSender<OutboundEvent>,
+        module_factories: &ModuleFactories,
     ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
         // Initialize OAuth2 service
         let oauth2_repo = OAuth2Repository::new(db.clone());
@@ -272,6 +334,27 @@ impl AppState {
         // Initialize filter cache for sync optimization
         let filter_cache = Arc::new(FilterCache::new());
 
+        // Initialize event visibility for every read path
+        let event_visibility = Arc::new(EventVisibilityService::new(db.clone()));
+        let policy_lists = Arc::new(PolicyListService::new(db.clone()));
+        let server_acls = Arc::new(ServerAclService::new(db.clone()));
+        let retention =
+            Arc::new(RetentionService::new(db.clone(), &config.retention, homeserver_name.clone()));
+        let room_shutdown = Arc::new(RoomShutdownService::new(db.clone(), homeserver_name.clone()));
+        let room_upgrades = Arc::new(RoomUpgradeService::new(db.clone(), homeserver_name.clone()));
+        let user_restrictions = Arc::new(UserRestrictionService::new(db.clone()));
+        let reports =
+            Arc::new(ReportService::new(db.clone(), &config.reports, homeserver_name.clone()));
+        let consent = Arc::new(ConsentService::new(
+            db.clone(),
+            &config.consent,
+            homeserver_name.clone(),
+            config.base_url(),
+        ));
+
+        // Load the configured spam-checker and third-party-rules modules
+        let modules = Arc::new(ModuleRegistry::load(&config.modules, module_factories)?);
+
         // Create repositories for metrics and monitoring
         let performance_repo = Arc::new(PerformanceRepository::new(db.clone()));
         let monitoring_repo = Arc::new(MonitoringRepository::new(db.clone()));
@@ -404,6 +487,16 @@ impl AppState {
             lazy_loading_cache: Some(lazy_cache),
             lazy_loading_metrics: Some(metrics),
             filter_cache,
+            event_visibility,
+            policy_lists,
+            server_acls,
+            retention,
+            room_shutdown,
+            room_upgrades,
+            user_restrictions,
+            reports,
+            consent,
+            modules,
             memory_tracker: Some(memory_tracker),
             lazy_loading_alerts: Some(lazy_loading_alerts),
             lazy_loading_benchmarks: Some(lazy_loading_benchmarks),
@@ -1 +1,3 @@
+pub mod policy_list_sync;
+pub mod retention_purge;
 pub mod typing_cleanup;
@@ -0,0 +1,33 @@
+use crate::AppState;
+use std::time::Duration;
+use tokio::time::interval;
+use tracing::{debug, error, info};
+
+/// Background task to reload policy list rules and apply their user bans
+/// Runs every `refresh_interval_secs`, starting immediately so rules are enforced from startup
+pub async fn start_policy_list_sync_task(state: AppState) {
+    let config = &state.config.policy_lists;
+    let mut interval = interval(Duration::from_secs(config.refresh_interval_secs.max(1)));
+
+    loop {
+        interval.tick().await;
+
+        if let Err(e) = state.policy_lists.refresh().await {
+            error!("Failed to reload policy list rules: {}", e);
+            continue;
+        }
+
+        let Some(moderator) = &config.auto_ban_moderator else {
+            continue;
+        };
+        match state
+            .policy_lists
+            .apply_bans(&state.db, &state.room_operations, moderator)
+            .await
+        {
+            Ok(0) => debug!("No new policy list bans to apply"),
+            Ok(issued) => info!("Applied {} policy list bans as {}", issued, moderator),
+            Err(e) => error!("Failed to apply policy list bans: {}", e),
+        }
+    }
+}
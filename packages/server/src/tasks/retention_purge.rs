@@ -0,0 +1,25 @@
+use crate::AppState;
+use std::time::Duration;
+use tokio::time::interval;
+use tracing::{debug, error, info};
+
+/// Background task to purge history past its room's retention lifetime
+/// Runs every `purge_interval_secs` while retention is enabled
+pub async fn start_retention_purge_task(state: AppState) {
+    let config = &state.config.retention;
+    if !config.enabled {
+        debug!("Message retention disabled, not starting the purge task");
+        return;
+    }
+    let mut interval = interval(Duration::from_secs(config.purge_interval_secs.max(1)));
+
+    loop {
+        interval.tick().await;
+
+        match state.retention.purge_expired().await {
+            Ok(0) => debug!("No expired history to purge"),
+            Ok(deleted) => info!("Purged {} expired events", deleted),
+            Err(e) => error!("Failed to purge expired history: {}", e),
+        }
+    }
+}
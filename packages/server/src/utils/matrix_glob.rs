@@ -0,0 +1,59 @@
+//! Matrix glob patterns
+//!
+//! Globs in the Matrix spec, such as policy rule entities and server ACL
+//! entries, only know two wildcards: `*` matches any run of characters and
+//! `?` matches exactly one. Every other character is literal, including
+//! `[`, `]`, `{`, `}` and `\`.
+
+use regex::Regex;
+
+/// Translate a glob into an anchored regex pattern
+pub fn glob_to_regex(glob: &str) -> String {
+    let mut pattern = String::with_capacity(glob.len() + 8);
+    pattern.push_str("(?s)^");
+    let mut literal = String::new();
+    for c in glob.chars() {
+        match c {
+            '*' | '?' => {
+                pattern.push_str(&regex::escape(&literal));
+                literal.clear();
+                pattern.push_str(if c == '*' { ".*" } else { "." });
+            },
+            c => literal.push(c),
+        }
+    }
+    pattern.push_str(&regex::escape(&literal));
+    pattern.push('$');
+    pattern
+}
+
+/// Compile a glob into a regex matching whole strings
+pub fn compile_glob(glob: &str) -> Result<Regex, regex::Error> {
+    Regex::new(&glob_to_regex(glob))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wildcards() -> Result<(), regex::Error> {
+        let glob = compile_glob("@spam?*:example.org")?;
+
+        assert!(glob.is_match("@spam1:example.org"));
+        assert!(glob.is_match("@spammer:example.org"));
+        assert!(!glob.is_match("@spam:example.org"));
+        assert!(!glob.is_match("@spam1:example.org.evil"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_other_characters_are_literal() -> Result<(), regex::Error> {
+        let glob = compile_glob(r"@[a-z]{2}\.+:example.org")?;
+
+        assert!(glob.is_match(r"@[a-z]{2}\.+:example.org"));
+        assert!(!glob.is_match("@ab.:example.org"));
+        assert!(!compile_glob("example.org")?.is_match("exampleXorg"));
+        Ok(())
+    }
+}
@@ -0,0 +1,91 @@
+use axum::extract::{FromRequestParts, Path};
+use axum::http::request::Parts;
+use serde::de::DeserializeOwned;
+
+use crate::error::MatrixError;
+
+/// Path extractor that rejects malformed path parameters with `M_INVALID_PARAM`
+///
+/// Behaves like [`axum::extract::Path`], but is intended for use with the
+/// validated identifier types from `matryx_entity` (`RoomId`, `UserId`,
+/// `EventId`, ...). A parameter that fails identifier grammar validation is
+/// turned into a Matrix error response before the handler runs, instead of
+/// axum's plain-text 400.
+///
+/// # Example
+/// ```rust,ignore
+/// pub async fn get(
+///     MatrixPath((room_id, event_id)): MatrixPath<(RoomId, EventId)>,
+/// ) -> Result<Json<Value>, StatusCode> { ... }
+/// ```
+#[derive(Debug, Clone)]
+pub struct MatrixPath<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for MatrixPath<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = MatrixError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match Path::<T>::from_request_parts(parts, state).await {
+            Ok(Path(value)) => Ok(MatrixPath(value)),
+            Err(rejection) => {
+                tracing::debug!(
+                    "Rejecting request to {} with invalid path parameter: {}",
+                    parts.uri.path(),
+                    rejection.body_text()
+                );
+                Err(MatrixError::InvalidParam)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, body::Body, http::Request, http::StatusCode, routing::get};
+    use matryx_entity::types::{EventId, RoomId};
+    use tower::ServiceExt;
+
+    async fn handler(MatrixPath((room_id, event_id)): MatrixPath<(RoomId, EventId)>) -> String {
+        format!("{} {}", room_id, event_id)
+    }
+
+    fn app() -> Router {
+        Router::new().route("/rooms/{room_id}/event/{event_id}", get(handler))
+    }
+
+    #[tokio::test]
+    async fn test_valid_identifiers_are_extracted() -> Result<(), Box<dyn std::error::Error>> {
+        let response = app()
+            .oneshot(
+                Request::builder()
+                    .uri("/rooms/%21room%3Aexample.org/event/%24event%3Aexample.org")
+                    .body(Body::empty())?,
+            )
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_malformed_identifiers_return_invalid_param()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let response = app()
+            .oneshot(
+                Request::builder()
+                    .uri("/rooms/not-a-room/event/%24event%3Aexample.org")
+                    .body(Body::empty())?,
+            )
+            .await?;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+        let json: serde_json::Value = serde_json::from_slice(&body)?;
+        assert_eq!(json["errcode"], "M_INVALID_PARAM");
+        Ok(())
+    }
+}
@@ -1,7 +1,9 @@
 pub mod canonical_json;
 pub mod canonical_json_errors;
 pub mod matrix_events;
+pub mod matrix_glob;
 pub mod matrix_identifiers;
+pub mod matrix_path;
 pub mod request_helpers;
 pub mod response_helpers;
 pub mod session_helpers;
@@ -119,6 +119,7 @@ impl MatrixTestServer {
             event_signer,
             dns_resolver,
             outbound_tx,
+            &matryx_server::modules::ModuleFactories::builtin(),
         )?;
 
         Ok(Self {
@@ -328,6 +329,7 @@ async fn create_test_app() -> Result<Router, Box<dyn std::error::Error>> {
         event_signer,
         dns_resolver,
         outbound_tx,
+        &matryx_server::modules::ModuleFactories::builtin(),
     )?;
 
     // Create a simple test router for now
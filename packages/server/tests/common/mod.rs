@@ -107,6 +107,7 @@ pub async fn create_test_app() -> Result<Router, Box<dyn std::error::Error>> {
         event_signer,
         dns_resolver,
         outbound_tx,
+        &matryx_server::modules::ModuleFactories::builtin(),
     )?;
 
     // Create a simple test router
@@ -197,6 +198,7 @@ pub async fn create_test_app_with_db(db: Surreal<Any>) -> Result<Router, Box<dyn
         event_signer,
         dns_resolver,
         outbound_tx,
+        &matryx_server::modules::ModuleFactories::builtin(),
     )?;
 
     // Create a simple test router
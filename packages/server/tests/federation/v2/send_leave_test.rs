@@ -110,6 +110,7 @@ async fn create_test_app_state(
         event_signer,
         dns_resolver,
         outbound_tx,
+        &matryx_server::modules::ModuleFactories::builtin(),
     )?)
 }
 
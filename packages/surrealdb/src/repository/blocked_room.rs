@@ -0,0 +1,70 @@
+use crate::repository::error::RepositoryError;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::{Surreal, engine::any::Any};
+
+/// A room shut down by a server admin
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockedRoom {
+    pub room_id: String,
+    pub blocked_by: String,
+    pub reason: Option<String>,
+    pub blocked_at: DateTime<Utc>,
+}
+
+#[derive(Clone)]
+pub struct BlockedRoomRepository {
+    db: Surreal<Any>,
+}
+
+impl BlockedRoomRepository {
+    pub fn new(db: Surreal<Any>) -> Self {
+        Self { db }
+    }
+
+    /// Block joins to a room, replacing any earlier block
+    pub async fn block_room(
+        &self,
+        room_id: &str,
+        blocked_by: &str,
+        reason: Option<&str>,
+    ) -> Result<BlockedRoom, RepositoryError> {
+        let query = "
+            UPSERT type::thing('blocked_room', $room_id) CONTENT {
+                room_id: $room_id,
+                blocked_by: $blocked_by,
+                reason: $reason,
+                blocked_at: time::now()
+            } RETURN room_id, blocked_by, reason, blocked_at
+        ";
+        let mut response = self
+            .db
+            .query(query)
+            .bind(("room_id", room_id.to_string()))
+            .bind(("blocked_by", blocked_by.to_string()))
+            .bind(("reason", reason.map(str::to_string)))
+            .await?;
+        let blocked: Option<BlockedRoom> = response.take(0)?;
+
+        blocked
+            .ok_or_else(|| RepositoryError::Database(surrealdb::Error::msg("Failed to block room")))
+    }
+
+    /// Lift a room's block, returning whether it was blocked
+    pub async fn unblock_room(&self, room_id: &str) -> Result<bool, RepositoryError> {
+        let removed: Option<BlockedRoom> = self.db.delete(("blocked_room", room_id)).await?;
+        Ok(removed.is_some())
+    }
+
+    pub async fn get_blocked_room(
+        &self,
+        room_id: &str,
+    ) -> Result<Option<BlockedRoom>, RepositoryError> {
+        let blocked: Option<BlockedRoom> = self.db.select(("blocked_room", room_id)).await?;
+        Ok(blocked)
+    }
+
+    pub async fn is_room_blocked(&self, room_id: &str) -> Result<bool, RepositoryError> {
+        Ok(self.get_blocked_room(room_id).await?.is_some())
+    }
+}
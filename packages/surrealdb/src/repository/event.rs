@@ -95,12 +95,12 @@ impl EventRepository {
         let query = match limit {
             Some(l) => {
                 format!(
-                    "SELECT * FROM event WHERE room_id = $room_id ORDER BY origin_server_ts DESC LIMIT {}",
+                    "SELECT * FROM event WHERE room_id = $room_id AND soft_failed != true ORDER BY origin_server_ts DESC LIMIT {}",
                     l
                 )
             },
             None => {
-                "SELECT * FROM event WHERE room_id = $room_id ORDER BY origin_server_ts DESC"
+                "SELECT * FROM event WHERE room_id = $room_id AND soft_failed != true ORDER BY origin_server_ts DESC"
                     .to_string()
             },
         };
@@ -121,24 +121,24 @@ impl EventRepository {
         let query = match (since_ts, limit) {
             (Some(ts), Some(l)) => {
                 format!(
-                    "SELECT * FROM event WHERE room_id = $room_id AND received_ts > {} ORDER BY received_ts ASC LIMIT {}",
+                    "SELECT * FROM event WHERE room_id = $room_id AND soft_failed != true AND received_ts > {} ORDER BY received_ts ASC LIMIT {}",
                     ts, l
                 )
             },
             (Some(ts), None) => {
                 format!(
-                    "SELECT * FROM event WHERE room_id = $room_id AND received_ts > {} ORDER BY received_ts ASC",
+                    "SELECT * FROM event WHERE room_id = $room_id AND soft_failed != true AND received_ts > {} ORDER BY received_ts ASC",
                     ts
                 )
             },
             (None, Some(l)) => {
                 format!(
-                    "SELECT * FROM event WHERE room_id = $room_id ORDER BY received_ts ASC LIMIT {}",
+                    "SELECT * FROM event WHERE room_id = $room_id AND soft_failed != true ORDER BY received_ts ASC LIMIT {}",
                     l
                 )
             },
             (None, None) => {
-                "SELECT * FROM event WHERE room_id = $room_id ORDER BY received_ts ASC".to_string()
+                "SELECT * FROM event WHERE room_id = $room_id AND soft_failed != true ORDER BY received_ts ASC".to_string()
             },
         };
 
@@ -157,7 +157,9 @@ impl EventRepository {
         let room_id_owned = room_id.to_string();
         let events: Vec<Event> = self
             .db
-            .query("SELECT * FROM event WHERE room_id = $room_id AND state_key IS NOT NULL")
+            .query(
+                "SELECT * FROM event WHERE room_id = $room_id AND state_key IS NOT NULL AND soft_failed != true",
+            )
             .bind(("room_id", room_id_owned))
             .await?
             .take(0)?;
@@ -190,7 +192,9 @@ impl EventRepository {
         // Create SurrealDB LiveQuery for events in specific room (message events only)
         let mut stream = self
             .db
-            .query("LIVE SELECT * FROM event WHERE room_id = $room_id AND state_key IS NULL")
+            .query(
+                "LIVE SELECT * FROM event WHERE room_id = $room_id AND state_key IS NULL AND soft_failed != true",
+            )
             .bind(("room_id", room_id.to_string()))
             .await
             .map_err(RepositoryError::Database)?;
@@ -228,7 +232,9 @@ impl EventRepository {
         // Create SurrealDB LiveQuery for state events in specific room
         let mut stream = self
             .db
-            .query("LIVE SELECT * FROM event WHERE room_id = $room_id AND state_key IS NOT NULL")
+            .query(
+                "LIVE SELECT * FROM event WHERE room_id = $room_id AND state_key IS NOT NULL AND soft_failed != true",
+            )
             .bind(("room_id", room_id.to_string()))
             .await
             .map_err(RepositoryError::Database)?;
@@ -272,6 +278,7 @@ impl EventRepository {
                     SELECT VALUE room_id FROM membership
                     WHERE user_id = $user_id AND membership IN ['join', 'invite']
                 )
+                AND soft_failed != true
             "#,
             )
             .bind(("user_id", user_id.to_string()))
@@ -410,11 +417,24 @@ impl EventRepository {
         content: Value,
         state_key: Option<String>,
     ) -> Result<Event, RepositoryError> {
+        let event = Self::build_room_event(room_id, event_type, sender, content, state_key);
+        self.create(&event).await
+    }
+
+    /// Build a local room event without storing it, for callers that need
+    /// its event ID before it can be sent
+    pub fn build_room_event(
+        room_id: &str,
+        event_type: &str,
+        sender: &str,
+        content: Value,
+        state_key: Option<String>,
+    ) -> Event {
         let event_id = format!("${}", Uuid::new_v4());
         let now = Utc::now();
 
-        let event = Event {
-            event_id: event_id.clone(),
+        Event {
+            event_id,
             room_id: room_id.to_string(),
             sender: sender.to_string(),
             event_type: event_type.to_string(),
@@ -432,9 +452,7 @@ impl EventRepository {
             received_ts: Some(now.timestamp_millis()),
             rejected_reason: None,
             soft_failed: Some(false),
-        };
-
-        self.create(&event).await
+        }
     }
 
     /// Send a message event to a room
@@ -2100,8 +2118,10 @@ impl EventRepository {
         let query = r#"
             SELECT VALUE event_id FROM event 
             WHERE room_id = $room_id 
+            AND soft_failed != true
             AND event_id NOT IN (
-                SELECT VALUE unnest(prev_events) FROM event WHERE room_id = $room_id
+                SELECT VALUE unnest(prev_events) FROM event
+                WHERE room_id = $room_id AND soft_failed != true
             )
             ORDER BY origin_server_ts DESC 
             LIMIT 20
@@ -1,5 +1,6 @@
 pub mod account_data;
 pub mod auth;
+pub mod blocked_room;
 pub mod bridge;
 pub mod capabilities;
 pub mod captcha;
@@ -34,12 +35,14 @@ pub mod membership;
 pub mod mention;
 pub mod messaging;
 pub mod metrics;
+pub mod moderation_report;
 pub mod monitoring;
 pub mod monitoring_service;
 pub mod notification;
 pub mod oauth2;
 pub mod pdu;
 pub mod performance;
+pub mod policy_list;
 pub mod power_levels;
 pub mod presence;
 pub mod profile;
@@ -55,6 +58,7 @@ pub mod receipt;
 pub mod registration;
 pub mod relations;
 pub mod reports;
+pub mod retention;
 pub mod room;
 pub mod room_alias;
 pub mod room_keys;
@@ -82,10 +86,14 @@ pub mod to_device;
 pub mod transaction;
 pub mod uia;
 pub mod user;
+pub mod user_consent;
+pub mod user_restriction;
+pub mod visibility;
 pub mod websocket;
 
 pub use account_data::*;
 pub use auth::*;
+pub use blocked_room::{BlockedRoom, BlockedRoomRepository};
 pub use bridge::*;
 pub use capabilities::CapabilitiesResponse as ServerCapabilitiesResponse;
 pub use capabilities::{CapabilitiesRepository, RoomVersionCapabilities, ServerCapabilities};
@@ -145,12 +153,18 @@ pub use membership::*;
 pub use mention::*;
 pub use messaging::{MessagingRepository, ToDeviceMessage as MessagingToDeviceMessage};
 pub use metrics::*;
+pub use moderation_report::{
+    ModerationReport, ModerationReportKind, ModerationReportRepository, ModerationReportStatus,
+};
 pub use monitoring::*;
 pub use monitoring_service::*;
 pub use notification::{NotificationRepository, NotificationSettings, PushCondition as NotificationPushCondition};
 pub use oauth2::*;
 pub use pdu::*;
 pub use performance::*;
+pub use policy_list::{
+    PolicyListRepository, PolicyListSubscription, PolicyRule, PolicyRuleKind,
+};
 pub use power_levels::*;
 pub use presence::*;
 pub use profile::*;
@@ -167,6 +181,7 @@ pub use reactions::*;
 pub use registration::*;
 pub use relations::*;
 pub use reports::*;
+pub use retention::{PurgedHistory, RetentionRepository};
 pub use room::{RoomRepository, EventContext as RoomEventContext, EventReport as RoomEventReport, FederationSettings as RoomFederationSettings};
 pub use room_alias::*;
 pub use room_keys::*;
@@ -199,4 +214,7 @@ pub use to_device::{ToDeviceRepository, ToDeviceMessage};
 pub use transaction::*;
 pub use uia::*;
 pub use user::*;
+pub use user_consent::{UserConsent, UserConsentRepository};
+pub use user_restriction::{UserRestriction, UserRestrictionKind, UserRestrictionRepository};
+pub use visibility::{StateChange, VisibilityRepository};
 pub use websocket::*;
@@ -0,0 +1,232 @@
+use crate::repository::error::RepositoryError;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::{Surreal, engine::any::Any};
+
+/// What a report is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationReportKind {
+    Room,
+    Event,
+    User,
+}
+
+impl ModerationReportKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModerationReportKind::Room => "room",
+            ModerationReportKind::Event => "event",
+            ModerationReportKind::User => "user",
+        }
+    }
+}
+
+/// Where a report is in its handling
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationReportStatus {
+    Open,
+    Acknowledged,
+    Resolved,
+}
+
+impl ModerationReportStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModerationReportStatus::Open => "open",
+            ModerationReportStatus::Acknowledged => "acknowledged",
+            ModerationReportStatus::Resolved => "resolved",
+        }
+    }
+
+    pub fn parse(status: &str) -> Option<Self> {
+        match status {
+            "open" => Some(ModerationReportStatus::Open),
+            "acknowledged" => Some(ModerationReportStatus::Acknowledged),
+            "resolved" => Some(ModerationReportStatus::Resolved),
+            _ => None,
+        }
+    }
+
+    /// Reports only move forward; a resolved report stays resolved
+    pub fn can_transition_to(&self, next: ModerationReportStatus) -> bool {
+        matches!(
+            (self, next),
+            (ModerationReportStatus::Open, ModerationReportStatus::Acknowledged) |
+                (ModerationReportStatus::Open, ModerationReportStatus::Resolved) |
+                (ModerationReportStatus::Acknowledged, ModerationReportStatus::Resolved)
+        )
+    }
+}
+
+/// A room, event or user report, shared by everyone who reported the same target
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModerationReport {
+    pub report_id: String,
+    /// `<kind>|<id>`, identifying what was reported for deduplication
+    pub target: String,
+    pub kind: ModerationReportKind,
+    pub room_id: Option<String>,
+    pub event_id: Option<String>,
+    pub reported_user_id: Option<String>,
+    /// The user who filed the report first
+    pub reporter_id: String,
+    /// Every user who reported the target while the report was unresolved
+    pub reporters: Vec<String>,
+    pub reason: Option<String>,
+    pub score: Option<i32>,
+    pub status: ModerationReportStatus,
+    pub action_taken: Option<String>,
+    pub handled_by: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ModerationReport {
+    /// The deduplication key for a reported room ID, event ID or user ID
+    pub fn target_for(kind: ModerationReportKind, id: &str) -> String {
+        format!("{}|{}", kind.as_str(), id)
+    }
+}
+
+#[derive(Clone)]
+pub struct ModerationReportRepository {
+    db: Surreal<Any>,
+}
+
+impl ModerationReportRepository {
+    pub fn new(db: Surreal<Any>) -> Self {
+        Self { db }
+    }
+
+    pub async fn create_report(
+        &self,
+        report: &ModerationReport,
+    ) -> Result<ModerationReport, RepositoryError> {
+        let created: Option<ModerationReport> = self
+            .db
+            .create(("moderation_report", report.report_id.as_str()))
+            .content(report.clone())
+            .await?;
+
+        created.ok_or_else(|| {
+            RepositoryError::Database(surrealdb::Error::msg("Failed to create report"))
+        })
+    }
+
+    /// The unresolved report for a target, if any
+    pub async fn find_unresolved_report(
+        &self,
+        target: &str,
+    ) -> Result<Option<ModerationReport>, RepositoryError> {
+        let query = "
+            SELECT * FROM moderation_report
+            WHERE target = $target AND status != 'resolved'
+            ORDER BY created_at DESC
+            LIMIT 1
+        ";
+        let mut response = self.db.query(query).bind(("target", target.to_string())).await?;
+        let reports: Vec<ModerationReport> = response.take(0)?;
+        Ok(reports.into_iter().next())
+    }
+
+    /// Record another user reporting the same target
+    pub async fn add_reporter(
+        &self,
+        report_id: &str,
+        reporter_id: &str,
+    ) -> Result<Option<ModerationReport>, RepositoryError> {
+        let query = "
+            UPDATE type::thing('moderation_report', $report_id) SET
+                reporters = array::union(reporters, [$reporter_id]),
+                updated_at = time::now()
+            RETURN AFTER
+        ";
+        let mut response = self
+            .db
+            .query(query)
+            .bind(("report_id", report_id.to_string()))
+            .bind(("reporter_id", reporter_id.to_string()))
+            .await?;
+        let updated: Option<ModerationReport> = response.take(0)?;
+        Ok(updated)
+    }
+
+    pub async fn get_report(
+        &self,
+        report_id: &str,
+    ) -> Result<Option<ModerationReport>, RepositoryError> {
+        let report: Option<ModerationReport> =
+            self.db.select(("moderation_report", report_id)).await?;
+        Ok(report)
+    }
+
+    /// Reports newest first, optionally only those with a given status
+    pub async fn list_reports(
+        &self,
+        status: Option<ModerationReportStatus>,
+        limit: u32,
+    ) -> Result<Vec<ModerationReport>, RepositoryError> {
+        let query = "
+            SELECT * FROM moderation_report
+            WHERE $status = NONE OR status = $status
+            ORDER BY created_at DESC
+            LIMIT $limit
+        ";
+        let mut response = self
+            .db
+            .query(query)
+            .bind(("status", status.map(|s| s.as_str().to_string())))
+            .bind(("limit", limit))
+            .await?;
+        let reports: Vec<ModerationReport> = response.take(0)?;
+        Ok(reports)
+    }
+
+    pub async fn update_status(
+        &self,
+        report_id: &str,
+        status: ModerationReportStatus,
+        action_taken: Option<&str>,
+        handled_by: &str,
+    ) -> Result<Option<ModerationReport>, RepositoryError> {
+        let query = "
+            UPDATE type::thing('moderation_report', $report_id) SET
+                status = $status,
+                action_taken = $action_taken ?? action_taken,
+                handled_by = $handled_by,
+                updated_at = time::now()
+            RETURN AFTER
+        ";
+        let mut response = self
+            .db
+            .query(query)
+            .bind(("report_id", report_id.to_string()))
+            .bind(("status", status.as_str().to_string()))
+            .bind(("action_taken", action_taken.map(str::to_string)))
+            .bind(("handled_by", handled_by.to_string()))
+            .await?;
+        let updated: Option<ModerationReport> = response.take(0)?;
+        Ok(updated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_only_move_forward() {
+        use ModerationReportStatus::*;
+
+        assert!(Open.can_transition_to(Acknowledged));
+        assert!(Open.can_transition_to(Resolved));
+        assert!(Acknowledged.can_transition_to(Resolved));
+
+        assert!(!Acknowledged.can_transition_to(Open));
+        assert!(!Resolved.can_transition_to(Open));
+        assert!(!Resolved.can_transition_to(Acknowledged));
+        assert!(!Open.can_transition_to(Open));
+    }
+}
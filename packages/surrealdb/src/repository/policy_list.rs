@@ -0,0 +1,189 @@
+use crate::repository::error::RepositoryError;
+use chrono::{DateTime, Utc};
+use matryx_entity::types::PolicyRuleContent;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use surrealdb::{Surreal, engine::any::Any};
+
+/// Event types of policy rules, including the unstable ones still
+/// published by Mjolnir and Draupnir
+pub const USER_RULE_TYPES: &[&str] = &[
+    "m.policy.rule.user",
+    "m.room.rule.user",
+    "org.matrix.mjolnir.rule.user",
+];
+pub const ROOM_RULE_TYPES: &[&str] = &[
+    "m.policy.rule.room",
+    "m.room.rule.room",
+    "org.matrix.mjolnir.rule.room",
+];
+pub const SERVER_RULE_TYPES: &[&str] = &[
+    "m.policy.rule.server",
+    "m.room.rule.server",
+    "org.matrix.mjolnir.rule.server",
+];
+
+/// What a policy rule applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyRuleKind {
+    User,
+    Room,
+    Server,
+}
+
+impl PolicyRuleKind {
+    pub fn from_event_type(event_type: &str) -> Option<Self> {
+        if USER_RULE_TYPES.contains(&event_type) {
+            Some(Self::User)
+        } else if ROOM_RULE_TYPES.contains(&event_type) {
+            Some(Self::Room)
+        } else if SERVER_RULE_TYPES.contains(&event_type) {
+            Some(Self::Server)
+        } else {
+            None
+        }
+    }
+}
+
+/// A policy list room the server enforces
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyListSubscription {
+    pub room_id: String,
+    pub subscribed_by: String,
+    pub subscribed_at: DateTime<Utc>,
+}
+
+/// A rule currently published in a policy list room
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyRule {
+    pub room_id: String,
+    pub kind: PolicyRuleKind,
+    pub state_key: String,
+    pub content: PolicyRuleContent,
+}
+
+#[derive(Deserialize)]
+struct PolicyRuleEvent {
+    room_id: String,
+    event_type: String,
+    state_key: String,
+    depth: Option<i64>,
+    origin_server_ts: i64,
+    content: Value,
+}
+
+#[derive(Clone)]
+pub struct PolicyListRepository {
+    db: Surreal<Any>,
+}
+
+impl PolicyListRepository {
+    pub fn new(db: Surreal<Any>) -> Self {
+        Self { db }
+    }
+
+    /// Start enforcing the rules of a policy list room
+    pub async fn subscribe(
+        &self,
+        room_id: &str,
+        subscribed_by: &str,
+    ) -> Result<PolicyListSubscription, RepositoryError> {
+        let query = "
+            UPSERT type::thing('policy_list_subscription', $room_id) CONTENT {
+                room_id: $room_id,
+                subscribed_by: $subscribed_by,
+                subscribed_at: time::now()
+            } RETURN room_id, subscribed_by, subscribed_at
+        ";
+        let mut response = self
+            .db
+            .query(query)
+            .bind(("room_id", room_id.to_string()))
+            .bind(("subscribed_by", subscribed_by.to_string()))
+            .await?;
+        let subscription: Option<PolicyListSubscription> = response.take(0)?;
+
+        subscription.ok_or_else(|| {
+            RepositoryError::Database(surrealdb::Error::msg("Failed to subscribe to policy list"))
+        })
+    }
+
+    /// Stop enforcing a policy list room, returning whether it was subscribed
+    pub async fn unsubscribe(&self, room_id: &str) -> Result<bool, RepositoryError> {
+        let removed: Option<PolicyListSubscription> =
+            self.db.delete(("policy_list_subscription", room_id)).await?;
+        Ok(removed.is_some())
+    }
+
+    pub async fn get_subscriptions(&self) -> Result<Vec<PolicyListSubscription>, RepositoryError> {
+        let query = "SELECT room_id, subscribed_by, subscribed_at FROM policy_list_subscription ORDER BY subscribed_at";
+        let mut response = self.db.query(query).await?;
+        let subscriptions: Vec<PolicyListSubscription> = response.take(0)?;
+        Ok(subscriptions)
+    }
+
+    /// The rules in force in the given policy list rooms
+    ///
+    /// Only the latest event for each rule counts, and revoked rules (empty
+    /// content) are left out.
+    pub async fn get_rules(&self, room_ids: &[String]) -> Result<Vec<PolicyRule>, RepositoryError> {
+        if room_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let event_types: Vec<&str> = USER_RULE_TYPES
+            .iter()
+            .chain(ROOM_RULE_TYPES)
+            .chain(SERVER_RULE_TYPES)
+            .copied()
+            .collect();
+
+        let query = "
+            SELECT room_id, event_type, state_key, depth, origin_server_ts, content
+            FROM event
+            WHERE room_id IN $room_ids
+            AND event_type IN $event_types
+            AND state_key IS NOT NONE
+            ORDER BY depth, origin_server_ts
+        ";
+        let mut response = self
+            .db
+            .query(query)
+            .bind(("room_ids", room_ids.to_vec()))
+            .bind(("event_types", event_types))
+            .await?;
+        let mut events: Vec<PolicyRuleEvent> = response.take(0)?;
+        events.sort_by_key(|event| (event.depth.unwrap_or(0), event.origin_server_ts));
+
+        // Later events replace earlier ones for the same state
+        let mut latest: HashMap<(String, String, String), PolicyRuleEvent> = HashMap::new();
+        for event in events {
+            let key = (event.room_id.clone(), event.event_type.clone(), event.state_key.clone());
+            latest.insert(key, event);
+        }
+
+        let mut rules = Vec::new();
+        for event in latest.into_values() {
+            let Some(kind) = PolicyRuleKind::from_event_type(&event.event_type) else {
+                continue;
+            };
+            let content: PolicyRuleContent = match serde_json::from_value(event.content) {
+                Ok(content) => content,
+                Err(_) => continue,
+            };
+            if content.is_revoked() || content.entity.is_empty() {
+                continue;
+            }
+            rules.push(PolicyRule {
+                room_id: event.room_id,
+                kind,
+                state_key: event.state_key,
+                content,
+            });
+        }
+
+        Ok(rules)
+    }
+}
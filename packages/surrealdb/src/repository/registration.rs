@@ -22,15 +22,31 @@ pub struct AuthFlow {
     pub params: HashMap<String, serde_json::Value>,
 }
 
+/// A token that lets someone register while registration is token-gated
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RegistrationToken {
     pub token: String,
+    /// How many registrations the token allows; unlimited when unset
     pub uses_allowed: Option<i32>,
-    pub uses_remaining: Option<i32>,
-    pub pending: bool,
-    pub completed: bool,
+    /// Registrations that passed the token stage but have not finished
+    pub pending: i32,
+    /// Registrations completed with the token
+    pub completed: i32,
     pub expires_at: Option<DateTime<Utc>>,
     pub created_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl RegistrationToken {
+    /// Whether the token can start another registration; pending uses count
+    /// against the limit so concurrent registrations cannot overshoot it
+    pub fn is_valid(&self, now: DateTime<Utc>) -> bool {
+        let expired = self.expires_at.is_some_and(|expires_at| expires_at <= now);
+        let used_up = self
+            .uses_allowed
+            .is_some_and(|uses_allowed| self.pending + self.completed >= uses_allowed);
+        !expired && !used_up
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -210,8 +226,115 @@ impl<C: Connection> RegistrationRepository<C> {
         Ok(device_result)
     }
 
+    pub async fn create_registration_token(
+        &self,
+        token: &str,
+        uses_allowed: Option<i32>,
+        expires_at: Option<DateTime<Utc>>,
+        created_by: &str,
+    ) -> Result<RegistrationToken, RepositoryError> {
+        if self.get_registration_token(token).await?.is_some() {
+            return Err(RepositoryError::Conflict {
+                message: format!("Registration token {} already exists", token),
+            });
+        }
+
+        let registration_token = RegistrationToken {
+            token: token.to_string(),
+            uses_allowed,
+            pending: 0,
+            completed: 0,
+            expires_at,
+            created_by: created_by.to_string(),
+            created_at: Utc::now(),
+        };
+        let created: Option<RegistrationToken> = self
+            .db
+            .create(("registration_token", token))
+            .content(registration_token)
+            .await?;
+
+        created.ok_or_else(|| {
+            RepositoryError::Database(surrealdb::Error::msg("Failed to create registration token"))
+        })
+    }
+
+    pub async fn get_registration_token(
+        &self,
+        token: &str,
+    ) -> Result<Option<RegistrationToken>, RepositoryError> {
+        let registration_token: Option<RegistrationToken> =
+            self.db.select(("registration_token", token)).await?;
+        Ok(registration_token)
+    }
+
+    /// All tokens, oldest first, optionally only those that are still valid
+    /// (or only those that are not)
+    pub async fn list_registration_tokens(
+        &self,
+        valid: Option<bool>,
+    ) -> Result<Vec<RegistrationToken>, RepositoryError> {
+        let query = "SELECT * FROM registration_token ORDER BY created_at ASC";
+        let mut result = self.db.query(query).await?;
+        let tokens: Vec<RegistrationToken> = result.take(0)?;
+
+        let now = Utc::now();
+        Ok(tokens
+            .into_iter()
+            .filter(|token| valid.is_none_or(|valid| token.is_valid(now) == valid))
+            .collect())
+    }
+
+    /// Change a token's use limit and expiry; `None` leaves a field as is
+    /// while `Some(None)` clears it
+    pub async fn update_registration_token(
+        &self,
+        token: &str,
+        uses_allowed: Option<Option<i32>>,
+        expires_at: Option<Option<DateTime<Utc>>>,
+    ) -> Result<Option<RegistrationToken>, RepositoryError> {
+        let Some(mut registration_token) = self.get_registration_token(token).await? else {
+            return Ok(None);
+        };
+
+        if let Some(uses_allowed) = uses_allowed {
+            registration_token.uses_allowed = uses_allowed;
+        }
+        if let Some(expires_at) = expires_at {
+            registration_token.expires_at = expires_at;
+        }
+
+        let updated: Option<RegistrationToken> = self
+            .db
+            .update(("registration_token", token))
+            .content(registration_token)
+            .await?;
+        Ok(updated)
+    }
+
+    /// Delete a token, returning whether it existed
+    pub async fn delete_registration_token(&self, token: &str) -> Result<bool, RepositoryError> {
+        let deleted: Option<RegistrationToken> =
+            self.db.delete(("registration_token", token)).await?;
+        Ok(deleted.is_some())
+    }
+
     pub async fn validate_registration_token(&self, token: &str) -> Result<bool, RepositoryError> {
-        let query = "SELECT * FROM registration_token WHERE token = $token AND pending = false AND completed = false AND (expires_at IS NONE OR expires_at > $now) AND (uses_remaining IS NONE OR uses_remaining > 0) LIMIT 1";
+        Ok(self
+            .get_registration_token(token)
+            .await?
+            .is_some_and(|registration_token| registration_token.is_valid(Utc::now())))
+    }
+
+    /// Take a pending use of a token for a registration in progress,
+    /// returning whether the token was valid
+    pub async fn reserve_registration_token(&self, token: &str) -> Result<bool, RepositoryError> {
+        let query = "
+            UPDATE type::thing('registration_token', $token) SET pending += 1
+            WHERE (expires_at IS NONE OR expires_at > $now)
+                AND (uses_allowed IS NONE OR pending + completed < uses_allowed)
+            RETURN AFTER
+        ";
         let mut result = self
             .db
             .query(query)
@@ -219,14 +342,27 @@ impl<C: Connection> RegistrationRepository<C> {
             .bind(("now", Utc::now()))
             .await?;
 
-        let token_record: Option<RegistrationToken> = result.take(0)?;
-        Ok(token_record.is_some())
+        let reserved: Option<RegistrationToken> = result.take(0)?;
+        Ok(reserved.is_some())
     }
 
-    pub async fn consume_registration_token(&self, token: &str) -> Result<(), RepositoryError> {
-        let query = "UPDATE registration_token SET uses_remaining = uses_remaining - 1, completed = (uses_remaining <= 1) WHERE token = $token";
-        let mut _result = self.db.query(query).bind(("token", token.to_string())).await?;
+    /// Turn a pending use into a completed one once the account exists
+    pub async fn complete_registration_token(&self, token: &str) -> Result<(), RepositoryError> {
+        let query = "
+            UPDATE type::thing('registration_token', $token)
+            SET pending = math::max([pending - 1, 0]), completed += 1
+        ";
+        self.db.query(query).bind(("token", token.to_string())).await?;
+        Ok(())
+    }
 
+    /// Give back a pending use whose registration was abandoned
+    pub async fn release_registration_token(&self, token: &str) -> Result<(), RepositoryError> {
+        let query = "
+            UPDATE type::thing('registration_token', $token)
+            SET pending = math::max([pending - 1, 0])
+        ";
+        self.db.query(query).bind(("token", token.to_string())).await?;
         Ok(())
     }
 
@@ -286,3 +422,43 @@ impl<C: Connection> RegistrationRepository<C> {
         Ok(flows)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(uses_allowed: Option<i32>, pending: i32, completed: i32) -> RegistrationToken {
+        RegistrationToken {
+            token: "abc".to_string(),
+            uses_allowed,
+            pending,
+            completed,
+            expires_at: None,
+            created_by: "@admin:example.com".to_string(),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_pending_uses_count_against_the_limit() {
+        let now = Utc::now();
+
+        assert!(token(None, 10, 10).is_valid(now));
+        assert!(token(Some(2), 1, 0).is_valid(now));
+        assert!(!token(Some(2), 1, 1).is_valid(now));
+        assert!(!token(Some(2), 2, 0).is_valid(now));
+        assert!(!token(Some(0), 0, 0).is_valid(now));
+    }
+
+    #[test]
+    fn test_expired_tokens_are_invalid() {
+        let now = Utc::now();
+        let mut expired = token(None, 0, 0);
+        expired.expires_at = Some(now - chrono::Duration::minutes(1));
+        let mut live = token(None, 0, 0);
+        live.expires_at = Some(now + chrono::Duration::minutes(1));
+
+        assert!(!expired.is_valid(now));
+        assert!(live.is_valid(now));
+    }
+}
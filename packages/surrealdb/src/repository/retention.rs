@@ -0,0 +1,205 @@
+use std::collections::HashSet;
+
+use crate::repository::error::RepositoryError;
+use matryx_entity::types::RoomRetentionContent;
+use serde::Deserialize;
+use serde_json::Value;
+use surrealdb::{Surreal, engine::any::Any};
+
+/// What a history purge removed
+#[derive(Debug, Clone, Default)]
+pub struct PurgedHistory {
+    pub events_deleted: usize,
+    /// `mxc://` URIs referenced by the deleted events
+    pub media_uris: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct PurgeCandidate {
+    event_id: String,
+    #[serde(default)]
+    content: Value,
+}
+
+#[derive(Clone)]
+pub struct RetentionRepository {
+    db: Surreal<Any>,
+}
+
+impl RetentionRepository {
+    pub fn new(db: Surreal<Any>) -> Self {
+        Self { db }
+    }
+
+    /// The room's current `m.room.retention` content
+    pub async fn get_room_retention(
+        &self,
+        room_id: &str,
+    ) -> Result<Option<RoomRetentionContent>, RepositoryError> {
+        let query = "
+            SELECT VALUE content FROM event
+            WHERE room_id = $room_id
+            AND event_type = 'm.room.retention'
+            AND state_key = ''
+            ORDER BY depth DESC, origin_server_ts DESC
+            LIMIT 1
+        ";
+        let mut response = self.db.query(query).bind(("room_id", room_id.to_string())).await?;
+        let contents: Vec<Value> = response.take(0)?;
+
+        match contents.into_iter().next() {
+            Some(content) => Ok(Some(serde_json::from_value(content)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Every room known to the server
+    pub async fn get_room_ids(&self) -> Result<Vec<String>, RepositoryError> {
+        let mut response = self.db.query("SELECT VALUE room_id FROM room").await?;
+        let room_ids: Vec<String> = response.take(0)?;
+        Ok(room_ids)
+    }
+
+    pub async fn get_event_timestamp(
+        &self,
+        room_id: &str,
+        event_id: &str,
+    ) -> Result<Option<i64>, RepositoryError> {
+        let query = "
+            SELECT VALUE origin_server_ts FROM event
+            WHERE room_id = $room_id AND event_id = $event_id
+            LIMIT 1
+        ";
+        let mut response = self
+            .db
+            .query(query)
+            .bind(("room_id", room_id.to_string()))
+            .bind(("event_id", event_id.to_string()))
+            .await?;
+        let timestamps: Vec<i64> = response.take(0)?;
+        Ok(timestamps.into_iter().next())
+    }
+
+    /// Delete the room's non-state events sent before `before_ts`
+    ///
+    /// State events and the room's latest event are always kept, so the
+    /// room's current state and the forward extremity of its DAG survive.
+    pub async fn purge_events_before(
+        &self,
+        room_id: &str,
+        before_ts: i64,
+    ) -> Result<PurgedHistory, RepositoryError> {
+        let query = "
+            LET $latest = (
+                SELECT VALUE event_id FROM event
+                WHERE room_id = $room_id
+                ORDER BY depth DESC, origin_server_ts DESC
+                LIMIT 1
+            );
+            SELECT event_id, content FROM event
+            WHERE room_id = $room_id
+            AND origin_server_ts < $before_ts
+            AND state_key IS NONE
+            AND event_id NOTINSIDE $latest;
+        ";
+        let mut response = self
+            .db
+            .query(query)
+            .bind(("room_id", room_id.to_string()))
+            .bind(("before_ts", before_ts))
+            .await?;
+        let candidates: Vec<PurgeCandidate> = response.take(1)?;
+        if candidates.is_empty() {
+            return Ok(PurgedHistory::default());
+        }
+
+        let mut media_uris = Vec::new();
+        let mut event_ids = Vec::with_capacity(candidates.len());
+        for candidate in candidates {
+            collect_media_uris(&candidate.content, &mut media_uris);
+            event_ids.push(candidate.event_id);
+        }
+        media_uris.sort();
+        media_uris.dedup();
+
+        self.db
+            .query("DELETE event WHERE room_id = $room_id AND event_id INSIDE $event_ids")
+            .bind(("room_id", room_id.to_string()))
+            .bind(("event_ids", event_ids.clone()))
+            .await?;
+
+        Ok(PurgedHistory { events_deleted: event_ids.len(), media_uris })
+    }
+
+    /// The URIs among `uris` still used by an event, a room avatar or a
+    /// user's profile
+    pub async fn get_referenced_media(
+        &self,
+        uris: &[String],
+    ) -> Result<HashSet<String>, RepositoryError> {
+        if uris.is_empty() {
+            return Ok(HashSet::new());
+        }
+
+        let query = "
+            SELECT VALUE content.url FROM event WHERE content.url INSIDE $uris;
+            SELECT VALUE content.file.url FROM event WHERE content.file.url INSIDE $uris;
+            SELECT VALUE content.info.thumbnail_url FROM event
+            WHERE content.info.thumbnail_url INSIDE $uris;
+            SELECT VALUE content.info.thumbnail_file.url FROM event
+            WHERE content.info.thumbnail_file.url INSIDE $uris;
+            SELECT VALUE content.avatar_url FROM event WHERE content.avatar_url INSIDE $uris;
+            SELECT VALUE avatar_url FROM room WHERE avatar_url INSIDE $uris;
+            SELECT VALUE avatar_url FROM user WHERE avatar_url INSIDE $uris;
+        ";
+        let mut response = self.db.query(query).bind(("uris", uris.to_vec())).await?;
+
+        let mut referenced = HashSet::new();
+        for statement in 0..7 {
+            let found: Vec<String> = response.take(statement)?;
+            referenced.extend(found);
+        }
+        Ok(referenced)
+    }
+}
+
+/// Media referenced by event content, including encrypted attachments
+/// and thumbnails
+fn collect_media_uris(content: &Value, uris: &mut Vec<String>) {
+    let candidates = [
+        content.get("url"),
+        content.pointer("/file/url"),
+        content.pointer("/info/thumbnail_url"),
+        content.pointer("/info/thumbnail_file/url"),
+    ];
+    uris.extend(
+        candidates
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .filter(|uri| uri.starts_with("mxc://"))
+            .map(str::to_string),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_collect_media_uris() {
+        let mut uris = Vec::new();
+        collect_media_uris(
+            &json!({
+                "msgtype": "m.image",
+                "url": "mxc://example.org/image",
+                "info": { "thumbnail_file": { "url": "mxc://example.org/thumb" } }
+            }),
+            &mut uris,
+        );
+        collect_media_uris(&json!({ "msgtype": "m.text", "body": "mxc://not/media" }), &mut uris);
+
+        assert_eq!(uris, vec!["mxc://example.org/image", "mxc://example.org/thumb"]);
+    }
+}
@@ -142,6 +142,8 @@ pub struct MemberInfo {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RoomUpgradeResponse {
     pub replacement_room: String,
+    /// The old room's tombstone, not yet sent
+    pub tombstone: Event,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -403,47 +405,7 @@ impl RoomRepository {
 
         if let Some(event) = events.first()
             && let Some(content) = event.get("content") {
-            let users = content
-                .get("users")
-                .and_then(|v| v.as_object())
-                .map(|obj| {
-                    obj.iter()
-                        .filter_map(|(k, v)| v.as_i64().map(|i| (k.clone(), i)))
-                        .collect()
-                })
-                .unwrap_or_default();
-
-            let events_map = content
-                .get("events")
-                .and_then(|v| v.as_object())
-                .map(|obj| {
-                    obj.iter()
-                        .filter_map(|(k, v)| v.as_i64().map(|i| (k.clone(), i)))
-                        .collect()
-                })
-                .unwrap_or_default();
-
-            return Ok(PowerLevels {
-                users,
-                users_default: content
-                    .get("users_default")
-                    .and_then(|v| v.as_i64())
-                    .unwrap_or(0),
-                events: events_map,
-                events_default: content
-                    .get("events_default")
-                    .and_then(|v| v.as_i64())
-                    .unwrap_or(0),
-                state_default: content
-                    .get("state_default")
-                    .and_then(|v| v.as_i64())
-                    .unwrap_or(50),
-                ban: content.get("ban").and_then(|v| v.as_i64()).unwrap_or(50),
-                kick: content.get("kick").and_then(|v| v.as_i64()).unwrap_or(50),
-                redact: content.get("redact").and_then(|v| v.as_i64()).unwrap_or(50),
-                invite: content.get("invite").and_then(|v| v.as_i64()).unwrap_or(50),
-                notifications: Default::default(),
-            });
+            return Ok(serde_json::from_value(content.clone())?);
         }
 
         // Return default power levels if no event found
@@ -1242,6 +1204,7 @@ impl RoomRepository {
             redact: 50,
             invite: 50,
             notifications: NotificationPowerLevels::default(),
+            unknown_fields: Default::default(),
         })
     }
 
@@ -1506,8 +1469,9 @@ impl RoomRepository {
             });
         }
 
-        // Create new room ID for the upgraded room
-        let new_room_id = format!("!{}:{}", uuid::Uuid::new_v4(), "localhost");
+        // The replacement room belongs to the upgrading user's server
+        let server_name = user_id.split_once(':').map_or("localhost", |(_, server)| server);
+        let new_room_id = format!("!{}:{}", uuid::Uuid::new_v4(), server_name);
 
         // Get current room state
         let current_room = self.get_by_id(room_id).await?.ok_or_else(|| {
@@ -1517,9 +1481,27 @@ impl RoomRepository {
             }
         })?;
 
-        // Get old room's creation event for predecessor
-        let old_creation_event = self.get_room_creation_event(room_id).await?;
-        let old_creation_event_id = old_creation_event.event_id.clone();
+        let event_repo = EventRepository::new(self.db.clone());
+
+        // Build the old room's tombstone first, so the new room can name it as its
+        // predecessor, but leave sending it to `tombstone_room` once the new room is ready
+        let tombstone_content = serde_json::json!({
+            "body": "This room has been replaced",
+            "replacement_room": new_room_id.clone()
+        });
+        let tombstone = EventRepository::build_room_event(
+            room_id,
+            "m.room.tombstone",
+            user_id,
+            tombstone_content,
+            Some("".to_string())
+        );
+        let tombstone_event_id = tombstone.event_id.clone();
+
+        let predecessor = serde_json::json!({
+            "room_id": room_id,
+            "event_id": tombstone_event_id
+        });
 
         // Create new room with upgraded version
         let new_room = Room {
@@ -1527,7 +1509,7 @@ impl RoomRepository {
             name: current_room.name.clone(),
             topic: current_room.topic.clone(),
             avatar_url: current_room.avatar_url.clone(),
-            canonical_alias: None, // Aliases don't transfer
+            canonical_alias: None, // Aliases are moved separately
             alt_aliases: Some(Vec::new()),
             creator: user_id.to_string(),
             is_public: current_room.is_public,
@@ -1540,10 +1522,7 @@ impl RoomRepository {
             power_levels: current_room.power_levels.clone(),
             encryption: current_room.encryption.clone(),
             room_type: current_room.room_type.clone(),
-            predecessor: Some(serde_json::json!({
-                "room_id": room_id,
-                "event_id": old_creation_event_id
-            })),
+            predecessor: Some(predecessor.clone()),
             federate: current_room.federate,
             tombstone: None,
             state_events_count: Some(0),
@@ -1554,37 +1533,80 @@ impl RoomRepository {
         // Create the new room
         self.create(&new_room).await?;
 
-        // Create EventRepository to store the tombstone event
-        let event_repo = EventRepository::new(self.db.clone());
-
-        // Create the tombstone event content
-        let tombstone_content = serde_json::json!({
-            "body": "This room has been replaced",
-            "replacement_room": new_room_id.clone()
+        // The new room's create event carries the room type and predecessor
+        let mut creation_content = serde_json::json!({
+            "creator": user_id,
+            "room_version": new_version,
+            "m.federate": current_room.federate.unwrap_or(true),
+            "predecessor": predecessor
         });
-
-        // Create and persist the tombstone event
-        let tombstone_event = event_repo.create_room_event(
-            room_id,
-            "m.room.tombstone",
-            &current_room.creator,
-            tombstone_content,
+        if let Some(room_type) = &current_room.room_type {
+            creation_content["type"] = serde_json::json!(room_type);
+        }
+        event_repo.create_room_event(
+            &new_room_id,
+            "m.room.create",
+            user_id,
+            creation_content,
             Some("".to_string())
         ).await?;
 
-        // Store the tombstone event ID
-        let tombstone_event_id = tombstone_event.event_id.clone();
+        // The upgrading user is the new room's first member
+        self.membership_repo
+            .create_membership(&matryx_entity::types::Membership {
+                user_id: user_id.to_string(),
+                room_id: new_room_id.clone(),
+                membership: MembershipState::Join,
+                reason: None,
+                invited_by: None,
+                updated_at: Some(chrono::Utc::now()),
+                display_name: None,
+                avatar_url: None,
+                is_direct: current_room.is_direct,
+                third_party_invite: None,
+                join_authorised_via_users_server: None,
+            })
+            .await?;
+        event_repo
+            .create_membership_change_event(
+                &new_room_id,
+                user_id,
+                user_id,
+                MembershipState::Join,
+                None,
+            )
+            .await?;
+
+        Ok(RoomUpgradeResponse { replacement_room: new_room_id, tombstone })
+    }
+
+    /// Send the tombstone built by `upgrade_room`, pointing the old room at its replacement
+    pub async fn tombstone_room(
+        &self,
+        room_id: &str,
+        tombstone: &Event,
+    ) -> Result<(), RepositoryError> {
+        let mut old_room = self.get_by_id(room_id).await?.ok_or_else(|| {
+            RepositoryError::NotFound {
+                entity_type: "Room".to_string(),
+                id: room_id.to_string(),
+            }
+        })?;
+
+        // Stamp it now, so it comes after everything else the upgrade sent
+        let mut tombstone = tombstone.clone();
+        let now = chrono::Utc::now().timestamp_millis();
+        tombstone.origin_server_ts = now;
+        tombstone.received_ts = Some(now);
+        EventRepository::new(self.db.clone()).create(&tombstone).await?;
 
-        // Update old room with successor
-        let mut old_room = current_room;
         old_room.tombstone = Some(serde_json::json!({
-            "replacement_room": new_room_id,
+            "replacement_room": tombstone.content.get("replacement_room"),
             "body": "This room has been replaced",
-            "event_id": tombstone_event_id
+            "event_id": tombstone.event_id
         }));
         self.update(&old_room).await?;
-
-        Ok(RoomUpgradeResponse { replacement_room: new_room_id })
+        Ok(())
     }
 
     /// Get room aliases
@@ -107,6 +107,18 @@ impl RoomAliasRepository {
         Ok(())
     }
 
+    /// Point an existing alias at another room, e.g. a room's replacement
+    pub async fn move_alias(&self, alias: &str, room_id: &str) -> Result<(), RepositoryError> {
+        let query = "UPDATE room_aliases SET room_id = $room_id WHERE alias = $alias";
+        self.db
+            .query(query)
+            .bind(("alias", alias.to_string()))
+            .bind(("room_id", room_id.to_string()))
+            .await?;
+
+        Ok(())
+    }
+
     /// Resolve alias to room information
     pub async fn resolve_alias(
         &self,
@@ -118,6 +118,17 @@ impl<C: Connection> ServerNoticeRepository<C> {
             });
         }
 
+        self.send_room_notice(room_id, notice_content, server_name).await
+    }
+
+    /// Post a notice as the server notices user into any room, such as a
+    /// moderation room, without the per-user membership check
+    pub async fn send_room_notice(
+        &self,
+        room_id: &str,
+        notice_content: &Value,
+        server_name: &str,
+    ) -> Result<String, RepositoryError> {
         let event_id = format!("${}", Uuid::new_v4());
         let server_user_id = format!("@server:{}", server_name);
         let timestamp = chrono::Utc::now().timestamp_millis() as u64;
@@ -193,6 +193,14 @@ impl<C: Connection> UiaRepository<C> {
         Ok(deleted_count.unwrap_or(0))
     }
 
+    /// Expired UIA sessions that have not been cleaned up yet
+    pub async fn get_expired_sessions(&self) -> Result<Vec<UiaSession>, RepositoryError> {
+        let query = "SELECT * FROM uia_sessions WHERE expires_at < datetime::now()";
+        let mut result = self.db.query(query).await?;
+        let sessions: Vec<UiaSession> = result.take(0)?;
+        Ok(sessions)
+    }
+
     /// Clean up expired UIA sessions
     pub async fn cleanup_expired_sessions(&self) -> Result<u64, RepositoryError> {
         let query = "DELETE FROM uia_sessions WHERE expires_at < datetime::now()";
@@ -0,0 +1,101 @@
+use crate::repository::error::RepositoryError;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::{Surreal, engine::any::Any};
+
+/// A user's acceptance of one version of a policy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserConsent {
+    pub user_id: String,
+    pub policy: String,
+    pub version: String,
+    pub accepted_at: DateTime<Utc>,
+}
+
+#[derive(Clone)]
+pub struct UserConsentRepository {
+    db: Surreal<Any>,
+}
+
+impl UserConsentRepository {
+    pub fn new(db: Surreal<Any>) -> Self {
+        Self { db }
+    }
+
+    fn record_id(user_id: &str, policy: &str, version: &str) -> String {
+        format!("{}|{}|{}", policy, version, user_id)
+    }
+
+    /// Record that a user accepted a policy version; accepting it again
+    /// keeps the original acceptance time
+    pub async fn record_consent(
+        &self,
+        user_id: &str,
+        policy: &str,
+        version: &str,
+    ) -> Result<(), RepositoryError> {
+        let query = "
+            UPSERT type::thing('user_consent', $id) SET
+                user_id = $user_id,
+                policy = $policy,
+                version = $version,
+                accepted_at = accepted_at ?? time::now()
+        ";
+        self.db
+            .query(query)
+            .bind(("id", Self::record_id(user_id, policy, version)))
+            .bind(("user_id", user_id.to_string()))
+            .bind(("policy", policy.to_string()))
+            .bind(("version", version.to_string()))
+            .await?;
+        Ok(())
+    }
+
+    /// Every policy version a user has accepted, oldest first
+    pub async fn get_user_consents(
+        &self,
+        user_id: &str,
+    ) -> Result<Vec<UserConsent>, RepositoryError> {
+        let query = "
+            SELECT user_id, policy, version, accepted_at
+            FROM user_consent
+            WHERE user_id = $user_id
+            ORDER BY accepted_at
+        ";
+        let mut response = self.db.query(query).bind(("user_id", user_id.to_string())).await?;
+        let consents: Vec<UserConsent> = response.take(0)?;
+        Ok(consents)
+    }
+
+    /// The `<policy>:<version>` pairs the user was last sent a consent
+    /// notice for
+    pub async fn get_notified_versions(
+        &self,
+        user_id: &str,
+    ) -> Result<Vec<String>, RepositoryError> {
+        let query = "SELECT VALUE versions FROM type::thing('user_consent_notice', $user_id)";
+        let mut response = self.db.query(query).bind(("user_id", user_id.to_string())).await?;
+        let versions: Option<Vec<String>> = response.take(0)?;
+        Ok(versions.unwrap_or_default())
+    }
+
+    pub async fn set_notified_versions(
+        &self,
+        user_id: &str,
+        versions: &[String],
+    ) -> Result<(), RepositoryError> {
+        let query = "
+            UPSERT type::thing('user_consent_notice', $user_id) CONTENT {
+                user_id: $user_id,
+                versions: $versions,
+                sent_at: time::now()
+            }
+        ";
+        self.db
+            .query(query)
+            .bind(("user_id", user_id.to_string()))
+            .bind(("versions", versions.to_vec()))
+            .await?;
+        Ok(())
+    }
+}
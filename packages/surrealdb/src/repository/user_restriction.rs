@@ -0,0 +1,121 @@
+use crate::repository::error::RepositoryError;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::{Surreal, engine::any::Any};
+
+/// The quieter alternatives to deactivation an admin can apply to an account
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UserRestrictionKind {
+    /// Events appear to succeed but are never persisted or federated
+    ShadowBan,
+    /// Read-only access (MSC3823)
+    Suspension,
+    /// No access beyond logging out (MSC3939)
+    Lock,
+}
+
+impl UserRestrictionKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserRestrictionKind::ShadowBan => "shadow_ban",
+            UserRestrictionKind::Suspension => "suspension",
+            UserRestrictionKind::Lock => "lock",
+        }
+    }
+
+    pub fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "shadow_ban" => Some(UserRestrictionKind::ShadowBan),
+            "suspension" => Some(UserRestrictionKind::Suspension),
+            "lock" => Some(UserRestrictionKind::Lock),
+            _ => None,
+        }
+    }
+}
+
+/// A restriction applied to a user, with who applied it and why
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserRestriction {
+    pub user_id: String,
+    pub kind: UserRestrictionKind,
+    pub reason: Option<String>,
+    pub restricted_by: String,
+    pub restricted_at: DateTime<Utc>,
+}
+
+#[derive(Clone)]
+pub struct UserRestrictionRepository {
+    db: Surreal<Any>,
+}
+
+impl UserRestrictionRepository {
+    pub fn new(db: Surreal<Any>) -> Self {
+        Self { db }
+    }
+
+    fn record_id(user_id: &str, kind: UserRestrictionKind) -> String {
+        format!("{}|{}", kind.as_str(), user_id)
+    }
+
+    /// Apply a restriction, replacing the reason and actor of an existing one
+    pub async fn restrict(
+        &self,
+        user_id: &str,
+        kind: UserRestrictionKind,
+        restricted_by: &str,
+        reason: Option<&str>,
+    ) -> Result<UserRestriction, RepositoryError> {
+        let query = "
+            UPSERT type::thing('user_restriction', $id) CONTENT {
+                user_id: $user_id,
+                kind: $kind,
+                reason: $reason,
+                restricted_by: $restricted_by,
+                restricted_at: time::now()
+            } RETURN user_id, kind, reason, restricted_by, restricted_at
+        ";
+        let mut response = self
+            .db
+            .query(query)
+            .bind(("id", Self::record_id(user_id, kind)))
+            .bind(("user_id", user_id.to_string()))
+            .bind(("kind", kind.as_str().to_string()))
+            .bind(("reason", reason.map(str::to_string)))
+            .bind(("restricted_by", restricted_by.to_string()))
+            .await?;
+        let restriction: Option<UserRestriction> = response.take(0)?;
+
+        restriction.ok_or_else(|| {
+            RepositoryError::Database(surrealdb::Error::msg("Failed to restrict user"))
+        })
+    }
+
+    /// Lift a restriction, returning whether it was in place
+    pub async fn lift(
+        &self,
+        user_id: &str,
+        kind: UserRestrictionKind,
+    ) -> Result<bool, RepositoryError> {
+        let removed: Option<UserRestriction> = self
+            .db
+            .delete(("user_restriction", Self::record_id(user_id, kind)))
+            .await?;
+        Ok(removed.is_some())
+    }
+
+    pub async fn get_user_restrictions(
+        &self,
+        user_id: &str,
+    ) -> Result<Vec<UserRestriction>, RepositoryError> {
+        let query = "
+            SELECT user_id, kind, reason, restricted_by, restricted_at
+            FROM user_restriction
+            WHERE user_id = $user_id
+            ORDER BY restricted_at
+        ";
+        let mut response = self.db.query(query).bind(("user_id", user_id.to_string())).await?;
+        let restrictions: Vec<UserRestriction> = response.take(0)?;
+        Ok(restrictions)
+    }
+}
@@ -0,0 +1,128 @@
+use crate::repository::error::RepositoryError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use surrealdb::{Surreal, engine::any::Any};
+
+/// A state event in the history of one piece of room state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateChange {
+    pub event_id: String,
+    pub state_key: String,
+    pub depth: Option<i64>,
+    pub origin_server_ts: i64,
+    pub content: Value,
+}
+
+impl StateChange {
+    /// Where the event sits in the room, in the order used for state at an event
+    pub fn position(&self) -> (i64, i64) {
+        (self.depth.unwrap_or(0), self.origin_server_ts)
+    }
+}
+
+/// Loads the state that decides who may see which events
+#[derive(Clone)]
+pub struct VisibilityRepository {
+    db: Surreal<Any>,
+}
+
+impl VisibilityRepository {
+    pub fn new(db: Surreal<Any>) -> Self {
+        Self { db }
+    }
+
+    /// Every `m.room.history_visibility` event of a room, oldest first
+    pub async fn get_history_visibility_changes(
+        &self,
+        room_id: &str,
+    ) -> Result<Vec<StateChange>, RepositoryError> {
+        let query = "
+            SELECT event_id, state_key, depth, origin_server_ts, content
+            FROM event
+            WHERE room_id = $room_id
+            AND event_type = 'm.room.history_visibility'
+            AND state_key = ''
+            ORDER BY depth, origin_server_ts
+        ";
+        let mut response = self.db.query(query).bind(("room_id", room_id.to_string())).await?;
+        let changes: Vec<StateChange> = response.take(0)?;
+        Ok(sorted(changes))
+    }
+
+    /// Every membership event of a user in a room, oldest first
+    pub async fn get_membership_changes(
+        &self,
+        room_id: &str,
+        user_id: &str,
+    ) -> Result<Vec<StateChange>, RepositoryError> {
+        let query = "
+            SELECT event_id, state_key, depth, origin_server_ts, content
+            FROM event
+            WHERE room_id = $room_id
+            AND event_type = 'm.room.member'
+            AND state_key = $user_id
+            ORDER BY depth, origin_server_ts
+        ";
+        let mut response = self
+            .db
+            .query(query)
+            .bind(("room_id", room_id.to_string()))
+            .bind(("user_id", user_id.to_string()))
+            .await?;
+        let changes: Vec<StateChange> = response.take(0)?;
+        Ok(sorted(changes))
+    }
+
+    /// Every membership event of the users of a server in a room, oldest first
+    pub async fn get_server_membership_changes(
+        &self,
+        room_id: &str,
+        server_name: &str,
+    ) -> Result<Vec<StateChange>, RepositoryError> {
+        let query = "
+            SELECT event_id, state_key, depth, origin_server_ts, content
+            FROM event
+            WHERE room_id = $room_id
+            AND event_type = 'm.room.member'
+            AND string::ends_with(state_key, $server_suffix)
+            ORDER BY depth, origin_server_ts
+        ";
+        let mut response = self
+            .db
+            .query(query)
+            .bind(("room_id", room_id.to_string()))
+            .bind(("server_suffix", format!(":{}", server_name)))
+            .await?;
+        let changes: Vec<StateChange> = response.take(0)?;
+        Ok(sorted(changes))
+    }
+
+    /// Users in a user's `m.ignored_user_list`
+    pub async fn get_ignored_users(&self, user_id: &str) -> Result<HashSet<String>, RepositoryError> {
+        let query = "
+            SELECT VALUE content
+            FROM account_data
+            WHERE user_id = $user_id
+            AND account_data_type = 'm.ignored_user_list'
+            AND room_id IS NONE
+            LIMIT 1
+        ";
+        let mut response = self.db.query(query).bind(("user_id", user_id.to_string())).await?;
+        let content: Option<Value> = response.take(0)?;
+
+        Ok(content
+            .as_ref()
+            .and_then(|content| content.get("ignored_users"))
+            .and_then(|ignored| ignored.as_object())
+            .map(|ignored| ignored.keys().cloned().collect())
+            .unwrap_or_default())
+    }
+}
+
+/// Events at the same depth and timestamp are ordered by ID so every
+/// lookup agrees on which came last
+fn sorted(mut changes: Vec<StateChange>) -> Vec<StateChange> {
+    changes.sort_by(|a, b| a.position().cmp(&b.position()).then_with(|| a.event_id.cmp(&b.event_id)));
+    changes
+}